@@ -56,11 +56,11 @@ fn main() {
 }
 
 /// This method:
-///   * Creates a zip archive of the `new` project template.
+///   * Creates zip archives of the project templates.
 ///   * Builds the `dylint` driver found in `ink_linting`, the compiled
 ///     driver is put into a zip archive as well.
 fn zip_template_and_build_dylint_driver(manifest_dir: PathBuf, out_dir: PathBuf) -> Result<()> {
-    zip_template(&manifest_dir, &out_dir)?;
+    zip_templates(&manifest_dir, &out_dir)?;
 
     check_dylint_link_installed()?;
 
@@ -107,10 +107,22 @@ fn zip_template_and_build_dylint_driver(manifest_dir: PathBuf, out_dir: PathBuf)
     res
 }
 
-/// Creates a zip archive `template.zip` of the `new` project template in `out_dir`.
-fn zip_template(manifest_dir: &Path, out_dir: &Path) -> Result<()> {
-    let template_dir = manifest_dir.join("templates").join("new");
-    let template_dst_file = out_dir.join("template.zip");
+/// Creates the zip archives of the project templates in `out_dir`: `template.zip` of the `new`
+/// contract template and `chain-extension-template.zip` of the `chain-extension` template.
+fn zip_templates(manifest_dir: &Path, out_dir: &Path) -> Result<()> {
+    zip_template(manifest_dir, out_dir, "new", "template.zip")?;
+    zip_template(
+        manifest_dir,
+        out_dir,
+        "chain-extension",
+        "chain-extension-template.zip",
+    )
+}
+
+/// Creates a zip archive `archive_name` of the project template `name` in `out_dir`.
+fn zip_template(manifest_dir: &Path, out_dir: &Path, name: &str, archive_name: &str) -> Result<()> {
+    let template_dir = manifest_dir.join("templates").join(name);
+    let template_dst_file = out_dir.join(archive_name);
     println!(
         "Creating template zip: template_dir '{}', destination archive '{}'",
         template_dir.display(),
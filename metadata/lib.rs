@@ -123,6 +123,10 @@ pub struct Source {
     /// with the metadata.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub wasm: Option<SourceWasm>,
+    /// The target triple the Wasm code was compiled for, if it is not the default
+    /// `wasm32-unknown-unknown`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
 }
 
 impl Source {
@@ -138,8 +142,15 @@ impl Source {
             language,
             compiler,
             wasm,
+            target: None,
         }
     }
+
+    /// Sets the target triple the Wasm code was compiled for.
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
 }
 
 /// The bytes of the compiled Wasm smart contract.
@@ -0,0 +1,204 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{config::config_dir, util::decode_hex};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sp_core::crypto::{AccountId32, Ss58Codec};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Name of the address book file inside of the configuration directory.
+const ADDRESS_BOOK_FILE: &str = "address-book.toml";
+
+/// Human readable aliases of deployed contracts.
+///
+/// It is stored in `address-book.toml` next to the user configuration, e.g.
+///
+/// ```toml
+/// [contracts.erc20]
+/// network = "rococo"
+/// address = "5C4hrfjw9DjXZTzV3MwzrrAr9P1MJhSrvWGWqi1eSuyUpnhM"
+/// code-hash = "0x1f64a3b7fb6e8d6d8a4ad62c4ec6d0d9d2b9ee3c7dd95d4e1b0b6d4f3a2e1c0b"
+/// metadata = "/home/alice/erc20/target/ink/metadata.json"
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AddressBook {
+    #[serde(default)]
+    pub contracts: BTreeMap<String, AddressBookEntry>,
+}
+
+/// A deployed contract known by an alias.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AddressBookEntry {
+    /// The network profile the contract is deployed on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// The SS58 address of the contract.
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_hash: Option<String>,
+    /// Path to the metadata of the contract.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<PathBuf>,
+}
+
+impl AddressBook {
+    /// Load the address book, returns an empty address book if the file does not exist.
+    pub fn load() -> Result<Self> {
+        match address_book_path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Load the address book from the file at `path`, returns an empty address book if the
+    /// file does not exist.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .context(format!("Failed to read address book {}", path.display()))?;
+        toml::from_str(&contents)
+            .context(format!("Failed to parse address book {}", path.display()))
+    }
+
+    /// Write the address book to the file at `path`.
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+        }
+        let contents = toml::to_string(self)?;
+        std::fs::write(path, contents)
+            .context(format!("Failed to write address book {}", path.display()))
+    }
+
+    /// Adds the `entry` under the `alias`, after validating its address and code hash.
+    pub fn add(&mut self, alias: &str, entry: AddressBookEntry) -> Result<()> {
+        if alias.is_empty() || AccountId32::from_ss58check(alias).is_ok() {
+            anyhow::bail!("The alias '{}' must not be empty or an SS58 address", alias)
+        }
+        AccountId32::from_ss58check(&entry.address)
+            .map_err(|e| anyhow::anyhow!("Invalid SS58 address '{}': {:?}", entry.address, e))?;
+        if let Some(code_hash) = &entry.code_hash {
+            let bytes = decode_hex(code_hash)?;
+            if bytes.len() != 32 {
+                anyhow::bail!("Code hash should be 32 bytes in length")
+            }
+        }
+        self.contracts.insert(alias.to_string(), entry);
+        Ok(())
+    }
+
+    /// Returns the entry with the given `alias`.
+    pub fn get(&self, alias: &str) -> Result<&AddressBookEntry> {
+        self.contracts.get(alias).ok_or_else(|| {
+            anyhow::anyhow!(
+                "'{}' is neither an SS58 address nor an alias of the address book",
+                alias
+            )
+        })
+    }
+
+    /// Returns the account id of `address_or_alias`, which is either an SS58 address or an alias
+    /// of the address book.
+    pub fn resolve(&self, address_or_alias: &str) -> Result<AccountId32> {
+        if let Ok(account_id) = AccountId32::from_ss58check(address_or_alias) {
+            return Ok(account_id);
+        }
+        let entry = self.get(address_or_alias)?;
+        AccountId32::from_ss58check(&entry.address)
+            .map_err(|e| anyhow::anyhow!("Invalid SS58 address '{}': {:?}", entry.address, e))
+    }
+}
+
+/// Returns the path of the address book file, if there is a configuration directory.
+pub fn address_book_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(ADDRESS_BOOK_FILE))
+}
+
+/// Parses an SS58 address or an alias of the address book, used for command line arguments.
+pub fn parse_address(input: &str) -> Result<AccountId32> {
+    AddressBook::load()?.resolve(input)
+}
+
+/// An account given on the command line, which is resolved on the chain unless it is an SS58
+/// address or an alias of the address book.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccountArg {
+    Account(AccountId32),
+    /// An index of the indices pallet, `index:<n>`, or the display name of an identity.
+    OnChain(String),
+}
+
+/// Parses an [`AccountArg`], used for command line arguments.
+pub fn parse_account_arg(input: &str) -> Result<AccountArg> {
+    match AddressBook::load()?.resolve(input) {
+        Ok(account) => Ok(AccountArg::Account(account)),
+        Err(_) if input.is_empty() => anyhow::bail!("The account must not be empty"),
+        Err(_) => Ok(AccountArg::OnChain(input.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    fn entry(address: &str) -> AddressBookEntry {
+        AddressBookEntry {
+            network: Some("rococo".to_string()),
+            address: address.to_string(),
+            code_hash: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn aliases_are_persisted_and_resolved() {
+        with_tmp_dir(|path| {
+            let file = path.join(ADDRESS_BOOK_FILE);
+            let mut address_book = AddressBook::load_from(&file)?;
+            address_book.add("erc20", entry(ALICE))?;
+            address_book.save_to(&file)?;
+
+            let address_book = AddressBook::load_from(&file)?;
+            assert_eq!(address_book.get("erc20")?, &entry(ALICE));
+            assert_eq!(address_book.resolve("erc20")?.to_ss58check(), ALICE);
+            assert_eq!(address_book.resolve(ALICE)?.to_ss58check(), ALICE);
+            assert!(address_book.resolve("flipper").is_err());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn invalid_entries_are_rejected() {
+        let mut address_book = AddressBook::default();
+        assert!(address_book.add("erc20", entry("5Grwva")).is_err());
+        assert!(address_book.add(ALICE, entry(ALICE)).is_err());
+
+        let mut with_code_hash = entry(ALICE);
+        with_code_hash.code_hash = Some("0x1234".to_string());
+        assert!(address_book.add("erc20", with_code_hash).is_err());
+    }
+}
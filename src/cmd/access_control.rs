@@ -0,0 +1,643 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A first-pass audit of which messages of a contract check their caller.
+//!
+//! The source of the contract is scanned with heuristics, not proven: a message is restricted
+//! if it compares the caller of `self.env().caller()` with `==` or `!=`, calls a function of the
+//! contract which does, calls a function named like a guard, e.g. `only_owner` or
+//! `ensure_admin`, or is annotated with `#[modifiers(..)]` of OpenBrush. Methods are identified by
+//! the type of their impl and their name, and `#[cfg(test)]` code is skipped. The mutability of
+//! the messages is taken from the metadata if the contract was built, and from their receivers
+//! otherwise.
+
+use super::{
+    add::{has_ink_arg, ink_args},
+    extrinsics::load_metadata_file,
+    interface::message_name,
+};
+use crate::{crate_metadata::CrateMetadata, name_value_println, workspace::ManifestPath};
+use anyhow::{Context, Result};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fs,
+    path::PathBuf,
+};
+use syn::{punctuated::Punctuated, visit::Visit};
+
+/// Report which messages of a contract check their caller, and which appear unrestricted.
+#[derive(Debug, clap::Args)]
+#[clap(name = "access-control")]
+pub struct AccessControlCommand {
+    /// Path to the `Cargo.toml` of the contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// Print the report as JSON.
+    #[clap(long)]
+    output_json: bool,
+}
+
+/// How the access to a message is restricted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Access {
+    /// The message neither mutates the storage nor accepts value.
+    ReadOnly,
+    Restricted,
+    Unrestricted,
+}
+
+/// The access of a message, as reported.
+#[derive(Debug, PartialEq, Serialize)]
+struct MessageAccess {
+    message: String,
+    mutates: bool,
+    payable: bool,
+    access: Access,
+    /// What restricts the message, e.g. ``calls `ensure_owner` ``.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    guard: Option<String>,
+}
+
+/// A function of the contract source.
+#[derive(Debug, Default)]
+struct Function {
+    /// The receiver is `&mut self`.
+    mutates: bool,
+    /// The function is an `#[ink(message)]`.
+    message: bool,
+    payable: bool,
+    /// The functions and methods called in the body, methods of the contract by the type of their
+    /// impl and their name, e.g. `Vault::authorize`.
+    calls: BTreeSet<String>,
+    /// The body compares the caller.
+    checks_caller: bool,
+    /// The OpenBrush `#[modifiers(..)]` of the function.
+    modifiers: Vec<String>,
+}
+
+impl AccessControlCommand {
+    pub fn exec(&self) -> Result<()> {
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        let crate_metadata = CrateMetadata::collect(&manifest_path)?;
+        let lib_path = crate_metadata.lib_src_path()?;
+        let src_dir = lib_path
+            .parent()
+            .context("The library of the contract has no directory")?;
+
+        let mut functions = BTreeMap::new();
+        for entry in walkdir::WalkDir::new(src_dir) {
+            let path = entry?.into_path();
+            if path.extension().map_or(true, |extension| extension != "rs") {
+                continue;
+            }
+            let source =
+                fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+            let file =
+                syn::parse_file(&source).context(format!("Failed to parse {}", path.display()))?;
+            collect_functions(&file.items, None, &mut functions);
+        }
+
+        let metadata_path = crate_metadata.metadata_path();
+        let messages: Vec<(String, bool, bool)> = if metadata_path.exists() {
+            let (_, project) = load_metadata_file(&metadata_path)?;
+            project
+                .spec()
+                .messages()
+                .iter()
+                .map(|message| {
+                    (
+                        message.label().to_string(),
+                        message.mutates(),
+                        message.payable(),
+                    )
+                })
+                .collect()
+        } else {
+            log::warn!(
+                "No metadata at {}, the messages are taken from the source",
+                metadata_path.display()
+            );
+            functions
+                .iter()
+                .filter(|(_, function)| function.message)
+                .map(|(key, function)| {
+                    (
+                        function_name(key).to_string(),
+                        function.mutates,
+                        function.payable,
+                    )
+                })
+                .collect()
+        };
+
+        let report = analyze(&functions, &messages);
+        if self.output_json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        for message in &report {
+            let access = match message.access {
+                Access::ReadOnly => "Read-only",
+                Access::Restricted => "Restricted",
+                Access::Unrestricted => "Unrestricted",
+            };
+            let mut details = Vec::new();
+            if message.mutates {
+                details.push("mutates".to_string());
+            }
+            if message.payable {
+                details.push("payable".to_string());
+            }
+            details.extend(message.guard.clone());
+            let details = if details.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", details.join(", "))
+            };
+            name_value_println!(access, format!("{}{}", message.message, details));
+        }
+        let unrestricted = report
+            .iter()
+            .filter(|message| message.access == Access::Unrestricted)
+            .count();
+        name_value_println!(
+            "Summary",
+            format!(
+                "{} of {} messages appear unrestricted, review them manually",
+                unrestricted,
+                report.len()
+            )
+        );
+        Ok(())
+    }
+}
+
+/// Returns the access of the `messages`, given by their label, mutability and payability.
+fn analyze(
+    functions: &BTreeMap<String, Function>,
+    messages: &[(String, bool, bool)],
+) -> Vec<MessageAccess> {
+    // the functions checking the caller, directly or by calling another one which does
+    let mut guards = functions
+        .iter()
+        .filter(|(_, function)| function.checks_caller)
+        .map(|(name, _)| name.as_str())
+        .collect::<BTreeSet<_>>();
+    loop {
+        let transitive = functions
+            .iter()
+            .filter(|(name, function)| {
+                !guards.contains(name.as_str())
+                    && !function.message
+                    && function
+                        .calls
+                        .iter()
+                        .any(|call| guards.contains(call.as_str()))
+            })
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        if transitive.is_empty() {
+            break;
+        }
+        guards.extend(transitive);
+    }
+
+    messages
+        .iter()
+        .map(|(label, mutates, payable)| {
+            let name = message_name(label);
+            let function = functions
+                .iter()
+                .filter(|(key, _)| function_name(key) == name)
+                .max_by_key(|(_, function)| function.message)
+                .map(|(_, function)| function);
+            let guard = function.and_then(|function| guard(function, &guards));
+            let access = match (guard.is_some(), *mutates || *payable) {
+                (true, _) => Access::Restricted,
+                (false, true) => Access::Unrestricted,
+                (false, false) => Access::ReadOnly,
+            };
+            MessageAccess {
+                message: label.clone(),
+                mutates: *mutates,
+                payable: *payable,
+                access,
+                guard: guard.or_else(|| {
+                    function
+                        .is_none()
+                        .then(|| "not found in the source".to_string())
+                        .filter(|_| access == Access::Unrestricted)
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Describes what restricts the `function`, if anything.
+fn guard(function: &Function, guards: &BTreeSet<&str>) -> Option<String> {
+    if function.checks_caller {
+        return Some("checks the caller".to_string());
+    }
+    if !function.modifiers.is_empty() {
+        return Some(format!("#[modifiers({})]", function.modifiers.join(", ")));
+    }
+    function
+        .calls
+        .iter()
+        .find(|call| guards.contains(call.as_str()) || is_guard_name(function_name(call)))
+        .map(|call| format!("calls `{}`", function_name(call)))
+}
+
+/// The name of a function without the type of its impl, e.g. `authorize` of `Vault::authorize`.
+fn function_name(key: &str) -> &str {
+    key.rsplit("::").next().unwrap_or(key)
+}
+
+/// Returns `true` if the function `name` is named like a guard, e.g. `only_owner`.
+fn is_guard_name(name: &str) -> bool {
+    const ROLES: [&str; 4] = ["owner", "admin", "role", "auth"];
+    (name.starts_with("only_") || name.starts_with("ensure_") || name.starts_with("assert_"))
+        && ROLES.iter().any(|role| name.contains(role))
+}
+
+/// Collects the functions and methods in the `items` outside of `#[cfg(test)]` code, methods by
+/// the type of their impl and their name, e.g. `Vault::authorize`.
+fn collect_functions(
+    items: &[syn::Item],
+    self_ty: Option<&str>,
+    functions: &mut BTreeMap<String, Function>,
+) {
+    for item in items {
+        match item {
+            syn::Item::Fn(item) if !is_test(&item.attrs) => {
+                functions.insert(
+                    item.sig.ident.to_string(),
+                    function(&item.attrs, &item.sig, &item.block, self_ty),
+                );
+            }
+            syn::Item::Impl(item) if !is_test(&item.attrs) => {
+                let self_ty = item.self_ty.to_token_stream().to_string();
+                for item in &item.items {
+                    match item {
+                        syn::ImplItem::Method(method) if !is_test(&method.attrs) => {
+                            functions.insert(
+                                format!("{}::{}", self_ty, method.sig.ident),
+                                function(&method.attrs, &method.sig, &method.block, Some(&self_ty)),
+                            );
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            syn::Item::Mod(module) if !is_test(&module.attrs) => {
+                if let Some((_, items)) = &module.content {
+                    collect_functions(items, self_ty, functions);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Returns `true` if the `attrs` contain `#[cfg(test)]` or `#[test]`.
+fn is_test(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path.is_ident("test")
+            || (attr.path.is_ident("cfg")
+                && attr
+                    .parse_args::<syn::Path>()
+                    .map_or(false, |path| path.is_ident("test")))
+    })
+}
+
+/// Analyzes a function with the `self_ty` of its impl, if it is a method.
+fn function(
+    attrs: &[syn::Attribute],
+    sig: &syn::Signature,
+    block: &syn::Block,
+    self_ty: Option<&str>,
+) -> Function {
+    let mutates = matches!(
+        sig.inputs.first(),
+        Some(syn::FnArg::Receiver(syn::Receiver {
+            reference: Some(_),
+            mutability: Some(_),
+            ..
+        }))
+    );
+    let payable = ink_args(attrs).iter().any(|meta| {
+        matches!(meta, syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("payable"))
+    });
+    let modifiers = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("modifiers"))
+        .flat_map(|attr| {
+            let mut idents = Vec::new();
+            collect_idents(attr.tokens.clone(), &mut idents);
+            idents
+        })
+        .collect();
+    let mut body = BodyVisitor {
+        self_ty,
+        calls: BTreeSet::new(),
+        callers: BTreeSet::new(),
+        checks_caller: false,
+    };
+    body.visit_block(block);
+    Function {
+        mutates,
+        message: has_ink_arg(attrs, "message"),
+        payable,
+        calls: body.calls,
+        checks_caller: body.checks_caller,
+        modifiers,
+    }
+}
+
+/// Collects the calls of the body of a function and whether it compares the caller.
+struct BodyVisitor<'a> {
+    /// The type of the impl of the function, to resolve the calls of `self` and `Self`.
+    self_ty: Option<&'a str>,
+    calls: BTreeSet<String>,
+    /// The local variables bound to the caller, e.g. `let caller = self.env().caller();`.
+    callers: BTreeSet<String>,
+    checks_caller: bool,
+}
+
+impl BodyVisitor<'_> {
+    /// Returns `true` if the `expr` is the caller, e.g. `self.env().caller()`.
+    fn is_caller(&self, expr: &syn::Expr) -> bool {
+        match expr {
+            syn::Expr::Paren(expr) => self.is_caller(&expr.expr),
+            syn::Expr::Reference(expr) => self.is_caller(&expr.expr),
+            syn::Expr::Path(path) => path
+                .path
+                .get_ident()
+                .map_or(false, |ident| self.callers.contains(&ident.to_string())),
+            syn::Expr::MethodCall(call) => call.method == "caller" && is_env(&call.receiver),
+            // e.g. `ink_env::caller::<Environment>()`
+            syn::Expr::Call(call) => match &*call.func {
+                syn::Expr::Path(path) => {
+                    let mut segments = path.path.segments.iter().rev();
+                    let mut next_is = |names: &[&str]| {
+                        segments.next().map_or(false, |segment| {
+                            names.iter().any(|name| segment.ident == name)
+                        })
+                    };
+                    next_is(&["caller"]) && next_is(&["ink_env", "env"])
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// The key of the function or method called by the `path`, e.g. `Vault::authorize` for
+    /// `Self::authorize`.
+    fn resolve(&self, path: &syn::Path) -> String {
+        let mut segments = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<_>>();
+        if let (Some(first), Some(self_ty)) = (segments.first_mut(), self.self_ty) {
+            if first == "Self" {
+                *first = self_ty.to_string();
+            }
+        }
+        segments.join("::")
+    }
+}
+
+impl<'ast> Visit<'ast> for BodyVisitor<'_> {
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        if let (syn::Pat::Ident(pat), Some((_, init))) = (&local.pat, &local.init) {
+            if self.is_caller(init) {
+                self.callers.insert(pat.ident.to_string());
+            }
+        }
+        syn::visit::visit_local(self, local);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast syn::ExprBinary) {
+        if matches!(expr.op, syn::BinOp::Eq(_) | syn::BinOp::Ne(_))
+            && (self.is_caller(&expr.left) || self.is_caller(&expr.right))
+        {
+            self.checks_caller = true;
+        }
+        syn::visit::visit_expr_binary(self, expr);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        let method = call.method.to_string();
+        let is_self =
+            matches!(&*call.receiver, syn::Expr::Path(path) if path.path.is_ident("self"));
+        match self.self_ty {
+            Some(self_ty) if is_self => self.calls.insert(format!("{}::{}", self_ty, method)),
+            _ => self.calls.insert(method.clone()),
+        };
+        // e.g. `self.owner.eq(&caller)`
+        if (method == "eq" || method == "ne")
+            && (self.is_caller(&call.receiver) || call.args.iter().any(|arg| self.is_caller(arg)))
+        {
+            self.checks_caller = true;
+        }
+        syn::visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(path) = &*call.func {
+            let key = self.resolve(&path.path);
+            self.calls.insert(key);
+        }
+        syn::visit::visit_expr_call(self, call);
+    }
+
+    /// The arguments of macros are visited if they are expressions, e.g. of `assert!`.
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        let args =
+            match mac.parse_body_with(Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated) {
+                Ok(args) => args,
+                Err(_) => return,
+            };
+        let compares = mac.path.is_ident("assert_eq") || mac.path.is_ident("assert_ne");
+        if compares && args.iter().take(2).any(|arg| self.is_caller(arg)) {
+            self.checks_caller = true;
+        }
+        for arg in &args {
+            self.visit_expr(arg);
+        }
+    }
+}
+
+/// Returns `true` if the `expr` is the environment of the contract, `self.env()` or `Self::env()`.
+fn is_env(expr: &syn::Expr) -> bool {
+    match expr {
+        syn::Expr::MethodCall(call) => {
+            call.method == "env"
+                && matches!(&*call.receiver, syn::Expr::Path(path) if path.path.is_ident("self"))
+        }
+        syn::Expr::Call(call) => {
+            matches!(&*call.func, syn::Expr::Path(path) if path.path.segments.len() == 2
+                && path.path.segments[0].ident == "Self"
+                && path.path.segments[1].ident == "env")
+        }
+        _ => false,
+    }
+}
+
+/// Collects the identifiers of the `tokens`, e.g. of the arguments of an attribute.
+pub(super) fn collect_idents(tokens: TokenStream, idents: &mut Vec<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => idents.push(ident.to_string()),
+            TokenTree::Group(group) => collect_idents(group.stream(), idents),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unguarded_mutating_messages_are_reported() {
+        let source = r#"
+            #[ink::contract]
+            mod vault {
+                impl Vault {
+                    #[ink(message)]
+                    pub fn balance(&self) -> Balance { self.balance }
+
+                    #[ink(message)]
+                    pub fn set_fee(&mut self, fee: u8) -> Result<(), Error> {
+                        self.authorize()?;
+                        self.fee = fee;
+                        Ok(())
+                    }
+
+                    #[ink(message)]
+                    pub fn pause(&mut self) {
+                        assert!(self.env().caller() == self.admin);
+                        self.paused = true;
+                    }
+
+                    #[ink(message, payable)]
+                    pub fn deposit(&mut self) {
+                        let caller = self.env().caller();
+                        self.deposits.insert(caller, &self.env().transferred_value());
+                    }
+
+                    #[ink(message)]
+                    #[modifiers(only_owner)]
+                    pub fn withdraw_all(&mut self) {}
+
+                    #[ink(message)]
+                    pub fn set_limit(&mut self, limit: Balance) {
+                        if limit == 0 {
+                            return
+                        }
+                        let caller = self.env().caller();
+                        self.limits.insert(caller, &limit);
+                    }
+
+                    #[ink(message)]
+                    pub fn set_rate(&mut self, rate: u8) {
+                        if self.validate() {
+                            self.rate = rate;
+                        }
+                    }
+
+                    fn authorize(&self) -> Result<(), Error> {
+                        self.check_caller()
+                    }
+
+                    fn check_caller(&self) -> Result<(), Error> {
+                        if self.env().caller() != self.owner {
+                            return Err(Error::NotOwner)
+                        }
+                        Ok(())
+                    }
+                }
+
+                impl Helper {
+                    fn validate(&self) -> bool {
+                        self.env().caller() == self.admin
+                    }
+                }
+
+                #[cfg(test)]
+                mod tests {
+                    impl Vault {
+                        fn validate(&self) -> bool {
+                            assert_eq!(Self::env().caller(), self.admin);
+                            true
+                        }
+                    }
+                }
+            }
+        "#;
+        let file = syn::parse_file(source).unwrap();
+        let mut functions = BTreeMap::new();
+        collect_functions(&file.items, None, &mut functions);
+        let messages = functions
+            .iter()
+            .filter(|(_, function)| function.message)
+            .map(|(key, function)| {
+                (
+                    function_name(key).to_string(),
+                    function.mutates,
+                    function.payable,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let access = analyze(&functions, &messages)
+            .into_iter()
+            .map(|message| (message.message, message.access, message.guard))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            access,
+            [
+                ("balance".to_string(), Access::ReadOnly, None),
+                ("deposit".to_string(), Access::Unrestricted, None),
+                (
+                    "pause".to_string(),
+                    Access::Restricted,
+                    Some("checks the caller".to_string())
+                ),
+                (
+                    "set_fee".to_string(),
+                    Access::Restricted,
+                    Some("calls `authorize`".to_string())
+                ),
+                ("set_limit".to_string(), Access::Unrestricted, None),
+                ("set_rate".to_string(), Access::Unrestricted, None),
+                (
+                    "withdraw_all".to_string(),
+                    Access::Restricted,
+                    Some("#[modifiers(only_owner)]".to_string())
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{name_value_println, util::decode_hex};
+use anyhow::Result;
+use sp_core::{
+    crypto::{AccountId32, Ss58AddressFormat, Ss58Codec},
+    ed25519, sr25519, Pair,
+};
+
+/// Generate and inspect accounts, like `subkey`.
+#[derive(Debug, clap::Args)]
+#[clap(name = "account")]
+pub struct AccountCommand {
+    #[clap(subcommand)]
+    action: AccountAction,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum AccountAction {
+    /// Generate an account with a new random mnemonic
+    Generate {
+        #[clap(flatten)]
+        format: KeyFormat,
+    },
+    /// Print the keys and the address of a secret URI, e.g. `//Alice` or a mnemonic
+    Inspect {
+        /// The secret URI.
+        suri: String,
+        /// Password for the secret URI.
+        #[clap(long, short)]
+        password: Option<String>,
+        #[clap(flatten)]
+        format: KeyFormat,
+    },
+    /// Derive a hard (`//`) or soft (`/`) path from a secret URI, e.g. `//stash/0`
+    Derive {
+        /// The secret URI.
+        suri: String,
+        /// The derivation path.
+        path: String,
+        /// Password for the secret URI.
+        #[clap(long, short)]
+        password: Option<String>,
+        #[clap(flatten)]
+        format: KeyFormat,
+    },
+    /// Convert an SS58 address or a hex encoded public key to the address of a network prefix
+    Convert {
+        /// The SS58 address or `0x` prefixed public key.
+        address: String,
+        /// The SS58 prefix of the network, e.g. 0 for Polkadot or 42 for generic Substrate.
+        #[clap(long, default_value = "42")]
+        prefix: u16,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct KeyFormat {
+    /// The signature scheme of the key.
+    #[clap(long, arg_enum, default_value = "sr25519")]
+    scheme: Scheme,
+    /// The SS58 prefix of the network, e.g. 0 for Polkadot or 42 for generic Substrate.
+    #[clap(long, default_value = "42")]
+    prefix: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ArgEnum)]
+enum Scheme {
+    Sr25519,
+    Ed25519,
+}
+
+/// The keys of an account.
+#[derive(Debug, PartialEq)]
+struct Keys {
+    /// The mnemonic the account was generated from.
+    phrase: Option<String>,
+    /// The seed of the key, unless it was derived with a soft junction.
+    secret_seed: Option<Vec<u8>>,
+    account: AccountId32,
+}
+
+impl AccountCommand {
+    pub fn exec(&self) -> Result<()> {
+        match &self.action {
+            AccountAction::Generate { format } => {
+                let keys = match format.scheme {
+                    Scheme::Sr25519 => generate::<sr25519::Pair>(),
+                    Scheme::Ed25519 => generate::<ed25519::Pair>(),
+                };
+                display_keys(&keys, format.prefix);
+            }
+            AccountAction::Inspect {
+                suri,
+                password,
+                format,
+            } => {
+                let keys = format.inspect(suri, password.as_deref())?;
+                display_keys(&keys, format.prefix);
+            }
+            AccountAction::Derive {
+                suri,
+                path,
+                password,
+                format,
+            } => {
+                let keys = format.inspect(&format!("{}{}", suri, path), password.as_deref())?;
+                display_keys(&keys, format.prefix);
+            }
+            AccountAction::Convert { address, prefix } => {
+                let account = parse_account(address)?;
+                name_value_println!("Public key", format!("0x{}", hex::encode(&account)));
+                name_value_println!("Address", address_with_prefix(&account, *prefix));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl KeyFormat {
+    fn inspect(&self, suri: &str, password: Option<&str>) -> Result<Keys> {
+        match self.scheme {
+            Scheme::Sr25519 => inspect::<sr25519::Pair>(suri, password),
+            Scheme::Ed25519 => inspect::<ed25519::Pair>(suri, password),
+        }
+    }
+}
+
+fn generate<P: Pair>() -> Keys
+where
+    AccountId32: From<P::Public>,
+{
+    let (pair, phrase, seed) = P::generate_with_phrase(None);
+    Keys {
+        phrase: Some(phrase),
+        secret_seed: Some(seed.as_ref().to_vec()),
+        account: pair.public().into(),
+    }
+}
+
+fn inspect<P: Pair>(suri: &str, password: Option<&str>) -> Result<Keys>
+where
+    AccountId32: From<P::Public>,
+{
+    let (pair, seed) = P::from_string_with_seed(suri, password)
+        .map_err(|err| anyhow::anyhow!("Invalid secret URI '{}': {:?}", suri, err))?;
+    Ok(Keys {
+        phrase: None,
+        secret_seed: seed.map(|seed| seed.as_ref().to_vec()),
+        account: pair.public().into(),
+    })
+}
+
+/// Parses an SS58 address of any network or a hex encoded public key.
+fn parse_account(input: &str) -> Result<AccountId32> {
+    if input.starts_with("0x") {
+        let bytes = decode_hex(input)?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("A public key should be 32 bytes in length"))?;
+        return Ok(AccountId32::new(bytes));
+    }
+    AccountId32::from_ss58check_with_version(input)
+        .map(|(account, _)| account)
+        .map_err(|err| anyhow::anyhow!("Invalid SS58 address '{}': {:?}", input, err))
+}
+
+fn address_with_prefix(account: &AccountId32, prefix: u16) -> String {
+    account.to_ss58check_with_version(Ss58AddressFormat::custom(prefix))
+}
+
+fn display_keys(keys: &Keys, prefix: u16) {
+    if let Some(phrase) = &keys.phrase {
+        name_value_println!("Phrase", phrase);
+    }
+    if let Some(seed) = &keys.secret_seed {
+        name_value_println!("Secret seed", format!("0x{}", hex::encode(seed)));
+    }
+    name_value_println!("Public key", format!("0x{}", hex::encode(&keys.account)));
+    name_value_println!("Address", address_with_prefix(&keys.account, prefix));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const ALICE_POLKADOT: &str = "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5";
+
+    #[test]
+    fn accounts_are_inspected_and_converted() {
+        let alice = inspect::<sr25519::Pair>("//Alice", None).unwrap();
+        assert_eq!(address_with_prefix(&alice.account, 42), ALICE);
+        assert_eq!(address_with_prefix(&alice.account, 0), ALICE_POLKADOT);
+
+        let public_key = format!("0x{}", hex::encode(&alice.account));
+        assert_eq!(parse_account(&public_key).unwrap(), alice.account);
+        assert_eq!(parse_account(ALICE_POLKADOT).unwrap(), alice.account);
+
+        let stash = inspect::<sr25519::Pair>("//Alice//stash", None).unwrap();
+        assert_ne!(stash.account, alice.account);
+        assert!(inspect::<sr25519::Pair>("Alice", None).is_err());
+    }
+}
@@ -0,0 +1,935 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Adding boilerplate and code to an existing contract.
+//!
+//! The source of the contract is parsed to find where the boilerplate belongs, it is inserted
+//! there and the rest of the file is left untouched. The manifest is edited with `toml_edit`, so
+//! that its comments and formatting are preserved.
+
+use super::{access_control::collect_idents, InitCiCommand};
+use crate::{
+    cmd::metadata::blake2_hash, crate_metadata::CrateMetadata, name_value_println,
+    workspace::ManifestPath,
+};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::{convert::TryFrom, fs, ops::Range, path::PathBuf};
+use toml_edit::{Document, InlineTable, Item, Table};
+
+/// The name of the module of the end-to-end tests.
+const E2E_TESTS_MODULE: &str = "e2e_tests";
+
+/// The feature the end-to-end tests are gated behind.
+const E2E_TESTS_FEATURE: &str = "e2e-tests";
+
+/// The OpenBrush release providing the PSP22 implementation, compatible with ink! 3.
+const OPENBRUSH_VERSION: &str = "2.0";
+
+/// Add boilerplate to an existing contract.
+#[derive(Debug, clap::Args)]
+#[clap(name = "add")]
+pub struct AddCommand {
+    #[clap(subcommand)]
+    action: AddAction,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum AddAction {
+    /// Add an end-to-end test module, run against a contracts node with
+    /// `cargo test --features e2e-tests`
+    #[clap(name = "e2e-tests")]
+    E2eTests {
+        /// Path to the `Cargo.toml` of the contract.
+        #[clap(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
+    },
+    /// Add a message skeleton with a unique selector and a unit test to the contract
+    #[clap(name = "message")]
+    Message {
+        /// The name of the message, e.g. `get_balance`.
+        name: String,
+        /// The selector of the message, derived from its name like ink! does by default.
+        #[clap(long, parse(try_from_str = parse_selector))]
+        selector: Option<u32>,
+        /// The message mutates the storage of the contract, i.e. takes `&mut self`.
+        #[clap(long)]
+        mutates: bool,
+        /// Path to the `Cargo.toml` of the contract.
+        #[clap(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
+    },
+    /// Add a CI pipeline, as `cargo contract init-ci` does
+    #[clap(name = "ci")]
+    Ci(InitCiCommand),
+    /// Implement the PSP22 fungible token standard with OpenBrush
+    #[clap(name = "psp22")]
+    Psp22 {
+        /// Path to the `Cargo.toml` of the contract.
+        #[clap(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
+    },
+}
+
+impl AddCommand {
+    pub fn exec(&self) -> Result<()> {
+        match &self.action {
+            AddAction::E2eTests { manifest_path } => {
+                let manifest_path = ManifestPath::try_from(manifest_path.as_ref())?;
+                add_e2e_tests(&manifest_path)
+            }
+            AddAction::Message {
+                name,
+                selector,
+                mutates,
+                manifest_path,
+            } => {
+                let manifest_path = ManifestPath::try_from(manifest_path.as_ref())?;
+                let message = NewMessage {
+                    name,
+                    selector: *selector,
+                    mutates: *mutates,
+                };
+                add_message(&manifest_path, &message)
+            }
+            AddAction::Ci(init_ci) => init_ci.exec(),
+            AddAction::Psp22 { manifest_path } => {
+                let manifest_path = ManifestPath::try_from(manifest_path.as_ref())?;
+                add_psp22(&manifest_path)
+            }
+        }
+    }
+}
+
+/// Adds the end-to-end test module to the contract and its dependencies to the manifest.
+fn add_e2e_tests(manifest_path: &ManifestPath) -> Result<()> {
+    let crate_metadata = CrateMetadata::collect(manifest_path)?;
+    let lib_path = crate_metadata.lib_src_path()?;
+    let source =
+        fs::read_to_string(&lib_path).context(format!("Failed to read {}", lib_path.display()))?;
+    let source = insert_e2e_tests(&source).context(format!(
+        "Failed to add the e2e tests to {}",
+        lib_path.display()
+    ))?;
+
+    let path = manifest_path.as_ref();
+    let mut manifest: Document = fs::read_to_string(path)
+        .context(format!("Failed to read {}", path.display()))?
+        .parse()
+        .context(format!("Failed to parse {}", path.display()))?;
+    let additions = add_e2e_manifest(&mut manifest)?;
+
+    fs::write(&lib_path, source).context(format!("Failed to write {}", lib_path.display()))?;
+    name_value_println!(
+        "Added",
+        format!("`mod {}` to {}", E2E_TESTS_MODULE, lib_path.display())
+    );
+    fs::write(path, manifest.to_string()).context(format!("Failed to write {}", path.display()))?;
+    for addition in additions {
+        name_value_println!("Added", format!("{} to {}", addition, path.display()));
+    }
+    println!(
+        "{} {}",
+        "Run the e2e tests with".bright_green().bold(),
+        format!("cargo test --features {}", E2E_TESTS_FEATURE).bold()
+    );
+    Ok(())
+}
+
+/// Adds the PSP22 implementation of OpenBrush to the contract and OpenBrush to the manifest.
+fn add_psp22(manifest_path: &ManifestPath) -> Result<()> {
+    let crate_metadata = CrateMetadata::collect(manifest_path)?;
+    let lib_path = crate_metadata.lib_src_path()?;
+    let source =
+        fs::read_to_string(&lib_path).context(format!("Failed to read {}", lib_path.display()))?;
+    let (source, storage) = insert_psp22(&source).context(format!(
+        "Failed to add the PSP22 implementation to {}",
+        lib_path.display()
+    ))?;
+
+    let path = manifest_path.as_ref();
+    let mut manifest: Document = fs::read_to_string(path)
+        .context(format!("Failed to read {}", path.display()))?
+        .parse()
+        .context(format!("Failed to parse {}", path.display()))?;
+    let additions = add_openbrush_manifest(&mut manifest)?;
+
+    fs::write(&lib_path, source).context(format!("Failed to write {}", lib_path.display()))?;
+    name_value_println!(
+        "Added",
+        format!("`impl PSP22 for {}` to {}", storage, lib_path.display())
+    );
+    fs::write(path, manifest.to_string()).context(format!("Failed to write {}", path.display()))?;
+    for addition in additions {
+        name_value_println!("Added", format!("{} to {}", addition, path.display()));
+    }
+    println!(
+        "{} {}",
+        "Mint the initial supply in the constructors, e.g. with"
+            .bright_green()
+            .bold(),
+        "instance._mint_to(caller, total_supply)".bold()
+    );
+    Ok(())
+}
+
+/// Adds the `message` and a unit test calling it to the contract.
+fn add_message(manifest_path: &ManifestPath, message: &NewMessage) -> Result<()> {
+    let crate_metadata = CrateMetadata::collect(manifest_path)?;
+    let lib_path = crate_metadata.lib_src_path()?;
+    let source =
+        fs::read_to_string(&lib_path).context(format!("Failed to read {}", lib_path.display()))?;
+    let (source, selector) = insert_message(&source, message).context(format!(
+        "Failed to add the message to {}",
+        lib_path.display()
+    ))?;
+    fs::write(&lib_path, source).context(format!("Failed to write {}", lib_path.display()))?;
+    name_value_println!(
+        "Added",
+        format!(
+            "`fn {}` with the selector 0x{:08X} to {}",
+            message.name,
+            selector,
+            lib_path.display()
+        )
+    );
+    Ok(())
+}
+
+/// Inserts the end-to-end test module at the end of the `#[ink::contract]` module of the
+/// `source`, indented like the items of the module.
+fn insert_e2e_tests(source: &str) -> Result<String> {
+    let file = syn::parse_file(source).context("Failed to parse the contract")?;
+    let (brace, items) = contract_module(&file)?;
+    if items
+        .iter()
+        .any(|item| matches!(item, syn::Item::Mod(module) if module.ident == E2E_TESTS_MODULE))
+    {
+        anyhow::bail!("The contract already contains a `mod {}`", E2E_TESTS_MODULE)
+    }
+
+    Ok(insert(
+        source,
+        vec![at_end_of(source, brace, &e2e_tests_module())],
+    ))
+}
+
+/// Turns the contract of the `source` into an OpenBrush contract implementing PSP22: the
+/// `#[ink::contract]` attribute becomes `#[openbrush::contract]`, the storage gets the PSP22 data
+/// as field and `impl PSP22` is added. Returns the updated source and the name of the storage.
+fn insert_psp22(source: &str) -> Result<(String, String)> {
+    let file = syn::parse_file(source).context("Failed to parse the contract")?;
+    let contract = contract(&file)?;
+    let (brace, items) = contract
+        .content
+        .as_ref()
+        .context("The `#[ink::contract]` module has no body")?;
+    let storage = items
+        .iter()
+        .find_map(|item| match item {
+            syn::Item::Struct(item) if has_ink_arg(&item.attrs, "storage") => Some(item),
+            _ => None,
+        })
+        .context("No `#[ink(storage)]` struct found in the contract")?;
+    let implements_psp22 = items.iter().any(|item| match item {
+        syn::Item::Impl(item) => {
+            let trait_ = item
+                .trait_
+                .as_ref()
+                .and_then(|(_, path, _)| path.segments.last());
+            trait_.map_or(false, |segment| segment.ident == "PSP22")
+        }
+        _ => false,
+    });
+    if implements_psp22 {
+        anyhow::bail!("The contract already implements PSP22")
+    }
+    let fields = match &storage.fields {
+        syn::Fields::Named(fields) => fields,
+        _ => anyhow::bail!("The `#[ink(storage)]` struct has no named fields"),
+    };
+
+    let mut edits = Vec::new();
+    let attr = contract
+        .attrs
+        .iter()
+        .find(|attr| is_contract_attr(attr))
+        .expect("the contract module has the attribute");
+    edits.push((
+        offset(source, attr.pound_token.spans[0].start())
+            ..offset(source, attr.bracket_token.span.end()),
+        "#[openbrush::contract]".to_string(),
+    ));
+    edits.push(at_start_of(
+        source,
+        brace,
+        "use ink_storage::traits::SpreadAllocate;\nuse openbrush::contracts::psp22::*;\n",
+    ));
+    let derive = storage
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("derive"));
+    match derive {
+        Some(derive) => {
+            let mut derived = Vec::new();
+            collect_idents(derive.tokens.clone(), &mut derived);
+            let missing = ["SpreadAllocate", "PSP22Storage"]
+                .into_iter()
+                .filter(|derive| !derived.iter().any(|derived| derived == *derive))
+                .map(|derive| format!(", {}", derive))
+                .collect::<String>();
+            let end = offset(source, derive.bracket_token.span.end());
+            let end = source[..end]
+                .rfind(')')
+                .context("Failed to parse the `derive`")?;
+            edits.push((end..end, missing));
+        }
+        None => {
+            let start = offset(source, storage.struct_token.span.start());
+            let start = match &storage.vis {
+                syn::Visibility::Public(vis) => offset(source, vis.pub_token.span.start()),
+                _ => start,
+            };
+            let indent = source[..start].rsplit('\n').next().unwrap_or_default();
+            edits.push((
+                start..start,
+                format!("#[derive(SpreadAllocate, PSP22Storage)]\n{}", indent),
+            ));
+        }
+    }
+    let close = offset(source, fields.brace_token.span.end()) - 1;
+    if !fields.named.is_empty() && !source[..close].trim_end().ends_with(',') {
+        let end = source[..close].trim_end().len();
+        edits.push((end..end, ",".to_string()));
+    }
+    let (at, code) = at_end_of(
+        source,
+        &fields.brace_token,
+        "#[PSP22StorageField]\npsp22: PSP22Data,\n",
+    );
+    // right after the last field, without an empty line
+    edits.push((at..at, code.replacen('\n', "", 1)));
+    let (at, code) = at_end_of(
+        source,
+        brace,
+        &format!("impl PSP22 for {} {{}}\n", storage.ident),
+    );
+    edits.push((at..at, code));
+    Ok((edit(source, edits), storage.ident.to_string()))
+}
+
+/// A message to add to a contract.
+#[derive(Debug)]
+struct NewMessage<'a> {
+    name: &'a str,
+    selector: Option<u32>,
+    mutates: bool,
+}
+
+/// Inserts the `message` at the end of the `impl` block of the storage of the contract in the
+/// `source`, and a unit test calling it at the end of its `mod tests`. Returns the updated source
+/// and the selector of the message.
+fn insert_message(source: &str, message: &NewMessage) -> Result<(String, u32)> {
+    syn::parse_str::<syn::Ident>(message.name)
+        .map_err(|_| anyhow::anyhow!("`{}` is not a valid message name", message.name))?;
+    let file = syn::parse_file(source).context("Failed to parse the contract")?;
+    let (contract_brace, items) = contract_module(&file)?;
+    let storage = items
+        .iter()
+        .find_map(|item| match item {
+            syn::Item::Struct(item) if has_ink_arg(&item.attrs, "storage") => Some(&item.ident),
+            _ => None,
+        })
+        .context("No `#[ink(storage)]` struct found in the contract")?;
+    let impls = items
+        .iter()
+        .filter_map(|item| match item {
+            syn::Item::Impl(item) if is_impl_of(item, storage) => Some(item),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let storage_impl = impls
+        .iter()
+        .find(|item| item.trait_.is_none())
+        .context(format!("No `impl {}` block found in the contract", storage))?;
+
+    let mut selectors = Vec::new();
+    let mut constructor = None;
+    for item in &impls {
+        for method in item.items.iter().filter_map(|item| match item {
+            syn::ImplItem::Method(method) => Some(method),
+            _ => None,
+        }) {
+            let name = method.sig.ident.to_string();
+            if name == message.name {
+                anyhow::bail!("The contract already contains a `fn {}`", message.name)
+            }
+            if has_ink_arg(&method.attrs, "message") {
+                let selector = ink_selector(&method.attrs).unwrap_or_else(|| {
+                    // messages of trait implementations are namespaced by the trait
+                    let path = match &item.trait_ {
+                        Some((_, path, _)) => {
+                            let segment = path.segments.last().expect("a path is never empty");
+                            format!("{}::{}", segment.ident, name)
+                        }
+                        None => name.clone(),
+                    };
+                    default_selector(&path)
+                });
+                selectors.push((selector, name));
+            } else if has_ink_arg(&method.attrs, "constructor")
+                && method.sig.inputs.is_empty()
+                && constructor.is_none()
+            {
+                constructor = Some(name);
+            }
+        }
+    }
+    let selector = message
+        .selector
+        .unwrap_or_else(|| default_selector(message.name));
+    if let Some((_, name)) = selectors.iter().find(|(existing, _)| *existing == selector) {
+        anyhow::bail!(
+            "The selector 0x{:08X} is already used by the message `{}`, pass another one with \
+             `--selector`",
+            selector,
+            name
+        )
+    }
+
+    let mut insertions = vec![at_end_of(
+        source,
+        &storage_impl.brace_token,
+        &message_fn(message, selector),
+    )];
+    let test = message_test(message, storage, constructor.as_deref());
+    let tests = items.iter().find_map(|item| match item {
+        syn::Item::Mod(module) if module.ident == "tests" => module.content.as_ref(),
+        _ => None,
+    });
+    match tests {
+        Some((brace, _)) => insertions.push(at_end_of(source, brace, &test)),
+        None => {
+            let tests = format!(
+                "#[cfg(test)]\nmod tests {{\n    use super::*;\n\n{}}}\n",
+                indented(&test, "    ")
+            );
+            insertions.push(at_end_of(source, contract_brace, &tests))
+        }
+    }
+    Ok((insert(source, insertions), selector))
+}
+
+fn message_fn(message: &NewMessage, selector: u32) -> String {
+    format!(
+        "/// TODO: document `{name}`.\n\
+         #[ink(message, selector = 0x{selector:08X})]\n\
+         pub fn {name}({receiver}) {{}}\n",
+        name = message.name,
+        selector = selector,
+        receiver = if message.mutates {
+            "&mut self"
+        } else {
+            "&self"
+        },
+    )
+}
+
+fn message_test(message: &NewMessage, storage: &syn::Ident, constructor: Option<&str>) -> String {
+    let body = match constructor {
+        Some(constructor) => format!(
+            "    let {mutability}contract = {storage}::{constructor}();\n    contract.{name}();\n",
+            mutability = if message.mutates { "mut " } else { "" },
+            storage = storage,
+            constructor = constructor,
+            name = message.name,
+        ),
+        None => format!(
+            "    // instantiate the contract with one of its constructors and call `{}`\n",
+            message.name
+        ),
+    };
+    format!(
+        "#[ink::test]\nfn {name}_works() {{\n{body}}}\n",
+        name = message.name,
+        body = body
+    )
+}
+
+/// Returns `true` if the `item` implements a trait or methods for the `storage`.
+fn is_impl_of(item: &syn::ItemImpl, storage: &syn::Ident) -> bool {
+    match &*item.self_ty {
+        syn::Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.ident == *storage),
+        _ => false,
+    }
+}
+
+/// Returns the arguments of the `#[ink(..)]` attributes in `attrs`.
+pub(super) fn ink_args(attrs: &[syn::Attribute]) -> Vec<syn::NestedMeta> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("ink"))
+        // arguments ink! accepts but `syn` does not, such as `selector = _`, are skipped
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => Some(list.nested.into_iter()),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// Returns `true` if the `attrs` contain `#[ink(arg)]`, e.g. `#[ink(message)]`.
+pub(super) fn has_ink_arg(attrs: &[syn::Attribute], arg: &str) -> bool {
+    ink_args(attrs).iter().any(
+        |meta| matches!(meta, syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident(arg)),
+    )
+}
+
+/// Returns the selector of an `#[ink(selector = ..)]` attribute in `attrs`.
+fn ink_selector(attrs: &[syn::Attribute]) -> Option<u32> {
+    ink_args(attrs).iter().find_map(|meta| match meta {
+        syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+            path,
+            lit: syn::Lit::Int(selector),
+            ..
+        })) if path.is_ident("selector") => selector.base10_parse().ok(),
+        _ => None,
+    })
+}
+
+/// Returns the selector ink! derives for a message at `path` without an explicit one: the first
+/// four bytes of the BLAKE2b hash of the path.
+pub(crate) fn default_selector(path: &str) -> u32 {
+    let hash = blake2_hash(path.as_bytes()).0;
+    u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]])
+}
+
+/// Parses a selector such as `0xCAFEBABE`.
+fn parse_selector(input: &str) -> Result<u32> {
+    let digits = input.trim_start_matches("0x");
+    if digits.len() != 8 {
+        anyhow::bail!("A selector should be 4 bytes in length, e.g. `0xCAFEBABE`")
+    }
+    u32::from_str_radix(digits, 16).context(format!("Invalid selector `{}`", input))
+}
+
+/// Returns the braces and the items of the `#[ink::contract]` module of the `file`.
+fn contract_module(file: &syn::File) -> Result<(&syn::token::Brace, &[syn::Item])> {
+    let (brace, items) = contract(file)?
+        .content
+        .as_ref()
+        .context("The `#[ink::contract]` module has no body")?;
+    Ok((brace, items.as_slice()))
+}
+
+/// Returns the `#[ink::contract]` module of the `file`.
+fn contract(file: &syn::File) -> Result<&syn::ItemMod> {
+    file.items
+        .iter()
+        .find_map(|item| match item {
+            syn::Item::Mod(module) if module.attrs.iter().any(is_contract_attr) => Some(module),
+            _ => None,
+        })
+        .context("No `#[ink::contract]` module found")
+}
+
+/// Returns `true` for `#[ink::contract]` or `#[ink_lang::contract]`.
+fn is_contract_attr(attr: &syn::Attribute) -> bool {
+    attr.path
+        .segments
+        .last()
+        .map_or(false, |segment| segment.ident == "contract")
+}
+
+/// Returns the byte offset of the `position` in the `source`.
+fn offset(source: &str, position: proc_macro2::LineColumn) -> usize {
+    let line_start = source
+        .split_inclusive('\n')
+        .take(position.line - 1)
+        .map(str::len)
+        .sum::<usize>();
+    // the column counts characters, not bytes
+    line_start
+        + source[line_start..]
+            .chars()
+            .take(position.column)
+            .map(char::len_utf8)
+            .sum::<usize>()
+}
+
+/// Returns where and what to insert to add the `code` at the end of the block closed by the
+/// `brace`, indented one level deeper than the closing brace.
+fn at_end_of(source: &str, brace: &syn::token::Brace, code: &str) -> (usize, String) {
+    // the span of the braces ends right after the closing one
+    let close = offset(source, brace.span.end()) - 1;
+    let line_start = source[..close].rfind('\n').map_or(0, |i| i + 1);
+    let closing_line = &source[line_start..close];
+    let (insert_at, prefix, indent) = if closing_line.trim().is_empty() {
+        (line_start, "", format!("{}    ", closing_line))
+    } else {
+        (close, "\n", "    ".to_string())
+    };
+    (
+        insert_at,
+        format!("{}\n{}", prefix, indented(code, &indent)),
+    )
+}
+
+/// Returns where and what to insert to add the `code` at the start of the block opened by the
+/// `brace`, on the line after the opening one.
+fn at_start_of(source: &str, brace: &syn::token::Brace, code: &str) -> (Range<usize>, String) {
+    let close = offset(source, brace.span.end()) - 1;
+    let line_start = source[..close].rfind('\n').map_or(0, |i| i + 1);
+    let indent = source[line_start..close]
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect::<String>();
+    // after the line of the opening brace
+    let open = offset(source, brace.span.start()) + 1;
+    let at = source[open..]
+        .find('\n')
+        .map_or(source.len(), |i| open + i + 1);
+    (
+        at..at,
+        format!("{}\n", indented(code, &format!("{}    ", indent))),
+    )
+}
+
+/// Returns the `code` with its non-empty lines indented by `indent`.
+fn indented(code: &str, indent: &str) -> String {
+    code.lines()
+        .map(|line| match line.is_empty() {
+            true => "\n".to_string(),
+            false => format!("{}{}\n", indent, line),
+        })
+        .collect()
+}
+
+/// Applies the `insertions` of `at_end_of` to the `source`.
+fn insert(source: &str, insertions: Vec<(usize, String)>) -> String {
+    let edits = insertions
+        .into_iter()
+        .map(|(offset, code)| (offset..offset, code))
+        .collect();
+    edit(source, edits)
+}
+
+/// Replaces the ranges of the `edits` of the `source` by their code.
+fn edit(source: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    let mut source = source.to_string();
+    // the later edits first, so that the offsets of the earlier ones remain valid
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    for (range, code) in edits {
+        source.replace_range(range, &code);
+    }
+    source
+}
+
+fn e2e_tests_module() -> String {
+    format!(
+        r#"/// End-to-end tests of the contract against a contracts node, run with
+/// `cargo test --features {feature}`. They require `cargo-contract` and
+/// `substrate-contracts-node` to be installed.
+#[cfg(all(test, feature = "{feature}"))]
+mod {module} {{
+    use std::{{
+        net::TcpStream,
+        process::{{Child, Command}},
+        thread,
+        time::Duration,
+    }};
+
+    /// A development `substrate-contracts-node`, which is killed when dropped.
+    struct Node(Child);
+
+    impl Node {{
+        /// Spawns the node and waits until it accepts connections.
+        fn spawn() -> anyhow::Result<Self> {{
+            let node = Node(
+                Command::new("substrate-contracts-node")
+                    .args(["--dev", "--tmp", "--ws-port", "9944"])
+                    .spawn()?,
+            );
+            for _ in 0..60 {{
+                if TcpStream::connect("127.0.0.1:9944").is_ok() {{
+                    return Ok(node);
+                }}
+                thread::sleep(Duration::from_millis(500));
+            }}
+            anyhow::bail!("The node did not accept connections")
+        }}
+    }}
+
+    impl Drop for Node {{
+        fn drop(&mut self) {{
+            let _ = self.0.kill();
+        }}
+    }}
+
+    /// Runs `cargo contract` with the `args` for this contract, returning its output.
+    fn cargo_contract(args: &[&str]) -> anyhow::Result<String> {{
+        let output = Command::new("cargo")
+            .arg("contract")
+            .args(args)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()?;
+        if !output.status.success() {{
+            anyhow::bail!("{{}}", String::from_utf8_lossy(&output.stderr))
+        }}
+        Ok(String::from_utf8(output.stdout)?)
+    }}
+
+    #[test]
+    fn it_works() -> anyhow::Result<()> {{
+        let _node = Node::spawn()?;
+        cargo_contract(&["build"])?;
+        cargo_contract(&["upload", "--suri", "//Alice"])?;
+        // instantiate the contract with `cargo_contract(&["instantiate", ..])` and call its
+        // messages with `cargo_contract(&["call", ..])`
+        Ok(())
+    }}
+}}
+"#,
+        feature = E2E_TESTS_FEATURE,
+        module = E2E_TESTS_MODULE,
+    )
+}
+
+/// Adds OpenBrush with its PSP22 implementation to the dependencies of the `manifest` and to its
+/// `std` feature, unless they are present already.
+fn add_openbrush_manifest(manifest: &mut Document) -> Result<Vec<String>> {
+    let mut additions = Vec::new();
+    let dependencies = manifest
+        .as_table_mut()
+        .entry("dependencies")
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_like_mut()
+        .context("`dependencies` is not a table")?;
+    if !dependencies.contains_key("openbrush") {
+        let mut openbrush = InlineTable::new();
+        openbrush.insert("version", OPENBRUSH_VERSION.into());
+        openbrush.insert("default-features", false.into());
+        openbrush.insert("features", toml_edit::Array::from_iter(["psp22"]).into());
+        dependencies.insert("openbrush", toml_edit::value(openbrush));
+        additions.push("`openbrush` to `[dependencies]`".to_string());
+    }
+
+    let std = manifest
+        .get_mut("features")
+        .and_then(|features| features.get_mut("std"))
+        .and_then(Item::as_array_mut);
+    if let Some(std) = std {
+        if !std
+            .iter()
+            .any(|feature| feature.as_str() == Some("openbrush/std"))
+        {
+            std.push("openbrush/std");
+            additions.push("`openbrush/std` to the `std` feature".to_string());
+        }
+    }
+    Ok(additions)
+}
+
+/// Adds the `e2e-tests` feature and the dev-dependencies of the end-to-end tests to the
+/// `manifest`, unless they are present already.
+fn add_e2e_manifest(manifest: &mut Document) -> Result<Vec<String>> {
+    let mut additions = Vec::new();
+    let features = manifest
+        .as_table_mut()
+        .entry("features")
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+        .context("`features` is not a table")?;
+    if !features.contains_key(E2E_TESTS_FEATURE) {
+        features.insert(E2E_TESTS_FEATURE, toml_edit::value(toml_edit::Array::new()));
+        additions.push(format!("`{} = []` in `[features]`", E2E_TESTS_FEATURE));
+    }
+
+    // the tests drive the `cargo contract` and node executables, only `anyhow` is required
+    let dependencies = [("anyhow", toml_edit::Value::from("1"))];
+
+    let dev_dependencies = manifest
+        .as_table_mut()
+        .entry("dev-dependencies")
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_like_mut()
+        .context("`dev-dependencies` is not a table")?;
+    for (name, dependency) in dependencies {
+        if dev_dependencies.contains_key(name) {
+            continue;
+        }
+        additions.push(format!("`{}` to `[dev-dependencies]`", name));
+        dev_dependencies.insert(name, toml_edit::value(dependency));
+    }
+    Ok(additions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn e2e_tests_are_inserted_into_the_contract() {
+        let source = r#"#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+#[ink::contract]
+mod flipper {
+    // Stores a single `bool`, flipped by `flip`.
+    #[ink(storage)]
+    pub struct Flipper {
+        value: bool,
+    }
+
+    #[cfg(test)]
+    mod tests {}
+}
+"#;
+        let inserted = insert_e2e_tests(source).unwrap();
+        assert!(inserted.starts_with(&source[..source.rfind("}\n").unwrap()]));
+        assert!(inserted
+            .contains("    #[cfg(all(test, feature = \"e2e-tests\"))]\n    mod e2e_tests {"));
+        assert!(inserted.ends_with("        }\n    }\n}\n"));
+        syn::parse_file(&inserted).unwrap();
+        assert!(insert_e2e_tests(&inserted).is_err());
+        assert!(insert_e2e_tests("mod flipper {}").is_err());
+
+        let mut manifest =
+            "[package]\nname = \"flipper\"\n\n[features]\n# The default\ndefault = [\"std\"]\n"
+                .parse::<Document>()
+                .unwrap();
+        assert_eq!(add_e2e_manifest(&mut manifest).unwrap().len(), 2);
+        assert!(add_e2e_manifest(&mut manifest).unwrap().is_empty());
+        let manifest = manifest.to_string();
+        assert!(manifest.contains("# The default"));
+        let manifest: toml::Value = toml::from_str(&manifest).unwrap();
+        assert_eq!(manifest["dev-dependencies"], toml::toml! { anyhow = "1" });
+        assert_eq!(
+            manifest["features"]["e2e-tests"],
+            toml::Value::Array(Vec::new())
+        );
+    }
+
+    #[test]
+    fn psp22_is_implemented_by_the_storage() {
+        let source = r#"#[ink::contract]
+mod token {
+    #[ink(storage)]
+    #[derive(Default)]
+    pub struct Token {
+        owner: AccountId
+    }
+
+    impl Token {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::default()
+        }
+    }
+}
+"#;
+        let (inserted, storage) = insert_psp22(source).unwrap();
+        assert_eq!(storage, "Token");
+        assert!(inserted.starts_with(
+            "#[openbrush::contract]\nmod token {\n    \
+             use ink_storage::traits::SpreadAllocate;\n    \
+             use openbrush::contracts::psp22::*;\n\n    #[ink(storage)]\n    \
+             #[derive(Default, SpreadAllocate, PSP22Storage)]\n"
+        ));
+        assert!(inserted.contains(
+            "        owner: AccountId,\n        #[PSP22StorageField]\n        \
+             psp22: PSP22Data,\n    }"
+        ));
+        assert!(inserted.ends_with("    }\n\n    impl PSP22 for Token {}\n}\n"));
+        syn::parse_file(&inserted).unwrap();
+        assert!(insert_psp22(&inserted).is_err());
+
+        let mut manifest = "[package]\nname = \"token\"\n\n[features]\nstd = [\"ink_lang/std\"]\n"
+            .parse::<Document>()
+            .unwrap();
+        assert_eq!(add_openbrush_manifest(&mut manifest).unwrap().len(), 2);
+        assert!(add_openbrush_manifest(&mut manifest).unwrap().is_empty());
+        let manifest: toml::Value = toml::from_str(&manifest.to_string()).unwrap();
+        assert_eq!(
+            manifest["dependencies"]["openbrush"]["features"],
+            toml::Value::Array(vec!["psp22".into()])
+        );
+        assert_eq!(
+            manifest["features"]["std"],
+            toml::Value::Array(vec!["ink_lang/std".into(), "openbrush/std".into()])
+        );
+    }
+
+    #[test]
+    fn message_is_inserted_with_a_unique_selector() {
+        let source = r#"#[ink::contract]
+mod flipper {
+    #[ink(storage)]
+    pub struct Flipper {
+        value: bool,
+    }
+
+    impl Flipper {
+        #[ink(constructor)]
+        pub fn default() -> Self {
+            Self { value: false }
+        }
+
+        #[ink(message)]
+        pub fn flip(&mut self) {
+            self.value = !self.value;
+        }
+
+        #[ink(message, selector = 0xCAFEBABE)]
+        pub fn get(&self) -> bool {
+            self.value
+        }
+    }
+}
+"#;
+        assert_eq!(default_selector("flip"), 0x633AA551);
+        assert_eq!(parse_selector("0xCAFEBABE").unwrap(), 0xCAFEBABE);
+        assert!(parse_selector("0xCAFE").is_err());
+
+        let message = |name, selector| NewMessage {
+            name,
+            selector,
+            mutates: true,
+        };
+        let (inserted, selector) = insert_message(source, &message("toggle", None)).unwrap();
+        assert_eq!(selector, default_selector("toggle"));
+        assert!(inserted.contains(&format!(
+            "        #[ink(message, selector = 0x{:08X})]\n        \
+             pub fn toggle(&mut self) {{}}\n    }}",
+            selector
+        )));
+        assert!(inserted.contains(
+            "    #[cfg(test)]\n    mod tests {\n        use super::*;\n\n        #[ink::test]\n        \
+             fn toggle_works() {\n            let mut contract = Flipper::default();\n"
+        ));
+        syn::parse_file(&inserted).unwrap();
+
+        assert!(insert_message(source, &message("get", None)).is_err());
+        assert!(insert_message(source, &message("fn", None)).is_err());
+        assert!(insert_message(source, &message("set", Some(0xCAFEBABE))).is_err());
+        assert!(insert_message(source, &message("set", Some(default_selector("flip")))).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+    address_book::{address_book_path, AddressBook, AddressBookEntry},
+    name_value_println,
+};
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+/// Manage human readable aliases of deployed contracts.
+///
+/// An alias can be used instead of an SS58 address by every command, e.g. as the `--contract` to
+/// call or as an `AccountId` argument of a message.
+#[derive(Debug, clap::Args)]
+#[clap(name = "address-book")]
+pub struct AddressBookCommand {
+    #[clap(subcommand)]
+    action: AddressBookAction,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum AddressBookAction {
+    /// Add a contract under an alias, replacing an existing contract with the same alias
+    Add {
+        /// The alias of the contract.
+        alias: String,
+        /// The SS58 address of the contract.
+        address: String,
+        /// The network profile the contract is deployed on.
+        #[clap(long)]
+        network: Option<String>,
+        /// The code hash of the contract.
+        #[clap(long)]
+        code_hash: Option<String>,
+        /// Path to the metadata of the contract.
+        #[clap(long, parse(from_os_str))]
+        metadata: Option<PathBuf>,
+    },
+    /// List the contracts of the address book
+    List,
+    /// Print the contract with the given alias
+    Resolve {
+        /// The alias of the contract.
+        alias: String,
+    },
+}
+
+impl AddressBookCommand {
+    pub fn exec(&self) -> Result<()> {
+        let path = address_book_path().context("No configuration directory found")?;
+        let mut address_book = AddressBook::load_from(&path)?;
+        match &self.action {
+            AddressBookAction::Add {
+                alias,
+                address,
+                network,
+                code_hash,
+                metadata,
+            } => {
+                let metadata = metadata
+                    .as_ref()
+                    .map(|metadata| metadata.canonicalize())
+                    .transpose()
+                    .context("Failed to resolve the metadata path")?;
+                address_book.add(
+                    alias,
+                    AddressBookEntry {
+                        network: network.clone(),
+                        address: address.clone(),
+                        code_hash: code_hash.clone(),
+                        metadata,
+                    },
+                )?;
+                address_book.save_to(&path)?;
+                name_value_println!("Added", alias);
+            }
+            AddressBookAction::List => {
+                for (alias, entry) in &address_book.contracts {
+                    println!("{}", display_entry(alias, entry));
+                }
+            }
+            AddressBookAction::Resolve { alias } => {
+                let entry = address_book.get(alias)?;
+                name_value_println!("Address", entry.address);
+                if let Some(network) = &entry.network {
+                    name_value_println!("Network", network);
+                }
+                if let Some(code_hash) = &entry.code_hash {
+                    name_value_println!("Code hash", code_hash);
+                }
+                if let Some(metadata) = &entry.metadata {
+                    name_value_println!("Metadata", metadata.display().to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn display_entry(alias: &str, entry: &AddressBookEntry) -> String {
+    match &entry.network {
+        Some(network) => format!("{} {} ({})", alias, entry.address, network),
+        None => format!("{} {}", alias, entry.address),
+    }
+}
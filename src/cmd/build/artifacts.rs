@@ -0,0 +1,156 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Copying the artifacts of a build to `--output-dir`, named after `--artifact-name`.
+//!
+//! The artifacts in `target/ink` are left as they are, so that the other commands still find
+//! them there.
+
+use crate::BuildResult;
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The values of the placeholders of an artifact name template.
+pub(crate) struct ArtifactVars<'a> {
+    /// The name of the contract, `{name}`.
+    pub name: &'a str,
+    /// The version of the contract's package, `{version}`.
+    pub version: String,
+    /// The name of the contract profile, or else the build mode, `{profile}`.
+    pub profile: String,
+    /// The name of the feature set in a feature matrix build, `{features}`.
+    pub features: Option<&'a str>,
+}
+
+/// Renders the file name of the artifacts, without extension, from the `template`.
+///
+/// Without a template the artifacts are named after the contract, and in a feature matrix build
+/// additionally after the feature set.
+pub(crate) fn render(template: Option<&str>, vars: &ArtifactVars) -> Result<String> {
+    let template = match (template, vars.features) {
+        (Some(template), Some(_)) if !template.contains("{features}") => {
+            anyhow::bail!(
+                "The artifact name `{}` must contain `{{features}}` in a feature matrix build, \
+                 otherwise the artifacts of the feature sets overwrite each other",
+                template
+            )
+        }
+        (Some(template), _) => template,
+        (None, Some(_)) => "{name}-{features}",
+        (None, None) => "{name}",
+    };
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .context(format!(
+                "Unclosed placeholder in the artifact name `{}`",
+                template
+            ))?;
+        let value = match &rest[start + 1..end] {
+            "name" => vars.name,
+            "version" => &vars.version,
+            "profile" => &vars.profile,
+            "features" => vars.features.unwrap_or_default(),
+            placeholder => {
+                anyhow::bail!(
+                    "Unknown placeholder `{{{}}}` in the artifact name, expected one of \
+                     `{{name}}`, `{{version}}`, `{{profile}}` or `{{features}}`",
+                    placeholder
+                )
+            }
+        };
+        rendered.push_str(value);
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+    if rendered.contains(std::path::is_separator) {
+        anyhow::bail!(
+            "The artifact name `{}` must not contain a path separator, use `--output-dir`",
+            rendered
+        )
+    }
+    Ok(rendered)
+}
+
+/// Copies the artifacts of `result` to `output_dir`, or else next to themselves, as
+/// `<artifact_name>.wasm`, `<artifact_name>.json` and `<artifact_name>.contract`, and points
+/// `result` to the copies.
+pub(crate) fn copy(
+    result: &mut BuildResult,
+    output_dir: Option<&Path>,
+    artifact_name: &str,
+) -> Result<()> {
+    let output_dir = output_dir.unwrap_or(&result.target_directory).to_path_buf();
+    fs::create_dir_all(&output_dir)
+        .context(format!("Failed to create {}", output_dir.display()))?;
+    let copy_to = |source: &Path, extension: &str| -> Result<PathBuf> {
+        let dest = output_dir.join(format!("{}.{}", artifact_name, extension));
+        if dest != source {
+            fs::copy(source, &dest).context(format!(
+                "Failed to copy {} to {}",
+                source.display(),
+                dest.display()
+            ))?;
+        }
+        Ok(dest)
+    };
+
+    if let Some(dest_wasm) = result.dest_wasm.as_mut() {
+        *dest_wasm = copy_to(dest_wasm, "wasm")?;
+        if let Some(optimization_result) = result.optimization_result.as_mut() {
+            optimization_result.dest_wasm = dest_wasm.clone();
+        }
+    }
+    if let Some(metadata_result) = result.metadata_result.as_mut() {
+        metadata_result.dest_metadata = copy_to(&metadata_result.dest_metadata, "json")?;
+        metadata_result.dest_bundle = copy_to(&metadata_result.dest_bundle, "contract")?;
+    }
+    result.target_directory = output_dir;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn artifact_name_is_rendered() {
+        let mut vars = ArtifactVars {
+            name: "flipper",
+            version: "1.2.0".into(),
+            profile: "release".into(),
+            features: None,
+        };
+        assert_eq!(
+            render(Some("{name}-{version}-{profile}"), &vars).unwrap(),
+            "flipper-1.2.0-release"
+        );
+        assert_eq!(render(None, &vars).unwrap(), "flipper");
+        assert!(render(Some("{name}-{commit}"), &vars).is_err());
+        assert!(render(Some("dist/{name}"), &vars).is_err());
+
+        vars.features = Some("rococo");
+        assert_eq!(render(None, &vars).unwrap(), "flipper-rococo");
+        assert!(render(Some("{name}-{version}"), &vars).is_err());
+    }
+}
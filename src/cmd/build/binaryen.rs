@@ -0,0 +1,249 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A pinned release of binaryen, whose `wasm-opt` optimizes the contract instead of whichever one
+//! happens to be installed, so that every machine produces the same Wasm.
+//!
+//! The release archive is downloaded with `curl`, verified against the SHA-256 checksum pinned in
+//! `CHECKSUMS` and unpacked into the data directory of `cargo-contract`, where later builds reuse
+//! it. Releases without a pinned checksum are refused, a checksum downloaded from the same place as
+//! the archive would not detect a tampered release.
+
+use crate::{config, maybe_println, Verbosity};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The location the binaryen releases are downloaded from.
+const RELEASES_URL: &str = "https://github.com/WebAssembly/binaryen/releases/download";
+
+/// The SHA-256 checksums of the binaryen release archives which can be downloaded, by version and
+/// platform, as published in the `.sha256` files of the releases. A release is added by adding the
+/// checksums of its archives for every platform of `platform()`.
+const CHECKSUMS: &[(u32, &str, &str)] = &[];
+
+/// The oldest binaryen release with a `wasm-opt` compatible with `cargo-contract`.
+const MIN_VERSION: u32 = 99;
+
+/// Parses a binaryen release such as `116` or `version_116`.
+pub(crate) fn parse_version(version: &str) -> Result<u32> {
+    let number: u32 = version
+        .trim()
+        .trim_start_matches("version_")
+        .parse()
+        .context(format!(
+            "Invalid wasm-opt version `{}`, expected a binaryen release such as `116`",
+            version
+        ))?;
+    if number < MIN_VERSION {
+        anyhow::bail!(
+            "The wasm-opt version {} is not supported, we require a version >= {}",
+            number,
+            MIN_VERSION
+        )
+    }
+    Ok(number)
+}
+
+/// Returns the path of `wasm-opt` of the binaryen release `version` in the cache, whether it was
+/// downloaded yet or not.
+pub(crate) fn wasm_opt_path(version: u32) -> Result<PathBuf> {
+    Ok(release_dir(version)?
+        .join(format!("binaryen-version_{}", version))
+        .join("bin")
+        .join(format!("wasm-opt{}", std::env::consts::EXE_SUFFIX)))
+}
+
+/// Returns the `wasm-opt` of the binaryen release `version`, downloading the release unless it is
+/// cached already.
+pub(crate) fn ensure_wasm_opt(version: u32, verbosity: Verbosity) -> Result<PathBuf> {
+    let wasm_opt = wasm_opt_path(version)?;
+    if wasm_opt.is_file() {
+        log::info!("Using the cached wasm-opt {}", wasm_opt.display());
+        return Ok(wasm_opt);
+    }
+    let platform = platform()?;
+    let expected = pinned_checksum(version, platform)?;
+    let archive_name = archive_name(version, platform);
+    let url = format!("{}/version_{}/{}", RELEASES_URL, version, archive_name);
+    maybe_println!(
+        verbosity,
+        " {} {}",
+        "[wasm-opt]".bold(),
+        format!("Downloading binaryen version_{}", version)
+            .bright_green()
+            .bold()
+    );
+
+    let release_dir = release_dir(version)?;
+    let cache_dir = release_dir
+        .parent()
+        .expect("the release directory is inside of the cache");
+    fs::create_dir_all(cache_dir)?;
+    // the release is downloaded next to the cache, so that an interrupted download is never used
+    let tmp_dir = tempfile::Builder::new()
+        .prefix(".download-")
+        .tempdir_in(cache_dir)?;
+    let archive = tmp_dir.path().join(&archive_name);
+    download(&url, &archive)?;
+    verify_checksum(&fs::read(&archive)?, expected).context(format!("Failed to verify {}", url))?;
+
+    let unpacked = tmp_dir.path().join("unpacked");
+    fs::create_dir_all(&unpacked)?;
+    run(Command::new("tar")
+        .arg("-xzf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(&unpacked))?;
+    // a build running in parallel may have cached the same release in the meantime
+    if let Err(err) = fs::rename(&unpacked, &release_dir) {
+        if !wasm_opt.is_file() {
+            return Err(err).context(format!("Failed to cache {}", release_dir.display()));
+        }
+    }
+    if !wasm_opt.is_file() {
+        anyhow::bail!("The binaryen release {} contains no wasm-opt", url)
+    }
+    Ok(wasm_opt)
+}
+
+/// Returns the directory the binaryen release `version` is cached in.
+fn release_dir(version: u32) -> Result<PathBuf> {
+    let data_dir = config::data_dir().context(
+        "Unable to determine the data directory to cache wasm-opt in, set \
+         CARGO_CONTRACT_DATA_DIR",
+    )?;
+    Ok(data_dir
+        .join("binaryen")
+        .join(format!("version_{}", version)))
+}
+
+/// Returns the name of the platform in the names of the binaryen release archives.
+fn platform() -> Result<&'static str> {
+    match (std::env::consts::ARCH, std::env::consts::OS) {
+        ("x86_64", "linux") => Ok("x86_64-linux"),
+        ("x86_64", "macos") => Ok("x86_64-macos"),
+        ("aarch64", "macos") => Ok("arm64-macos"),
+        ("x86_64", "windows") => Ok("x86_64-windows"),
+        (arch, os) => anyhow::bail!(
+            "binaryen publishes no releases for {}-{}, pass the path of a wasm-opt with \
+             `--wasm-opt-path` instead",
+            arch,
+            os
+        ),
+    }
+}
+
+fn archive_name(version: u32, platform: &str) -> String {
+    format!("binaryen-version_{}-{}.tar.gz", version, platform)
+}
+
+/// Returns the pinned SHA-256 checksum of the archive of the binaryen release `version` for the
+/// `platform`.
+fn pinned_checksum(version: u32, platform: &str) -> Result<&'static str> {
+    CHECKSUMS
+        .iter()
+        .find(|(pinned, pinned_platform, _)| *pinned == version && *pinned_platform == platform)
+        .map(|(_, _, checksum)| *checksum)
+        .with_context(|| {
+            format!(
+                "No checksum of binaryen version_{} for {} is pinned, so it cannot be downloaded \
+                 safely. Pass the path of a wasm-opt of this release with `--wasm-opt-path` \
+                 instead",
+                version, platform
+            )
+        })
+}
+
+/// Checks the SHA-256 checksum of the `archive` against the `expected` one.
+fn verify_checksum(archive: &[u8], expected: &str) -> Result<()> {
+    let actual = hex::encode(Sha256::digest(archive));
+    if !actual.eq_ignore_ascii_case(expected) {
+        anyhow::bail!(
+            "The SHA-256 checksum {} does not match the pinned checksum {}",
+            actual,
+            expected
+        )
+    }
+    Ok(())
+}
+
+fn download(url: &str, dest: &Path) -> Result<()> {
+    log::info!("Downloading {} to {}", url, dest.display());
+    run(Command::new("curl")
+        .args([
+            "--fail",
+            "--location",
+            "--silent",
+            "--show-error",
+            "--output",
+        ])
+        .arg(dest)
+        .arg(url))
+    .context(format!("Failed to download {}", url))
+}
+
+/// Runs the `command`, failing with its stderr if it is not successful.
+fn run(command: &mut Command) -> Result<()> {
+    log::info!("Invoking {:?}", command);
+    let output = command
+        .output()
+        .context(format!("Error executing `{:?}`", command))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`{:?}` failed with exit code {:?}: {}",
+            command,
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn releases_are_parsed_and_verified() {
+        assert_eq!(parse_version("116").unwrap(), 116);
+        assert_eq!(parse_version("version_110").unwrap(), 110);
+        assert!(parse_version("98").is_err());
+        assert!(parse_version("latest").is_err());
+        assert_eq!(
+            archive_name(116, "x86_64-linux"),
+            "binaryen-version_116-x86_64-linux.tar.gz"
+        );
+
+        let archive = b"binaryen";
+        let checksum = hex::encode(Sha256::digest(archive));
+        assert!(verify_checksum(archive, &checksum).is_ok());
+        assert!(verify_checksum(archive, &checksum.to_uppercase()).is_ok());
+        assert!(verify_checksum(b"tampered", &checksum).is_err());
+        assert!(verify_checksum(archive, "").is_err());
+        assert!(pinned_checksum(1, "x86_64-linux").is_err());
+        for (version, platform, checksum) in CHECKSUMS {
+            assert_eq!(pinned_checksum(*version, platform).unwrap(), *checksum);
+            assert_eq!(checksum.len(), 64, "{} {}", version, platform);
+            assert!(hex::decode(checksum).is_ok());
+        }
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checksums of the artifacts of a build, for verifying release artifacts.
+//!
+//! `SHA256SUMS` lists the SHA-256 checksums of the `.wasm`, `.json` and `.contract` files in the
+//! format of `sha256sum`, so that they can be checked with `sha256sum -c SHA256SUMS`.
+//! `CODE_HASHES` lists the BLAKE2-256 hash of every Wasm, i.e. the code hash on chain.
+//!
+//! If the secret URI of a key is set in `CARGO_CONTRACT_SIGNING_SURI`, `SHA256SUMS` is signed with
+//! it, the sr25519 signature is written to `SHA256SUMS.sig`.
+
+use crate::{cmd::metadata::blake2_hash, maybe_println, BuildResult, Verbosity};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use sha2::{Digest, Sha256};
+use sp_core::{crypto::Ss58Codec, sr25519, Pair};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The environment variable holding the secret URI of the key to sign the checksums with.
+const SIGNING_SURI_ENV: &str = "CARGO_CONTRACT_SIGNING_SURI";
+
+const SHA256SUMS: &str = "SHA256SUMS";
+const CODE_HASHES: &str = "CODE_HASHES";
+
+/// Writes the checksums of the artifacts of the `results` into each directory containing them.
+pub(crate) fn write(results: &[BuildResult], verbosity: Verbosity) -> Result<()> {
+    let mut artifacts = BTreeMap::<PathBuf, Vec<PathBuf>>::new();
+    for result in results {
+        let paths = result.dest_wasm.iter().chain(
+            result
+                .metadata_result
+                .iter()
+                .flat_map(|metadata| [&metadata.dest_metadata, &metadata.dest_bundle]),
+        );
+        for path in paths {
+            let dir = path
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .to_path_buf();
+            artifacts.entry(dir).or_default().push(path.clone());
+        }
+    }
+
+    let signing_suri = std::env::var(SIGNING_SURI_ENV).ok();
+    for (dir, paths) in artifacts {
+        let sums = sha256sums(&paths)?;
+        let sums_path = dir.join(SHA256SUMS);
+        fs::write(&sums_path, &sums).context(format!("Failed to write {}", sums_path.display()))?;
+        let code_hashes_path = dir.join(CODE_HASHES);
+        fs::write(&code_hashes_path, code_hashes(&paths)?)
+            .context(format!("Failed to write {}", code_hashes_path.display()))?;
+        maybe_println!(
+            verbosity,
+            " {} {}, {}",
+            "Checksums written to".bright_green().bold(),
+            sums_path.display(),
+            code_hashes_path.display()
+        );
+
+        if let Some(suri) = &signing_suri {
+            let signature_path = dir.join(format!("{}.sig", SHA256SUMS));
+            fs::write(&signature_path, sign(suri, sums.as_bytes())?)
+                .context(format!("Failed to write {}", signature_path.display()))?;
+            maybe_println!(
+                verbosity,
+                " {} {}",
+                "Checksums signed to".bright_green().bold(),
+                signature_path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Returns the SHA-256 checksums of the files at `paths` in the format of `sha256sum`.
+fn sha256sums(paths: &[PathBuf]) -> Result<String> {
+    let mut sums = String::new();
+    for path in paths {
+        let content = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+        sums.push_str(&format!(
+            "{}  {}\n",
+            hex::encode(Sha256::digest(&content)),
+            file_name(path)
+        ));
+    }
+    Ok(sums)
+}
+
+/// Returns the code hashes of the Wasm files among `paths`.
+fn code_hashes(paths: &[PathBuf]) -> Result<String> {
+    let mut hashes = String::new();
+    for path in paths
+        .iter()
+        .filter(|path| path.extension().map_or(false, |ext| ext == "wasm"))
+    {
+        let code = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+        hashes.push_str(&format!(
+            "0x{}  {}\n",
+            hex::encode(blake2_hash(&code).0),
+            file_name(path)
+        ));
+    }
+    Ok(hashes)
+}
+
+/// Signs the `message` with the key of the secret `suri`, returning the signature and the signer
+/// as JSON.
+fn sign(suri: &str, message: &[u8]) -> Result<String> {
+    let pair = sr25519::Pair::from_string(suri, None)
+        .map_err(|err| anyhow::anyhow!("Invalid secret URI in {}: {:?}", SIGNING_SURI_ENV, err))?;
+    let signature = serde_json::json!({
+        "signer": pair.public().to_ss58check(),
+        "signature": format!("0x{}", hex::encode(pair.sign(message))),
+    });
+    Ok(serde_json::to_string_pretty(&signature)?)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn checksums_are_listed_like_sha256sum() {
+        with_tmp_dir(|dir| {
+            let wasm = dir.join("flipper.wasm");
+            let metadata = dir.join("metadata.json");
+            fs::write(&wasm, "")?;
+            fs::write(&metadata, "{}")?;
+
+            let sums = sha256sums(&[wasm.clone(), metadata])?;
+            assert_eq!(
+                sums,
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  flipper.wasm\n\
+                 44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a  metadata.json\n"
+            );
+            assert_eq!(
+                code_hashes(&[wasm])?,
+                format!("0x{}  flipper.wasm\n", hex::encode(blake2_hash(&[]).0))
+            );
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tuning the `[profile.release]` of a build with `--opt-level`, `--lto` and `--codegen-units`,
+//! and comparing the sizes and build times of several such configurations with `--compare`.
+//!
+//! The settings are passed to `cargo` as `CARGO_PROFILE_RELEASE_*` environment variables, which
+//! take precedence over the `[profile.release]` of the manifest.
+
+use super::{execute, ExecuteArgs};
+use crate::{maybe_println, BuildArtifacts};
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use std::{fmt::Write, time::Instant};
+
+/// The `lto` setting of the `[profile.release]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum LtoMode {
+    /// Link time optimization across all crates, the default for contracts.
+    Fat,
+    /// A faster link time optimization, resulting in a slightly larger Wasm.
+    Thin,
+    /// No link time optimization across crates.
+    Off,
+}
+
+impl LtoMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fat => "fat",
+            Self::Thin => "thin",
+            Self::Off => "off",
+        }
+    }
+}
+
+/// Settings of the `[profile.release]` which override the ones of the manifest.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Tuning {
+    pub opt_level: Option<String>,
+    pub lto: Option<LtoMode>,
+    pub codegen_units: Option<u32>,
+}
+
+impl Tuning {
+    /// Returns the environment variables overriding the settings of the `[profile.release]`.
+    pub fn env(&self) -> Vec<(String, String)> {
+        let mut env = Vec::new();
+        if let Some(opt_level) = &self.opt_level {
+            env.push(("CARGO_PROFILE_RELEASE_OPT_LEVEL".into(), opt_level.clone()));
+        }
+        if let Some(lto) = self.lto {
+            env.push(("CARGO_PROFILE_RELEASE_LTO".into(), lto.as_str().into()));
+        }
+        if let Some(codegen_units) = self.codegen_units {
+            env.push((
+                "CARGO_PROFILE_RELEASE_CODEGEN_UNITS".into(),
+                codegen_units.to_string(),
+            ));
+        }
+        env
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "opt-level={} lto={} codegen-units={}",
+            self.opt_level.as_deref().unwrap_or("-"),
+            self.lto.map_or("-", |lto| lto.as_str()),
+            self.codegen_units
+                .map_or_else(|| "-".to_string(), |units| units.to_string())
+        )
+    }
+}
+
+/// The configurations built by `--compare`.
+///
+/// The default configuration for contracts is built last, so that its artifacts are the ones left
+/// in `target/ink`.
+fn configurations() -> Vec<Tuning> {
+    let tuning = |opt_level: &str, lto, codegen_units| Tuning {
+        opt_level: Some(opt_level.into()),
+        lto: Some(lto),
+        codegen_units: Some(codegen_units),
+    };
+    vec![
+        tuning("s", LtoMode::Fat, 1),
+        tuning("3", LtoMode::Fat, 1),
+        tuning("z", LtoMode::Thin, 16),
+        tuning("z", LtoMode::Fat, 1),
+    ]
+}
+
+/// The size and build time of the Wasm built with a configuration.
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub configuration: String,
+    /// The size of the Wasm before `wasm-opt`, in KiB.
+    pub original_size: f64,
+    /// The size of the optimized Wasm, in KiB.
+    pub optimized_size: f64,
+    /// The duration of the build in seconds.
+    pub build_time: f64,
+}
+
+/// The results of building a contract under several configurations.
+#[derive(Debug, Serialize)]
+pub struct ComparisonReport {
+    pub comparisons: Vec<Comparison>,
+}
+
+impl ComparisonReport {
+    /// Displays the comparisons as a table.
+    pub fn display(&self) -> String {
+        let mut table = format!(
+            "{:<42} {:>14} {:>14} {:>10}\n",
+            "Configuration", "Original", "Optimized", "Time"
+        );
+        for comparison in &self.comparisons {
+            let _ = writeln!(
+                table,
+                "{:<42} {:>13.1}K {:>13.1}K {:>9.1}s",
+                comparison.configuration,
+                comparison.original_size,
+                comparison.optimized_size,
+                comparison.build_time
+            );
+        }
+        table
+    }
+
+    pub fn serialize_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Builds the Wasm of the contract with every configuration, using the `execute_args` otherwise.
+pub(crate) fn compare<F>(execute_args: F) -> Result<ComparisonReport>
+where
+    F: Fn() -> Result<ExecuteArgs>,
+{
+    let mut comparisons = Vec::new();
+    for tuning in configurations() {
+        let mut args = execute_args()?;
+        args.build_artifact = BuildArtifacts::CodeOnly;
+        args.cargo_flags.env.extend(tuning.env());
+        maybe_println!(
+            args.verbosity,
+            " {} {}",
+            "Building with".bright_green().bold(),
+            tuning.describe().bold()
+        );
+
+        let started = Instant::now();
+        let result = execute(args)?;
+        let build_time = started.elapsed().as_secs_f64();
+        let optimization = result
+            .optimization_result
+            .expect("the Wasm is optimized when building code only");
+        comparisons.push(Comparison {
+            configuration: tuning.describe(),
+            original_size: optimization.original_size,
+            optimized_size: optimization.optimized_size,
+            build_time,
+        });
+    }
+    Ok(ComparisonReport { comparisons })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tuning_overrides_the_release_profile() {
+        let tuning = Tuning {
+            opt_level: Some("s".into()),
+            lto: Some(LtoMode::Thin),
+            codegen_units: None,
+        };
+        assert_eq!(
+            tuning.env(),
+            vec![
+                ("CARGO_PROFILE_RELEASE_OPT_LEVEL".into(), "s".into()),
+                ("CARGO_PROFILE_RELEASE_LTO".into(), "thin".into()),
+            ]
+        );
+        assert_eq!(tuning.describe(), "opt-level=s lto=thin codegen-units=-");
+        assert!(Tuning::default().env().is_empty());
+
+        let report = ComparisonReport {
+            comparisons: vec![Comparison {
+                configuration: tuning.describe(),
+                original_size: 20.31,
+                optimized_size: 8.5,
+                build_time: 12.0,
+            }],
+        };
+        let table = report.display();
+        assert!(table.starts_with("Configuration"));
+        assert!(table.contains("opt-level=s lto=thin codegen-units=-"));
+        assert!(table.contains("20.3K"));
+        assert!(table.contains("8.5K"));
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{execute, ExecuteArgs};
+use crate::{
+    cmd::{inspect::section_name, metadata::blake2_hash},
+    maybe_println, BuildResult,
+};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use parity_wasm::elements::Module;
+use std::fs;
+
+/// Builds the contract a second time in a pristine target directory and compares the resulting
+/// Wasm with the Wasm of the `first` build.
+///
+/// Returns an error listing the sections which differ if the builds are not identical.
+pub(crate) fn check(first: &BuildResult, mut args: ExecuteArgs) -> Result<()> {
+    let first_wasm = read_wasm(first)?;
+    let target_dir = tempfile::Builder::new()
+        .prefix("cargo-contract.determinism.")
+        .tempdir()?;
+    maybe_println!(
+        args.verbosity,
+        " {} {}",
+        "[determinism]".bold(),
+        "Building again in a pristine target directory"
+            .bright_green()
+            .bold()
+    );
+    let verbosity = args.verbosity;
+    // `--config` is forwarded to every `cargo` invocation, including `cargo metadata`, hence the
+    // artifacts of the second build are written to the temporary directory as well.
+    args.cargo_flags.config.push(format!(
+        "build.target-dir='{}'",
+        target_dir.path().display()
+    ));
+    let second_wasm = read_wasm(&execute(args)?)?;
+
+    if first_wasm == second_wasm {
+        maybe_println!(
+            verbosity,
+            " {} {}",
+            "[determinism]".bold(),
+            format!(
+                "The builds are identical, code hash 0x{}",
+                hex::encode(blake2_hash(&first_wasm).0)
+            )
+            .bright_green()
+            .bold()
+        );
+        return Ok(());
+    }
+    anyhow::bail!(
+        "The build is not deterministic, the Wasm of two builds differs in: {}",
+        differing_sections(&first_wasm, &second_wasm)?.join(", ")
+    )
+}
+
+fn read_wasm(result: &BuildResult) -> Result<Vec<u8>> {
+    let path = result
+        .dest_wasm
+        .as_ref()
+        .context("Checking the determinism requires the Wasm to be built")?;
+    fs::read(path).context(format!("Failed to read {}", path.display()))
+}
+
+/// Returns the names of the sections which differ between the modules `a` and `b`.
+fn differing_sections(a: &[u8], b: &[u8]) -> Result<Vec<String>> {
+    let a: Module = parity_wasm::deserialize_buffer(a)?;
+    let b: Module = parity_wasm::deserialize_buffer(b)?;
+    if a.sections().len() != b.sections().len() {
+        return Ok(vec![format!(
+            "the number of sections ({} and {})",
+            a.sections().len(),
+            b.sections().len()
+        )]);
+    }
+    let mut differing = Vec::new();
+    for (section_a, section_b) in a.sections().iter().zip(b.sections()) {
+        let name_a = section_name(section_a);
+        let name_b = section_name(section_b);
+        if name_a != name_b {
+            differing.push(format!("{} and {}", name_a, name_b));
+        } else if parity_wasm::serialize(section_a.clone())?
+            != parity_wasm::serialize(section_b.clone())?
+        {
+            differing.push(name_a);
+        }
+    }
+    Ok(differing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn differing_code_section_is_reported() {
+        let wasm = |value| {
+            wabt::wat2wasm(format!(
+                r#"(module
+                    (import "env" "memory" (memory 2 16))
+                    (func (export "call") (result i32) (i32.const {}))
+                )"#,
+                value
+            ))
+            .expect("invalid wat")
+        };
+
+        assert!(differing_sections(&wasm(1), &wasm(1)).unwrap().is_empty());
+        assert_eq!(
+            differing_sections(&wasm(1), &wasm(2)).unwrap(),
+            vec!["code".to_string()]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Low-level outputs of `rustc` for the contract crate, e.g. its LLVM IR.
+//!
+//! `rustc` writes the outputs of every crate next to its `rlib` in the `deps` directory of the
+//! Wasm target, from where the ones of the contract crate are copied to `target/ink/emit/`.
+
+use crate::{crate_metadata::CrateMetadata, maybe_println, Verbosity};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// An additional output of `rustc`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum Emit {
+    /// The assembly of the Wasm target.
+    Asm,
+    /// The LLVM IR, before it is optimized by the link time optimization.
+    LlvmIr,
+}
+
+impl Emit {
+    fn rustc_name(&self) -> &'static str {
+        match self {
+            Self::Asm => "asm",
+            Self::LlvmIr => "llvm-ir",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Asm => "s",
+            Self::LlvmIr => "ll",
+        }
+    }
+}
+
+/// Returns the `RUSTFLAGS` argument which makes `rustc` write the `emit` outputs besides the
+/// regular ones.
+pub(crate) fn rustflag(emit: &[Emit]) -> Option<String> {
+    if emit.is_empty() {
+        return None;
+    }
+    let mut kinds = emit.iter().map(Emit::rustc_name).collect::<Vec<_>>();
+    kinds.extend(["link", "metadata", "dep-info"]);
+    Some(format!("--emit={}", kinds.join(",")))
+}
+
+/// Copies the `emit` outputs of the contract crate to `target/ink/emit/<name>.<extension>`.
+pub(crate) fn collect(
+    crate_metadata: &CrateMetadata,
+    emit: &[Emit],
+    verbosity: Verbosity,
+) -> Result<Vec<PathBuf>> {
+    let deps = crate_metadata
+        .original_wasm
+        .parent()
+        .expect("the Wasm is in the release directory")
+        .join("deps");
+    let emit_dir = crate_metadata.target_directory.join("emit");
+    fs::create_dir_all(&emit_dir).context(format!("Failed to create {}", emit_dir.display()))?;
+
+    let name = &crate_metadata.contract_artifact_name;
+    let mut outputs = Vec::new();
+    for kind in emit {
+        let source = latest_output(&deps, name, kind.extension())?.context(format!(
+            "`rustc` did not emit {} for {} in {}",
+            kind.rustc_name(),
+            name,
+            deps.display()
+        ))?;
+        let dest = emit_dir.join(format!("{}.{}", name, kind.extension()));
+        fs::copy(&source, &dest).context(format!("Failed to copy {}", source.display()))?;
+        maybe_println!(
+            verbosity,
+            "       {} {} to '{}'",
+            "Emitted".bright_green().bold(),
+            kind.rustc_name(),
+            dest.display()
+        );
+        outputs.push(dest);
+    }
+    Ok(outputs)
+}
+
+/// Returns the most recent `<name>-<hash>.<extension>` in `deps`, as outputs of builds with other
+/// features are kept next to it.
+fn latest_output(deps: &Path, name: &str, extension: &str) -> Result<Option<PathBuf>> {
+    let prefix = format!("{}-", name);
+    let mut latest: Option<(SystemTime, PathBuf)> = None;
+    for entry in fs::read_dir(deps).context(format!("Failed to read {}", deps.display()))? {
+        let path = entry?.path();
+        let matches = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .map_or(false, |file_name| {
+                file_name.starts_with(&prefix) && file_name.ends_with(&format!(".{}", extension))
+            });
+        if !matches {
+            continue;
+        }
+        let modified = fs::metadata(&path)?.modified()?;
+        if latest
+            .as_ref()
+            .map_or(true, |(latest, _)| modified > *latest)
+        {
+            latest = Some((modified, path));
+        }
+    }
+    Ok(latest.map(|(_, path)| path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn contract_output_is_found() {
+        assert_eq!(
+            rustflag(&[Emit::LlvmIr, Emit::Asm]).unwrap(),
+            "--emit=llvm-ir,asm,link,metadata,dep-info"
+        );
+        with_tmp_dir(|deps| {
+            fs::write(deps.join("flipper-0a1b.ll"), "")?;
+            fs::write(deps.join("flipper_v2-2c3d.ll"), "")?;
+            fs::write(deps.join("ink_env-4e5f.ll"), "")?;
+            fs::write(deps.join("flipper-0a1b.rlib"), "")?;
+
+            let found = latest_output(deps, "flipper", "ll")?;
+            assert_eq!(found, Some(deps.join("flipper-0a1b.ll")));
+            assert_eq!(latest_output(deps, "flipper", "s")?, None);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,214 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{crate_metadata::CrateMetadata, maybe_println, BuildMode, Verbosity};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Deserialize;
+use std::{
+    ffi::OsString,
+    fmt::{Display, Formatter, Result as DisplayResult},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Shell commands defined in `[package.metadata.contract.hooks]` of the contract's `Cargo.toml`,
+/// which are executed at the respective stage of the build, e.g.
+///
+/// ```toml
+/// [package.metadata.contract.hooks]
+/// pre-build = "python3 scripts/gen_constants.py"
+/// post-optimize = "cp $CARGO_CONTRACT_WASM dist/"
+/// ```
+///
+/// The commands are executed in the directory of the contract's `Cargo.toml`, the paths of the
+/// build artifacts are exposed as `CARGO_CONTRACT_*` environment variables.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct BuildHooks {
+    /// Executed before the contract is linted and compiled.
+    pub pre_build: Option<String>,
+    /// Executed after the contract was compiled and its Wasm post processed.
+    pub post_build: Option<String>,
+    /// Executed after the Wasm was optimized.
+    pub post_optimize: Option<String>,
+}
+
+/// The stage of the build at which a hook is executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hook {
+    PreBuild,
+    PostBuild,
+    PostOptimize,
+}
+
+impl Display for Hook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        match self {
+            Self::PreBuild => write!(f, "pre-build"),
+            Self::PostBuild => write!(f, "post-build"),
+            Self::PostOptimize => write!(f, "post-optimize"),
+        }
+    }
+}
+
+impl BuildHooks {
+    /// Returns the command configured for `hook`.
+    pub(crate) fn command(&self, hook: Hook) -> Option<&str> {
+        match hook {
+            Hook::PreBuild => self.pre_build.as_deref(),
+            Hook::PostBuild => self.post_build.as_deref(),
+            Hook::PostOptimize => self.post_optimize.as_deref(),
+        }
+    }
+
+    /// Executes the command configured for `hook`, if any.
+    pub(crate) fn run(
+        &self,
+        hook: Hook,
+        crate_metadata: &CrateMetadata,
+        build_mode: BuildMode,
+        verbosity: Verbosity,
+    ) -> Result<()> {
+        let command = match self.command(hook) {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+        maybe_println!(
+            verbosity,
+            " {} {}",
+            "[hook]".bold(),
+            format!("Running `{}` hook", hook).bright_green().bold()
+        );
+        let working_dir = crate_metadata.manifest_path.absolute_directory()?;
+        let env = vec![
+            ("CARGO_CONTRACT_HOOK", hook.to_string().into()),
+            (
+                "CARGO_CONTRACT_MANIFEST_PATH",
+                working_dir.join("Cargo.toml").into(),
+            ),
+            (
+                "CARGO_CONTRACT_TARGET_DIR",
+                crate_metadata.target_directory.clone().into(),
+            ),
+            (
+                "CARGO_CONTRACT_ORIGINAL_WASM",
+                crate_metadata.original_wasm.clone().into(),
+            ),
+            (
+                "CARGO_CONTRACT_WASM",
+                crate_metadata.dest_wasm.clone().into(),
+            ),
+            (
+                "CARGO_CONTRACT_METADATA",
+                crate_metadata.metadata_path().into(),
+            ),
+            ("CARGO_CONTRACT_BUILD_MODE", build_mode.to_string().into()),
+        ];
+        execute(hook, command, &working_dir, env, verbosity)
+    }
+}
+
+/// Returns the shell and its arguments with which hook commands are executed.
+pub(crate) fn shell() -> (&'static str, &'static str) {
+    if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    }
+}
+
+/// Executes the hook `command` with the platform's shell.
+fn execute(
+    hook: Hook,
+    command: &str,
+    working_dir: &Path,
+    env: Vec<(&str, OsString)>,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let (shell, shell_arg) = shell();
+    let mut cmd = Command::new(shell);
+    cmd.arg(shell_arg)
+        .arg(command)
+        .current_dir(working_dir)
+        .envs(env);
+    // With `--quiet` (e.g. for `--output-json`) only our own output may be written to `STDOUT`.
+    if verbosity == Verbosity::Quiet {
+        cmd.stdout(Stdio::null());
+    }
+    log::info!("Invoking `{}` hook: {:?}", hook, cmd);
+
+    let status = cmd
+        .status()
+        .context(format!("Error executing `{}` hook `{}`", hook, command))?;
+    if !status.success() {
+        anyhow::bail!("The `{}` hook `{}` failed with {}", hook, command, status)
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn hooks_are_deserialized() {
+        let hooks: BuildHooks = toml::from_str(
+            r#"
+            pre-build = "./gen.sh"
+            post-optimize = "cp $CARGO_CONTRACT_WASM dist/"
+            "#,
+        )
+        .expect("hooks must be deserialized");
+        assert_eq!(
+            hooks,
+            BuildHooks {
+                pre_build: Some("./gen.sh".to_string()),
+                post_build: None,
+                post_optimize: Some("cp $CARGO_CONTRACT_WASM dist/".to_string()),
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hook_is_executed_with_env() {
+        with_tmp_dir(|path| {
+            let env = vec![("CARGO_CONTRACT_HOOK", "post-build".into())];
+            execute(
+                Hook::PostBuild,
+                "echo $CARGO_CONTRACT_HOOK > hook.txt",
+                path,
+                env,
+                Verbosity::Quiet,
+            )?;
+            let output = std::fs::read_to_string(path.join("hook.txt"))?;
+            assert_eq!(output.trim(), "post-build");
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_hook_returns_error() {
+        with_tmp_dir(|path| {
+            let res = execute(Hook::PreBuild, "exit 1", path, vec![], Verbosity::Quiet);
+            assert!(res.is_err());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{workspace::Manifest, BuildResult};
+use anyhow::Result;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Features which are part of every ink! contract and hence not varied in a matrix build.
+const IGNORED_FEATURES: [&str; 3] = ["default", "std", "ink-as-dependency"];
+
+/// The maximum number of features for which all combinations are built.
+const MAX_COMBINATION_FEATURES: usize = 8;
+
+/// The name given to the feature set without any features enabled.
+const BASE_FEATURE_SET: &str = "base";
+
+/// A named set of contract features, the contract is built once for every set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureSet {
+    /// The name of the set, which is appended to the names of the artifacts.
+    pub name: String,
+    pub features: Vec<String>,
+}
+
+/// Returns the feature sets defined in `[package.metadata.contract.feature-matrix]`, e.g.
+///
+/// ```toml
+/// [package.metadata.contract.feature-matrix]
+/// rococo = ["rococo"]
+/// kusama = ["kusama", "xcm"]
+/// ```
+pub(crate) fn from_manifest(manifest: &Manifest) -> Result<Vec<FeatureSet>> {
+    let matrix = manifest.get_feature_matrix()?.ok_or_else(|| {
+        anyhow::anyhow!("No `[package.metadata.contract.feature-matrix]` found in the `Cargo.toml`")
+    })?;
+    if matrix.is_empty() {
+        anyhow::bail!("The `[package.metadata.contract.feature-matrix]` is empty")
+    }
+    Ok(matrix
+        .into_iter()
+        .map(|(name, features)| FeatureSet { name, features })
+        .collect())
+}
+
+/// Returns a feature set for every combination of the contract's features.
+///
+/// The features which every ink! contract has, e.g. `std`, are not part of the combinations.
+pub(crate) fn all_combinations(manifest: &Manifest) -> Result<Vec<FeatureSet>> {
+    let features = manifest
+        .get_features()
+        .into_iter()
+        .filter(|feature| !IGNORED_FEATURES.contains(&feature.as_str()))
+        .collect::<Vec<_>>();
+    if features.len() > MAX_COMBINATION_FEATURES {
+        anyhow::bail!(
+            "The contract has {} features, building all {} combinations is not supported. \
+            Define the feature sets to build in `[package.metadata.contract.feature-matrix]` instead.",
+            features.len(),
+            1usize << features.len()
+        )
+    }
+    Ok(combinations(&features))
+}
+
+fn combinations(features: &[String]) -> Vec<FeatureSet> {
+    (0..1usize << features.len())
+        .map(|mask| {
+            let features = features
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, feature)| feature.clone())
+                .collect::<Vec<_>>();
+            let name = if features.is_empty() {
+                BASE_FEATURE_SET.to_string()
+            } else {
+                features.join("+")
+            };
+            FeatureSet { name, features }
+        })
+        .collect()
+}
+
+/// Renames the artifacts of `result` to include the name of the `feature_set`, so that they are
+/// not overwritten by the build of the next feature set.
+pub(crate) fn rename_artifacts(result: &mut BuildResult, feature_set: &FeatureSet) -> Result<()> {
+    if let Some(dest_wasm) = result.dest_wasm.as_mut() {
+        *dest_wasm = rename_with_suffix(dest_wasm, &feature_set.name)?;
+    }
+    if let Some(optimization_result) = result.optimization_result.as_mut() {
+        optimization_result.dest_wasm = result
+            .dest_wasm
+            .clone()
+            .expect("an optimized wasm always has a destination");
+    }
+    if let Some(metadata_result) = result.metadata_result.as_mut() {
+        metadata_result.dest_metadata =
+            rename_with_suffix(&metadata_result.dest_metadata, &feature_set.name)?;
+        metadata_result.dest_bundle =
+            rename_with_suffix(&metadata_result.dest_bundle, &feature_set.name)?;
+    }
+    result.feature_set = Some(feature_set.name.clone());
+    Ok(())
+}
+
+/// Renames `path` from `<name>.<ext>` to `<name>-<suffix>.<ext>`, returning the new path.
+fn rename_with_suffix(path: &Path, suffix: &str) -> Result<PathBuf> {
+    let stem = path
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("Artifact {} has no file name", path.display()))?
+        .to_string_lossy();
+    let mut file_name = format!("{}-{}", stem, suffix);
+    if let Some(extension) = path.extension() {
+        file_name.push('.');
+        file_name.push_str(&extension.to_string_lossy());
+    }
+    let new_path = path.with_file_name(file_name);
+    fs::rename(path, &new_path)?;
+    Ok(new_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn all_feature_combinations_are_generated() {
+        let features = vec!["a".to_string(), "b".to_string()];
+        let names = combinations(&features)
+            .into_iter()
+            .map(|set| set.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["base", "a", "b", "a+b"]);
+    }
+
+    #[test]
+    fn artifact_is_renamed_with_suffix() {
+        with_tmp_dir(|path| {
+            let wasm = path.join("flipper.wasm");
+            fs::write(&wasm, [0u8])?;
+
+            let renamed = rename_with_suffix(&wasm, "rococo")?;
+
+            assert_eq!(renamed, path.join("flipper-rococo.wasm"));
+            assert!(renamed.exists());
+            assert!(!wasm.exists());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Building all contracts of a workspace.
+//!
+//! The `cargo` invocations of the builds are serialized, as `cargo` uses all CPUs by itself.
+//! The post processing, optimization and validation of the Wasm of each contract only uses a
+//! single CPU though, so they run in parallel on up to `--jobs` threads.
+
+use super::{execute, ExecuteArgs};
+use crate::{cmd::graph::is_contract, BuildResult, ManifestPath};
+use anyhow::{Context, Result};
+use cargo_metadata::MetadataCommand;
+use std::{
+    collections::VecDeque,
+    sync::{mpsc, Arc, Mutex, MutexGuard, PoisonError},
+    thread,
+};
+
+/// Serializes the `cargo` invocations of the builds running in parallel.
+pub(crate) type CargoLock = Arc<Mutex<()>>;
+
+/// Blocks until no other build invokes `cargo`, if the build is one of several running in
+/// parallel.
+pub(super) fn lock_cargo(lock: Option<&CargoLock>) -> Option<MutexGuard<'_, ()>> {
+    lock.map(|lock| lock.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// Returns the manifests of the contracts which are members of the workspace at
+/// `manifest_path`.
+pub(crate) fn contract_manifests(manifest_path: &ManifestPath) -> Result<Vec<ManifestPath>> {
+    let metadata = MetadataCommand::new()
+        .manifest_path(manifest_path.as_ref())
+        .no_deps()
+        .exec()
+        .context("Error invoking `cargo metadata`")?;
+    let manifests = metadata
+        .packages
+        .iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .filter(|package| is_contract(package))
+        .map(|package| ManifestPath::new(&package.manifest_path))
+        .collect::<Result<Vec<_>>>()?;
+    if manifests.is_empty() {
+        anyhow::bail!(
+            "No contracts found in the workspace of {}",
+            manifest_path.as_ref().display()
+        )
+    }
+    Ok(manifests)
+}
+
+/// Executes the builds of `args` on up to `jobs` threads, returning the results in the order of
+/// the `args`.
+pub(crate) fn execute_parallel(args: Vec<ExecuteArgs>, jobs: usize) -> Result<Vec<BuildResult>> {
+    let count = args.len();
+    let cargo_lock = CargoLock::default();
+    let queue = args
+        .into_iter()
+        .map(|mut args| {
+            args.cargo_lock = Some(cargo_lock.clone());
+            args
+        })
+        .enumerate()
+        .collect::<VecDeque<_>>();
+    let queue = Arc::new(Mutex::new(queue));
+
+    let (sender, receiver) = mpsc::channel();
+    let workers = (0..jobs.clamp(1, count.max(1)))
+        .map(|_| {
+            let queue = queue.clone();
+            let sender = sender.clone();
+            thread::spawn(move || loop {
+                let next = queue
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .pop_front();
+                let (index, args) = match next {
+                    Some(next) => next,
+                    None => break,
+                };
+                let manifest_path = args.manifest_path.clone();
+                let result = execute(args).context(format!(
+                    "Failed to build {}",
+                    manifest_path.as_ref().display()
+                ));
+                if sender.send((index, result)).is_err() {
+                    break;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    drop(sender);
+
+    let mut results = receiver.into_iter().collect::<Vec<_>>();
+    for worker in workers {
+        worker
+            .join()
+            .map_err(|_| anyhow::anyhow!("A build thread panicked"))?;
+    }
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cargo_lock_is_only_taken_for_parallel_builds() {
+        assert!(lock_cargo(None).is_none());
+
+        let lock = CargoLock::default();
+        let guard = lock_cargo(Some(&lock));
+        assert!(guard.is_some());
+        assert!(lock.try_lock().is_err());
+        drop(guard);
+        assert!(lock.try_lock().is_ok());
+    }
+}
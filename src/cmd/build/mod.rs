@@ -0,0 +1,2722 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+mod artifacts;
+mod binaryen;
+mod checksums;
+mod compare;
+mod determinism;
+mod emit;
+mod hooks;
+mod matrix;
+mod members;
+mod plan;
+mod profile;
+mod rustflags;
+mod size_report;
+
+pub(crate) use self::{
+    binaryen::parse_version, compare::ComparisonReport, plan::BuildPlan, size_report::parse_size,
+};
+use self::{
+    compare::{LtoMode, Tuning},
+    members::CargoLock,
+    plan::Step,
+};
+pub use self::{emit::Emit, hooks::BuildHooks, profile::ContractProfile};
+
+use crate::{
+    crate_metadata::CrateMetadata,
+    exit_code::{Classify, ExitCode},
+    maybe_println, step_println, timings,
+    toolchain::{self, WasmTarget},
+    util, validate_wasm,
+    workspace::{Manifest, ManifestPath, Profile, ProfileConflict, Workspace},
+    BuildArtifacts, BuildMode, BuildResult, CargoFlags, CargoOptions, Determinism,
+    OptimizationFallback, OptimizationPasses, OptimizationResult, OutputType, PanicMode,
+    UnstableFlags, UnstableOptions, Verbosity, VerbosityFlags,
+};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use parity_wasm::elements::{CustomSection, External, Internal, MemoryType, Module, Section};
+use regex::Regex;
+use semver::{Version, VersionReq};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    ffi::{OsStr, OsString},
+    fs::metadata,
+    path::{Path, PathBuf},
+    process::Command,
+    str,
+};
+
+/// This is the maximum number of pages available for a contract to allocate.
+pub(crate) const MAX_MEMORY_PAGES: u32 = 16;
+
+/// The RUSTFLAGS added for the experimental `wasm64-unknown-unknown` target, which generates
+/// 64-bit memory accesses of the `memory64` proposal.
+const WASM64_RUSTFLAGS: &str = "-C target-feature=+memory64";
+
+/// Arguments to use when executing `build` or `check` commands.
+#[derive(Default)]
+pub(crate) struct ExecuteArgs {
+    /// The location of the Cargo manifest (`Cargo.toml`) file to use.
+    pub(crate) manifest_path: ManifestPath,
+    pub(crate) verbosity: Verbosity,
+    pub(crate) build_mode: BuildMode,
+    cargo_flags: CargoFlags,
+    pub(crate) build_artifact: BuildArtifacts,
+    unstable_flags: UnstableFlags,
+    optimization_passes: OptimizationPasses,
+    keep_debug_symbols: bool,
+    keep_debug_artifacts: bool,
+    wasm_opt_path: Option<PathBuf>,
+    /// The binaryen release whose `wasm-opt` is downloaded and used, from `--wasm-opt-version`.
+    wasm_opt_version: Option<u32>,
+    max_code_size: Option<u64>,
+    install_missing_components: bool,
+    list_offending: bool,
+    determinism: Determinism,
+    output_type: OutputType,
+    /// Shared by builds running in parallel to serialize their `cargo` invocations.
+    cargo_lock: Option<CargoLock>,
+}
+
+/// Executes build of the smart contract which produces a Wasm binary that is ready for deploying.
+///
+/// It does so by invoking `cargo build` and then post processing the final binary.
+#[derive(Debug, clap::Args)]
+#[clap(name = "build")]
+pub struct BuildCommand {
+    /// Path to the `Cargo.toml` of the contract to build
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// By default the contract is compiled with debug functionality
+    /// included. This enables the contract to output debug messages,
+    /// but increases the contract size and the amount of gas used.
+    ///
+    /// A production contract should always be build in `release` mode!
+    /// Then no debug functionality is compiled into the contract.
+    #[clap(long = "--release")]
+    build_release: bool,
+    #[clap(flatten)]
+    cargo_options: CargoOptions,
+    /// Which build artifacts to generate.
+    ///
+    /// - `all`: Generate the Wasm, the metadata and a bundled `<name>.contract` file.
+    ///
+    /// - `code-only`: Only the Wasm is created, generation of metadata and a bundled
+    ///   `<name>.contract` file is skipped.
+    #[clap(
+        long = "generate",
+        default_value = "all",
+        value_name = "all | code-only",
+        verbatim_doc_comment
+    )]
+    build_artifact: BuildArtifacts,
+    #[clap(flatten)]
+    verbosity: VerbosityFlags,
+    #[clap(flatten)]
+    unstable_options: UnstableOptions,
+    /// Number of optimization passes, passed as an argument to `wasm-opt`.
+    ///
+    /// - `0`: execute no optimization passes
+    ///
+    /// - `1`: execute 1 optimization pass (quick & useful opts, useful for iteration builds)
+    ///
+    /// - `2`, execute 2 optimization passes (most opts, generally gets most perf)
+    ///
+    /// - `3`, execute 3 optimization passes (spends potentially a lot of time optimizing)
+    ///
+    /// - `4`, execute 4 optimization passes (also flatten the IR, which can take a lot more time and memory
+    /// but is useful on more nested / complex / less-optimized input)
+    ///
+    /// - `s`, execute default optimization passes, focusing on code size
+    ///
+    /// - `z`, execute default optimization passes, super-focusing on code size
+    ///
+    /// - The default value is `z`
+    ///
+    /// - It is possible to define the number of optimization passes in the
+    ///   `[package.metadata.contract]` of your `Cargo.toml` as e.g. `optimization-passes = "3"`.
+    ///   The CLI argument always takes precedence over the profile value.
+    #[clap(long)]
+    optimization_passes: Option<OptimizationPasses>,
+    /// Do not remove symbols (Wasm name section) when optimizing.
+    ///
+    /// This is useful if one wants to analyze or debug the optimized binary.
+    #[clap(long)]
+    keep_debug_symbols: bool,
+    /// Optimize with the `wasm-opt` executable at the given path instead of the bundled one.
+    ///
+    /// If `cargo-contract` was installed without the `wasm-opt` feature, the executable is
+    /// searched in the `PATH` by default.
+    #[clap(long, parse(from_os_str))]
+    wasm_opt_path: Option<PathBuf>,
+    /// Optimize with the `wasm-opt` of the given binaryen release, e.g. `116`.
+    ///
+    /// The release is downloaded once, verified against its published SHA-256 checksum and
+    /// cached in the data directory of `cargo-contract`. Overrides `wasm-opt-version` of
+    /// `[package.metadata.contract]`.
+    #[clap(
+        long,
+        value_name = "VERSION",
+        conflicts_with = "wasm_opt_path",
+        parse(try_from_str = binaryen::parse_version)
+    )]
+    wasm_opt_version: Option<u32>,
+    /// Keep the intermediate build artifacts for inspection.
+    ///
+    /// This retains the temporary workspaces used for building the contract and generating its
+    /// metadata, as well as the Wasm before optimization as `<name>-pre-opt.wasm`.
+    #[clap(long)]
+    keep_debug_artifacts: bool,
+    /// Build the contract once for every combination of its features.
+    ///
+    /// The artifacts of each build are suffixed with the enabled features, e.g.
+    /// `<name>-rococo+xcm.contract`. The features `std` and `ink-as-dependency` are not varied.
+    #[clap(long, conflicts_with = "feature_matrix")]
+    all_feature_combos: bool,
+    /// Build the contract once for every feature set defined in
+    /// `[package.metadata.contract.feature-matrix]`.
+    ///
+    /// The artifacts of each build are suffixed with the name of the feature set.
+    #[clap(long)]
+    feature_matrix: bool,
+    /// Build the contract a second time in a pristine target directory and fail if the
+    /// resulting Wasm differs, listing the sections which differ.
+    #[clap(long, conflicts_with_all = &["all_feature_combos", "feature_matrix"])]
+    check_determinism: bool,
+    /// Build every contract of the workspace at `--manifest-path`.
+    #[clap(
+        long,
+        conflicts_with_all = &["all_feature_combos", "feature_matrix", "check_determinism"]
+    )]
+    workspace: bool,
+    /// The number of contracts of a `--workspace` build whose Wasm is post processed, optimized
+    /// and validated in parallel. Defaults to the number of CPUs.
+    ///
+    /// The contracts are still compiled one after another, as `cargo` uses all CPUs by itself.
+    #[clap(long, short = 'j', requires = "workspace")]
+    jobs: Option<usize>,
+
+    /// Export the build output in JSON format.
+    #[clap(long, conflicts_with = "verbose")]
+    output_json: bool,
+    /// Print the commands and steps the build would execute as JSON, without executing them.
+    #[clap(long)]
+    build_plan: bool,
+    /// Build with the settings of the profile defined in
+    /// `[package.metadata.contract.profiles.<name>]`, e.g. for a specific chain.
+    ///
+    /// Options passed on the command line take precedence over the profile.
+    #[clap(long)]
+    profile: Option<String>,
+    /// Install a missing `rust-src` component or `wasm32-unknown-unknown` target with `rustup`
+    /// without asking for confirmation.
+    #[clap(long = "yes", short = 'y')]
+    install_missing_components: bool,
+    /// Additionally write the assembly or the LLVM IR of the contract crate to
+    /// `target/ink/emit/`, e.g. to inspect the code generated for hot functions.
+    ///
+    /// The LLVM IR is the one before the link time optimization across crates.
+    #[clap(long, arg_enum, use_delimiter = true, value_name = "asm | llvm-ir")]
+    emit: Vec<Emit>,
+    /// List every non-deterministic instruction found in the contract Wasm, instead of only
+    /// the first few.
+    #[clap(long)]
+    list_offending: bool,
+    /// Allow non-deterministic instructions, e.g. floating point arithmetic, with `relaxed`.
+    ///
+    /// Such code can only be uploaded with `cargo contract upload --determinism relaxed` for
+    /// off-chain execution, it cannot be instantiated.
+    #[clap(
+        long,
+        arg_enum,
+        default_value = "enforced",
+        value_name = "enforced | relaxed"
+    )]
+    determinism: Determinism,
+    /// Copy the artifacts to this directory, in addition to `target/ink`.
+    #[clap(long, parse(from_os_str))]
+    output_dir: Option<PathBuf>,
+    /// The file name of the artifacts without extension, e.g. `{name}-{version}-{profile}`.
+    ///
+    /// The placeholders are the name and version of the contract, the `--profile` or else the
+    /// build mode, and the feature set of a feature matrix build as `{features}`.
+    #[clap(long, value_name = "TEMPLATE")]
+    artifact_name: Option<String>,
+    /// Write the SHA-256 checksums of the artifacts to `SHA256SUMS` and the code hashes of the
+    /// Wasm to `CODE_HASHES`, next to the artifacts.
+    ///
+    /// If `CARGO_CONTRACT_SIGNING_SURI` is set, `SHA256SUMS` is signed with the sr25519 key of
+    /// the secret URI, the signature is written to `SHA256SUMS.sig`.
+    #[clap(long)]
+    checksums: bool,
+    /// Build with the default `[profile.release]` settings for contracts, overwriting the ones
+    /// of the `Cargo.toml`, e.g. for builds which must be reproducible by others.
+    #[clap(long)]
+    profile_strict: bool,
+    /// The `opt-level` of the `[profile.release]`, overriding the one of the `Cargo.toml`.
+    #[clap(long, possible_values = &["0", "1", "2", "3", "s", "z"])]
+    opt_level: Option<String>,
+    /// The `lto` of the `[profile.release]`, overriding the one of the `Cargo.toml`.
+    #[clap(long, arg_enum, value_name = "fat | thin | off")]
+    lto: Option<LtoMode>,
+    /// The `codegen-units` of the `[profile.release]`, overriding the one of the `Cargo.toml`.
+    #[clap(long, value_name = "N")]
+    codegen_units: Option<u32>,
+    /// Build the Wasm with several `opt-level`, `lto` and `codegen-units` configurations and
+    /// print a table of the resulting sizes and build times.
+    ///
+    /// The configuration of the defaults for contracts is built last, its Wasm is kept.
+    #[clap(
+        long,
+        conflicts_with_all = &[
+            "opt_level", "lto", "codegen_units", "workspace", "all_feature_combos",
+            "feature_matrix", "check_determinism", "build_plan"
+        ]
+    )]
+    compare: bool,
+    /// Record the sizes of the Wasm for the current git commit in `.ink/size-history.csv`, or in
+    /// the given file, as JSON if its name ends with `.json`.
+    ///
+    /// The history is shown with `cargo contract size-history show`.
+    #[clap(
+        long,
+        value_name = "PATH",
+        parse(from_os_str),
+        conflicts_with_all = &["workspace", "all_feature_combos", "feature_matrix", "compare"]
+    )]
+    size_history: Option<Option<PathBuf>>,
+}
+
+impl BuildCommand {
+    pub fn exec(&self) -> Result<BuildResult> {
+        let mut result = execute(self.execute_args()?)?;
+        if self.check_determinism {
+            determinism::check(&result, self.execute_args()?)?;
+        }
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        self.copy_artifacts(&manifest_path, &mut result)?;
+        self.write_checksums(std::slice::from_ref(&result))?;
+        if let Some(history) = &self.size_history {
+            crate::cmd::size_history::record(&manifest_path, history.as_deref(), &result)?;
+        }
+        Ok(result)
+    }
+
+    /// Builds the contract for every feature set if `--all-feature-combos` or `--feature-matrix`
+    /// was passed, otherwise returns `None`.
+    pub fn exec_matrix(&self) -> Result<Option<Vec<BuildResult>>> {
+        if !self.all_feature_combos && !self.feature_matrix {
+            return Ok(None);
+        }
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        let manifest = Manifest::new(manifest_path.clone())?;
+        let feature_sets = if self.all_feature_combos {
+            matrix::all_combinations(&manifest)?
+        } else {
+            matrix::from_manifest(&manifest)?
+        };
+
+        let mut results = Vec::new();
+        for feature_set in feature_sets {
+            let mut args = self.execute_args()?;
+            maybe_println!(
+                args.verbosity,
+                "{} {}",
+                "Building feature set".bright_blue().bold(),
+                feature_set.name.bold()
+            );
+            args.cargo_flags
+                .features
+                .extend(feature_set.features.iter().cloned());
+            let mut result = execute(args)?;
+            matrix::rename_artifacts(&mut result, &feature_set)?;
+            self.copy_artifacts(&manifest_path, &mut result)?;
+            results.push(result);
+        }
+        self.write_checksums(&results)?;
+        Ok(Some(results))
+    }
+
+    /// Builds every contract of the workspace if `--workspace` was passed, otherwise returns
+    /// `None`.
+    pub fn exec_workspace(&self) -> Result<Option<Vec<BuildResult>>> {
+        if !self.workspace {
+            return Ok(None);
+        }
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        let manifests = members::contract_manifests(&manifest_path)?;
+        let args = manifests
+            .iter()
+            .map(|manifest_path| self.execute_args_for(manifest_path.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        let jobs = match self.jobs {
+            Some(jobs) => jobs,
+            None => std::thread::available_parallelism()
+                .map(Into::into)
+                .unwrap_or(1),
+        };
+        let mut results = members::execute_parallel(args, jobs)?;
+        for (manifest_path, result) in manifests.iter().zip(results.iter_mut()) {
+            self.copy_artifacts(manifest_path, result)?;
+        }
+        self.write_checksums(&results)?;
+        Ok(Some(results))
+    }
+
+    /// Writes the checksums of the artifacts of the `results` if `--checksums` was passed.
+    fn write_checksums(&self, results: &[BuildResult]) -> Result<()> {
+        match results.first() {
+            Some(result) if self.checksums => checksums::write(results, result.verbosity),
+            _ => Ok(()),
+        }
+    }
+
+    /// Copies the artifacts of the contract at `manifest_path` to `--output-dir`, named after
+    /// `--artifact-name`, if either was passed.
+    fn copy_artifacts(&self, manifest_path: &ManifestPath, result: &mut BuildResult) -> Result<()> {
+        if self.output_dir.is_none() && self.artifact_name.is_none() {
+            return Ok(());
+        }
+        let crate_metadata = CrateMetadata::collect(manifest_path)?;
+        let vars = artifacts::ArtifactVars {
+            name: &crate_metadata.contract_artifact_name,
+            version: crate_metadata.root_package.version.to_string(),
+            profile: self
+                .profile
+                .clone()
+                .unwrap_or_else(|| result.build_mode.to_string()),
+            features: result.feature_set.as_deref(),
+        };
+        let artifact_name = artifacts::render(self.artifact_name.as_deref(), &vars)?;
+        artifacts::copy(result, self.output_dir.as_deref(), &artifact_name)
+    }
+
+    /// Builds the Wasm of the contract with several profile configurations if `--compare` was
+    /// passed, otherwise returns `None`.
+    pub fn compare(&self) -> Result<Option<ComparisonReport>> {
+        if !self.compare {
+            return Ok(None);
+        }
+        compare::compare(|| self.execute_args()).map(Some)
+    }
+
+    /// Whether the build output is printed as JSON.
+    pub fn output_json(&self) -> bool {
+        self.output_json
+    }
+
+    /// Returns the plan of the build if `--build-plan` was passed, in which case nothing must be
+    /// executed.
+    pub fn plan(&self) -> Result<Option<BuildPlan>> {
+        if !self.build_plan {
+            return Ok(None);
+        }
+        plan::plan(&self.execute_args()?).map(Some)
+    }
+
+    fn execute_args(&self) -> Result<ExecuteArgs> {
+        self.execute_args_for(ManifestPath::try_from(self.manifest_path.as_ref())?)
+    }
+
+    /// Returns the arguments to build the contract at `manifest_path`.
+    fn execute_args_for(&self, manifest_path: ManifestPath) -> Result<ExecuteArgs> {
+        let unstable_flags: UnstableFlags =
+            TryFrom::<&UnstableOptions>::try_from(&self.unstable_options)?;
+        let mut verbosity = TryFrom::<&VerbosityFlags>::try_from(&self.verbosity)?;
+        let profile = match self.profile.as_ref() {
+            Some(name) => {
+                let manifest = Manifest::new(manifest_path.clone())?;
+                ContractProfile::load(&manifest, name)?
+            }
+            None => ContractProfile::default(),
+        };
+
+        // The CLI flag `optimization-passes` overwrites optimization passes which are
+        // potentially defined in the selected contract profile or the `Cargo.toml` profile.
+        let optimization_passes = match self.optimization_passes.or(profile.optimization_passes()?)
+        {
+            Some(opt_passes) => opt_passes,
+            None => {
+                let mut manifest = Manifest::new(manifest_path.clone())?;
+                match manifest.get_profile_optimization_passes() {
+                    // if no setting is found, neither on the cli nor in the profile,
+                    // then we use the default
+                    None => OptimizationPasses::default(),
+                    Some(opt_passes) => opt_passes,
+                }
+            }
+        };
+
+        let build_mode = match self.build_release {
+            true => BuildMode::Release,
+            false => BuildMode::Debug,
+        };
+
+        let output_type = match self.output_json {
+            true => OutputType::Json,
+            false => OutputType::HumanReadable,
+        };
+
+        // We want to ensure that the only thing in `STDOUT` is our JSON formatted string.
+        if matches!(output_type, OutputType::Json) {
+            verbosity = Verbosity::Quiet;
+        }
+
+        let mut cargo_flags = CargoFlags::from(&self.cargo_options);
+        cargo_flags.features = profile.features;
+        cargo_flags.env = profile.env.into_iter().collect();
+        cargo_flags.emit = self.emit.clone();
+        cargo_flags.profile_strict = self.profile_strict;
+        let tuning = Tuning {
+            opt_level: self.opt_level.clone(),
+            lto: self.lto,
+            codegen_units: self.codegen_units,
+        };
+        cargo_flags.env.extend(tuning.env());
+
+        let args = ExecuteArgs {
+            manifest_path,
+            verbosity,
+            build_mode,
+            cargo_flags,
+            build_artifact: self.build_artifact,
+            unstable_flags,
+            optimization_passes,
+            keep_debug_symbols: self.keep_debug_symbols || profile.keep_debug_symbols,
+            keep_debug_artifacts: self.keep_debug_artifacts,
+            wasm_opt_path: self.wasm_opt_path.clone(),
+            wasm_opt_version: self.wasm_opt_version,
+            max_code_size: profile.max_code_size,
+            install_missing_components: self.install_missing_components,
+            list_offending: self.list_offending,
+            determinism: self.determinism,
+            output_type,
+            cargo_lock: None,
+        };
+
+        Ok(args)
+    }
+}
+
+#[derive(Debug, clap::Args)]
+#[clap(name = "check")]
+pub struct CheckCommand {
+    /// Path to the `Cargo.toml` of the contract to build
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    #[clap(flatten)]
+    cargo_options: CargoOptions,
+    #[clap(flatten)]
+    verbosity: VerbosityFlags,
+    #[clap(flatten)]
+    unstable_options: UnstableOptions,
+    /// Install a missing `rust-src` component or `wasm32-unknown-unknown` target with `rustup`
+    /// without asking for confirmation.
+    #[clap(long = "yes", short = 'y')]
+    install_missing_components: bool,
+    /// Add the missing settings a contract requires to its `Cargo.toml` before checking it, as
+    /// `cargo contract fix-manifest` does.
+    #[clap(long)]
+    fix: bool,
+}
+
+impl CheckCommand {
+    pub fn exec(&self) -> Result<BuildResult> {
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        let unstable_flags: UnstableFlags =
+            TryFrom::<&UnstableOptions>::try_from(&self.unstable_options)?;
+        let verbosity: Verbosity = TryFrom::<&VerbosityFlags>::try_from(&self.verbosity)?;
+        if self.fix {
+            crate::cmd::fix_manifest::fix_manifest(&manifest_path, false)?;
+        }
+
+        let args = ExecuteArgs {
+            manifest_path,
+            verbosity,
+            build_mode: BuildMode::Debug,
+            cargo_flags: CargoFlags::from(&self.cargo_options),
+            build_artifact: BuildArtifacts::CheckOnly,
+            unstable_flags,
+            optimization_passes: OptimizationPasses::Zero,
+            keep_debug_symbols: false,
+            keep_debug_artifacts: false,
+            wasm_opt_path: None,
+            wasm_opt_version: None,
+            max_code_size: None,
+            install_missing_components: self.install_missing_components,
+            list_offending: false,
+            determinism: Determinism::default(),
+            output_type: OutputType::default(),
+            cargo_lock: None,
+        };
+
+        execute(args)
+    }
+}
+
+/// Executes the supplied cargo command on the project in the specified directory, defaults to the
+/// current directory.
+///
+/// Uses the unstable cargo feature [`build-std`](https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#build-std)
+/// to build the standard library with [`panic_immediate_abort`](https://github.com/johnthagen/min-sized-rust#remove-panic-string-formatting-with-panic_immediate_abort)
+/// which reduces the size of the Wasm binary by not including panic strings and formatting code.
+/// The built crates and the panic strategy can be changed with `--build-std` and `--panic`, see
+/// [`PanicMode`].
+///
+/// # `Cargo.toml` optimizations
+///
+/// The original `Cargo.toml` will be amended to remove the `rlib` crate type in order to minimize
+/// the final Wasm binary size.
+///
+/// Preferred default `[profile.release]` settings will be added if they are missing, existing
+/// user-defined settings will be preserved and a warning is printed for each of them which differs
+/// from the default. With `--profile-strict` the defaults overwrite the user-defined settings
+/// instead. A contract which is a workspace member inherits the `[profile.release]` of its
+/// workspace.
+///
+/// The `[workspace]` will be added if it is missing to ignore `workspace` from parent `Cargo.toml`.
+///
+/// To disable this and use the original `Cargo.toml` as is then pass the `-Z original_manifest` flag.
+/// `--profile-strict` then overrides the `[profile.release]` with `CARGO_PROFILE_RELEASE_*`
+/// environment variables.
+fn exec_cargo_for_wasm_target(
+    crate_metadata: &CrateMetadata,
+    command: &str,
+    build_mode: BuildMode,
+    cargo_flags: &CargoFlags,
+    verbosity: Verbosity,
+    unstable_flags: &UnstableFlags,
+    keep_debug_artifacts: bool,
+) -> Result<()> {
+    util::assert_channel().classify(ExitCode::Toolchain)?;
+    let rustflags = target_rustflags(crate_metadata, cargo_flags)?;
+    // the original manifest is not amended, its `[profile.release]` is overridden by cargo's
+    // environment instead
+    let profile_env = if unstable_flags.original_manifest && cargo_flags.profile_strict {
+        Profile::default_contract_release().release_env()
+    } else {
+        Vec::new()
+    };
+
+    let cargo_build = |manifest_path: &ManifestPath| {
+        let args = wasm_cargo_args(manifest_path, crate_metadata, build_mode, cargo_flags)?;
+        let mut env = profile_env
+            .iter()
+            .map(|(var, value)| (var.as_str(), Some(value.as_str())))
+            .collect::<Vec<_>>();
+        env.extend(wasm_cargo_env(
+            &rustflags,
+            crate_metadata,
+            build_mode,
+            cargo_flags,
+        ));
+        // Cargo is invoked from the contract's directory, so that configuration files such as
+        // `.cargo/config.toml` (e.g. containing a vendored source replacement) are respected,
+        // even when building a temporary workspace.
+        util::invoke_cargo(
+            command,
+            &args,
+            crate_metadata.manifest_path.directory(),
+            verbosity,
+            env,
+        )
+        .classify(ExitCode::Build)?;
+
+        Ok(())
+    };
+
+    if unstable_flags.original_manifest {
+        maybe_println!(
+            verbosity,
+            "{} {}",
+            "warning:".yellow().bold(),
+            "with 'original-manifest' enabled, the contract binary may not be of optimal size."
+                .bold()
+        );
+        // cargo reads the `[profile.release]` from the root of the workspace
+        let workspace_manifest = crate_metadata.cargo_meta.workspace_root.join("Cargo.toml");
+        let conflicts = Manifest::new(ManifestPath::new(&workspace_manifest)?)?
+            .profile_release_conflicts(&Profile::default_contract_release());
+        cargo_build(&crate_metadata.manifest_path)?;
+        warn_profile_conflicts(&conflicts, cargo_flags.profile_strict, verbosity);
+    } else {
+        let workspace_profile = workspace_profile_release(crate_metadata)?;
+        let mut conflicts = Vec::new();
+        Workspace::new(&crate_metadata.cargo_meta, &crate_metadata.root_package.id)?
+            .with_root_package_manifest(|manifest| {
+                manifest.with_removed_crate_type("rlib")?;
+                if let Some(workspace_profile) = &workspace_profile {
+                    manifest.with_inherited_profile_release(workspace_profile)?;
+                }
+                conflicts =
+                    manifest.profile_release_conflicts(&Profile::default_contract_release());
+                if cargo_flags.profile_strict {
+                    manifest.with_profile_release_enforced(Profile::default_contract_release())?;
+                } else {
+                    manifest.with_profile_release_defaults(Profile::default_contract_release())?;
+                }
+                manifest.with_workspace()?;
+                Ok(())
+            })?
+            .keep_temp_dir(keep_debug_artifacts)
+            .using_temp(cargo_build)?
+            .into_iter()
+            .for_each(|path| print_debug_artifact(verbosity, "build workspace", &path));
+        warn_profile_conflicts(&conflicts, cargo_flags.profile_strict, verbosity);
+    }
+
+    Ok(())
+}
+
+/// Returns the `[profile.release]` of the workspace the contract is a member of, if any.
+fn workspace_profile_release(crate_metadata: &CrateMetadata) -> Result<Option<toml::value::Table>> {
+    let workspace_manifest = crate_metadata.cargo_meta.workspace_root.join("Cargo.toml");
+    if workspace_manifest.canonicalize()? == crate_metadata.manifest_path.as_ref().canonicalize()? {
+        return Ok(None);
+    }
+    let manifest = Manifest::new(ManifestPath::new(&workspace_manifest)?)?;
+    Ok(manifest.profile_release().cloned())
+}
+
+/// Warns about the user-defined `[profile.release]` settings which differ from the preferred
+/// defaults, and which were overwritten if the profile is `strict`.
+fn warn_profile_conflicts(conflicts: &[ProfileConflict], strict: bool, verbosity: Verbosity) {
+    for conflict in conflicts {
+        let warning = if strict {
+            format!(
+                "{} in `[profile.release]` was overwritten with `{}` due to `--profile-strict`",
+                conflict, conflict.default
+            )
+        } else {
+            format!(
+                "{} in `[profile.release]` differs from the default `{}` for contracts, pass \
+                 `--profile-strict` to build with the default",
+                conflict, conflict.default
+            )
+        };
+        maybe_println!(verbosity, "{} {}", "warning:".yellow().bold(), warning);
+    }
+}
+
+/// Asserts that the Wasm at `dest_wasm` does not exceed the `max_code_size` in bytes.
+fn assert_max_code_size(dest_wasm: &Path, max_code_size: u64) -> Result<()> {
+    let code_size = metadata(dest_wasm)?.len();
+    if code_size > max_code_size {
+        anyhow::bail!(
+            "The contract's code size of {} bytes exceeds the maximum code size of {} bytes \
+            of the selected profile",
+            code_size,
+            max_code_size
+        )
+    }
+    Ok(())
+}
+
+/// Prints the location of an intermediate artifact which is kept due to `--keep-debug-artifacts`.
+pub(crate) fn print_debug_artifact(verbosity: Verbosity, description: &str, path: &Path) {
+    maybe_println!(
+        verbosity,
+        "       {} {} at '{}'",
+        "Keeping".bright_yellow().bold(),
+        description,
+        path.display()
+    );
+}
+
+/// Returns the RUSTFLAGS for compiling the contract for its Wasm target, including the ones
+/// requested by `--emit`.
+fn target_rustflags(crate_metadata: &CrateMetadata, cargo_flags: &CargoFlags) -> Result<String> {
+    let mut rustflags = rustflags::wasm_rustflags(cargo_flags)?;
+    if let Some(emit) = emit::rustflag(&cargo_flags.emit) {
+        rustflags = format!("{} {}", rustflags, emit);
+    }
+    if crate_metadata.wasm_target == WasmTarget::Wasm64 {
+        rustflags = format!("{} {}", rustflags, WASM64_RUSTFLAGS);
+    }
+    Ok(rustflags)
+}
+
+/// Returns the arguments for invoking `cargo build` or `cargo check` for the Wasm target of the
+/// package at `manifest_path`.
+fn wasm_cargo_args(
+    manifest_path: &ManifestPath,
+    crate_metadata: &CrateMetadata,
+    build_mode: BuildMode,
+    cargo_flags: &CargoFlags,
+) -> Result<Vec<String>> {
+    let target_dir = &crate_metadata.target_directory;
+    // The crates passed on the command line take precedence over the ones of the manifest.
+    let build_std = if cargo_flags.build_std.is_empty() {
+        &crate_metadata.build_std
+    } else {
+        &cargo_flags.build_std
+    };
+    let build_std = match build_std.is_empty() {
+        true => "-Zbuild-std".to_string(),
+        false => format!("-Zbuild-std={}", build_std.join(",")),
+    };
+    let mut args = vec![
+        manifest_path.cargo_arg()?,
+        format!("--target={}", crate_metadata.wasm_target.triple()),
+        build_std,
+        "--no-default-features".to_string(),
+        "--release".to_string(),
+        // only the library target is a contract, binary targets of the package are not built
+        "--lib".to_string(),
+        format!("--target-dir={}", target_dir.to_string_lossy()),
+    ];
+    args.extend(cargo_flags.args(true));
+    args.extend(cargo_flags.features_arg(None));
+    if build_mode == BuildMode::Debug {
+        args.push("--features=ink_env/ink-debug".to_string());
+    }
+    match panic_mode(crate_metadata, build_mode, cargo_flags) {
+        PanicMode::ImmediateAbort => {
+            args.push("-Zbuild-std-features=panic_immediate_abort".to_string())
+        }
+        PanicMode::Abort => (),
+        PanicMode::Unwind => args.push("-Zbuild-std-features=panic-unwind".to_string()),
+    }
+    // Arguments passed after `--` come last, so that they take precedence where possible.
+    args.extend(cargo_flags.build_args.iter().cloned());
+    Ok(args)
+}
+
+/// Returns the environment variables for invoking `cargo build` or `cargo check` for the Wasm
+/// target.
+///
+/// The `rustflags` already contain the `RUSTFLAGS` of the user, see
+/// [`rustflags::wasm_rustflags`].
+fn wasm_cargo_env<'a>(
+    rustflags: &'a str,
+    crate_metadata: &CrateMetadata,
+    build_mode: BuildMode,
+    cargo_flags: &'a CargoFlags,
+) -> Vec<(&'a str, Option<&'a str>)> {
+    // `CARGO_ENCODED_RUSTFLAGS` would take precedence over the merged `RUSTFLAGS`.
+    let mut env = vec![
+        ("RUSTFLAGS", Some(rustflags)),
+        ("CARGO_ENCODED_RUSTFLAGS", None),
+    ];
+    if panic_mode(crate_metadata, build_mode, cargo_flags) == PanicMode::Unwind {
+        // Overrides the `panic = "abort"` which is added to the `[profile.release]` by default.
+        env.push(("CARGO_PROFILE_RELEASE_PANIC", Some("unwind")));
+    }
+    env.extend(
+        cargo_flags
+            .env()
+            .into_iter()
+            .filter(|(key, _)| *key != "RUSTFLAGS"),
+    );
+    env
+}
+
+/// Returns how panics are handled in the contract's Wasm: the mode passed on the command line,
+/// the one of `[package.metadata.contract]`, or the default of the `build_mode`.
+fn panic_mode(
+    crate_metadata: &CrateMetadata,
+    build_mode: BuildMode,
+    cargo_flags: &CargoFlags,
+) -> PanicMode {
+    cargo_flags
+        .panic
+        .or(crate_metadata.panic)
+        .unwrap_or(match build_mode {
+            BuildMode::Debug => PanicMode::Abort,
+            BuildMode::Release => PanicMode::ImmediateAbort,
+        })
+}
+
+/// Returns the arguments for invoking `cargo dylint` with the ink! linting driver.
+fn dylint_args(crate_metadata: &CrateMetadata) -> Result<Vec<String>> {
+    Ok(vec![
+        "--lib".to_string(),
+        "ink_linting".to_string(),
+        crate_metadata.manifest_path.cargo_arg()?,
+    ])
+}
+
+/// Executes `cargo dylint` with the ink! linting driver that is built during
+/// the `build.rs`.
+///
+/// We create a temporary folder, extract the linting driver there and run
+/// `cargo dylint` with it.
+fn exec_cargo_dylint(
+    crate_metadata: &CrateMetadata,
+    cargo_flags: &CargoFlags,
+    verbosity: Verbosity,
+) -> Result<()> {
+    check_dylint_requirements(crate_metadata.manifest_path.directory())?;
+
+    let tmp_dir = tempfile::Builder::new()
+        .prefix("cargo-contract-dylint_")
+        .tempdir()?;
+    log::debug!("Using temp workspace at '{}'", tmp_dir.path().display());
+
+    let driver = include_bytes!(concat!(env!("OUT_DIR"), "/ink-dylint-driver.zip"));
+    crate::util::unzip(driver, tmp_dir.path().to_path_buf(), None)?;
+
+    let args = dylint_args(crate_metadata)?;
+    let tmp_dir_path = tmp_dir.path().as_os_str().to_string_lossy();
+    let mut env = vec![
+        ("DYLINT_LIBRARY_PATH", Some(tmp_dir_path.as_ref())),
+        // For tests we need to set the `DYLINT_DRIVER_PATH` to a tmp folder,
+        // otherwise tests running in parallel will try to write to the same
+        // file at the same time which will result in a `Text file busy` error.
+        #[cfg(test)]
+        ("DYLINT_DRIVER_PATH", Some(tmp_dir_path.as_ref())),
+        // We need to remove the `CARGO_TARGET_DIR` environment variable in
+        // case `cargo dylint` is invoked.
+        //
+        // This is because the ink! dylint driver crate found in `dylint` uses a
+        // fixed Rust toolchain via the `ink_linting/rust-toolchain` file. By
+        // removing this env variable we avoid issues with different Rust toolchains
+        // interfering with each other.
+        ("CARGO_TARGET_DIR", None),
+        // There are generally problems with having a custom `rustc` wrapper, while
+        // executing `dylint` (which has a custom linker). Especially for `sccache`
+        // there is this bug: https://github.com/mozilla/sccache/issues/1000.
+        // Until we have a justification for leaving the wrapper we should unset it.
+        ("RUSTC_WRAPPER", None),
+    ];
+    env.extend(cargo_flags.env());
+    let working_dir = crate_metadata
+        .manifest_path
+        .directory()
+        .unwrap_or_else(|| Path::new("."))
+        .canonicalize()?;
+
+    let verbosity = if verbosity == Verbosity::Verbose {
+        // `dylint` is verbose by default, it doesn't have a `--verbose` argument,
+        Verbosity::Default
+    } else {
+        verbosity
+    };
+    util::invoke_cargo("dylint", &args, Some(working_dir), verbosity, env)
+        .classify(ExitCode::Validation)?;
+
+    Ok(())
+}
+
+/// Checks if all requirements for `dylint` are installed.
+///
+/// We require only an installed version of `cargo-dylint` here and don't
+/// check for an installed version of `dylint-link`. This is because
+/// `dylint-link` is only required for the `dylint` driver build process
+/// in `build.rs`.
+///
+/// This function takes a `_working_dir` which is only used for unit tests.
+fn check_dylint_requirements(_working_dir: Option<&Path>) -> Result<()> {
+    let execute_cmd = |cmd: &mut Command| {
+        // when testing this function we set the `PATH` to the `working_dir`
+        // so that we can have mocked binaries in there which are executed
+        // instead of the real ones.
+        #[cfg(test)]
+        {
+            let default_dir = PathBuf::from(".");
+            let working_dir = _working_dir.unwrap_or(default_dir.as_path());
+            let path_env = std::env::var_os("PATH").unwrap();
+            let paths =
+                std::iter::once(working_dir.to_path_buf()).chain(std::env::split_paths(&path_env));
+            let path_env = std::env::join_paths(paths).unwrap();
+            cmd.env("PATH", path_env);
+        }
+
+        cmd.stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .map_err(|err| {
+                log::debug!("Error spawning `{:?}`", cmd);
+                err
+            })?
+            .wait()
+            .map(|res| res.success())
+            .map_err(|err| {
+                log::debug!("Error waiting for `{:?}`: {:?}", cmd, err);
+                err
+            })
+    };
+
+    // when testing this function we should never fall back to a `cargo` specified
+    // in the env variable, as this would mess with the mocked binaries.
+    #[cfg(not(test))]
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    #[cfg(test)]
+    let cargo = "cargo";
+
+    if !execute_cmd(Command::new(cargo).arg("dylint").arg("--version"))? {
+        anyhow::bail!("cargo-dylint was not found!\n\
+            Make sure it is installed and the binary is in your PATH environment.\n\n\
+            You can install it by executing `cargo install cargo-dylint`."
+            .to_string()
+            .bright_yellow());
+    }
+
+    Ok(())
+}
+
+/// Ensures the Wasm memory import of a given module has the maximum number of pages.
+///
+/// Iterates over the import section, finds the memory import entry if any and adjusts the maximum
+/// limit.
+pub(crate) fn ensure_maximum_memory_pages(
+    module: &mut Module,
+    maximum_allowed_pages: u32,
+) -> Result<()> {
+    let mem_ty = module
+        .import_section_mut()
+        .and_then(|section| {
+            section
+                .entries_mut()
+                .iter_mut()
+                .find_map(|entry| match entry.external_mut() {
+                    External::Memory(ref mut mem_ty) => Some(mem_ty),
+                    _ => None,
+                })
+        })
+        .context("Memory import is not found. Is --import-memory specified in the linker args")?;
+
+    if let Some(requested_maximum) = mem_ty.limits().maximum() {
+        // The module already has maximum, check if it is within the limit bail out.
+        if requested_maximum > maximum_allowed_pages {
+            anyhow::bail!(
+                "The wasm module requires {} pages. The maximum allowed number of pages is {}",
+                requested_maximum,
+                maximum_allowed_pages,
+            );
+        }
+    } else {
+        let initial = mem_ty.limits().initial();
+        *mem_ty = MemoryType::new(initial, Some(MAX_MEMORY_PAGES));
+    }
+
+    Ok(())
+}
+
+/// Strips all custom sections.
+///
+/// Presently all custom sections are not required so they can be stripped safely.
+/// The name section is already stripped by `wasm-opt`. The custom sections declared in
+/// `[package.metadata.contract.custom-sections]` are embedded afterwards.
+fn strip_custom_sections(module: &mut Module) {
+    module.sections_mut().retain(|section| match section {
+        Section::Reloc(_) => false,
+        Section::Custom(custom) if custom.name() != "name" => false,
+        _ => true,
+    })
+}
+
+/// Appends the custom sections declared in `[package.metadata.contract.custom-sections]`, e.g.
+/// the license or the source URL of the contract, with the UTF-8 encoded value as content.
+fn embed_custom_sections(module: &mut Module, custom_sections: &BTreeMap<String, String>) {
+    for (name, content) in custom_sections {
+        module
+            .sections_mut()
+            .push(Section::Custom(CustomSection::new(
+                name.clone(),
+                content.as_bytes().to_vec(),
+            )));
+    }
+}
+
+/// A contract should export nothing but the "call" and "deploy" functions.
+///
+/// Any elements not referenced by these exports become orphaned and are removed by `wasm-opt`.
+fn strip_exports(module: &mut Module) {
+    if let Some(section) = module.export_section_mut() {
+        section.entries_mut().retain(|entry| {
+            matches!(entry.internal(), Internal::Function(_))
+                && (entry.field() == "call" || entry.field() == "deploy")
+        })
+    }
+}
+
+/// Load and parse a Wasm file from disk.
+pub(crate) fn load_module<P: AsRef<Path>>(path: P) -> Result<Module> {
+    let path = path.as_ref();
+    parity_wasm::deserialize_file(path).context(format!(
+        "Loading of wasm module at '{}' failed",
+        path.display(),
+    ))
+}
+
+/// Performs required post-processing steps on the Wasm artifact.
+///
+/// Non-deterministic instructions are only rejected if the `determinism` is enforced.
+///
+/// A `wasm64` module is copied as is: `parity-wasm` does not support the `memory64` proposal, so
+/// it can not be stripped, it is validated with `wasmparser` instead.
+fn post_process_wasm(
+    crate_metadata: &CrateMetadata,
+    list_offending: bool,
+    determinism: Determinism,
+    verbosity: Verbosity,
+) -> Result<()> {
+    if crate_metadata.wasm_target == WasmTarget::Wasm64 {
+        maybe_println!(
+            verbosity,
+            "{} {}",
+            "warning:".yellow().bold(),
+            "wasm64 is experimental, the exports and custom sections of the Wasm are not stripped."
+                .bold()
+        );
+        let wasm = std::fs::read(&crate_metadata.original_wasm)
+            .context("Loading of original wasm failed")?;
+        validate_wasm::validate_wasm64(&wasm, determinism == Determinism::Enforced)
+            .classify(ExitCode::Validation)?;
+        std::fs::write(&crate_metadata.dest_wasm, wasm)
+            .context("Copying of original wasm failed")?;
+        return Ok(());
+    }
+
+    // Deserialize Wasm module from a file.
+    let mut module =
+        load_module(&crate_metadata.original_wasm).context("Loading of original wasm failed")?;
+
+    strip_exports(&mut module);
+    ensure_maximum_memory_pages(&mut module, MAX_MEMORY_PAGES)?;
+    strip_custom_sections(&mut module);
+    embed_custom_sections(&mut module, &crate_metadata.custom_sections);
+
+    validate_wasm::validate_import_section(&module).classify(ExitCode::Validation)?;
+    if determinism == Determinism::Enforced {
+        validate_wasm::validate_instructions(&module, list_offending)
+            .classify(ExitCode::Validation)?;
+    }
+
+    debug_assert!(
+        !module.clone().to_bytes().unwrap().is_empty(),
+        "resulting wasm size of post processing must be > 0"
+    );
+
+    parity_wasm::serialize_to_file(&crate_metadata.dest_wasm, module)?;
+    Ok(())
+}
+
+/// Attempts to perform optional Wasm optimization using `binaryen`.
+///
+/// The intention is to reduce the size of bloated Wasm binaries as a result of missing
+/// optimizations (or bugs?) between Rust and Wasm.
+fn optimize_wasm(
+    crate_metadata: &CrateMetadata,
+    wasm_opt: &WasmOpt,
+    optimization_passes: OptimizationPasses,
+    keep_debug_symbols: bool,
+) -> Result<OptimizationResult> {
+    let mut dest_optimized = crate_metadata.dest_wasm.clone();
+    dest_optimized.set_file_name(format!(
+        "{}-opt.wasm",
+        crate_metadata.contract_artifact_name
+    ));
+    let fallback = optimize_with_fallback(
+        wasm_opt,
+        &crate_metadata.dest_wasm,
+        &dest_optimized,
+        optimization_passes,
+        keep_debug_symbols,
+        crate_metadata.wasm_target,
+    )?;
+
+    let original_bytes = metadata(&crate_metadata.dest_wasm)?.len();
+    let original_size = original_bytes as f64 / 1000.0;
+    let optimized_size = metadata(&dest_optimized)?.len() as f64 / 1000.0;
+
+    // overwrite existing destination wasm file with the optimised version
+    std::fs::rename(&dest_optimized, &crate_metadata.dest_wasm)?;
+    Ok(OptimizationResult {
+        dest_wasm: crate_metadata.dest_wasm.clone(),
+        original_size,
+        original_bytes,
+        optimized_size,
+        fallback,
+    })
+}
+
+/// Optimizes `dest_wasm` into `dest_optimized` with the `optimization_passes`.
+///
+/// Some binaryen versions crash on particular contracts. If `wasm-opt` fails, it is retried with
+/// the safer `fallback_passes`, and the passes which were dropped are returned. Only if all of
+/// them fail the error of the requested passes is returned.
+fn optimize_with_fallback(
+    wasm_opt: &WasmOpt,
+    dest_wasm: &Path,
+    dest_optimized: &Path,
+    optimization_passes: OptimizationPasses,
+    keep_debug_symbols: bool,
+    wasm_target: WasmTarget,
+) -> Result<Option<OptimizationFallback>> {
+    let optimize = |passes: OptimizationPasses| -> Result<()> {
+        log::info!("Optimization level passed to wasm-opt: {}", passes);
+        match wasm_opt {
+            #[cfg(feature = "wasm-opt")]
+            WasmOpt::Bundled => do_bundled_optimization(
+                dest_wasm.as_os_str(),
+                dest_optimized.as_os_str(),
+                passes,
+                keep_debug_symbols,
+                wasm_target,
+            )?,
+            WasmOpt::External(wasm_opt_path) => do_optimization(
+                wasm_opt_path,
+                dest_wasm.as_os_str(),
+                dest_optimized.as_os_str(),
+                passes,
+                keep_debug_symbols,
+                wasm_target,
+            )?,
+        }
+        if !dest_optimized.exists() {
+            anyhow::bail!(
+                "Optimization failed, optimized wasm output file `{}` not found.",
+                dest_optimized.display()
+            );
+        }
+        Ok(())
+    };
+
+    let error = match optimize(optimization_passes) {
+        Ok(()) => return Ok(None),
+        Err(error) => error,
+    };
+    let mut dropped = vec![format!("-O{}", optimization_passes)];
+    for passes in fallback_passes(optimization_passes) {
+        log::warn!(
+            "wasm-opt failed with {}, retrying with -O{}: {:#}",
+            dropped.join(", "),
+            passes,
+            error
+        );
+        match optimize(passes) {
+            Ok(()) => {
+                return Ok(Some(OptimizationFallback {
+                    dropped,
+                    applied: format!("-O{}", passes),
+                    error: format!("{:#}", error),
+                }))
+            }
+            Err(_) => dropped.push(format!("-O{}", passes)),
+        }
+    }
+    Err(error.context(format!(
+        "wasm-opt failed with all of the optimization passes {}",
+        dropped.join(", ")
+    )))
+}
+
+/// Returns the optimization passes to retry a failed `wasm-opt` with, from the most to the least
+/// optimizing, each of them running fewer passes than `optimization_passes`.
+fn fallback_passes(optimization_passes: OptimizationPasses) -> Vec<OptimizationPasses> {
+    let level = |passes| match passes {
+        OptimizationPasses::Zero => 0,
+        OptimizationPasses::One => 1,
+        OptimizationPasses::Two => 2,
+        OptimizationPasses::Three | OptimizationPasses::S => 3,
+        OptimizationPasses::Four | OptimizationPasses::Z => 4,
+    };
+    [
+        OptimizationPasses::Two,
+        OptimizationPasses::One,
+        OptimizationPasses::Zero,
+    ]
+    .into_iter()
+    .filter(|passes| level(*passes) < level(optimization_passes))
+    .collect()
+}
+
+/// The `wasm-opt` used to optimize the contract's Wasm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum WasmOpt {
+    /// The binaryen library linked into `cargo-contract` via the `wasm-opt` crate.
+    #[cfg(feature = "wasm-opt")]
+    Bundled,
+    /// The `wasm-opt` executable at the given path.
+    External(PathBuf),
+}
+
+impl WasmOpt {
+    /// Returns the `wasm-opt` to use: the executable at `wasm_opt_path` if one was given,
+    /// otherwise the bundled one or, if `cargo-contract` was built without it, the executable
+    /// found in the `PATH`.
+    pub(crate) fn resolve(wasm_opt_path: Option<&Path>) -> Result<Self> {
+        if let Some(path) = wasm_opt_path {
+            if !path.is_file() {
+                anyhow::bail!("No wasm-opt executable found at {}", path.display());
+            }
+            return Ok(Self::External(path.to_path_buf()));
+        }
+        #[cfg(feature = "wasm-opt")]
+        {
+            Ok(Self::Bundled)
+        }
+        #[cfg(not(feature = "wasm-opt"))]
+        {
+            find_wasm_opt().map(Self::External).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "wasm-opt not found! Make sure the binary is in your PATH environment.\n\n\
+                    We use this tool to optimize the size of your contract's Wasm binary.\n\n\
+                    wasm-opt is part of the binaryen package. You can find detailed\n\
+                    installation instructions on https://github.com/WebAssembly/binaryen#tools.\n\n\
+                    There are ready-to-install packages for many platforms:\n\
+                    * Debian/Ubuntu: apt-get install binaryen\n\
+                    * Homebrew: brew install binaryen\n\
+                    * Arch Linux: pacman -S binaryen\n\
+                    * Windows: scoop install binaryen, or binary releases at \
+                    https://github.com/WebAssembly/binaryen/releases\n\n\
+                    Alternatively install cargo-contract with the `wasm-opt` feature enabled."
+                        .to_string()
+                        .bright_yellow()
+                )
+            })
+        }
+    }
+
+    /// Returns the `wasm-opt` of the binaryen release `version`, downloading it if necessary,
+    /// unless a `wasm_opt_path` is given.
+    pub(crate) fn resolve_pinned(
+        wasm_opt_path: Option<&Path>,
+        version: Option<u32>,
+        verbosity: Verbosity,
+    ) -> Result<Self> {
+        match (wasm_opt_path, version) {
+            (None, Some(version)) => {
+                binaryen::ensure_wasm_opt(version, verbosity).map(Self::External)
+            }
+            _ => Self::resolve(wasm_opt_path),
+        }
+    }
+}
+
+/// Returns the path of the `wasm-opt` executable.
+///
+/// Besides the `PATH`, the install locations of the common Windows package managers are searched,
+/// since those are not necessarily on the `PATH` of the current shell.
+#[cfg(not(feature = "wasm-opt"))]
+fn find_wasm_opt() -> Option<PathBuf> {
+    if let Ok(path) = which::which("wasm-opt") {
+        return Some(path);
+    }
+    windows_wasm_opt_locations()
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+/// Returns the locations `wasm-opt.exe` is installed to by `winget`, `scoop` and `chocolatey`,
+/// or as part of a binaryen release in the program files.
+#[cfg(not(feature = "wasm-opt"))]
+fn windows_wasm_opt_locations() -> Vec<PathBuf> {
+    if !cfg!(windows) {
+        return Vec::new();
+    }
+    let env_dir = |var| std::env::var_os(var).map(PathBuf::from);
+    let mut locations = Vec::new();
+    if let Some(local_app_data) = env_dir("LOCALAPPDATA") {
+        let winget = local_app_data.join(r"Microsoft\WinGet");
+        locations.push(winget.join(r"Links\wasm-opt.exe"));
+        // `winget` extracts archives to a directory named after the package id, binaryen
+        // releases contain a versioned directory with the `bin` directory inside.
+        let packages = std::fs::read_dir(winget.join("Packages"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.to_string_lossy().to_lowercase().contains("binaryen"));
+        for package in packages {
+            locations.push(package.join(r"bin\wasm-opt.exe"));
+            for release in std::fs::read_dir(&package).into_iter().flatten().flatten() {
+                locations.push(release.path().join(r"bin\wasm-opt.exe"));
+            }
+        }
+    }
+    if let Some(user_profile) = env_dir("USERPROFILE") {
+        locations.push(user_profile.join(r"scoop\shims\wasm-opt.exe"));
+    }
+    if let Some(program_data) = env_dir("ProgramData") {
+        locations.push(program_data.join(r"chocolatey\bin\wasm-opt.exe"));
+    }
+    if let Some(program_files) = env_dir("ProgramFiles") {
+        locations.push(program_files.join(r"binaryen\bin\wasm-opt.exe"));
+    }
+    locations
+}
+
+/// Optimizes the Wasm supplied as `dest_wasm` using the binaryen library bundled via the
+/// `wasm-opt` crate.
+///
+/// If successful, the optimized Wasm is written to `dest_optimized`.
+#[cfg(feature = "wasm-opt")]
+fn do_bundled_optimization(
+    dest_wasm: &OsStr,
+    dest_optimized: &OsStr,
+    optimization_level: OptimizationPasses,
+    keep_debug_symbols: bool,
+    wasm_target: WasmTarget,
+) -> Result<()> {
+    use wasm_opt::{Feature, OptimizationOptions};
+
+    let mut options = match optimization_level {
+        OptimizationPasses::Zero => OptimizationOptions::new_opt_level_0(),
+        OptimizationPasses::One => OptimizationOptions::new_opt_level_1(),
+        OptimizationPasses::Two => OptimizationOptions::new_opt_level_2(),
+        OptimizationPasses::Three => OptimizationOptions::new_opt_level_3(),
+        OptimizationPasses::Four => OptimizationOptions::new_opt_level_4(),
+        OptimizationPasses::S => OptimizationOptions::new_optimize_for_size(),
+        OptimizationPasses::Z => OptimizationOptions::new_optimize_for_size_aggressively(),
+    };
+    if wasm_target == WasmTarget::Wasm64 {
+        options.enable_feature(Feature::Memory64);
+    }
+    log::info!("Invoking the bundled wasm-opt on {:?}", dest_wasm);
+    options
+        // the memory in our module is imported, `wasm-opt` needs to be told that
+        // the memory is initialized to zeroes, otherwise it won't run the
+        // memory-packing pre-pass.
+        .zero_filled_memory(true)
+        .debug_info(keep_debug_symbols)
+        .run(dest_wasm, dest_optimized)
+        .map_err(|err| {
+            anyhow::anyhow!(
+                "The wasm-opt optimization failed.\n\n\
+                The error which wasm-opt returned was: \n{}",
+                err
+            )
+        })
+}
+
+/// Optimizes the Wasm supplied as `dest_wasm` using the `wasm-opt` executable at
+/// `wasm_opt_path`.
+///
+/// The supplied `optimization_level` denotes the number of optimization passes,
+/// resulting in potentially a lot of time spent optimizing.
+///
+/// If successful, the optimized Wasm is written to `dest_optimized`.
+fn do_optimization(
+    wasm_opt_path: &Path,
+    dest_wasm: &OsStr,
+    dest_optimized: &OsStr,
+    optimization_level: OptimizationPasses,
+    keep_debug_symbols: bool,
+    wasm_target: WasmTarget,
+) -> Result<()> {
+    log::info!("Path to wasm-opt executable: {}", wasm_opt_path.display());
+
+    let _ = check_wasm_opt_version_compatibility(wasm_opt_path)?;
+
+    let mut command = Command::new(wasm_opt_path);
+    command.args(wasm_opt_args(
+        dest_wasm,
+        dest_optimized,
+        optimization_level,
+        keep_debug_symbols,
+        wasm_target,
+    ));
+    log::info!("Invoking wasm-opt with {:?}", command);
+    let output = command.output().map_err(|err| {
+        anyhow::anyhow!(
+            "Executing {} failed with {:?}",
+            wasm_opt_path.display(),
+            err
+        )
+    })?;
+
+    if !output.status.success() {
+        let err = str::from_utf8(&output.stderr)
+            .expect("Cannot convert stderr output of wasm-opt to string")
+            .trim();
+        anyhow::bail!(
+            "The wasm-opt optimization failed.\n\n\
+            The error which wasm-opt returned was: \n{}",
+            err
+        );
+    }
+    Ok(())
+}
+
+/// Returns the arguments for invoking `wasm-opt` on `dest_wasm`.
+fn wasm_opt_args(
+    dest_wasm: &OsStr,
+    dest_optimized: &OsStr,
+    optimization_level: OptimizationPasses,
+    keep_debug_symbols: bool,
+    wasm_target: WasmTarget,
+) -> Vec<OsString> {
+    let mut args = vec![
+        dest_wasm.to_os_string(),
+        format!("-O{}", optimization_level).into(),
+        "-o".into(),
+        dest_optimized.to_os_string(),
+        // the memory in our module is imported, `wasm-opt` needs to be told that
+        // the memory is initialized to zeroes, otherwise it won't run the
+        // memory-packing pre-pass.
+        "--zero-filled-memory".into(),
+    ];
+    if keep_debug_symbols {
+        args.push("-g".into());
+    }
+    if wasm_target == WasmTarget::Wasm64 {
+        args.push("--enable-memory64".into());
+    }
+    args
+}
+
+/// Checks if the `wasm-opt` binary under `wasm_opt_path` returns a version
+/// compatible with `cargo-contract`.
+///
+/// Currently this must be a version >= 99.
+fn check_wasm_opt_version_compatibility(wasm_opt_path: &Path) -> Result<()> {
+    let mut cmd_res = Command::new(wasm_opt_path).arg("--version").output();
+
+    // The following condition is a workaround for a spurious CI failure:
+    // ```
+    // Executing `"/tmp/cargo-contract.test.GGnC0p/wasm-opt-mocked" --version` failed with
+    // Os { code: 26, kind: ExecutableFileBusy, message: "Text file busy" }
+    // ```
+    if cmd_res.is_err() && format!("{:?}", cmd_res).contains("ExecutableFileBusy") {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        cmd_res = Command::new(wasm_opt_path).arg("--version").output();
+    }
+
+    let res = cmd_res.map_err(|err| {
+        anyhow::anyhow!(
+            "Executing `{:?} --version` failed with {:?}",
+            wasm_opt_path.display(),
+            err
+        )
+    })?;
+    if !res.status.success() {
+        let err = str::from_utf8(&res.stderr)
+            .expect("Cannot convert stderr output of wasm-opt to string")
+            .trim();
+        anyhow::bail!(
+            "Getting version information from wasm-opt failed.\n\
+            The error which wasm-opt returned was: \n{}",
+            err
+        );
+    }
+
+    // ```sh
+    // $ wasm-opt --version
+    // wasm-opt version 99 (version_99-79-gc12cc3f50)
+    // ```
+    let github_note = "\n\n\
+        If you tried installing from your system package manager the best\n\
+        way forward is to download a recent binary release directly:\n\n\
+        https://github.com/WebAssembly/binaryen/releases\n\n\
+        Make sure that the `wasm-opt` file from that release is in your `PATH`.";
+    let version_stdout = str::from_utf8(&res.stdout)
+        .expect("Cannot convert stdout output of wasm-opt to string")
+        .trim();
+    let re = Regex::new(r"wasm-opt version (\d+)").expect("invalid regex");
+    let captures = re.captures(version_stdout).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Unable to extract version information from '{}'.\n\
+            Your wasm-opt version is most probably too old. Make sure you use a version >= 99.{}",
+            version_stdout,
+            github_note,
+        )
+    })?;
+    let version_number: u32 = captures
+        .get(1) // first capture group is at index 1
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unable to extract version number from '{:?}'",
+                version_stdout
+            )
+        })?
+        .as_str()
+        .parse()
+        .map_err(|err| {
+            anyhow::anyhow!(
+                "Parsing version number failed with '{:?}' for '{:?}'",
+                err,
+                version_stdout
+            )
+        })?;
+
+    log::info!(
+        "The wasm-opt version output is '{}', which was parsed to '{}'",
+        version_stdout,
+        version_number
+    );
+    if version_number < 99 {
+        anyhow::bail!(
+            "Your wasm-opt version is {}, but we require a version >= 99.{}",
+            version_number,
+            github_note,
+        );
+    }
+    Ok(())
+}
+
+/// Asserts that the contract's dependencies are compatible to the ones used in ink!.
+///
+/// This function utilizes `cargo tree`, which takes semver into consideration.
+///
+/// Hence this function only returns an `Err` if it is a proper mismatch according
+/// to semantic versioning. This means that either:
+///     - the major version mismatches, differences in the minor/patch version
+///       are not considered incompatible.
+///     - or if the version starts with zero (i.e. `0.y.z`) a mismatch in the minor
+///       version is already considered incompatible.
+fn assert_compatible_ink_dependencies(
+    manifest_path: &ManifestPath,
+    cargo_flags: &CargoFlags,
+    verbosity: Verbosity,
+) -> Result<()> {
+    for dependency in ["parity-scale-codec", "scale-info"].iter() {
+        let mut args = vec![
+            "-i".to_string(),
+            dependency.to_string(),
+            "--duplicates".to_string(),
+        ];
+        args.extend(cargo_flags.args(true));
+        let env = cargo_flags.env();
+        let _ = util::invoke_cargo("tree", &args, manifest_path.directory(), verbosity, env)
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "Mismatching versions of `{}` were found!\n\
+                     Please ensure that your contract and your ink! dependencies use a compatible \
+                     version of this package.",
+                    dependency
+                )
+            })?;
+    }
+    Ok(())
+}
+
+/// The major versions of ink! and the `cargo-contract` versions which support them.
+const INK_COMPATIBILITY: [(u64, &str); 3] = [
+    (3, ">=0.18.0, <2.0.0"),
+    (4, ">=2.0.0, <4.0.0"),
+    (5, ">=4.0.0, <5.0.0"),
+];
+
+/// Checks whether the contract's `ink_version` is supported by this version of `cargo-contract`.
+///
+/// Building a contract with an incompatible `cargo-contract` fails in obscure ways, e.g. when
+/// generating the metadata, hence we fail early with a hint on which version to install.
+pub fn assert_compatible_ink_version(ink_version: &Version) -> Result<()> {
+    let cargo_contract_version =
+        Version::parse(env!("CARGO_PKG_VERSION")).expect("the crate version must be valid");
+    check_ink_compatibility(ink_version, &cargo_contract_version)
+}
+
+fn check_ink_compatibility(ink_version: &Version, cargo_contract_version: &Version) -> Result<()> {
+    let requirement = match INK_COMPATIBILITY
+        .iter()
+        .find(|(ink_major, _)| *ink_major == ink_version.major)
+    {
+        Some((_, requirement)) => {
+            VersionReq::parse(requirement).expect("the compatibility requirements must be valid")
+        }
+        None if ink_version.major < INK_COMPATIBILITY[0].0 => {
+            anyhow::bail!(
+                "ink! {} is not supported, the contract must use at least ink! {}.0.0",
+                ink_version,
+                INK_COMPATIBILITY[0].0
+            )
+        }
+        None => {
+            anyhow::bail!(
+                "ink! {} is newer than any version known to cargo-contract {}. \
+                Update cargo-contract with `cargo install cargo-contract --force`.",
+                ink_version,
+                cargo_contract_version
+            )
+        }
+    };
+    if !requirement.matches(cargo_contract_version) {
+        anyhow::bail!(
+            "ink! {}.x requires cargo-contract {}, but this is cargo-contract {}. \
+            Install a compatible version with `cargo install cargo-contract --version '{}' --force`.",
+            ink_version.major,
+            requirement,
+            cargo_contract_version,
+            requirement
+        )
+    }
+    Ok(())
+}
+
+/// Checks whether the supplied `ink_version` already contains the debug feature.
+///
+/// This feature was introduced in `3.0.0-rc4` with `ink_env/ink-debug`.
+pub fn assert_debug_mode_supported(ink_version: &Version) -> anyhow::Result<()> {
+    log::info!("Contract version: {:?}", ink_version);
+    let minimum_version = Version::parse("3.0.0-rc4").expect("parsing version failed");
+    if ink_version < &minimum_version {
+        anyhow::bail!(
+            "Building the contract in debug mode requires an ink! version newer than `3.0.0-rc3`!"
+        );
+    }
+    Ok(())
+}
+
+/// Executes build of the smart contract which produces a Wasm binary that is ready for deploying.
+///
+/// It does so by invoking `cargo build` and then post processing the final binary.
+pub(crate) fn execute(args: ExecuteArgs) -> Result<BuildResult> {
+    let ExecuteArgs {
+        manifest_path,
+        verbosity,
+        build_mode,
+        cargo_flags,
+        build_artifact,
+        unstable_flags,
+        optimization_passes,
+        keep_debug_symbols,
+        keep_debug_artifacts,
+        wasm_opt_path,
+        wasm_opt_version,
+        max_code_size,
+        install_missing_components,
+        list_offending,
+        determinism,
+        output_type,
+        cargo_lock,
+    } = args;
+
+    crate::cmd::check_project(&manifest_path, verbosity)?;
+    let mut crate_metadata = CrateMetadata::collect_with(&manifest_path, &cargo_flags)?;
+    crate_metadata.set_wasm_target(unstable_flags.wasm_target());
+    toolchain::ensure_wasm_requirements(
+        &manifest_path.absolute_directory()?,
+        crate_metadata.wasm_target,
+        install_missing_components,
+        verbosity,
+    )
+    .classify(ExitCode::Toolchain)?;
+
+    assert_compatible_ink_version(&crate_metadata.ink_version)?;
+    assert_compatible_ink_dependencies(&manifest_path, &cargo_flags, verbosity)?;
+    if build_mode == BuildMode::Debug {
+        assert_debug_mode_supported(&crate_metadata.ink_version)?;
+    }
+
+    let hooks = &crate_metadata.hooks;
+    let mut opt_result: Option<OptimizationResult> = None;
+    let mut metadata_result = None;
+    let mut cargo_guard = None;
+    for step in plan::steps(build_artifact) {
+        match step {
+            Step::Hook(hook) => hooks.run(hook, &crate_metadata, build_mode, verbosity)?,
+            Step::Lint => {
+                cargo_guard = Some(members::lock_cargo(cargo_lock.as_ref()));
+                step_println!(
+                    verbosity,
+                    1,
+                    build_artifact.steps(),
+                    "Checking ink! linting rules"
+                );
+                exec_cargo_dylint(&crate_metadata, &cargo_flags, verbosity)?;
+            }
+            Step::CargoCheck | Step::CargoBuild => {
+                let (command, build_mode, description) = match step {
+                    Step::CargoCheck => ("check", BuildMode::Release, "Executing `cargo check`"),
+                    _ => ("build", build_mode, "Building cargo project"),
+                };
+                step_println!(verbosity, 2, build_artifact.steps(), description);
+                exec_cargo_for_wasm_target(
+                    &crate_metadata,
+                    command,
+                    build_mode,
+                    &cargo_flags,
+                    verbosity,
+                    &unstable_flags,
+                    keep_debug_artifacts,
+                )?;
+                cargo_guard = None;
+            }
+            Step::PostProcess => {
+                step_println!(
+                    verbosity,
+                    3,
+                    build_artifact.steps(),
+                    "Post processing wasm file"
+                );
+                if !cargo_flags.emit.is_empty() {
+                    emit::collect(&crate_metadata, &cargo_flags.emit, verbosity)?;
+                }
+                post_process_wasm(&crate_metadata, list_offending, determinism, verbosity)?;
+            }
+            Step::Optimize => {
+                if keep_debug_artifacts {
+                    let mut pre_opt_wasm = crate_metadata.dest_wasm.clone();
+                    pre_opt_wasm.set_file_name(format!(
+                        "{}-pre-opt.wasm",
+                        crate_metadata.contract_artifact_name
+                    ));
+                    std::fs::copy(&crate_metadata.dest_wasm, &pre_opt_wasm)?;
+                    print_debug_artifact(verbosity, "Wasm before optimization", &pre_opt_wasm);
+                }
+
+                step_println!(verbosity, 4, build_artifact.steps(), "Optimizing wasm file");
+                let wasm_opt = WasmOpt::resolve_pinned(
+                    wasm_opt_path.as_deref(),
+                    wasm_opt_version.or(crate_metadata.wasm_opt_version),
+                    verbosity,
+                )?;
+                let optimization_result = optimize_wasm(
+                    &crate_metadata,
+                    &wasm_opt,
+                    optimization_passes,
+                    keep_debug_symbols,
+                )?;
+                if let Some(max_code_size) = max_code_size {
+                    assert_max_code_size(&optimization_result.dest_wasm, max_code_size)?;
+                }
+                if let Some(max_size) = crate_metadata.max_size {
+                    size_report::assert_size_budget(
+                        &crate_metadata,
+                        &optimization_result.dest_wasm,
+                        max_size,
+                    )
+                    .classify(ExitCode::Validation)?;
+                }
+                if crate_metadata.wasm_target == WasmTarget::Wasm32 {
+                    validate_wasm::validate_custom_sections(
+                        &load_module(&optimization_result.dest_wasm)?,
+                        &crate_metadata.custom_sections,
+                    )
+                    .classify(ExitCode::Validation)?;
+                }
+                opt_result = Some(optimization_result);
+            }
+            Step::Metadata => {
+                let dest_wasm = &opt_result
+                    .as_ref()
+                    .expect("the Wasm is optimized before the metadata is generated")
+                    .dest_wasm;
+                let _cargo_guard = members::lock_cargo(cargo_lock.as_ref());
+                metadata_result = Some(super::metadata::execute(
+                    &crate_metadata,
+                    dest_wasm,
+                    &cargo_flags,
+                    verbosity,
+                    build_artifact.steps(),
+                    &unstable_flags,
+                    keep_debug_artifacts,
+                )?);
+            }
+        }
+    }
+    drop(cargo_guard);
+    timings::end_step();
+    let dest_wasm = opt_result.as_ref().map(|r| r.dest_wasm.clone());
+    if let Some(result) = &opt_result {
+        tracing::info!(
+            target: "cargo_contract::events::artifact",
+            path = %result.dest_wasm.display(),
+            original_size = result.original_size,
+            optimized_size = result.optimized_size,
+        );
+    }
+    if let Some(result) = &metadata_result {
+        for path in [&result.dest_metadata, &result.dest_bundle] {
+            let size = metadata(path).map(|file| file.len()).unwrap_or_default();
+            tracing::info!(
+                target: "cargo_contract::events::artifact",
+                path = %path.display(),
+                size,
+            );
+        }
+    }
+
+    Ok(BuildResult {
+        dest_wasm,
+        metadata_result,
+        target_directory: crate_metadata.target_directory,
+        optimization_result: opt_result,
+        build_mode,
+        build_artifact,
+        verbosity,
+        feature_set: None,
+        output_type,
+    })
+}
+
+#[cfg(feature = "test-ci-only")]
+#[cfg(test)]
+mod tests_ci_only {
+    use super::{
+        assert_compatible_ink_dependencies, assert_debug_mode_supported, check_ink_compatibility,
+        check_wasm_opt_version_compatibility, optimize_with_fallback, wasm_cargo_args,
+        wasm_cargo_env, WasmOpt,
+    };
+    use crate::{
+        cmd::{build::load_module, BuildCommand},
+        crate_metadata::CrateMetadata,
+        util::tests::{with_new_contract_project, with_tmp_dir},
+        workspace::Manifest,
+        BuildArtifacts, BuildMode, CargoFlags, CargoOptions, Determinism, ManifestPath,
+        OptimizationPasses, OutputType, PanicMode, UnstableOptions, Verbosity, VerbosityFlags,
+    };
+    use semver::Version;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+    use std::{
+        ffi::OsStr,
+        io::Write,
+        path::{Path, PathBuf},
+    };
+
+    /// Modifies the `Cargo.toml` under the supplied `cargo_toml_path` by
+    /// setting `optimization-passes` in `[package.metadata.contract]` to `passes`.
+    fn write_optimization_passes_into_manifest(cargo_toml_path: &Path, passes: OptimizationPasses) {
+        let manifest_path =
+            ManifestPath::new(cargo_toml_path).expect("manifest path creation failed");
+        let mut manifest = Manifest::new(manifest_path.clone()).expect("manifest creation failed");
+        manifest
+            .set_profile_optimization_passes(passes)
+            .expect("setting `optimization-passes` in profile failed");
+        manifest
+            .write(&manifest_path)
+            .expect("writing manifest failed");
+    }
+
+    fn has_debug_symbols<P: AsRef<Path>>(p: P) -> bool {
+        load_module(p)
+            .unwrap()
+            .custom_sections()
+            .any(|e| e.name() == "name")
+    }
+
+    /// Creates an executable file at `path` with the content `content`.
+    ///
+    /// Currently works only on `unix`.
+    #[cfg(unix)]
+    fn create_executable(path: &Path, content: &str) {
+        {
+            let mut file = std::fs::File::create(&path).unwrap();
+            file.write_all(content.as_bytes())
+                .expect("writing of executable failed");
+        }
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o777))
+            .expect("setting permissions failed");
+    }
+
+    /// Creates an executable `wasm-opt-mocked` file which outputs
+    /// "wasm-opt version `version`".
+    ///
+    /// Returns the path to this file.
+    ///
+    /// Currently works only on `unix`.
+    #[cfg(unix)]
+    fn mock_wasm_opt_version(tmp_dir: &Path, version: &str) -> PathBuf {
+        let path = tmp_dir.join("wasm-opt-mocked");
+        let content = format!("#!/bin/sh\necho \"wasm-opt version {}\"", version);
+        create_executable(&path, &content);
+        path
+    }
+
+    #[test]
+    fn build_code_only() {
+        with_new_contract_project(|manifest_path| {
+            let args = crate::cmd::build::ExecuteArgs {
+                manifest_path,
+                build_mode: BuildMode::Release,
+                build_artifact: BuildArtifacts::CodeOnly,
+                ..Default::default()
+            };
+
+            let res = super::execute(args).expect("build failed");
+
+            // our ci has set `CARGO_TARGET_DIR` to cache artifacts.
+            // this dir does not include `/target/` as a path, hence
+            // we can't match for e.g. `foo_project/target/ink`.
+            //
+            // we also can't match for `/ink` here, since this would match
+            // for `/ink` being the root path.
+            assert!(res.target_directory.ends_with("ink"));
+
+            assert!(
+                res.metadata_result.is_none(),
+                "CodeOnly should not generate the metadata"
+            );
+
+            let optimized_size = res.optimization_result.unwrap().optimized_size;
+            assert!(optimized_size > 0.0);
+
+            // our optimized contract template should always be below 3k.
+            assert!(optimized_size < 3.0);
+
+            // we specified that debug symbols should be removed
+            // original code should have some but the optimized version should have them removed
+            assert!(!has_debug_symbols(&res.dest_wasm.unwrap()));
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn check_must_not_output_contract_artifacts_in_project_dir() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            let project_dir = manifest_path.directory().expect("directory must exist");
+            let args = crate::cmd::build::ExecuteArgs {
+                manifest_path: manifest_path.clone(),
+                build_artifact: BuildArtifacts::CheckOnly,
+                ..Default::default()
+            };
+
+            // when
+            super::execute(args).expect("build failed");
+
+            // then
+            assert!(
+                !project_dir.join("target/ink/new_project.contract").exists(),
+                "found contract artifact in project directory!"
+            );
+            assert!(
+                !project_dir.join("target/ink/new_project.wasm").exists(),
+                "found wasm artifact in project directory!"
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn optimization_passes_from_cli_must_take_precedence_over_profile() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            write_optimization_passes_into_manifest(
+                manifest_path.as_ref(),
+                OptimizationPasses::Three,
+            );
+            let cmd = BuildCommand {
+                manifest_path: Some(manifest_path.into()),
+                build_artifact: BuildArtifacts::All,
+                build_release: false,
+                cargo_options: CargoOptions::default(),
+                verbosity: VerbosityFlags::default(),
+                unstable_options: UnstableOptions::default(),
+
+                // we choose zero optimization passes as the "cli" parameter
+                optimization_passes: Some(OptimizationPasses::Zero),
+                keep_debug_symbols: false,
+                keep_debug_artifacts: false,
+                wasm_opt_path: None,
+                wasm_opt_version: None,
+                all_feature_combos: false,
+                feature_matrix: false,
+                check_determinism: false,
+                output_json: false,
+                build_plan: false,
+                profile: None,
+                install_missing_components: false,
+                workspace: false,
+                jobs: None,
+                emit: Vec::new(),
+                list_offending: false,
+                determinism: Determinism::Enforced,
+                output_dir: None,
+                artifact_name: None,
+                checksums: false,
+                profile_strict: false,
+                opt_level: None,
+                lto: None,
+                codegen_units: None,
+                compare: false,
+                size_history: None,
+            };
+
+            // when
+            let res = cmd.exec().expect("build failed");
+            let optimization = res
+                .optimization_result
+                .expect("no optimization result available");
+
+            // then
+            // The size does not exactly match the original size even without optimization
+            // passed because there is still some post processing happening.
+            let size_diff = optimization.original_size - optimization.optimized_size;
+            assert!(
+                0.0 < size_diff && size_diff < 10.0,
+                "The optimized size savings are larger than allowed or negative: {}",
+                size_diff,
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn optimization_passes_from_profile_must_be_used() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            write_optimization_passes_into_manifest(
+                manifest_path.as_ref(),
+                OptimizationPasses::Three,
+            );
+            let cmd = BuildCommand {
+                manifest_path: Some(manifest_path.into()),
+                build_artifact: BuildArtifacts::All,
+                build_release: false,
+                cargo_options: CargoOptions::default(),
+                verbosity: VerbosityFlags::default(),
+                unstable_options: UnstableOptions::default(),
+
+                // we choose no optimization passes as the "cli" parameter
+                optimization_passes: None,
+                keep_debug_symbols: false,
+                keep_debug_artifacts: false,
+                wasm_opt_path: None,
+                wasm_opt_version: None,
+                all_feature_combos: false,
+                feature_matrix: false,
+                check_determinism: false,
+                output_json: false,
+                build_plan: false,
+                profile: None,
+                install_missing_components: false,
+                workspace: false,
+                jobs: None,
+                emit: Vec::new(),
+                list_offending: false,
+                determinism: Determinism::Enforced,
+                output_dir: None,
+                artifact_name: None,
+                checksums: false,
+                profile_strict: false,
+                opt_level: None,
+                lto: None,
+                codegen_units: None,
+                compare: false,
+                size_history: None,
+            };
+
+            // when
+            let res = cmd.exec().expect("build failed");
+            let optimization = res
+                .optimization_result
+                .expect("no optimization result available");
+
+            // then
+            // The size does not exactly match the original size even without optimization
+            // passed because there is still some post processing happening.
+            let size_diff = optimization.original_size - optimization.optimized_size;
+            assert!(
+                size_diff > (optimization.original_size / 2.0),
+                "The optimized size savings are too small: {}",
+                size_diff,
+            );
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn build_plan_must_not_execute_any_step() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            let cmd = BuildCommand {
+                manifest_path: Some(manifest_path.clone().into()),
+                build_artifact: BuildArtifacts::All,
+                build_release: false,
+                cargo_options: CargoOptions::default(),
+                verbosity: VerbosityFlags::default(),
+                unstable_options: UnstableOptions::default(),
+                optimization_passes: None,
+                keep_debug_symbols: false,
+                keep_debug_artifacts: false,
+                wasm_opt_path: None,
+                wasm_opt_version: None,
+                all_feature_combos: false,
+                feature_matrix: false,
+                check_determinism: false,
+                output_json: false,
+                build_plan: true,
+                profile: None,
+                install_missing_components: false,
+                workspace: false,
+                jobs: None,
+                emit: Vec::new(),
+                list_offending: false,
+                determinism: Determinism::Enforced,
+                output_dir: None,
+                artifact_name: None,
+                checksums: false,
+                profile_strict: false,
+                opt_level: None,
+                lto: None,
+                codegen_units: None,
+                compare: false,
+                size_history: None,
+            };
+
+            // when
+            let plan = cmd.plan()?.expect("build plan must be returned");
+
+            // then
+            let crate_metadata = CrateMetadata::collect(&manifest_path)?;
+            assert_eq!(plan.steps.len(), 6);
+            assert_eq!(
+                plan.artifacts,
+                vec![
+                    crate_metadata.dest_wasm.clone(),
+                    crate_metadata.metadata_path(),
+                    crate_metadata.target_directory.join("new_project.contract"),
+                ]
+            );
+            assert!(
+                !crate_metadata.dest_wasm.exists(),
+                "the build plan must not produce any artifacts"
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn build_std_and_panic_mode_are_configurable() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            let mut manifest = std::fs::OpenOptions::new()
+                .append(true)
+                .open(&manifest_path)?;
+            writeln!(
+                manifest,
+                "\n[package.metadata.contract]\nbuild-std = [\"core\", \"alloc\"]\npanic = \"abort\""
+            )?;
+            let crate_metadata = CrateMetadata::collect(&manifest_path)?;
+            let args = |cargo_flags: &CargoFlags| {
+                wasm_cargo_args(
+                    &manifest_path,
+                    &crate_metadata,
+                    BuildMode::Release,
+                    cargo_flags,
+                )
+                .expect("arguments must be returned")
+            };
+
+            // when
+            let manifest_args = args(&CargoFlags::default());
+            let mut cargo_flags = CargoFlags::default();
+            cargo_flags.build_std = vec!["core".to_string()];
+            cargo_flags.panic = Some(PanicMode::Unwind);
+            let cli_args = args(&cargo_flags);
+
+            // then
+            assert!(manifest_args.contains(&"-Zbuild-std=core,alloc".to_string()));
+            assert!(!manifest_args
+                .iter()
+                .any(|arg| arg.contains("build-std-features")));
+            assert!(cli_args.contains(&"-Zbuild-std=core".to_string()));
+            assert!(cli_args.contains(&"-Zbuild-std-features=panic-unwind".to_string()));
+            assert!(
+                wasm_cargo_env("", &crate_metadata, BuildMode::Release, &cargo_flags)
+                    .contains(&("CARGO_PROFILE_RELEASE_PANIC", Some("unwind")))
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn project_template_dependencies_must_be_ink_compatible() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            // the manifest path
+
+            // when
+            let res = assert_compatible_ink_dependencies(
+                &manifest_path,
+                &CargoFlags::default(),
+                Verbosity::Default,
+            );
+
+            // then
+            assert!(res.is_ok());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn detect_mismatching_parity_scale_codec_dependencies() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            // the manifest path
+
+            // at the time of writing this test ink! already uses `parity-scale-codec`
+            // in a version > 2, hence 1 is an incompatible version.
+            let mut manifest = Manifest::new(manifest_path.clone())?;
+            manifest
+                .set_dependency_version("scale", "1.0.0")
+                .expect("setting `scale` version failed");
+            manifest
+                .write(&manifest_path)
+                .expect("writing manifest failed");
+
+            // when
+            let res = assert_compatible_ink_dependencies(
+                &manifest_path,
+                &CargoFlags::default(),
+                Verbosity::Default,
+            );
+
+            // then
+            assert!(res.is_err());
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn incompatible_wasm_opt_version_must_be_detected_if_built_from_repo() {
+        with_tmp_dir(|path| {
+            // given
+            let path = mock_wasm_opt_version(path, "98 (version_13-79-gc12cc3f50)");
+
+            // when
+            let res = check_wasm_opt_version_compatibility(&path);
+
+            // then
+            assert!(res.is_err());
+            assert!(
+                format!("{:?}", res).starts_with(
+                    "Err(Your wasm-opt version is 98, but we require a version >= 99."
+                ),
+                "Expected a different output, found {:?}",
+                res
+            );
+
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn compatible_wasm_opt_version_must_be_detected_if_built_from_repo() {
+        with_tmp_dir(|path| {
+            // given
+            let path = mock_wasm_opt_version(path, "99 (version_99-79-gc12cc3f50");
+
+            // when
+            let res = check_wasm_opt_version_compatibility(&path);
+
+            // then
+            assert!(res.is_ok());
+
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn incompatible_wasm_opt_version_must_be_detected_if_installed_as_package() {
+        with_tmp_dir(|path| {
+            // given
+            let path = mock_wasm_opt_version(path, "98");
+
+            // when
+            let res = check_wasm_opt_version_compatibility(&path);
+
+            // then
+            assert!(res.is_err());
+
+            // this println is here to debug a spuriously failing CI at the following assert.
+            eprintln!("error: {:?}", res);
+            assert!(format!("{:?}", res)
+                .starts_with("Err(Your wasm-opt version is 98, but we require a version >= 99."));
+
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn compatible_wasm_opt_version_must_be_detected_if_installed_as_package() {
+        with_tmp_dir(|path| {
+            // given
+            let path = mock_wasm_opt_version(path, "99");
+
+            // when
+            let res = check_wasm_opt_version_compatibility(&path);
+
+            // then
+            assert!(res.is_ok());
+
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_wasm_opt_must_be_retried_with_fewer_passes() {
+        with_tmp_dir(|path| {
+            // given
+            let wasm_opt = path.join("wasm-opt-mocked");
+            create_executable(
+                &wasm_opt,
+                "#!/bin/sh\n\
+                 if [ \"$1\" = \"--version\" ]; then echo \"wasm-opt version 101\"; exit 0; fi\n\
+                 case \"$2\" in -Oz|-O2) echo \"Fatal: crashed\" >&2; exit 1;; esac\n\
+                 cp \"$1\" \"$4\"\n",
+            );
+            let dest_wasm = path.join("contract.wasm");
+            let dest_optimized = path.join("contract-opt.wasm");
+            std::fs::write(&dest_wasm, "\0asm")?;
+
+            // when
+            let fallback = optimize_with_fallback(
+                &WasmOpt::External(wasm_opt),
+                &dest_wasm,
+                &dest_optimized,
+                OptimizationPasses::Z,
+                false,
+                Default::default(),
+            )?
+            .expect("wasm-opt must have been retried");
+
+            // then
+            assert_eq!(fallback.dropped, vec!["-Oz", "-O2"]);
+            assert_eq!(fallback.applied, "-O1");
+            assert!(fallback.error.contains("Fatal: crashed"));
+            assert!(dest_optimized.exists());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn contract_lib_name_different_from_package_name_must_build() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            let mut manifest =
+                Manifest::new(manifest_path.clone()).expect("manifest creation failed");
+            let _ = manifest
+                .set_lib_name("some_lib_name")
+                .expect("setting lib name failed");
+            let _ = manifest
+                .set_package_name("some_package_name")
+                .expect("setting pacakge name failed");
+            manifest
+                .write(&manifest_path)
+                .expect("writing manifest failed");
+
+            // when
+            let cmd = BuildCommand {
+                manifest_path: Some(manifest_path.into()),
+                build_artifact: BuildArtifacts::All,
+                build_release: false,
+                cargo_options: CargoOptions::default(),
+                verbosity: VerbosityFlags::default(),
+                unstable_options: UnstableOptions::default(),
+                optimization_passes: None,
+                keep_debug_symbols: false,
+                keep_debug_artifacts: false,
+                wasm_opt_path: None,
+                wasm_opt_version: None,
+                all_feature_combos: false,
+                feature_matrix: false,
+                check_determinism: false,
+                output_json: false,
+                build_plan: false,
+                profile: None,
+                install_missing_components: false,
+                workspace: false,
+                jobs: None,
+                emit: Vec::new(),
+                list_offending: false,
+                determinism: Determinism::Enforced,
+                output_dir: None,
+                artifact_name: None,
+                checksums: false,
+                profile_strict: false,
+                opt_level: None,
+                lto: None,
+                codegen_units: None,
+                compare: false,
+                size_history: None,
+            };
+            let res = cmd.exec().expect("build failed");
+
+            // then
+            assert_eq!(
+                res.dest_wasm
+                    .expect("`dest_wasm` does not exist")
+                    .file_name(),
+                Some(OsStr::new("some_lib_name.wasm"))
+            );
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    pub fn debug_mode_must_be_compatible() {
+        let _ =
+            assert_debug_mode_supported(&Version::parse("3.0.0-rc4").expect("parsing must work"))
+                .expect("debug mode must be compatible");
+        let _ =
+            assert_debug_mode_supported(&Version::parse("4.0.0-rc1").expect("parsing must work"))
+                .expect("debug mode must be compatible");
+        let _ = assert_debug_mode_supported(&Version::parse("5.0.0").expect("parsing must work"))
+            .expect("debug mode must be compatible");
+    }
+
+    #[test]
+    pub fn debug_mode_must_be_incompatible() {
+        let res =
+            assert_debug_mode_supported(&Version::parse("3.0.0-rc3").expect("parsing must work"))
+                .expect_err("assertion must fail");
+        assert_eq!(
+            res.to_string(),
+            "Building the contract in debug mode requires an ink! version newer than `3.0.0-rc3`!"
+        );
+    }
+
+    #[test]
+    pub fn ink_version_must_be_compatible_with_cargo_contract() {
+        let version = |version| Version::parse(version).expect("parsing must work");
+
+        check_ink_compatibility(&version("3.0.0-rc9"), &version("0.18.0"))
+            .expect("ink! 3 must be supported by cargo-contract 0.18");
+        check_ink_compatibility(&version("4.2.0"), &version("3.2.0"))
+            .expect("ink! 4 must be supported by cargo-contract 3");
+
+        let res = check_ink_compatibility(&version("5.0.0"), &version("0.18.0"))
+            .expect_err("assertion must fail");
+        assert!(res
+            .to_string()
+            .starts_with("ink! 5.x requires cargo-contract >=4.0.0, <5.0.0"));
+        assert!(check_ink_compatibility(&version("2.1.0"), &version("0.18.0")).is_err());
+        assert!(check_ink_compatibility(&version("9.0.0"), &version("0.18.0")).is_err());
+    }
+
+    #[test]
+    fn building_template_in_debug_mode_must_work() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            let args = crate::cmd::build::ExecuteArgs {
+                manifest_path,
+                build_mode: BuildMode::Debug,
+                ..Default::default()
+            };
+
+            // when
+            let res = super::execute(args);
+
+            // then
+            assert!(res.is_ok(), "building template in debug mode failed!");
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn building_template_in_release_mode_must_work() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            let args = crate::cmd::build::ExecuteArgs {
+                manifest_path,
+                build_mode: BuildMode::Release,
+                ..Default::default()
+            };
+
+            // when
+            let res = super::execute(args);
+
+            // then
+            assert!(res.is_ok(), "building template in release mode failed!");
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn building_contract_with_source_file_in_subfolder_must_work() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            let path = manifest_path.directory().expect("dir must exist");
+            let old_lib_path = path.join(Path::new("lib.rs"));
+            let new_lib_path = path.join(Path::new("srcfoo")).join(Path::new("lib.rs"));
+            let new_dir_path = path.join(Path::new("srcfoo"));
+            std::fs::create_dir_all(new_dir_path).expect("creating dir must work");
+            std::fs::rename(old_lib_path, new_lib_path).expect("moving file must work");
+
+            let mut manifest =
+                Manifest::new(manifest_path.clone()).expect("creating manifest must work");
+            manifest
+                .set_lib_path("srcfoo/lib.rs")
+                .expect("setting lib path must work");
+            manifest.write(&manifest_path).expect("writing must work");
+
+            let args = crate::cmd::build::ExecuteArgs {
+                manifest_path,
+                build_artifact: BuildArtifacts::CheckOnly,
+                ..Default::default()
+            };
+
+            // when
+            let res = super::execute(args);
+
+            // then
+            assert!(res.is_ok(), "building contract failed!");
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn keep_debug_symbols_in_debug_mode() {
+        with_new_contract_project(|manifest_path| {
+            let args = crate::cmd::build::ExecuteArgs {
+                manifest_path,
+                build_mode: BuildMode::Debug,
+                build_artifact: BuildArtifacts::CodeOnly,
+                keep_debug_symbols: true,
+                ..Default::default()
+            };
+
+            let res = super::execute(args).expect("build failed");
+
+            // we specified that debug symbols should be kept
+            assert!(has_debug_symbols(&res.dest_wasm.unwrap()));
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn keep_debug_symbols_in_release_mode() {
+        with_new_contract_project(|manifest_path| {
+            let args = crate::cmd::build::ExecuteArgs {
+                manifest_path,
+                build_mode: BuildMode::Release,
+                build_artifact: BuildArtifacts::CodeOnly,
+                keep_debug_symbols: true,
+                ..Default::default()
+            };
+
+            let res = super::execute(args).expect("build failed");
+
+            // we specified that debug symbols should be kept
+            assert!(has_debug_symbols(&res.dest_wasm.unwrap()));
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn build_with_json_output_works() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            let args = crate::cmd::build::ExecuteArgs {
+                manifest_path,
+                output_type: OutputType::Json,
+                ..Default::default()
+            };
+
+            // when
+            let res = super::execute(args).expect("build failed");
+
+            // then
+            assert!(res.serialize_json().is_ok());
+            Ok(())
+        })
+    }
+
+    // This test has to be ignored until the next ink! rc.
+    // Before that we don't have the `__ink_dylint_…` markers available
+    // to actually run `dylint`.
+    #[test]
+    #[ignore]
+    fn dylint_must_find_issue() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            let contract = r#"
+            #![cfg_attr(not(feature = "std"), no_std)]
+
+            use ink_lang as ink;
+
+            #[ink::contract]
+            mod fail_mapping_01 {
+                use ink_storage::{traits::SpreadAllocate, Mapping};
+
+                #[ink(storage)]
+                #[derive(SpreadAllocate)]
+                pub struct MyContract {
+                    balances: Mapping<AccountId, Balance>,
+                }
+
+                impl MyContract {
+                    #[ink(constructor)]
+                    pub fn new() -> Self {
+                        Self {
+                            balances: Default::default(),
+                        }
+                    }
+
+                    /// Returns the total token supply.
+                    #[ink(message)]
+                    pub fn get(&self) {
+                        // ...
+                    }
+                }
+            }"#;
+            let project_dir = manifest_path.directory().expect("directory must exist");
+            let lib = project_dir.join("lib.rs");
+            std::fs::write(&lib, contract)?;
+
+            let args = crate::cmd::build::ExecuteArgs {
+                manifest_path,
+                build_artifact: BuildArtifacts::CheckOnly,
+                ..Default::default()
+            };
+
+            // when
+            let res = super::execute(args);
+
+            // then
+            match res {
+                Err(err) => {
+                    eprintln!("err: {:?}", err);
+                    assert!(err.to_string().contains(
+                        "help: add an `initialize_contract` function in this constructor"
+                    ));
+                }
+                _ => panic!("build succeeded, but must fail!"),
+            };
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn missing_cargo_dylint_installation_must_be_detected() {
+        with_new_contract_project(|manifest_path| {
+            // given
+            let manifest_dir = manifest_path.directory().unwrap();
+
+            // mock existing `dylint-link` binary
+            create_executable(&manifest_dir.join("dylint-link"), "#!/bin/sh\nexit 0");
+
+            // mock a non-existing `cargo dylint` installation.
+            create_executable(&manifest_dir.join("cargo"), "#!/bin/sh\nexit 1");
+
+            // when
+            let args = crate::cmd::build::ExecuteArgs {
+                manifest_path,
+                ..Default::default()
+            };
+            let res = super::execute(args).map(|_| ()).unwrap_err();
+
+            // then
+            assert!(format!("{:?}", res).contains("cargo-dylint was not found!"));
+
+            Ok(())
+        })
+    }
+}
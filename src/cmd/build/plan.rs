@@ -0,0 +1,361 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    binaryen, dylint_args,
+    hooks::{self, Hook},
+    target_rustflags, wasm_cargo_args, wasm_cargo_env, wasm_opt_args, ExecuteArgs, WasmOpt,
+};
+use crate::{
+    cmd::metadata::metadata_gen_args, crate_metadata::CrateMetadata, BuildArtifacts, BuildMode,
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// The steps a build would execute, determined without executing any of them.
+///
+/// This allows build systems such as Bazel or Nix to wrap the build, or to audit what a build
+/// does before running it.
+#[derive(Debug, Serialize)]
+pub struct BuildPlan {
+    /// The manifest of the contract which is built.
+    pub manifest_path: PathBuf,
+    /// The directory all artifacts are written to.
+    pub target_directory: PathBuf,
+    pub build_mode: BuildMode,
+    pub build_artifact: BuildArtifacts,
+    /// The steps of the build in the order in which they are executed.
+    pub steps: Vec<BuildStep>,
+    /// The final artifacts of the build.
+    pub artifacts: Vec<PathBuf>,
+}
+
+/// A single step of a build.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BuildStep {
+    /// An external program is invoked.
+    Command {
+        description: String,
+        program: String,
+        args: Vec<String>,
+        /// Environment variables which are set, or removed if the value is `null`.
+        env: BTreeMap<String, Option<String>>,
+        /// If `true` the command is executed in a temporary workspace containing an amended copy
+        /// of the contract's manifest, the manifest path in `args` then refers to that copy.
+        temporary_workspace: bool,
+    },
+    /// A step which is performed by `cargo-contract` itself.
+    Internal {
+        description: String,
+        inputs: Vec<PathBuf>,
+        outputs: Vec<PathBuf>,
+    },
+}
+
+impl BuildPlan {
+    fn new(
+        crate_metadata: &CrateMetadata,
+        args: &ExecuteArgs,
+        steps: Vec<BuildStep>,
+        artifacts: Vec<PathBuf>,
+    ) -> Self {
+        Self {
+            manifest_path: crate_metadata.manifest_path.as_ref().to_path_buf(),
+            target_directory: crate_metadata.target_directory.clone(),
+            build_mode: args.build_mode,
+            build_artifact: args.build_artifact,
+            steps,
+            artifacts,
+        }
+    }
+
+    /// Returns the plan serialized as JSON.
+    pub fn serialize_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// A step of a build, as executed by [`super::execute`] and described by [`plan`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Step {
+    /// Runs the command configured for the hook, if any.
+    Hook(Hook),
+    Lint,
+    /// `cargo check` for the Wasm target.
+    CargoCheck,
+    /// `cargo build` for the Wasm target.
+    CargoBuild,
+    PostProcess,
+    Optimize,
+    /// Generates the metadata and the bundle.
+    Metadata,
+}
+
+/// Returns the steps of a build of the `build_artifact`, in the order in which they are executed.
+pub(super) fn steps(build_artifact: BuildArtifacts) -> Vec<Step> {
+    let mut steps = vec![Step::Hook(Hook::PreBuild), Step::Lint];
+    if build_artifact == BuildArtifacts::CheckOnly {
+        steps.push(Step::CargoCheck);
+        return steps;
+    }
+    steps.extend([
+        Step::CargoBuild,
+        Step::PostProcess,
+        Step::Hook(Hook::PostBuild),
+        Step::Optimize,
+        Step::Hook(Hook::PostOptimize),
+    ]);
+    if build_artifact == BuildArtifacts::All {
+        steps.push(Step::Metadata);
+    }
+    steps
+}
+
+/// Determine the steps a build with the given `args` would execute.
+pub(crate) fn plan(args: &ExecuteArgs) -> Result<BuildPlan> {
+    let mut crate_metadata = CrateMetadata::collect_with(&args.manifest_path, &args.cargo_flags)?;
+    crate_metadata.set_wasm_target(args.unstable_flags.wasm_target());
+    let temporary_workspace = !args.unstable_flags.original_manifest;
+    let mut dest_optimized = crate_metadata.dest_wasm.clone();
+    dest_optimized.set_file_name(format!(
+        "{}-opt.wasm",
+        crate_metadata.contract_artifact_name
+    ));
+    let dest_bundle = crate_metadata.target_directory.join(format!(
+        "{}.contract",
+        crate_metadata.contract_artifact_name
+    ));
+
+    let mut build_steps = Vec::new();
+    let mut artifacts = Vec::new();
+    for step in steps(args.build_artifact) {
+        match step {
+            Step::Hook(hook) => build_steps.extend(hook_step(&crate_metadata, hook)),
+            Step::Lint => build_steps.push(lint_step(&crate_metadata, args)?),
+            Step::CargoCheck => build_steps.push(cargo_wasm_step(
+                &crate_metadata,
+                "check",
+                BuildMode::Release,
+                args,
+                temporary_workspace,
+            )?),
+            Step::CargoBuild => build_steps.push(cargo_wasm_step(
+                &crate_metadata,
+                "build",
+                args.build_mode,
+                args,
+                temporary_workspace,
+            )?),
+            Step::PostProcess => build_steps.push(BuildStep::Internal {
+                description: "Post processing wasm file".to_string(),
+                inputs: vec![crate_metadata.original_wasm.clone()],
+                outputs: vec![crate_metadata.dest_wasm.clone()],
+            }),
+            Step::Optimize => {
+                build_steps.push(wasm_opt_step(
+                    args,
+                    &crate_metadata.dest_wasm,
+                    &dest_optimized,
+                    crate_metadata.wasm_opt_version,
+                )?);
+                artifacts.push(crate_metadata.dest_wasm.clone());
+            }
+            Step::Metadata => {
+                let mut cargo_args = vec!["run".to_string()];
+                cargo_args.extend(metadata_gen_args(
+                    &crate_metadata.manifest_path,
+                    &crate_metadata.target_directory,
+                    &crate_metadata.root_package.name,
+                    &args.cargo_flags,
+                )?);
+                build_steps.push(BuildStep::Command {
+                    description: "Generating metadata".to_string(),
+                    program: "cargo".to_string(),
+                    args: cargo_args,
+                    env: env_map(args.cargo_flags.env()),
+                    temporary_workspace,
+                });
+                build_steps.push(BuildStep::Internal {
+                    description: "Generating bundle".to_string(),
+                    inputs: vec![
+                        crate_metadata.dest_wasm.clone(),
+                        crate_metadata.metadata_path(),
+                    ],
+                    outputs: vec![dest_bundle.clone()],
+                });
+                artifacts.push(crate_metadata.metadata_path());
+                artifacts.push(dest_bundle.clone());
+            }
+        }
+    }
+
+    Ok(BuildPlan::new(
+        &crate_metadata,
+        args,
+        build_steps,
+        artifacts,
+    ))
+}
+
+/// Converts the environment variables as passed to `util::invoke_cargo` into a map.
+fn env_map(env: Vec<(&str, Option<&str>)>) -> BTreeMap<String, Option<String>> {
+    env.into_iter()
+        .map(|(key, value)| (key.to_string(), value.map(ToString::to_string)))
+        .collect()
+}
+
+/// Returns the step for `hook`, if a command is configured for it.
+///
+/// The `CARGO_CONTRACT_*` environment variables which are set for hooks are not listed.
+fn hook_step(crate_metadata: &CrateMetadata, hook: Hook) -> Option<BuildStep> {
+    let command = crate_metadata.hooks.command(hook)?;
+    let (shell, shell_arg) = hooks::shell();
+    Some(BuildStep::Command {
+        description: format!("Running `{}` hook", hook),
+        program: shell.to_string(),
+        args: vec![shell_arg.to_string(), command.to_string()],
+        env: BTreeMap::new(),
+        temporary_workspace: false,
+    })
+}
+
+fn lint_step(crate_metadata: &CrateMetadata, execute_args: &ExecuteArgs) -> Result<BuildStep> {
+    let mut args = vec!["dylint".to_string()];
+    args.extend(dylint_args(crate_metadata)?);
+    let mut env = vec![
+        ("DYLINT_LIBRARY_PATH", Some("<temporary directory>")),
+        ("CARGO_TARGET_DIR", None),
+        ("RUSTC_WRAPPER", None),
+    ];
+    env.extend(execute_args.cargo_flags.env());
+    Ok(BuildStep::Command {
+        description: "Checking ink! linting rules".to_string(),
+        program: "cargo".to_string(),
+        args,
+        env: env_map(env),
+        temporary_workspace: false,
+    })
+}
+
+fn cargo_wasm_step(
+    crate_metadata: &CrateMetadata,
+    command: &str,
+    build_mode: BuildMode,
+    args: &ExecuteArgs,
+    temporary_workspace: bool,
+) -> Result<BuildStep> {
+    let mut cargo_args = vec![command.to_string()];
+    cargo_args.extend(wasm_cargo_args(
+        &crate_metadata.manifest_path,
+        crate_metadata,
+        build_mode,
+        &args.cargo_flags,
+    )?);
+    let rustflags = target_rustflags(crate_metadata, &args.cargo_flags)?;
+    let env = wasm_cargo_env(&rustflags, crate_metadata, build_mode, &args.cargo_flags);
+    Ok(BuildStep::Command {
+        description: format!("Executing `cargo {}`", command),
+        program: "cargo".to_string(),
+        args: cargo_args,
+        env: env_map(env),
+        temporary_workspace,
+    })
+}
+
+/// Returns the step optimizing `dest_wasm`, which is executed internally if the bundled
+/// `wasm-opt` is used.
+///
+/// A pinned `wasm-opt` is not downloaded for the plan, the path it is cached at is shown instead.
+fn wasm_opt_step(
+    args: &ExecuteArgs,
+    dest_wasm: &Path,
+    dest_optimized: &Path,
+    wasm_opt_version: Option<u32>,
+) -> Result<BuildStep> {
+    let description = "Optimizing wasm file".to_string();
+    let pinned = match (
+        &args.wasm_opt_path,
+        args.wasm_opt_version.or(wasm_opt_version),
+    ) {
+        (None, Some(version)) => Some(binaryen::wasm_opt_path(version)?),
+        _ => None,
+    };
+    let resolved = match pinned {
+        Some(path) => Ok(WasmOpt::External(path)),
+        None => WasmOpt::resolve(args.wasm_opt_path.as_deref()),
+    };
+    let program = match resolved {
+        #[cfg(feature = "wasm-opt")]
+        Ok(WasmOpt::Bundled) => {
+            return Ok(BuildStep::Internal {
+                description,
+                inputs: vec![dest_wasm.to_path_buf()],
+                outputs: vec![dest_optimized.to_path_buf()],
+            })
+        }
+        Ok(WasmOpt::External(path)) => path.display().to_string(),
+        // the plan can be printed without `wasm-opt` being installed
+        Err(_) if args.wasm_opt_path.is_none() => "wasm-opt".to_string(),
+        Err(err) => return Err(err),
+    };
+    Ok(BuildStep::Command {
+        description,
+        program,
+        args: wasm_opt_args(
+            dest_wasm.as_os_str(),
+            dest_optimized.as_os_str(),
+            args.optimization_passes,
+            args.keep_debug_symbols,
+            args.unstable_flags.wasm_target(),
+        )
+        .iter()
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect(),
+        env: BTreeMap::new(),
+        temporary_workspace: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hooks_run_around_the_steps_they_are_named_after() {
+        assert_eq!(
+            steps(BuildArtifacts::CheckOnly),
+            [Step::Hook(Hook::PreBuild), Step::Lint, Step::CargoCheck]
+        );
+        assert_eq!(
+            steps(BuildArtifacts::All),
+            [
+                Step::Hook(Hook::PreBuild),
+                Step::Lint,
+                Step::CargoBuild,
+                Step::PostProcess,
+                Step::Hook(Hook::PostBuild),
+                Step::Optimize,
+                Step::Hook(Hook::PostOptimize),
+                Step::Metadata,
+            ]
+        );
+    }
+}
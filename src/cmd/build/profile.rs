@@ -0,0 +1,136 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{workspace::Manifest, OptimizationPasses};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{collections::BTreeMap, str::FromStr};
+use url::Url;
+
+/// The settings for building and deploying the contract to a specific chain, defined in
+/// `[package.metadata.contract.profiles.<name>]` of the contract's `Cargo.toml`, e.g.
+///
+/// ```toml
+/// [package.metadata.contract.profiles.astar]
+/// endpoint = "wss://rpc.shibuya.astar.network"
+/// max-code-size = 131072
+/// optimization-passes = "z"
+/// features = ["astar"]
+/// env = { ASTAR_RUNTIME = "shibuya" }
+/// ```
+///
+/// Chain specific environment types are selected via `features`, e.g. by using
+/// `#[cfg_attr(feature = "astar", ink_lang::contract(env = AstarEnvironment))]`.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ContractProfile {
+    /// Websockets url of a node of the chain, used by `upload`, `instantiate` and `call` when the
+    /// profile is selected with `--network`.
+    pub endpoint: Option<Url>,
+    /// The maximum size in bytes of the optimized Wasm accepted by the chain.
+    pub max_code_size: Option<u64>,
+    /// Number of optimization passes, passed as an argument to `wasm-opt`.
+    optimization_passes: Option<toml::Value>,
+    /// Do not remove symbols (Wasm name section) when optimizing.
+    #[serde(default)]
+    pub keep_debug_symbols: bool,
+    /// The features of the contract to enable.
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Environment variables which are set for every `cargo` invocation.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl ContractProfile {
+    /// Load the profile with the given `name` from the contract's manifest.
+    pub fn load(manifest: &Manifest, name: &str) -> Result<Self> {
+        Self::find(manifest, name)?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Profile '{}' not found in `[package.metadata.contract.profiles]`. \
+                Known profiles: [{}]",
+                name,
+                manifest.get_contract_profile_names().join(", ")
+            )
+        })
+    }
+
+    /// Returns the profile with the given `name` from the contract's manifest, if it exists.
+    pub fn find(manifest: &Manifest, name: &str) -> Result<Option<Self>> {
+        manifest
+            .get_contract_profile(name)
+            .cloned()
+            .map(|profile| profile.try_into())
+            .transpose()
+            .context(format!(
+                "Invalid `[package.metadata.contract.profiles.{}]`",
+                name
+            ))
+    }
+
+    /// Returns the number of optimization passes of the profile, if they are specified.
+    pub fn optimization_passes(&self) -> Result<Option<OptimizationPasses>> {
+        self.optimization_passes
+            .as_ref()
+            .map(|passes| OptimizationPasses::from_str(&passes.to_string()))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{util::tests::with_tmp_dir, workspace::ManifestPath};
+
+    #[test]
+    fn profile_is_loaded_from_manifest() {
+        with_tmp_dir(|path| {
+            let manifest_path = path.join("Cargo.toml");
+            std::fs::write(
+                &manifest_path,
+                r#"
+                [package]
+                name = "flipper"
+                version = "0.1.0"
+
+                [package.metadata.contract.profiles.astar]
+                endpoint = "wss://rpc.shibuya.astar.network"
+                max-code-size = 131072
+                optimization-passes = 3
+                features = ["astar"]
+                env = { ASTAR_RUNTIME = "shibuya" }
+                "#,
+            )?;
+            let manifest = Manifest::new(ManifestPath::new(&manifest_path)?)?;
+
+            let profile = ContractProfile::load(&manifest, "astar")?;
+
+            assert_eq!(
+                profile.endpoint.as_ref().map(Url::as_str),
+                Some("wss://rpc.shibuya.astar.network/")
+            );
+            assert_eq!(profile.max_code_size, Some(131072));
+            assert_eq!(
+                profile.optimization_passes()?,
+                Some(OptimizationPasses::Three)
+            );
+            assert_eq!(profile.features, vec!["astar".to_string()]);
+            assert_eq!(profile.env["ASTAR_RUNTIME"], "shibuya");
+            assert!(ContractProfile::load(&manifest, "kusama").is_err());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Merging of the `RUSTFLAGS` of the user with the flags which are required for building a
+//! contract.
+
+use crate::CargoFlags;
+use anyhow::Result;
+
+/// The stack size of the contract in bytes, unless the user sets another one.
+const DEFAULT_STACK_SIZE: &str = "65536";
+
+/// Returns the `RUSTFLAGS` for building the contract's Wasm.
+///
+/// The flags of the user are taken from the `RUSTFLAGS` in the `env` of the contract profile, or
+/// else from the `CARGO_ENCODED_RUSTFLAGS` or `RUSTFLAGS` environment variable.
+pub(crate) fn wasm_rustflags(cargo_flags: &CargoFlags) -> Result<String> {
+    let profile_flags = cargo_flags
+        .env
+        .iter()
+        .find(|(key, _)| key == "RUSTFLAGS")
+        .map(|(_, value)| value.clone());
+    let user_flags = match profile_flags {
+        Some(flags) => split(&flags),
+        None => match std::env::var("CARGO_ENCODED_RUSTFLAGS") {
+            Ok(flags) if !flags.is_empty() => flags.split('\x1f').map(String::from).collect(),
+            _ => split(&std::env::var("RUSTFLAGS").unwrap_or_default()),
+        },
+    };
+    if !user_flags.is_empty() {
+        log::info!("Merging the user defined RUSTFLAGS {:?}", user_flags);
+    }
+    merge(&user_flags)
+}
+
+fn split(flags: &str) -> Vec<String> {
+    flags.split_whitespace().map(String::from).collect()
+}
+
+/// Merges the `user_flags` with the flags required for building a contract.
+///
+/// Flags which are required anyway are dropped and a stack size set by the user replaces the
+/// default one. Flags which would produce a Wasm that can not be deployed, or which override
+/// settings `cargo-contract` controls otherwise, result in an error.
+fn merge(user_flags: &[String]) -> Result<String> {
+    let mut stack_size = DEFAULT_STACK_SIZE.to_string();
+    let mut merged = Vec::new();
+    let mut flags = user_flags.iter();
+    while let Some(flag) = flags.next() {
+        let codegen = match flag.as_str() {
+            "-C" | "--codegen" => Some(flags.next().map(String::as_str).unwrap_or_default()),
+            flag => flag
+                .strip_prefix("--codegen=")
+                .or_else(|| flag.strip_prefix("-C")),
+        };
+        let option = match codegen {
+            Some(option) => option,
+            None => {
+                if flag.contains(char::is_whitespace) {
+                    anyhow::bail!(
+                        "The RUSTFLAGS argument {:?} contains whitespace, which can not be \
+                        passed on",
+                        flag
+                    )
+                }
+                merged.push(flag.clone());
+                continue;
+            }
+        };
+        let (key, value) = match option.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (option, None),
+        };
+        match (key, value) {
+            ("link-arg", Some(arg)) if arg.starts_with("-zstack-size=") => {
+                stack_size = arg.trim_start_matches("-zstack-size=").to_string();
+            }
+            ("link-arg", Some("--import-memory")) => (),
+            ("linker-plugin-lto", None | Some("yes" | "y" | "on" | "true")) => (),
+            ("linker-plugin-lto", Some(_)) => {
+                anyhow::bail!(
+                    "The RUSTFLAGS `-C {}` conflict with `-C linker-plugin-lto`, which is \
+                    required for building a contract",
+                    option
+                )
+            }
+            ("panic", _) => {
+                anyhow::bail!(
+                    "The RUSTFLAGS `-C {}` conflict with the panic strategy of the build, use \
+                    `--panic` or `panic` in `[package.metadata.contract]` instead",
+                    option
+                )
+            }
+            ("opt-level", _) => {
+                anyhow::bail!(
+                    "The RUSTFLAGS `-C {}` override the `opt-level` of the `[profile.release]`, \
+                    set it there instead",
+                    option
+                )
+            }
+            _ => merged.push(format!("-C{}", option)),
+        }
+    }
+
+    let mut rustflags = vec![
+        format!("-C link-arg=-zstack-size={}", stack_size),
+        "-C link-arg=--import-memory".to_string(),
+        "-Clinker-plugin-lto".to_string(),
+    ];
+    rustflags.extend(merged);
+    Ok(rustflags.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(flags: &str) -> Vec<String> {
+        split(flags)
+    }
+
+    #[test]
+    fn user_flags_are_merged_with_required_flags() {
+        assert_eq!(
+            merge(&[]).unwrap(),
+            "-C link-arg=-zstack-size=65536 -C link-arg=--import-memory -Clinker-plugin-lto"
+        );
+        assert_eq!(
+            merge(&flags(
+                "-C target-feature=+sign-ext -Clink-arg=--import-memory --cfg foo \
+                -C link-arg=-zstack-size=32768"
+            ))
+            .unwrap(),
+            "-C link-arg=-zstack-size=32768 -C link-arg=--import-memory -Clinker-plugin-lto \
+            -Ctarget-feature=+sign-ext --cfg foo"
+        );
+    }
+
+    #[test]
+    fn conflicting_user_flags_are_rejected() {
+        for conflicting in [
+            "-C panic=unwind",
+            "-Copt-level=0",
+            "--codegen=linker-plugin-lto=no",
+        ] {
+            assert!(merge(&flags(conflicting)).is_err(), "{}", conflicting);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The size budget of a contract, `max-size` in `[package.metadata.contract]`, and the analysis of
+//! what contributes to the size of its Wasm.
+
+use super::load_module;
+use crate::crate_metadata::CrateMetadata;
+use anyhow::{Context, Result};
+use parity_wasm::elements::{ImportCountType, Module};
+use std::{fs, path::Path};
+
+/// The number of contributors listed if the budget is exceeded.
+const TOP_CONTRIBUTORS: usize = 10;
+
+/// A function or the data of a Wasm module, and the number of bytes it takes up.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Contributor {
+    pub name: String,
+    pub size: usize,
+}
+
+/// Parses a size such as `120K`, `1MiB` or `4096`, in bytes.
+///
+/// The `K` and `M` suffixes, optionally followed by `B`, denote multiples of 1000, followed by
+/// `iB` they denote multiples of 1024.
+pub(crate) fn parse_size(size: &str) -> Result<u64> {
+    let size = size.trim();
+    let upper = size.to_ascii_uppercase();
+    let (digits, base) = match upper.strip_suffix("IB") {
+        Some(digits) => (digits, 1024),
+        None => (upper.trim_end_matches('B'), 1000),
+    };
+    let digits = digits.trim_end();
+    let (number, multiplier) = if let Some(number) = digits.strip_suffix('K') {
+        (number, base)
+    } else if let Some(number) = digits.strip_suffix('M') {
+        (number, base * base)
+    } else {
+        (digits, 1)
+    };
+    let number: u64 = number.trim().parse().context(format!(
+        "Invalid size `{}`, expected e.g. `120K`, `1M` or a number of bytes",
+        size
+    ))?;
+    Ok(number * multiplier)
+}
+
+/// Fails if the Wasm at `dest_wasm` exceeds the `max_size` in bytes, listing the functions
+/// contributing most to the size of the contract.
+///
+/// The functions are taken from the Wasm before the optimization, which still contains their
+/// names.
+pub(crate) fn assert_size_budget(
+    crate_metadata: &CrateMetadata,
+    dest_wasm: &Path,
+    max_size: u64,
+) -> Result<()> {
+    let size = fs::metadata(dest_wasm)?.len();
+    if size <= max_size {
+        return Ok(());
+    }
+    // a `wasm64` module can not be parsed, the budget is enforced nevertheless
+    let contributors = match load_module(&crate_metadata.original_wasm) {
+        Ok(module) => top_contributors(&module, TOP_CONTRIBUTORS)
+            .iter()
+            .map(|contributor| format!("  {:>8} bytes  {}", contributor.size, contributor.name))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Err(err) => format!("  The Wasm could not be analyzed: {:#}", err),
+    };
+    anyhow::bail!(
+        "The contract's code size of {} bytes exceeds the budget of {} bytes set by `max-size` in \
+         `[package.metadata.contract]`.\n\nThe largest contributors before the optimization:\n{}",
+        size,
+        max_size,
+        contributors
+    )
+}
+
+/// Returns the `count` largest function bodies of the `module`, together with its data segments,
+/// ordered by size.
+pub(crate) fn top_contributors(module: &Module, count: usize) -> Vec<Contributor> {
+    let module = module
+        .clone()
+        .parse_names()
+        .unwrap_or_else(|(_, module)| module);
+    let names = module
+        .names_section()
+        .and_then(|section| section.functions())
+        .map(|functions| functions.names());
+    let imported = module.import_count(ImportCountType::Function) as u32;
+    let bodies = module
+        .code_section()
+        .map(|section| section.bodies())
+        .unwrap_or_default();
+
+    let mut contributors = bodies
+        .iter()
+        .enumerate()
+        .map(|(index, body)| {
+            let function = imported + index as u32;
+            let name = names
+                .and_then(|names| names.get(function))
+                .map(|name| format!("{:#}", rustc_demangle::demangle(name)))
+                .unwrap_or_else(|| format!("function #{}", function));
+            let size = parity_wasm::serialize(body.clone())
+                .map(|bytes| bytes.len())
+                .unwrap_or_default();
+            Contributor { name, size }
+        })
+        .collect::<Vec<_>>();
+    let data_size = module
+        .data_section()
+        .map(|section| {
+            section
+                .entries()
+                .iter()
+                .map(|segment| segment.value().len())
+                .sum()
+        })
+        .unwrap_or_default();
+    if data_size > 0 {
+        contributors.push(Contributor {
+            name: "data segments".into(),
+            size: data_size,
+        });
+    }
+    contributors.sort_by(|a, b| b.size.cmp(&a.size));
+    contributors.truncate(count);
+    contributors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_are_parsed_and_contributors_ranked() {
+        assert_eq!(parse_size("120K").unwrap(), 120 * 1000);
+        assert_eq!(parse_size("1 MiB").unwrap(), 1024 * 1024);
+        assert_eq!(parse_size("1MB").unwrap(), 1000 * 1000);
+        assert_eq!(parse_size("64kb").unwrap(), 64 * 1000);
+        assert_eq!(parse_size("64KiB").unwrap(), 64 * 1024);
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert!(parse_size("big").is_err());
+
+        let wasm = wabt::Wat2Wasm::new()
+            .write_debug_names(true)
+            .convert(
+                r#"(module
+                (memory 1)
+                (func $small)
+                (func $large (result i32)
+                    i32.const 1 i32.const 2 i32.add i32.const 3 i32.add)
+                (data (i32.const 0) "abcdefgh"))"#,
+            )
+            .unwrap();
+        let module: Module = parity_wasm::deserialize_buffer(wasm.as_ref()).unwrap();
+        let contributors = top_contributors(&module, 2);
+        assert_eq!(contributors.len(), 2);
+        assert_eq!(contributors[0].name, "large");
+        assert_eq!(contributors[1].name, "data segments");
+        assert_eq!(contributors[1].size, 8);
+    }
+}
@@ -0,0 +1,400 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::extrinsics::network_profile;
+use crate::{
+    config::Config, crate_metadata::CrateMetadata, name_value_println, workspace::ManifestPath,
+};
+use anyhow::{Context, Result};
+use heck::ToShoutySnakeCase as _;
+use std::{
+    convert::TryFrom,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// The toolchain of the pipelines if neither `--toolchain` nor the `[ci]` configuration set one.
+const DEFAULT_TOOLCHAIN: &str = "nightly";
+
+/// Generate a CI pipeline for the contract project.
+///
+/// The pipeline lints, tests and builds the contract and keeps the contract bundle as an
+/// artifact. On tagged commits the code of the contract is uploaded to every network, signed by
+/// the secret key URI in the `<NETWORK>_SURI` secret.
+///
+/// The toolchain and networks default to the `[ci]` section of the `cargo-contract`
+/// configuration file.
+#[derive(Debug, clap::Args)]
+#[clap(name = "init-ci")]
+pub struct InitCiCommand {
+    /// Path to the `Cargo.toml` of the contract
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The CI service to generate the pipeline for.
+    #[clap(long, arg_enum, default_value = "github")]
+    provider: CiProvider,
+    /// The Rust toolchain the contract is built with, e.g. `nightly-2022-06-30`.
+    #[clap(long)]
+    toolchain: Option<String>,
+    /// The network profiles the code of the contract is uploaded to on tagged commits.
+    #[clap(long = "network", value_name = "NAME", use_delimiter = true)]
+    networks: Vec<String>,
+    /// Overwrite an existing pipeline.
+    #[clap(long)]
+    force: bool,
+}
+
+/// The CI services pipelines can be generated for.
+#[derive(Debug, Clone, Copy, clap::ArgEnum)]
+pub enum CiProvider {
+    /// A GitHub Actions workflow in `.github/workflows/contract.yml`.
+    Github,
+    /// A GitLab CI pipeline in `.gitlab-ci.yml`.
+    Gitlab,
+}
+
+impl InitCiCommand {
+    pub fn exec(&self) -> Result<()> {
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        let crate_metadata = CrateMetadata::collect(&manifest_path)?;
+        let config = Config::load()?;
+
+        let project_dir = manifest_path.absolute_directory()?;
+        let root = repository_root(&project_dir);
+        let manifest = project_dir
+            .join("Cargo.toml")
+            .strip_prefix(&root)
+            .expect("the project directory is inside of the repository")
+            .to_path_buf();
+        // the target directory of cargo is set to `target` in the pipelines
+        let artifacts = crate_metadata
+            .target_directory
+            .strip_prefix(crate_metadata.cargo_meta.target_directory.as_std_path())
+            .context("The contract artifacts are outside of the target directory")?;
+
+        let network_names = match self.networks.is_empty() {
+            true => &config.ci.networks,
+            false => &self.networks,
+        };
+        let networks = network_names
+            .iter()
+            .map(|name| {
+                let profile = network_profile(Some(name), self.manifest_path.as_ref())?
+                    .expect("a network name is given");
+                Ok((name.clone(), profile.url.to_string()))
+            })
+            .collect::<Result<_>>()?;
+
+        let pipeline = Pipeline {
+            toolchain: self
+                .toolchain
+                .clone()
+                .or_else(|| config.ci.toolchain.clone())
+                .unwrap_or_else(|| DEFAULT_TOOLCHAIN.to_string()),
+            manifest_path: unix_path(&manifest),
+            artifacts: format!("target/{}", unix_path(artifacts)),
+            name: crate_metadata.contract_artifact_name.clone(),
+            networks,
+        };
+        let (path, contents) = match self.provider {
+            CiProvider::Github => (
+                root.join(".github").join("workflows").join("contract.yml"),
+                pipeline.github()?,
+            ),
+            CiProvider::Gitlab => (root.join(".gitlab-ci.yml"), pipeline.gitlab()?),
+        };
+
+        if path.exists() && !self.force {
+            anyhow::bail!(
+                "{} already exists, use --force to overwrite it",
+                path.display()
+            )
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(&path, contents).context(format!("Failed to write {}", path.display()))?;
+        name_value_println!("Generated", path.display().to_string());
+        Ok(())
+    }
+}
+
+/// Returns the root of the git repository containing `dir`, or `dir` if it is not part of one.
+fn repository_root(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+/// Returns the `path` with `/` separators, as used by the CI runners.
+fn unix_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The parameters of a generated pipeline.
+struct Pipeline {
+    toolchain: String,
+    /// Path of the `Cargo.toml` of the contract, relative to the repository root.
+    manifest_path: String,
+    /// Directory of the contract artifacts, relative to the repository root.
+    artifacts: String,
+    /// The name of the contract artifacts.
+    name: String,
+    /// The names and node urls of the networks the code is uploaded to.
+    networks: Vec<(String, String)>,
+}
+
+impl Pipeline {
+    fn install_commands(&self) -> Vec<String> {
+        vec![
+            format!(
+                "rustup toolchain install {} --profile minimal --component rust-src,rustfmt,clippy \
+                --target wasm32-unknown-unknown",
+                self.toolchain
+            ),
+            format!("rustup default {}", self.toolchain),
+            "cargo install cargo-dylint dylint-link".to_string(),
+            format!(
+                "cargo install cargo-contract --version {} --locked --force",
+                env!("CARGO_PKG_VERSION")
+            ),
+        ]
+    }
+
+    fn artifact_paths(&self) -> Vec<String> {
+        vec![
+            format!("{}/{}.contract", self.artifacts, self.name),
+            format!("{}/{}.wasm", self.artifacts, self.name),
+            format!("{}/metadata.json", self.artifacts),
+        ]
+    }
+
+    fn upload_command(&self, network: &str, url: &str) -> String {
+        format!(
+            "cargo contract upload --manifest-path {} --url {} --suri \"${}_SURI\" {}/{}.wasm",
+            self.manifest_path,
+            url,
+            network.to_shouty_snake_case(),
+            self.artifacts,
+            self.name
+        )
+    }
+
+    fn github(&self) -> Result<String> {
+        let mut out = String::new();
+        writeln!(
+            out,
+            r#"# Generated by `cargo contract init-ci`.
+name: contract
+
+on:
+  push:
+    branches: [main, master]
+    tags: ["v*"]
+  pull_request:
+
+env:
+  CARGO_TARGET_DIR: target
+  CARGO_TERM_COLOR: always
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v3
+      - name: Install toolchain and tools
+        run: |"#
+        )?;
+        for command in self.install_commands() {
+            writeln!(out, "          {}", command)?;
+        }
+        writeln!(
+            out,
+            r#"      - name: Lint
+        run: |
+          cargo fmt --manifest-path {manifest} -- --check
+          cargo clippy --manifest-path {manifest} -- -D warnings
+      - name: Test
+        run: cargo contract test --manifest-path {manifest}
+      - name: Build
+        run: cargo contract build --release --manifest-path {manifest}
+      - uses: actions/upload-artifact@v3
+        with:
+          name: {name}
+          path: |"#,
+            manifest = self.manifest_path,
+            name = self.name
+        )?;
+        for path in self.artifact_paths() {
+            writeln!(out, "            {}", path)?;
+        }
+
+        for (network, url) in &self.networks {
+            writeln!(
+                out,
+                r#"
+  upload-{network}:
+    needs: build
+    if: startsWith(github.ref, 'refs/tags/')
+    runs-on: ubuntu-latest
+    environment: {network}
+    steps:
+      - uses: actions/checkout@v3
+      - uses: actions/download-artifact@v3
+        with:
+          name: {name}
+          path: {artifacts}
+      - name: Install toolchain and tools
+        run: |"#,
+                network = network,
+                name = self.name,
+                artifacts = self.artifacts
+            )?;
+            for command in self.install_commands() {
+                writeln!(out, "          {}", command)?;
+            }
+            writeln!(
+                out,
+                r#"      - name: Upload
+        env:
+          {variable}_SURI: ${{{{ secrets.{variable}_SURI }}}}
+        run: {upload}"#,
+                variable = network.to_shouty_snake_case(),
+                upload = self.upload_command(network, url)
+            )?;
+        }
+        Ok(out)
+    }
+
+    fn gitlab(&self) -> Result<String> {
+        let mut out = String::new();
+        writeln!(
+            out,
+            r#"# Generated by `cargo contract init-ci`.
+stages:
+  - lint
+  - test
+  - build
+  - upload
+
+variables:
+  CARGO_TARGET_DIR: target
+
+default:
+  image: rust:latest
+  before_script:"#
+        )?;
+        for command in self.install_commands() {
+            writeln!(out, "    - {}", command)?;
+        }
+        writeln!(
+            out,
+            r#"
+lint:
+  stage: lint
+  script:
+    - cargo fmt --manifest-path {manifest} -- --check
+    - cargo clippy --manifest-path {manifest} -- -D warnings
+
+test:
+  stage: test
+  script:
+    - cargo contract test --manifest-path {manifest}
+
+build:
+  stage: build
+  script:
+    - cargo contract build --release --manifest-path {manifest}
+  artifacts:
+    name: {name}
+    paths:"#,
+            manifest = self.manifest_path,
+            name = self.name
+        )?;
+        for path in self.artifact_paths() {
+            writeln!(out, "      - {}", path)?;
+        }
+
+        for (network, url) in &self.networks {
+            writeln!(
+                out,
+                r#"
+upload-{network}:
+  stage: upload
+  needs: [build]
+  rules:
+    - if: $CI_COMMIT_TAG
+  environment: {network}
+  script:
+    - {upload}"#,
+                network = network,
+                upload = self.upload_command(network, url)
+            )?;
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipeline() -> Pipeline {
+        Pipeline {
+            toolchain: "nightly-2022-06-30".to_string(),
+            manifest_path: "flipper/Cargo.toml".to_string(),
+            artifacts: "target/ink".to_string(),
+            name: "flipper".to_string(),
+            networks: vec![(
+                "rococo".to_string(),
+                "wss://rococo-contracts-rpc.polkadot.io/".to_string(),
+            )],
+        }
+    }
+
+    #[test]
+    fn github_workflow_builds_and_uploads() {
+        let workflow = pipeline().github().unwrap();
+
+        assert!(workflow.contains("rustup default nightly-2022-06-30\n"));
+        assert!(workflow.contains(
+            "      - name: Build\n        \
+            run: cargo contract build --release --manifest-path flipper/Cargo.toml\n"
+        ));
+        assert!(workflow.contains("            target/ink/flipper.contract\n"));
+        assert!(workflow.contains("  upload-rococo:\n    needs: build\n"));
+        assert!(workflow.contains("          ROCOCO_SURI: ${{ secrets.ROCOCO_SURI }}\n"));
+        assert!(workflow.contains(
+            "run: cargo contract upload --manifest-path flipper/Cargo.toml \
+            --url wss://rococo-contracts-rpc.polkadot.io/ --suri \"$ROCOCO_SURI\" \
+            target/ink/flipper.wasm\n"
+        ));
+    }
+
+    #[test]
+    fn gitlab_pipeline_builds_and_uploads() {
+        let pipeline = pipeline().gitlab().unwrap();
+
+        assert!(pipeline.contains("    - rustup default nightly-2022-06-30\n"));
+        assert!(pipeline.contains("    - cargo contract test --manifest-path flipper/Cargo.toml\n"));
+        assert!(pipeline.contains("      - target/ink/metadata.json\n"));
+        assert!(pipeline.contains("upload-rococo:\n  stage: upload\n"));
+    }
+}
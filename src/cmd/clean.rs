@@ -0,0 +1,218 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+    crate_metadata::CrateMetadata,
+    name_value_println,
+    toolchain::WasmTarget,
+    workspace::{ManifestPath, KEPT_WORKSPACE_MARKER},
+};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The names of the directories of the Wasm targets in the `cargo` target directory.
+const WASM_TARGETS: [&str; 2] = [WasmTarget::Wasm32.triple(), WasmTarget::Wasm64.triple()];
+
+/// The `cargo` profile the Wasm of the contract is built with.
+const BUILD_PROFILE: &str = "release";
+
+/// The prefix of the temporary workspaces which are kept by `--keep-debug-artifacts`, e.g. for
+/// generating the metadata.
+const TEMP_WORKSPACE_PREFIX: &str = "cargo-contract_";
+
+/// Removes the artifacts generated by building the contract, without touching the `cargo` target
+/// directory shared with the other crates of the workspace.
+#[derive(Debug, clap::Args)]
+#[clap(name = "clean")]
+pub struct CleanCommand {
+    /// Path to the `Cargo.toml` of the contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// Remove the outputs of all `cargo` profiles, e.g. the build scripts built for the host,
+    /// instead of only the ones of the profile contracts are built with.
+    #[clap(long)]
+    all_profiles: bool,
+    /// Only list the paths which would be removed.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl CleanCommand {
+    pub fn exec(&self) -> Result<()> {
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        let crate_metadata = CrateMetadata::collect(&manifest_path)?;
+        let mut paths = artifact_paths(&crate_metadata.target_directory, self.all_profiles)?;
+        paths.extend(temp_workspaces(
+            &std::env::temp_dir(),
+            crate_metadata.root_package.manifest_path.as_std_path(),
+        )?);
+
+        let mut total_size = 0;
+        for path in &paths {
+            let size = disk_usage(path);
+            total_size += size;
+            if self.dry_run {
+                name_value_println!(
+                    "Would remove",
+                    format!("{} ({})", path.display(), mib(size))
+                );
+                continue;
+            }
+            let result = if path.is_dir() {
+                fs::remove_dir_all(path)
+            } else {
+                fs::remove_file(path)
+            };
+            result.context(format!("Failed to remove {}", path.display()))?;
+            name_value_println!("Removed", format!("{} ({})", path.display(), mib(size)));
+        }
+        if paths.is_empty() {
+            println!("{}", "Nothing to clean".bright_green().bold());
+        } else if !self.dry_run {
+            println!(
+                "{} {}",
+                "Freed".bright_green().bold(),
+                mib(total_size).bold()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Returns the paths in the `target/ink` directory of the contract to remove.
+///
+/// These are the artifacts of the contract, e.g. `<name>.contract`, and the outputs of `cargo`
+/// for the Wasm target, including its fingerprints. Only with `all_profiles` the outputs of other
+/// profiles than the one contracts are built with are removed, and with them the whole directory.
+fn artifact_paths(target_directory: &Path, all_profiles: bool) -> Result<Vec<PathBuf>> {
+    if !target_directory.exists() {
+        return Ok(Vec::new());
+    }
+    if all_profiles {
+        return Ok(vec![target_directory.to_path_buf()]);
+    }
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(target_directory)? {
+        let path = entry?.path();
+        if !path.is_dir() || path.ends_with("emit") {
+            paths.push(path);
+        }
+    }
+    for wasm_target in WASM_TARGETS {
+        let wasm_target = target_directory.join(wasm_target).join(BUILD_PROFILE);
+        if wasm_target.exists() {
+            paths.push(wasm_target);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Returns the temporary workspaces in `temp_dir` which were kept for inspection of the contract
+/// at `manifest_path`.
+///
+/// Only directories with the marker naming this manifest are returned, so that neither the
+/// workspaces of other contracts nor directories of other programs are touched.
+fn temp_workspaces(temp_dir: &Path, manifest_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(temp_dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if !entry.path().is_dir() || !name.to_string_lossy().starts_with(TEMP_WORKSPACE_PREFIX) {
+            continue;
+        }
+        let marker = fs::read_to_string(entry.path().join(KEPT_WORKSPACE_MARKER));
+        if matches!(marker, Ok(marker) if Path::new(&marker) == manifest_path) {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Returns the size of all files at `path` in bytes.
+fn disk_usage(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+fn mib(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn only_artifacts_of_the_build_profile_are_removed() {
+        with_tmp_dir(|target| {
+            let release = target.join(WASM_TARGETS[0]).join(BUILD_PROFILE);
+            fs::create_dir_all(release.join(".fingerprint"))?;
+            fs::create_dir_all(target.join(WASM_TARGETS[0]).join("debug"))?;
+            fs::create_dir_all(target.join("release").join("build"))?;
+            fs::create_dir_all(target.join("emit"))?;
+            fs::write(target.join("flipper.contract"), "")?;
+            fs::write(target.join("metadata.json"), "")?;
+
+            assert_eq!(
+                artifact_paths(target, false)?,
+                vec![
+                    target.join("emit"),
+                    target.join("flipper.contract"),
+                    target.join("metadata.json"),
+                    release,
+                ]
+            );
+            assert_eq!(artifact_paths(target, true)?, vec![target.to_path_buf()]);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn only_kept_workspaces_of_the_contract_are_removed() {
+        with_tmp_dir(|temp_dir| {
+            let manifest_path = Path::new("/contracts/flipper/Cargo.toml");
+            let kept = temp_dir.join("cargo-contract_flipper");
+            let other_contract = temp_dir.join("cargo-contract_erc20");
+            let unmarked = temp_dir.join("cargo-contract_unmarked");
+            for dir in [&kept, &other_contract, &unmarked] {
+                fs::create_dir(dir)?;
+            }
+            fs::write(
+                kept.join(KEPT_WORKSPACE_MARKER),
+                "/contracts/flipper/Cargo.toml",
+            )?;
+            fs::write(
+                other_contract.join(KEPT_WORKSPACE_MARKER),
+                "/contracts/erc20/Cargo.toml",
+            )?;
+
+            assert_eq!(temp_workspaces(temp_dir, manifest_path)?, vec![kept]);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,457 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Emits a Go package with a client of the contract on top of
+//! [`go-substrate-rpc-client`](https://github.com/centrifuge/go-substrate-rpc-client).
+//!
+//! The types used by the messages are declared as Go types which are SCALE encoded by the codec
+//! of the client. Enums are structs holding the index of the variant and a pointer to the fields
+//! of every variant, with custom `Encode` and `Decode` methods.
+
+use super::ir::{Contract, Fields, Message, Ty, TypeDecl, TypeKind};
+use anyhow::Result;
+use heck::{ToLowerCamelCase as _, ToSnakeCase as _, ToUpperCamelCase as _};
+use std::fmt::Write;
+
+const GSRPC: &str = "github.com/centrifuge/go-substrate-rpc-client/v4";
+
+pub(crate) fn emit(contract: &Contract) -> Result<String> {
+    let client = contract.name.to_upper_camel_case();
+    let has_enums = contract
+        .types
+        .iter()
+        .any(|decl| matches!(decl.kind, TypeKind::Enum(_)));
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Package {package} is a client for the `{name}` contract, generated from its \
+        metadata by `cargo contract typegen`.",
+        package = package(&contract.name),
+        name = contract.name
+    )?;
+    writeln!(out, "package {}\n", package(&contract.name))?;
+    writeln!(out, "import (")?;
+    writeln!(out, "\t\"encoding/json\"")?;
+    writeln!(out, "\t\"fmt\"\n")?;
+    writeln!(out, "\tgsrpc \"{}\"", GSRPC)?;
+    if has_enums {
+        writeln!(out, "\t\"{}/scale\"", GSRPC)?;
+    }
+    writeln!(out, "\t\"{}/types\"", GSRPC)?;
+    writeln!(out, "\t\"{}/types/codec\"", GSRPC)?;
+    writeln!(out, "\t\"github.com/vedhavyas/go-subkey/v2\"")?;
+    writeln!(out, ")")?;
+
+    for decl in &contract.types {
+        writeln!(out)?;
+        declaration(&mut out, decl)?;
+    }
+    for message in &contract.messages {
+        writeln!(out)?;
+        input(&mut out, message)?;
+    }
+
+    writeln!(out)?;
+    comments(&mut out, &contract.docs, "")?;
+    writeln!(
+        out,
+        r#"type {client} struct {{
+	api       *gsrpc.SubstrateAPI
+	Address   string
+	AccountID types.AccountID
+}}
+
+// New returns the client of the contract deployed at the SS58 `address`.
+func New(api *gsrpc.SubstrateAPI, address string) (*{client}, error) {{
+	_, publicKey, err := subkey.SS58Decode(address)
+	if err != nil {{
+		return nil, err
+	}}
+	accountID, err := types.NewAccountID(publicKey)
+	if err != nil {{
+		return nil, err
+	}}
+	return &{client}{{api: api, Address: address, AccountID: *accountID}}, nil
+}}"#,
+        client = client
+    )?;
+
+    for message in &contract.messages {
+        writeln!(out)?;
+        match message.mutates {
+            true => call(&mut out, &client, message)?,
+            false => read(&mut out, &client, message)?,
+        }
+    }
+
+    writeln!(
+        out,
+        r#"
+type callRequest struct {{
+	Origin              string  `json:"origin"`
+	Dest                string  `json:"dest"`
+	Value               uint64  `json:"value"`
+	GasLimit            uint64  `json:"gasLimit"`
+	StorageDepositLimit *uint64 `json:"storageDepositLimit"`
+	InputData           string  `json:"inputData"`
+}}
+
+// read dry runs a call of the contract by the SS58 `origin` and decodes its return value.
+func (c *{client}) read(origin string, input []byte, result interface{{}}) error {{
+	var response struct {{
+		Result struct {{
+			Ok *struct {{
+				Flags uint32 `json:"flags"`
+				Data  string `json:"data"`
+			}} `json:"Ok"`
+			Err json.RawMessage `json:"Err"`
+		}} `json:"result"`
+	}}
+	request := callRequest{{Origin: origin, Dest: c.Address, InputData: codec.HexEncodeToString(input)}}
+	if err := c.api.Client.Call(&response, "contracts_call", request); err != nil {{
+		return err
+	}}
+	if response.Result.Ok == nil {{
+		return fmt.Errorf("contract call failed: %s", response.Result.Err)
+	}}
+	if response.Result.Ok.Flags&1 != 0 {{
+		return fmt.Errorf("contract call reverted")
+	}}
+	return codec.DecodeFromHex(response.Result.Ok.Data, result)
+}}"#,
+        client = client
+    )?;
+    Ok(out)
+}
+
+/// Returns the name of the Go package, which is lowercase without underscores.
+fn package(contract: &str) -> String {
+    contract.to_snake_case().replace('_', "")
+}
+
+fn comments(out: &mut String, docs: &[String], indent: &str) -> Result<()> {
+    for line in docs {
+        writeln!(out, "{}// {}", indent, line)?;
+    }
+    Ok(())
+}
+
+fn declaration(out: &mut String, decl: &TypeDecl) -> Result<()> {
+    comments(out, &decl.docs, "")?;
+    let variants = match &decl.kind {
+        TypeKind::Struct(fields) => {
+            writeln!(out, "type {} {}", decl.name, fields_type(fields))?;
+            return Ok(());
+        }
+        TypeKind::Enum(variants) => variants,
+    };
+    writeln!(out, "type {} struct {{", decl.name)?;
+    writeln!(out, "\tVariant uint8")?;
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            writeln!(
+                out,
+                "\tAs{} *{}",
+                variant.name,
+                fields_type(&variant.fields)
+            )?;
+        }
+    }
+    writeln!(out, "}}\n")?;
+
+    writeln!(out, "const (")?;
+    for variant in variants {
+        comments(out, &variant.docs, "\t")?;
+        writeln!(
+            out,
+            "\t{}{} uint8 = {}",
+            decl.name, variant.name, variant.index
+        )?;
+    }
+    writeln!(out, ")\n")?;
+
+    writeln!(
+        out,
+        "func (v {}) Encode(encoder scale.Encoder) error {{",
+        decl.name
+    )?;
+    writeln!(
+        out,
+        "\tif err := encoder.PushByte(v.Variant); err != nil {{\n\t\treturn err\n\t}}"
+    )?;
+    writeln!(out, "\tswitch v.Variant {{")?;
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            writeln!(out, "\tcase {}{}:", decl.name, variant.name)?;
+            writeln!(out, "\t\treturn encoder.Encode(*v.As{})", variant.name)?;
+        }
+    }
+    writeln!(out, "\t}}\n\treturn nil\n}}\n")?;
+
+    writeln!(
+        out,
+        "func (v *{}) Decode(decoder scale.Decoder) error {{",
+        decl.name
+    )?;
+    writeln!(
+        out,
+        "\tvariant, err := decoder.ReadOneByte()\n\tif err != nil {{\n\t\treturn err\n\t}}"
+    )?;
+    writeln!(out, "\tv.Variant = variant\n\tswitch variant {{")?;
+    for variant in variants {
+        writeln!(out, "\tcase {}{}:", decl.name, variant.name)?;
+        match variant.fields {
+            Fields::Unit => writeln!(out, "\t\treturn nil")?,
+            _ => {
+                writeln!(
+                    out,
+                    "\t\tv.As{} = new({})",
+                    variant.name,
+                    fields_type(&variant.fields)
+                )?;
+                writeln!(out, "\t\treturn decoder.Decode(v.As{})", variant.name)?;
+            }
+        }
+    }
+    writeln!(out, "\t}}")?;
+    writeln!(
+        out,
+        "\treturn fmt.Errorf(\"unknown variant %d of {}\", variant)\n}}",
+        decl.name
+    )?;
+    Ok(())
+}
+
+/// Returns the type of the fields of a struct or enum variant.
+///
+/// A single unnamed field of an enum variant is represented by its type.
+fn fields_type(fields: &Fields) -> String {
+    match fields {
+        Fields::Unit => "struct{}".to_string(),
+        Fields::Unnamed(types) if types.len() == 1 => go_type(&types[0]),
+        Fields::Unnamed(types) => tuple_type(types),
+        Fields::Named(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(name, ty)| format!("{} {}", name.to_upper_camel_case(), go_type(ty)))
+                .collect::<Vec<_>>();
+            format!("struct {{ {} }}", fields.join("; "))
+        }
+    }
+}
+
+fn tuple_type(types: &[Ty]) -> String {
+    if types.is_empty() {
+        return "struct{}".to_string();
+    }
+    let fields = types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| format!("F{} {}", i, go_type(ty)))
+        .collect::<Vec<_>>();
+    format!("struct {{ {} }}", fields.join("; "))
+}
+
+fn go_type(ty: &Ty) -> String {
+    match ty {
+        Ty::Bool => "types.Bool".to_string(),
+        Ty::Char => "types.U32".to_string(),
+        Ty::Str => "types.Text".to_string(),
+        Ty::Uint(bits) => format!("types.U{}", bits),
+        Ty::Int(bits) => format!("types.I{}", bits),
+        Ty::Bytes => "types.Bytes".to_string(),
+        Ty::Sequence(item) => format!("[]{}", go_type(item)),
+        Ty::Array(item, len) => format!("[{}]{}", len, go_type(item)),
+        Ty::Tuple(items) => tuple_type(items),
+        Ty::Option(item) => format!("types.Option[{}]", go_type(item)),
+        Ty::AccountId => "types.AccountID".to_string(),
+        Ty::Hash => "types.Hash".to_string(),
+        Ty::Named(name) => name.clone(),
+    }
+}
+
+/// Returns the parameter name of an argument, keywords such as `type` get a trailing underscore.
+fn identifier(name: &str) -> String {
+    const KEYWORDS: [&str; 9] = [
+        "chan",
+        "default",
+        "func",
+        "go",
+        "interface",
+        "map",
+        "range",
+        "select",
+        "type",
+    ];
+    let name = name.to_lower_camel_case();
+    match KEYWORDS.contains(&name.as_str()) {
+        true => format!("{}_", name),
+        false => name,
+    }
+}
+
+fn params(message: &Message) -> Vec<String> {
+    message
+        .args
+        .iter()
+        .map(|(name, ty)| format!("{} {}", identifier(name), go_type(ty)))
+        .collect()
+}
+
+fn arg_names(message: &Message) -> String {
+    message
+        .args
+        .iter()
+        .map(|(name, _)| identifier(name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Writes the function returning the SCALE encoded input of the `message`, which is its selector
+/// followed by the arguments.
+fn input(out: &mut String, message: &Message) -> Result<()> {
+    let selector = message
+        .selector
+        .iter()
+        .map(|byte| format!("0x{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(", ");
+    writeln!(
+        out,
+        r#"// {name}Input returns the input of a call of `{label}`.
+func {name}Input({params}) ([]byte, error) {{
+	input := []byte{{{selector}}}
+	for _, arg := range []interface{{}}{{{args}}} {{
+		encoded, err := codec.Encode(arg)
+		if err != nil {{
+			return nil, err
+		}}
+		input = append(input, encoded...)
+	}}
+	return input, nil
+}}"#,
+        name = message.name.to_upper_camel_case(),
+        label = message.label,
+        params = params(message).join(", "),
+        selector = selector,
+        args = arg_names(message),
+    )?;
+    Ok(())
+}
+
+/// Writes the method returning the `Contracts.call` extrinsic of a mutating `message`.
+fn call(out: &mut String, client: &str, message: &Message) -> Result<()> {
+    let name = message.name.to_upper_camel_case();
+    let mut params = vec!["meta *types.Metadata".to_string()];
+    params.extend(self::params(message));
+    let value = match message.payable {
+        true => {
+            params.push("transferredValue types.UCompact".to_string());
+            "transferredValue"
+        }
+        false => "types.NewUCompactFromUInt(0)",
+    };
+    params.push("gasLimit types.UCompact".to_string());
+
+    comments(out, &message.docs, "")?;
+    writeln!(
+        out,
+        r#"// {name}Call returns the extrinsic calling `{label}`, which is to be signed and submitted.
+func (c *{client}) {name}Call({params}) (types.Call, error) {{
+	input, err := {name}Input({args})
+	if err != nil {{
+		return types.Call{{}}, err
+	}}
+	dest := types.MultiAddress{{IsID: true, AsID: c.AccountID}}
+	storageDepositLimit := types.NewEmptyOption[types.UCompact]()
+	return types.NewCall(meta, "Contracts.call", dest, {value}, gasLimit, storageDepositLimit, types.Bytes(input))
+}}"#,
+        name = name,
+        label = message.label,
+        client = client,
+        params = params.join(", "),
+        args = arg_names(message),
+        value = value,
+    )?;
+    Ok(())
+}
+
+/// Writes the method dry running a non-mutating `message` and returning its return value.
+fn read(out: &mut String, client: &str, message: &Message) -> Result<()> {
+    let name = message.name.to_upper_camel_case();
+    let mut params = vec!["origin string".to_string()];
+    params.extend(self::params(message));
+    let returns = message
+        .returns
+        .as_ref()
+        .map(go_type)
+        .unwrap_or_else(|| "struct{}".to_string());
+
+    comments(out, &message.docs, "")?;
+    writeln!(
+        out,
+        r#"func (c *{client}) {name}({params}) ({returns}, error) {{
+	var result {returns}
+	input, err := {name}Input({args})
+	if err != nil {{
+		return result, err
+	}}
+	err = c.read(origin, input, &result)
+	return result, err
+}}"#,
+        client = client,
+        name = name,
+        params = params.join(", "),
+        returns = returns,
+        args = arg_names(message),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::interface::tests::erc20_project;
+
+    #[test]
+    fn go_client_encodes_enums_and_calls_messages() {
+        let contract = Contract::from_project("erc20", &erc20_project()).unwrap();
+
+        let package = emit(&contract).unwrap();
+
+        assert!(package.contains("package erc20\n"));
+        assert!(package.contains(
+            r#"type Error struct {
+	Variant uint8
+	AsOther *types.Text
+}
+
+const (
+	ErrorInsufficientBalance uint8 = 0
+	ErrorOther uint8 = 1
+)"#
+        ));
+        assert!(package.contains(
+            "func (c *Erc20) TransferCall(meta *types.Metadata, to types.AccountID, \
+            value types.U128, transferredValue types.UCompact, gasLimit types.UCompact) \
+            (types.Call, error) {"
+        ));
+        assert!(
+            package.contains("func (c *Erc20) TotalSupply(origin string) (types.U128, error) {")
+        );
+    }
+}
@@ -0,0 +1,338 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The language independent representation of a contract's messages and types, from which the
+//! bindings of the different languages are emitted.
+
+use crate::cmd::interface::{message_name, EXTERNAL_CRATES};
+use anyhow::{Context, Result};
+use ink_metadata::InkProject;
+use scale_info::{form::PortableForm, Field, PortableRegistry, Type, TypeDef, TypeDefPrimitive};
+use std::{collections::BTreeMap, convert::TryInto};
+
+/// A contract with its messages and the types they use.
+#[derive(Debug)]
+pub(crate) struct Contract {
+    pub name: String,
+    pub docs: Vec<String>,
+    pub messages: Vec<Message>,
+    /// The declarations of all named types, ordered by their name.
+    pub types: Vec<TypeDecl>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Message {
+    /// The label of the message in the metadata, e.g. `Erc20::transfer`.
+    pub label: String,
+    /// The name of the message without the trait prefix, e.g. `transfer`.
+    pub name: String,
+    pub selector: [u8; 4],
+    pub args: Vec<(String, Ty)>,
+    pub returns: Option<Ty>,
+    pub mutates: bool,
+    pub payable: bool,
+    pub docs: Vec<String>,
+}
+
+/// A reference to a type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Ty {
+    Bool,
+    Char,
+    Str,
+    /// An unsigned integer with the number of bits.
+    Uint(u16),
+    /// A signed integer with the number of bits.
+    Int(u16),
+    /// A sequence of bytes, `Vec<u8>`.
+    Bytes,
+    Sequence(Box<Ty>),
+    Array(Box<Ty>, u32),
+    Tuple(Vec<Ty>),
+    Option(Box<Ty>),
+    AccountId,
+    Hash,
+    /// A struct or enum, whose declaration is part of [`Contract::types`].
+    Named(String),
+}
+
+/// The declaration of a struct or enum.
+#[derive(Debug)]
+pub(crate) struct TypeDecl {
+    pub name: String,
+    pub docs: Vec<String>,
+    pub kind: TypeKind,
+}
+
+#[derive(Debug)]
+pub(crate) enum TypeKind {
+    Struct(Fields),
+    Enum(Vec<Variant>),
+}
+
+#[derive(Debug)]
+pub(crate) struct Variant {
+    pub name: String,
+    pub index: u8,
+    pub fields: Fields,
+    pub docs: Vec<String>,
+}
+
+#[derive(Debug)]
+pub(crate) enum Fields {
+    Unit,
+    Named(Vec<(String, Ty)>),
+    Unnamed(Vec<Ty>),
+}
+
+impl Contract {
+    /// Resolves the messages of the `project` and the types they use.
+    pub fn from_project(name: &str, project: &InkProject) -> Result<Self> {
+        let mut resolver = Resolver {
+            registry: project.registry(),
+            names: BTreeMap::new(),
+            decls: BTreeMap::new(),
+        };
+        let messages = project
+            .spec()
+            .messages()
+            .iter()
+            .map(|message| {
+                let args = message
+                    .args()
+                    .iter()
+                    .map(|arg| Ok((arg.label().to_string(), resolver.ty(arg.ty().ty().id())?)))
+                    .collect::<Result<_>>()?;
+                let returns = message
+                    .return_type()
+                    .opt_type()
+                    .map(|ty| resolver.ty(ty.ty().id()))
+                    .transpose()?;
+                Ok(Message {
+                    label: message.label().to_string(),
+                    name: message_name(message.label()).to_string(),
+                    selector: message
+                        .selector()
+                        .to_bytes()
+                        .try_into()
+                        .context("A selector must have 4 bytes")?,
+                    args,
+                    returns,
+                    mutates: message.mutates(),
+                    payable: message.payable(),
+                    docs: trim_docs(message.docs()),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            name: name.to_string(),
+            docs: trim_docs(project.spec().docs()),
+            messages,
+            types: resolver.decls.into_values().collect(),
+        })
+    }
+}
+
+fn trim_docs(docs: &[String]) -> Vec<String> {
+    docs.iter().map(|line| line.trim().to_string()).collect()
+}
+
+struct Resolver<'a> {
+    registry: &'a PortableRegistry,
+    /// The names of the declared types by their id.
+    names: BTreeMap<u32, String>,
+    /// The declarations by their name.
+    decls: BTreeMap<String, TypeDecl>,
+}
+
+impl<'a> Resolver<'a> {
+    fn ty(&mut self, id: u32) -> Result<Ty> {
+        let ty = self
+            .registry
+            .resolve(id)
+            .context(format!("Type {} not found in the type registry", id))?;
+        let segments = ty.path().segments();
+        let resolved = match ty.type_def() {
+            TypeDef::Primitive(primitive) => primitive_ty(primitive),
+            TypeDef::Sequence(sequence) => match self.ty(sequence.type_param().id())? {
+                Ty::Uint(8) => Ty::Bytes,
+                item => Ty::Sequence(Box::new(item)),
+            },
+            TypeDef::Array(array) => {
+                Ty::Array(Box::new(self.ty(array.type_param().id())?), array.len())
+            }
+            TypeDef::Tuple(tuple) => Ty::Tuple(
+                tuple
+                    .fields()
+                    .iter()
+                    .map(|field| self.ty(field.id()))
+                    .collect::<Result<_>>()?,
+            ),
+            TypeDef::Compact(compact) => self.ty(compact.type_param().id())?,
+            TypeDef::BitSequence(_) => anyhow::bail!("Bit sequences are not supported"),
+            TypeDef::Composite(_) if is_ink_type(segments, "AccountId") => Ty::AccountId,
+            TypeDef::Composite(_) if is_ink_type(segments, "Hash") => Ty::Hash,
+            TypeDef::Variant(variant) if segments == ["Option"] => {
+                let some = variant
+                    .variants()
+                    .iter()
+                    .find(|variant| variant.name() == "Some")
+                    .and_then(|variant| variant.fields().first())
+                    .context("Option without a `Some` variant")?;
+                Ty::Option(Box::new(self.ty(some.ty().id())?))
+            }
+            TypeDef::Composite(_) | TypeDef::Variant(_) => Ty::Named(self.declare(id, ty)?),
+        };
+        Ok(resolved)
+    }
+
+    /// Declares the struct or enum `ty` and returns its name.
+    ///
+    /// Types defined by the contract keep their name, other types such as `Result` are
+    /// monomorphized, hence their name is suffixed with the type id, e.g. `Result5`.
+    fn declare(&mut self, id: u32, ty: &Type<PortableForm>) -> Result<String> {
+        if let Some(name) = self.names.get(&id) {
+            return Ok(name.clone());
+        }
+        let segments = ty.path().segments();
+        let ident = segments
+            .last()
+            .context(format!("Type {} has no path", id))?;
+        let is_custom = segments.len() > 1
+            && !EXTERNAL_CRATES.contains(&segments[0].as_str())
+            && ty.type_params().is_empty();
+        let name = match is_custom {
+            true => ident.clone(),
+            false => format!("{}{}", ident, id),
+        };
+        if self.decls.contains_key(&name) {
+            anyhow::bail!(
+                "The contract defines multiple types named {}, which is not supported",
+                name
+            )
+        }
+        // the name is registered first, so that recursive types terminate
+        self.names.insert(id, name.clone());
+        let kind = match ty.type_def() {
+            TypeDef::Composite(composite) => TypeKind::Struct(self.fields(composite.fields())?),
+            TypeDef::Variant(variant) => TypeKind::Enum(
+                variant
+                    .variants()
+                    .iter()
+                    .map(|variant| {
+                        Ok(Variant {
+                            name: variant.name().clone(),
+                            index: variant.index(),
+                            fields: self.fields(variant.fields())?,
+                            docs: trim_docs(variant.docs()),
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            _ => unreachable!("only composite and variant types are declared"),
+        };
+        self.decls.insert(
+            name.clone(),
+            TypeDecl {
+                name: name.clone(),
+                docs: trim_docs(ty.docs()),
+                kind,
+            },
+        );
+        Ok(name)
+    }
+
+    fn fields(&mut self, fields: &[Field<PortableForm>]) -> Result<Fields> {
+        if fields.is_empty() {
+            return Ok(Fields::Unit);
+        }
+        if fields.iter().all(|field| field.name().is_none()) {
+            return Ok(Fields::Unnamed(
+                fields
+                    .iter()
+                    .map(|field| self.ty(field.ty().id()))
+                    .collect::<Result<_>>()?,
+            ));
+        }
+        fields
+            .iter()
+            .map(|field| {
+                let name = field
+                    .name()
+                    .context("Mixed named and unnamed fields are not supported")?;
+                Ok((name.clone(), self.ty(field.ty().id())?))
+            })
+            .collect::<Result<_>>()
+            .map(Fields::Named)
+    }
+}
+
+/// Returns `true` if the path `segments` refer to the environment type `ident` of ink!.
+fn is_ink_type(segments: &[String], ident: &str) -> bool {
+    segments.first().map(String::as_str) == Some("ink_env")
+        && segments.last().map(String::as_str) == Some(ident)
+}
+
+fn primitive_ty(primitive: &TypeDefPrimitive) -> Ty {
+    match primitive {
+        TypeDefPrimitive::Bool => Ty::Bool,
+        TypeDefPrimitive::Char => Ty::Char,
+        TypeDefPrimitive::Str => Ty::Str,
+        TypeDefPrimitive::U8 => Ty::Uint(8),
+        TypeDefPrimitive::U16 => Ty::Uint(16),
+        TypeDefPrimitive::U32 => Ty::Uint(32),
+        TypeDefPrimitive::U64 => Ty::Uint(64),
+        TypeDefPrimitive::U128 => Ty::Uint(128),
+        TypeDefPrimitive::U256 => Ty::Uint(256),
+        TypeDefPrimitive::I8 => Ty::Int(8),
+        TypeDefPrimitive::I16 => Ty::Int(16),
+        TypeDefPrimitive::I32 => Ty::Int(32),
+        TypeDefPrimitive::I64 => Ty::Int(64),
+        TypeDefPrimitive::I128 => Ty::Int(128),
+        TypeDefPrimitive::I256 => Ty::Int(256),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::interface::tests::erc20_project;
+
+    #[test]
+    fn messages_and_types_are_resolved() {
+        let contract = Contract::from_project("erc20", &erc20_project()).unwrap();
+
+        let transfer = &contract.messages[1];
+        assert_eq!(transfer.name, "transfer");
+        assert_eq!(transfer.selector, [0x84, 0xa1, 0x5d, 0xa1]);
+        assert_eq!(
+            transfer.args,
+            vec![
+                ("to".to_string(), Ty::AccountId),
+                ("value".to_string(), Ty::Uint(128))
+            ]
+        );
+        assert_eq!(transfer.returns, Some(Ty::Named("Result5".to_string())));
+        assert_eq!(
+            contract
+                .types
+                .iter()
+                .map(|decl| decl.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Error", "Result5"]
+        );
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+mod go;
+mod ir;
+mod python;
+
+use super::extrinsics::load_metadata_file;
+use crate::{crate_metadata::CrateMetadata, workspace::ManifestPath};
+use anyhow::{Context, Result};
+use std::{convert::TryFrom, path::PathBuf};
+
+/// Generates client bindings of a contract in other languages from its metadata.
+///
+/// The messages and the types they use are resolved to a language independent representation,
+/// from which the bindings of the selected language are emitted.
+#[derive(Debug, clap::Args)]
+#[clap(name = "typegen")]
+pub struct TypegenCommand {
+    /// Path to the `metadata.json` or `<name>.contract` file of the contract. Defaults to the
+    /// metadata of the contract at `--manifest-path`, which must have been built.
+    #[clap(parse(from_os_str))]
+    metadata_path: Option<PathBuf>,
+    /// Path to the `Cargo.toml` of the contract
+    #[clap(long, parse(from_os_str), conflicts_with = "metadata_path")]
+    manifest_path: Option<PathBuf>,
+    /// The language of the bindings.
+    #[clap(long, arg_enum)]
+    lang: Lang,
+    /// Write the bindings to this file instead of printing them.
+    #[clap(long, short, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+/// The languages bindings can be generated for.
+#[derive(Debug, Clone, Copy, clap::ArgEnum)]
+pub enum Lang {
+    /// A Python module with a client class wrapping `substrate-interface`.
+    Python,
+    /// A Go package with a client on top of `go-substrate-rpc-client`.
+    Go,
+}
+
+impl TypegenCommand {
+    pub fn exec(&self) -> Result<()> {
+        let path = match &self.metadata_path {
+            Some(path) => path.clone(),
+            None => {
+                let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+                CrateMetadata::collect(&manifest_path)?.metadata_path()
+            }
+        };
+        let (metadata, project) = load_metadata_file(&path)?;
+        let contract = ir::Contract::from_project(&metadata.contract.name, &project)?;
+        let generated = match self.lang {
+            Lang::Python => python::emit(&contract)?,
+            Lang::Go => go::emit(&contract)?,
+        };
+
+        match &self.output {
+            Some(output) => std::fs::write(output, generated)
+                .context(format!("Failed to write {}", output.display())),
+            None => {
+                print!("{}", generated);
+                Ok(())
+            }
+        }
+    }
+}
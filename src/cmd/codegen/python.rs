@@ -0,0 +1,223 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Emits a Python module with a client class wrapping the `ContractInstance` of
+//! [`substrate-interface`](https://github.com/polkascan/py-substrate-interface), which encodes
+//! and decodes the values according to the contract's metadata.
+
+use super::ir::{Contract, Ty, TypeKind};
+use anyhow::Result;
+use heck::{ToSnakeCase as _, ToUpperCamelCase as _};
+use std::fmt::Write;
+
+pub(crate) fn emit(contract: &Contract) -> Result<String> {
+    let class = contract.name.to_upper_camel_case();
+    let mut out = String::new();
+    writeln!(
+        out,
+        "\"\"\"Client for the `{}` contract, generated from its metadata by \
+        `cargo contract typegen`.\"\"\"\n",
+        contract.name
+    )?;
+    writeln!(
+        out,
+        "from typing import Any, Dict, List, Optional, Tuple, Union\n"
+    )?;
+    writeln!(
+        out,
+        "from substrateinterface import ContractInstance, ExtrinsicReceipt, Keypair, SubstrateInterface\n"
+    )?;
+
+    for decl in &contract.types {
+        for line in &decl.docs {
+            writeln!(out, "# {}", line)?;
+        }
+        let alias = match decl.kind {
+            // unit variants are represented by their name, others by a dict with the name as key
+            TypeKind::Enum(_) => "Union[str, Dict[str, Any]]",
+            TypeKind::Struct(_) => "Any",
+        };
+        writeln!(out, "{} = {}\n", decl.name, alias)?;
+    }
+
+    writeln!(out, "\nclass {}:", class)?;
+    docstring(&mut out, &contract.docs, "    ")?;
+    writeln!(
+        out,
+        r#"
+    def __init__(self, contract: ContractInstance):
+        self.contract = contract
+
+    @classmethod
+    def at(cls, substrate: SubstrateInterface, contract_address: str, metadata_file: str) -> "{class}":
+        """Returns the client of the contract deployed at `contract_address`."""
+        return cls(
+            ContractInstance.create_from_address(
+                contract_address=contract_address,
+                metadata_file=metadata_file,
+                substrate=substrate,
+            )
+        )"#,
+        class = class
+    )?;
+
+    for message in &contract.messages {
+        let mut params = vec!["self".to_string(), "keypair: Keypair".to_string()];
+        params.extend(
+            message
+                .args
+                .iter()
+                .map(|(name, ty)| format!("{}: {}", identifier(name), type_hint(ty))),
+        );
+        let args = message
+            .args
+            .iter()
+            .map(|(name, _)| format!("\"{}\": {}", name, identifier(name)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let name = message.name.to_snake_case();
+
+        writeln!(out)?;
+        if message.mutates {
+            if message.payable {
+                params.push("transferred_value: int = 0".to_string());
+            }
+            params.push("gas_limit: Optional[int] = None".to_string());
+            writeln!(
+                out,
+                "    def {}({}) -> ExtrinsicReceipt:",
+                name,
+                params.join(", ")
+            )?;
+            docstring(&mut out, &message.docs, "        ")?;
+            let value = if message.payable {
+                "transferred_value"
+            } else {
+                "0"
+            };
+            writeln!(
+                out,
+                "        return self.contract.exec(\n            keypair, \"{}\", args={{{}}}, \
+                value={}, gas_limit=gas_limit\n        )",
+                message.label, args, value
+            )?;
+        } else {
+            let returns = message
+                .returns
+                .as_ref()
+                .map(type_hint)
+                .unwrap_or_else(|| "None".to_string());
+            writeln!(
+                out,
+                "    def {}({}) -> {}:",
+                name,
+                params.join(", "),
+                returns
+            )?;
+            docstring(&mut out, &message.docs, "        ")?;
+            writeln!(
+                out,
+                "        result = self.contract.read(keypair, \"{}\", args={{{}}})\n        \
+                return result.contract_result_data.value",
+                message.label, args
+            )?;
+        }
+    }
+    Ok(out)
+}
+
+fn docstring(out: &mut String, docs: &[String], indent: &str) -> Result<()> {
+    if docs.is_empty() {
+        return Ok(());
+    }
+    writeln!(
+        out,
+        "{}\"\"\"{}",
+        indent,
+        docs.join(&format!("\n{}", indent))
+    )?;
+    writeln!(out, "{}\"\"\"", indent)?;
+    Ok(())
+}
+
+/// The keywords of Python, `keyword.kwlist` followed by `keyword.softkwlist`.
+const KEYWORDS: [&str; 39] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield", "_", "case", "match", "type",
+];
+
+/// Returns the parameter name of an argument, keywords such as `from` get a trailing underscore.
+fn identifier(name: &str) -> String {
+    match KEYWORDS.contains(&name) {
+        true => format!("{}_", name),
+        false => name.to_string(),
+    }
+}
+
+/// Returns the type hint of the value `substrate-interface` expects or returns for the `ty`.
+fn type_hint(ty: &Ty) -> String {
+    match ty {
+        Ty::Bool => "bool".to_string(),
+        Ty::Char | Ty::Str | Ty::AccountId | Ty::Hash => "str".to_string(),
+        Ty::Uint(_) | Ty::Int(_) => "int".to_string(),
+        Ty::Bytes => "Union[str, bytes]".to_string(),
+        Ty::Sequence(item) | Ty::Array(item, _) => format!("List[{}]", type_hint(item)),
+        Ty::Tuple(items) if items.is_empty() => "None".to_string(),
+        Ty::Tuple(items) => format!(
+            "Tuple[{}]",
+            items.iter().map(type_hint).collect::<Vec<_>>().join(", ")
+        ),
+        Ty::Option(item) => format!("Optional[{}]", type_hint(item)),
+        Ty::Named(name) => name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::interface::tests::erc20_project;
+
+    #[test]
+    fn python_client_wraps_contract_instance() {
+        let contract = Contract::from_project("erc20", &erc20_project()).unwrap();
+
+        let module = emit(&contract).unwrap();
+
+        assert!(module.contains("class Erc20:"));
+        assert!(module.contains("Error = Union[str, Dict[str, Any]]"));
+        assert!(module.contains(
+            r#"    def total_supply(self, keypair: Keypair) -> int:
+        """Returns the total token supply.
+        """
+        result = self.contract.read(keypair, "total_supply", args={})
+        return result.contract_result_data.value"#
+        ));
+        assert!(module.contains(
+            r#"    def transfer(self, keypair: Keypair, to: str, value: int, transferred_value: int = 0, gas_limit: Optional[int] = None) -> ExtrinsicReceipt:"#
+        ));
+    }
+
+    #[test]
+    fn keywords_get_a_trailing_underscore() {
+        assert_eq!(identifier("from"), "from_");
+        assert_eq!(identifier("nonlocal"), "nonlocal_");
+        assert_eq!(identifier("match"), "match_");
+        assert_eq!(identifier("type"), "type_");
+        assert_eq!(identifier("value"), "value");
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::extrinsics::load_metadata;
+use crate::{address_book::AddressBook, Opts};
+use anyhow::Result;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use sp_keyring::AccountKeyring;
+use std::{io, path::PathBuf};
+
+/// Completes the contract message, constructor, `--contract` and `--suri` values by invoking the
+/// hidden `cargo contract __complete` helper.
+const BASH_DYNAMIC_COMPLETIONS: &str = r#"
+_cargo_contract_dynamic() {
+    local cur prev
+    cur="${COMP_WORDS[COMP_CWORD]}"
+    prev="${COMP_WORDS[COMP_CWORD-1]}"
+    case "${prev}" in
+        --message|-m)
+            COMPREPLY=($(compgen -W "$(cargo contract __complete messages 2>/dev/null)" -- "${cur}"))
+            return 0
+            ;;
+        --constructor)
+            COMPREPLY=($(compgen -W "$(cargo contract __complete constructors 2>/dev/null)" -- "${cur}"))
+            return 0
+            ;;
+        --contract)
+            COMPREPLY=($(compgen -W "$(cargo contract __complete contracts 2>/dev/null)" -- "${cur}"))
+            return 0
+            ;;
+        --suri|-s)
+            COMPREPLY=($(compgen -W "$(cargo contract __complete accounts 2>/dev/null)" -- "${cur}"))
+            return 0
+            ;;
+    esac
+    _cargo "$@"
+}
+
+complete -F _cargo_contract_dynamic -o bashdefault -o default cargo
+"#;
+
+const FISH_DYNAMIC_COMPLETIONS: &str = r#"
+complete -c cargo -n "__fish_seen_subcommand_from call" -l message -s m -x -a "(cargo contract __complete messages 2>/dev/null)"
+complete -c cargo -n "__fish_seen_subcommand_from instantiate" -l constructor -x -a "(cargo contract __complete constructors 2>/dev/null)"
+complete -c cargo -n "__fish_seen_subcommand_from call" -l contract -x -a "(cargo contract __complete contracts 2>/dev/null)"
+complete -c cargo -n "__fish_seen_subcommand_from upload instantiate call" -l suri -s s -x -a "(cargo contract __complete accounts 2>/dev/null)"
+"#;
+
+/// Generate shell completions for `cargo contract`.
+///
+/// For bash and fish the completions additionally complete the message and constructor names of
+/// the contract in the current directory, the aliases of the address book for `--contract` and
+/// the development accounts of the keyring for `--suri`.
+#[derive(Debug, clap::Args)]
+#[clap(name = "completions")]
+pub struct CompletionsCommand {
+    /// The shell to generate the completions for.
+    #[clap(arg_enum)]
+    shell: Shell,
+}
+
+impl CompletionsCommand {
+    pub fn exec(&self) -> Result<()> {
+        let mut stdout = io::stdout();
+        clap_complete::generate(self.shell, &mut Opts::command(), "cargo", &mut stdout);
+        match self.shell {
+            Shell::Bash => print!("{}", BASH_DYNAMIC_COMPLETIONS),
+            Shell::Fish => print!("{}", FISH_DYNAMIC_COMPLETIONS),
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+/// The kind of values to complete.
+#[derive(Debug, Clone, Copy, clap::ArgEnum)]
+pub enum CompletionKind {
+    /// The labels of the messages of the contract.
+    Messages,
+    /// The labels of the constructors of the contract.
+    Constructors,
+    /// The aliases of the contracts in the address book.
+    Contracts,
+    /// The secret URIs of the development accounts of the keyring.
+    Accounts,
+}
+
+/// Print the candidates for a dynamic shell completion, one per line.
+///
+/// This is invoked by the shell completion scripts, so failures are not reported: without a
+/// built contract there is simply nothing to complete.
+#[derive(Debug, clap::Args)]
+#[clap(name = "__complete")]
+pub struct CompleteCommand {
+    #[clap(arg_enum)]
+    kind: CompletionKind,
+    /// Path to the `Cargo.toml` of the contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+}
+
+impl CompleteCommand {
+    pub fn exec(&self) -> Result<()> {
+        for candidate in self.candidates() {
+            println!("{}", candidate);
+        }
+        Ok(())
+    }
+
+    fn candidates(&self) -> Vec<String> {
+        match self.kind {
+            CompletionKind::Accounts => AccountKeyring::iter()
+                .map(|account| format!("//{}", account))
+                .collect(),
+            CompletionKind::Contracts => match AddressBook::load() {
+                Ok(book) => contract_aliases(&book),
+                Err(err) => {
+                    log::debug!("No address book to complete from: {:?}", err);
+                    Vec::new()
+                }
+            },
+            CompletionKind::Messages | CompletionKind::Constructors => {
+                let project = match load_metadata(self.manifest_path.as_ref()) {
+                    Ok((_, project, _)) => project,
+                    Err(err) => {
+                        log::debug!("No contract metadata to complete from: {:?}", err);
+                        return Vec::new();
+                    }
+                };
+                let spec = project.spec();
+                if let CompletionKind::Messages = self.kind {
+                    spec.messages()
+                        .iter()
+                        .map(|message| message.label().to_string())
+                        .collect()
+                } else {
+                    spec.constructors()
+                        .iter()
+                        .map(|constructor| constructor.label().to_string())
+                        .collect()
+                }
+            }
+        }
+    }
+}
+
+/// Returns the aliases of the contracts in the address `book`.
+fn contract_aliases(book: &AddressBook) -> Vec<String> {
+    book.contracts.keys().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address_book::AddressBookEntry;
+
+    fn complete(kind: CompletionKind) -> Vec<String> {
+        CompleteCommand {
+            kind,
+            manifest_path: None,
+        }
+        .candidates()
+    }
+
+    #[test]
+    fn accounts_are_completed_from_the_keyring() {
+        let accounts = complete(CompletionKind::Accounts);
+        assert!(accounts.contains(&"//Alice".to_string()), "{:?}", accounts);
+        assert!(accounts.contains(&"//Ferdie".to_string()), "{:?}", accounts);
+        assert!(accounts.iter().all(|account| account.starts_with("//")));
+    }
+
+    #[test]
+    fn contracts_are_completed_from_the_address_book() {
+        let mut book = AddressBook::default();
+        for alias in ["erc20", "dao"] {
+            let entry = AddressBookEntry {
+                network: None,
+                address: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".into(),
+                code_hash: None,
+                metadata: None,
+            };
+            book.add(alias, entry).unwrap();
+        }
+        assert_eq!(contract_aliases(&book), ["dao", "erc20"]);
+    }
+
+    #[test]
+    fn dynamic_completions_invoke_the_helper_for_every_kind() {
+        for kind in ["messages", "constructors", "contracts", "accounts"] {
+            let helper = format!("cargo contract __complete {}", kind);
+            assert!(BASH_DYNAMIC_COMPLETIONS.contains(&helper), "{}", kind);
+            assert!(FISH_DYNAMIC_COMPLETIONS.contains(&helper), "{}", kind);
+        }
+    }
+}
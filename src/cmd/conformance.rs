@@ -0,0 +1,420 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checks the metadata of a contract against the messages and events of a standard token
+//! interface.
+//!
+//! The messages of the standards are implemented as ink! traits, e.g. `PSP22`, so their selectors
+//! are derived from the trait and message name, e.g. `PSP22::transfer`. Types are compared by
+//! their name without their path, e.g. `AccountId` for `ink_env::types::AccountId`.
+
+use super::{add::default_selector, extrinsics::load_metadata_file, interface::message_name};
+use crate::{
+    cmd::interface::RustTypes, crate_metadata::CrateMetadata, name_value_println,
+    workspace::ManifestPath,
+};
+use anyhow::Result;
+use colored::Colorize;
+use ink_metadata::InkProject;
+use regex::Regex;
+use std::{convert::TryFrom, path::PathBuf};
+
+/// Checks that a contract implements a standard token interface.
+#[derive(Debug, clap::Args)]
+#[clap(name = "conformance")]
+pub struct ConformanceCommand {
+    /// Path to the `metadata.json` or `<name>.contract` file of the contract. Defaults to the
+    /// metadata of the contract at `--manifest-path`, which must have been built.
+    #[clap(parse(from_os_str))]
+    metadata_path: Option<PathBuf>,
+    /// Path to the `Cargo.toml` of the contract
+    #[clap(long, parse(from_os_str), conflicts_with = "metadata_path")]
+    manifest_path: Option<PathBuf>,
+    /// The standard the contract has to conform to.
+    #[clap(long, arg_enum)]
+    standard: Standard,
+}
+
+/// The standard token interfaces of the Polkadot Standard Proposals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+pub enum Standard {
+    /// Fungible tokens.
+    Psp22,
+    /// Non-fungible tokens.
+    Psp34,
+    /// Multi tokens.
+    Psp37,
+}
+
+/// A message required by a standard.
+struct MessageSpec {
+    name: &'static str,
+    /// The types of the arguments, in order.
+    args: &'static [&'static str],
+    /// The return type, `()` for none.
+    returns: &'static str,
+}
+
+/// An event required by a standard.
+struct EventSpec {
+    name: &'static str,
+    /// The labels and types of the fields.
+    fields: &'static [(&'static str, &'static str)],
+}
+
+const fn message(
+    name: &'static str,
+    args: &'static [&'static str],
+    returns: &'static str,
+) -> MessageSpec {
+    MessageSpec {
+        name,
+        args,
+        returns,
+    }
+}
+
+const PSP22_MESSAGES: &[MessageSpec] = &[
+    message("total_supply", &[], "u128"),
+    message("balance_of", &["AccountId"], "u128"),
+    message("allowance", &["AccountId", "AccountId"], "u128"),
+    message(
+        "transfer",
+        &["AccountId", "u128", "Vec<u8>"],
+        "Result<(), PSP22Error>",
+    ),
+    message(
+        "transfer_from",
+        &["AccountId", "AccountId", "u128", "Vec<u8>"],
+        "Result<(), PSP22Error>",
+    ),
+    message("approve", &["AccountId", "u128"], "Result<(), PSP22Error>"),
+    message(
+        "increase_allowance",
+        &["AccountId", "u128"],
+        "Result<(), PSP22Error>",
+    ),
+    message(
+        "decrease_allowance",
+        &["AccountId", "u128"],
+        "Result<(), PSP22Error>",
+    ),
+];
+
+const PSP22_EVENTS: &[EventSpec] = &[
+    EventSpec {
+        name: "Transfer",
+        fields: &[
+            ("from", "Option<AccountId>"),
+            ("to", "Option<AccountId>"),
+            ("value", "u128"),
+        ],
+    },
+    EventSpec {
+        name: "Approval",
+        fields: &[
+            ("owner", "AccountId"),
+            ("spender", "AccountId"),
+            ("value", "u128"),
+        ],
+    },
+];
+
+const PSP34_MESSAGES: &[MessageSpec] = &[
+    message("collection_id", &[], "Id"),
+    message("balance_of", &["AccountId"], "u32"),
+    message("owner_of", &["Id"], "Option<AccountId>"),
+    message(
+        "allowance",
+        &["AccountId", "AccountId", "Option<Id>"],
+        "bool",
+    ),
+    message(
+        "approve",
+        &["AccountId", "Option<Id>", "bool"],
+        "Result<(), PSP34Error>",
+    ),
+    message(
+        "transfer",
+        &["AccountId", "Id", "Vec<u8>"],
+        "Result<(), PSP34Error>",
+    ),
+    message("total_supply", &[], "u128"),
+];
+
+const PSP34_EVENTS: &[EventSpec] = &[
+    EventSpec {
+        name: "Transfer",
+        fields: &[
+            ("from", "Option<AccountId>"),
+            ("to", "Option<AccountId>"),
+            ("id", "Id"),
+        ],
+    },
+    EventSpec {
+        name: "Approval",
+        fields: &[
+            ("owner", "AccountId"),
+            ("operator", "AccountId"),
+            ("id", "Option<Id>"),
+            ("approved", "bool"),
+        ],
+    },
+];
+
+const PSP37_MESSAGES: &[MessageSpec] = &[
+    message("balance_of", &["AccountId", "Option<Id>"], "u128"),
+    message("total_supply", &["Option<Id>"], "u128"),
+    message(
+        "allowance",
+        &["AccountId", "AccountId", "Option<Id>"],
+        "u128",
+    ),
+    message(
+        "approve",
+        &["AccountId", "Option<Id>", "u128"],
+        "Result<(), PSP37Error>",
+    ),
+    message(
+        "transfer",
+        &["AccountId", "Id", "u128", "Vec<u8>"],
+        "Result<(), PSP37Error>",
+    ),
+    message(
+        "transfer_from",
+        &["AccountId", "AccountId", "Id", "u128", "Vec<u8>"],
+        "Result<(), PSP37Error>",
+    ),
+];
+
+const PSP37_EVENTS: &[EventSpec] = &[
+    EventSpec {
+        name: "Transfer",
+        fields: &[
+            ("from", "Option<AccountId>"),
+            ("to", "Option<AccountId>"),
+            ("id", "Id"),
+            ("value", "u128"),
+        ],
+    },
+    EventSpec {
+        name: "Approval",
+        fields: &[
+            ("owner", "AccountId"),
+            ("operator", "AccountId"),
+            ("id", "Option<Id>"),
+            ("value", "u128"),
+        ],
+    },
+];
+
+impl Standard {
+    /// The name of the trait of the standard, e.g. `PSP22`.
+    fn trait_name(self) -> &'static str {
+        match self {
+            Self::Psp22 => "PSP22",
+            Self::Psp34 => "PSP34",
+            Self::Psp37 => "PSP37",
+        }
+    }
+
+    fn messages(self) -> &'static [MessageSpec] {
+        match self {
+            Self::Psp22 => PSP22_MESSAGES,
+            Self::Psp34 => PSP34_MESSAGES,
+            Self::Psp37 => PSP37_MESSAGES,
+        }
+    }
+
+    fn events(self) -> &'static [EventSpec] {
+        match self {
+            Self::Psp22 => PSP22_EVENTS,
+            Self::Psp34 => PSP34_EVENTS,
+            Self::Psp37 => PSP37_EVENTS,
+        }
+    }
+}
+
+impl ConformanceCommand {
+    pub fn exec(&self) -> Result<()> {
+        let path = match &self.metadata_path {
+            Some(path) => path.clone(),
+            None => {
+                let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+                CrateMetadata::collect(&manifest_path)?.metadata_path()
+            }
+        };
+        let (metadata, project) = load_metadata_file(&path)?;
+        let deviations = deviations(self.standard, &project)?;
+        let standard = self.standard.trait_name();
+        if deviations.is_empty() {
+            name_value_println!(
+                "Conforms",
+                format!("{} conforms to {}", metadata.contract.name, standard)
+            );
+            return Ok(());
+        }
+        for deviation in &deviations {
+            println!("{} {}", "-".red().bold(), deviation);
+        }
+        anyhow::bail!(
+            "{} does not conform to {}, found {} deviation(s)",
+            metadata.contract.name,
+            standard,
+            deviations.len()
+        )
+    }
+}
+
+/// Returns the deviations of the contract `project` from the messages and events of the
+/// `standard`.
+fn deviations(standard: Standard, project: &InkProject) -> Result<Vec<String>> {
+    let mut types = RustTypes::new(project.registry());
+    let mut name = |id| -> Result<String> { Ok(without_paths(&types.name(id)?)) };
+    let mut deviations = Vec::new();
+
+    for spec in standard.messages() {
+        let path = format!("{}::{}", standard.trait_name(), spec.name);
+        let message = match project
+            .spec()
+            .messages()
+            .iter()
+            .find(|message| message_name(message.label()) == spec.name)
+        {
+            Some(message) => message,
+            None => {
+                deviations.push(format!("The message `{}` is missing", path));
+                continue;
+            }
+        };
+        let expected = default_selector(&path).to_be_bytes();
+        let actual = message.selector().to_bytes();
+        if actual != expected {
+            deviations.push(format!(
+                "The selector of `{}` is 0x{}, expected 0x{}",
+                path,
+                hex::encode(actual),
+                hex::encode(expected)
+            ));
+        }
+        let args = message
+            .args()
+            .iter()
+            .map(|arg| name(arg.ty().ty().id()))
+            .collect::<Result<Vec<_>>>()?;
+        if args != spec.args {
+            deviations.push(format!(
+                "The arguments of `{}` are ({}), expected ({})",
+                path,
+                args.join(", "),
+                spec.args.join(", ")
+            ));
+        }
+        let returns = match message.return_type().opt_type() {
+            Some(ty) => name(ty.ty().id())?,
+            None => "()".to_string(),
+        };
+        if returns != spec.returns {
+            deviations.push(format!(
+                "`{}` returns {}, expected {}",
+                path, returns, spec.returns
+            ));
+        }
+    }
+
+    for spec in standard.events() {
+        let event = match project
+            .spec()
+            .events()
+            .iter()
+            .find(|event| event.label() == spec.name)
+        {
+            Some(event) => event,
+            None => {
+                deviations.push(format!("The event `{}` is missing", spec.name));
+                continue;
+            }
+        };
+        let fields = event
+            .args()
+            .iter()
+            .map(|arg| Ok((arg.label().to_string(), name(arg.ty().ty().id())?)))
+            .collect::<Result<Vec<_>>>()?;
+        let expected = spec
+            .fields
+            .iter()
+            .map(|(label, ty)| (label.to_string(), ty.to_string()))
+            .collect::<Vec<_>>();
+        if fields != expected {
+            let display = |fields: &[(String, String)]| {
+                fields
+                    .iter()
+                    .map(|(label, ty)| format!("{}: {}", label, ty))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            deviations.push(format!(
+                "The fields of the event `{}` are {{ {} }}, expected {{ {} }}",
+                spec.name,
+                display(&fields),
+                display(&expected)
+            ));
+        }
+    }
+    Ok(deviations)
+}
+
+/// Returns the type `name` without the paths of its types, e.g. `Option<AccountId>` for
+/// `Option<ink_env::AccountId>`.
+fn without_paths(name: &str) -> String {
+    let path = Regex::new(r"\b\w+::").expect("the regex is valid");
+    path.replace_all(name, "").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::interface::tests::erc20_project;
+
+    #[test]
+    fn deviations_from_psp22_are_reported() {
+        assert_eq!(default_selector("PSP22::total_supply"), 0x162DF8C2);
+        assert_eq!(
+            without_paths("Result<Option<ink_env::AccountId>, erc20::Error>"),
+            "Result<Option<AccountId>, Error>"
+        );
+
+        let deviations = deviations(Standard::Psp22, &erc20_project()).unwrap();
+        assert_eq!(
+            deviations[..3],
+            [
+                "The selector of `PSP22::total_supply` is 0xdb6375a8, expected 0x162df8c2",
+                "The message `PSP22::balance_of` is missing",
+                "The message `PSP22::allowance` is missing",
+            ]
+        );
+        assert!(deviations.contains(
+            &"The arguments of `PSP22::transfer` are (AccountId, u128), expected \
+              (AccountId, u128, Vec<u8>)"
+                .to_string()
+        ));
+        assert!(deviations.contains(
+            &"`PSP22::transfer` returns Result<(), Error>, expected Result<(), PSP22Error>"
+                .to_string()
+        ));
+        assert!(deviations.contains(&"The event `Transfer` is missing".to_string()));
+    }
+}
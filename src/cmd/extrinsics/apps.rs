@@ -0,0 +1,55 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::ExtrinsicOpts;
+use crate::name_value_println;
+use anyhow::Result;
+
+const APPS_URL: &str = "https://polkadot.js.org/apps/";
+
+/// Prints the encoded `call` and a link to the extrinsic decoder of polkadot-js apps, where it
+/// can be reviewed and submitted by a teammate, instead of submitting it.
+pub(super) fn display_apps_link(opts: &ExtrinsicOpts, call: Vec<u8>) -> Result<()> {
+    name_value_println!("Call data", format!("0x{}", hex::encode(&call)));
+    name_value_println!("Apps link", apps_link(&opts.url()?, &call));
+    Ok(())
+}
+
+/// Returns the link to the decoded `call` on the chain at `url` in polkadot-js apps.
+fn apps_link(url: &url::Url, call: &[u8]) -> String {
+    let rpc: String = url::form_urlencoded::byte_serialize(url.as_str().as_bytes()).collect();
+    format!(
+        "{}?rpc={}#/extrinsics/decode/0x{}",
+        APPS_URL,
+        rpc,
+        hex::encode(call)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_contains_rpc_and_call() {
+        let url = url::Url::parse("wss://rococo-contracts-rpc.polkadot.io").unwrap();
+        assert_eq!(
+            apps_link(&url, &[0x28, 0x03, 0x00]),
+            "https://polkadot.js.org/apps/?rpc=wss%3A%2F%2Frococo-contracts-rpc.polkadot.io%2F\
+             #/extrinsics/decode/0x280300"
+        );
+    }
+}
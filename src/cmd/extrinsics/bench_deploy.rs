@@ -0,0 +1,296 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    constructor_args::{complete_args, configured_args},
+    dispatch_error::describe,
+    fees::{estimate_fee, Cost},
+    identity::resolve_account_args,
+    instantiate::{Code, ContractInstantiateResult, InstantiateRequest},
+    load_metadata, node_url, parse_balance,
+    runtime_api::api,
+    token_decimals, transcoder_for_args,
+    upload::{determinism_variants, CodeUploadRequest, CodeUploadResult},
+    Balance, ExtrinsicSigner, RuntimeApi,
+};
+use crate::{crate_metadata::CrateMetadata, name_value_println};
+use anyhow::{anyhow, Context, Result};
+use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use sp_core::{crypto::Pair, sr25519, Bytes};
+use std::{fmt::Write, fs, path::PathBuf};
+use subxt::{rpc::NumberOrHex, ClientBuilder};
+
+/// Compare the cost of deploying the contract on several networks, e.g. to decide where to
+/// deploy it.
+///
+/// The upload and the instantiation are dry-run against every network, nothing is submitted.
+#[derive(Debug, clap::Args)]
+#[clap(name = "bench-deploy")]
+pub struct BenchDeployCommand {
+    /// The names of the network profiles to compare, from the `cargo-contract` configuration file
+    /// or with an `endpoint` in `[package.metadata.contract.profiles]` of the contract.
+    #[clap(required = true)]
+    networks: Vec<String>,
+    /// Path to the `Cargo.toml` of the contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// Path to Wasm contract code, defaults to `./target/ink/<name>.wasm`.
+    #[clap(long, parse(from_os_str))]
+    wasm_path: Option<PathBuf>,
+    /// The name of the contract constructor to call
+    #[clap(long, default_value = "new")]
+    constructor: String,
+    /// The constructor arguments, encoded as strings. Omitted trailing arguments are taken from
+    /// `[package.metadata.contract.constructor-args.<constructor>]` or the default of their type.
+    #[clap(long)]
+    args: Vec<String>,
+    /// Transfers an initial balance to the instantiated contract
+    #[clap(long, default_value = "0", parse(try_from_str = parse_balance))]
+    value: Balance,
+    /// Maximum amount of gas to be used for the dry-runs
+    #[clap(name = "gas", long, default_value = "50000000000")]
+    gas_limit: u64,
+    /// Secret key URI of the account the dry-runs are done for. The account has to be funded on
+    /// every network to cover the deposits.
+    #[clap(long, short)]
+    suri: String,
+    /// Password for the secret key.
+    #[clap(long, short)]
+    password: Option<String>,
+}
+
+/// The estimated cost of deploying the contract on a network.
+#[derive(Debug)]
+struct Estimate {
+    /// The deposit for storing the code.
+    code_deposit: Balance,
+    /// The deposit for the storage of the instantiated contract, besides its code.
+    storage_deposit: Balance,
+    /// The fee of `instantiate_with_code`.
+    fee: Balance,
+    /// The decimals of the native token, if the chain reports them.
+    decimals: Option<u32>,
+}
+
+impl BenchDeployCommand {
+    pub fn run(&self) -> Result<()> {
+        let (crate_metadata, contract_metadata, _) = load_metadata(self.manifest_path.as_ref())?;
+        let wasm_path = self
+            .wasm_path
+            .clone()
+            .unwrap_or_else(|| crate_metadata.dest_wasm.clone());
+        let code =
+            fs::read(&wasm_path).context(format!("Failed to read from {}", wasm_path.display()))?;
+        let pair = sr25519::Pair::from_string(&self.suri, self.password.as_deref())
+            .map_err(|_| anyhow!("Secret string error"))?;
+        let signer = ExtrinsicSigner::pair(pair);
+
+        let mut rows = Vec::new();
+        for network in &self.networks {
+            name_value_println!("Estimating", network);
+            let estimate =
+                self.estimate(&crate_metadata, &contract_metadata, network, &code, &signer);
+            if let Err(err) = &estimate {
+                log::debug!(
+                    "Failed to estimate the deployment on {}: {:?}",
+                    network,
+                    err
+                );
+            }
+            rows.push((network.clone(), estimate));
+        }
+        print!("{}", table(&rows));
+        Ok(())
+    }
+
+    /// Dry-runs the upload of the `code` and its instantiation on the `network`.
+    fn estimate(
+        &self,
+        crate_metadata: &CrateMetadata,
+        contract_metadata: &ink_metadata::InkProject,
+        network: &str,
+        code: &[u8],
+        signer: &ExtrinsicSigner,
+    ) -> Result<Estimate> {
+        let url = node_url(None, Some(network), self.manifest_path.as_ref())?;
+        // balances given as fixed-point numbers depend on the decimals of every chain
+        let (args, _) = complete_args(
+            contract_metadata,
+            &configured_args(crate_metadata),
+            &self.constructor,
+            &self.args,
+        )?;
+        let transcoder = transcoder_for_args(contract_metadata, &url, &args)?;
+        let args = resolve_account_args(&transcoder, &url, &self.constructor, &args)?;
+        let data = transcoder.encode(&self.constructor, &args)?;
+
+        async_std::task::block_on(async {
+            let api = ClientBuilder::new()
+                .set_url(url.as_str())
+                .build()
+                .await?
+                .to_runtime_api::<RuntimeApi>();
+            let cli = WsClientBuilder::default().build(url.as_str()).await?;
+            let metadata = api.client.metadata();
+
+            let upload = CodeUploadRequest {
+                origin: signer.account_id().clone(),
+                code: Bytes(code.to_vec()),
+                storage_deposit_limit: None,
+                // the first variant is `Enforced`
+                determinism: determinism_variants(metadata)
+                    .and_then(|variants| variants.into_iter().next()),
+            };
+            let uploaded: CodeUploadResult = cli
+                .request("contracts_upload_code", rpc_params![upload])
+                .await?;
+            let uploaded = uploaded
+                .map_err(|err| anyhow!("The upload failed: {}", describe(metadata, &err)))?;
+
+            let instantiate = InstantiateRequest {
+                origin: signer.account_id().clone(),
+                value: NumberOrHex::Hex(self.value.into()),
+                gas_limit: NumberOrHex::Number(self.gas_limit),
+                storage_deposit_limit: None,
+                code: Code::Upload(Bytes(code.to_vec())),
+                data: Bytes(data.clone()),
+                salt: Bytes(Vec::new()),
+            };
+            let instantiated: ContractInstantiateResult = cli
+                .request("contracts_instantiate", rpc_params![instantiate])
+                .await?;
+            match &instantiated.result {
+                Ok(ret_val) if ret_val.result.did_revert() => {
+                    anyhow::bail!("The constructor reverted")
+                }
+                Ok(_) => (),
+                Err(err) => {
+                    anyhow::bail!("The instantiation failed: {}", describe(metadata, err))
+                }
+            }
+            // the deposit of `instantiate_with_code` includes the deposit of the code
+            let deposit = Cost::new(self.value, &instantiated.storage_deposit).deposit;
+
+            let instantiate_with_code = api::contracts::calls::InstantiateWithCode {
+                value: self.value,
+                gas_limit: instantiated.gas_required,
+                storage_deposit_limit: None,
+                code: code.to_vec(),
+                data,
+                salt: Vec::new(),
+            };
+            let fee = estimate_fee(&api, instantiate_with_code, signer).await?;
+
+            Ok(Estimate {
+                code_deposit: uploaded.deposit,
+                storage_deposit: deposit.saturating_sub(uploaded.deposit),
+                fee,
+                decimals: token_decimals(&url).await.ok(),
+            })
+        })
+    }
+}
+
+/// Displays the estimates of the networks as a table, with the reason of failed estimates.
+fn table(rows: &[(String, Result<Estimate>)]) -> String {
+    let mut table = format!(
+        "{:<20} {:>20} {:>20} {:>20} {:>20}\n",
+        "Network", "Code deposit", "Storage deposit", "Fee", "Total"
+    );
+    for (network, estimate) in rows {
+        let _ = match estimate {
+            Ok(estimate) => {
+                let balance = |value| format_balance(value, estimate.decimals);
+                let total = estimate
+                    .code_deposit
+                    .saturating_add(estimate.storage_deposit)
+                    .saturating_add(estimate.fee);
+                writeln!(
+                    table,
+                    "{:<20} {:>20} {:>20} {:>20} {:>20}",
+                    network,
+                    balance(estimate.code_deposit),
+                    balance(estimate.storage_deposit),
+                    balance(estimate.fee),
+                    balance(total)
+                )
+            }
+            Err(err) => writeln!(table, "{:<20} Failed: {:#}", network, err),
+        };
+    }
+    table
+}
+
+/// Formats the `value` in units of the token with the `decimals`, or in the smallest unit if
+/// they are unknown.
+fn format_balance(value: Balance, decimals: Option<u32>) -> String {
+    let decimals = match decimals {
+        Some(decimals) if decimals > 0 => decimals,
+        _ => return value.to_string(),
+    };
+    let unit = 10u128.pow(decimals);
+    let fraction = format!("{:0width$}", value % unit, width = decimals as usize);
+    match fraction.trim_end_matches('0') {
+        "" => (value / unit).to_string(),
+        fraction => format!("{}.{}", value / unit, fraction),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_are_compared_in_a_table() {
+        let rows = vec![
+            (
+                "rococo".to_string(),
+                Ok(Estimate {
+                    code_deposit: 1_500_000_000_000,
+                    storage_deposit: 20_000_000_000,
+                    fee: 1_000_000_000,
+                    decimals: Some(12),
+                }),
+            ),
+            ("shibuya".to_string(), Err(anyhow!("Connection refused"))),
+            (
+                "local".to_string(),
+                Ok(Estimate {
+                    code_deposit: 100,
+                    storage_deposit: 0,
+                    fee: 5,
+                    decimals: None,
+                }),
+            ),
+        ];
+        let table = table(&rows);
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("Network"));
+        assert_eq!(
+            lines[1].split_whitespace().collect::<Vec<_>>(),
+            ["rococo", "1.5", "0.02", "0.001", "1.521"]
+        );
+        assert_eq!(
+            lines[2],
+            format!("{:<20} Failed: Connection refused", "shibuya")
+        );
+        assert_eq!(
+            lines[3].split_whitespace().collect::<Vec<_>>(),
+            ["local", "100", "0", "5", "105"]
+        );
+    }
+}
@@ -15,25 +15,53 @@
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::{
-    display_contract_exec_result, display_events, load_metadata, parse_balance,
-    wait_for_success_and_handle_error, Balance, ContractMessageTranscoder, ExtrinsicOpts,
-    PairSigner, RuntimeApi, EXEC_RESULT_MAX_KEY_COL_WIDTH,
+    apps::display_apps_link,
+    display_contract_exec_result, display_events, display_explorer_links, encode_call,
+    fees::Cost,
+    identity::{resolve_account_args, resolve_on_chain},
+    instantiate::parse_code_hash,
+    load_metadata, parse_balance,
+    runtime_api::api,
+    schedule::schedule_call,
+    session::{Session, SessionKind},
+    storage_diff::display_storage_diff,
+    submission::submit_extrinsic,
+    transcoder_for_args, Balance, ContractMessageTranscoder, ExplorerPage, ExtrinsicOpts,
+    ExtrinsicSigner, RuntimeApi, EXEC_RESULT_MAX_KEY_COL_WIDTH,
 };
-use crate::name_value_println;
-use anyhow::Result;
-use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use crate::{
+    address_book::{parse_account_arg, AccountArg},
+    exit_code::{classify, ExitCode},
+    name_value_println, timings,
+};
+use anyhow::{anyhow, Context, Result};
+use ink_metadata::layout::Layout;
+use jsonrpsee::{
+    core::client::ClientT,
+    rpc_params,
+    ws_client::{WsClient, WsClientBuilder},
+};
+use scale::{Decode, Encode};
+use scale_info::form::PortableForm;
 use serde::Serialize;
-use sp_core::Bytes;
-use std::fmt::Debug;
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    Bytes,
+};
+use std::{fmt::Debug, path::PathBuf, str::FromStr};
 use subxt::{rpc::NumberOrHex, ClientBuilder, Config, DefaultConfig, Signer};
 
-type ContractExecResult = pallet_contracts_primitives::ContractExecResult<Balance>;
+pub(super) type ContractExecResult = pallet_contracts_primitives::ContractExecResult<Balance>;
 
-#[derive(Debug, clap::Args)]
+#[derive(Clone, Debug, clap::Args)]
 #[clap(name = "call", about = "Call a contract")]
 pub struct CallCommand {
-    /// The address of the the contract to call.
-    #[clap(name = "contract", long, env = "CONTRACT")]
+    /// The address of the the contract to call, its alias in the address book, its `index:<n>`
+    /// in the indices pallet or the display name of its identity on the chain.
+    #[clap(name = "contract", long, env = "CONTRACT", parse(try_from_str = parse_account_arg))]
+    contract_arg: AccountArg,
+    /// The account of the `contract_arg`, resolved by [`Self::run`].
+    #[clap(skip = AccountId32::new([0; 32]))]
     contract: <DefaultConfig as Config>::AccountId,
     /// The name of the contract message to call.
     #[clap(long, short)]
@@ -49,123 +77,424 @@ pub struct CallCommand {
     /// The value to be transferred as part of the call.
     #[clap(name = "value", long, parse(try_from_str = parse_balance), default_value = "0")]
     value: Balance,
+    /// Record all RPC requests, responses and events of this call to the given file, so that the
+    /// session can be reproduced offline with `cargo contract replay`.
+    #[clap(long, parse(from_os_str))]
+    record: Option<PathBuf>,
+    /// Dry-run the message at a historical block, given by its hash or number, e.g. to query a
+    /// balance as of that block.
+    #[clap(
+        long,
+        value_name = "HASH | NUMBER",
+        requires = "dry_run",
+        conflicts_with = "record"
+    )]
+    from_block: Option<BlockRef>,
+    /// Print the changes of the storage of the contract the call would make, decoded with the
+    /// storage layout. The call is dry-run with `dev_dryRun`, so point `--url` at a fork of the
+    /// chain, e.g. by chopsticks. Nothing is submitted.
+    #[clap(long, requires = "dry_run", conflicts_with = "from_block")]
+    storage_diff: bool,
+    /// Schedule the call at this block with `Scheduler::schedule`, e.g. for a time-delayed
+    /// parameter change. Most runtimes only allow root to schedule, pass `--sudo` for the call to
+    /// be scheduled by root and dispatched with the signer as origin.
+    #[clap(
+        long,
+        value_name = "BLOCK",
+        conflicts_with_all = &["dry_run", "apps_link"]
+    )]
+    schedule_at: Option<u32>,
+}
+
+/// A block given by its hash or number.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockRef {
+    Hash(<DefaultConfig as Config>::Hash),
+    Number(u32),
+}
+
+impl FromStr for BlockRef {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        if input.starts_with("0x") {
+            return Ok(BlockRef::Hash(parse_code_hash(input)?));
+        }
+        let number = input
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Expected a block hash or number, got '{}'", input))?;
+        Ok(BlockRef::Number(number))
+    }
 }
 
 impl CallCommand {
+    /// A call of the `message` of the `contract`, e.g. entered in `cargo contract console`.
+    pub(super) fn new(
+        contract: <DefaultConfig as Config>::AccountId,
+        message: String,
+        args: Vec<String>,
+        extrinsic_opts: ExtrinsicOpts,
+        gas_limit: u64,
+        value: Balance,
+    ) -> Self {
+        CallCommand {
+            contract_arg: AccountArg::Account(contract.clone()),
+            contract,
+            message,
+            args,
+            extrinsic_opts,
+            gas_limit,
+            value,
+            record: None,
+            from_block: None,
+            storage_diff: false,
+            schedule_at: None,
+        }
+    }
+
     pub fn run(&self) -> Result<()> {
-        let (_, contract_metadata) = load_metadata(self.extrinsic_opts.manifest_path.as_ref())?;
-        let transcoder = ContractMessageTranscoder::new(&contract_metadata);
-        let call_data = transcoder.encode(&self.message, &self.args)?;
-        let signer = super::pair_signer(self.extrinsic_opts.signer()?);
+        let url = self.extrinsic_opts.url()?;
+        let contract = match &self.contract_arg {
+            AccountArg::Account(contract) => contract.clone(),
+            AccountArg::OnChain(name) => {
+                let contract = async_std::task::block_on(resolve_on_chain(&url, name))?;
+                name_value_println!("Contract", contract.to_ss58check());
+                contract
+            }
+        };
+        CallCommand {
+            contract,
+            ..self.clone()
+        }
+        .exec(url)
+    }
+
+    /// Executes the call of the resolved `contract`.
+    fn exec(&self, url: url::Url) -> Result<()> {
+        let (_, contract_metadata, _) = load_metadata(self.extrinsic_opts.manifest_path.as_ref())?;
+        let transcoder = transcoder_for_args(&contract_metadata, &url, &self.args)?;
+        let args = resolve_account_args(&transcoder, &url, &self.message, &self.args)?;
+        let call_data = transcoder.encode(&self.message, &args)?;
+        if self.extrinsic_opts.apps_link {
+            // nothing is signed, the call is submitted by whoever opens the link
+            return async_std::task::block_on(self.apps_link(&url, call_data));
+        }
+        let signer = self.extrinsic_opts.extrinsic_signer()?;
+        let mut session = self.record.as_ref().map(|path| {
+            Session::new(
+                path,
+                SessionKind::Call,
+                &self.message,
+                &args,
+                &call_data,
+                &url,
+            )
+        });
 
         async_std::task::block_on(async {
             if self.extrinsic_opts.dry_run {
-                self.call_rpc(call_data, &signer, &transcoder).await
+                self.call_rpc(
+                    &url,
+                    call_data.clone(),
+                    &signer,
+                    &transcoder,
+                    session.as_mut(),
+                )
+                .await?;
+                if self.storage_diff {
+                    let layout = contract_metadata.layout();
+                    self.storage_diff(&url, call_data, &signer, &transcoder, layout)
+                        .await?;
+                }
+                Ok(())
             } else {
-                self.call(call_data, &signer, &transcoder).await
+                self.call(&url, call_data, &signer, &transcoder, session.as_mut())
+                    .await
             }
         })
     }
 
-    async fn call_rpc(
+    /// Dry-runs the call and prints its result, which is returned if the call succeeded.
+    pub(super) async fn call_rpc(
         &self,
+        url: &url::Url,
         data: Vec<u8>,
-        signer: &PairSigner,
+        signer: &ExtrinsicSigner,
         transcoder: &ContractMessageTranscoder<'_>,
-    ) -> Result<()> {
-        let url = self.extrinsic_opts.url.to_string();
-        let cli = WsClientBuilder::default().build(&url).await?;
+        session: Option<&mut Session>,
+    ) -> Result<ContractExecResult> {
+        let cli = WsClientBuilder::default().build(url.as_str()).await?;
+        let call_request = self.call_request(data, signer);
+        if let Some(block) = &self.from_block {
+            let result = self.call_at_block(&cli, &call_request, block).await?;
+            display_call_result(&result, transcoder, &self.message)?;
+            ensure_call_succeeded(&result)?;
+            return Ok(result);
+        }
+        let params = rpc_params![&call_request];
+        let dry_run = timings::phase("Dry-run RPC");
+        let response: serde_json::Value = cli.request("contracts_call", params).await?;
+        drop(dry_run);
+        if let Some(session) = session {
+            session.record_rpc("contracts_call", &call_request, &response)?;
+        }
+        let result: ContractExecResult = serde_json::from_value(response)?;
+        display_call_result(&result, transcoder, &self.message)?;
+        ensure_call_succeeded(&result)?;
+        Ok(result)
+    }
+
+    fn call_request(&self, data: Vec<u8>, signer: &ExtrinsicSigner) -> RpcCallRequest {
         let storage_deposit_limit = self
             .extrinsic_opts
             .storage_deposit_limit
             .as_ref()
             .map(|limit| NumberOrHex::Hex((*limit).into()));
-        let call_request = RpcCallRequest {
+        RpcCallRequest {
             origin: signer.account_id().clone(),
             dest: self.contract.clone(),
             value: NumberOrHex::Hex(self.value.into()),
             gas_limit: NumberOrHex::Number(self.gas_limit),
             storage_deposit_limit,
             input_data: Bytes(data),
-        };
-        let params = rpc_params![call_request];
-        let result: ContractExecResult = cli.request("contracts_call", params).await?;
-
-        match result.result {
-            Ok(ref ret_val) => {
-                let value = transcoder.decode_return(&self.message, &mut &ret_val.data.0[..])?;
-                name_value_println!(
-                    "Result",
-                    String::from("Success!"),
-                    EXEC_RESULT_MAX_KEY_COL_WIDTH
-                );
-                name_value_println!(
-                    "Reverted",
-                    format!("{:?}", ret_val.did_revert()),
-                    EXEC_RESULT_MAX_KEY_COL_WIDTH
-                );
-                name_value_println!("Data", format!("{}", value), EXEC_RESULT_MAX_KEY_COL_WIDTH);
-            }
-            Err(err) => {
-                name_value_println!(
-                    "Result",
-                    format!("Error: {:?}", err),
-                    EXEC_RESULT_MAX_KEY_COL_WIDTH
-                );
+        }
+    }
+
+    /// Estimates the storage deposit of the call with a dry-run.
+    async fn estimate_cost(
+        &self,
+        url: &url::Url,
+        data: Vec<u8>,
+        signer: &ExtrinsicSigner,
+        session: Option<&mut Session>,
+    ) -> Result<Cost> {
+        let cli = WsClientBuilder::default().build(url.as_str()).await?;
+        let call_request = self.call_request(data, signer);
+        let response: serde_json::Value = cli
+            .request("contracts_call", rpc_params![&call_request])
+            .await?;
+        if let Some(session) = session {
+            session.record_rpc("contracts_call", &call_request, &response)?;
+        }
+        let result: ContractExecResult = serde_json::from_value(response)?;
+        Ok(Cost::new(self.value, &result.storage_deposit))
+    }
+
+    /// Dry-runs the call via the `ContractsApi_call` runtime API at the state of the `block`.
+    async fn call_at_block(
+        &self,
+        cli: &WsClient,
+        call_request: &RpcCallRequest,
+        block: &BlockRef,
+    ) -> Result<ContractExecResult> {
+        let at = match block {
+            BlockRef::Hash(hash) => *hash,
+            BlockRef::Number(number) => {
+                let hash: Option<<DefaultConfig as Config>::Hash> = cli
+                    .request("chain_getBlockHash", rpc_params![number])
+                    .await?;
+                hash.context(format!("Block {} not found", number))?
             }
+        };
+        let args = (
+            &call_request.origin,
+            &call_request.dest,
+            self.value,
+            self.gas_limit,
+            self.extrinsic_opts.storage_deposit_limit,
+            &call_request.input_data.0,
+        )
+            .encode();
+        let response: Bytes = cli
+            .request(
+                "state_call",
+                rpc_params!["ContractsApi_call", Bytes(args), at],
+            )
+            .await?;
+        ContractExecResult::decode(&mut &response.0[..])
+            .context("Failed to decode the result of `ContractsApi_call`")
+    }
+
+    /// Prints a link to review and submit the call in polkadot-js apps.
+    async fn apps_link(&self, url: &url::Url, data: Vec<u8>) -> Result<()> {
+        let api = ClientBuilder::new()
+            .set_url(url.as_str())
+            .build()
+            .await?
+            .to_runtime_api::<RuntimeApi>();
+        let metadata = api.client.metadata();
+        let call = encode_call(metadata, &self.contract_call(data))?;
+        display_apps_link(&self.extrinsic_opts, call)
+    }
+
+    /// Prints the changes of the storage the call would make, dry-run by the fork at `url`.
+    async fn storage_diff(
+        &self,
+        url: &url::Url,
+        data: Vec<u8>,
+        signer: &ExtrinsicSigner,
+        transcoder: &ContractMessageTranscoder<'_>,
+        layout: &Layout<PortableForm>,
+    ) -> Result<()> {
+        let api = ClientBuilder::new()
+            .set_url(url.as_str())
+            .build()
+            .await?
+            .to_runtime_api::<RuntimeApi>();
+        let call = encode_call(api.client.metadata(), &self.contract_call(data))?;
+        display_storage_diff(
+            url,
+            &self.contract,
+            call,
+            signer.account_id(),
+            transcoder,
+            layout,
+        )
+        .await
+    }
+
+    fn contract_call(&self, data: Vec<u8>) -> api::contracts::calls::Call {
+        api::contracts::calls::Call {
+            dest: self.contract.clone().into(),
+            value: self.value,
+            gas_limit: self.gas_limit,
+            storage_deposit_limit: self.extrinsic_opts.storage_deposit_limit,
+            data,
         }
-        display_contract_exec_result(&result)?;
-        Ok(())
     }
 
-    async fn call(
+    pub(super) async fn call(
         &self,
+        url: &url::Url,
         data: Vec<u8>,
-        signer: &PairSigner,
+        signer: &ExtrinsicSigner,
         transcoder: &ContractMessageTranscoder<'_>,
+        mut session: Option<&mut Session>,
     ) -> Result<()> {
-        let url = self.extrinsic_opts.url.to_string();
         let api = ClientBuilder::new()
-            .set_url(&url)
+            .set_url(url.as_str())
             .build()
             .await?
             .to_runtime_api::<RuntimeApi>();
 
         log::debug!("calling contract {:?}", self.contract);
-        let tx_progress = api
-            .tx()
-            .contracts()
-            .call(
-                self.contract.clone().into(),
-                self.value,
-                self.gas_limit,
-                self.extrinsic_opts.storage_deposit_limit,
-                data,
-            )
-            .sign_and_submit_then_watch(signer)
+        let cost = self
+            .estimate_cost(url, data.clone(), signer, session.as_deref_mut())
             .await?;
-
-        let result = wait_for_success_and_handle_error(tx_progress).await?;
+        let call = self.contract_call(data);
+        let opts = &self.extrinsic_opts;
+        let encoded = encode_call(api.client.metadata(), &call)?;
+        let result = match self.schedule_at {
+            Some(when) => {
+                let scheduled =
+                    schedule_call(&api, &call, when, signer.account_id(), opts.sudo).await?;
+                submit_extrinsic(&api, scheduled, signer, opts, cost).await?
+            }
+            None => submit_extrinsic(&api, call, signer, opts, cost).await?,
+        };
+        if let Some(session) = session {
+            session.record_extrinsic(encoded, &result)?;
+        }
 
         display_events(
             &result,
             transcoder,
             api.client.metadata(),
             &self.extrinsic_opts.verbosity()?,
+        )?;
+        if let Some(when) = self.schedule_at {
+            name_value_println!("Scheduled", format!("at block #{}", when));
+        }
+        display_explorer_links(
+            &self.extrinsic_opts,
+            &[
+                ExplorerPage::Extrinsic(result.extrinsic_hash()),
+                ExplorerPage::Block(result.block_hash()),
+            ],
         )
     }
 }
 
+/// Print to stdout the result of a `call` dry-run via RPC, decoding the return value of `message`.
+pub(super) fn display_call_result(
+    result: &ContractExecResult,
+    transcoder: &ContractMessageTranscoder<'_>,
+    message: &str,
+) -> Result<()> {
+    match result.result {
+        Ok(ref ret_val) => {
+            let value = transcoder.decode_return(message, &mut &ret_val.data.0[..])?;
+            name_value_println!(
+                "Result",
+                String::from("Success!"),
+                EXEC_RESULT_MAX_KEY_COL_WIDTH
+            );
+            name_value_println!(
+                "Reverted",
+                format!("{:?}", ret_val.did_revert()),
+                EXEC_RESULT_MAX_KEY_COL_WIDTH
+            );
+            name_value_println!("Data", format!("{}", value), EXEC_RESULT_MAX_KEY_COL_WIDTH);
+        }
+        Err(ref err) => {
+            name_value_println!(
+                "Result",
+                format!("Error: {:?}", err),
+                EXEC_RESULT_MAX_KEY_COL_WIDTH
+            );
+        }
+    }
+    display_contract_exec_result(result)?;
+    Ok(())
+}
+
+/// Fails with the exit code of a reverted or failed dry-run if the call did not succeed.
+pub(super) fn ensure_call_succeeded(result: &ContractExecResult) -> Result<()> {
+    match result.result {
+        Ok(ref ret_val) if ret_val.did_revert() => Err(classify(
+            anyhow!("The contract reverted"),
+            ExitCode::Reverted,
+        )),
+        Ok(_) => Ok(()),
+        Err(ref err) => Err(classify(
+            anyhow!("The dry-run failed: {:?}", err),
+            ExitCode::Dispatch,
+        )),
+    }
+}
+
 /// A struct that encodes RPC parameters required for a call to a smart contract.
 ///
 /// Copied from `pallet-contracts-rpc`.
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcCallRequest {
-    origin: <DefaultConfig as Config>::AccountId,
-    dest: <DefaultConfig as Config>::AccountId,
-    value: NumberOrHex,
-    gas_limit: NumberOrHex,
-    storage_deposit_limit: Option<NumberOrHex>,
-    input_data: Bytes,
+    pub(super) origin: <DefaultConfig as Config>::AccountId,
+    pub(super) dest: <DefaultConfig as Config>::AccountId,
+    pub(super) value: NumberOrHex,
+    pub(super) gas_limit: NumberOrHex,
+    pub(super) storage_deposit_limit: Option<NumberOrHex>,
+    pub(super) input_data: Bytes,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_is_parsed_from_hash_or_number() {
+        assert_eq!(
+            "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d"
+                .parse::<BlockRef>()
+                .unwrap(),
+            BlockRef::Hash(
+                parse_code_hash(
+                    "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d"
+                )
+                .unwrap()
+            )
+        );
+        assert_eq!("1234".parse::<BlockRef>().unwrap(), BlockRef::Number(1234));
+        assert!("latest".parse::<BlockRef>().is_err());
+    }
 }
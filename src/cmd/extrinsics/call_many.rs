@@ -0,0 +1,500 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Calling a message of a contract once per row of a CSV file, e.g. for airdrops.
+//!
+//! The calls of `--batch-size` rows are submitted in one `Utility::batch`, and `--txs-per-block`
+//! extrinsics are submitted at once with consecutive nonces, before waiting for their inclusion.
+//! The outcome of every row is recorded in a progress file by the hash of its call, so that an
+//! interrupted run resumes with the rows which have not succeeded yet, even if rows were added or
+//! reordered in the meantime.
+
+use super::{
+    dispatch_error::describe,
+    encode_call,
+    fees::Cost,
+    load_metadata,
+    nonce::Nonce,
+    runtime_api::api,
+    submission::{submit, submit_extrinsic, EncodedCall},
+    Balance, ContractAccount, ContractMessageTranscoder, ExtrinsicOpts, ExtrinsicSigner,
+    RuntimeApi, RuntimeDispatchError, RuntimeEvent,
+};
+use crate::{address_book::parse_address, cmd::interface::message_name, name_value_println};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use scale::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use sp_core::hashing::blake2_256;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use subxt::{Call, ClientBuilder, DefaultConfig, Metadata, Signer, TransactionEvents};
+
+#[derive(Debug, clap::Args)]
+#[clap(
+    name = "call-many",
+    about = "Call a contract message once per row of a CSV file"
+)]
+pub struct CallManyCommand {
+    /// The address of the the contract to call, or its alias in the address book.
+    #[clap(long, env = "CONTRACT", parse(try_from_str = parse_address))]
+    contract: ContractAccount,
+    /// The name of the contract message to call.
+    #[clap(long, short)]
+    message: String,
+    /// The arguments of the calls, one row per call. The header names the arguments of the
+    /// message, e.g. `to,value`.
+    #[clap(long, parse(from_os_str))]
+    csv: PathBuf,
+    /// The number of calls submitted in one `Utility::batch`.
+    #[clap(long, default_value = "1")]
+    batch_size: usize,
+    /// The number of extrinsics submitted before waiting for their inclusion in a block.
+    #[clap(long, default_value = "1")]
+    txs_per_block: usize,
+    /// The file recording the outcome of every row, to resume an interrupted run. Defaults to
+    /// the `--csv` with the extension `progress.json`.
+    #[clap(long, parse(from_os_str))]
+    progress: Option<PathBuf>,
+    #[clap(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// Maximum amount of gas to be used for each call.
+    #[clap(name = "gas", long, default_value = "50000000000")]
+    gas_limit: u64,
+}
+
+/// A row of the CSV file with the encoded call of the message.
+struct Row {
+    /// The line of the row in the CSV file.
+    line: usize,
+    /// Identifies the row in the progress file, see [`row_keys`].
+    key: String,
+    data: Vec<u8>,
+}
+
+/// The outcome of the rows of a CSV file, by their key.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Progress {
+    /// The rows whose calls succeeded.
+    succeeded: BTreeSet<String>,
+    /// The error of each row whose call failed, which is retried when resuming.
+    failed: BTreeMap<String, String>,
+}
+
+impl Progress {
+    fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let progress =
+            fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&progress).context(format!("Failed to parse {}", path.display()))
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let progress = serde_json::to_string_pretty(self)? + "\n";
+        fs::write(path, progress).context(format!("Failed to write {}", path.display()))
+    }
+
+    /// Records the `outcome` of the rows of a submitted batch.
+    fn record(&mut self, rows: &[&Row], outcome: BatchOutcome) {
+        let (succeeded, error) = match outcome {
+            BatchOutcome::Completed => (rows.len(), None),
+            BatchOutcome::Interrupted { index, error } => (index.min(rows.len()), Some(error)),
+            BatchOutcome::Failed(error) => (0, Some(error)),
+        };
+        let (succeeded, failed) = rows.split_at(succeeded);
+        for row in succeeded {
+            self.failed.remove(&row.key);
+            self.succeeded.insert(row.key.clone());
+        }
+        if let (Some((first, rest)), Some(error)) = (failed.split_first(), error) {
+            self.failed.insert(first.key.clone(), error);
+            // the calls after an interrupted one are not executed
+            for row in rest {
+                let error = format!("Not executed, the call of line {} failed", first.line);
+                self.failed.insert(row.key.clone(), error);
+            }
+        }
+    }
+}
+
+/// The outcome of a submitted batch of calls.
+#[derive(Debug)]
+enum BatchOutcome {
+    Completed,
+    /// The call at `index` failed, the calls after it were not executed.
+    Interrupted {
+        index: usize,
+        error: String,
+    },
+    /// The extrinsic failed.
+    Failed(String),
+}
+
+/// `Utility::batch`, with the calls encoded already.
+#[derive(Clone, Debug, Encode)]
+struct BatchCall(Vec<EncodedCall>);
+
+impl Call for BatchCall {
+    const PALLET: &'static str = "Utility";
+    const FUNCTION: &'static str = "batch";
+}
+
+impl CallManyCommand {
+    pub fn run(&self) -> Result<()> {
+        if self.batch_size == 0 || self.txs_per_block == 0 {
+            anyhow::bail!("`--batch-size` and `--txs-per-block` must be at least 1")
+        }
+        if self.extrinsic_opts.dry_run || self.extrinsic_opts.sudo {
+            anyhow::bail!("`call-many` does not support `--dry-run` and `--sudo`")
+        }
+        let (_, project, _) = load_metadata(self.extrinsic_opts.manifest_path.as_ref())?;
+        let transcoder = ContractMessageTranscoder::new(&project);
+        let labels = project
+            .spec()
+            .messages()
+            .iter()
+            .find(|message| message_name(message.label()) == self.message)
+            .context(format!("No message `{}` in the metadata", self.message))?
+            .args()
+            .iter()
+            .map(|arg| arg.label().as_str())
+            .collect::<Vec<_>>();
+
+        let csv = fs::read_to_string(&self.csv)
+            .context(format!("Failed to read {}", self.csv.display()))?;
+        let calls = parse_rows(&csv, &labels)
+            .context(format!("Failed to parse {}", self.csv.display()))?
+            .into_iter()
+            .map(|(line, args)| {
+                let data = transcoder
+                    .encode(&self.message, &args)
+                    .context(format!("Failed to encode the call of line {}", line))?;
+                Ok((line, data))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let keys = row_keys(calls.iter().map(|(_, data)| &data[..]));
+        let rows = calls
+            .into_iter()
+            .zip(keys)
+            .map(|((line, data), key)| Row { line, key, data })
+            .collect::<Vec<_>>();
+
+        let progress_path = self
+            .progress
+            .clone()
+            .unwrap_or_else(|| self.csv.with_extension("progress.json"));
+        let mut progress = Progress::load(&progress_path)?;
+        let pending = rows
+            .iter()
+            .filter(|row| !progress.succeeded.contains(&row.key))
+            .collect::<Vec<_>>();
+        if pending.len() < rows.len() {
+            name_value_println!(
+                "Resuming",
+                format!(
+                    "{} of {} rows succeeded already",
+                    rows.len() - pending.len(),
+                    rows.len()
+                )
+            );
+        }
+
+        let signer = self.extrinsic_opts.extrinsic_signer()?;
+        async_std::task::block_on(async {
+            let url = self.extrinsic_opts.url()?;
+            let api = ClientBuilder::new()
+                .set_url(url.as_str())
+                .build()
+                .await?
+                .to_runtime_api::<RuntimeApi>();
+            let batches = pending.chunks(self.batch_size).collect::<Vec<_>>();
+            let mut submitted = 0;
+            for wave in batches.chunks(self.txs_per_block) {
+                let mut nonce = api
+                    .client
+                    .rpc()
+                    .system_account_next_index(signer.account_id())
+                    .await?;
+                let mut submissions = Vec::new();
+                for batch in wave {
+                    submissions.push(self.submit_batch(&api, batch, &signer, nonce));
+                    nonce += 1;
+                }
+                let outcomes = futures::future::join_all(submissions).await;
+                for (batch, outcome) in wave.iter().zip(outcomes) {
+                    progress.record(batch, outcome);
+                    submitted += batch.len();
+                }
+                progress.save(&progress_path)?;
+                log::info!("Submitted {} of {} pending rows", submitted, pending.len());
+            }
+            Ok::<_, anyhow::Error>(())
+        })?;
+
+        let succeeded = rows
+            .iter()
+            .filter(|row| progress.succeeded.contains(&row.key))
+            .count();
+        name_value_println!("Succeeded", format!("{} of {} rows", succeeded, rows.len()));
+        name_value_println!("Progress", progress_path.display().to_string());
+        if succeeded == rows.len() {
+            return Ok(());
+        }
+        for row in &rows {
+            if let Some(error) = progress.failed.get(&row.key) {
+                eprintln!("{} line {}: {}", "Failed".red().bold(), row.line, error);
+            }
+        }
+        anyhow::bail!(
+            "{} rows did not succeed, run the command again to retry them",
+            rows.len() - succeeded
+        )
+    }
+
+    /// Submits the calls of the `rows`, in a `Utility::batch` if there is more than one.
+    async fn submit_batch(
+        &self,
+        api: &RuntimeApi,
+        rows: &[&Row],
+        signer: &ExtrinsicSigner,
+        nonce: u32,
+    ) -> BatchOutcome {
+        let mut opts = self.extrinsic_opts.clone();
+        opts.nonce = Some(Nonce::Fixed(nonce));
+        let cost = Cost {
+            value: 0,
+            deposit: 0,
+        };
+        let result = match rows {
+            [row] => submit_extrinsic(api, self.contract_call(row), signer, &opts, cost).await,
+            rows => {
+                let calls = rows
+                    .iter()
+                    .map(|row| encode_call(api.client.metadata(), &self.contract_call(row)))
+                    .collect::<Result<Vec<_>>>();
+                match calls {
+                    Ok(calls) => {
+                        let batch = BatchCall(calls.into_iter().map(EncodedCall).collect());
+                        submit(api, || Ok(batch.clone()), signer, &opts, cost).await
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        };
+        match result {
+            Ok(events) => batch_outcome(api.client.metadata(), &events),
+            Err(err) => BatchOutcome::Failed(format!("{:#}", err)),
+        }
+    }
+
+    fn contract_call(&self, row: &Row) -> api::contracts::calls::Call {
+        api::contracts::calls::Call {
+            dest: self.contract.clone().into(),
+            value: Balance::default(),
+            gas_limit: self.gas_limit,
+            storage_deposit_limit: self.extrinsic_opts.storage_deposit_limit,
+            data: row.data.clone(),
+        }
+    }
+}
+
+/// The `Utility::batch` extrinsic succeeds even if one of its calls fails, which is reported with
+/// the `BatchInterrupted` event instead.
+fn batch_outcome(
+    metadata: &Metadata,
+    events: &TransactionEvents<'_, DefaultConfig, RuntimeEvent>,
+) -> BatchOutcome {
+    for event in events.iter_raw() {
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => return BatchOutcome::Failed(err.to_string()),
+        };
+        if event.pallet == "Utility" && event.variant == "BatchInterrupted" {
+            return match <(u32, RuntimeDispatchError)>::decode(&mut &event.data[..]) {
+                Ok((index, error)) => BatchOutcome::Interrupted {
+                    index: index as usize,
+                    error: describe(metadata, &error),
+                },
+                Err(err) => BatchOutcome::Failed(err.to_string()),
+            };
+        }
+    }
+    BatchOutcome::Completed
+}
+
+/// Returns the keys of the rows with the encoded `calls`, i.e. the hash of the call and how many
+/// rows with the same call precede the row, so that identical rows are still called once each.
+fn row_keys<'a>(calls: impl Iterator<Item = &'a [u8]>) -> Vec<String> {
+    let mut occurrences = BTreeMap::new();
+    calls
+        .map(|call| {
+            let hash = blake2_256(call);
+            let occurrence = occurrences.entry(hash).or_insert(0);
+            *occurrence += 1;
+            format!("0x{}#{}", hex::encode(hash), occurrence)
+        })
+        .collect()
+}
+
+/// Parses the rows of the `csv` into the arguments named by `labels`, along with their lines.
+///
+/// Fields can be quoted with `"`, e.g. to contain commas, and quotes are escaped as `""`.
+fn parse_rows(csv: &str, labels: &[&str]) -> Result<Vec<(usize, Vec<String>)>> {
+    let mut lines = csv
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines.next().context("The CSV file is empty")?;
+    let header = split_fields(header)?;
+    let columns = labels
+        .iter()
+        .map(|label| {
+            header
+                .iter()
+                .position(|column| column == label)
+                .context(format!("No column `{}` in the header", label))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if header.len() != labels.len() {
+        anyhow::bail!(
+            "The header has {} columns, but the message takes `{}`",
+            header.len(),
+            labels.join(",")
+        )
+    }
+    lines
+        .map(|(index, line)| {
+            let fields = split_fields(line).context(format!("Line {}", index + 1))?;
+            if fields.len() != header.len() {
+                anyhow::bail!(
+                    "Line {} has {} columns, expected {}",
+                    index + 1,
+                    fields.len(),
+                    header.len()
+                )
+            }
+            let args = columns
+                .iter()
+                .map(|column| fields[*column].clone())
+                .collect();
+            Ok((index + 1, args))
+        })
+        .collect()
+}
+
+fn split_fields(line: &str) -> Result<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => fields.push(std::mem::take(&mut field).trim().to_string()),
+            (c, _) => field.push(c),
+        }
+    }
+    if quoted {
+        anyhow::bail!("Unterminated quote")
+    }
+    fields.push(field.trim().to_string());
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_parsed_and_progress_is_resumable() {
+        let csv = "value,to\n\
+                   100,5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY\n\
+                   \n\
+                   \"2_000\",\"alice\"\n";
+        let rows = parse_rows(csv, &["to", "value"]).unwrap();
+        assert_eq!(
+            rows,
+            [
+                (
+                    2,
+                    vec![
+                        "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
+                        "100".to_string()
+                    ]
+                ),
+                (4, vec!["alice".to_string(), "2_000".to_string()]),
+            ]
+        );
+        assert!(parse_rows("to\nalice", &["to", "value"]).is_err());
+        assert_eq!(
+            split_fields(r#""(1, ""a"")",2"#).unwrap(),
+            [r#"(1, "a")"#, "2"]
+        );
+
+        let calls: [&[u8]; 6] = [&[1], &[2], &[3], &[4], &[5], &[1]];
+        let keys = row_keys(calls.into_iter());
+        assert_ne!(keys[0], keys[5], "identical rows must be called once each");
+        // the rows are identified by their call, not their line
+        assert_eq!(row_keys([&[9u8][..], &[1u8][..]].into_iter())[1], keys[0]);
+        let rows = keys
+            .into_iter()
+            .enumerate()
+            .map(|(line, key)| Row {
+                line: line + 2,
+                key,
+                data: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        let key = |index: usize| rows[index].key.clone();
+
+        let mut progress = Progress::default();
+        progress.record(
+            &[&rows[0], &rows[1], &rows[2]],
+            BatchOutcome::Interrupted {
+                index: 1,
+                error: "ContractReverted".into(),
+            },
+        );
+        progress.record(
+            &[&rows[3], &rows[4]],
+            BatchOutcome::Failed("Inability to pay some fees".into()),
+        );
+        assert_eq!(progress.succeeded, BTreeSet::from([key(0)]));
+        assert_eq!(progress.failed[&key(1)], "ContractReverted");
+        assert_eq!(
+            progress.failed[&key(2)],
+            "Not executed, the call of line 3 failed"
+        );
+        assert_eq!(progress.failed[&key(3)], "Inability to pay some fees");
+        assert_eq!(progress.failed[&key(4)], "Inability to pay some fees");
+
+        progress.record(
+            &[&rows[1], &rows[2], &rows[3], &rows[4], &rows[5]],
+            BatchOutcome::Completed,
+        );
+        assert_eq!(progress.succeeded.len(), 6);
+        assert!(progress.failed.is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{node_url, upload::determinism_variants, RuntimeApi};
+use crate::name_value_println;
+use anyhow::{Context, Result};
+use scale::{Compact, Decode};
+use scale_info::{PortableRegistry, TypeDef, TypeDefPrimitive};
+use std::path::PathBuf;
+use subxt::ClientBuilder;
+
+/// The name of `pallet-contracts` in the runtime metadata.
+const CONTRACTS_PALLET: &str = "Contracts";
+
+/// The weights of the schedule, one per instruction and host function, which are only printed with
+/// `--all`.
+const SCHEDULE_WEIGHTS: [&str; 2] = ["Schedule.instruction_weights.", "Schedule.host_fn_weights."];
+
+#[derive(Debug, clap::Args)]
+#[clap(
+    name = "chain-info",
+    about = "Print the pallet-contracts configuration of a chain"
+)]
+pub struct ChainInfoCommand {
+    /// Websockets url of a substrate node. Defaults to the url of the `--network`, or to
+    /// `ws://localhost:9944`.
+    #[clap(long, parse(try_from_str))]
+    url: Option<url::Url>,
+    /// The name of a network profile from the `cargo-contract` configuration file, or of a
+    /// profile with an `endpoint` in `[package.metadata.contract.profiles]` of the contract.
+    #[clap(long)]
+    network: Option<String>,
+    /// Path to the `Cargo.toml` of the contract, whose profiles are considered for `--network`.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// Also print the weight of every instruction and host function of the schedule.
+    #[clap(long)]
+    all: bool,
+}
+
+impl ChainInfoCommand {
+    pub fn run(&self) -> Result<()> {
+        let url = node_url(
+            self.url.as_ref(),
+            self.network.as_deref(),
+            self.manifest_path.as_ref(),
+        )?;
+        async_std::task::block_on(async {
+            let api = ClientBuilder::new()
+                .set_url(url.as_str())
+                .build()
+                .await?
+                .to_runtime_api::<RuntimeApi>();
+            let version = api.client.rpc().runtime_version(None).await?;
+            let metadata = api.client.metadata();
+
+            name_value_println!("Url", url.to_string());
+            if let Some(spec_name) = version.other.get("specName").and_then(|name| name.as_str()) {
+                name_value_println!("Runtime", spec_name.to_string());
+            }
+            name_value_println!("Spec version", version.spec_version.to_string());
+            let determinism = match determinism_variants(metadata) {
+                Some(variants) => variants.join(", "),
+                None => "Enforced only".to_string(),
+            };
+            name_value_println!("Determinism", determinism);
+
+            let runtime = metadata.runtime_metadata();
+            let pallet = runtime
+                .pallets
+                .iter()
+                .find(|pallet| pallet.name == CONTRACTS_PALLET)
+                .context(format!("The runtime has no `{}` pallet", CONTRACTS_PALLET))?;
+            let mut constants = Vec::new();
+            for constant in &pallet.constants {
+                decode_value(
+                    &runtime.types,
+                    constant.ty.id(),
+                    &mut &constant.value[..],
+                    &constant.name,
+                    &mut constants,
+                )
+                .context(format!("Failed to decode the constant `{}`", constant.name))?;
+            }
+            constants.retain(|(name, _)| self.all || !is_schedule_weight(name));
+            let width = constants
+                .iter()
+                .map(|(name, _)| name.len())
+                .max()
+                .unwrap_or(0);
+            println!();
+            for (name, value) in constants {
+                name_value_println!(name, value, width);
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Returns `true` for the weights of the instructions and host functions of the schedule, but not
+/// for the version of the instruction weights, which changes whenever they do.
+fn is_schedule_weight(name: &str) -> bool {
+    name != "Schedule.instruction_weights.version"
+        && SCHEDULE_WEIGHTS
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+/// Decodes the value of the type `id` from the `input`, and appends it to `values` as pairs of a
+/// path and the displayed value, e.g. `Schedule.limits.memory_pages` and `16`.
+fn decode_value(
+    registry: &PortableRegistry,
+    id: u32,
+    input: &mut &[u8],
+    path: &str,
+    values: &mut Vec<(String, String)>,
+) -> Result<()> {
+    let ty = registry
+        .resolve(id)
+        .context(format!("Type {} not found in the type registry", id))?;
+    match ty.type_def() {
+        TypeDef::Primitive(primitive) => {
+            values.push((path.to_string(), primitive_value(primitive, input)?))
+        }
+        TypeDef::Compact(_) => {
+            let value = Compact::<u128>::decode(input)?.0;
+            values.push((path.to_string(), value.to_string()))
+        }
+        TypeDef::Composite(composite) => {
+            for (index, field) in composite.fields().iter().enumerate() {
+                let name = field.name().cloned().unwrap_or_else(|| index.to_string());
+                let path = match composite.fields().len() {
+                    // newtypes such as `Weight(u64)` are displayed as their inner value
+                    1 if field.name().is_none() => path.to_string(),
+                    _ => format!("{}.{}", path, name),
+                };
+                decode_value(registry, field.ty().id(), input, &path, values)?;
+            }
+        }
+        TypeDef::Variant(variant) => {
+            let index = u8::decode(input)?;
+            let variant = variant
+                .variants()
+                .iter()
+                .find(|variant| variant.index() == index)
+                .context(format!("Invalid variant index {} of {}", index, path))?;
+            if variant.fields().is_empty() {
+                values.push((path.to_string(), variant.name().clone()));
+            }
+            for field in variant.fields() {
+                let name = field
+                    .name()
+                    .cloned()
+                    .unwrap_or_else(|| variant.name().clone());
+                decode_value(
+                    registry,
+                    field.ty().id(),
+                    input,
+                    &format!("{}.{}", path, name),
+                    values,
+                )?;
+            }
+        }
+        TypeDef::Sequence(sequence) => {
+            let len = Compact::<u32>::decode(input)?.0 as usize;
+            decode_elements(
+                registry,
+                sequence.type_param().id(),
+                len,
+                input,
+                path,
+                values,
+            )?;
+        }
+        TypeDef::Array(array) => {
+            decode_elements(
+                registry,
+                array.type_param().id(),
+                array.len() as usize,
+                input,
+                path,
+                values,
+            )?;
+        }
+        TypeDef::Tuple(tuple) => {
+            for (index, field) in tuple.fields().iter().enumerate() {
+                decode_value(
+                    registry,
+                    field.id(),
+                    input,
+                    &format!("{}.{}", path, index),
+                    values,
+                )?;
+            }
+        }
+        TypeDef::BitSequence(_) => anyhow::bail!("Bit sequences are not supported"),
+    }
+    Ok(())
+}
+
+/// Decodes `len` elements of the type `element`, bytes are displayed as hex.
+fn decode_elements(
+    registry: &PortableRegistry,
+    element: u32,
+    len: usize,
+    input: &mut &[u8],
+    path: &str,
+    values: &mut Vec<(String, String)>,
+) -> Result<()> {
+    let ty = registry
+        .resolve(element)
+        .context(format!("Type {} not found in the type registry", element))?;
+    if let TypeDef::Primitive(TypeDefPrimitive::U8) = ty.type_def() {
+        if input.len() < len {
+            anyhow::bail!("Not enough data to decode {}", path)
+        }
+        let (bytes, rest) = input.split_at(len);
+        values.push((path.to_string(), format!("0x{}", hex::encode(bytes))));
+        *input = rest;
+        return Ok(());
+    }
+    for index in 0..len {
+        decode_value(
+            registry,
+            element,
+            input,
+            &format!("{}[{}]", path, index),
+            values,
+        )?;
+    }
+    Ok(())
+}
+
+fn primitive_value(primitive: &TypeDefPrimitive, input: &mut &[u8]) -> Result<String> {
+    let value = match primitive {
+        TypeDefPrimitive::Bool => bool::decode(input)?.to_string(),
+        TypeDefPrimitive::Char => anyhow::bail!("Chars are not supported"),
+        TypeDefPrimitive::Str => String::decode(input)?,
+        TypeDefPrimitive::U8 => u8::decode(input)?.to_string(),
+        TypeDefPrimitive::U16 => u16::decode(input)?.to_string(),
+        TypeDefPrimitive::U32 => u32::decode(input)?.to_string(),
+        TypeDefPrimitive::U64 => u64::decode(input)?.to_string(),
+        TypeDefPrimitive::U128 => u128::decode(input)?.to_string(),
+        TypeDefPrimitive::U256 => format!("0x{}", hex::encode(<[u8; 32]>::decode(input)?)),
+        TypeDefPrimitive::I8 => i8::decode(input)?.to_string(),
+        TypeDefPrimitive::I16 => i16::decode(input)?.to_string(),
+        TypeDefPrimitive::I32 => i32::decode(input)?.to_string(),
+        TypeDefPrimitive::I64 => i64::decode(input)?.to_string(),
+        TypeDefPrimitive::I128 => i128::decode(input)?.to_string(),
+        TypeDefPrimitive::I256 => format!("0x{}", hex::encode(<[u8; 32]>::decode(input)?)),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale::Encode;
+
+    #[derive(Encode, scale_info::TypeInfo)]
+    struct Limits {
+        memory_pages: u32,
+        code_len: u32,
+    }
+
+    #[derive(Encode, scale_info::TypeInfo)]
+    struct InstructionWeights {
+        version: u32,
+        i64const: u64,
+    }
+
+    #[derive(Encode, scale_info::TypeInfo)]
+    struct Schedule {
+        limits: Limits,
+        instruction_weights: InstructionWeights,
+        salt: Vec<u8>,
+        deletion: Option<u64>,
+    }
+
+    #[test]
+    fn constants_are_decoded_with_their_path() {
+        let mut registry = scale_info::Registry::new();
+        let id = registry
+            .register_type(&scale_info::meta_type::<Schedule>())
+            .id();
+        let registry = PortableRegistry::from(registry);
+        let schedule = Schedule {
+            limits: Limits {
+                memory_pages: 16,
+                code_len: 128 * 1024,
+            },
+            instruction_weights: InstructionWeights {
+                version: 3,
+                i64const: 1_500,
+            },
+            salt: vec![0xca, 0xfe],
+            deletion: Some(42),
+        };
+
+        let mut values = Vec::new();
+        decode_value(
+            &registry,
+            id,
+            &mut &schedule.encode()[..],
+            "Schedule",
+            &mut values,
+        )
+        .unwrap();
+        let values = values
+            .iter()
+            .map(|(path, value)| (path.as_str(), value.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [
+                ("Schedule.limits.memory_pages", "16"),
+                ("Schedule.limits.code_len", "131072"),
+                ("Schedule.instruction_weights.version", "3"),
+                ("Schedule.instruction_weights.i64const", "1500"),
+                ("Schedule.salt", "0xcafe"),
+                ("Schedule.deletion.Some", "42"),
+            ]
+        );
+        assert!(!is_schedule_weight(values[2].0));
+        assert!(is_schedule_weight(values[3].0));
+    }
+}
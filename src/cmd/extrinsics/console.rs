@@ -0,0 +1,407 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An interactive console to call the messages of a contract.
+//!
+//! Each line is a message with its arguments, which is dry-run if it does not mutate the storage
+//! and submitted otherwise, like `cargo contract call`. Return values can be assigned to
+//! variables with `let`, which are substituted for `$name` in later lines. The history and the
+//! variables are kept in the configuration directory across sessions.
+
+use super::{
+    load_metadata, parse_balance, Balance, CallCommand, ContractMessageTranscoder, ExtrinsicOpts,
+};
+use crate::{address_book::parse_address, config::config_dir, name_value_println, output};
+use anyhow::{Context, Result};
+use ink_metadata::{InkProject, MessageSpec};
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    validate::Validator, Editor, Helper,
+};
+use scale_info::form::PortableForm;
+use std::{collections::BTreeMap, fs, path::PathBuf};
+use subxt::{Config, DefaultConfig};
+
+const HISTORY_FILE: &str = "console_history";
+const VARIABLES_FILE: &str = "console_variables.json";
+/// The variable holding the result of the last dry-run.
+const LAST_RESULT: &str = "_";
+const COMMANDS: [&str; 6] = ["help", "messages", "use", "let", "vars", "exit"];
+
+/// Start an interactive console to call the messages of a contract.
+#[derive(Debug, clap::Args)]
+#[clap(name = "console")]
+pub struct ConsoleCommand {
+    /// The address of the contract to call, or its alias in the address book. Can be changed
+    /// with `use <address>` in the console.
+    #[clap(long, env = "CONTRACT", parse(try_from_str = parse_address))]
+    contract: Option<<DefaultConfig as Config>::AccountId>,
+    #[clap(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// Maximum amount of gas to be used for each call.
+    #[clap(name = "gas", long, default_value = "50000000000")]
+    gas_limit: u64,
+    /// The value to be transferred as part of each call.
+    #[clap(name = "value", long, parse(try_from_str = parse_balance), default_value = "0")]
+    value: Balance,
+}
+
+/// Completes the commands of the console, the messages of the contract and the variables.
+struct ConsoleHelper {
+    words: Vec<String>,
+}
+
+impl Completer for ConsoleHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .rfind(|c: char| c.is_whitespace() || "([{,".contains(c))
+            .map_or(0, |index| index + 1);
+        let word = &line[start..pos];
+        let candidates = self
+            .words
+            .iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .cloned()
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ConsoleHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ConsoleHelper {}
+
+impl Validator for ConsoleHelper {}
+
+impl Helper for ConsoleHelper {}
+
+impl ConsoleCommand {
+    pub fn run(&self) -> Result<()> {
+        if !output::is_interactive() {
+            anyhow::bail!("`cargo contract console` requires a terminal")
+        }
+        let (_, project, _) = load_metadata(self.extrinsic_opts.manifest_path.as_ref())?;
+        let transcoder = ContractMessageTranscoder::new(&project);
+        let mut console = Console {
+            command: self,
+            project: &project,
+            transcoder,
+            contract: self.contract.clone(),
+            variables: load_variables()?,
+        };
+
+        let mut editor = Editor::<ConsoleHelper>::new();
+        editor.set_helper(Some(ConsoleHelper {
+            words: console.words(),
+        }));
+        let history = config_dir().map(|dir| dir.join(HISTORY_FILE));
+        if let Some(history) = &history {
+            // there is no history before the first session
+            let _ = editor.load_history(history);
+        }
+        println!("Enter a message with its arguments, or `help` for the commands of the console");
+
+        loop {
+            let line = match editor.readline("> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(err) => return Err(err.into()),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            editor.add_history_entry(line);
+            match console.execute(line) {
+                Ok(true) => (),
+                Ok(false) => break,
+                Err(err) => output::error(format!("{:#}", err)),
+            }
+            if let Some(helper) = editor.helper_mut() {
+                helper.words = console.words();
+            }
+        }
+
+        if let Some(history) = &history {
+            fs::create_dir_all(history.parent().expect("the history is in a directory"))?;
+            editor.save_history(history)?;
+        }
+        Ok(())
+    }
+}
+
+/// The state of a console session.
+struct Console<'a> {
+    command: &'a ConsoleCommand,
+    project: &'a InkProject,
+    transcoder: ContractMessageTranscoder<'a>,
+    contract: Option<<DefaultConfig as Config>::AccountId>,
+    variables: BTreeMap<String, String>,
+}
+
+impl<'a> Console<'a> {
+    /// Executes a line of the console, returns `false` to end the session.
+    fn execute(&mut self, line: &str) -> Result<bool> {
+        let words = split_args(&substitute(line, &self.variables)?)?;
+        let command = match words.first() {
+            Some(command) => command.as_str(),
+            None => return Ok(true),
+        };
+        match command {
+            "exit" | "quit" => return Ok(false),
+            "help" => print_help(),
+            "messages" => {
+                for message in self.project.spec().messages() {
+                    let args = message
+                        .args()
+                        .iter()
+                        .map(|arg| arg.label().to_string())
+                        .collect::<Vec<_>>();
+                    let kind = if message.mutates() { "mut" } else { "read" };
+                    name_value_println!(kind, format!("{}({})", message.label(), args.join(", ")));
+                }
+            }
+            "vars" => {
+                for (name, value) in &self.variables {
+                    let variable = format!("${}", name);
+                    name_value_println!(variable, value);
+                }
+            }
+            "use" => {
+                let address = words.get(1).context("Usage: use <address>")?;
+                self.contract = Some(parse_address(address)?);
+                name_value_println!("Contract", address);
+            }
+            "let" => {
+                let (name, expression) = match &words[1..] {
+                    [name, equals, expression @ ..] if equals == "=" && !expression.is_empty() => {
+                        (name, expression)
+                    }
+                    _ => anyhow::bail!("Usage: let <name> = <value> | <message> <args>"),
+                };
+                let is_message = self.message(&expression[0]).is_some();
+                let value = if is_message {
+                    self.call(&expression[0], &expression[1..])?
+                        .context("A submitted call has no result to assign")?
+                } else {
+                    expression.join(" ")
+                };
+                self.variables
+                    .insert(name.trim_start_matches('$').into(), value);
+                save_variables(&self.variables)?;
+            }
+            message => {
+                if let Some(value) = self.call(message, &words[1..])? {
+                    self.variables.insert(LAST_RESULT.into(), value);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Calls the `message`, returns its return value if it was dry-run.
+    fn call(&self, message: &str, args: &[String]) -> Result<Option<String>> {
+        let spec = self
+            .message(message)
+            .context(format!("No message `{}`, see `messages`", message))?;
+        let contract = self
+            .contract
+            .clone()
+            .context("No contract to call, pass `--contract` or enter `use <address>`")?;
+        let opts = &self.command.extrinsic_opts;
+        let call = CallCommand::new(
+            contract,
+            message.into(),
+            args.to_vec(),
+            opts.clone(),
+            self.command.gas_limit,
+            self.command.value,
+        );
+        let data = self.transcoder.encode(message, args)?;
+        let signer = opts.extrinsic_signer()?;
+        let url = opts.url()?;
+
+        async_std::task::block_on(async {
+            if spec.mutates() && !opts.dry_run {
+                call.call(&url, data, &signer, &self.transcoder, None)
+                    .await?;
+                return Ok(None);
+            }
+            let result = call
+                .call_rpc(&url, data, &signer, &self.transcoder, None)
+                .await?;
+            match result.result {
+                Ok(ret_val) => {
+                    let value = self
+                        .transcoder
+                        .decode_return(message, &mut &ret_val.data.0[..])?;
+                    Ok(Some(value.to_string()))
+                }
+                Err(_) => Ok(None),
+            }
+        })
+    }
+
+    fn message(&self, label: &str) -> Option<&MessageSpec<PortableForm>> {
+        self.project
+            .spec()
+            .messages()
+            .iter()
+            .find(|message| message.label() == label)
+    }
+
+    /// The words to complete.
+    fn words(&self) -> Vec<String> {
+        COMMANDS
+            .iter()
+            .map(ToString::to_string)
+            .chain(
+                self.project
+                    .spec()
+                    .messages()
+                    .iter()
+                    .map(|message| message.label().to_string()),
+            )
+            .chain(self.variables.keys().map(|name| format!("${}", name)))
+            .collect()
+    }
+}
+
+fn print_help() {
+    println!(
+        "<message> <args>               Dry-run a read-only message or submit a mutating one\n\
+         let <name> = <message> <args>  Assign the return value of a message to `$name`\n\
+         let <name> = <value>           Assign a value, e.g. an address, to `$name`\n\
+         use <address>                  Call the contract at the address or alias\n\
+         messages                       List the messages of the contract\n\
+         vars                           List the variables, `$_` is the last result\n\
+         exit                           End the session, as does Ctrl-D"
+    );
+}
+
+/// Replaces the variables `$name` in the `line` with their value.
+fn substitute(line: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    let mut substituted = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find('$') {
+        substituted.push_str(&rest[..start]);
+        let name_len = rest[start + 1..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len() - start - 1);
+        let name = &rest[start + 1..start + 1 + name_len];
+        // `let $name = ..` assigns the variable instead
+        if substituted.trim() == "let" {
+            substituted.push_str(name);
+        } else {
+            let value = variables
+                .get(name)
+                .context(format!("No variable `${}`, see `vars`", name))?;
+            substituted.push_str(value);
+        }
+        rest = &rest[start + 1 + name_len..];
+    }
+    substituted.push_str(rest);
+    Ok(substituted)
+}
+
+/// Splits a line at whitespace, except within quotes and brackets, e.g. of a string or tuple
+/// argument.
+fn split_args(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut depth = 0usize;
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' | '[' | '{' if !quoted => depth += 1,
+            ')' | ']' | '}' if !quoted => {
+                depth = depth
+                    .checked_sub(1)
+                    .context(format!("Unbalanced `{}`", c))?
+            }
+            c if c.is_whitespace() && !quoted && depth == 0 => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                continue;
+            }
+            _ => (),
+        }
+        word.push(c);
+    }
+    if quoted || depth > 0 {
+        anyhow::bail!("Unterminated argument `{}`", word)
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn variables_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(VARIABLES_FILE))
+}
+
+fn load_variables() -> Result<BTreeMap<String, String>> {
+    match variables_path() {
+        Some(path) if path.exists() => {
+            let json = fs::read_to_string(&path)?;
+            serde_json::from_str(&json).context(format!("Failed to parse {}", path.display()))
+        }
+        _ => Ok(BTreeMap::new()),
+    }
+}
+
+fn save_variables(variables: &BTreeMap<String, String>) -> Result<()> {
+    if let Some(path) = variables_path() {
+        fs::create_dir_all(path.parent().expect("the variables are in a directory"))?;
+        fs::write(path, serde_json::to_string_pretty(variables)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_are_substituted_and_split() {
+        let mut variables = BTreeMap::new();
+        variables.insert("bob".to_string(), "5FHneW46".to_string());
+        let line = substitute(r#"transfer $bob 100 "a memo" [1, 2]"#, &variables).unwrap();
+        assert_eq!(
+            split_args(&line).unwrap(),
+            ["transfer", "5FHneW46", "100", "\"a memo\"", "[1, 2]"]
+        );
+        assert_eq!(
+            split_args(&substitute("let $total = total_supply", &variables).unwrap()).unwrap(),
+            ["let", "total", "=", "total_supply"]
+        );
+        assert!(substitute("balance_of $alice", &variables).is_err());
+        assert!(split_args("transfer (1, 2").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The defaults of the trailing arguments of a constructor which are omitted in `--args`.
+//!
+//! An omitted argument is taken from `[package.metadata.contract.constructor-args.<constructor>]`
+//! of the contract, e.g.
+//!
+//! ```toml
+//! [package.metadata.contract.constructor-args.new]
+//! total_supply = "1_000_000"
+//! ```
+//!
+//! and otherwise it is the `Default` of its type, if that is the zero value of the type: `0`,
+//! `false`, `""`, `[]`, `()` or `None`. The metadata does not record whether a type of the
+//! contract implements `Default`, so there is no default of other types, e.g. of an `AccountId`.
+
+use crate::crate_metadata::CrateMetadata;
+use anyhow::Result;
+use ink_metadata::InkProject;
+use scale_info::{PortableRegistry, TypeDef, TypeDefPrimitive};
+use serde_json::Value;
+
+/// An argument of a constructor which was omitted and assumed to have a default.
+#[derive(Debug, PartialEq)]
+pub(super) struct AssumedArg {
+    pub(super) label: String,
+    pub(super) value: String,
+    pub(super) source: &'static str,
+}
+
+/// Returns `[package.metadata.contract.constructor-args]` of the contract, if any.
+pub(super) fn configured_args(crate_metadata: &CrateMetadata) -> Value {
+    crate_metadata
+        .root_package
+        .metadata
+        .pointer("/contract/constructor-args")
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+/// Returns the `args` of the `constructor` with the defaults of the omitted trailing arguments,
+/// and the assumed arguments.
+///
+/// `configured` are the arguments of `[package.metadata.contract.constructor-args]`.
+pub(super) fn complete_args(
+    project: &InkProject,
+    configured: &Value,
+    constructor: &str,
+    args: &[String],
+) -> Result<(Vec<String>, Vec<AssumedArg>)> {
+    // the transcoder reports if there is no such constructor
+    let spec = match project
+        .spec()
+        .constructors()
+        .iter()
+        .find(|spec| spec.label() == constructor)
+    {
+        Some(spec) => spec,
+        None => return Ok((args.to_vec(), Vec::new())),
+    };
+
+    let mut completed = args.to_vec();
+    let mut assumed = Vec::new();
+    let mut missing = Vec::new();
+    for arg in spec.args().iter().skip(args.len()) {
+        let label = arg.label().to_string();
+        let configured = configured
+            .get(spec.label())
+            .and_then(|args| args.get(&label));
+        let (value, source) = match configured {
+            Some(Value::String(value)) => (value.clone(), "Cargo.toml"),
+            Some(value) => (value.to_string(), "Cargo.toml"),
+            None => match type_default(project.registry(), arg.ty().ty().id()) {
+                Some(value) => (value, "default of the type"),
+                None => {
+                    missing.push(label);
+                    continue;
+                }
+            },
+        };
+        completed.push(value.clone());
+        assumed.push(AssumedArg {
+            label,
+            value,
+            source,
+        });
+    }
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "The arguments {} of the constructor `{}` have no default, pass them with `--args` or \
+             set them in `[package.metadata.contract.constructor-args.{}]`",
+            missing.join(", "),
+            spec.label(),
+            spec.label()
+        )
+    }
+    Ok((completed, assumed))
+}
+
+/// Returns the `Default` of the type with the `id` if it is the zero value of the type.
+fn type_default(registry: &PortableRegistry, id: u32) -> Option<String> {
+    let ty = registry.resolve(id)?;
+    match ty.type_def() {
+        TypeDef::Primitive(TypeDefPrimitive::Bool) => Some("false".into()),
+        TypeDef::Primitive(TypeDefPrimitive::Str) => Some("\"\"".into()),
+        TypeDef::Primitive(
+            TypeDefPrimitive::Char | TypeDefPrimitive::U256 | TypeDefPrimitive::I256,
+        ) => None,
+        TypeDef::Primitive(_) => Some("0".into()),
+        TypeDef::Compact(compact) => type_default(registry, compact.type_param().id()),
+        TypeDef::Sequence(_) => Some("[]".into()),
+        TypeDef::Tuple(tuple) if tuple.fields().is_empty() => Some("()".into()),
+        TypeDef::Variant(_) if is_option(ty.path().segments()) => Some("None".into()),
+        _ => None,
+    }
+}
+
+fn is_option(segments: &[String]) -> bool {
+    segments.len() == 1 && segments[0] == "Option"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn omitted_args_are_completed() {
+        let project: InkProject = serde_json::from_value(json!({
+            "spec": {
+                "constructors": [{
+                    "args": [
+                        { "label": "owner", "type": { "displayName": ["AccountId"], "type": 2 } },
+                        { "label": "total_supply", "type": { "displayName": ["u128"], "type": 0 } },
+                        { "label": "paused", "type": { "displayName": ["bool"], "type": 1 } },
+                    ],
+                    "docs": [],
+                    "label": "new",
+                    "payable": false,
+                    "selector": "0x9bae9d5e",
+                }],
+                "docs": [],
+                "events": [],
+                "messages": [],
+            },
+            "storage": { "struct": { "fields": [] } },
+            "types": [
+                { "id": 0, "type": { "def": { "primitive": "u128" } } },
+                { "id": 1, "type": { "def": { "primitive": "bool" } } },
+                { "id": 2, "type": {
+                    "def": { "composite": { "fields": [{ "type": 3 }] } },
+                    "path": ["ink_env", "types", "AccountId"],
+                } },
+                { "id": 3, "type": { "def": { "array": { "len": 32, "type": 4 } } } },
+                { "id": 4, "type": { "def": { "primitive": "u8" } } },
+            ],
+        }))
+        .unwrap();
+        let configured = json!({ "new": { "total_supply": 1000 } });
+
+        let (args, assumed) =
+            complete_args(&project, &configured, "new", &["ALICE".to_string()]).unwrap();
+        assert_eq!(args, ["ALICE", "1000", "false"]);
+        assert_eq!(
+            assumed,
+            [
+                AssumedArg {
+                    label: "total_supply".into(),
+                    value: "1000".into(),
+                    source: "Cargo.toml",
+                },
+                AssumedArg {
+                    label: "paused".into(),
+                    value: "false".into(),
+                    source: "default of the type",
+                },
+            ]
+        );
+
+        let error = complete_args(&project, &configured, "new", &[]).unwrap_err();
+        assert!(error.to_string().starts_with("The arguments owner of"));
+
+        // the label is compared exactly, `ne` is no constructor of the contract
+        let (args, assumed) = complete_args(&project, &configured, "ne", &[]).unwrap();
+        assert!(args.is_empty() && assumed.is_empty());
+    }
+}
@@ -0,0 +1,311 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    instantiate::instantiate_with_code, load_metadata, parse_balance, upload::upload_code, Balance,
+    ContractMessageTranscoder, ExtrinsicOpts,
+};
+use crate::{
+    cmd::graph::{ContractGraph, Dependency},
+    name_value_println,
+    workspace::ManifestPath,
+};
+use anyhow::{Context, Result};
+use cargo_metadata::MetadataCommand;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use sp_core::crypto::Ss58Codec;
+use std::{collections::BTreeMap, convert::TryFrom, path::PathBuf};
+
+/// Deploy the contracts of a workspace as described by a deployment script.
+///
+/// The contracts are deployed in the order of their dependencies: the constructor arguments of a
+/// contract may refer to the `${<contract>.address}` or `${<contract>.code_hash}` of other
+/// contracts of the script, which are deployed first and substituted into the arguments.
+#[derive(Debug, clap::Args)]
+#[clap(name = "deploy")]
+pub struct DeployCommand {
+    /// Path to the deployment script.
+    #[clap(parse(from_os_str), default_value = "deploy.toml")]
+    script: PathBuf,
+    #[clap(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// Maximum amount of gas to be used for every instantiation
+    #[clap(name = "gas", long, default_value = "50000000000")]
+    gas_limit: u64,
+}
+
+/// A deployment script, e.g.
+///
+/// ```toml
+/// [[contracts]]
+/// name = "erc20"
+/// args = ["1000000"]
+///
+/// [[contracts]]
+/// name = "dex"
+/// upload-only = true
+///
+/// [[contracts]]
+/// name = "factory"
+/// args = ["${erc20.address}", "${dex.code_hash}"]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct DeployScript {
+    contracts: Vec<ContractStep>,
+}
+
+/// The deployment of a contract of the workspace.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ContractStep {
+    /// The name of the contract package.
+    name: String,
+    /// The name of the constructor.
+    #[serde(default = "default_constructor")]
+    constructor: String,
+    /// The constructor arguments, which may contain references to other contracts.
+    #[serde(default)]
+    args: Vec<String>,
+    /// The balance transferred to the contract, e.g. `1 DOT`.
+    value: Option<String>,
+    /// Only upload the code of the contract, e.g. to instantiate it by its code hash.
+    #[serde(default)]
+    upload_only: bool,
+}
+
+fn default_constructor() -> String {
+    "new".to_string()
+}
+
+/// The code hash and address of a deployed contract.
+#[derive(Debug)]
+struct Deployed {
+    code_hash: String,
+    address: Option<String>,
+}
+
+impl DeployCommand {
+    pub fn run(&self) -> Result<()> {
+        let contents = std::fs::read_to_string(&self.script)
+            .context(format!("Failed to read {}", self.script.display()))?;
+        let script: DeployScript = toml::from_str(&contents)
+            .context(format!("Failed to parse {}", self.script.display()))?;
+        let order = deployment_order(&script)?;
+        name_value_println!("Order", order.join(", "));
+        if self.extrinsic_opts.dry_run {
+            return Ok(());
+        }
+        if self.extrinsic_opts.apps_link {
+            anyhow::bail!(
+                "`--apps-link` is not supported by `deploy`, as the steps depend on the results of \
+                 the previous ones"
+            )
+        }
+
+        let manifest_path = ManifestPath::try_from(self.extrinsic_opts.manifest_path.as_ref())?;
+        let metadata = MetadataCommand::new()
+            .manifest_path(manifest_path.as_ref())
+            .no_deps()
+            .exec()
+            .context("Error invoking `cargo metadata`")?;
+
+        let mut deployed = BTreeMap::<String, Deployed>::new();
+        for name in &order {
+            let step = script
+                .contracts
+                .iter()
+                .find(|step| step.name == *name)
+                .expect("the order consists of the contracts of the script");
+            let package = metadata
+                .packages
+                .iter()
+                .find(|package| package.name == step.name)
+                .context(format!(
+                    "Contract {} is not a package of the workspace",
+                    step.name
+                ))?;
+            let manifest = package.manifest_path.clone().into_std_path_buf();
+            let (crate_metadata, project, constructor_return_types) =
+                load_metadata(Some(&manifest))?;
+            let transcoder = ContractMessageTranscoder::new(&project)
+                .with_constructor_return_types(constructor_return_types);
+            let code = std::fs::read(&crate_metadata.dest_wasm).context(format!(
+                "Failed to read {}, build the contract first",
+                crate_metadata.dest_wasm.display()
+            ))?;
+
+            let result = if step.upload_only {
+                let code_hash = upload_code(&self.extrinsic_opts, &transcoder, code)?;
+                Deployed {
+                    code_hash: format!("{:?}", code_hash),
+                    address: None,
+                }
+            } else {
+                let args = step
+                    .args
+                    .iter()
+                    .map(|arg| substitute(arg, &deployed))
+                    .collect::<Result<Vec<_>>>()?;
+                let data = transcoder.encode(&step.constructor, &args)?;
+                let value = match &step.value {
+                    Some(value) => parse_balance(value)?,
+                    None => Balance::default(),
+                };
+                let (code_hash, contract) = instantiate_with_code(
+                    &self.extrinsic_opts,
+                    transcoder,
+                    code,
+                    &step.constructor,
+                    data,
+                    value,
+                    self.gas_limit,
+                )?;
+                Deployed {
+                    code_hash: format!("{:?}", code_hash),
+                    address: Some(contract.to_ss58check()),
+                }
+            };
+            name_value_println!("Contract", step.name);
+            name_value_println!("Code hash", result.code_hash);
+            if let Some(address) = &result.address {
+                name_value_println!("Address", address);
+            }
+            deployed.insert(step.name.clone(), result);
+        }
+        Ok(())
+    }
+}
+
+fn reference_regex() -> Regex {
+    Regex::new(r"\$\{([A-Za-z0-9_-]+)\.(address|code_hash)\}").expect("valid regex")
+}
+
+/// Returns the names of the contracts of the `script` in the order they have to be deployed in,
+/// every contract after the contracts its arguments refer to.
+fn deployment_order(script: &DeployScript) -> Result<Vec<String>> {
+    let regex = reference_regex();
+    let mut graph = ContractGraph::default();
+    for step in &script.contracts {
+        graph.add_contract(&step.name);
+    }
+    for step in &script.contracts {
+        for arg in &step.args {
+            for reference in regex.captures_iter(arg) {
+                let on = &reference[1];
+                if !script.contracts.iter().any(|step| step.name == on) {
+                    anyhow::bail!(
+                        "The arguments of {} refer to {}, which is not part of the script",
+                        step.name,
+                        on
+                    )
+                }
+                let dependency = match &reference[2] {
+                    "address" => Dependency::ContractRef,
+                    _ => Dependency::CodeHash,
+                };
+                graph.add_dependency(&step.name, on, dependency);
+            }
+        }
+    }
+    graph.deployment_order()
+}
+
+/// Replaces the references to other contracts in `arg` with their address or code hash.
+fn substitute(arg: &str, deployed: &BTreeMap<String, Deployed>) -> Result<String> {
+    let mut error = None;
+    let substituted = reference_regex().replace_all(arg, |reference: &Captures| {
+        let value = deployed
+            .get(&reference[1])
+            .and_then(|deployed| match &reference[2] {
+                "address" => deployed.address.clone(),
+                _ => Some(deployed.code_hash.clone()),
+            });
+        value.unwrap_or_else(|| {
+            error = Some(format!(
+                "{} has no {}, only its code was uploaded",
+                &reference[1], &reference[2]
+            ));
+            String::new()
+        })
+    });
+    match error {
+        Some(error) => Err(anyhow::anyhow!(error)),
+        None => Ok(substituted.into_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script() -> DeployScript {
+        toml::from_str(
+            r#"
+            [[contracts]]
+            name = "factory"
+            args = ["${erc20.address}", "${dex.code_hash}"]
+
+            [[contracts]]
+            name = "dex"
+            upload-only = true
+
+            [[contracts]]
+            name = "erc20"
+            args = ["1000000"]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn referenced_contracts_are_deployed_first() {
+        assert_eq!(
+            deployment_order(&script()).unwrap(),
+            vec!["dex", "erc20", "factory"]
+        );
+    }
+
+    #[test]
+    fn references_are_substituted() {
+        let mut deployed = BTreeMap::new();
+        deployed.insert(
+            "erc20".to_string(),
+            Deployed {
+                code_hash: "0x01".to_string(),
+                address: Some("5C4hrfjw9DjXZTzV3MwzrrAr9P1MJhSrvWGWqi1eSuyUpnhM".to_string()),
+            },
+        );
+        deployed.insert(
+            "dex".to_string(),
+            Deployed {
+                code_hash: "0x02".to_string(),
+                address: None,
+            },
+        );
+
+        assert_eq!(
+            substitute("${erc20.address}", &deployed).unwrap(),
+            "5C4hrfjw9DjXZTzV3MwzrrAr9P1MJhSrvWGWqi1eSuyUpnhM"
+        );
+        assert_eq!(
+            substitute("[${erc20.code_hash}, ${dex.code_hash}]", &deployed).unwrap(),
+            "[0x01, 0x02]"
+        );
+        assert!(substitute("${dex.address}", &deployed).is_err());
+    }
+}
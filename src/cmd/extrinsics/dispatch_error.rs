@@ -0,0 +1,185 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Describes the `DispatchError` of a failed extrinsic, resolving a `Module` error against the
+//! metadata of the chain, e.g. as `Contracts::StorageDepositLimitExhausted` with the docs of the
+//! error, instead of the indices of its pallet and variant.
+
+use super::RuntimeDispatchError;
+use crate::exit_code::{classify, ExitCode};
+use anyhow::{anyhow, Result};
+use scale::{Decode, Encode};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef, Variant};
+use std::fmt::Debug;
+use subxt::{BasicError, Metadata, RawEventDetails};
+
+/// The index of the `Module` variant of `DispatchError`.
+const MODULE_VARIANT: u8 = 3;
+
+/// Returns the description of the dispatch `error`, with the pallet, name and docs of a module
+/// error, or the error as it is if it is no module error of the runtime `metadata`.
+pub(super) fn describe<E: Encode + Debug>(metadata: &Metadata, error: &E) -> String {
+    module_indices(&error.encode())
+        .and_then(|(pallet, error)| module_error(metadata, pallet, error))
+        .unwrap_or_else(|| format!("{:?}", error))
+}
+
+/// Returns the pallet and error index of an `encoded` module error.
+fn module_indices(encoded: &[u8]) -> Option<(u8, u8)> {
+    // newer chains append reserved bytes to the error index
+    match encoded {
+        [MODULE_VARIANT, pallet, error, ..] => Some((*pallet, *error)),
+        _ => None,
+    }
+}
+
+/// Returns the description of the error `error_index` of the pallet with the `pallet_index`.
+fn module_error(metadata: &Metadata, pallet_index: u8, error_index: u8) -> Option<String> {
+    let runtime = metadata.runtime_metadata();
+    let pallet = runtime
+        .pallets
+        .iter()
+        .find(|pallet| pallet.index == pallet_index)?;
+    let errors = pallet.error.as_ref()?.ty.id();
+    let error = error_description(&runtime.types, errors, error_index)?;
+    Some(format!("{}::{}", pallet.name, error))
+}
+
+/// Returns the names of the pallet and of the error `error_index` of the pallet with the
+/// `pallet_index`.
+fn module_error_name(
+    metadata: &Metadata,
+    pallet_index: u8,
+    error_index: u8,
+) -> Option<(&str, &str)> {
+    let runtime = metadata.runtime_metadata();
+    let pallet = runtime
+        .pallets
+        .iter()
+        .find(|pallet| pallet.index == pallet_index)?;
+    let errors = pallet.error.as_ref()?.ty.id();
+    let error = error_variant(&runtime.types, errors, error_index)?;
+    Some((pallet.name.as_str(), error.name().as_str()))
+}
+
+/// Returns the variant with the `index` of the errors enum `ty`.
+fn error_variant(types: &PortableRegistry, ty: u32, index: u8) -> Option<&Variant<PortableForm>> {
+    let errors = match types.resolve(ty)?.type_def() {
+        TypeDef::Variant(errors) => errors,
+        _ => return None,
+    };
+    errors
+        .variants()
+        .iter()
+        .find(|error| error.index() == index)
+}
+
+/// Returns the name and docs of the variant with the `index` of the errors enum `ty`.
+fn error_description(types: &PortableRegistry, ty: u32, index: u8) -> Option<String> {
+    let error = error_variant(types, ty, index)?;
+    let docs = error
+        .docs()
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(if docs.is_empty() {
+        error.name().clone()
+    } else {
+        format!("{}: {}", error.name(), docs)
+    })
+}
+
+/// Returns the error of the extrinsic, if it failed according to its `events`. It is classified
+/// as `Reverted` if the contract reverted, and as `Dispatch` otherwise.
+pub(super) fn extrinsic_error(
+    metadata: &Metadata,
+    events: impl IntoIterator<Item = Result<RawEventDetails, BasicError>>,
+) -> Result<Option<anyhow::Error>> {
+    for event in events {
+        let event = event?;
+        if event.pallet == "System" && event.variant == "ExtrinsicFailed" {
+            // the error is followed by the dispatch info of the extrinsic
+            let error = RuntimeDispatchError::decode(&mut &event.data[..])?;
+            return Ok(Some(classify(
+                anyhow!("The extrinsic failed: {}", describe(metadata, &error)),
+                exit_code(metadata, &error.encode()),
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the exit code of the `encoded` dispatch error of an extrinsic.
+fn exit_code(metadata: &Metadata, encoded: &[u8]) -> ExitCode {
+    let name = module_indices(encoded)
+        .and_then(|(pallet, error)| module_error_name(metadata, pallet, error));
+    match name {
+        Some(("Contracts", "ContractReverted")) => ExitCode::Reverted,
+        _ => ExitCode::Dispatch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(dead_code)]
+    #[derive(scale_info::TypeInfo)]
+    enum Error {
+        /// The code is too large.
+        CodeTooLarge,
+        /// The storage deposit exceeds the limit.
+        ///
+        /// Raise the limit.
+        StorageDepositLimitExhausted,
+    }
+
+    #[derive(Debug, Encode)]
+    enum DispatchError {
+        #[codec(index = 2)]
+        BadOrigin,
+        #[codec(index = 3)]
+        Module { index: u8, error: [u8; 4] },
+    }
+
+    #[test]
+    fn module_errors_are_described_with_their_docs() {
+        let mut registry = scale_info::Registry::new();
+        let id = registry
+            .register_type(&scale_info::meta_type::<Error>())
+            .id();
+        let registry = PortableRegistry::from(registry);
+
+        let error = DispatchError::Module {
+            index: 8,
+            error: [1, 0, 0, 0],
+        };
+        let (pallet, index) = module_indices(&error.encode()).unwrap();
+        assert_eq!(pallet, 8);
+        assert_eq!(
+            error_description(&registry, id, index).unwrap(),
+            "StorageDepositLimitExhausted: The storage deposit exceeds the limit. Raise the limit."
+        );
+        assert_eq!(error_description(&registry, id, 2), None);
+        assert_eq!(
+            error_variant(&registry, id, 0).map(|error| error.name().as_str()),
+            Some("CodeTooLarge")
+        );
+        assert_eq!(module_indices(&DispatchError::BadOrigin.encode()), None);
+    }
+}
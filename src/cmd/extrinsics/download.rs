@@ -0,0 +1,95 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{instantiate::parse_code_hash, node_url, CodeHash, RuntimeApi};
+use crate::name_value_println;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use subxt::ClientBuilder;
+
+#[derive(Debug, clap::Args)]
+#[clap(
+    name = "download",
+    about = "Download the Wasm code of a code hash from the chain"
+)]
+pub struct DownloadCommand {
+    /// The hash of the uploaded code.
+    #[clap(parse(try_from_str = parse_code_hash))]
+    code_hash: CodeHash,
+    /// The file to write the code to, defaults to `<code_hash>.wasm` or `<code_hash>.wat`.
+    #[clap(long, short, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// Write the code disassembled to the WebAssembly text format.
+    #[clap(long)]
+    wat: bool,
+    /// Websockets url of a substrate node. Defaults to the url of the `--network`, or to
+    /// `ws://localhost:9944`.
+    #[clap(long, parse(try_from_str))]
+    url: Option<url::Url>,
+    /// The name of a network profile from the `cargo-contract` configuration file, or of a
+    /// profile with an `endpoint` in `[package.metadata.contract.profiles]` of the contract.
+    #[clap(long)]
+    network: Option<String>,
+    /// Path to the `Cargo.toml` of the contract, whose profiles are considered for `--network`.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+}
+
+impl DownloadCommand {
+    pub fn run(&self) -> Result<()> {
+        let code = async_std::task::block_on(self.fetch_code())?;
+
+        let output = match &self.output {
+            Some(output) => output.clone(),
+            None => {
+                let extension = if self.wat { "wat" } else { "wasm" };
+                PathBuf::from(format!("{:x}.{}", self.code_hash, extension))
+            }
+        };
+        if self.wat {
+            let wat = wasmprinter::print_bytes(&code).context("Failed to disassemble the code")?;
+            std::fs::write(&output, wat)
+        } else {
+            std::fs::write(&output, &code)
+        }
+        .context(format!("Failed to write the code to {}", output.display()))?;
+
+        name_value_println!("Code hash", format!("{:?}", self.code_hash));
+        name_value_println!("Size", format!("{} bytes", code.len()));
+        name_value_println!("Written to", output.display().to_string());
+        Ok(())
+    }
+
+    /// Fetches the code as it was uploaded, before it was instrumented by `pallet-contracts`.
+    async fn fetch_code(&self) -> Result<Vec<u8>> {
+        let url = node_url(
+            self.url.as_ref(),
+            self.network.as_deref(),
+            self.manifest_path.as_ref(),
+        )?;
+        let api = ClientBuilder::new()
+            .set_url(url.as_str())
+            .build()
+            .await?
+            .to_runtime_api::<RuntimeApi>();
+
+        api.storage()
+            .contracts()
+            .pristine_code(self.code_hash, None)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No code stored for the code hash {:?}", self.code_hash))
+    }
+}
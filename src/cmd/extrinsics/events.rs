@@ -24,13 +24,24 @@ use colored::Colorize as _;
 
 use anyhow::Result;
 use scale::Input as _;
-use subxt::{self, DefaultConfig, Event, TransactionEvents};
+use subxt::{self, BasicError, DefaultConfig, Event, RawEventDetails, TransactionEvents};
 
 pub fn display_events(
     result: &TransactionEvents<DefaultConfig, RuntimeEvent>,
     transcoder: &ContractMessageTranscoder,
     subxt_metadata: &subxt::Metadata,
     verbosity: &Verbosity,
+) -> Result<()> {
+    display_raw_events(result.iter_raw(), transcoder, subxt_metadata, verbosity)
+}
+
+/// Displays the `events`, e.g. of an extrinsic which was not submitted by a [`TransactionEvents`]
+/// of `subxt`.
+pub fn display_raw_events(
+    events: impl IntoIterator<Item = Result<RawEventDetails, BasicError>>,
+    transcoder: &ContractMessageTranscoder,
+    subxt_metadata: &subxt::Metadata,
+    verbosity: &Verbosity,
 ) -> Result<()> {
     if matches!(verbosity, Verbosity::Quiet) {
         return Ok(());
@@ -47,7 +58,7 @@ pub fn display_events(
 
     const EVENT_FIELD_INDENT: usize = DEFAULT_KEY_COL_WIDTH - 3;
 
-    for event in result.iter_raw() {
+    for event in events {
         let event = event?;
         log::debug!("displaying event {:?}", event);
 
@@ -0,0 +1,107 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{CodeHash, ContractAccount, ExtrinsicOpts};
+use crate::{config::ExplorerUrls, name_value_println};
+use anyhow::{Context, Result};
+use colored::Colorize as _;
+use sp_core::{crypto::Ss58Codec, H256};
+use std::process::Command;
+
+/// A page of a block explorer which can be linked after a successful extrinsic.
+pub enum ExplorerPage {
+    Block(H256),
+    Extrinsic(H256),
+    Contract(ContractAccount),
+    CodeHash(CodeHash),
+}
+
+impl ExplorerPage {
+    /// Returns the url of this page, if the explorer has a template configured for it.
+    fn url(&self, explorer: &ExplorerUrls) -> Option<String> {
+        let (template, placeholder, value) = match self {
+            ExplorerPage::Block(hash) => (&explorer.block, "block", format!("{:?}", hash)),
+            ExplorerPage::Extrinsic(hash) => {
+                (&explorer.extrinsic, "extrinsic", format!("{:?}", hash))
+            }
+            ExplorerPage::Contract(account) => {
+                (&explorer.contract, "contract", account.to_ss58check())
+            }
+            ExplorerPage::CodeHash(hash) => {
+                (&explorer.code_hash, "code_hash", format!("{:?}", hash))
+            }
+        };
+        template
+            .as_ref()
+            .map(|template| ExplorerUrls::render(template, placeholder, &value))
+    }
+}
+
+/// Print the explorer urls of the given `pages` for the selected `--network`.
+///
+/// With `--open-explorer` the first page with a configured url is opened in the browser.
+pub fn display_explorer_links(opts: &ExtrinsicOpts, pages: &[ExplorerPage]) -> Result<()> {
+    let profile = match opts.network_profile()? {
+        Some(profile) => profile,
+        None => {
+            if opts.open_explorer {
+                println!(
+                    "{} `--open-explorer` requires a `--network` with explorer urls configured",
+                    "Warning:".yellow().bold()
+                );
+            }
+            return Ok(());
+        }
+    };
+
+    let urls = pages
+        .iter()
+        .filter_map(|page| page.url(&profile.explorer))
+        .collect::<Vec<_>>();
+    for url in &urls {
+        name_value_println!("Explorer", url);
+    }
+
+    if opts.open_explorer {
+        match urls.first() {
+            Some(url) => open_in_browser(url)?,
+            None => {
+                println!(
+                    "{} no explorer url templates are configured for this network",
+                    "Warning:".yellow().bold()
+                )
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Open the `url` with the default browser of the platform.
+fn open_in_browser(url: &str) -> Result<()> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(&["/C", "start", ""]);
+        cmd
+    } else {
+        Command::new("xdg-open")
+    };
+    cmd.arg(url)
+        .status()
+        .context(format!("Failed to open {} in the browser", url))?;
+    Ok(())
+}
@@ -0,0 +1,123 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Failover between the nodes of a network profile with `fallback-urls`.
+//!
+//! Before connecting, the nodes are checked in order and the first healthy one is used. A node is
+//! healthy if it responds in time, is not syncing, has peers and its finalized block lags at most
+//! `max-finality-lag` blocks behind its best block.
+
+use crate::{config::NetworkProfile, output};
+use anyhow::{Context, Result};
+use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use serde::Deserialize;
+use std::{iter, time::Duration};
+use url::Url;
+
+/// How long a node may take to connect and to respond to each request of the health check.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The result of `system_health`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Health {
+    peers: u64,
+    is_syncing: bool,
+    should_have_peers: bool,
+}
+
+/// The part of a header returned by `chain_getHeader` needed for the health check.
+#[derive(Deserialize)]
+struct Header {
+    /// The hex encoded block number.
+    number: String,
+}
+
+/// Returns the url of the first healthy node of the `profile`, warning about the unhealthy ones.
+///
+/// Without `fallback-urls` the node at `url` is used without a health check.
+pub(super) fn healthy_url(profile: &NetworkProfile) -> Result<Url> {
+    if profile.fallback_urls.is_empty() {
+        return Ok(profile.url.clone());
+    }
+    let mut unhealthy = Vec::new();
+    for url in iter::once(&profile.url).chain(&profile.fallback_urls) {
+        match async_std::task::block_on(check_health(url, profile.max_finality_lag)) {
+            Ok(()) => {
+                if !unhealthy.is_empty() {
+                    output::warning(format!("Failing over to {}", url));
+                }
+                return Ok(url.clone());
+            }
+            Err(err) => {
+                output::warning(format!("The node at {} is unhealthy: {:#}", url, err));
+                unhealthy.push(url.to_string());
+            }
+        }
+    }
+    anyhow::bail!(
+        "None of the nodes of the network is healthy: {}",
+        unhealthy.join(", ")
+    )
+}
+
+/// Fails with the reason if the node at `url` is unhealthy.
+async fn check_health(url: &Url, max_finality_lag: u32) -> Result<()> {
+    let cli = WsClientBuilder::default()
+        .connection_timeout(TIMEOUT)
+        .request_timeout(TIMEOUT)
+        .build(url.as_str())
+        .await?;
+    let health: Health = cli.request("system_health", rpc_params![]).await?;
+    if health.is_syncing {
+        anyhow::bail!("it is syncing")
+    }
+    if health.should_have_peers && health.peers == 0 {
+        anyhow::bail!("it has no peers")
+    }
+    let best: Header = cli.request("chain_getHeader", rpc_params![]).await?;
+    let finalized_hash: String = cli.request("chain_getFinalizedHead", rpc_params![]).await?;
+    let finalized: Header = cli
+        .request("chain_getHeader", rpc_params![finalized_hash])
+        .await?;
+    let lag = block_number(&best)?.saturating_sub(block_number(&finalized)?);
+    if lag > max_finality_lag {
+        anyhow::bail!("its finalized block lags {} blocks behind", lag)
+    }
+    Ok(())
+}
+
+fn block_number(header: &Header) -> Result<u32> {
+    let number = header.number.trim_start_matches("0x");
+    u32::from_str_radix(number, 16).context(format!("Invalid block number {}", header.number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_nodes_are_used_without_health_check() {
+        let profile: NetworkProfile =
+            toml::from_str(r#"url = "ws://localhost:1""#).expect("the profile is valid");
+        assert_eq!(healthy_url(&profile).unwrap(), profile.url);
+
+        let header = Header {
+            number: "0x1f".into(),
+        };
+        assert_eq!(block_number(&header).unwrap(), 31);
+    }
+}
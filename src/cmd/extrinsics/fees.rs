@@ -0,0 +1,155 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{Balance, ContractAccount, ExtrinsicSigner, RuntimeApi};
+use crate::name_value_println;
+use anyhow::{Context, Result};
+use jsonrpsee::{core::client::ClientT, rpc_params};
+use pallet_contracts_primitives::StorageDeposit;
+use scale::Encode;
+use sp_core::{crypto::Ss58Codec, Bytes};
+use subxt::Call;
+
+/// The balance an extrinsic transfers or reserves from the signer, besides its fee.
+#[derive(Debug, Default)]
+pub(super) struct Cost {
+    /// The value transferred to the contract.
+    pub(super) value: Balance,
+    /// The storage deposit, as estimated by a dry-run.
+    pub(super) deposit: Balance,
+}
+
+impl Cost {
+    /// The cost of an extrinsic with the `storage_deposit` of its dry-run.
+    pub(super) fn new(value: Balance, storage_deposit: &StorageDeposit<Balance>) -> Self {
+        let deposit = match storage_deposit {
+            StorageDeposit::Charge(deposit) => *deposit,
+            StorageDeposit::Refund(_) => 0,
+        };
+        Self { value, deposit }
+    }
+}
+
+/// Returns the fee of the `call` signed by the `signer`, as estimated by `payment_queryInfo`.
+pub(super) async fn estimate_fee<C>(
+    api: &RuntimeApi,
+    call: C,
+    signer: &ExtrinsicSigner,
+) -> Result<Balance>
+where
+    C: Call + Send + Sync,
+{
+    let extrinsic = api
+        .client
+        .create_signed(call, &signer.for_fee_estimation(), Default::default())
+        .await?;
+    let info: serde_json::Value = api
+        .client
+        .rpc()
+        .client
+        .request("payment_queryInfo", rpc_params![Bytes(extrinsic.encode())])
+        .await?;
+    // Depending on the node version the fee is a number or a string.
+    match &info["partialFee"] {
+        serde_json::Value::String(fee) => fee.parse().ok(),
+        serde_json::Value::Number(fee) => fee.as_u64().map(Into::into),
+        _ => None,
+    }
+    .context(format!(
+        "Unexpected response of `payment_queryInfo`: {}",
+        info
+    ))
+}
+
+/// Fails with the missing amount if the free balance of the `account` does not cover the `fee`
+/// and the `cost` of an extrinsic, which would otherwise fail with `FundsUnavailable`.
+///
+/// Without a `fee` it is paid in another asset than the native token.
+pub(super) async fn ensure_affordable(
+    api: &RuntimeApi,
+    account: &ContractAccount,
+    fee: Option<Balance>,
+    cost: &Cost,
+) -> Result<()> {
+    if let Some(fee) = fee {
+        name_value_println!("Estimated fee", format!("{}", fee));
+    }
+    if cost.deposit > 0 {
+        name_value_println!("Deposit", format!("{}", cost.deposit));
+    }
+    let info = api
+        .storage()
+        .system()
+        .account(account.clone(), None)
+        .await?;
+    let frozen = info.data.misc_frozen.max(info.data.fee_frozen);
+    let available = info.data.free.saturating_sub(frozen);
+    check_balance(account, available, fee, cost)
+}
+
+fn check_balance(
+    account: &ContractAccount,
+    available: Balance,
+    fee: Option<Balance>,
+    cost: &Cost,
+) -> Result<()> {
+    let required = fee
+        .unwrap_or_default()
+        .saturating_add(cost.value)
+        .saturating_add(cost.deposit);
+    if available < required {
+        let fee = fee
+            .map(|fee| format!("the fee of {}, ", fee))
+            .unwrap_or_default();
+        anyhow::bail!(
+            "Insufficient free balance of {}: {} required for {}the value of {} and the deposit \
+             of {}, but only {} available, {} short",
+            account.to_ss58check(),
+            required,
+            fee,
+            cost.value,
+            cost.deposit,
+            available,
+            required - available
+        )
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortfall_is_reported() {
+        let account = ContractAccount::new([1; 32]);
+        let cost = Cost::new(100, &StorageDeposit::Charge(50));
+        assert!(check_balance(&account, 160, Some(10), &cost).is_ok());
+
+        let err = check_balance(&account, 150, Some(10), &cost).unwrap_err();
+        let expected = "160 required for the fee of 10, the value of 100 and the deposit of 50, \
+            but only 150 available, 10 short";
+        assert!(err.to_string().ends_with(expected));
+
+        // a fee paid in another asset is not part of the native balance
+        assert!(check_balance(&account, 150, None, &cost).is_ok());
+        let err = check_balance(&account, 140, None, &cost).unwrap_err();
+        let expected = "150 required for the value of 100 and the deposit of 50, but only 140 \
+            available, 10 short";
+        assert!(err.to_string().ends_with(expected));
+        assert_eq!(Cost::new(1, &StorageDeposit::Refund(5)).deposit, 0);
+    }
+}
@@ -0,0 +1,332 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    call::{display_call_result, ensure_call_succeeded, ContractExecResult, RpcCallRequest},
+    load_metadata, node_url,
+    state::{contract_info_key, storage_cells, ContractInfo, StorageCell},
+    CodeHash, ContractAccount, ContractMessageTranscoder,
+};
+use crate::{address_book::parse_address, name_value_println};
+use anyhow::{anyhow, Context, Result};
+use jsonrpsee::{
+    core::client::ClientT,
+    rpc_params,
+    ws_client::{WsClient, WsClientBuilder},
+};
+use scale::Decode;
+use serde::Deserialize;
+use sp_core::{hashing::blake2_256, storage::ChildInfo, Blake2Hasher, Bytes};
+use sp_state_machine::{read_child_proof_check, read_proof_check, StorageProof};
+use std::path::PathBuf;
+use subxt::rpc::NumberOrHex;
+
+/// Query a contract without a signer, optionally verifying the result with read proofs.
+///
+/// With `--with-proof`, the storage is read at a finalized block of a second node, along with
+/// Merkle proofs of it against the state root of that block. The value of a `--field` is then
+/// proven. The result of a `--message` is computed by executing the contract on the node, which
+/// cannot be proven, but it is dry-run at that block after the storage cells of the contract are.
+#[derive(Debug, clap::Args)]
+#[clap(name = "get")]
+pub struct GetCommand {
+    /// The address of the contract to query, or its alias in the address book.
+    #[clap(long, env = "CONTRACT", parse(try_from_str = parse_address))]
+    contract: ContractAccount,
+    /// The name of the message to dry-run.
+    #[clap(long, short, required_unless_present = "field")]
+    message: Option<String>,
+    /// The arguments of the message.
+    #[clap(long, requires = "message")]
+    args: Vec<String>,
+    /// The path of a storage cell to read instead, e.g. `total_supply`, as exported by
+    /// `cargo contract state export`.
+    #[clap(long, conflicts_with = "message")]
+    field: Option<String>,
+    /// Verify the value of the `--field`, or the storage the `--message` is dry-run on, with read
+    /// proofs against the state root of the latest finalized header of the `--trusted-url`.
+    #[clap(long, requires = "trusted_url")]
+    with_proof: bool,
+    /// Websockets url of a second node, e.g. operated by yourself, which provides the finalized
+    /// header for `--with-proof`.
+    #[clap(long, parse(try_from_str))]
+    trusted_url: Option<url::Url>,
+    /// The account the message is dry-run by, defaults to the contract itself.
+    #[clap(long, parse(try_from_str = parse_address))]
+    caller: Option<ContractAccount>,
+    /// Maximum amount of gas to be used for the dry-run of the message.
+    #[clap(name = "gas", long, default_value = "50000000000")]
+    gas_limit: u64,
+    /// Websockets url of a substrate node. Defaults to the url of the `--network`, or to
+    /// `ws://localhost:9944`.
+    #[clap(long, parse(try_from_str))]
+    url: Option<url::Url>,
+    /// The name of a network profile from the `cargo-contract` configuration file, or of a
+    /// profile with an `endpoint` in `[package.metadata.contract.profiles]` of the contract.
+    #[clap(long)]
+    network: Option<String>,
+    /// Path to the `Cargo.toml` of the contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+}
+
+/// The response of `state_getReadProof` and `state_getChildReadProof`.
+#[derive(Deserialize)]
+struct ReadProof {
+    proof: Vec<Bytes>,
+}
+
+impl From<ReadProof> for StorageProof {
+    fn from(proof: ReadProof) -> Self {
+        StorageProof::new(proof.proof.into_iter().map(|node| node.0))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+    state_root: CodeHash,
+}
+
+/// A finalized block of the `--trusted-url`, which the storage is proven against.
+struct TrustedBlock {
+    hash: CodeHash,
+    state_root: CodeHash,
+}
+
+impl GetCommand {
+    pub fn run(&self) -> Result<()> {
+        let (_, project, _) = load_metadata(self.manifest_path.as_ref())?;
+        let transcoder = ContractMessageTranscoder::new(&project);
+        let url = node_url(
+            self.url.as_ref(),
+            self.network.as_deref(),
+            self.manifest_path.as_ref(),
+        )?;
+        async_std::task::block_on(async {
+            let cli = WsClientBuilder::default().build(url.as_str()).await?;
+            let trusted = match self.with_proof {
+                true => Some(self.trusted_block().await?),
+                false => None,
+            };
+            let cells = storage_cells(&transcoder, project.layout())?;
+            if let Some(message) = &self.message {
+                if let Some(trusted) = &trusted {
+                    self.proven_cells(&cli, trusted, &cells).await?;
+                }
+                let at = trusted.map(|trusted| trusted.hash);
+                return self.query(&cli, message, &transcoder, at).await;
+            }
+            let field = self
+                .field
+                .as_ref()
+                .expect("`--field` is required without `--message`");
+
+            let cell = cells
+                .iter()
+                .find(|cell| &cell.path == field)
+                .context(format!(
+                    "No storage cell `{}` in the layout, pass one of: {}",
+                    field,
+                    cells
+                        .iter()
+                        .map(|cell| cell.path.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))?;
+            let value = match &trusted {
+                Some(trusted) => self
+                    .proven_cells(&cli, trusted, std::slice::from_ref(cell))
+                    .await?
+                    .remove(0),
+                None => self.cell(&cli, cell).await?,
+            };
+            let value = match value {
+                Some(value) => transcoder.decode(cell.ty, &mut &value[..])?.to_string(),
+                None => "None, the cell is empty".to_string(),
+            };
+            name_value_println!("Field", field.to_string());
+            name_value_println!("Value", value);
+            Ok(())
+        })
+    }
+
+    /// Dry-runs the `message` at the block `at`, or the latest one, and prints its result.
+    async fn query(
+        &self,
+        cli: &WsClient,
+        message: &str,
+        transcoder: &ContractMessageTranscoder<'_>,
+        at: Option<CodeHash>,
+    ) -> Result<()> {
+        let call_request = RpcCallRequest {
+            origin: self.caller.clone().unwrap_or_else(|| self.contract.clone()),
+            dest: self.contract.clone(),
+            value: NumberOrHex::Hex(0.into()),
+            gas_limit: NumberOrHex::Number(self.gas_limit),
+            storage_deposit_limit: None,
+            input_data: Bytes(transcoder.encode(message, &self.args)?),
+        };
+        let result: ContractExecResult = cli
+            .request("contracts_call", rpc_params![&call_request, at])
+            .await?;
+        display_call_result(&result, transcoder, message)?;
+        ensure_call_succeeded(&result)
+    }
+
+    /// Reads the value of the `cell` from the node, without verifying it.
+    async fn cell(&self, cli: &WsClient, cell: &StorageCell) -> Result<Option<Vec<u8>>> {
+        let info = self.contract_info(cli).await?;
+        let child = ChildInfo::new_default(&info.trie_id);
+        let value: Option<Bytes> = cli
+            .request(
+                "childstate_getStorage",
+                rpc_params![
+                    Bytes(child.prefixed_storage_key().into_inner()),
+                    Bytes(blake2_256(&cell.key).to_vec())
+                ],
+            )
+            .await?;
+        Ok(value.map(|value| value.0))
+    }
+
+    async fn contract_info(&self, cli: &WsClient) -> Result<ContractInfo> {
+        let info: Option<Bytes> = cli
+            .request(
+                "state_getStorage",
+                rpc_params![Bytes(contract_info_key(&self.contract))],
+            )
+            .await?;
+        let info = info.context("No contract at the given address")?;
+        ContractInfo::decode(&mut &info.0[..]).context("Failed to decode the contract info")
+    }
+
+    /// Fetches the latest finalized block of the `--trusted-url`.
+    async fn trusted_block(&self) -> Result<TrustedBlock> {
+        let trusted_url = self
+            .trusted_url
+            .as_ref()
+            .expect("`--with-proof` requires `--trusted-url`");
+        let trusted = WsClientBuilder::default()
+            .build(trusted_url.as_str())
+            .await?;
+        let hash: CodeHash = trusted
+            .request("chain_getFinalizedHead", rpc_params![])
+            .await?;
+        let header: Header = trusted
+            .request("chain_getHeader", rpc_params![hash])
+            .await?;
+        Ok(TrustedBlock {
+            hash,
+            state_root: header.state_root,
+        })
+    }
+
+    /// Reads the values of the `cells` from the node at the `trusted` block, and verifies them with
+    /// read proofs against the state root of that block.
+    async fn proven_cells(
+        &self,
+        cli: &WsClient,
+        trusted: &TrustedBlock,
+        cells: &[StorageCell],
+    ) -> Result<Vec<Option<Vec<u8>>>> {
+        let info_key = contract_info_key(&self.contract);
+        let proof: ReadProof = cli
+            .request(
+                "state_getReadProof",
+                rpc_params![vec![Bytes(info_key.clone())], trusted.hash],
+            )
+            .await
+            .context("Failed to fetch the read proof, the node might not know the block")?;
+        let mut values =
+            read_proof_check::<Blake2Hasher, _>(trusted.state_root, proof.into(), [&info_key])
+                .map_err(|err| anyhow!("Invalid read proof of the contract info: {}", err))?;
+        let info = values
+            .remove(&info_key)
+            .flatten()
+            .context("No contract at the given address in the finalized block")?;
+        let info =
+            ContractInfo::decode(&mut &info[..]).context("Failed to decode the contract info")?;
+
+        let child = ChildInfo::new_default(&info.trie_id);
+        let keys = cells
+            .iter()
+            .map(|cell| blake2_256(&cell.key).to_vec())
+            .collect::<Vec<_>>();
+        let proof: ReadProof = cli
+            .request(
+                "state_getChildReadProof",
+                rpc_params![
+                    Bytes(child.prefixed_storage_key().into_inner()),
+                    keys.iter().cloned().map(Bytes).collect::<Vec<_>>(),
+                    trusted.hash
+                ],
+            )
+            .await?;
+        let mut values = read_child_proof_check::<Blake2Hasher, _>(
+            trusted.state_root,
+            proof.into(),
+            &child,
+            &keys,
+        )
+        .map_err(|err| anyhow!("Invalid read proof of the storage: {}", err))?;
+
+        name_value_println!("Block", format!("{:?}", trusted.hash));
+        name_value_println!(
+            "Verified",
+            format!(
+                "{} storage cells against the state root {:?}",
+                cells.len(),
+                trusted.state_root
+            )
+        );
+        Ok(keys
+            .iter()
+            .map(|key| values.remove(key).flatten())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::extrinsics::state::tests::project;
+
+    #[test]
+    fn storage_cells_have_static_keys() {
+        let project = project();
+        let transcoder = ContractMessageTranscoder::new(&project);
+        let cells = storage_cells(&transcoder, project.layout()).unwrap();
+
+        let mut paused = [0; 32];
+        paused[0] = 1;
+        assert_eq!(
+            cells,
+            [
+                StorageCell {
+                    path: "total_supply".into(),
+                    key: [0; 32],
+                    ty: 0,
+                },
+                StorageCell {
+                    path: "paused".into(),
+                    key: paused,
+                    ty: 1,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{call_index, CodeHash};
+use crate::name_value_println;
+use anyhow::{Context, Result};
+use scale::Encode;
+use scale_info::TypeDef;
+use sp_core::hashing::blake2_256;
+
+/// A call prepared for its submission through OpenGov: the call is noted as a preimage with
+/// `Preimage::note_preimage` and proposed by its hash with `Referenda::submit`.
+pub(super) struct GovernanceProposal {
+    /// The encoded call, which is the preimage.
+    call: Vec<u8>,
+    /// The encoded `Preimage::note_preimage` call.
+    note_preimage: Vec<u8>,
+    /// The encoded `Referenda::submit` call on the root track, if the runtime has referenda.
+    submit: Option<Vec<u8>>,
+}
+
+impl GovernanceProposal {
+    /// Prepares the encoded `call` for a referendum, using the pallet and call indices of the
+    /// runtime `metadata`.
+    pub(super) fn new(metadata: &subxt::Metadata, call: Vec<u8>) -> Result<Self> {
+        let mut note_preimage = {
+            let (pallet, index) = call_index(metadata, "Preimage", "note_preimage")?;
+            vec![pallet, index]
+        };
+        call.encode_to(&mut note_preimage);
+
+        let submit = match root_origin(metadata) {
+            Ok(origin) => {
+                let index = call_index(metadata, "Referenda", "submit")?;
+                Some(submit_call(
+                    index,
+                    origin,
+                    preimage_hash(&call),
+                    call.len() as u32,
+                ))
+            }
+            Err(err) => {
+                log::warn!("No referendum call data: {:?}", err);
+                None
+            }
+        };
+        Ok(Self {
+            call,
+            note_preimage,
+            submit,
+        })
+    }
+
+    /// Prints the preimage, its hash and the call data to submit it.
+    pub(super) fn display(&self) {
+        name_value_println!("Call hash", format!("{:?}", preimage_hash(&self.call)));
+        name_value_println!("Length", format!("{} bytes", self.call.len()));
+        name_value_println!("Preimage", format!("0x{}", hex::encode(&self.call)));
+        name_value_println!(
+            "Note preimage",
+            format!("0x{}", hex::encode(&self.note_preimage))
+        );
+        if let Some(submit) = &self.submit {
+            name_value_println!("Referendum", format!("0x{}", hex::encode(submit)));
+        }
+    }
+}
+
+fn preimage_hash(preimage: &[u8]) -> CodeHash {
+    blake2_256(preimage).into()
+}
+
+/// Returns the encoded `Root` variant of the origins accepted by `Referenda::submit`.
+fn root_origin(metadata: &subxt::Metadata) -> Result<[u8; 2]> {
+    let runtime = metadata.runtime_metadata();
+    let pallet = runtime
+        .pallets
+        .iter()
+        .find(|pallet| pallet.name == "Referenda")
+        .context("The runtime has no Referenda pallet")?;
+    let calls = pallet
+        .calls
+        .as_ref()
+        .context("The Referenda pallet has no calls")?;
+    let variant = |id| match runtime.types.resolve(id).map(|ty| ty.type_def()) {
+        Some(TypeDef::Variant(variant)) => Some(variant),
+        _ => None,
+    };
+    let origin = variant(calls.ty.id())
+        .and_then(|calls| calls.variants().iter().find(|call| call.name() == "submit"))
+        .and_then(|submit| submit.fields().first())
+        .and_then(|origin| {
+            // The origin is boxed, which is transparent in the type registry.
+            variant(origin.ty().id())
+        })
+        .context("Unexpected signature of `Referenda::submit`")?;
+    let system = origin
+        .variants()
+        .iter()
+        .find(|origin| origin.name() == "system")
+        .context("The runtime has no system origin")?;
+    // `frame_system::RawOrigin::Root`
+    Ok([system.index(), 0])
+}
+
+/// Encodes `Referenda::submit` for the preimage with the `hash` and `len`, enacted one block
+/// after its approval.
+fn submit_call(index: (u8, u8), origin: [u8; 2], hash: CodeHash, len: u32) -> Vec<u8> {
+    let mut call = vec![index.0, index.1];
+    call.extend_from_slice(&origin);
+    // `Bounded::Lookup { hash, len }`
+    call.push(2);
+    hash.encode_to(&mut call);
+    len.encode_to(&mut call);
+    // `DispatchTime::After(1)`
+    call.push(1);
+    1u32.encode_to(&mut call);
+    call
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn referendum_refers_to_the_preimage_by_hash_and_length() {
+        let preimage = vec![8, 7, 4, 0];
+        let hash = preimage_hash(&preimage);
+        let call = submit_call((21, 0), [0, 0], hash, preimage.len() as u32);
+
+        assert_eq!(&call[..5], &[21, 0, 0, 0, 2]);
+        assert_eq!(&call[5..37], hash.as_bytes());
+        assert_eq!(&call[37..], &[4, 0, 0, 0, 1, 1, 0, 0, 0]);
+    }
+}
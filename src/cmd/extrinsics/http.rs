@@ -0,0 +1,153 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The minimal HTTP/1.1 needed by the local servers on `127.0.0.1`, one request per connection.
+
+use anyhow::Result;
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+/// How long reading a request or writing a response may stall, so that a client which stops
+/// sending does not block the server.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The maximum size of a request including its body, the requests are small JSON documents.
+const MAX_REQUEST_SIZE: usize = 1024 * 1024;
+
+/// A request read from a connection.
+#[derive(Debug, PartialEq)]
+pub(super) struct Request {
+    pub(super) method: String,
+    /// The path, without the query.
+    pub(super) path: String,
+    pub(super) query: Option<String>,
+    pub(super) body: Vec<u8>,
+}
+
+impl Request {
+    /// Returns the value of the query parameter `name`.
+    pub(super) fn query_param(&self, name: &str) -> Option<&str> {
+        self.query.as_deref()?.split('&').find_map(|param| {
+            param
+                .strip_prefix(name)
+                .and_then(|value| value.strip_prefix('='))
+        })
+    }
+}
+
+/// Sets the read and write timeouts of an accepted connection.
+pub(super) fn set_timeouts(stream: &TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    Ok(())
+}
+
+/// Reads a request, with a body of the given `Content-Length`. Requests larger than
+/// `MAX_REQUEST_SIZE` are rejected.
+pub(super) fn read_request(stream: &mut impl Read) -> Result<Request> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    let (head, body_start) = loop {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            anyhow::bail!("Incomplete request")
+        }
+        request.extend_from_slice(&buffer[..read]);
+        if request.len() > MAX_REQUEST_SIZE {
+            anyhow::bail!("The request exceeds {} bytes", MAX_REQUEST_SIZE)
+        }
+        if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+            break (
+                String::from_utf8_lossy(&request[..end]).to_string(),
+                end + 4,
+            );
+        }
+    };
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or_default();
+    if body_start.saturating_add(content_length) > MAX_REQUEST_SIZE {
+        anyhow::bail!("The request exceeds {} bytes", MAX_REQUEST_SIZE)
+    }
+    while request.len() < body_start + content_length {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default();
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query.to_string())),
+        None => (target, None),
+    };
+    Ok(Request {
+        method,
+        path: path.to_string(),
+        query,
+        body: request[body_start..].to_vec(),
+    })
+}
+
+/// Writes a response, which any origin may read, and closes the connection.
+pub(super) fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nAccess-Control-Allow-Origin: *\r\nAccess-Control-Allow-Headers: *\r\n\
+         Content-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_read_with_their_body() {
+        let raw = "POST /encode?pretty=1&signature=0x01 HTTP/1.1\r\nHost: localhost\r\n\
+                   content-length: 4\r\n\r\n{}\r\n";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/encode");
+        assert_eq!(request.query_param("signature"), Some("0x01"));
+        assert_eq!(request.query_param("sign"), None);
+        assert_eq!(request.body, b"{}\r\n");
+
+        let oversized = format!("POST / HTTP/1.1\r\ncontent-length: {}\r\n\r\n", usize::MAX);
+        assert!(read_request(&mut oversized.as_bytes()).is_err());
+        let endless_head = vec![b'a'; MAX_REQUEST_SIZE + 1];
+        assert!(read_request(&mut endless_head.as_slice()).is_err());
+    }
+}
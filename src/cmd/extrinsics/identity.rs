@@ -0,0 +1,252 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Resolves accounts given by their index in the indices pallet, `index:<n>`, or by the display
+//! name of their identity in the identity pallet of the chain.
+//!
+//! The storage of the pallets is read directly, assuming the `u128` balances of the pallets of
+//! Substrate. An identity whose display name is only stored as hash cannot be found by its name.
+
+use super::ContractMessageTranscoder;
+use crate::address_book::AddressBook;
+use anyhow::{Context, Result};
+use jsonrpsee::{
+    core::client::ClientT,
+    rpc_params,
+    ws_client::{WsClient, WsClientBuilder},
+};
+use scale::{Compact, Decode, Encode, Input};
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    hashing::{blake2_128, twox_128},
+    storage::{StorageChangeSet, StorageData, StorageKey},
+    Bytes, H256,
+};
+
+/// How many identities are read with one request.
+const PAGE_SIZE: u32 = 512;
+
+/// Returns the account with the `index` in the indices pallet, given as `index:<n>`, or with the
+/// display `name` of its identity, on the chain at `url`.
+pub(super) async fn resolve_on_chain(url: &url::Url, name: &str) -> Result<AccountId32> {
+    let cli = WsClientBuilder::default().build(url.as_str()).await?;
+    match name.strip_prefix("index:") {
+        Some(index) => {
+            let index = index
+                .parse()
+                .context(format!("Invalid account index `{}`", index))?;
+            account_of_index(&cli, index).await
+        }
+        None => account_of_identity(&cli, name).await,
+    }
+}
+
+/// Returns the `args` of the constructor or message `name` with the `AccountId` arguments given
+/// as `index:<n>` or as the display name of an identity resolved on the chain at `url`.
+///
+/// SS58 addresses, aliases of the address book and arguments given literally, e.g. as hex or
+/// bytes, are left as they are.
+pub(super) fn resolve_account_args(
+    transcoder: &ContractMessageTranscoder<'_>,
+    url: &url::Url,
+    name: &str,
+    args: &[String],
+) -> Result<Vec<String>> {
+    let specs = match transcoder.args(name) {
+        Some(specs) => specs,
+        None => return Ok(args.to_vec()),
+    };
+    let address_book = AddressBook::load()?;
+    let mut resolved = args.to_vec();
+    for (spec, arg) in specs.iter().zip(resolved.iter_mut()) {
+        let display_name = spec.ty().display_name().segments().last();
+        let is_account = display_name.map(String::as_str) == Some("AccountId");
+        let name = match on_chain_name(arg) {
+            Some(name) if is_account && address_book.resolve(name).is_err() => name,
+            _ => continue,
+        };
+        let account = async_std::task::block_on(resolve_on_chain(url, name))
+            .context(format!("Invalid argument `{}`", spec.label()))?;
+        *arg = account.to_ss58check();
+    }
+    Ok(resolved)
+}
+
+/// Returns the `index:<n>` or display name of an identity an `AccountId` argument is given as, or
+/// `None` if it is a literal value, e.g. `0x` prefixed hex or a byte array.
+fn on_chain_name(arg: &str) -> Option<&str> {
+    if arg.starts_with("index:") {
+        return Some(arg);
+    }
+    // quoted names are allowed for names with spaces, e.g. `'"Alice Smith"'`
+    let name = arg.trim_matches('"');
+    let is_literal = name.starts_with("0x")
+        || name.starts_with('[')
+        || name.starts_with('(')
+        || name.chars().all(|c| c.is_ascii_digit());
+    if name.is_empty() || is_literal {
+        return None;
+    }
+    Some(name)
+}
+
+async fn account_of_index(cli: &WsClient, index: u32) -> Result<AccountId32> {
+    let key = [
+        &twox_128(b"Indices")[..],
+        &twox_128(b"Accounts")[..],
+        &blake2_128(&index.encode())[..],
+        &index.encode()[..],
+    ]
+    .concat();
+    let value: Option<Bytes> = cli
+        .request("state_getStorage", rpc_params![Bytes(key)])
+        .await?;
+    let value = value.context(format!(
+        "No account has the index {}, or the chain has no indices pallet",
+        index
+    ))?;
+    // the account is followed by its deposit and whether it is frozen
+    AccountId32::decode(&mut &value.0[..]).context("Failed to decode the account of the index")
+}
+
+async fn account_of_identity(cli: &WsClient, name: &str) -> Result<AccountId32> {
+    let prefix = [&twox_128(b"Identity")[..], &twox_128(b"IdentityOf")[..]].concat();
+    let mut matches = Vec::new();
+    let mut start: Option<StorageKey> = None;
+    loop {
+        let keys: Vec<StorageKey> = cli
+            .request(
+                "state_getKeysPaged",
+                rpc_params![Bytes(prefix.clone()), PAGE_SIZE, start.clone()],
+            )
+            .await?;
+        if keys.is_empty() {
+            break;
+        }
+        let change_sets: Vec<StorageChangeSet<H256>> = cli
+            .request("state_queryStorageAt", rpc_params![keys.clone()])
+            .await?;
+        for (key, value) in change_sets.into_iter().flat_map(|set| set.changes) {
+            if let Some(StorageData(value)) = value {
+                // registrations which fail to decode are skipped, as their name is unknown
+                let display = display_name(&mut &value[..]).ok().flatten();
+                if display.as_deref() == Some(name.as_bytes()) {
+                    // the key ends with the account, after its `twox_64` hash
+                    let account = &key.0[key.0.len().saturating_sub(32)..];
+                    matches.push(AccountId32::decode(&mut &account[..])?);
+                }
+            }
+        }
+        if keys.len() < PAGE_SIZE as usize {
+            break;
+        }
+        start = keys.last().cloned();
+    }
+    match &matches[..] {
+        [account] => Ok(account.clone()),
+        [] => anyhow::bail!(
+            "'{}' is neither an SS58 address, an alias of the address book, an `index:<n>` nor \
+             the display name of an identity on the chain",
+            name
+        ),
+        accounts => anyhow::bail!(
+            "The display name '{}' is ambiguous, it is the identity of {}, give one of them",
+            name,
+            accounts
+                .iter()
+                .map(|account| account.to_ss58check())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Decodes the display name of the `Registration` of an identity, if it is stored as raw bytes.
+fn display_name(input: &mut &[u8]) -> Result<Option<Vec<u8>>> {
+    let judgements = Compact::<u32>::decode(input)?.0;
+    for _ in 0..judgements {
+        let _registrar = u32::decode(input)?;
+        // `Judgement::FeePaid` is the only judgement with a value, the fee
+        if input.read_byte()? == 1 {
+            u128::decode(input)?;
+        }
+    }
+    let _deposit = u128::decode(input)?;
+    // the `additional` fields of the `IdentityInfo` precede its `display`
+    let additional = Compact::<u32>::decode(input)?.0;
+    for _ in 0..additional {
+        data(input)?;
+        data(input)?;
+    }
+    data(input)
+}
+
+/// Decodes the `Data` of an identity field, which is `None` unless it is stored as raw bytes.
+fn data(input: &mut &[u8]) -> Result<Option<Vec<u8>>> {
+    match input.read_byte()? {
+        0 => Ok(None),
+        raw @ 1..=33 => {
+            let mut bytes = vec![0; usize::from(raw - 1)];
+            input.read(&mut bytes)?;
+            Ok(Some(bytes))
+        }
+        // `BlakeTwo256`, `Sha256`, `Keccak256` and `ShaThree256`
+        34..=37 => {
+            <[u8; 32]>::decode(input)?;
+            Ok(None)
+        }
+        variant => anyhow::bail!("Invalid variant {} of the data of an identity", variant),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_names_are_decoded() {
+        let mut registration = Vec::new();
+        // a `FeePaid` and a `Reasonable` judgement
+        Compact(2u32).encode_to(&mut registration);
+        (0u32, 1u8, 100u128).encode_to(&mut registration);
+        (1u32, 2u8).encode_to(&mut registration);
+        // the deposit and one additional field, with a raw key and a hashed value
+        1_000u128.encode_to(&mut registration);
+        Compact(1u32).encode_to(&mut registration);
+        registration.extend([4, b'k', b'e', b'y']);
+        registration.push(34);
+        registration.extend([0; 32]);
+        // the display name
+        registration.extend([6, b'A', b'l', b'i', b'c', b'e']);
+
+        let name = display_name(&mut &registration[..]).unwrap();
+        assert_eq!(name.as_deref(), Some(&b"Alice"[..]));
+    }
+
+    #[test]
+    fn only_indices_and_names_are_resolved_on_chain() {
+        assert_eq!(on_chain_name("index:42"), Some("index:42"));
+        assert_eq!(on_chain_name("alice"), Some("alice"));
+        assert_eq!(on_chain_name(r#""Alice Smith""#), Some("Alice Smith"));
+        assert_eq!(
+            on_chain_name("0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d"),
+            None
+        );
+        assert_eq!(on_chain_name("[0, 1, 2]"), None);
+        assert_eq!(on_chain_name("42"), None);
+        assert_eq!(on_chain_name(""), None);
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    load_metadata, node_url,
+    runtime_api::api::{contracts::Event as ContractsEvent, Event},
+    transcode::Value,
+    ContractAccount, ContractMessageTranscoder, RuntimeApi,
+};
+use crate::{address_book::parse_address, name_value_println};
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use sp_core::crypto::Ss58Codec;
+use std::path::{Path, PathBuf};
+use subxt::{ClientBuilder, Config, DefaultConfig};
+
+type BlockHash = <DefaultConfig as Config>::Hash;
+
+/// Index the events of a contract into an SQLite database.
+///
+/// The events of all finalized blocks since `--from-block` are decoded with the metadata of the
+/// contract and written to the `events` table, afterwards new finalized blocks are followed. An
+/// interrupted index is resumed from the last indexed block.
+#[derive(Debug, clap::Args)]
+#[clap(name = "index")]
+pub struct IndexCommand {
+    /// The address of the contract, or its alias in the address book.
+    #[clap(long, env = "CONTRACT", parse(try_from_str = parse_address))]
+    contract: ContractAccount,
+    /// The SQLite database to write the events to, created if it does not exist.
+    #[clap(long, parse(from_os_str), default_value = "events.sqlite")]
+    db: PathBuf,
+    /// The first block to index, defaults to the block after the last indexed block or to the
+    /// genesis block.
+    #[clap(long)]
+    from_block: Option<u32>,
+    /// Path to the `Cargo.toml` of the contract, whose metadata is used to decode the events.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// Websockets url of a substrate node. Defaults to the url of the `--network`, or to
+    /// `ws://localhost:9944`.
+    #[clap(long, parse(try_from_str))]
+    url: Option<url::Url>,
+    /// The name of a network profile.
+    #[clap(long)]
+    network: Option<String>,
+}
+
+/// A decoded event emitted by the contract.
+#[derive(Debug, PartialEq)]
+struct IndexedEvent {
+    /// The index of the event within the events of its block.
+    index: u32,
+    /// The name of the event.
+    name: Option<String>,
+    /// The event fields in the display format of the decoded values.
+    fields: String,
+    /// The SCALE encoded event.
+    data: Vec<u8>,
+}
+
+impl IndexCommand {
+    pub fn run(&self) -> Result<()> {
+        let (_, contract_metadata, _) = load_metadata(self.manifest_path.as_ref())?;
+        let transcoder = ContractMessageTranscoder::new(&contract_metadata);
+        let url = node_url(
+            self.url.as_ref(),
+            self.network.as_deref(),
+            self.manifest_path.as_ref(),
+        )?;
+        let index = EventIndex::open(&self.db)?;
+        let contract = self.contract.to_ss58check();
+        let mut next = match self.from_block {
+            Some(from_block) => from_block,
+            None => index.last_block(&contract)?.map_or(0, |last| last + 1),
+        };
+
+        async_std::task::block_on(async {
+            let api = ClientBuilder::new()
+                .set_url(url.as_str())
+                .build()
+                .await?
+                .to_runtime_api::<RuntimeApi>();
+            let rpc = api.client.rpc();
+            let mut finalized = rpc.subscribe_finalized_blocks().await?;
+            name_value_println!("Indexing", format!("{} from block #{}", contract, next));
+
+            while let Some(header) = finalized.next().await {
+                let head = header?.number;
+                while next <= head {
+                    let hash = rpc
+                        .block_hash(Some(next.into()))
+                        .await?
+                        .context(format!("Block {} not found", next))?;
+                    let records = api.storage().system().events(Some(hash)).await?;
+                    let events = records
+                        .into_iter()
+                        .enumerate()
+                        .filter_map(|(index, record)| match record.event {
+                            Event::Contracts(ContractsEvent::ContractEmitted {
+                                contract,
+                                data,
+                            }) if contract == self.contract => Some((index as u32, data)),
+                            _ => None,
+                        })
+                        .map(|(index, data)| decode_event(&transcoder, index, data))
+                        .collect::<Vec<_>>();
+                    if !events.is_empty() {
+                        log::info!("Indexed {} events of block #{}", events.len(), next);
+                    }
+                    index.insert(&contract, next, hash, &events)?;
+                    next += 1;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Decodes the `data` of a `ContractEmitted` event, events which fail to decode are indexed
+/// without their fields.
+fn decode_event(transcoder: &ContractMessageTranscoder, index: u32, data: Vec<u8>) -> IndexedEvent {
+    let (name, fields) = match transcoder.decode_contract_event(&mut &data[..]) {
+        Ok(Value::Map(map)) => (map.ident(), format!("{}", Value::Map(map))),
+        Ok(value) => (None, format!("{}", value)),
+        Err(err) => {
+            log::warn!("Failed to decode event {}: {:?}", index, err);
+            (None, String::new())
+        }
+    };
+    IndexedEvent {
+        index,
+        name,
+        fields,
+        data,
+    }
+}
+
+/// The SQLite database of indexed events.
+struct EventIndex {
+    connection: Connection,
+}
+
+impl EventIndex {
+    /// Opens the database at `path`, creating the tables if they do not exist yet.
+    fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path)
+            .context(format!("Failed to open database {}", path.display()))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS events (
+                contract TEXT NOT NULL,
+                block_number INTEGER NOT NULL,
+                block_hash TEXT NOT NULL,
+                event_index INTEGER NOT NULL,
+                name TEXT,
+                fields TEXT NOT NULL,
+                data BLOB NOT NULL,
+                PRIMARY KEY (block_hash, event_index)
+            );
+            CREATE INDEX IF NOT EXISTS events_by_name ON events (contract, name);
+            CREATE TABLE IF NOT EXISTS progress (
+                contract TEXT PRIMARY KEY,
+                last_block INTEGER NOT NULL
+            );",
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Returns the last block indexed for the `contract`.
+    fn last_block(&self, contract: &str) -> Result<Option<u32>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT last_block FROM progress WHERE contract = ?1",
+                params![contract],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Writes the `events` of the block and records the block as indexed, atomically.
+    fn insert(
+        &self,
+        contract: &str,
+        number: u32,
+        hash: BlockHash,
+        events: &[IndexedEvent],
+    ) -> Result<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        for event in events {
+            transaction.execute(
+                "INSERT OR REPLACE INTO events
+                    (contract, block_number, block_hash, event_index, name, fields, data)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    contract,
+                    number,
+                    format!("{:?}", hash),
+                    event.index,
+                    event.name,
+                    event.fields,
+                    event.data
+                ],
+            )?;
+        }
+        transaction.execute(
+            "INSERT OR REPLACE INTO progress (contract, last_block) VALUES (?1, ?2)",
+            params![contract, number],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    const CONTRACT: &str = "5C4hrfjw9DjXZTzV3MwzrrAr9P1MJhSrvWGWqi1eSuyUpnhM";
+
+    #[test]
+    fn events_are_indexed_and_resumed() {
+        with_tmp_dir(|path| {
+            let db = path.join("events.sqlite");
+            let index = EventIndex::open(&db)?;
+            assert_eq!(index.last_block(CONTRACT)?, None);
+
+            let event = IndexedEvent {
+                index: 2,
+                name: Some("Transfer".to_string()),
+                fields: "Transfer { value: 10 }".to_string(),
+                data: vec![0, 10],
+            };
+            index.insert(CONTRACT, 7, BlockHash::repeat_byte(1), &[event])?;
+            index.insert(CONTRACT, 8, BlockHash::repeat_byte(2), &[])?;
+
+            let index = EventIndex::open(&db)?;
+            assert_eq!(index.last_block(CONTRACT)?, Some(8));
+            let (number, name): (u32, String) = index.connection.query_row(
+                "SELECT block_number, name FROM events WHERE contract = ?1",
+                params![CONTRACT],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            assert_eq!((number, name.as_str()), (7, "Transfer"));
+            Ok(())
+        })
+    }
+}
@@ -15,23 +15,42 @@
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::{
-    display_contract_exec_result, display_events, parse_balance, runtime_api::api,
-    wait_for_success_and_handle_error, Balance, CodeHash, ContractAccount,
-    ContractMessageTranscoder, ExtrinsicOpts, PairSigner, RuntimeApi,
-    EXEC_RESULT_MAX_KEY_COL_WIDTH,
+    apps::display_apps_link,
+    constructor_args::{complete_args, configured_args},
+    display_contract_exec_result, display_events, encode_call,
+    fees::Cost,
+    identity::resolve_account_args,
+    parse_balance,
+    runtime_api::api,
+    session::{Session, SessionKind},
+    submission::submit_extrinsic,
+    transcoder_for_args,
+    xcm::XcmOpts,
+    Balance, CodeHash, ContractAccount, ContractMessageTranscoder, ExplorerPage, ExtrinsicOpts,
+    ExtrinsicSigner, RuntimeApi, EXEC_RESULT_MAX_KEY_COL_WIDTH,
+};
+use crate::{
+    exit_code::{classify, ExitCode},
+    name_value_println, timings,
+    util::decode_hex,
+    Verbosity,
 };
-use crate::{name_value_println, util::decode_hex, Verbosity};
 use anyhow::{anyhow, Context, Result};
 use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
 use serde::Serialize;
-use sp_core::{crypto::Ss58Codec, Bytes};
+use sp_core::{
+    crypto::Ss58Codec,
+    hashing::{blake2_256, twox_128},
+    storage::StorageKey,
+    Bytes,
+};
 use std::{
     fs,
     path::{Path, PathBuf},
 };
 use subxt::{rpc::NumberOrHex, ClientBuilder, Config, DefaultConfig, Signer};
 
-type ContractInstantiateResult =
+pub(super) type ContractInstantiateResult =
     pallet_contracts_primitives::ContractInstantiateResult<ContractAccount, Balance>;
 
 #[derive(Debug, clap::Args)]
@@ -49,7 +68,8 @@ pub struct InstantiateCommand {
     /// The name of the contract constructor to call
     #[clap(name = "constructor", long, default_value = "new")]
     constructor: String,
-    /// The constructor arguments, encoded as strings
+    /// The constructor arguments, encoded as strings. Omitted trailing arguments are taken from
+    /// `[package.metadata.contract.constructor-args.<constructor>]` or the default of their type.
     #[clap(long)]
     args: Vec<String>,
     #[clap(flatten)]
@@ -64,10 +84,20 @@ pub struct InstantiateCommand {
     /// of the same contract code from the same account.
     #[clap(long, parse(try_from_str = parse_hex_bytes))]
     salt: Option<Bytes>,
+    /// Record all RPC requests, responses and events of this instantiation to the given file, so
+    /// that the session can be reproduced offline with `cargo contract replay`.
+    #[clap(long, parse(from_os_str))]
+    record: Option<PathBuf>,
+    #[clap(flatten)]
+    xcm_opts: XcmOpts,
+    /// Upload the Wasm code with `instantiate_with_code` even if the chain already stores code
+    /// with the same hash, which is otherwise reused by instantiating it with `instantiate`.
+    #[clap(long, conflicts_with = "code_hash")]
+    force_upload: bool,
 }
 
 /// Parse a hex encoded 32 byte hash. Returns error if not exactly 32 bytes.
-fn parse_code_hash(input: &str) -> Result<<DefaultConfig as Config>::Hash> {
+pub(super) fn parse_code_hash(input: &str) -> Result<<DefaultConfig as Config>::Hash> {
     let bytes = decode_hex(input)?;
     if bytes.len() != 32 {
         anyhow::bail!("Code hash should be 32 bytes in length")
@@ -90,12 +120,31 @@ impl InstantiateCommand {
     /// Creates an extrinsic with the `Contracts::instantiate` Call, submits via RPC, then waits for
     /// the `ContractsEvent::Instantiated` event.
     pub fn run(&self) -> Result<()> {
-        let (crate_metadata, contract_metadata) =
+        let (crate_metadata, contract_metadata, constructor_return_types) =
             super::load_metadata(self.extrinsic_opts.manifest_path.as_ref())?;
-        let transcoder = ContractMessageTranscoder::new(&contract_metadata);
-        let data = transcoder.encode(&self.constructor, &self.args)?;
-        let signer = super::pair_signer(self.extrinsic_opts.signer()?);
-        let url = self.extrinsic_opts.url.clone();
+        let url = self.extrinsic_opts.url()?;
+        let (args, assumed) = complete_args(
+            &contract_metadata,
+            &configured_args(&crate_metadata),
+            &self.constructor,
+            &self.args,
+        )?;
+        for arg in assumed {
+            name_value_println!(
+                "Assumed",
+                format!("{} = {} ({})", arg.label, arg.value, arg.source)
+            );
+        }
+        let transcoder = transcoder_for_args(&contract_metadata, &url, &args)?
+            .with_constructor_return_types(constructor_return_types);
+        let args = resolve_account_args(&transcoder, &url, &self.constructor, &args)?;
+        let data = transcoder.encode(&self.constructor, &args)?;
+        // nothing is signed for `--apps-link`, the call is submitted by whoever opens the link
+        let signer = if self.extrinsic_opts.apps_link {
+            None
+        } else {
+            Some(self.extrinsic_opts.extrinsic_signer()?)
+        };
         let verbosity = self.extrinsic_opts.verbosity()?;
 
         fn load_code(wasm_path: &Path) -> Result<Code> {
@@ -118,8 +167,19 @@ impl InstantiateCommand {
             (None, Some(code_hash)) => Ok(Code::Existing(*code_hash)),
         }?;
         let salt = self.salt.clone().unwrap_or_else(|| Bytes(Vec::new()));
+        let session = self.record.as_ref().map(|path| {
+            Session::new(
+                path,
+                SessionKind::Instantiate,
+                &self.constructor,
+                &args,
+                &data,
+                &url,
+            )
+        });
 
         let args = InstantiateArgs {
+            constructor: self.constructor.clone(),
             value: self.value,
             gas_limit: self.gas_limit,
             storage_deposit_limit: self.extrinsic_opts.storage_deposit_limit,
@@ -127,19 +187,57 @@ impl InstantiateCommand {
             salt,
         };
 
-        let exec = Exec {
+        let mut exec = Exec {
             args,
+            opts: &self.extrinsic_opts,
             url,
             verbosity,
             signer,
             transcoder,
+            session,
+            xcm: self.xcm_opts.enabled(),
         };
 
-        async_std::task::block_on(async move { exec.exec(code, self.extrinsic_opts.dry_run).await })
+        async_std::task::block_on(async move {
+            exec.exec(code, self.extrinsic_opts.dry_run, self.force_upload)
+                .await
+        })
     }
 }
 
+/// Uploads the `code` and instantiates it with the constructor input `data`, returning the code
+/// hash and the account of the new contract.
+pub(super) fn instantiate_with_code(
+    opts: &ExtrinsicOpts,
+    transcoder: ContractMessageTranscoder<'_>,
+    code: Vec<u8>,
+    constructor: &str,
+    data: Vec<u8>,
+    value: Balance,
+    gas_limit: u64,
+) -> Result<(CodeHash, ContractAccount)> {
+    let mut exec = Exec {
+        args: InstantiateArgs {
+            constructor: constructor.to_string(),
+            value,
+            gas_limit,
+            storage_deposit_limit: opts.storage_deposit_limit,
+            data,
+            salt: Bytes(Vec::new()),
+        },
+        opts,
+        url: opts.url()?,
+        verbosity: opts.verbosity()?,
+        signer: Some(opts.extrinsic_signer()?),
+        transcoder,
+        session: None,
+        xcm: None,
+    };
+    async_std::task::block_on(exec.instantiate_with_code(code.into()))
+}
+
 struct InstantiateArgs {
+    constructor: String,
     value: super::Balance,
     gas_limit: u64,
     storage_deposit_limit: Option<Balance>,
@@ -149,13 +247,23 @@ struct InstantiateArgs {
 
 pub struct Exec<'a> {
     args: InstantiateArgs,
+    opts: &'a ExtrinsicOpts,
     verbosity: Verbosity,
     url: url::Url,
-    signer: PairSigner,
+    signer: Option<ExtrinsicSigner>,
     transcoder: ContractMessageTranscoder<'a>,
+    session: Option<Session>,
+    xcm: Option<&'a XcmOpts>,
 }
 
 impl<'a> Exec<'a> {
+    /// The signer of the extrinsics, which is only absent for `--apps-link`.
+    fn signer(&self) -> &ExtrinsicSigner {
+        self.signer
+            .as_ref()
+            .expect("the signer is loaded unless `--apps-link` is passed")
+    }
+
     async fn subxt_api(&self) -> Result<RuntimeApi> {
         let api = ClientBuilder::new()
             .set_url(self.url.to_string())
@@ -165,42 +273,32 @@ impl<'a> Exec<'a> {
         Ok(api)
     }
 
-    async fn exec(&self, code: Code, dry_run: bool) -> Result<()> {
+    async fn exec(&mut self, code: Code, dry_run: bool, force_upload: bool) -> Result<()> {
+        let code = match code {
+            Code::Upload(code) if !force_upload => self.reuse_uploaded_code(code).await?,
+            code => code,
+        };
         if dry_run {
             let result = self.instantiate_dry_run(code).await?;
-            match result.result {
-                Ok(ref ret_val) => {
-                    name_value_println!(
-                        "Result",
-                        String::from("Success!"),
-                        EXEC_RESULT_MAX_KEY_COL_WIDTH
-                    );
-                    name_value_println!(
-                        "Contract",
-                        ret_val.account_id.to_ss58check(),
-                        EXEC_RESULT_MAX_KEY_COL_WIDTH
-                    );
-                    name_value_println!(
-                        "Reverted",
-                        format!("{:?}", ret_val.result.did_revert()),
-                        EXEC_RESULT_MAX_KEY_COL_WIDTH
-                    );
-                    name_value_println!(
-                        "Data",
-                        format!("{:?}", ret_val.result.data),
-                        EXEC_RESULT_MAX_KEY_COL_WIDTH
-                    );
-                }
-                Err(err) => {
-                    name_value_println!(
-                        "Result",
-                        format!("Error: {:?}", err),
-                        EXEC_RESULT_MAX_KEY_COL_WIDTH
-                    );
-                }
-            }
-            display_contract_exec_result(&result)?;
-            return Ok(());
+            display_instantiate_result(&result, &self.transcoder, &self.args.constructor)?;
+            return match result.result {
+                Ok(ret_val) if ret_val.result.did_revert() => Err(revert_error(
+                    &self.transcoder,
+                    &self.args.constructor,
+                    &ret_val.result.data.0,
+                )),
+                Ok(_) => Ok(()),
+                Err(err) => Err(classify(
+                    anyhow!("The dry-run failed: {:?}", err),
+                    ExitCode::Dispatch,
+                )),
+            };
+        }
+        if let Some(xcm) = self.xcm {
+            return self.instantiate_via_xcm(code, xcm).await;
+        }
+        if self.opts.apps_link {
+            return self.apps_link(code).await;
         }
 
         match code {
@@ -211,31 +309,52 @@ impl<'a> Exec<'a> {
             }
             Code::Existing(code_hash) => {
                 let contract_account = self.instantiate(code_hash).await?;
+                name_value_println!("Code hash", format!("{:?}", code_hash));
                 name_value_println!("Contract", contract_account.to_ss58check());
             }
         }
         Ok(())
     }
 
-    async fn instantiate_with_code(&self, code: Bytes) -> Result<(CodeHash, ContractAccount)> {
+    /// Instantiates the already uploaded code instead of uploading the `code` again, if the chain
+    /// stores code with its hash. This saves the fee and the deposit of the upload.
+    async fn reuse_uploaded_code(&self, code: Bytes) -> Result<Code> {
+        let code_hash = wasm_code_hash(&code);
         let api = self.subxt_api().await?;
-        let tx_progress = api
-            .tx()
-            .contracts()
-            .instantiate_with_code(
-                self.args.value,
-                self.args.gas_limit,
-                self.args.storage_deposit_limit,
-                code.to_vec(),
-                self.args.data.clone(),
-                self.args.salt.0.clone(),
-            )
-            .sign_and_submit_then_watch(&self.signer)
+        let owner_info = api
+            .client
+            .rpc()
+            .storage(&owner_info_key(&code_hash), None)
             .await?;
+        if owner_info.is_none() {
+            return Ok(Code::Upload(code));
+        }
+        name_value_println!(
+            "Reusing code",
+            format!("{:?} is already stored on chain", code_hash)
+        );
+        log::info!("Pass `--force-upload` to upload the code with `instantiate_with_code` anyway");
+        Ok(Code::Existing(code_hash))
+    }
 
-        let result = wait_for_success_and_handle_error(tx_progress).await?;
-
+    async fn instantiate_with_code(&mut self, code: Bytes) -> Result<(CodeHash, ContractAccount)> {
+        let api = self.subxt_api().await?;
+        let cost = self.estimate_cost(Code::Upload(code.clone())).await?;
+        let instantiate_with_code = api::contracts::calls::InstantiateWithCode {
+            value: self.args.value,
+            gas_limit: self.args.gas_limit,
+            storage_deposit_limit: self.args.storage_deposit_limit,
+            code: code.to_vec(),
+            data: self.args.data.clone(),
+            salt: self.args.salt.0.clone(),
+        };
         let metadata = api.client.metadata();
+        let encoded = encode_call(metadata, &instantiate_with_code)?;
+        let result =
+            submit_extrinsic(&api, instantiate_with_code, self.signer(), self.opts, cost).await?;
+        if let Some(session) = self.session.as_mut() {
+            session.record_extrinsic(encoded, &result)?;
+        }
 
         display_events(&result, &self.transcoder, metadata, &self.verbosity)?;
 
@@ -246,38 +365,122 @@ impl<'a> Exec<'a> {
             .find_first::<api::contracts::events::Instantiated>()?
             .ok_or_else(|| anyhow!("Failed to find Instantiated event"))?;
 
+        display_explorer_links(
+            self.opts,
+            &[
+                ExplorerPage::Contract(instantiated.contract.clone()),
+                ExplorerPage::CodeHash(code_stored.code_hash),
+                ExplorerPage::Extrinsic(result.extrinsic_hash()),
+                ExplorerPage::Block(result.block_hash()),
+            ],
+        )?;
+
         Ok((code_stored.code_hash, instantiated.contract))
     }
 
-    async fn instantiate(&self, code_hash: CodeHash) -> Result<ContractAccount> {
+    /// Sends the instantiation as a `Transact` XCM from another chain. The contract is
+    /// instantiated by the origin the message is dispatched with on the parachain, so its address
+    /// is not known to the sender.
+    async fn instantiate_via_xcm(&self, code: Code, xcm: &XcmOpts) -> Result<()> {
         let api = self.subxt_api().await?;
-        let tx_progress = api
-            .tx()
-            .contracts()
-            .instantiate(
-                self.args.value,
-                self.args.gas_limit,
-                self.args.storage_deposit_limit,
-                code_hash,
-                self.args.data.clone(),
-                self.args.salt.0.clone(),
-            )
-            .sign_and_submit_then_watch(&self.signer)
+        let call = self.encode_call(api.client.metadata(), code)?;
+        let hash = xcm
+            .send_transact(call, self.args.gas_limit, self.signer())
             .await?;
+        name_value_println!("XCM sent", format!("{:?}", hash));
+        Ok(())
+    }
 
-        let result = wait_for_success_and_handle_error(tx_progress).await?;
+    /// Prints a link to review and submit the instantiation in polkadot-js apps.
+    async fn apps_link(&self, code: Code) -> Result<()> {
+        let api = self.subxt_api().await?;
+        let metadata = api.client.metadata();
+        let call = self.encode_call(metadata, code)?;
+        display_apps_link(self.opts, call)
+    }
 
+    /// Encodes the call of `instantiate_with_code` or `instantiate` with its call index.
+    fn encode_call(&self, metadata: &subxt::Metadata, code: Code) -> Result<Vec<u8>> {
+        match code {
+            Code::Upload(code) => {
+                let call = api::contracts::calls::InstantiateWithCode {
+                    value: self.args.value,
+                    gas_limit: self.args.gas_limit,
+                    storage_deposit_limit: self.args.storage_deposit_limit,
+                    code: code.0,
+                    data: self.args.data.clone(),
+                    salt: self.args.salt.0.clone(),
+                };
+                encode_call(metadata, &call)
+            }
+            Code::Existing(code_hash) => {
+                let call = api::contracts::calls::Instantiate {
+                    value: self.args.value,
+                    gas_limit: self.args.gas_limit,
+                    storage_deposit_limit: self.args.storage_deposit_limit,
+                    code_hash,
+                    data: self.args.data.clone(),
+                    salt: self.args.salt.0.clone(),
+                };
+                encode_call(metadata, &call)
+            }
+        }
+    }
+
+    async fn instantiate(&mut self, code_hash: CodeHash) -> Result<ContractAccount> {
+        let api = self.subxt_api().await?;
+        let cost = self.estimate_cost(Code::Existing(code_hash)).await?;
+        let instantiate = api::contracts::calls::Instantiate {
+            value: self.args.value,
+            gas_limit: self.args.gas_limit,
+            storage_deposit_limit: self.args.storage_deposit_limit,
+            code_hash,
+            data: self.args.data.clone(),
+            salt: self.args.salt.0.clone(),
+        };
         let metadata = api.client.metadata();
+        let encoded = encode_call(metadata, &instantiate)?;
+        let result = submit_extrinsic(&api, instantiate, self.signer(), self.opts, cost).await?;
+        if let Some(session) = self.session.as_mut() {
+            session.record_extrinsic(encoded, &result)?;
+        }
         display_events(&result, &self.transcoder, metadata, &self.verbosity)?;
 
         let instantiated = result
             .find_first::<api::contracts::events::Instantiated>()?
             .ok_or_else(|| anyhow!("Failed to find Instantiated event"))?;
 
+        display_explorer_links(
+            self.opts,
+            &[
+                ExplorerPage::Contract(instantiated.contract.clone()),
+                ExplorerPage::Extrinsic(result.extrinsic_hash()),
+                ExplorerPage::Block(result.block_hash()),
+            ],
+        )?;
+
         Ok(instantiated.contract)
     }
 
-    async fn instantiate_dry_run(&self, code: Code) -> Result<ContractInstantiateResult> {
+    /// Estimates the storage deposit of the instantiation with a dry-run.
+    ///
+    /// Fails if the constructor reverts, as the revert of the extrinsic would only be reported as
+    /// `ContractReverted`, without the error returned by the constructor.
+    async fn estimate_cost(&mut self, code: Code) -> Result<Cost> {
+        let result = self.instantiate_dry_run(code).await?;
+        if let Ok(ret_val) = &result.result {
+            if ret_val.result.did_revert() {
+                return Err(revert_error(
+                    &self.transcoder,
+                    &self.args.constructor,
+                    &ret_val.result.data.0,
+                ));
+            }
+        }
+        Ok(Cost::new(self.args.value, &result.storage_deposit))
+    }
+
+    async fn instantiate_dry_run(&mut self, code: Code) -> Result<ContractInstantiateResult> {
         let url = self.url.to_string();
         let cli = WsClientBuilder::default().build(&url).await?;
         let storage_deposit_limit = self
@@ -286,7 +489,7 @@ impl<'a> Exec<'a> {
             .as_ref()
             .map(|limit| NumberOrHex::Hex((*limit).into()));
         let call_request = InstantiateRequest {
-            origin: self.signer.account_id().clone(),
+            origin: self.signer().account_id().clone(),
             value: NumberOrHex::Hex(self.args.value.into()),
             gas_limit: NumberOrHex::Number(self.args.gas_limit),
             storage_deposit_limit,
@@ -294,30 +497,129 @@ impl<'a> Exec<'a> {
             data: self.args.data.clone().into(),
             salt: self.args.salt.clone(),
         };
-        let params = rpc_params![call_request];
-        let result: ContractInstantiateResult =
-            cli.request("contracts_instantiate", params).await?;
+        let params = rpc_params![&call_request];
+        let dry_run = timings::phase("Dry-run RPC");
+        let response: serde_json::Value = cli.request("contracts_instantiate", params).await?;
+        drop(dry_run);
+        if let Some(session) = self.session.as_mut() {
+            session.record_rpc("contracts_instantiate", &call_request, &response)?;
+        }
+        let result: ContractInstantiateResult = serde_json::from_value(response)?;
         Ok(result)
     }
 }
 
+/// Print to stdout the result of an `instantiate` dry-run via RPC, decoding the data returned by
+/// the `constructor` if its return type is known.
+pub(super) fn display_instantiate_result(
+    result: &ContractInstantiateResult,
+    transcoder: &ContractMessageTranscoder<'_>,
+    constructor: &str,
+) -> Result<()> {
+    match result.result {
+        Ok(ref ret_val) => {
+            name_value_println!(
+                "Result",
+                String::from("Success!"),
+                EXEC_RESULT_MAX_KEY_COL_WIDTH
+            );
+            name_value_println!(
+                "Contract",
+                ret_val.account_id.to_ss58check(),
+                EXEC_RESULT_MAX_KEY_COL_WIDTH
+            );
+            name_value_println!(
+                "Reverted",
+                format!("{:?}", ret_val.result.did_revert()),
+                EXEC_RESULT_MAX_KEY_COL_WIDTH
+            );
+            name_value_println!(
+                "Data",
+                describe_return(transcoder, constructor, &ret_val.result.data.0),
+                EXEC_RESULT_MAX_KEY_COL_WIDTH
+            );
+        }
+        Err(ref err) => {
+            name_value_println!(
+                "Result",
+                format!("Error: {:?}", err),
+                EXEC_RESULT_MAX_KEY_COL_WIDTH
+            );
+        }
+    }
+    display_contract_exec_result(result)?;
+    Ok(())
+}
+
+/// Describes the `data` returned by the `constructor`, decoded with its return type if it is known.
+fn describe_return(
+    transcoder: &ContractMessageTranscoder<'_>,
+    constructor: &str,
+    data: &[u8],
+) -> String {
+    match transcoder.decode_constructor_return(constructor, &mut &data[..]) {
+        Ok(Some(value)) => value.to_string(),
+        Ok(None) => format!("0x{}", hex::encode(data)),
+        Err(err) => {
+            log::debug!(
+                "Failed to decode the data returned by {}: {}",
+                constructor,
+                err
+            );
+            format!("0x{}", hex::encode(data))
+        }
+    }
+}
+
+/// Returns the error of the revert of the `constructor` with the returned `data`, e.g. the error
+/// of a fallible constructor.
+fn revert_error(
+    transcoder: &ContractMessageTranscoder<'_>,
+    constructor: &str,
+    data: &[u8],
+) -> anyhow::Error {
+    let error = if data.is_empty() {
+        anyhow!("The constructor reverted")
+    } else {
+        anyhow!(
+            "The constructor reverted with {}",
+            describe_return(transcoder, constructor, data)
+        )
+    };
+    classify(error, ExitCode::Reverted)
+}
+
 /// A struct that encodes RPC parameters required to instantiate a new smart contract.
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-struct InstantiateRequest {
-    origin: <DefaultConfig as Config>::AccountId,
-    value: NumberOrHex,
-    gas_limit: NumberOrHex,
-    storage_deposit_limit: Option<NumberOrHex>,
-    code: Code,
-    data: Bytes,
-    salt: Bytes,
+pub(super) struct InstantiateRequest {
+    pub(super) origin: <DefaultConfig as Config>::AccountId,
+    pub(super) value: NumberOrHex,
+    pub(super) gas_limit: NumberOrHex,
+    pub(super) storage_deposit_limit: Option<NumberOrHex>,
+    pub(super) code: Code,
+    pub(super) data: Bytes,
+    pub(super) salt: Bytes,
+}
+
+/// The hash of the `code` as computed by `pallet-contracts` on upload.
+pub(super) fn wasm_code_hash(code: &[u8]) -> CodeHash {
+    blake2_256(code).into()
+}
+
+/// The storage key of the owner of the code with the `code_hash`, which exists for every
+/// uploaded code and is much smaller than the code itself.
+pub(super) fn owner_info_key(code_hash: &CodeHash) -> StorageKey {
+    let mut key = [twox_128(b"Contracts"), twox_128(b"OwnerInfoOf")].concat();
+    // `OwnerInfoOf` uses the `Identity` hasher
+    key.extend_from_slice(code_hash.as_ref());
+    StorageKey(key)
 }
 
 /// Reference to an existing code hash or a new Wasm module.
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-enum Code {
+pub(super) enum Code {
     /// A Wasm module as raw bytes.
     Upload(Bytes),
     /// The code hash of an on-chain Wasm blob.
@@ -341,4 +643,13 @@ mod tests {
                 .is_ok()
         )
     }
+
+    #[test]
+    fn owner_info_key_ends_with_code_hash() {
+        let code_hash = wasm_code_hash(b"\0asm");
+        let key = owner_info_key(&code_hash).0;
+        assert_eq!(key.len(), 16 + 16 + 32);
+        assert_eq!(&key[..16], &twox_128(b"Contracts"));
+        assert_eq!(&key[32..], code_hash.as_ref());
+    }
 }
@@ -14,12 +14,9 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use anyhow::Result;
+use crate::testing::{ContractsNode, CONTRACTS_NODE};
 use predicates::prelude::*;
-use std::{ffi::OsStr, path::Path, process, str, thread, time};
-use subxt::{Client, ClientBuilder};
-
-const CONTRACTS_NODE: &str = "substrate-contracts-node";
+use std::{path::Path, str};
 
 /// Create a `cargo contract` command
 fn cargo_contract(path: &Path) -> assert_cmd::Command {
@@ -28,87 +25,6 @@ fn cargo_contract(path: &Path) -> assert_cmd::Command {
     cmd
 }
 
-/// Spawn and manage an instance of a compatible contracts enabled chain node.
-#[allow(dead_code)]
-struct ContractsNodeProcess {
-    proc: process::Child,
-    tmp_dir: tempfile::TempDir,
-    client: Client<subxt::DefaultConfig>,
-}
-
-impl Drop for ContractsNodeProcess {
-    fn drop(&mut self) {
-        self.kill()
-    }
-}
-
-impl ContractsNodeProcess {
-    async fn spawn<S>(program: S) -> Result<Self>
-    where
-        S: AsRef<OsStr>,
-    {
-        let tmp_dir = tempfile::Builder::new()
-            .prefix("cargo-contract.cli.test.node")
-            .tempdir()?;
-
-        let mut proc = process::Command::new(program)
-            .env("RUST_LOG", "error")
-            .arg("--dev")
-            .arg(format!("--base-path={}", tmp_dir.path().to_string_lossy()))
-            .spawn()?;
-        // wait for rpc to be initialized
-        const MAX_ATTEMPTS: u32 = 10;
-        let mut attempts = 1;
-        let client = loop {
-            thread::sleep(time::Duration::from_secs(1));
-            log::info!(
-                "Connecting to contracts enabled node, attempt {}/{}",
-                attempts,
-                MAX_ATTEMPTS
-            );
-            let result = ClientBuilder::new().build().await;
-            if let Ok(client) = result {
-                break Ok(client);
-            }
-            if attempts < MAX_ATTEMPTS {
-                attempts += 1;
-                continue;
-            }
-            if let Err(err) = result {
-                break Err(err);
-            }
-        };
-        match client {
-            Ok(client) => Ok(Self {
-                proc,
-                client,
-                tmp_dir,
-            }),
-            Err(err) => {
-                let err = anyhow::anyhow!(
-                    "Failed to connect to node rpc after {} attempts: {}",
-                    attempts,
-                    err
-                );
-                log::error!("{}", err);
-                proc.kill()?;
-                Err(err)
-            }
-        }
-    }
-
-    fn kill(&mut self) {
-        log::info!("Killing contracts node process {}", self.proc.id());
-        if let Err(err) = self.proc.kill() {
-            log::error!(
-                "Error killing contracts node process {}: {}",
-                self.proc.id(),
-                err
-            )
-        }
-    }
-}
-
 /// Sanity test the whole lifecycle of:
 ///   new -> build -> upload -> instantiate -> call
 ///
@@ -132,7 +48,7 @@ async fn build_upload_instantiate_call() {
         .expect("temporary directory creation failed");
 
     // Spawn the contracts node
-    let node_process = ContractsNodeProcess::spawn(CONTRACTS_NODE)
+    let node_process = ContractsNode::spawn(CONTRACTS_NODE)
         .await
         .expect("Error spawning contracts node");
 
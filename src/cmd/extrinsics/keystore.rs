@@ -0,0 +1,176 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Accounts exported as JSON keystore files from polkadot-js or its browser extension.
+//!
+//! The secret key is PKCS#8 encoded and, unless the account has no password, encrypted with
+//! `xsalsa20-poly1305` using a key derived from the password with `scrypt`.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use sp_core::{sr25519, Pair};
+use std::path::Path;
+use xsalsa20poly1305::{
+    aead::{Aead, NewAead},
+    Key, XNonce, XSalsa20Poly1305,
+};
+
+const PKCS8_HEADER: [u8; 16] = [48, 83, 2, 1, 1, 48, 5, 6, 3, 43, 101, 112, 4, 34, 4, 32];
+const PKCS8_DIVIDER: [u8; 5] = [161, 35, 3, 33, 0];
+const SECRET_KEY_LEN: usize = 64;
+const PUBLIC_KEY_LEN: usize = 32;
+/// The salt followed by the `N`, `p` and `r` parameters of `scrypt`.
+const SCRYPT_HEADER_LEN: usize = 32 + 3 * 4;
+const NONCE_LEN: usize = 24;
+
+#[derive(Debug, Deserialize)]
+struct KeystoreFile {
+    encoded: String,
+    encoding: Encoding,
+}
+
+#[derive(Debug, Deserialize)]
+struct Encoding {
+    /// The key format and the signature scheme, e.g. `["pkcs8", "sr25519"]`.
+    content: Vec<String>,
+    /// The encryption, e.g. `["scrypt", "xsalsa20-poly1305"]` or `["none"]`.
+    #[serde(rename = "type")]
+    ty: Vec<String>,
+}
+
+/// Loads the sr25519 key pair of the keystore file at `path`, decrypted with the `password`.
+pub(super) fn load(path: &Path, password: Option<&str>) -> Result<sr25519::Pair> {
+    let contents = std::fs::read_to_string(path)
+        .context(format!("Failed to read keystore file {}", path.display()))?;
+    let file: KeystoreFile = serde_json::from_str(&contents)
+        .context(format!("Failed to parse keystore file {}", path.display()))?;
+    decode(&file, password)
+}
+
+fn decode(file: &KeystoreFile, password: Option<&str>) -> Result<sr25519::Pair> {
+    if !file
+        .encoding
+        .content
+        .iter()
+        .any(|content| content == "sr25519")
+    {
+        anyhow::bail!(
+            "Only sr25519 accounts can sign extrinsics, the keystore file contains {:?}",
+            file.encoding.content
+        )
+    }
+    let encoded = base64::decode(&file.encoded).context("Invalid base64 in keystore file")?;
+    let pkcs8 = if file.encoding.ty.iter().any(|ty| ty == "xsalsa20-poly1305") {
+        if !file.encoding.ty.iter().any(|ty| ty == "scrypt") {
+            anyhow::bail!("Only keystore files encrypted with scrypt (version 3) are supported")
+        }
+        let password = password.context("The keystore file is encrypted, pass its password")?;
+        decrypt(&encoded, password)?
+    } else {
+        encoded
+    };
+
+    let secret_start = PKCS8_HEADER.len();
+    let divider_start = secret_start + SECRET_KEY_LEN;
+    let public_start = divider_start + PKCS8_DIVIDER.len();
+    if pkcs8.len() < public_start + PUBLIC_KEY_LEN
+        || pkcs8[..secret_start] != PKCS8_HEADER
+        || pkcs8[divider_start..public_start] != PKCS8_DIVIDER
+    {
+        anyhow::bail!("Invalid PKCS#8 key in keystore file")
+    }
+    let secret = schnorrkel::SecretKey::from_ed25519_bytes(&pkcs8[secret_start..divider_start])
+        .map_err(|err| anyhow::anyhow!("Invalid secret key in keystore file: {}", err))?;
+    let pair = sr25519::Pair::from_seed_slice(&secret.to_bytes())
+        .map_err(|err| anyhow::anyhow!("Invalid secret key in keystore file: {:?}", err))?;
+    if pair.public().0[..] != pkcs8[public_start..public_start + PUBLIC_KEY_LEN] {
+        anyhow::bail!("The secret key of the keystore file does not match its public key")
+    }
+    Ok(pair)
+}
+
+fn decrypt(encoded: &[u8], password: &str) -> Result<Vec<u8>> {
+    if encoded.len() < SCRYPT_HEADER_LEN + NONCE_LEN {
+        anyhow::bail!("The encrypted key of the keystore file is too short")
+    }
+    let param = |index: usize| {
+        let start = 32 + index * 4;
+        u32::from_le_bytes(encoded[start..start + 4].try_into().expect("4 bytes"))
+    };
+    let (n, p, r) = (param(0), param(1), param(2));
+    if !n.is_power_of_two() {
+        anyhow::bail!("Invalid scrypt parameter N = {} in keystore file", n)
+    }
+    let params = scrypt::Params::new(n.trailing_zeros() as u8, r, p)
+        .map_err(|err| anyhow::anyhow!("Invalid scrypt parameters in keystore file: {}", err))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(password.as_bytes(), &encoded[..32], &params, &mut key)
+        .map_err(|err| anyhow::anyhow!("Failed to derive the key from the password: {}", err))?;
+
+    let nonce = XNonce::from_slice(&encoded[SCRYPT_HEADER_LEN..SCRYPT_HEADER_LEN + NONCE_LEN]);
+    XSalsa20Poly1305::new(Key::from_slice(&key))
+        .decrypt(nonce, &encoded[SCRYPT_HEADER_LEN + NONCE_LEN..])
+        .map_err(|_| anyhow::anyhow!("Invalid password for the keystore file"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes the key pair of the `seed` like polkadot-js, with a cheap `scrypt`.
+    fn keystore_file(seed: [u8; 32], password: &str) -> KeystoreFile {
+        let keypair = schnorrkel::MiniSecretKey::from_bytes(&seed)
+            .unwrap()
+            .expand_to_keypair(schnorrkel::ExpansionMode::Ed25519);
+        let mut pkcs8 = PKCS8_HEADER.to_vec();
+        pkcs8.extend(keypair.secret.to_ed25519_bytes());
+        pkcs8.extend(PKCS8_DIVIDER);
+        pkcs8.extend(keypair.public.to_bytes());
+
+        let (salt, nonce) = ([7u8; 32], [9u8; NONCE_LEN]);
+        let (log_n, p, r) = (4, 1, 8);
+        let mut key = [0u8; 32];
+        let params = scrypt::Params::new(log_n, r, p).unwrap();
+        scrypt::scrypt(password.as_bytes(), &salt, &params, &mut key).unwrap();
+        let encrypted = XSalsa20Poly1305::new(Key::from_slice(&key))
+            .encrypt(XNonce::from_slice(&nonce), &pkcs8[..])
+            .unwrap();
+
+        let mut encoded = salt.to_vec();
+        for param in [1u32 << log_n, p, r] {
+            encoded.extend(param.to_le_bytes());
+        }
+        encoded.extend(nonce);
+        encoded.extend(encrypted);
+        KeystoreFile {
+            encoded: base64::encode(encoded),
+            encoding: Encoding {
+                content: vec!["pkcs8".to_string(), "sr25519".to_string()],
+                ty: vec!["scrypt".to_string(), "xsalsa20-poly1305".to_string()],
+            },
+        }
+    }
+
+    #[test]
+    fn encrypted_keystore_is_decoded() {
+        let file = keystore_file([1; 32], "secret");
+        let pair = decode(&file, Some("secret")).unwrap();
+
+        assert_eq!(pair.public(), sr25519::Pair::from_seed(&[1; 32]).public());
+        assert!(decode(&file, Some("wrong")).is_err());
+        assert!(decode(&file, None).is_err());
+    }
+}
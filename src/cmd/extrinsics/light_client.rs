@@ -0,0 +1,495 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The embedded light client of `--light`, which syncs the chain from its chain specification
+//! instead of trusting the node of an RPC endpoint.
+//!
+//! The light client serves its JSON-RPC on a local WebSocket, to which the extrinsics connect like
+//! to a node. It does not serve the `contracts_*` RPC of a node, which is translated into
+//! `state_call` of the `ContractsApi` runtime API, assuming the `u128` balances of Substrate.
+//!
+//! Requires the `light-client` feature.
+
+use anyhow::Result;
+use scale::Encode;
+use serde_json::{json, Value};
+
+#[cfg(feature = "light-client")]
+pub(super) use self::bridge::url;
+
+/// Fails, as `cargo-contract` is built without the `light-client` feature.
+#[cfg(not(feature = "light-client"))]
+pub(super) fn url(
+    _chain_spec: &std::path::Path,
+    _relay_chain_spec: Option<&std::path::Path>,
+) -> Result<url::Url> {
+    anyhow::bail!(
+        "`--light` requires cargo-contract to be built with the `light-client` feature, e.g. \
+         `cargo install cargo-contract --features light-client`"
+    )
+}
+
+/// A `contracts_*` RPC of a node, which is translated into a runtime API call.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Translation {
+    Call,
+    Instantiate,
+    UploadCode,
+}
+
+impl Translation {
+    fn of_method(method: &str) -> Option<Self> {
+        match method {
+            "contracts_call" => Some(Self::Call),
+            "contracts_instantiate" => Some(Self::Instantiate),
+            "contracts_upload_code" => Some(Self::UploadCode),
+            _ => None,
+        }
+    }
+
+    /// The runtime API function of the RPC.
+    fn function(&self) -> &'static str {
+        match self {
+            Self::Call => "ContractsApi_call",
+            Self::Instantiate => "ContractsApi_instantiate",
+            Self::UploadCode => "ContractsApi_upload_code",
+        }
+    }
+
+    /// Returns the SCALE encoded arguments of the runtime API function for the JSON `request` of
+    /// the RPC.
+    fn encode_args(&self, request: &Value) -> Result<Vec<u8>> {
+        let origin = account(&request["origin"])?;
+        let storage_deposit_limit = match &request["storageDepositLimit"] {
+            Value::Null => None,
+            limit => Some(number(limit)?),
+        };
+        Ok(match self {
+            Self::Call => (
+                origin,
+                account(&request["dest"])?,
+                number(&request["value"])?,
+                u64::try_from(number(&request["gasLimit"])?)?,
+                storage_deposit_limit,
+                bytes(&request["inputData"])?,
+            )
+                .encode(),
+            Self::Instantiate => {
+                // `Code::Upload` and `Code::Existing` of `pallet-contracts-primitives`
+                let code = match (&request["code"]["upload"], &request["code"]["existing"]) {
+                    (Value::Null, Value::Null) => anyhow::bail!("Missing code to instantiate"),
+                    (Value::Null, hash) => (1u8, code_hash(hash)?).encode(),
+                    (code, _) => (0u8, bytes(code)?).encode(),
+                };
+                let mut args = (
+                    origin,
+                    number(&request["value"])?,
+                    u64::try_from(number(&request["gasLimit"])?)?,
+                    storage_deposit_limit,
+                )
+                    .encode();
+                args.extend(code);
+                (bytes(&request["data"])?, bytes(&request["salt"])?).encode_to(&mut args);
+                args
+            }
+            Self::UploadCode => {
+                let mut args = (origin, bytes(&request["code"])?, storage_deposit_limit).encode();
+                // the runtimes which support relaxed determinism take it as last argument
+                match request["determinism"].as_str() {
+                    Some("Enforced") | Some("Deterministic") => args.push(0),
+                    Some(_) => args.push(1),
+                    None => {}
+                }
+                args
+            }
+        })
+    }
+
+    /// Returns the JSON result of the RPC for the SCALE encoded `result` of the runtime API.
+    fn decode_result(&self, result: &[u8]) -> Result<Value> {
+        use scale::Decode;
+        let result = &mut &result[..];
+        Ok(match self {
+            Self::Call => serde_json::to_value(super::call::ContractExecResult::decode(result)?)?,
+            Self::Instantiate => serde_json::to_value(
+                super::instantiate::ContractInstantiateResult::decode(result)?,
+            )?,
+            Self::UploadCode => {
+                serde_json::to_value(super::upload::CodeUploadResult::decode(result)?)?
+            }
+        })
+    }
+}
+
+/// Returns the `state_call` request for the JSON-RPC `request`, and the translation of its result,
+/// if it is a `contracts_*` RPC. Other requests are returned unchanged.
+fn translate_request(mut request: Value) -> Result<(Value, Option<Translation>)> {
+    let translation = match request["method"].as_str().and_then(Translation::of_method) {
+        Some(translation) => translation,
+        None => return Ok((request, None)),
+    };
+    let args = translation.encode_args(&request["params"][0])?;
+    let mut params = vec![
+        json!(translation.function()),
+        json!(format!("0x{}", hex::encode(args))),
+    ];
+    if let Some(at) = request["params"].get(1).filter(|at| !at.is_null()) {
+        params.push(at.clone());
+    }
+    request["method"] = json!("state_call");
+    request["params"] = json!(params);
+    Ok((request, Some(translation)))
+}
+
+/// Translates the result of the `state_call` of the JSON-RPC `response` into the result of the
+/// `contracts_*` RPC.
+fn translate_response(mut response: Value, translation: Translation) -> Value {
+    let result = match response["result"].as_str() {
+        Some(result) => result.trim_start_matches("0x").to_string(),
+        None => return response,
+    };
+    let decoded = hex::decode(result)
+        .map_err(anyhow::Error::from)
+        .and_then(|result| translation.decode_result(&result));
+    match decoded {
+        Ok(result) => response["result"] = result,
+        Err(err) => {
+            if let Some(response) = response.as_object_mut() {
+                response.remove("result");
+            }
+            response["error"] = json!({
+                "code": -32603,
+                "message": format!("Failed to decode the result of the runtime API: {:#}", err),
+            });
+        }
+    }
+    response
+}
+
+/// Parses a `u128` given as JSON number or as hex string, like `NumberOrHex`.
+fn number(value: &Value) -> Result<u128> {
+    match value {
+        Value::Number(number) => number
+            .as_u64()
+            .map(u128::from)
+            .ok_or_else(|| anyhow::anyhow!("Invalid number {}", number)),
+        Value::String(hex) => Ok(u128::from_str_radix(hex.trim_start_matches("0x"), 16)?),
+        _ => anyhow::bail!("Expected a number, got {}", value),
+    }
+}
+
+/// Parses the hex string of `Bytes`.
+fn bytes(value: &Value) -> Result<Vec<u8>> {
+    match value.as_str() {
+        Some(hex) => Ok(hex::decode(hex.trim_start_matches("0x"))?),
+        None => anyhow::bail!("Expected hex encoded bytes, got {}", value),
+    }
+}
+
+/// Parses the hex string of a code hash.
+fn code_hash(value: &Value) -> Result<[u8; 32]> {
+    <[u8; 32]>::try_from(bytes(value)?)
+        .map_err(|_| anyhow::anyhow!("Expected a 32 byte code hash, got {}", value))
+}
+
+/// Parses the SS58 address of an `AccountId32`.
+fn account(value: &Value) -> Result<sp_core::crypto::AccountId32> {
+    use sp_core::crypto::Ss58Codec;
+    match value.as_str() {
+        Some(address) => sp_core::crypto::AccountId32::from_ss58check(address)
+            .map_err(|err| anyhow::anyhow!("Invalid account {}: {:?}", address, err)),
+        None => anyhow::bail!("Expected an SS58 address, got {}", value),
+    }
+}
+
+#[cfg(feature = "light-client")]
+mod bridge {
+    use super::{translate_request, translate_response, Translation};
+    use crate::output;
+    use anyhow::{Context, Result};
+    use async_std::net::{TcpListener, TcpStream};
+    use futures::{AsyncRead, AsyncWrite, StreamExt};
+    use serde_json::Value;
+    use smoldot_light::{
+        platform::async_std::AsyncStdTcpWebSocket, AddChainConfig, AddChainConfigJsonRpc, ChainId,
+        Client, ClientConfig,
+    };
+    use soketto::handshake::{server::Response, Server};
+    use std::{
+        collections::HashMap,
+        iter,
+        num::NonZeroU32,
+        path::Path,
+        sync::{Arc, Mutex},
+    };
+    use url::Url;
+
+    /// The url of the local endpoint of the light client, once it is started.
+    static ENDPOINT: Mutex<Option<Url>> = Mutex::new(None);
+
+    /// The light client, with the chain specifications of its chain and relay chain.
+    struct LightClient {
+        client: Mutex<Client<AsyncStdTcpWebSocket>>,
+        chain_spec: String,
+        relay_chain: Option<ChainId>,
+    }
+
+    /// Starts the light client for the chain with the specification at `chain_spec`, a parachain
+    /// of the relay chain at `relay_chain_spec` if given, and returns the url of its local JSON-RPC
+    /// endpoint. The light client is started once and serves the whole process.
+    pub(in super::super) fn url(chain_spec: &Path, relay_chain_spec: Option<&Path>) -> Result<Url> {
+        let mut endpoint = ENDPOINT
+            .lock()
+            .expect("the endpoint lock is never poisoned");
+        if let Some(url) = &*endpoint {
+            return Ok(url.clone());
+        }
+        let read = |path: &Path| {
+            std::fs::read_to_string(path).context(format!("Failed to read {}", path.display()))
+        };
+        let chain_spec = read(chain_spec)?;
+        let relay_chain_spec = relay_chain_spec.map(read).transpose()?;
+
+        let mut client = Client::new(ClientConfig {
+            tasks_spawner: Box::new(|_name, task| {
+                async_std::task::spawn(task);
+            }),
+            system_name: env!("CARGO_PKG_NAME").into(),
+            system_version: env!("CARGO_PKG_VERSION").into(),
+        });
+        let relay_chain = match relay_chain_spec {
+            Some(relay_chain_spec) => {
+                let relay_chain = client
+                    .add_chain(AddChainConfig {
+                        user_data: (),
+                        specification: &relay_chain_spec,
+                        database_content: "",
+                        potential_relay_chains: iter::empty(),
+                        json_rpc: AddChainConfigJsonRpc::Disabled,
+                    })
+                    .map_err(|err| anyhow::anyhow!("Invalid relay chain specification: {}", err))?;
+                Some(relay_chain.chain_id)
+            }
+            None => None,
+        };
+        let light_client = Arc::new(LightClient {
+            client: Mutex::new(client),
+            chain_spec,
+            relay_chain,
+        });
+
+        let listener = async_std::task::block_on(TcpListener::bind("127.0.0.1:0"))?;
+        let url = Url::parse(&format!("ws://{}", listener.local_addr()?))?;
+        async_std::task::spawn(async move {
+            let mut incoming = listener.incoming();
+            while let Some(Ok(stream)) = incoming.next().await {
+                let light_client = light_client.clone();
+                async_std::task::spawn(async move {
+                    if let Err(err) = serve(light_client, stream).await {
+                        output::warning(format!("The light client connection failed: {:#}", err));
+                    }
+                });
+            }
+        });
+        *endpoint = Some(url.clone());
+        Ok(url)
+    }
+
+    /// Serves the JSON-RPC of the light client on the WebSocket `stream`.
+    ///
+    /// Every connection adds the chain to the light client, which shares the chains with the same
+    /// specification, so that the responses are received per connection.
+    async fn serve(light_client: Arc<LightClient>, stream: TcpStream) -> Result<()> {
+        let mut server = Server::new(stream);
+        let key = server.receive_request().await?.key();
+        server
+            .send_response(&Response::Accept {
+                key,
+                protocol: None,
+            })
+            .await?;
+        let (sender, mut receiver) = server.into_builder().finish();
+
+        let chain = {
+            let mut client = light_client.client.lock().expect("never poisoned");
+            client
+                .add_chain(AddChainConfig {
+                    user_data: (),
+                    specification: &light_client.chain_spec,
+                    database_content: "",
+                    potential_relay_chains: light_client.relay_chain.into_iter(),
+                    json_rpc: AddChainConfigJsonRpc::Enabled {
+                        max_pending_requests: NonZeroU32::new(128).expect("128 is not zero"),
+                        max_subscriptions: 1024,
+                    },
+                })
+                .map_err(|err| anyhow::anyhow!("Invalid chain specification: {}", err))?
+        };
+        let mut responses = chain
+            .json_rpc_responses
+            .context("The JSON-RPC of the chain is enabled")?;
+        // the translations of the pending requests, by their id
+        let pending = Arc::new(Mutex::new(HashMap::<String, Translation>::new()));
+
+        let forward = {
+            let pending = pending.clone();
+            async_std::task::spawn(async move {
+                let mut sender = sender;
+                while let Some(response) = responses.next().await {
+                    let response = translate(&pending, response);
+                    if send(&mut sender, &response).await.is_err() {
+                        break;
+                    }
+                }
+            })
+        };
+
+        let mut message = Vec::new();
+        let result = loop {
+            message.clear();
+            match receiver.receive_data(&mut message).await {
+                Ok(_) => {}
+                Err(soketto::connection::Error::Closed) => break Ok(()),
+                Err(err) => break Err(err.into()),
+            }
+            let (request, translation) = match serde_json::from_slice(&message)
+                .map_err(anyhow::Error::from)
+                .and_then(translate_request)
+            {
+                Ok(translated) => translated,
+                Err(err) => break Err(err.context("Invalid JSON-RPC request")),
+            };
+            if let Some(translation) = translation {
+                let id = request["id"].to_string();
+                pending
+                    .lock()
+                    .expect("never poisoned")
+                    .insert(id, translation);
+            }
+            let rejected = light_client
+                .client
+                .lock()
+                .expect("never poisoned")
+                .json_rpc_request(request.to_string(), chain.chain_id);
+            if let Err(err) = rejected {
+                break Err(anyhow::anyhow!(
+                    "The light client rejected a request: {}",
+                    err
+                ));
+            }
+        };
+        forward.cancel().await;
+        light_client
+            .client
+            .lock()
+            .expect("never poisoned")
+            .remove_chain(chain.chain_id);
+        result
+    }
+
+    /// Translates the `response` if it is the response to a translated request.
+    fn translate(pending: &Mutex<HashMap<String, Translation>>, response: String) -> String {
+        let value: Value = match serde_json::from_str(&response) {
+            Ok(value) => value,
+            Err(_) => return response,
+        };
+        let translation = value.get("id").and_then(|id| {
+            pending
+                .lock()
+                .expect("never poisoned")
+                .remove(&id.to_string())
+        });
+        match translation {
+            Some(translation) => translate_response(value, translation).to_string(),
+            None => response,
+        }
+    }
+
+    async fn send<T>(sender: &mut soketto::connection::Sender<T>, message: &str) -> Result<()>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+    {
+        sender.send_text(message).await?;
+        sender.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pallet_contracts_primitives::{
+        ContractResult, ExecReturnValue, ReturnFlags, StorageDeposit,
+    };
+
+    #[test]
+    fn contracts_rpc_is_translated_into_runtime_api_calls() {
+        let alice = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "contracts_call",
+            "params": [{
+                "origin": alice,
+                "dest": alice,
+                "value": 0,
+                "gasLimit": "0x3e8",
+                "storageDepositLimit": null,
+                "inputData": "0x0102",
+            }],
+        });
+        let (request, translation) = translate_request(request).unwrap();
+        assert_eq!(translation, Some(Translation::Call));
+        assert_eq!(request["method"], "state_call");
+        assert_eq!(request["params"][0], "ContractsApi_call");
+        let account = account(&json!(alice)).unwrap();
+        let args = (
+            account.clone(),
+            account,
+            0u128,
+            1000u64,
+            None::<u128>,
+            vec![1u8, 2],
+        );
+        assert_eq!(
+            request["params"][1],
+            format!("0x{}", hex::encode(args.encode()))
+        );
+
+        let result: super::super::call::ContractExecResult = ContractResult {
+            gas_consumed: 10,
+            gas_required: 20,
+            storage_deposit: StorageDeposit::Charge(0),
+            debug_message: Vec::new(),
+            result: Ok(ExecReturnValue {
+                flags: ReturnFlags::empty(),
+                data: sp_core::Bytes(vec![3]),
+            }),
+        };
+        let response = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "result": format!("0x{}", hex::encode(result.encode())),
+        });
+        let response = translate_response(response, Translation::Call);
+        assert_eq!(response["result"], serde_json::to_value(&result).unwrap());
+
+        let (request, translation) =
+            translate_request(json!({ "id": 8, "method": "system_health", "params": [] })).unwrap();
+        assert_eq!(translation, None);
+        assert_eq!(request["method"], "system_health");
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    call::{ContractExecResult, RpcCallRequest},
+    call_index, node_url, Balance, ContractAccount,
+};
+use crate::{address_book::parse_address, util::DEFAULT_KEY_COL_WIDTH};
+use anyhow::{Context, Result};
+use colored::Colorize as _;
+use jsonrpsee::{
+    core::client::{ClientT, SubscriptionClientT},
+    rpc_params,
+    ws_client::{WsClient, WsClientBuilder},
+};
+use scale::{Compact, Decode};
+use sp_core::{crypto::Ss58Codec, Bytes};
+use sp_runtime::{generic::Era, MultiAddress, MultiSignature};
+use std::path::PathBuf;
+use subxt::{rpc::NumberOrHex, ClientBuilder, Config, DefaultConfig};
+
+type BlockHash = <DefaultConfig as Config>::Hash;
+
+/// Stream the debug output of the calls of a contract.
+///
+/// Every `Contracts::call` extrinsic targeting the contract in a new block is dry-run again at
+/// the parent of the block, which yields the output of `ink_env::debug_println!`. This requires
+/// a node with debug output enabled, e.g. a development node.
+#[derive(Debug, clap::Args)]
+#[clap(name = "logs")]
+pub struct LogsCommand {
+    /// The address of the contract, or its alias in the address book.
+    #[clap(long, env = "CONTRACT", parse(try_from_str = parse_address))]
+    contract: ContractAccount,
+    /// Path to the `Cargo.toml` of the contract, used to look up its network profiles.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// Websockets url of a substrate node. Defaults to the url of the `--network`, or to
+    /// `ws://localhost:9944`.
+    #[clap(long, parse(try_from_str))]
+    url: Option<url::Url>,
+    /// The name of a network profile.
+    #[clap(long)]
+    network: Option<String>,
+}
+
+/// A `Contracts::call` extrinsic.
+#[derive(Debug, PartialEq)]
+struct ContractCall {
+    origin: ContractAccount,
+    dest: ContractAccount,
+    value: Balance,
+    gas_limit: u64,
+    storage_deposit_limit: Option<Balance>,
+    data: Vec<u8>,
+}
+
+impl LogsCommand {
+    pub fn run(&self) -> Result<()> {
+        let url = node_url(
+            self.url.as_ref(),
+            self.network.as_deref(),
+            self.manifest_path.as_ref(),
+        )?;
+        async_std::task::block_on(async {
+            let client = ClientBuilder::new()
+                .set_url(url.as_str())
+                .build::<DefaultConfig>()
+                .await?;
+            let call_index = call_index(client.metadata(), "Contracts", "call")?;
+            let cli = WsClientBuilder::default().build(url.as_str()).await?;
+            let mut heads = cli
+                .subscribe::<serde_json::Value>(
+                    "chain_subscribeNewHeads",
+                    rpc_params![],
+                    "chain_unsubscribeNewHeads",
+                )
+                .await?;
+            log::info!("Waiting for calls of {}", self.contract.to_ss58check());
+
+            while let Some(header) = heads.next().await {
+                let header = header?;
+                let number = header["number"]
+                    .as_str()
+                    .and_then(|number| {
+                        u32::from_str_radix(number.trim_start_matches("0x"), 16).ok()
+                    })
+                    .context("Invalid block number in header")?;
+                let parent: BlockHash = serde_json::from_value(header["parentHash"].clone())?;
+                self.block_logs(&cli, call_index, number, parent).await?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Prints the debug output of the calls of the contract in the block with the `number`.
+    async fn block_logs(
+        &self,
+        cli: &WsClient,
+        call_index: (u8, u8),
+        number: u32,
+        parent: BlockHash,
+    ) -> Result<()> {
+        let hash: Option<BlockHash> = cli
+            .request("chain_getBlockHash", rpc_params![number])
+            .await?;
+        let hash = hash.context(format!("Block {} not found", number))?;
+        let block: serde_json::Value = cli.request("chain_getBlock", rpc_params![hash]).await?;
+        let extrinsics: Vec<Bytes> = serde_json::from_value(block["block"]["extrinsics"].clone())?;
+
+        for extrinsic in extrinsics {
+            let call = match decode_contract_call(&extrinsic.0, call_index) {
+                Ok(Some(call)) if call.dest == self.contract => call,
+                Ok(_) => continue,
+                Err(err) => {
+                    log::debug!("Skipping extrinsic of block {}: {:?}", number, err);
+                    continue;
+                }
+            };
+            let request = RpcCallRequest {
+                origin: call.origin.clone(),
+                dest: call.dest,
+                value: NumberOrHex::Hex(call.value.into()),
+                gas_limit: NumberOrHex::Number(call.gas_limit),
+                storage_deposit_limit: call
+                    .storage_deposit_limit
+                    .map(|limit| NumberOrHex::Hex(limit.into())),
+                input_data: Bytes(call.data),
+            };
+            let response: serde_json::Value = cli
+                .request("contracts_call", rpc_params![&request, parent])
+                .await?;
+            let result: ContractExecResult = serde_json::from_value(response)?;
+            let debug_message = String::from_utf8_lossy(&result.debug_message);
+            for line in debug_message.lines() {
+                println!(
+                    "{:>width$} {} {}",
+                    format!("#{}", number).bright_purple().bold(),
+                    call.origin.to_ss58check().bright_white(),
+                    line,
+                    width = DEFAULT_KEY_COL_WIDTH
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a signed `Contracts::call` extrinsic, returns `None` for other extrinsics.
+///
+/// The signed extensions are expected to be the ones of the default configuration, i.e. the
+/// mortality, the nonce and the tip.
+fn decode_contract_call(extrinsic: &[u8], call_index: (u8, u8)) -> Result<Option<ContractCall>> {
+    let input = &mut &extrinsic[..];
+    let _len = Compact::<u32>::decode(input)?;
+    let version = u8::decode(input)?;
+    if version & 0b1000_0000 == 0 {
+        return Ok(None);
+    }
+    let origin = match MultiAddress::<ContractAccount, u32>::decode(input)? {
+        MultiAddress::Id(origin) => origin,
+        _ => return Ok(None),
+    };
+    let _signature = MultiSignature::decode(input)?;
+    let _era = Era::decode(input)?;
+    let _nonce = Compact::<u32>::decode(input)?;
+    let _tip = Compact::<Balance>::decode(input)?;
+    if <(u8, u8)>::decode(input)? != call_index {
+        return Ok(None);
+    }
+    let dest = match MultiAddress::<ContractAccount, u32>::decode(input)? {
+        MultiAddress::Id(dest) => dest,
+        _ => return Ok(None),
+    };
+    Ok(Some(ContractCall {
+        origin,
+        dest,
+        value: Compact::<Balance>::decode(input)?.0,
+        gas_limit: Compact::<u64>::decode(input)?.0,
+        storage_deposit_limit: Option::<Compact<Balance>>::decode(input)?.map(|limit| limit.0),
+        data: Vec::<u8>::decode(input)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale::Encode;
+
+    #[test]
+    fn contract_calls_are_decoded() {
+        let origin = ContractAccount::new([1; 32]);
+        let dest = ContractAccount::new([2; 32]);
+        let call = (
+            (7u8, 6u8),
+            MultiAddress::<ContractAccount, u32>::Id(dest.clone()),
+            Compact(10 as Balance),
+            Compact(5_000u64),
+            Option::<Compact<Balance>>::None,
+            vec![0xde_u8, 0xad],
+        );
+        let signed = (
+            0b1000_0100u8,
+            MultiAddress::<ContractAccount, u32>::Id(origin.clone()),
+            MultiSignature::Sr25519(sp_core::sr25519::Signature::from_raw([0; 64])),
+            Era::Immortal,
+            Compact(3u32),
+            Compact(0 as Balance),
+            call,
+        )
+            .encode();
+        let extrinsic = signed.encode();
+
+        assert_eq!(
+            decode_contract_call(&extrinsic, (7, 6)).unwrap(),
+            Some(ContractCall {
+                origin,
+                dest,
+                value: 10,
+                gas_limit: 5_000,
+                storage_deposit_limit: None,
+                data: vec![0xde, 0xad],
+            })
+        );
+        assert_eq!(decode_contract_call(&extrinsic, (7, 0)).unwrap(), None);
+    }
+}
@@ -0,0 +1,397 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Conversion of the ink! metadata of contracts built with other ink! versions into the
+//! `V3` format, which is used for encoding and decoding messages, constructors and events.
+
+use anyhow::{Context, Result};
+use ink_metadata::InkProject;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// The versions of the ink! metadata format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum MetadataVersion {
+    /// ink! `3.0.0-rc1` to `3.0.0-rc5`, using the `scale-info` 0.x type registry format.
+    V1,
+    /// ink! `3.0.0-rc6` and `3.0.0-rc7`, with message and argument names instead of labels.
+    V2,
+    /// ink! `3.0.0-rc8` and later 3.x releases.
+    V3,
+    /// ink! 4 and later, without a version key wrapping the metadata.
+    V4,
+}
+
+impl MetadataVersion {
+    /// Detects the version of the `abi` section of a metadata file.
+    fn detect(abi: &Map<String, Value>) -> Result<Self> {
+        if abi.contains_key("V3") {
+            Ok(Self::V3)
+        } else if abi.contains_key("V2") {
+            Ok(Self::V2)
+        } else if abi.contains_key("V1") {
+            Ok(Self::V1)
+        } else if abi.contains_key("V0") {
+            anyhow::bail!(
+                "The metadata was generated with ink! 2, which is not supported. \
+                Rebuild the contract with ink! 3 or later."
+            )
+        } else if abi.contains_key("version") {
+            Ok(Self::V4)
+        } else {
+            anyhow::bail!("Unknown ink! metadata version")
+        }
+    }
+
+    /// The key wrapping the metadata, ink! 4 metadata is not wrapped.
+    fn key(&self) -> Option<&'static str> {
+        match self {
+            Self::V1 => Some("V1"),
+            Self::V2 => Some("V2"),
+            Self::V3 => Some("V3"),
+            Self::V4 => None,
+        }
+    }
+}
+
+/// Returns the [`InkProject`] of the `abi` section of a metadata file, converting it to the
+/// `V3` format if it was generated by an older or newer ink! version.
+pub fn into_ink_project(mut abi: Map<String, Value>) -> Result<InkProject> {
+    let version = MetadataVersion::detect(&abi)?;
+    let mut project = match version.key() {
+        Some(key) => abi.remove(key).expect("the key was just detected; qed"),
+        None => Value::Object(abi),
+    };
+    if version != MetadataVersion::V3 {
+        log::info!("Converting ink! metadata {:?} to V3", version);
+    }
+    if version == MetadataVersion::V1 {
+        upgrade_type_registry(&mut project)?;
+    }
+    if version <= MetadataVersion::V2 {
+        upgrade_names_to_labels(&mut project);
+    }
+    if version == MetadataVersion::V4 {
+        downgrade_from_v4(&mut project);
+    }
+    serde_json::from_value(project).context(format!(
+        "Failed to convert ink! metadata {:?} to V3",
+        version
+    ))
+}
+
+/// Returns the type ids of the return types of the constructors of the `abi` section of a metadata
+/// file, by the labels of the constructors.
+///
+/// Only ink! 4 metadata specifies the return types of constructors, e.g. the `Result` of a fallible
+/// constructor, which are removed by the conversion to `V3`. The ids refer to the type registry of
+/// the converted [`InkProject`], which keeps the types of ink! 4 metadata as they are.
+pub fn constructor_return_types(abi: &Map<String, Value>) -> BTreeMap<String, u32> {
+    if !matches!(MetadataVersion::detect(abi), Ok(MetadataVersion::V4)) {
+        return BTreeMap::new();
+    }
+    let constructors = abi
+        .get("spec")
+        .and_then(|spec| spec.get("constructors"))
+        .and_then(Value::as_array);
+    constructors
+        .into_iter()
+        .flatten()
+        .filter_map(|constructor| {
+            let label = constructor.get("label")?.as_str()?;
+            let ty = constructor.pointer("/returnType/type")?.as_u64()?;
+            Some((label.to_string(), ty as u32))
+        })
+        .collect()
+}
+
+/// Converts the `scale-info` 0.x type registry of `V1` metadata to the `scale-info` 1.x format.
+///
+/// Types are no longer referenced by their 1-based position in the registry, but by a 0-based
+/// `id`, which is stored along with every type.
+fn upgrade_type_registry(project: &mut Value) -> Result<()> {
+    let types = project
+        .get_mut("types")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| anyhow::anyhow!("The V1 metadata contains no type registry"))?;
+    for (id, ty) in types.iter_mut().enumerate() {
+        if let Some(params) = ty.get_mut("params").and_then(Value::as_array_mut) {
+            // the names of type parameters are not part of the `scale-info` 0.x format
+            for (i, param) in params.iter_mut().enumerate() {
+                *param = serde_json::json!({ "name": format!("T{}", i), "type": param.take() });
+            }
+        }
+        if let Some(variants) = ty
+            .pointer_mut("/def/variant/variants")
+            .and_then(Value::as_array_mut)
+        {
+            for (index, variant) in variants.iter_mut().enumerate() {
+                if let Some(variant) = variant.as_object_mut() {
+                    let index = variant
+                        .remove("discriminant")
+                        .unwrap_or_else(|| index.into());
+                    variant.insert("index".into(), index);
+                }
+            }
+        }
+        *ty = serde_json::json!({ "id": id, "type": ty.take() });
+    }
+    shift_type_ids(project);
+    Ok(())
+}
+
+/// Decrements every type id referenced in `value` by one.
+fn shift_type_ids(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match (key.as_str(), value) {
+                    ("type" | "ty" | "bit_store_type" | "bit_order_type", Value::Number(id)) => {
+                        *id = decrement(id).into()
+                    }
+                    ("tuple", Value::Array(ids)) => {
+                        for id in ids.iter_mut() {
+                            if let Value::Number(number) = id {
+                                *id = decrement(number).into();
+                            }
+                        }
+                    }
+                    (_, value) => shift_type_ids(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(shift_type_ids),
+        _ => (),
+    }
+}
+
+fn decrement(id: &serde_json::Number) -> u64 {
+    id.as_u64().unwrap_or_default().saturating_sub(1)
+}
+
+/// Renames the `name` of constructors, messages, events and their arguments to `label`.
+///
+/// The names of constructors and messages were paths, e.g. `["Erc20", "transfer"]`, which are
+/// joined with `::`.
+fn upgrade_names_to_labels(project: &mut Value) {
+    for section in ["constructors", "messages", "events"] {
+        let items = match project
+            .pointer_mut(&format!("/spec/{}", section))
+            .and_then(Value::as_array_mut)
+        {
+            Some(items) => items,
+            None => continue,
+        };
+        for item in items.iter_mut() {
+            rename_to_label(item);
+            if let Some(args) = item.get_mut("args").and_then(Value::as_array_mut) {
+                args.iter_mut().for_each(rename_to_label);
+            }
+        }
+    }
+}
+
+fn rename_to_label(item: &mut Value) {
+    if let Some(item) = item.as_object_mut() {
+        if let Some(name) = item.remove("name") {
+            let label = match name {
+                Value::Array(segments) => segments
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join("::")
+                    .into(),
+                name => name,
+            };
+            item.insert("label".into(), label);
+        }
+    }
+}
+
+/// Removes the parts of ink! 4 metadata which are not part of the `V3` format.
+///
+/// The storage layout changed completely and is replaced by an empty layout, it is not required
+/// for encoding and decoding messages.
+fn downgrade_from_v4(project: &mut Value) {
+    if let Some(project) = project.as_object_mut() {
+        project.remove("version");
+        project.insert(
+            "storage".into(),
+            serde_json::json!({ "struct": { "fields": [] } }),
+        );
+    }
+    let spec = match project.get_mut("spec").and_then(Value::as_object_mut) {
+        Some(spec) => spec,
+        None => return,
+    };
+    spec.remove("environment");
+    spec.remove("lang_error");
+    let removed_fields = [
+        ("constructors", &["payable", "returnType", "default"][..]),
+        ("messages", &["default"][..]),
+        ("events", &["module_path", "signature_topic"][..]),
+    ];
+    for (section, fields) in removed_fields {
+        if let Some(items) = spec.get_mut(section).and_then(Value::as_array_mut) {
+            for item in items.iter_mut().filter_map(Value::as_object_mut) {
+                for field in fields {
+                    item.remove(*field);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn abi(value: Value) -> Map<String, Value> {
+        value.as_object().expect("abi must be an object").clone()
+    }
+
+    #[test]
+    fn metadata_version_is_detected() {
+        let version = |value| MetadataVersion::detect(&abi(value));
+        assert_eq!(version(json!({ "V1": {} })).unwrap(), MetadataVersion::V1);
+        assert_eq!(version(json!({ "V3": {} })).unwrap(), MetadataVersion::V3);
+        assert_eq!(
+            version(json!({ "version": "4", "spec": {} })).unwrap(),
+            MetadataVersion::V4
+        );
+        assert!(version(json!({ "V0": {} })).is_err());
+        assert!(version(json!({ "spec": {} })).is_err());
+    }
+
+    #[test]
+    fn v1_type_registry_is_upgraded() {
+        let mut project = json!({
+            "spec": {
+                "messages": [{
+                    "args": [{ "name": "value", "type": { "displayName": ["bool"], "type": 1 } }],
+                    "returnType": { "displayName": ["Option"], "type": 2 },
+                }],
+            },
+            "storage": { "cell": { "key": "0x00", "ty": 1 } },
+            "types": [
+                { "def": { "primitive": "bool" } },
+                {
+                    "def": { "variant": { "variants": [
+                        { "name": "None" },
+                        { "name": "Some", "fields": [{ "type": 1 }] },
+                    ] } },
+                    "params": [1],
+                    "path": ["Option"],
+                },
+            ],
+        });
+
+        upgrade_type_registry(&mut project).unwrap();
+
+        assert_eq!(
+            project.pointer("/spec/messages/0/args/0/type/type"),
+            Some(&json!(0))
+        );
+        assert_eq!(
+            project.pointer("/spec/messages/0/returnType/type"),
+            Some(&json!(1))
+        );
+        assert_eq!(project.pointer("/storage/cell/ty"), Some(&json!(0)));
+        assert_eq!(project.pointer("/types/1/id"), Some(&json!(1)));
+        assert_eq!(
+            project.pointer("/types/1/type/params/0"),
+            Some(&json!({ "name": "T0", "type": 0 }))
+        );
+        assert_eq!(
+            project.pointer("/types/1/type/def/variant/variants/1"),
+            Some(&json!({ "name": "Some", "fields": [{ "type": 0 }], "index": 1 }))
+        );
+    }
+
+    #[test]
+    fn v2_names_are_converted_to_labels() {
+        let mut project = json!({
+            "spec": {
+                "constructors": [{ "name": ["new"], "args": [{ "name": "init_value" }] }],
+                "messages": [{ "name": ["Flipper", "flip"], "args": [] }],
+                "events": [{ "name": "Flipped", "args": [{ "name": "value" }] }],
+            },
+        });
+
+        upgrade_names_to_labels(&mut project);
+
+        assert_eq!(
+            project,
+            json!({
+                "spec": {
+                    "constructors": [{ "label": "new", "args": [{ "label": "init_value" }] }],
+                    "messages": [{ "label": "Flipper::flip", "args": [] }],
+                    "events": [{ "label": "Flipped", "args": [{ "label": "value" }] }],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn v4_metadata_is_downgraded() {
+        let mut project = json!({
+            "version": "4",
+            "spec": {
+                "constructors": [{ "label": "new", "payable": false, "returnType": null }],
+                "environment": {},
+                "lang_error": { "type": 3 },
+                "messages": [],
+                "events": [],
+            },
+            "storage": { "root": { "root_key": "0x00000000" } },
+        });
+
+        downgrade_from_v4(&mut project);
+
+        assert_eq!(
+            project,
+            json!({
+                "spec": {
+                    "constructors": [{ "label": "new" }],
+                    "messages": [],
+                    "events": [],
+                },
+                "storage": { "struct": { "fields": [] } },
+            })
+        );
+    }
+
+    #[test]
+    fn constructor_return_types_are_kept_from_v4_metadata() {
+        let v4 = abi(json!({
+            "version": "4",
+            "spec": {
+                "constructors": [
+                    { "label": "new", "returnType": { "displayName": [], "type": 5 } },
+                    { "label": "default", "returnType": null },
+                ],
+            },
+        }));
+        assert_eq!(
+            constructor_return_types(&v4),
+            BTreeMap::from([("new".to_string(), 5)])
+        );
+
+        let v3 = abi(json!({ "V3": { "spec": { "constructors": [{ "label": "new" }] } } }));
+        assert!(constructor_return_types(&v3).is_empty());
+    }
+}
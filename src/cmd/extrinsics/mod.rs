@@ -14,40 +14,114 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
+mod apps;
+mod bench_deploy;
 mod call;
+mod call_many;
+mod chain_info;
+mod console;
+mod constructor_args;
+mod deploy;
+mod dispatch_error;
+mod download;
 mod events;
+mod explorer;
+mod failover;
+mod fees;
+mod get;
+mod governance;
+mod http;
+mod identity;
+mod index;
 mod instantiate;
+mod keystore;
+mod light_client;
+mod logs;
+mod metadata_versions;
+mod nonce;
+mod progress;
+mod replay;
 mod runtime_api;
+mod schedule;
+mod serve;
+mod session;
+mod signed_extensions;
+mod signer;
+mod simulate_upgrade;
+mod state;
+mod storage_diff;
+mod submission;
+mod submit;
+mod sudo;
+mod terminate;
 mod transcode;
 mod upload;
+mod verify_metadata;
+mod xcm;
 
 #[cfg(test)]
 #[cfg(feature = "integration-tests")]
 mod integration_tests;
 
 use anyhow::{anyhow, Context, Result};
-use std::{fs::File, path::PathBuf};
+use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
-use self::{events::display_events, transcode::ContractMessageTranscoder};
+use self::{
+    events::{display_events, display_raw_events},
+    explorer::{display_explorer_links, ExplorerPage},
+    signer::ExtrinsicSigner,
+    transcode::ContractMessageTranscoder,
+};
 use crate::{
-    crate_metadata::CrateMetadata, name_value_println, workspace::ManifestPath, Verbosity,
-    VerbosityFlags,
+    cmd::build::ContractProfile,
+    config::{
+        Config as UserConfig, NetworkProfile, SignedExtensionConfig, DEFAULT_MAX_FINALITY_LAG,
+    },
+    crate_metadata::CrateMetadata,
+    name_value_println, output, timings,
+    workspace::{Manifest, ManifestPath},
+    Verbosity, VerbosityFlags,
 };
 use pallet_contracts_primitives::ContractResult;
-use sp_core::{crypto::Pair, sr25519};
+use scale::{Decode, Encode};
+use sp_core::{
+    crypto::{Pair, Ss58Codec},
+    sr25519,
+};
 use subxt::{Config, DefaultConfig};
 
+pub use bench_deploy::BenchDeployCommand;
 pub use call::CallCommand;
+pub use call_many::CallManyCommand;
+pub use chain_info::ChainInfoCommand;
+pub use console::ConsoleCommand;
+pub use deploy::DeployCommand;
+pub use download::DownloadCommand;
+pub use get::GetCommand;
+pub use index::IndexCommand;
 pub use instantiate::InstantiateCommand;
+pub use logs::LogsCommand;
+pub use replay::ReplayCommand;
 pub use runtime_api::api::{DispatchError as RuntimeDispatchError, Event as RuntimeEvent};
+pub use serve::ServeCommand;
+pub use simulate_upgrade::SimulateUpgradeCommand;
+pub use state::StateCommand;
+pub use submit::SubmitCommand;
+pub use terminate::TerminateCommand;
 pub use upload::UploadCommand;
+pub use verify_metadata::VerifyMetadataCommand;
 
 type Balance = u128;
 type CodeHash = <DefaultConfig as Config>::Hash;
 type ContractAccount = <DefaultConfig as Config>::AccountId;
-type PairSigner = subxt::PairSigner<DefaultConfig, SignedExtra, sp_core::sr25519::Pair>;
-type SignedExtra = subxt::DefaultExtra<DefaultConfig>;
-type RuntimeApi = runtime_api::api::RuntimeApi<DefaultConfig, SignedExtra>;
+pub(crate) type SignedExtra = signed_extensions::ContractsExtra;
+pub(crate) type RuntimeApi = runtime_api::api::RuntimeApi<DefaultConfig, SignedExtra>;
 
 /// Arguments required for creating and sending an extrinsic to a substrate node.
 #[derive(Clone, Debug, clap::Args)]
@@ -55,20 +129,64 @@ pub struct ExtrinsicOpts {
     /// Path to the `Cargo.toml` of the contract.
     #[clap(long, parse(from_os_str))]
     manifest_path: Option<PathBuf>,
-    /// Websockets url of a substrate node.
+    /// Websockets url of a substrate node. Defaults to the url of the `--network`, or to
+    /// `ws://localhost:9944`.
+    #[clap(name = "url", long, parse(try_from_str))]
+    url: Option<url::Url>,
+    /// The name of a network profile from the `cargo-contract` configuration file, or of a
+    /// profile with an `endpoint` in `[package.metadata.contract.profiles]` of the contract.
+    #[clap(long)]
+    network: Option<String>,
+    /// Connect through an embedded light client, which syncs the chain with this chain
+    /// specification, instead of trusting the node at `--url`. Requires the `light-client`
+    /// feature.
     #[clap(
-        name = "url",
         long,
-        parse(try_from_str),
-        default_value = "ws://localhost:9944"
+        value_name = "CHAIN_SPEC",
+        parse(from_os_str),
+        conflicts_with_all = &["url", "network"]
     )]
-    url: url::Url,
+    light: Option<PathBuf>,
+    /// The chain specification of the relay chain, if the chain of `--light` is a parachain.
+    #[clap(
+        long,
+        value_name = "CHAIN_SPEC",
+        parse(from_os_str),
+        requires = "light"
+    )]
+    light_relay_chain: Option<PathBuf>,
     /// Secret key URI for the account deploying the contract.
-    #[clap(name = "suri", long, short)]
-    suri: String,
+    #[clap(
+        name = "suri",
+        long,
+        short,
+        required_unless_present_any = &["keyfile", "sign_external", "apps_link"]
+    )]
+    suri: Option<String>,
+    /// A JSON keystore file of the account deploying the contract, as exported by polkadot-js
+    /// or its browser extension.
+    #[clap(long, parse(from_os_str), conflicts_with = "suri")]
+    keyfile: Option<PathBuf>,
+    /// Sign with an external signer instead, e.g. an air-gapped device or a browser extension,
+    /// for the given SS58 address. The payload of each extrinsic is printed as hex and QR code,
+    /// and its signature is read from stdin.
+    #[clap(
+        long,
+        value_name = "ADDRESS",
+        parse(try_from_str = parse_account),
+        conflicts_with_all = &["suri", "keyfile"]
+    )]
+    sign_external: Option<ContractAccount>,
+    /// Receive the signatures of `--sign-external` on a local HTTP callback at this port
+    /// instead, either as body or as `signature` query parameter of a request.
+    #[clap(long, value_name = "PORT", requires = "sign_external")]
+    sign_external_port: Option<u16>,
     /// Password for the secret key.
     #[clap(name = "password", long, short)]
     password: Option<String>,
+    /// Prompt for the password of the secret key, instead of passing it on the command line.
+    #[clap(long, conflicts_with = "password")]
+    password_interactive: bool,
     #[clap(flatten)]
     verbosity: VerbosityFlags,
     /// Dry-run the extrinsic via rpc, instead of as an extrinsic. Chain state will not be mutated.
@@ -78,25 +196,242 @@ pub struct ExtrinsicOpts {
     /// consumed.
     #[clap(long, parse(try_from_str = parse_balance))]
     storage_deposit_limit: Option<Balance>,
+    /// Open the block explorer page of the result in the browser. The explorer url templates are
+    /// configured per network profile.
+    #[clap(long)]
+    open_explorer: bool,
+    /// Wrap the extrinsic into `Sudo::sudo`, e.g. to upload code to a development chain which
+    /// restricts uploads. The signer has to be the sudo key of the chain, the calls of
+    /// `pallet-contracts` are dispatched with the signer as origin by `Utility::dispatch_as`.
+    #[clap(long, conflicts_with_all = &["dry_run", "apps_link"])]
+    sudo: bool,
+    /// Wait for the finalization of the block which includes the extrinsic, instead of only for
+    /// its inclusion.
+    #[clap(long, conflicts_with = "dry_run")]
+    finalized: bool,
+    /// The nonce of the extrinsic, or `auto-increment` to coordinate the nonces of concurrent
+    /// submissions from the same account on this machine. Defaults to the next nonce of the
+    /// account on the chain.
+    #[clap(long, value_name = "auto-increment | NONCE")]
+    nonce: Option<nonce::Nonce>,
+    /// Instead of submitting the extrinsic, print its call data and a link to polkadot-js apps,
+    /// where it can be reviewed and submitted, e.g. by a teammate.
+    #[clap(long, conflicts_with = "dry_run")]
+    apps_link: bool,
+    /// Pay the transaction fee in the asset with this id of the `pallet-assets` of the chain,
+    /// instead of in its native token. Requires the chain to support `ChargeAssetTxPayment`.
+    #[clap(long, value_name = "ASSET_ID")]
+    fee_asset: Option<u32>,
+    /// The url once it is resolved, so that every connection of a command goes to the same node
+    /// and the health of the nodes of the network is only checked once.
+    #[clap(skip)]
+    resolved_url: Arc<Mutex<Option<url::Url>>>,
 }
 
 impl ExtrinsicOpts {
     pub fn signer(&self) -> Result<sr25519::Pair> {
-        sr25519::Pair::from_string(&self.suri, self.password.as_ref().map(String::as_ref))
+        let password = if self.password_interactive {
+            if !output::has_terminal() {
+                anyhow::bail!("`--password-interactive` requires a terminal, pass `--password`")
+            }
+            Some(rpassword::prompt_password("Password: ")?)
+        } else {
+            self.password.clone()
+        };
+        if let Some(keyfile) = &self.keyfile {
+            return keystore::load(keyfile, password.as_deref());
+        }
+        let suri = self
+            .suri
+            .as_ref()
+            .context("Either `--suri` or `--keyfile` is required")?;
+        sr25519::Pair::from_string(suri, password.as_deref())
             .map_err(|_| anyhow::anyhow!("Secret string error"))
     }
 
+    /// Returns the signer of the extrinsics, i.e. the key pair or the external signer.
+    fn extrinsic_signer(&self) -> Result<ExtrinsicSigner> {
+        match &self.sign_external {
+            Some(account) => Ok(ExtrinsicSigner::external(
+                account.clone(),
+                self.sign_external_port,
+            )),
+            None => Ok(ExtrinsicSigner::pair(self.signer()?)),
+        }
+    }
+
     /// Returns the verbosity
     pub fn verbosity(&self) -> Result<Verbosity> {
         TryFrom::try_from(&self.verbosity)
     }
+
+    /// Returns the network profile selected with `--network`, if any.
+    pub fn network_profile(&self) -> Result<Option<NetworkProfile>> {
+        network_profile(self.network.as_deref(), self.manifest_path.as_ref())
+    }
+
+    /// Returns the signed extensions configured for the network selected with `--network`.
+    pub fn signed_extensions(&self) -> Result<Vec<SignedExtensionConfig>> {
+        Ok(self
+            .network_profile()?
+            .map(|profile| profile.signed_extensions)
+            .unwrap_or_default())
+    }
+
+    /// Returns the url of the node to connect to, resolved once per command.
+    pub fn url(&self) -> Result<url::Url> {
+        let mut resolved = self.resolved_url.lock().expect("the lock is not poisoned");
+        if let Some(url) = &*resolved {
+            return Ok(url.clone());
+        }
+        let url = match &self.light {
+            Some(chain_spec) => light_client::url(chain_spec, self.light_relay_chain.as_deref())?,
+            None => node_url(
+                self.url.as_ref(),
+                self.network.as_deref(),
+                self.manifest_path.as_ref(),
+            )?,
+        };
+        *resolved = Some(url.clone());
+        Ok(url)
+    }
+}
+
+/// Returns the network profile with the given `name`, if any.
+///
+/// Network profiles of the user configuration take precedence over the profiles of the contract
+/// at `manifest_path`.
+pub(crate) fn network_profile(
+    name: Option<&str>,
+    manifest_path: Option<&PathBuf>,
+) -> Result<Option<NetworkProfile>> {
+    let name = match name {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    let config = UserConfig::load()?;
+    if let Some(profile) = config.networks.get(name) {
+        return Ok(Some(profile.clone()));
+    }
+    if let Some(endpoint) = contract_profile_endpoint(name, manifest_path)? {
+        return Ok(Some(NetworkProfile {
+            url: endpoint,
+            fallback_urls: Vec::new(),
+            max_finality_lag: DEFAULT_MAX_FINALITY_LAG,
+            explorer: Default::default(),
+            signed_extensions: Vec::new(),
+            fork: false,
+        }));
+    }
+    config.network(name).map(|profile| Some(profile.clone()))
+}
+
+/// Returns the `endpoint` of the contract profile with the given `name`, if any.
+fn contract_profile_endpoint(
+    name: &str,
+    manifest_path: Option<&PathBuf>,
+) -> Result<Option<url::Url>> {
+    let manifest = ManifestPath::try_from(manifest_path)
+        .and_then(Manifest::new)
+        .ok();
+    match manifest {
+        Some(manifest) => Ok(ContractProfile::find(&manifest, name)?.and_then(|p| p.endpoint)),
+        None => Ok(None),
+    }
+}
+
+/// Returns the url of the node to connect to.
+///
+/// An explicit `url` takes precedence over the url of the `network` profile, which may fail over
+/// to one of the `fallback-urls` of the profile.
+fn node_url(
+    url: Option<&url::Url>,
+    network: Option<&str>,
+    manifest_path: Option<&PathBuf>,
+) -> Result<url::Url> {
+    if let Some(url) = url {
+        return Ok(url.clone());
+    }
+    match network_profile(network, manifest_path)? {
+        Some(profile) => failover::healthy_url(&profile),
+        None => Ok(url::Url::parse(DEFAULT_URL)?),
+    }
+}
+
+/// The url of a locally running node, used if neither `--url` nor `--network` is specified.
+const DEFAULT_URL: &str = "ws://localhost:9944";
+
+/// Encodes the `call` with its pallet and call index in the runtime `metadata`, like the call of
+/// an extrinsic.
+fn encode_call<C: subxt::Call>(metadata: &subxt::Metadata, call: &C) -> Result<Vec<u8>> {
+    let (pallet, index) = call_index(metadata, C::PALLET, C::FUNCTION)?;
+    let mut encoded = vec![pallet, index];
+    call.encode_to(&mut encoded);
+    Ok(encoded)
+}
+
+/// Returns the pallet and call index of the `call` of the `pallet` in the runtime `metadata`.
+fn call_index(metadata: &subxt::Metadata, pallet: &str, call: &str) -> Result<(u8, u8)> {
+    let runtime = metadata.runtime_metadata();
+    let pallet_metadata = runtime
+        .pallets
+        .iter()
+        .find(|metadata| metadata.name == pallet)
+        .context(format!("The runtime has no {} pallet", pallet))?;
+    let calls = pallet_metadata
+        .calls
+        .as_ref()
+        .context(format!("The {} pallet has no calls", pallet))?;
+    let index = match runtime.types.resolve(calls.ty.id()).map(|ty| ty.type_def()) {
+        Some(scale_info::TypeDef::Variant(variant)) => variant
+            .variants()
+            .iter()
+            .find(|variant| variant.name() == call)
+            .map(|variant| variant.index()),
+        _ => None,
+    };
+    let index = index.context(format!("The {} pallet has no {} call", pallet, call))?;
+    Ok((pallet_metadata.index, index))
+}
+
+/// Warns if the call data of `len` bytes exceeds the maximum length of a normal extrinsic of the
+/// chain, so that it would be rejected as exhausting the resources of a block.
+fn warn_if_exceeds_block_length(metadata: &subxt::Metadata, len: usize) {
+    let runtime = metadata.runtime_metadata();
+    let block_length = runtime
+        .pallets
+        .iter()
+        .find(|pallet| pallet.name == "System")
+        .and_then(|pallet| {
+            pallet
+                .constants
+                .iter()
+                .find(|constant| constant.name == "BlockLength")
+        });
+    // `BlockLength` is the `PerDispatchClass` of the maximum lengths, with the normal class first
+    let max = match block_length.and_then(|constant| u32::decode(&mut &constant.value[..]).ok()) {
+        Some(max) => max as usize,
+        None => return,
+    };
+    if len > max {
+        output::warning(format!(
+            "The call data of {} bytes exceeds the maximum length of an extrinsic of {} bytes, \
+             the chain will reject it",
+            len, max
+        ));
+    }
 }
 
 /// For a contract project with its `Cargo.toml` at the specified `manifest_path`, load the cargo
-/// [`CrateMetadata`] along with the contract metadata [`ink_metadata::InkProject`].
+/// [`CrateMetadata`] along with the contract metadata [`ink_metadata::InkProject`] and the type ids
+/// of the return types of its constructors by their labels.
 pub fn load_metadata(
     manifest_path: Option<&PathBuf>,
-) -> Result<(CrateMetadata, ink_metadata::InkProject)> {
+) -> Result<(
+    CrateMetadata,
+    ink_metadata::InkProject,
+    BTreeMap<String, u32>,
+)> {
     let manifest_path = ManifestPath::try_from(manifest_path)?;
     let crate_metadata = CrateMetadata::collect(&manifest_path)?;
     let path = crate_metadata.metadata_path();
@@ -107,34 +442,74 @@ pub fn load_metadata(
         ));
     }
 
+    let (metadata, ink_project) = load_metadata_file(&path)?;
+    // only ink! 4 metadata specifies the return types of the constructors
+    let constructor_return_types = metadata_versions::constructor_return_types(&metadata.abi);
+    Ok((crate_metadata, ink_project, constructor_return_types))
+}
+
+/// Load the contract metadata along with its [`ink_metadata::InkProject`] from a `metadata.json`
+/// or `<name>.contract` file at `path`.
+pub fn load_metadata_file(
+    path: &Path,
+) -> Result<(
+    contract_metadata::ContractMetadata,
+    ink_metadata::InkProject,
+)> {
     let file =
-        File::open(&path).context(format!("Failed to open metadata file {}", path.display()))?;
+        File::open(path).context(format!("Failed to open metadata file {}", path.display()))?;
     let metadata: contract_metadata::ContractMetadata = serde_json::from_reader(file).context(
         format!("Failed to deserialize metadata file {}", path.display()),
     )?;
-    let ink_metadata =
-        serde_json::from_value(serde_json::Value::Object(metadata.abi)).context(format!(
+    let ink_project =
+        metadata_versions::into_ink_project(metadata.abi.clone()).context(format!(
             "Failed to deserialize ink project metadata from file {}",
             path.display()
         ))?;
-    if let ink_metadata::MetadataVersioned::V3(ink_project) = ink_metadata {
-        Ok((crate_metadata, ink_project))
-    } else {
-        Err(anyhow!("Unsupported ink metadata version. Expected V1"))
-    }
+    Ok((metadata, ink_project))
+}
+
+fn parse_account(input: &str) -> Result<ContractAccount> {
+    ContractAccount::from_ss58check(input)
+        .map_err(|err| anyhow::anyhow!("Invalid SS58 address '{}': {:?}", input, err))
 }
 
-/// Parse Rust style integer balance literals which can contain underscores.
+/// Parse Rust style integer balance literals which can contain underscores and an exponent, e.g.
+/// `1_000e6`.
 fn parse_balance(input: &str) -> Result<Balance> {
-    input
-        .replace('_', "")
+    transcode::whole_digits(input, 0)?
         .parse::<Balance>()
-        .map_err(Into::into)
+        .map_err(|_| anyhow!("`{}` is out of the range of a balance", input))
+}
+
+/// Returns the transcoder of the contract `metadata`. If one of the `args` is a fixed-point
+/// number, balances are scaled by the decimals of the token of the chain at `url`.
+fn transcoder_for_args<'a>(
+    metadata: &'a ink_metadata::InkProject,
+    url: &url::Url,
+    args: &[String],
+) -> Result<ContractMessageTranscoder<'a>> {
+    let transcoder = ContractMessageTranscoder::new(metadata);
+    if !args.iter().any(|arg| transcode::is_fixed_point(arg)) {
+        return Ok(transcoder);
+    }
+    let decimals = async_std::task::block_on(token_decimals(url))?;
+    Ok(transcoder.with_token_decimals(decimals))
 }
 
-/// Create a new [`PairSigner`] from the given [`sr25519::Pair`].
-pub fn pair_signer(pair: sr25519::Pair) -> PairSigner {
-    PairSigner::new(pair)
+/// Returns the decimals of the native token of the chain at `url`.
+async fn token_decimals(url: &url::Url) -> Result<u32> {
+    let cli = WsClientBuilder::default().build(url.as_str()).await?;
+    let properties: serde_json::Value = cli.request("system_properties", rpc_params![]).await?;
+    // chains with several tokens report the decimals of each, the native token first
+    let decimals = match &properties["tokenDecimals"] {
+        serde_json::Value::Array(decimals) => decimals.first().cloned().unwrap_or_default(),
+        decimals => decimals.clone(),
+    };
+    decimals
+        .as_u64()
+        .map(|decimals| decimals as u32)
+        .context("The chain does not report the decimals of its token")
 }
 
 const STORAGE_DEPOSIT_KEY: &str = "Storage Deposit";
@@ -142,9 +517,8 @@ pub const EXEC_RESULT_MAX_KEY_COL_WIDTH: usize = STORAGE_DEPOSIT_KEY.len() + 1;
 
 /// Print to stdout the fields of the result of a `instantiate` or `call` dry-run via RPC.
 pub fn display_contract_exec_result<R>(result: &ContractResult<R, Balance>) -> Result<()> {
-    let mut debug_message_lines = std::str::from_utf8(&result.debug_message)
-        .context("Error decoding UTF8 debug message bytes")?
-        .lines();
+    let debug_message = std::str::from_utf8(&result.debug_message)
+        .context("Error decoding UTF8 debug message bytes")?;
     name_value_println!(
         "Gas Consumed",
         format!("{:?}", result.gas_consumed),
@@ -161,47 +535,43 @@ pub fn display_contract_exec_result<R>(result: &ContractResult<R, Balance>) -> R
         EXEC_RESULT_MAX_KEY_COL_WIDTH
     );
 
-    // print debug messages aligned, only first line has key
-    if let Some(debug_message) = debug_message_lines.next() {
+    // the lines of the debug messages are aligned, only the first line has the key
+    if !debug_message.is_empty() {
         name_value_println!(
             "Debug Message",
-            format!("{}", debug_message),
-            EXEC_RESULT_MAX_KEY_COL_WIDTH
-        );
-    }
-
-    for debug_message in debug_message_lines {
-        name_value_println!(
-            "",
-            format!("{}", debug_message),
+            debug_message,
             EXEC_RESULT_MAX_KEY_COL_WIDTH
         );
     }
     Ok(())
 }
 
-/// Wait for the transaction to be included successfully into a block.
+/// Wait for the transaction to be included successfully into a block, or to be finalized with
+/// `--finalized`, printing the changes of its status meanwhile.
 ///
 /// # Errors
 ///
-/// If a runtime Module error occurs, this will only display the pallet and error indices. Dynamic
-/// lookups of the actual error will be available once the following issue is resolved:
-/// <https://github.com/paritytech/subxt/issues/443>.
-///
-/// # Finality
-///
-/// Currently this will report success once the transaction is included in a block. In the future
-/// there could be a flag to wait for finality before reporting success.
-async fn wait_for_success_and_handle_error<T>(
-    tx_progress: subxt::TransactionProgress<'_, T, RuntimeDispatchError, RuntimeEvent>,
-) -> Result<subxt::TransactionEvents<'_, T, RuntimeEvent>>
-where
-    T: Config,
-{
-    tx_progress
-        .wait_for_in_block()
-        .await?
-        .wait_for_success()
-        .await
-        .map_err(Into::into)
+/// If a runtime Module error occurs, it is reported with the pallet, name and docs of the error
+/// from the metadata of the chain.
+async fn wait_for_success_and_handle_error<'a>(
+    client: &'a subxt::Client<DefaultConfig>,
+    tx_progress: subxt::TransactionProgress<'a, DefaultConfig, RuntimeDispatchError, RuntimeEvent>,
+    opts: &ExtrinsicOpts,
+) -> Result<subxt::TransactionEvents<'a, DefaultConfig, RuntimeEvent>> {
+    let _inclusion = timings::phase(if opts.finalized {
+        "Waiting for finalization"
+    } else {
+        "Waiting for inclusion"
+    });
+    let in_block = progress::follow(client, tx_progress, opts.finalized, opts.verbosity()?).await?;
+    let events = in_block.fetch_events().await?;
+    if let Some(error) = dispatch_error::extrinsic_error(client.metadata(), events.iter_raw())? {
+        return Err(error);
+    }
+    tracing::info!(
+        target: "cargo_contract::events::extrinsic",
+        extrinsic_hash = ?events.extrinsic_hash(),
+        block_hash = ?events.block_hash(),
+    );
+    Ok(events)
 }
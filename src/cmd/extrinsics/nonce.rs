@@ -0,0 +1,171 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Nonces of concurrent submissions from the same account.
+//!
+//! With `--nonce auto-increment` the processes submitting from an account on the same machine,
+//! e.g. the jobs of a CI matrix, serialize their submissions through a lock file and record the
+//! last nonce they used in a state file next to it. Each process uses the nonce after the
+//! recorded one, unless the chain is already ahead of it.
+
+use super::CodeHash;
+use anyhow::{Context, Result};
+use sp_core::crypto::{AccountId32, Ss58Codec};
+use std::{
+    fs::OpenOptions,
+    path::{Path, PathBuf},
+    str::FromStr,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// How long to wait for the lock of another process before giving up.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(120);
+/// Locks older than this are considered left behind by a crashed process.
+const STALE_LOCK_AGE: Duration = Duration::from_secs(300);
+
+/// The nonce of an extrinsic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Nonce {
+    /// Serialize the nonces of the processes submitting from the same account on this machine.
+    AutoIncrement,
+    /// Use the given nonce.
+    Fixed(u32),
+}
+
+impl FromStr for Nonce {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        match input {
+            "auto-increment" => Ok(Nonce::AutoIncrement),
+            number => number.parse().map(Nonce::Fixed).map_err(|_| {
+                anyhow::anyhow!("Expected `auto-increment` or a number, got '{}'", input)
+            }),
+        }
+    }
+}
+
+/// Returns whether a submission failed because its nonce was used by another extrinsic.
+pub(super) fn is_stale_nonce(err: &subxt::BasicError) -> bool {
+    let err = err.to_string();
+    err.contains("Transaction is outdated")
+        || err.contains("Priority is too low")
+        || err.contains("Transaction Already Imported")
+}
+
+/// The exclusive access to the recorded nonce of an account, released on drop.
+pub(super) struct NonceLock {
+    lock: PathBuf,
+    state: PathBuf,
+}
+
+impl NonceLock {
+    /// Waits for the lock of the nonces of the `account` on the chain with the `genesis` hash.
+    pub(super) fn acquire(genesis: &CodeHash, account: &AccountId32) -> Result<Self> {
+        let dir = std::env::temp_dir().join("cargo-contract-nonces");
+        Self::acquire_in(&dir, genesis, account)
+    }
+
+    fn acquire_in(dir: &Path, genesis: &CodeHash, account: &AccountId32) -> Result<Self> {
+        std::fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+        let name = format!("{:x}-{}", genesis, account.to_ss58check());
+        let lock = dir.join(format!("{}.lock", name));
+        let state = dir.join(format!("{}.nonce", name));
+
+        let start = SystemTime::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&lock) {
+                Ok(_) => return Ok(Self { lock, state }),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let age = std::fs::metadata(&lock)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok());
+                    if age.map_or(false, |age| age > STALE_LOCK_AGE) {
+                        log::warn!("Removing stale nonce lock {}", lock.display());
+                        let _ = std::fs::remove_file(&lock);
+                        continue;
+                    }
+                    if start.elapsed().unwrap_or_default() > LOCK_TIMEOUT {
+                        anyhow::bail!("Timed out waiting for the nonce lock {}", lock.display())
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(err) => {
+                    return Err(err).context(format!("Failed to create {}", lock.display()))
+                }
+            }
+        }
+    }
+
+    /// Returns the nonce to use, given the next nonce of the account according to the chain.
+    pub(super) fn next(&self, chain_next: u32) -> Result<u32> {
+        let recorded = match std::fs::read_to_string(&self.state) {
+            Ok(contents) => contents.trim().parse::<u32>().ok().map(|last| last + 1),
+            Err(_) => None,
+        };
+        Ok(recorded.map_or(chain_next, |recorded| recorded.max(chain_next)))
+    }
+
+    /// Records the `nonce` as used.
+    pub(super) fn commit(&self, nonce: u32) -> Result<()> {
+        std::fs::write(&self.state, nonce.to_string())
+            .context(format!("Failed to write {}", self.state.display()))
+    }
+}
+
+impl Drop for NonceLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.lock);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn nonces_are_incremented_across_locks() {
+        with_tmp_dir(|dir| {
+            let genesis = CodeHash::repeat_byte(1);
+            let account = AccountId32::new([2; 32]);
+
+            let lock = NonceLock::acquire_in(dir, &genesis, &account)?;
+            assert_eq!(lock.next(5)?, 5);
+            lock.commit(5)?;
+            drop(lock);
+
+            let lock = NonceLock::acquire_in(dir, &genesis, &account)?;
+            // the chain does not know about the pending extrinsic with nonce 5 yet
+            assert_eq!(lock.next(5)?, 6);
+            // the chain is ahead, e.g. because of submissions from another machine
+            assert_eq!(lock.next(9)?, 9);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn nonce_is_parsed() {
+        assert_eq!(
+            "auto-increment".parse::<Nonce>().unwrap(),
+            Nonce::AutoIncrement
+        );
+        assert_eq!("7".parse::<Nonce>().unwrap(), Nonce::Fixed(7));
+        assert!("next".parse::<Nonce>().is_err());
+    }
+}
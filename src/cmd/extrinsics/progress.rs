@@ -0,0 +1,258 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The progress of a submitted extrinsic until its inclusion in a block, or its finalization with
+//! `--finalized`, printed as it happens and emitted as structured events.
+
+use super::{RuntimeDispatchError, RuntimeEvent};
+use crate::{
+    exit_code::{classify, ExitCode},
+    maybe_println, output, Verbosity,
+};
+use anyhow::Result;
+use jsonrpsee::core::client::Subscription;
+use sp_core::H256;
+use std::fmt;
+use subxt::{
+    rpc::SubstrateTransactionStatus, Client, DefaultConfig, TransactionInBlock,
+    TransactionProgress, TransactionStatus,
+};
+
+/// A change of the status of a submitted extrinsic.
+#[derive(Debug, PartialEq)]
+enum Progress {
+    /// Waiting in the transaction pool for the extrinsics with lower nonces.
+    Future,
+    Ready,
+    /// Broadcast to the given number of peers.
+    Broadcast(usize),
+    /// Included in the block with the number, if known, and hash.
+    InBlock(Option<u32>, H256),
+    /// The block including the extrinsic was retracted by a fork, so that it is included anew.
+    Retracted(H256),
+    Finalized(Option<u32>, H256),
+}
+
+impl Progress {
+    /// The name of the status in the structured events.
+    fn name(&self) -> &'static str {
+        match self {
+            Progress::Future => "future",
+            Progress::Ready => "ready",
+            Progress::Broadcast(_) => "broadcast",
+            Progress::InBlock(..) => "in_block",
+            Progress::Retracted(_) => "retracted",
+            Progress::Finalized(..) => "finalized",
+        }
+    }
+
+    fn block(&self) -> (Option<u32>, Option<H256>) {
+        match self {
+            Progress::InBlock(number, hash) | Progress::Finalized(number, hash) => {
+                (*number, Some(*hash))
+            }
+            Progress::Retracted(hash) => (None, Some(*hash)),
+            _ => (None, None),
+        }
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let block = |number: &Option<u32>, hash: &H256| match number {
+            Some(number) => format!("#{} ({:?})", number, hash),
+            None => format!("{:?}", hash),
+        };
+        match self {
+            Progress::Future => write!(f, "Waiting in the pool for the lower nonces of the signer"),
+            Progress::Ready => write!(f, "Ready in the transaction pool"),
+            Progress::Broadcast(peers) => write!(f, "Broadcast to {} peers", peers),
+            Progress::InBlock(number, hash) => write!(f, "In block {}", block(number, hash)),
+            Progress::Retracted(hash) => write!(
+                f,
+                "Block {:?} was retracted by a fork, waiting for a new block",
+                hash
+            ),
+            Progress::Finalized(number, hash) => write!(f, "Finalized {}", block(number, hash)),
+        }
+    }
+}
+
+/// Why an extrinsic was not included in a block.
+#[derive(Debug, thiserror::Error)]
+enum Failure {
+    #[error("The block {0:?} was not finalized in time")]
+    FinalityTimeout(H256),
+    #[error("The extrinsic was replaced by the extrinsic {0:?} with the same nonce")]
+    Usurped(H256),
+    #[error("The extrinsic was dropped from the transaction pool")]
+    Dropped,
+    #[error("The extrinsic is invalid")]
+    Invalid,
+    #[error("The node stopped reporting the status of the extrinsic")]
+    Unreported,
+}
+
+impl Failure {
+    /// The node rejected the extrinsic or failed to report on it, which are failures of the RPC.
+    fn into_error(self) -> anyhow::Error {
+        classify(self.into(), ExitCode::Rpc)
+    }
+}
+
+/// Prints the `progress` and emits it as structured event.
+fn report(progress: &Progress, verbosity: Verbosity) {
+    let (block_number, block_hash) = progress.block();
+    tracing::info!(
+        target: "cargo_contract::events::extrinsic_status",
+        status = progress.name(),
+        block_number = ?block_number,
+        block_hash = ?block_hash,
+    );
+    maybe_println!(verbosity, "{}", output::progress(progress));
+}
+
+/// Follows the `tx_progress` of an extrinsic until it is included in a block, or finalized if
+/// `finalized` is set, reporting every change of its status.
+pub(super) async fn follow<'a>(
+    client: &'a Client<DefaultConfig>,
+    mut tx_progress: TransactionProgress<'a, DefaultConfig, RuntimeDispatchError, RuntimeEvent>,
+    finalized: bool,
+    verbosity: Verbosity,
+) -> Result<TransactionInBlock<'a, DefaultConfig, RuntimeDispatchError, RuntimeEvent>> {
+    while let Some(status) = tx_progress.next_item().await {
+        let progress = match status? {
+            TransactionStatus::Future => Progress::Future,
+            TransactionStatus::Ready => Progress::Ready,
+            TransactionStatus::Broadcast(peers) => Progress::Broadcast(peers.len()),
+            TransactionStatus::InBlock(in_block) => {
+                let hash = in_block.block_hash();
+                report(
+                    &Progress::InBlock(block_number(client, hash).await, hash),
+                    verbosity,
+                );
+                if finalized {
+                    continue;
+                }
+                return Ok(in_block);
+            }
+            TransactionStatus::Retracted(hash) => Progress::Retracted(hash),
+            TransactionStatus::Finalized(in_block) => {
+                let hash = in_block.block_hash();
+                report(
+                    &Progress::Finalized(block_number(client, hash).await, hash),
+                    verbosity,
+                );
+                return Ok(in_block);
+            }
+            TransactionStatus::FinalityTimeout(hash) => {
+                return Err(Failure::FinalityTimeout(hash).into_error())
+            }
+            TransactionStatus::Usurped(hash) => return Err(Failure::Usurped(hash).into_error()),
+            TransactionStatus::Dropped => return Err(Failure::Dropped.into_error()),
+            TransactionStatus::Invalid => return Err(Failure::Invalid.into_error()),
+        };
+        report(&progress, verbosity);
+    }
+    Err(Failure::Unreported.into_error())
+}
+
+/// Follows the `statuses` of an extrinsic submitted with `author_submitAndWatchExtrinsic` like
+/// [`follow`], returning the hash of the block which includes it.
+pub(super) async fn follow_statuses(
+    client: &Client<DefaultConfig>,
+    mut statuses: Subscription<SubstrateTransactionStatus<H256, H256>>,
+    finalized: bool,
+    verbosity: Verbosity,
+) -> Result<H256> {
+    while let Some(status) = statuses.next().await {
+        let progress = match status? {
+            SubstrateTransactionStatus::Future => Progress::Future,
+            SubstrateTransactionStatus::Ready => Progress::Ready,
+            SubstrateTransactionStatus::Broadcast(peers) => Progress::Broadcast(peers.len()),
+            SubstrateTransactionStatus::InBlock(hash) => {
+                report(
+                    &Progress::InBlock(block_number(client, hash).await, hash),
+                    verbosity,
+                );
+                if finalized {
+                    continue;
+                }
+                return Ok(hash);
+            }
+            SubstrateTransactionStatus::Retracted(hash) => Progress::Retracted(hash),
+            SubstrateTransactionStatus::Finalized(hash) => {
+                report(
+                    &Progress::Finalized(block_number(client, hash).await, hash),
+                    verbosity,
+                );
+                return Ok(hash);
+            }
+            SubstrateTransactionStatus::FinalityTimeout(hash) => {
+                return Err(Failure::FinalityTimeout(hash).into_error())
+            }
+            SubstrateTransactionStatus::Usurped(hash) => {
+                return Err(Failure::Usurped(hash).into_error())
+            }
+            SubstrateTransactionStatus::Dropped => return Err(Failure::Dropped.into_error()),
+            SubstrateTransactionStatus::Invalid => return Err(Failure::Invalid.into_error()),
+        };
+        report(&progress, verbosity);
+    }
+    Err(Failure::Unreported.into_error())
+}
+
+/// Returns the number of the block with the `hash`, if the node knows it.
+async fn block_number(client: &Client<DefaultConfig>, hash: H256) -> Option<u32> {
+    match client.rpc().header(Some(hash)).await {
+        Ok(header) => header.map(|header| header.number),
+        Err(err) => {
+            log::debug!("Failed to fetch the header of block {:?}: {}", hash, err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_is_displayed_with_the_block() {
+        let hash = H256::repeat_byte(1);
+        assert_eq!(Progress::Broadcast(3).to_string(), "Broadcast to 3 peers");
+        assert_eq!(
+            Progress::InBlock(Some(42), hash).to_string(),
+            format!("In block #42 ({:?})", hash)
+        );
+        assert_eq!(
+            Progress::Finalized(None, hash).to_string(),
+            format!("Finalized {:?}", hash)
+        );
+        assert_eq!(Progress::Retracted(hash).block(), (None, Some(hash)));
+        assert_eq!(Progress::Ready.name(), "ready");
+    }
+
+    #[test]
+    fn rejected_extrinsics_are_rpc_failures() {
+        let error = Failure::Dropped.into_error();
+        assert_eq!(crate::exit_code::exit_code(&error), ExitCode::Rpc);
+        assert_eq!(
+            error.to_string(),
+            "The extrinsic was dropped from the transaction pool"
+        );
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    call::{display_call_result, ContractExecResult},
+    instantiate::{display_instantiate_result, ContractInstantiateResult},
+    load_metadata,
+    runtime_api::api::contracts::events::ContractEmitted,
+    session::Session,
+    ContractAccount, ContractMessageTranscoder,
+};
+use crate::{name_value_println, DEFAULT_KEY_COL_WIDTH};
+use anyhow::{anyhow, Result};
+use colored::Colorize as _;
+use scale::Decode as _;
+use std::path::PathBuf;
+use subxt::Event;
+
+#[derive(Debug, clap::Args)]
+#[clap(name = "replay", about = "Replay a session recorded with `--record`")]
+pub struct ReplayCommand {
+    /// Path to the session file written by `call --record` or `instantiate --record`.
+    #[clap(parse(from_os_str))]
+    session: PathBuf,
+    /// Path to the `Cargo.toml` of the contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+}
+
+impl ReplayCommand {
+    /// Decode the recorded RPC responses and events with the metadata of the local contract,
+    /// without connecting to a node.
+    pub fn run(&self) -> Result<()> {
+        let session = Session::load(&self.session)?;
+        let (_, contract_metadata, constructor_return_types) =
+            load_metadata(self.manifest_path.as_ref())?;
+        let transcoder = ContractMessageTranscoder::new(&contract_metadata)
+            .with_constructor_return_types(constructor_return_types);
+
+        name_value_println!(
+            "Recorded",
+            format!("cargo-contract {}", session.cargo_contract_version)
+        );
+        name_value_println!("Url", session.url);
+        name_value_println!("Command", format!("{:?}", session.kind).to_lowercase());
+        name_value_println!("Name", session.name);
+        name_value_println!("Args", session.args.join(" "));
+
+        let data = transcoder.encode(&session.name, &session.args)?;
+        if data != session.data.0 {
+            println!(
+                "{} the arguments now encode to 0x{} instead of the recorded 0x{}",
+                "Warning:".yellow().bold(),
+                hex::encode(&data),
+                hex::encode(&session.data.0),
+            );
+        }
+        println!();
+
+        for exchange in &session.rpc {
+            name_value_println!("Rpc", exchange.method);
+            match exchange.method.as_str() {
+                "contracts_call" => {
+                    let result: ContractExecResult =
+                        serde_json::from_value(exchange.response.clone())?;
+                    display_call_result(&result, &transcoder, &session.name)?;
+                }
+                "contracts_instantiate" => {
+                    let result: ContractInstantiateResult =
+                        serde_json::from_value(exchange.response.clone())?;
+                    display_instantiate_result(&result, &transcoder, &session.name)?;
+                }
+                method => return Err(anyhow!("Unsupported recorded RPC method {}", method)),
+            }
+            println!();
+        }
+
+        for extrinsic in &session.extrinsics {
+            name_value_println!("Extrinsic", extrinsic.extrinsic_hash);
+            name_value_println!("Block", extrinsic.block_hash);
+            name_value_println!("Call", format!("0x{}", hex::encode(&extrinsic.call.0)));
+        }
+
+        for event in &session.events {
+            println!(
+                "{:>width$} {} ➜ {}",
+                "Event".bright_green().bold(),
+                event.pallet.bright_white(),
+                event.variant.bright_white().bold(),
+                width = DEFAULT_KEY_COL_WIDTH
+            );
+            if <ContractEmitted as Event>::is_event(&event.pallet, &event.variant) {
+                let (contract, data) =
+                    <(ContractAccount, Vec<u8>)>::decode(&mut &event.data.0[..])?;
+                let contract_event = transcoder.decode_contract_event(&mut &data[..])?;
+                name_value_println!("contract", format!("{}", contract));
+                name_value_println!("data", format!("{}", contract_event));
+            } else {
+                name_value_println!("data", format!("0x{}", hex::encode(&event.data.0)));
+            }
+        }
+        Ok(())
+    }
+}
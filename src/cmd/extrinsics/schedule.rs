@@ -0,0 +1,130 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Scheduling of a call at a future block with `Scheduler::schedule`, e.g. for time-delayed admin
+//! operations of a contract.
+//!
+//! The scheduled call is dispatched with the origin which scheduled it. Most runtimes only allow
+//! root to schedule calls, so with `--sudo` the call is wrapped into `Utility::dispatch_as` with
+//! the signer as origin, as only accounts can call contracts.
+
+use super::{encode_call, submission::EncodedCall, sudo::dispatch_as, ContractAccount, RuntimeApi};
+use anyhow::{Context, Result};
+use scale::Encode;
+use subxt::{Call, Metadata};
+
+/// The priority of scheduled calls, in the middle of the highest `0` and the lowest `255`.
+const PRIORITY: u8 = 127;
+
+/// `Scheduler::schedule` of an encoded call.
+#[derive(Debug, Encode)]
+pub(super) struct ScheduleCall {
+    when: u32,
+    maybe_periodic: Option<(u32, u32)>,
+    priority: u8,
+    call: EncodedCall,
+}
+
+impl Call for ScheduleCall {
+    const PALLET: &'static str = "Scheduler";
+    const FUNCTION: &'static str = "schedule";
+}
+
+/// Returns the `call` scheduled at the block `when`, dispatched with the origin of the `signer`
+/// if it is scheduled with `sudo`.
+pub(super) async fn schedule_call<C: Call>(
+    api: &RuntimeApi,
+    call: &C,
+    when: u32,
+    signer: &ContractAccount,
+    sudo: bool,
+) -> Result<ScheduleCall> {
+    let best = api
+        .client
+        .rpc()
+        .header(None)
+        .await?
+        .context("The node returned no best block")?
+        .number;
+    if when <= best {
+        anyhow::bail!(
+            "Cannot schedule at block #{}, the chain is at block #{} already",
+            when,
+            best
+        )
+    }
+    let metadata = api.client.metadata();
+    let mut call = encode_call(metadata, call)?;
+    if sudo {
+        call = dispatch_as(metadata, signer, call)?;
+    }
+    if takes_maybe_hashed(metadata) {
+        // `MaybeHashed::Value` of older runtimes, which also accept the hash of a preimage
+        call.insert(0, 0);
+    }
+    Ok(ScheduleCall {
+        when,
+        maybe_periodic: None,
+        priority: PRIORITY,
+        call: EncodedCall(call),
+    })
+}
+
+/// Returns whether `Scheduler::schedule` takes the call as `MaybeHashed`, as older runtimes do.
+fn takes_maybe_hashed(metadata: &Metadata) -> bool {
+    let runtime = metadata.runtime_metadata();
+    let calls = runtime
+        .pallets
+        .iter()
+        .find(|pallet| pallet.name == ScheduleCall::PALLET)
+        .and_then(|pallet| pallet.calls.as_ref());
+    let schedule = match calls.and_then(|calls| runtime.types.resolve(calls.ty.id())) {
+        Some(ty) => match ty.type_def() {
+            scale_info::TypeDef::Variant(calls) => calls
+                .variants()
+                .iter()
+                .find(|call| call.name() == ScheduleCall::FUNCTION),
+            _ => None,
+        },
+        None => None,
+    };
+    let call = schedule.and_then(|schedule| {
+        schedule
+            .fields()
+            .iter()
+            .find(|field| field.name().map(String::as_str) == Some("call"))
+    });
+    call.and_then(|call| runtime.types.resolve(call.ty().id()))
+        .map_or(false, |ty| {
+            ty.path().segments().last().map(String::as_str) == Some("MaybeHashed")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheduled_calls_are_encoded_after_the_priority() {
+        let call = ScheduleCall {
+            when: 100,
+            maybe_periodic: None,
+            priority: PRIORITY,
+            call: EncodedCall(vec![7, 6, 0xaa]),
+        };
+        assert_eq!(call.encode(), [100, 0, 0, 0, 0, 127, 7, 6, 0xaa]);
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A local HTTP server for the development of frontends, serving the artifacts of a contract and
+//! encoding and decoding its calls with the transcoder of `cargo contract call`.
+//!
+//! The artifacts are read for every request, so that a rebuild is served without a restart.
+
+use super::{
+    http::{read_request, respond, set_timeouts, Request},
+    load_metadata_file, ContractMessageTranscoder,
+};
+use crate::{
+    cmd::interface::openrpc, crate_metadata::CrateMetadata, name_value_println, util::decode_hex,
+    workspace::ManifestPath,
+};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{convert::TryFrom, net::TcpListener, path::PathBuf};
+
+/// Serve the artifacts of the contract and encode and decode its calls on localhost.
+#[derive(Debug, clap::Args)]
+#[clap(name = "serve")]
+pub struct ServeCommand {
+    /// The port to listen on, on `127.0.0.1`.
+    #[clap(long, default_value = "8000")]
+    port: u16,
+    /// Path to the `Cargo.toml` of the contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+}
+
+/// The body of `POST /encode`.
+#[derive(Deserialize)]
+struct EncodeRequest {
+    /// The name of a constructor or message.
+    name: String,
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+/// The body of `POST /decode`.
+#[derive(Deserialize)]
+struct DecodeRequest {
+    /// The message whose return value is decoded, otherwise the data is decoded as event.
+    message: Option<String>,
+    data: String,
+}
+
+/// A failed request, answered with its status and the error as JSON.
+struct Failure {
+    status: &'static str,
+    error: anyhow::Error,
+}
+
+impl From<anyhow::Error> for Failure {
+    fn from(error: anyhow::Error) -> Self {
+        Failure {
+            status: "400 Bad Request",
+            error,
+        }
+    }
+}
+
+impl ServeCommand {
+    pub fn run(&self) -> Result<()> {
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        let crate_metadata = CrateMetadata::collect(&manifest_path)?;
+        let listener = TcpListener::bind(("127.0.0.1", self.port))
+            .context(format!("Failed to listen on port {}", self.port))?;
+        name_value_println!("Serving", format!("http://127.0.0.1:{}", self.port));
+
+        for stream in listener.incoming() {
+            let mut stream = stream?;
+            set_timeouts(&stream)?;
+            let request = match read_request(&mut stream) {
+                Ok(request) => request,
+                Err(err) => {
+                    log::warn!("Failed to read a request: {}", err);
+                    continue;
+                }
+            };
+            let (status, body) = match handle(&crate_metadata, &request) {
+                Ok(body) => ("200 OK", body),
+                Err(failure) => {
+                    log::info!("{} {}: {:#}", request.method, request.path, failure.error);
+                    let body = json!({ "error": format!("{:#}", failure.error) });
+                    (failure.status, body.to_string())
+                }
+            };
+            if let Err(err) = respond(&mut stream, status, "application/json", &body) {
+                log::warn!("Failed to respond: {}", err);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the JSON body of the response to the `request`.
+fn handle(crate_metadata: &CrateMetadata, request: &Request) -> Result<String, Failure> {
+    let bundle_path = crate_metadata.target_directory.join(format!(
+        "{}.contract",
+        crate_metadata.contract_artifact_name
+    ));
+    let metadata_path = crate_metadata.metadata_path();
+    match (request.method.as_str(), request.path.as_str()) {
+        // the preflight requests of browsers
+        ("OPTIONS", _) => Ok(String::new()),
+        ("GET", "/") => Ok(json!({
+            "GET /bundle": "The `.contract` bundle of the contract",
+            "GET /metadata": "The `metadata.json` of the contract",
+            "GET /messages": "The constructors and messages as OpenRPC document",
+            "POST /encode": "{\"name\", \"args\"} to the call data of a constructor or message",
+            "POST /decode": "{\"message\", \"data\"} to the return value, without message an event",
+        })
+        .to_string()),
+        ("GET", "/bundle") => read_artifact(&bundle_path),
+        ("GET", "/metadata") => read_artifact(&metadata_path),
+        ("GET", "/messages") => {
+            let (metadata, project) = load_metadata_file(&metadata_path)?;
+            let document = openrpc(
+                &metadata.contract.name,
+                &metadata.contract.version.to_string(),
+                &project,
+            )?;
+            Ok(document.to_string())
+        }
+        ("POST", "/encode") => {
+            let request: EncodeRequest = parse_body(request)?;
+            let (_, project) = load_metadata_file(&metadata_path)?;
+            let transcoder = ContractMessageTranscoder::new(&project);
+            let data = transcoder.encode(&request.name, &request.args)?;
+            Ok(json!({ "data": format!("0x{}", hex::encode(data)) }).to_string())
+        }
+        ("POST", "/decode") => {
+            let request: DecodeRequest = parse_body(request)?;
+            let data = decode_hex(&request.data).context("The data is not hex encoded")?;
+            let (_, project) = load_metadata_file(&metadata_path)?;
+            let transcoder = ContractMessageTranscoder::new(&project);
+            let value = match &request.message {
+                Some(message) => transcoder.decode_return(message, &mut &data[..])?,
+                None => transcoder.decode_contract_event(&mut &data[..])?,
+            };
+            Ok(json!({ "value": value.to_string() }).to_string())
+        }
+        (method, path) => Err(Failure {
+            status: "404 Not Found",
+            error: anyhow::anyhow!("No endpoint {} {}, see GET /", method, path),
+        }),
+    }
+}
+
+fn read_artifact(path: &std::path::Path) -> Result<String, Failure> {
+    std::fs::read_to_string(path).map_err(|err| Failure {
+        status: "404 Not Found",
+        error: anyhow::anyhow!(
+            "Failed to read {}, build the contract with `cargo contract build`: {}",
+            path.display(),
+            err
+        ),
+    })
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(request: &Request) -> Result<T, Failure> {
+    let body: Value = serde_json::from_slice(&request.body).context("The body is not JSON")?;
+    Ok(serde_json::from_value(body).context("Unexpected fields of the body")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bodies_are_parsed() {
+        let request = Request {
+            method: "POST".into(),
+            path: "/encode".into(),
+            query: None,
+            body: br#"{"name": "transfer", "args": ["ALICE", "100"]}"#.to_vec(),
+        };
+        let encode: EncodeRequest = parse_body(&request).ok().unwrap();
+        assert_eq!(encode.name, "transfer");
+        assert_eq!(encode.args, ["ALICE", "100"]);
+
+        let request = Request {
+            body: b"not json".to_vec(),
+            ..request
+        };
+        let failure = parse_body::<EncodeRequest>(&request).err().unwrap();
+        assert_eq!(failure.status, "400 Bad Request");
+    }
+}
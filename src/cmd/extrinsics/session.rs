@@ -0,0 +1,243 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::RuntimeEvent;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+use subxt::{Config, TransactionEvents};
+
+/// The kind of extrinsic command a [`Session`] was recorded for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionKind {
+    Call,
+    Instantiate,
+}
+
+/// A recording of all the RPC traffic and chain events of a single `call` or `instantiate`.
+///
+/// The session is written to disk after every recorded item, so that it is available even when
+/// the command subsequently fails, e.g. because a response could not be decoded.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    #[serde(skip)]
+    path: PathBuf,
+    /// The version of `cargo-contract` the session was recorded with.
+    pub cargo_contract_version: String,
+    pub kind: SessionKind,
+    /// The name of the message or constructor.
+    pub name: String,
+    /// The arguments as supplied on the command line.
+    pub args: Vec<String>,
+    /// The SCALE encoded input data which was sent to the contract.
+    pub data: Bytes,
+    pub url: String,
+    pub rpc: Vec<RpcExchange>,
+    /// The extrinsics submitted unless the session was a dry-run.
+    #[serde(default)]
+    pub extrinsics: Vec<RecordedExtrinsic>,
+    pub events: Vec<RecordedEvent>,
+}
+
+/// A single RPC request together with the raw response of the node.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RpcExchange {
+    pub method: String,
+    pub params: serde_json::Value,
+    pub response: serde_json::Value,
+}
+
+/// An extrinsic submitted to the node and included in a block.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedExtrinsic {
+    /// The SCALE encoded call with its pallet and call index, before it is wrapped for `--sudo`
+    /// or `--schedule-at`.
+    pub call: Bytes,
+    pub extrinsic_hash: String,
+    pub block_hash: String,
+}
+
+/// A raw event emitted by the extrinsic, as received from the node.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedEvent {
+    pub pallet: String,
+    pub variant: String,
+    pub pallet_index: u8,
+    pub variant_index: u8,
+    pub data: Bytes,
+}
+
+impl Session {
+    /// Start a new session, which will be written to `path`.
+    pub fn new(
+        path: &Path,
+        kind: SessionKind,
+        name: &str,
+        args: &[String],
+        data: &[u8],
+        url: &url::Url,
+    ) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            cargo_contract_version: env!("CARGO_PKG_VERSION").to_string(),
+            kind,
+            name: name.to_string(),
+            args: args.to_vec(),
+            data: data.to_vec().into(),
+            url: url.to_string(),
+            rpc: Vec::new(),
+            extrinsics: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Load a previously recorded session.
+    pub fn load(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).context(format!("Failed to open session file {}", path.display()))?;
+        let mut session: Self = serde_json::from_reader(file).context(format!(
+            "Failed to deserialize session file {}",
+            path.display()
+        ))?;
+        session.path = path.to_path_buf();
+        Ok(session)
+    }
+
+    /// Record an RPC request and the raw response of the node.
+    pub fn record_rpc<P: Serialize>(
+        &mut self,
+        method: &str,
+        params: &P,
+        response: &serde_json::Value,
+    ) -> Result<()> {
+        self.rpc.push(RpcExchange {
+            method: method.to_string(),
+            params: serde_json::to_value(params)?,
+            response: response.clone(),
+        });
+        self.write()
+    }
+
+    /// Record an extrinsic submitted with the encoded `call` and all events it emitted once it
+    /// was included.
+    pub fn record_extrinsic<T: Config>(
+        &mut self,
+        call: Vec<u8>,
+        result: &TransactionEvents<T, RuntimeEvent>,
+    ) -> Result<()> {
+        self.extrinsics.push(RecordedExtrinsic {
+            call: call.into(),
+            extrinsic_hash: format!("{:?}", result.extrinsic_hash()),
+            block_hash: format!("{:?}", result.block_hash()),
+        });
+        self.record_events(result)
+    }
+
+    /// Record all events emitted by a successfully included extrinsic.
+    fn record_events<T: Config>(
+        &mut self,
+        result: &TransactionEvents<T, RuntimeEvent>,
+    ) -> Result<()> {
+        for event in result.iter_raw() {
+            let event = event?;
+            self.events.push(RecordedEvent {
+                pallet: event.pallet,
+                variant: event.variant,
+                pallet_index: event.pallet_index,
+                variant_index: event.variant_index,
+                data: event.data,
+            });
+        }
+        self.write()
+    }
+
+    fn write(&self) -> Result<()> {
+        let file = File::create(&self.path).context(format!(
+            "Failed to create session file {}",
+            self.path.display()
+        ))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn session_roundtrips_through_file() {
+        with_tmp_dir(|path| {
+            let path = path.join("session.json");
+            let url = url::Url::parse("ws://localhost:9944").unwrap();
+            let mut session = Session::new(
+                &path,
+                SessionKind::Call,
+                "flip",
+                &[],
+                &[0x63, 0x3a, 0xa5, 0x51],
+                &url,
+            );
+            session.record_rpc(
+                "contracts_call",
+                &serde_json::json!({ "inputData": "0x633aa551" }),
+                &serde_json::json!({ "gasConsumed": 42 }),
+            )?;
+
+            let loaded = Session::load(&path)?;
+            assert_eq!(loaded.kind, SessionKind::Call);
+            assert_eq!(loaded.name, "flip");
+            assert_eq!(loaded.data.0, vec![0x63, 0x3a, 0xa5, 0x51]);
+            assert_eq!(loaded.rpc.len(), 1);
+            assert_eq!(loaded.rpc[0].method, "contracts_call");
+            assert_eq!(loaded.rpc[0].response["gasConsumed"], 42);
+            assert!(loaded.extrinsics.is_empty());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn submitted_extrinsics_are_loaded() {
+        with_tmp_dir(|path| {
+            let path = path.join("session.json");
+            let url = url::Url::parse("ws://localhost:9944").unwrap();
+            let mut session = Session::new(&path, SessionKind::Call, "flip", &[], &[], &url);
+            session.extrinsics.push(RecordedExtrinsic {
+                call: vec![0x07, 0x00].into(),
+                extrinsic_hash: "0x01".into(),
+                block_hash: "0x02".into(),
+            });
+            session.write()?;
+
+            let json = std::fs::read_to_string(&path)?;
+            assert!(json.contains("\"extrinsicHash\": \"0x01\""));
+            let loaded = Session::load(&path)?;
+            assert_eq!(loaded.extrinsics.len(), 1);
+            assert_eq!(loaded.extrinsics[0].call.0, vec![0x07, 0x00]);
+            assert_eq!(loaded.extrinsics[0].block_hash, "0x02");
+            Ok(())
+        })
+    }
+}
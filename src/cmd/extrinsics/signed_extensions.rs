@@ -0,0 +1,472 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The signed extensions of the extrinsics, in the order and with the extensions of the chain.
+//!
+//! By default the signed extensions of `subxt`'s `DefaultExtra` are used. Chains which require
+//! other signed extensions, e.g. `ChargeAssetTxPayment` instead of `ChargeTransactionPayment`,
+//! are supported by configuring the SCALE encoded values of those extensions for their network:
+//!
+//! ```toml
+//! [[networks.statemine.signed-extensions]]
+//! identifier = "ChargeAssetTxPayment"
+//! # no tip, pay the fee in the native asset
+//! extra = "0x0000"
+//! ```
+//!
+//! Before submitting an extrinsic the signed extensions are arranged in the order of the runtime
+//! metadata of the chain, see [`configure`]. The values of the configured extensions take the
+//! place of the default ones. Extensions without any values, such as `CheckNonZeroSender`, need
+//! no configuration. The arrangement is carried by the signer of the extrinsics, which
+//! arranges the extensions of the payload before signing it.
+
+use crate::{config::SignedExtensionConfig, util::decode_hex};
+use anyhow::{Context, Result};
+use scale::{Compact, Decode, Encode, Output};
+use scale_info::{PortableRegistry, TypeDef};
+use sp_runtime::{
+    traits::{DispatchInfoOf, SignedExtension},
+    transaction_validity::TransactionValidityError,
+};
+use std::collections::BTreeMap;
+use subxt::{
+    extrinsic::{
+        ChargeTransactionPayment, CheckGenesis, CheckMortality, CheckNonce, CheckSpecVersion,
+        CheckTxVersion, CheckWeight, DefaultExtra,
+    },
+    Config, DefaultConfig, SignedExtra,
+};
+
+/// The signed extension of `pallet-asset-tx-payment`, which pays the fee in an asset.
+const ASSET_TX_PAYMENT: &str = "ChargeAssetTxPayment";
+
+/// The identifiers of the signed extensions of the `DefaultExtra`, in its order.
+fn default_identifiers() -> [&'static str; 7] {
+    [
+        CheckSpecVersion::<DefaultConfig>::IDENTIFIER,
+        CheckTxVersion::<DefaultConfig>::IDENTIFIER,
+        CheckGenesis::<DefaultConfig>::IDENTIFIER,
+        CheckMortality::<DefaultConfig>::IDENTIFIER,
+        CheckNonce::<DefaultConfig>::IDENTIFIER,
+        CheckWeight::<DefaultConfig>::IDENTIFIER,
+        ChargeTransactionPayment::<DefaultConfig>::IDENTIFIER,
+    ]
+}
+
+/// The SCALE encoded values of a signed extension.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Encoded {
+    /// The value included in the extrinsic.
+    extra: Vec<u8>,
+    /// The value only included in the signed payload.
+    additional_signed: Vec<u8>,
+}
+
+/// The signed extensions of a chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct Layout {
+    /// The identifiers of the signed extensions, in the order of the runtime metadata.
+    order: Vec<String>,
+    /// The configured values of signed extensions, by their identifier.
+    custom: BTreeMap<String, Encoded>,
+}
+
+impl Layout {
+    /// Returns the layout of the signed extensions `order` of a runtime, with the `custom`
+    /// extensions configured for its network. The `zero_sized` extensions encode no values.
+    fn new(
+        order: Vec<String>,
+        zero_sized: &[String],
+        custom: &[SignedExtensionConfig],
+    ) -> Result<Self> {
+        let custom = custom
+            .iter()
+            .map(|extension| {
+                let hex = |value: &str| {
+                    decode_hex(value).context(format!(
+                        "Invalid hex value `{}` of the signed extension `{}`",
+                        value, extension.identifier
+                    ))
+                };
+                let encoded = Encoded {
+                    extra: hex(&extension.extra)?,
+                    additional_signed: hex(&extension.additional_signed)?,
+                };
+                Ok((extension.identifier.clone(), encoded))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        let defaults = default_identifiers();
+        if let Some(missing) = order.iter().find(|id| {
+            !defaults.contains(&id.as_str())
+                && !custom.contains_key(*id)
+                && !zero_sized.contains(id)
+        }) {
+            anyhow::bail!(
+                "The chain requires the signed extension `{}`, configure its SCALE encoded values \
+                 in `[[networks.<name>.signed-extensions]]` of the configuration file",
+                missing
+            )
+        }
+        if let Some(unused) = custom.keys().find(|id| !order.contains(id)) {
+            anyhow::bail!(
+                "The configured signed extension `{}` is not used by the chain, whose extensions \
+                 are {}",
+                unused,
+                order.join(", ")
+            )
+        }
+        Ok(Self { order, custom })
+    }
+}
+
+/// Returns the arrangement of the signed extensions the runtime `metadata` requires, with the
+/// `custom` extensions of the network. With a `fee_asset` the fee is paid in that asset.
+pub(super) fn configure(
+    metadata: &subxt::Metadata,
+    custom: &[SignedExtensionConfig],
+    fee_asset: Option<u32>,
+) -> Result<Layout> {
+    let runtime = metadata.runtime_metadata();
+    let extensions = &runtime.extrinsic.signed_extensions;
+    let order = extensions
+        .iter()
+        .map(|extension| extension.identifier.clone())
+        .collect::<Vec<_>>();
+    let zero_sized = extensions
+        .iter()
+        .filter(|extension| {
+            is_zero_sized(&runtime.types, extension.ty.id())
+                && is_zero_sized(&runtime.types, extension.additional_signed.id())
+        })
+        .map(|extension| extension.identifier.clone())
+        .collect::<Vec<_>>();
+    let mut custom = custom.to_vec();
+    if let Some(asset_id) = fee_asset {
+        if !order.iter().any(|id| id == ASSET_TX_PAYMENT) {
+            anyhow::bail!(
+                "The chain does not support paying fees in other assets, it has no `{}` signed \
+                 extension",
+                ASSET_TX_PAYMENT
+            )
+        }
+        custom.retain(|extension| extension.identifier != ASSET_TX_PAYMENT);
+        custom.push(asset_tx_payment(asset_id));
+    }
+    Layout::new(order, &zero_sized, &custom)
+}
+
+/// Returns whether the values of the type `id` are encoded as no bytes at all, e.g. `()` or
+/// `CheckNonZeroSender<T>(PhantomData<T>)`.
+fn is_zero_sized(registry: &PortableRegistry, id: u32) -> bool {
+    match registry.resolve(id).map(|ty| ty.type_def()) {
+        Some(TypeDef::Composite(composite)) => composite
+            .fields()
+            .iter()
+            .all(|field| is_zero_sized(registry, field.ty().id())),
+        Some(TypeDef::Tuple(tuple)) => tuple
+            .fields()
+            .iter()
+            .all(|field| is_zero_sized(registry, field.id())),
+        Some(TypeDef::Array(array)) => {
+            array.len() == 0 || is_zero_sized(registry, array.type_param().id())
+        }
+        _ => false,
+    }
+}
+
+/// Returns the `ChargeAssetTxPayment` extension paying the fee in the asset `asset_id`, without a
+/// tip.
+fn asset_tx_payment(asset_id: u32) -> SignedExtensionConfig {
+    let tip = Compact(0u128);
+    SignedExtensionConfig {
+        identifier: ASSET_TX_PAYMENT.to_string(),
+        extra: format!("0x{}", hex::encode((tip, Some(asset_id)).encode())),
+        additional_signed: String::new(),
+    }
+}
+
+/// The `SignedExtra` of the extrinsics, see the module documentation.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, Debug, scale_info::TypeInfo)]
+pub struct ContractsExtra {
+    default: DefaultExtra<DefaultConfig>,
+}
+
+impl SignedExtra<DefaultConfig> for ContractsExtra {
+    type Extra = EncodedExtensions;
+    type Parameters = ();
+
+    fn new(
+        spec_version: u32,
+        tx_version: u32,
+        nonce: <DefaultConfig as Config>::Index,
+        genesis_hash: <DefaultConfig as Config>::Hash,
+        additional_params: Self::Parameters,
+    ) -> Self {
+        Self {
+            default: DefaultExtra::new(
+                spec_version,
+                tx_version,
+                nonce,
+                genesis_hash,
+                additional_params,
+            ),
+        }
+    }
+
+    /// The extensions of the `DefaultExtra`, which the signer arranges for the chain.
+    fn extra(&self) -> Self::Extra {
+        arrange(&default_extensions(&self.default), None)
+    }
+}
+
+impl SignedExtension for ContractsExtra {
+    const IDENTIFIER: &'static str = "ContractsExtra";
+    type AccountId = <DefaultConfig as Config>::AccountId;
+    type Call = ();
+    type AdditionalSigned = RawBytes;
+    type Pre = ();
+
+    fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
+        SignedExtra::extra(self).additional_signed()
+    }
+
+    fn pre_dispatch(
+        self,
+        _who: &Self::AccountId,
+        _call: &Self::Call,
+        _info: &DispatchInfoOf<Self::Call>,
+        _len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        Ok(())
+    }
+}
+
+/// Returns the encoded values of the signed extensions of the `default` extra, with their
+/// identifiers.
+fn default_extensions(default: &DefaultExtra<DefaultConfig>) -> [(&'static str, Encoded); 7] {
+    let (spec_version, tx_version, genesis, mortality, nonce, weight, payment) = default.extra();
+    [
+        encoded(&spec_version),
+        encoded(&tx_version),
+        encoded(&genesis),
+        encoded(&mortality),
+        encoded(&nonce),
+        encoded(&weight),
+        encoded(&payment),
+    ]
+}
+
+/// Returns the encoded values of a default signed extension, with its identifier.
+fn encoded<E: SignedExtension>(extension: &E) -> (&'static str, Encoded) {
+    let additional_signed = extension
+        .additional_signed()
+        .expect("the additional data of the default signed extensions is infallible; qed");
+    let encoded = Encoded {
+        extra: extension.encode(),
+        additional_signed: additional_signed.encode(),
+    };
+    (E::IDENTIFIER, encoded)
+}
+
+/// Concatenates the values of the signed extensions in the order of the `layout`, or of the
+/// `defaults` if there is none.
+fn arrange(defaults: &[(&'static str, Encoded)], layout: Option<&Layout>) -> EncodedExtensions {
+    let mut extensions = EncodedExtensions::default();
+    let mut append = |encoded: &Encoded| {
+        extensions.extra.extend_from_slice(&encoded.extra);
+        extensions
+            .additional_signed
+            .0
+            .extend_from_slice(&encoded.additional_signed);
+    };
+    match layout {
+        None => defaults.iter().for_each(|(_, encoded)| append(encoded)),
+        Some(layout) => {
+            for identifier in &layout.order {
+                let encoded = layout.custom.get(identifier).or_else(|| {
+                    defaults
+                        .iter()
+                        .find(|(default, _)| default == identifier)
+                        .map(|(_, encoded)| encoded)
+                });
+                // extensions which are neither default nor configured are zero-sized
+                if let Some(encoded) = encoded {
+                    append(encoded)
+                }
+            }
+        }
+    }
+    extensions.defaults = defaults.to_vec();
+    extensions
+}
+
+/// The concatenated values of the signed extensions of an extrinsic.
+#[derive(Clone, Debug, Default, PartialEq, Eq, scale_info::TypeInfo)]
+pub struct EncodedExtensions {
+    extra: Vec<u8>,
+    additional_signed: RawBytes,
+    /// The values of the default signed extensions, to arrange them anew.
+    #[codec(skip)]
+    defaults: Vec<(&'static str, Encoded)>,
+}
+
+impl EncodedExtensions {
+    /// Returns the extensions arranged like the `layout` of a chain.
+    pub(super) fn arranged(&self, layout: &Layout) -> Self {
+        arrange(&self.defaults, Some(layout))
+    }
+}
+
+impl Encode for EncodedExtensions {
+    fn size_hint(&self) -> usize {
+        self.extra.len()
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.extra)
+    }
+}
+
+impl Decode for EncodedExtensions {
+    fn decode<I: scale::Input>(_input: &mut I) -> Result<Self, scale::Error> {
+        Err("The signed extensions can only be decoded with the runtime metadata".into())
+    }
+}
+
+impl SignedExtension for EncodedExtensions {
+    const IDENTIFIER: &'static str = "EncodedExtensions";
+    type AccountId = <DefaultConfig as Config>::AccountId;
+    type Call = ();
+    type AdditionalSigned = RawBytes;
+    type Pre = ();
+
+    fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
+        Ok(self.additional_signed.clone())
+    }
+
+    fn pre_dispatch(
+        self,
+        _who: &Self::AccountId,
+        _call: &Self::Call,
+        _info: &DispatchInfoOf<Self::Call>,
+        _len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        Ok(())
+    }
+}
+
+/// Bytes which are encoded as they are, without a length prefix.
+#[derive(Clone, Debug, Default, PartialEq, Eq, scale_info::TypeInfo)]
+pub struct RawBytes(Vec<u8>);
+
+impl Encode for RawBytes {
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_extensions_are_arranged_like_the_runtime() {
+        let extra = DefaultExtra::<DefaultConfig>::new(100, 12, 3, Default::default(), ());
+        let defaults = default_extensions(&extra);
+        // without a layout, the extensions are encoded like the `DefaultExtra`
+        let default = extra.extra();
+        let unarranged = arrange(&defaults, None);
+        assert_eq!(unarranged.extra, default.encode());
+        assert_eq!(
+            unarranged.additional_signed,
+            RawBytes(default.additional_signed().unwrap().encode())
+        );
+
+        let mut order = default_identifiers()
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>();
+        order[6] = "ChargeAssetTxPayment".into();
+        let asset_payment = SignedExtensionConfig {
+            identifier: "ChargeAssetTxPayment".into(),
+            extra: "0x0000".into(),
+            additional_signed: String::new(),
+        };
+        assert!(Layout::new(order.clone(), &[], &[]).is_err());
+        let layout = Layout::new(order.clone(), &[], &[asset_payment.clone()]).unwrap();
+        let arranged = unarranged.arranged(&layout);
+        // `ChargeTransactionPayment` with no tip is encoded as a single compact zero, which is
+        // replaced by the configured value
+        let mut expected = default.encode();
+        expected.pop();
+        expected.extend_from_slice(&[0, 0]);
+        assert_eq!(arranged.extra, expected);
+        assert_eq!(
+            arranged.additional_signed.0,
+            default.additional_signed().unwrap().encode()
+        );
+
+        let fee_asset = asset_tx_payment(1984);
+        assert_eq!(fee_asset.identifier, asset_payment.identifier);
+        assert_eq!(fee_asset.extra, "0x0001c0070000");
+
+        order.pop();
+        assert!(Layout::new(order, &[], &[asset_payment]).is_err());
+    }
+
+    #[allow(dead_code)]
+    #[derive(scale_info::TypeInfo)]
+    struct CheckNonZeroSender(std::marker::PhantomData<u32>);
+
+    #[test]
+    fn zero_sized_signed_extensions_need_no_configuration() {
+        let mut registry = scale_info::Registry::new();
+        let non_zero_sender = registry
+            .register_type(&scale_info::meta_type::<CheckNonZeroSender>())
+            .id();
+        let unit = registry.register_type(&scale_info::meta_type::<()>()).id();
+        let tip = registry
+            .register_type(&scale_info::meta_type::<u128>())
+            .id();
+        let registry = PortableRegistry::from(registry);
+        assert!(is_zero_sized(&registry, non_zero_sender));
+        assert!(is_zero_sized(&registry, unit));
+        assert!(!is_zero_sized(&registry, tip));
+
+        // the order of substrate-contracts-node and node-template
+        let mut order = default_identifiers()
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>();
+        order.insert(0, "CheckNonZeroSender".into());
+        let zero_sized = ["CheckNonZeroSender".to_string()];
+        assert!(Layout::new(order.clone(), &[], &[]).is_err());
+        let layout = Layout::new(order, &zero_sized, &[]).unwrap();
+
+        let extra = DefaultExtra::<DefaultConfig>::new(100, 12, 3, Default::default(), ());
+        let arranged = arrange(&default_extensions(&extra), Some(&layout));
+        let default = extra.extra();
+        assert_eq!(arranged.extra, default.encode());
+        assert_eq!(
+            arranged.additional_signed.0,
+            default.additional_signed().unwrap().encode()
+        );
+    }
+}
@@ -0,0 +1,347 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Signing of extrinsics with a key pair, or by an external signer.
+//!
+//! With `--sign-external` no secret is needed on this machine: the payload of each extrinsic is
+//! printed as hex and as QR code in the Substrate UOS format, e.g. for an air-gapped signer, and
+//! the signature is either pasted back or posted to a local HTTP callback, e.g. by a small page
+//! using the Talisman or SubWallet extension.
+
+use super::{
+    http::{read_request, respond, set_timeouts, Request},
+    signed_extensions::Layout,
+    ContractAccount, SignedExtra,
+};
+use crate::util::decode_hex;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use scale::Encode;
+use sp_core::{
+    crypto::{Ss58Codec, UncheckedFrom},
+    hashing::blake2_256,
+    sr25519, Pair,
+};
+use sp_runtime::traits::SignedExtension;
+use std::{
+    io::{BufRead, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+};
+use subxt::{DefaultConfig, SignedPayload, Signer, UncheckedExtrinsic};
+
+/// Payloads longer than this are signed as their `blake2_256` hash.
+const MAX_UNHASHED_PAYLOAD_LEN: usize = 256;
+/// The UOS prefix of an sr25519 transaction to sign: substrate, sr25519, sign transaction.
+const UOS_PREFIX: [u8; 3] = [0x53, 0x01, 0x02];
+
+/// The signer of the extrinsics of a command.
+#[derive(Clone)]
+pub(super) struct ExtrinsicSigner {
+    kind: SignerKind,
+    /// The arrangement of the signed extensions of the chain the extrinsics are submitted to, the
+    /// one of the `DefaultExtra` if there is none.
+    signed_extensions: Option<Layout>,
+}
+
+#[derive(Clone)]
+enum SignerKind {
+    /// Signs with the key pair of `--suri` or `--keyfile`.
+    Pair(subxt::PairSigner<DefaultConfig, SignedExtra, sr25519::Pair>),
+    /// Asks an external signer for each signature.
+    External(ExternalSigner),
+}
+
+/// Asks an external signer for the signatures of an account.
+#[derive(Clone)]
+pub(super) struct ExternalSigner {
+    account: ContractAccount,
+    nonce: Option<u32>,
+    /// The port of the local HTTP callback to receive the signature on, instead of stdin.
+    callback_port: Option<u16>,
+    /// Sign with an invalid signature without asking, to estimate the fee of an extrinsic.
+    estimate_only: bool,
+    /// Why the last signature could not be obtained. [`Signer::sign`] cannot fail, so the
+    /// extrinsic is signed with an invalid signature and the error is returned by
+    /// [`ExtrinsicSigner::take_failure`] instead.
+    failure: Arc<Mutex<Option<anyhow::Error>>>,
+}
+
+impl ExtrinsicSigner {
+    pub(super) fn pair(pair: sr25519::Pair) -> Self {
+        Self {
+            kind: SignerKind::Pair(subxt::PairSigner::new(pair)),
+            signed_extensions: None,
+        }
+    }
+
+    pub(super) fn external(account: ContractAccount, callback_port: Option<u16>) -> Self {
+        Self {
+            kind: SignerKind::External(ExternalSigner {
+                account,
+                nonce: None,
+                callback_port,
+                estimate_only: false,
+                failure: Default::default(),
+            }),
+            signed_extensions: None,
+        }
+    }
+
+    /// Arranges the signed extensions of the following extrinsics like the `layout` of the chain
+    /// they are submitted to, or like the `DefaultExtra` without one.
+    pub(super) fn set_signed_extensions(&mut self, layout: Option<Layout>) {
+        self.signed_extensions = layout;
+    }
+
+    pub(super) fn account_id(&self) -> &ContractAccount {
+        match &self.kind {
+            SignerKind::Pair(signer) => signer.account_id(),
+            SignerKind::External(signer) => &signer.account,
+        }
+    }
+
+    pub(super) fn set_nonce(&mut self, nonce: u32) {
+        match &mut self.kind {
+            SignerKind::Pair(signer) => signer.set_nonce(nonce),
+            SignerKind::External(signer) => signer.nonce = Some(nonce),
+        }
+    }
+
+    /// Fails with the reason the external signer did not sign the last extrinsic, which has to be
+    /// checked after submitting an extrinsic. The node rejects the extrinsic in this case, as its
+    /// signature is invalid.
+    pub(super) fn take_failure(&self) -> Result<()> {
+        match &self.kind {
+            SignerKind::Pair(_) => Ok(()),
+            SignerKind::External(signer) => match signer.failure.lock().expect("poisoned").take() {
+                Some(err) => Err(err),
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// The signer to sign extrinsics which are only used to estimate their fee, which does not
+    /// bother the external signer.
+    pub(super) fn for_fee_estimation(&self) -> Self {
+        let kind = match &self.kind {
+            SignerKind::Pair(_) => self.kind.clone(),
+            SignerKind::External(signer) => SignerKind::External(ExternalSigner {
+                estimate_only: true,
+                ..signer.clone()
+            }),
+        };
+        Self {
+            kind,
+            signed_extensions: self.signed_extensions.clone(),
+        }
+    }
+}
+
+impl Signer<DefaultConfig, SignedExtra> for ExtrinsicSigner {
+    fn account_id(&self) -> &ContractAccount {
+        ExtrinsicSigner::account_id(self)
+    }
+
+    fn nonce(&self) -> Option<u32> {
+        match &self.kind {
+            SignerKind::Pair(signer) => Signer::nonce(signer),
+            SignerKind::External(signer) => signer.nonce,
+        }
+    }
+
+    fn sign(
+        &self,
+        extrinsic: SignedPayload<DefaultConfig, SignedExtra>,
+    ) -> UncheckedExtrinsic<DefaultConfig, SignedExtra> {
+        let (call, extra, additional) = extrinsic.deconstruct();
+        let (extra, additional) = match &self.signed_extensions {
+            Some(layout) => {
+                let extra = extra.arranged(layout);
+                let additional = SignedExtension::additional_signed(&extra)
+                    .expect("the additional data of the arranged extensions is infallible; qed");
+                (extra, additional)
+            }
+            None => (extra, additional),
+        };
+        let signer = match &self.kind {
+            SignerKind::Pair(signer) => {
+                return signer.sign(SignedPayload::from_raw(call, extra, additional))
+            }
+            SignerKind::External(signer) => signer,
+        };
+        let payload = (&call, &extra, &additional).encode();
+        let invalid = sr25519::Signature::from_raw([0; 64]);
+        let signature = if signer.estimate_only {
+            invalid
+        } else {
+            signer.request_signature(&payload).unwrap_or_else(|err| {
+                *signer.failure.lock().expect("poisoned") = Some(err);
+                invalid
+            })
+        };
+        UncheckedExtrinsic::new_signed(call, signer.account.clone().into(), signature.into(), extra)
+    }
+}
+
+impl ExternalSigner {
+    /// Prints the `payload` and blocks until a valid signature of it was received.
+    fn request_signature(&self, payload: &[u8]) -> Result<sr25519::Signature> {
+        let mut uos = UOS_PREFIX.to_vec();
+        uos.extend_from_slice(self.account.as_ref());
+        uos.extend_from_slice(payload);
+        match qrcode::QrCode::new(&uos) {
+            Ok(code) => {
+                println!(
+                    "{}",
+                    code.render::<qrcode::render::unicode::Dense1x2>().build()
+                )
+            }
+            Err(err) => log::warn!("The payload does not fit into a QR code: {}", err),
+        }
+        println!(
+            "{} {} {}",
+            "Sign the payload".bright_blue().bold(),
+            "with the account".bright_blue().bold(),
+            self.account.to_ss58check()
+        );
+        println!("0x{}", hex::encode(payload));
+        if payload.len() > MAX_UNHASHED_PAYLOAD_LEN {
+            println!(
+                "{}",
+                "The payload is signed as its blake2_256 hash, which most signers do implicitly"
+                    .dimmed()
+            );
+        }
+
+        match self.callback_port {
+            Some(port) => self.receive_signature(port, payload),
+            None => self.read_signature(payload),
+        }
+    }
+
+    /// Reads the signature from stdin, asking again for an invalid one.
+    fn read_signature(&self, payload: &[u8]) -> Result<sr25519::Signature> {
+        let stdin = std::io::stdin();
+        let mut lines = stdin.lock().lines();
+        loop {
+            print!("Signature: ");
+            std::io::stdout().flush()?;
+            let line = lines
+                .next()
+                .context("Aborted signing, no signature received")??;
+            match verify_signature(line.trim(), payload, &self.account) {
+                Ok(signature) => return Ok(signature),
+                Err(err) => eprintln!("{} {}", "Invalid signature:".bright_red(), err),
+            }
+        }
+    }
+
+    /// Waits for the signature on the local HTTP callback, either as the body of a request or as
+    /// its `signature` query parameter.
+    fn receive_signature(&self, port: u16, payload: &[u8]) -> Result<sr25519::Signature> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .context(format!("Failed to listen on port {}", port))?;
+        println!(
+            "Waiting for the signature on http://127.0.0.1:{}, e.g. `curl -d 0x... \
+             http://127.0.0.1:{}`",
+            port, port
+        );
+        for stream in listener.incoming() {
+            let mut stream = stream?;
+            set_timeouts(&stream)?;
+            let result = read_request(&mut stream)
+                .and_then(|request| signature_of(&request))
+                .and_then(|signature| verify_signature(&signature, payload, &self.account));
+            let (status, body) = match &result {
+                Ok(_) => ("200 OK", "Signature received\n".to_string()),
+                Err(err) => ("400 Bad Request", format!("Invalid signature: {}\n", err)),
+            };
+            respond(&mut stream, status, "text/plain", &body)?;
+            match result {
+                Ok(signature) => return Ok(signature),
+                Err(err) => eprintln!("{} {}", "Invalid signature:".bright_red(), err),
+            }
+        }
+        anyhow::bail!("Aborted signing, no signature received")
+    }
+}
+
+/// Returns the signature of a request, i.e. its body or its `signature` query parameter.
+fn signature_of(request: &Request) -> Result<String> {
+    let body = String::from_utf8_lossy(&request.body).trim().to_string();
+    if !body.is_empty() {
+        return Ok(body);
+    }
+    request
+        .query_param("signature")
+        .map(ToString::to_string)
+        .context("Expected the signature as body or as `signature` query parameter")
+}
+
+/// Parses the hex encoded `signature`, optionally prefixed with the sr25519 variant of a
+/// `MultiSignature`, and verifies it to be a signature of the `payload` by the `account`.
+fn verify_signature(
+    signature: &str,
+    payload: &[u8],
+    account: &ContractAccount,
+) -> Result<sr25519::Signature> {
+    let bytes = decode_hex(signature).context("The signature is not hex encoded")?;
+    let bytes = match bytes.len() {
+        64 => &bytes[..],
+        65 if bytes[0] == 1 => &bytes[1..],
+        len => anyhow::bail!("Expected a 64 byte sr25519 signature, got {} bytes", len),
+    };
+    let signature = sr25519::Signature::from_slice(bytes).context("Invalid sr25519 signature")?;
+    let public = sr25519::Public::unchecked_from(*account.as_ref());
+    let verified = if payload.len() > MAX_UNHASHED_PAYLOAD_LEN {
+        sr25519::Pair::verify(&signature, blake2_256(payload), &public)
+    } else {
+        sr25519::Pair::verify(&signature, payload, &public)
+    };
+    if !verified {
+        anyhow::bail!(
+            "The signature is not a signature of the payload by {}",
+            account.to_ss58check()
+        )
+    }
+    Ok(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_are_verified() {
+        let pair = sr25519::Pair::from_seed(&[1; 32]);
+        let account = ContractAccount::from(pair.public());
+        let payload = vec![7u8; 300];
+        let signature = pair.sign(&blake2_256(&payload));
+
+        let hex = format!("0x{}", hex::encode(signature));
+        assert_eq!(
+            verify_signature(&hex, &payload, &account).unwrap(),
+            signature
+        );
+        let multi_signature = format!("0x01{}", hex::encode(signature));
+        assert!(verify_signature(&multi_signature, &payload, &account).is_ok());
+
+        assert!(verify_signature(&hex, &payload[1..], &account).is_err());
+        let other = ContractAccount::new([2; 32]);
+        assert!(verify_signature(&hex, &payload, &other).is_err());
+    }
+}
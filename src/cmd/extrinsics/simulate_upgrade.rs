@@ -0,0 +1,591 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    call::{ContractExecResult, RpcCallRequest},
+    display_events,
+    fees::Cost,
+    instantiate::{owner_info_key, wasm_code_hash},
+    load_metadata, load_metadata_file,
+    runtime_api::api,
+    state::{contract_storage, decode_storage, StateField},
+    submission::submit_extrinsic,
+    upload::upload_code,
+    CodeHash, ContractAccount, ContractMessageTranscoder, ExtrinsicOpts, ExtrinsicSigner,
+    RuntimeApi,
+};
+use crate::{address_book::parse_address, cmd::interface::message_name, name_value_println};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use ink_metadata::InkProject;
+use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use serde::Deserialize;
+use sp_core::Bytes;
+use std::{collections::BTreeMap, fmt, path::PathBuf};
+use subxt::{rpc::NumberOrHex, ClientBuilder, Signer};
+
+/// Rehearse the upgrade of a contract to new code, before doing it for real.
+///
+/// Uploads the new code, upgrades the contract by calling a message of it which calls
+/// `ink_env::set_code_hash` with the code hash as its only argument, and dry-runs the same calls
+/// before and after the upgrade. Any call whose outcome changed is reported as incompatibility.
+///
+/// The current storage of the contract is also decoded with both the old and the new storage
+/// layout, and any cell which is decoded differently is reported as incompatibility.
+///
+/// The upgrade is submitted to the node at `--url`, which has to run a local fork of the live
+/// chain, e.g. with `chopsticks`, so that the live contract is not upgraded.
+#[derive(Debug, clap::Args)]
+#[clap(name = "simulate-upgrade")]
+pub struct SimulateUpgradeCommand {
+    /// The address of the contract to upgrade, or its alias in the address book.
+    #[clap(long, env = "CONTRACT", parse(try_from_str = parse_address))]
+    contract: ContractAccount,
+    /// Path to the new Wasm contract code, defaults to `./target/ink/<name>.wasm`.
+    #[clap(parse(from_os_str))]
+    wasm_path: Option<PathBuf>,
+    /// The `metadata.json` or `<name>.contract` of the code the contract currently runs. Defaults
+    /// to the metadata of the new code.
+    #[clap(long, parse(from_os_str))]
+    old_metadata: Option<PathBuf>,
+    /// The name of the message upgrading the contract.
+    #[clap(long, short, default_value = "set_code")]
+    message: String,
+    /// A TOML file with the calls to replay before and after the upgrade, as `[[call]]` tables
+    /// with a `message` and its `args`. Defaults to all messages which neither take arguments nor
+    /// mutate the storage.
+    #[clap(long, parse(from_os_str))]
+    calls: Option<PathBuf>,
+    #[clap(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// Maximum amount of gas to be used for the upgrade and each call.
+    #[clap(name = "gas", long, default_value = "50000000000")]
+    gas_limit: u64,
+    /// Submit the upgrade even if the node is not a declared local fork of the chain, i.e. neither
+    /// the `--network` profile sets `fork = true` nor the node at a loopback `--url` serves the
+    /// RPC of `chopsticks`.
+    #[clap(long)]
+    i_know_this_is_live: bool,
+}
+
+/// The file of the `--calls`.
+#[derive(Debug, Deserialize)]
+struct CallsFile {
+    #[serde(rename = "call", default)]
+    calls: Vec<RepresentativeCall>,
+}
+
+/// A call replayed before and after the upgrade.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+struct RepresentativeCall {
+    message: String,
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+impl fmt::Display for RepresentativeCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.message, self.args.join(", "))
+    }
+}
+
+/// The outcome of the dry-run of a call.
+#[derive(Clone, Debug, PartialEq)]
+enum Outcome {
+    Returned(String),
+    Reverted(String),
+    Failed(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Returned(value) => write!(f, "{}", value),
+            Outcome::Reverted(value) => write!(f, "reverted with {}", value),
+            Outcome::Failed(err) => write!(f, "failed with {}", err),
+        }
+    }
+}
+
+impl SimulateUpgradeCommand {
+    pub fn run(&self) -> Result<()> {
+        if self.extrinsic_opts.dry_run {
+            anyhow::bail!(
+                "`simulate-upgrade` submits the upgrade to the node, point `--url` to a fork of \
+                 the chain instead of passing `--dry-run`"
+            )
+        }
+        let (crate_metadata, new_project, _) =
+            load_metadata(self.extrinsic_opts.manifest_path.as_ref())?;
+        let old_project = match &self.old_metadata {
+            Some(path) => Some(load_metadata_file(path)?.1),
+            None => None,
+        };
+        let old_project = old_project.as_ref().unwrap_or(&new_project);
+        let old_transcoder = ContractMessageTranscoder::new(old_project);
+        let new_transcoder = ContractMessageTranscoder::new(&new_project);
+
+        let wasm_path = self.wasm_path.clone().unwrap_or(crate_metadata.dest_wasm);
+        let code = std::fs::read(&wasm_path)
+            .context(format!("Failed to read from {}", wasm_path.display()))?;
+        let calls = match &self.calls {
+            Some(path) => load_calls(path)?,
+            None => queries(old_project, &new_project),
+        };
+        let signer = self.extrinsic_opts.extrinsic_signer()?;
+        let url = self.extrinsic_opts.url()?;
+        async_std::task::block_on(self.ensure_fork(&url))?;
+
+        let layout_incompatibilities = async_std::task::block_on(self.storage_incompatibilities(
+            &url,
+            (&old_transcoder, old_project),
+            (&new_transcoder, &new_project),
+        ))?;
+        let before = async_std::task::block_on(self.dry_run(&calls, &signer, &old_transcoder))?;
+        let code_hash = wasm_code_hash(&code);
+        if async_std::task::block_on(self.is_uploaded(&code_hash))? {
+            name_value_println!("Reusing code", format!("{:?}", code_hash));
+        } else {
+            upload_code(&self.extrinsic_opts, &new_transcoder, code)?;
+        }
+        async_std::task::block_on(self.upgrade(&code_hash, &signer, &old_transcoder))?;
+        let after = async_std::task::block_on(self.dry_run(&calls, &signer, &new_transcoder))?;
+
+        println!();
+        for ((call, before), after) in calls.iter().zip(&before).zip(&after) {
+            name_value_println!("Call", call.to_string());
+            name_value_println!("Before", before.to_string());
+            name_value_println!("After", after.to_string());
+        }
+        let incompatibilities = incompatibilities(&calls, &before, &after);
+        println!();
+        if incompatibilities.is_empty() && layout_incompatibilities.is_empty() {
+            println!(
+                "{}",
+                format!(
+                    "No incompatibilities in {} calls and the storage layout",
+                    calls.len()
+                )
+                .green()
+            );
+            return Ok(());
+        }
+        for incompatibility in layout_incompatibilities.iter().chain(&incompatibilities) {
+            eprintln!("{} {}", "Incompatible".red().bold(), incompatibility);
+        }
+        anyhow::bail!(
+            "The upgrade changes the outcome of {} of {} calls and the decoding of {} storage \
+             cells",
+            incompatibilities.len(),
+            calls.len(),
+            layout_incompatibilities.len()
+        )
+    }
+
+    /// Fails unless the node at `url` runs a declared local fork of the chain, since the upgrade
+    /// is submitted for real.
+    async fn ensure_fork(&self, url: &url::Url) -> Result<()> {
+        if self.i_know_this_is_live {
+            log::warn!("Not checking that {} runs a fork of the chain", url);
+            return Ok(());
+        }
+        let profile = self.extrinsic_opts.network_profile()?;
+        if profile.map_or(false, |profile| profile.fork) {
+            return Ok(());
+        }
+        if is_loopback(url) {
+            let cli = WsClientBuilder::default().build(url.as_str()).await?;
+            let methods: RpcMethods = cli.request("rpc_methods", rpc_params![]).await?;
+            if methods
+                .methods
+                .iter()
+                .any(|method| method == "dev_newBlock")
+            {
+                return Ok(());
+            }
+        }
+        anyhow::bail!(
+            "{} is not a declared local fork of the chain and the upgrade would be submitted to \
+             the live chain. Set `fork = true` in the profile of the `--network`, run the fork \
+             locally with chopsticks, or pass `--i-know-this-is-live`",
+            url
+        )
+    }
+
+    /// Decodes the current storage of the contract with the `old` and the `new` layout and
+    /// describes the cells which are decoded differently.
+    async fn storage_incompatibilities(
+        &self,
+        url: &url::Url,
+        old: (&ContractMessageTranscoder<'_>, &InkProject),
+        new: (&ContractMessageTranscoder<'_>, &InkProject),
+    ) -> Result<Vec<String>> {
+        let cli = WsClientBuilder::default().build(url.as_str()).await?;
+        let block: Option<CodeHash> = cli.request("chain_getBlockHash", rpc_params![]).await?;
+        let block = block.context("The node returned no best block")?;
+        let (_, entries) = contract_storage(&cli, &self.contract, block).await?;
+
+        let before = decode_storage(old.0, old.1.layout(), &entries)
+            .context("Failed to decode the storage with the old layout, pass `--old-metadata`")?;
+        match decode_storage(new.0, new.1.layout(), &entries) {
+            Ok(after) => Ok(layout_incompatibilities(before, after)),
+            Err(err) => Ok(vec![format!(
+                "the storage cannot be decoded with the new layout: {}",
+                err
+            )]),
+        }
+    }
+
+    /// Returns `true` if the code with the `code_hash` is already stored on chain.
+    async fn is_uploaded(&self, code_hash: &CodeHash) -> Result<bool> {
+        let url = self.extrinsic_opts.url()?;
+        let api = ClientBuilder::new()
+            .set_url(url.as_str())
+            .build()
+            .await?
+            .to_runtime_api::<RuntimeApi>();
+        let owner_info = api
+            .client
+            .rpc()
+            .storage(&owner_info_key(code_hash), None)
+            .await?;
+        Ok(owner_info.is_some())
+    }
+
+    /// Dry-runs the `calls` against the current state of the contract.
+    async fn dry_run(
+        &self,
+        calls: &[RepresentativeCall],
+        signer: &ExtrinsicSigner,
+        transcoder: &ContractMessageTranscoder<'_>,
+    ) -> Result<Vec<Outcome>> {
+        let url = self.extrinsic_opts.url()?;
+        let cli = WsClientBuilder::default().build(url.as_str()).await?;
+        let mut outcomes = Vec::new();
+        for call in calls {
+            let data = match transcoder.encode(&call.message, &call.args) {
+                Ok(data) => data,
+                Err(err) => {
+                    outcomes.push(Outcome::Failed(format!("an encoding error: {}", err)));
+                    continue;
+                }
+            };
+            let result: ContractExecResult = cli
+                .request(
+                    "contracts_call",
+                    rpc_params![self.call_request(data, signer)],
+                )
+                .await?;
+            outcomes.push(outcome(&result, transcoder, &call.message));
+        }
+        Ok(outcomes)
+    }
+
+    /// Upgrades the contract to the code with the `code_hash`, by calling its `--message`.
+    async fn upgrade(
+        &self,
+        code_hash: &CodeHash,
+        signer: &ExtrinsicSigner,
+        transcoder: &ContractMessageTranscoder<'_>,
+    ) -> Result<()> {
+        let data = transcoder
+            .encode(&self.message, &[format!("{:?}", code_hash)])
+            .context(format!(
+                "Failed to encode `{}` with the code hash as its only argument, pass the \
+                 `--message` upgrading the contract",
+                self.message
+            ))?;
+        let url = self.extrinsic_opts.url()?;
+        let cli = WsClientBuilder::default().build(url.as_str()).await?;
+        let dry_run: ContractExecResult = cli
+            .request(
+                "contracts_call",
+                rpc_params![self.call_request(data.clone(), signer)],
+            )
+            .await?;
+        match outcome(&dry_run, transcoder, &self.message) {
+            Outcome::Returned(_) => (),
+            outcome => anyhow::bail!("The upgrade {}", outcome),
+        }
+
+        let api = ClientBuilder::new()
+            .set_url(url.as_str())
+            .build()
+            .await?
+            .to_runtime_api::<RuntimeApi>();
+        let call = api::contracts::calls::Call {
+            dest: self.contract.clone().into(),
+            value: 0,
+            gas_limit: self.gas_limit,
+            storage_deposit_limit: self.extrinsic_opts.storage_deposit_limit,
+            data,
+        };
+        let cost = Cost::new(0, &dry_run.storage_deposit);
+        let result = submit_extrinsic(&api, call, signer, &self.extrinsic_opts, cost).await?;
+        display_events(
+            &result,
+            transcoder,
+            api.client.metadata(),
+            &self.extrinsic_opts.verbosity()?,
+        )?;
+        name_value_println!("Upgraded", format!("{:?}", code_hash));
+        Ok(())
+    }
+
+    fn call_request(&self, data: Vec<u8>, signer: &ExtrinsicSigner) -> RpcCallRequest {
+        RpcCallRequest {
+            origin: signer.account_id().clone(),
+            dest: self.contract.clone(),
+            value: NumberOrHex::Hex(0.into()),
+            gas_limit: NumberOrHex::Number(self.gas_limit),
+            storage_deposit_limit: self
+                .extrinsic_opts
+                .storage_deposit_limit
+                .map(|limit| NumberOrHex::Hex(limit.into())),
+            input_data: Bytes(data),
+        }
+    }
+}
+
+/// The response of `rpc_methods`.
+#[derive(Debug, Deserialize)]
+struct RpcMethods {
+    methods: Vec<String>,
+}
+
+/// Returns `true` if the `url` points to the local machine.
+fn is_loopback(url: &url::Url) -> bool {
+    match url.host() {
+        Some(url::Host::Domain(domain)) => domain == "localhost",
+        Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    }
+}
+
+/// The storage of a contract decoded with a layout, see [`decode_storage`].
+type DecodedStorage = (Vec<StateField>, BTreeMap<String, String>);
+
+/// Describes the entries of the storage which are decoded differently with the new layout than
+/// `before` with the old one. Cells are matched by their key, so that renamed fields are still
+/// compatible.
+fn layout_incompatibilities(before: DecodedStorage, after: DecodedStorage) -> Vec<String> {
+    let by_key = |(fields, unresolved): DecodedStorage| {
+        fields
+            .into_iter()
+            .map(|field| (field.key, (Some(field.path), field.value)))
+            .chain(
+                unresolved
+                    .into_iter()
+                    .map(|(key, value)| (key, (None, value))),
+            )
+            .collect::<BTreeMap<_, _>>()
+    };
+    let before = by_key(before);
+    let mut after = by_key(after);
+
+    let mut incompatibilities = Vec::new();
+    for (key, (path, value)) in before {
+        let path = path.unwrap_or_else(|| key.clone());
+        match after.remove(&key) {
+            Some((_, new_value)) if new_value == value => (),
+            Some((_, new_value)) => incompatibilities.push(format!(
+                "storage `{}`: {} with the old layout, {} with the new one",
+                path, value, new_value
+            )),
+            None => incompatibilities.push(format!(
+                "storage `{}`: {} is no cell of the new layout",
+                path, value
+            )),
+        }
+    }
+    // the entries of cells of the old layout are unresolved with the new one if they moved
+    incompatibilities.extend(after.into_iter().filter_map(|(_, (path, value))| {
+        path.map(|path| {
+            format!(
+                "storage `{}`: {} was no cell of the old layout",
+                path, value
+            )
+        })
+    }));
+    incompatibilities
+}
+
+fn load_calls(path: &PathBuf) -> Result<Vec<RepresentativeCall>> {
+    let calls = std::fs::read_to_string(path)
+        .context(format!("Failed to read the calls from {}", path.display()))?;
+    let calls: CallsFile = toml::from_str(&calls)
+        .context(format!("Failed to parse the calls of {}", path.display()))?;
+    if calls.calls.is_empty() {
+        anyhow::bail!("{} contains no `[[call]]`", path.display())
+    }
+    Ok(calls.calls)
+}
+
+/// The messages of both the old and the new code which neither take arguments nor mutate the
+/// storage, i.e. the getters whose results should survive the upgrade.
+fn queries(old: &InkProject, new: &InkProject) -> Vec<RepresentativeCall> {
+    let is_query = |project: &InkProject, label: &str| {
+        project.spec().messages().iter().any(|message| {
+            message.label() == label && !message.mutates() && message.args().is_empty()
+        })
+    };
+    old.spec()
+        .messages()
+        .iter()
+        .map(|message| message.label().as_str())
+        .filter(|label| is_query(old, label) && is_query(new, label))
+        .map(|label| RepresentativeCall {
+            message: message_name(label).to_string(),
+            args: Vec::new(),
+        })
+        .collect()
+}
+
+/// The outcome of the dry-run `result` of the `message`, with its decoded return value.
+fn outcome(
+    result: &ContractExecResult,
+    transcoder: &ContractMessageTranscoder<'_>,
+    message: &str,
+) -> Outcome {
+    match &result.result {
+        Ok(ret_val) => {
+            let value = match transcoder.decode_return(message, &mut &ret_val.data.0[..]) {
+                Ok(value) => value.to_string(),
+                Err(err) => {
+                    return Outcome::Failed(format!("a return value decoding error: {}", err))
+                }
+            };
+            if ret_val.did_revert() {
+                Outcome::Reverted(value)
+            } else {
+                Outcome::Returned(value)
+            }
+        }
+        Err(err) => Outcome::Failed(format!("{:?}", err)),
+    }
+}
+
+/// Describes the calls whose outcome differs `before` and `after` the upgrade.
+fn incompatibilities(
+    calls: &[RepresentativeCall],
+    before: &[Outcome],
+    after: &[Outcome],
+) -> Vec<String> {
+    calls
+        .iter()
+        .zip(before.iter().zip(after))
+        .filter(|(_, (before, after))| before != after)
+        .map(|(call, (before, after))| {
+            let mut incompatibility = format!("{}: {} before, {} after", call, before, after);
+            if let (Outcome::Failed(err), false) = (after, matches!(before, Outcome::Failed(_))) {
+                if err.contains("ContractTrapped") {
+                    incompatibility
+                        .push_str(", the new code might not decode the storage of the contract");
+                }
+            }
+            incompatibility
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_outcomes_are_incompatible() {
+        let calls: CallsFile = toml::from_str(
+            r#"
+            [[call]]
+            message = "get"
+
+            [[call]]
+            message = "balance_of"
+            args = ["5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"]
+
+            [[call]]
+            message = "total_supply"
+            "#,
+        )
+        .unwrap();
+        let before = [
+            Outcome::Returned("true".into()),
+            Outcome::Returned("100".into()),
+            Outcome::Returned("1000".into()),
+        ];
+        let after = [
+            Outcome::Returned("true".into()),
+            Outcome::Failed(
+                "Module { index: 8, error: 11, message: Some(\"ContractTrapped\") }".into(),
+            ),
+            Outcome::Returned("0".into()),
+        ];
+
+        let incompatibilities = incompatibilities(&calls.calls, &before, &after);
+        assert_eq!(incompatibilities.len(), 2);
+        assert!(incompatibilities[0].starts_with(
+            "balance_of(5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY): 100 before, failed"
+        ));
+        assert!(incompatibilities[0].ends_with("might not decode the storage of the contract"));
+        assert_eq!(incompatibilities[1], "total_supply(): 1000 before, 0 after");
+    }
+
+    #[test]
+    fn cells_decoded_differently_are_incompatible() {
+        let field = |path: &str, key: &str, value: &str| StateField {
+            path: path.to_string(),
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        let before = (
+            vec![
+                field("total_supply", "0x00", "1000"),
+                field("owner", "0x01", "true"),
+                field("paused", "0x02", "false"),
+            ],
+            BTreeMap::new(),
+        );
+        let mut unresolved = BTreeMap::new();
+        unresolved.insert("0xaa".to_string(), "0x00".to_string());
+        let after = (
+            vec![
+                // renamed, but at the same key
+                field("supply", "0x00", "1000"),
+                // the type changed
+                field("owner", "0x01", "0x01"),
+            ],
+            unresolved,
+        );
+
+        assert_eq!(
+            layout_incompatibilities(before, after),
+            [
+                "storage `owner`: true with the old layout, 0x01 with the new one",
+                "storage `paused`: false is no cell of the new layout",
+            ]
+        );
+    }
+
+    #[test]
+    fn only_local_urls_are_loopback() {
+        let loopback = |url: &str| is_loopback(&url::Url::parse(url).unwrap());
+        assert!(loopback("ws://localhost:8000"));
+        assert!(loopback("ws://127.0.0.1:8000"));
+        assert!(loopback("ws://[::1]:8000"));
+        assert!(!loopback("wss://rococo-contracts-rpc.polkadot.io"));
+    }
+}
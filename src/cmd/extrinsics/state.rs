@@ -0,0 +1,632 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Exporting the storage of a contract and restoring it on a re-deployed contract.
+//!
+//! The storage of a contract lives in a child trie of `pallet-contracts`, under the `blake2_256`
+//! hashes of the keys of its cells. The keys of the cells are known from the storage layout of
+//! the metadata, the keys of the entries of mappings are hashed again by ink! and can only be
+//! exported raw.
+
+use super::{load_metadata, node_url, CodeHash, ContractAccount, ContractMessageTranscoder};
+use crate::{address_book::parse_address, name_value_println};
+use anyhow::{Context, Result};
+use ink_metadata::{
+    layout::{Layout, StructLayout},
+    InkProject,
+};
+use jsonrpsee::{
+    core::client::ClientT,
+    rpc_params,
+    ws_client::{WsClient, WsClientBuilder},
+};
+use scale::Decode;
+use scale_info::form::PortableForm;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    crypto::Ss58Codec,
+    hashing::{blake2_256, twox_128, twox_64},
+    Bytes,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+/// The prefix of the keys of the child tries of `pallet-contracts`.
+pub(super) const CHILD_STORAGE_PREFIX: &[u8] = b":child_storage:default:";
+
+/// The number of keys of the child trie requested at once, nodes limit the size of a page.
+const KEYS_PAGE_SIZE: u32 = 1000;
+
+/// Export the storage of a contract, or restore an exported storage on a re-deployed contract.
+#[derive(Debug, clap::Args)]
+#[clap(name = "state")]
+pub struct StateCommand {
+    #[clap(subcommand)]
+    action: StateAction,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum StateAction {
+    /// Export the storage of a contract to a JSON file, decoded with the storage layout of the
+    /// contract metadata
+    #[clap(name = "export")]
+    Export(ExportCommand),
+    /// Print the calls restoring an exported storage on a re-deployed contract
+    #[clap(name = "import")]
+    Import(ImportCommand),
+}
+
+#[derive(Debug, clap::Args)]
+struct ExportCommand {
+    /// The address of the contract, or its alias in the address book.
+    #[clap(long, env = "CONTRACT", parse(try_from_str = parse_address))]
+    contract: ContractAccount,
+    /// The file to write the state to.
+    #[clap(long, short, parse(from_os_str), default_value = "state.json")]
+    output: PathBuf,
+    /// Websockets url of a substrate node. Defaults to the url of the `--network`, or to
+    /// `ws://localhost:9944`.
+    #[clap(long, parse(try_from_str))]
+    url: Option<url::Url>,
+    /// The name of a network profile from the `cargo-contract` configuration file, or of a
+    /// profile with an `endpoint` in `[package.metadata.contract.profiles]` of the contract.
+    #[clap(long)]
+    network: Option<String>,
+    /// Path to the `Cargo.toml` of the contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+struct ImportCommand {
+    /// The state exported with `cargo contract state export`.
+    #[clap(parse(from_os_str), default_value = "state.json")]
+    state: PathBuf,
+    /// The address of the re-deployed contract, or its alias in the address book. Its storage
+    /// fields are restored by calling the message `set_<field>` with the exported value.
+    #[clap(
+        long,
+        env = "CONTRACT",
+        parse(try_from_str = parse_address),
+        required_unless_present = "constructor"
+    )]
+    contract: Option<ContractAccount>,
+    /// Instead of calling setters, restore the storage with this constructor of the re-deployed
+    /// contract, which takes the exported fields as arguments of the same name.
+    #[clap(long)]
+    constructor: Option<String>,
+    /// Path to the `Cargo.toml` of the re-deployed contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+}
+
+/// The exported storage of a contract.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContractState {
+    contract: String,
+    code_hash: String,
+    block: String,
+    /// The decoded cells of the storage layout.
+    fields: Vec<StateField>,
+    /// The entries of the child trie which are not a cell of the storage layout, e.g. of
+    /// mappings, by their hashed key.
+    unresolved: BTreeMap<String, String>,
+}
+
+/// The decoded value of a cell of the storage layout.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct StateField {
+    /// The path of the cell in the storage layout, e.g. `balances.len`.
+    pub(super) path: String,
+    pub(super) key: String,
+    /// The value in the syntax of the arguments of `cargo contract call`.
+    pub(super) value: String,
+}
+
+/// The prefix of `pallet_contracts::ContractInfo`, which is stable across runtimes.
+#[derive(Decode)]
+pub(super) struct ContractInfo {
+    pub(super) trie_id: Vec<u8>,
+    pub(super) code_hash: CodeHash,
+}
+
+impl StateCommand {
+    pub fn run(&self) -> Result<()> {
+        match &self.action {
+            StateAction::Export(export) => async_std::task::block_on(export.run()),
+            StateAction::Import(import) => import.run(),
+        }
+    }
+}
+
+impl ExportCommand {
+    async fn run(&self) -> Result<()> {
+        let (_, project, _) = load_metadata(self.manifest_path.as_ref())?;
+        let transcoder = ContractMessageTranscoder::new(&project);
+        let url = node_url(
+            self.url.as_ref(),
+            self.network.as_deref(),
+            self.manifest_path.as_ref(),
+        )?;
+        let cli = WsClientBuilder::default().build(url.as_str()).await?;
+        // all reads are pinned to one block, so that the exported state is consistent
+        let block: Option<CodeHash> = cli.request("chain_getBlockHash", rpc_params![]).await?;
+        let block = block.context("The node returned no best block")?;
+
+        let (info, entries) = contract_storage(&cli, &self.contract, block).await?;
+        let (fields, unresolved) = decode_storage(&transcoder, project.layout(), &entries)?;
+        let state = ContractState {
+            contract: self.contract.to_ss58check(),
+            code_hash: format!("{:?}", info.code_hash),
+            block: format!("{:?}", block),
+            unresolved,
+            fields,
+        };
+        let json = serde_json::to_string_pretty(&state)?;
+        std::fs::write(&self.output, json)
+            .context(format!("Failed to write {}", self.output.display()))?;
+
+        name_value_println!("Contract", state.contract);
+        name_value_println!("Block", state.block);
+        name_value_println!("Fields", state.fields.len().to_string());
+        name_value_println!("Unresolved", state.unresolved.len().to_string());
+        name_value_println!("Written to", self.output.display().to_string());
+        if !state.unresolved.is_empty() {
+            log::warn!(
+                "Entries of mappings cannot be decoded without their keys and are exported raw"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Reads the info of the `contract` and the entries of its child trie at the `block`, by their
+/// hashed key.
+pub(super) async fn contract_storage(
+    cli: &WsClient,
+    contract: &ContractAccount,
+    block: CodeHash,
+) -> Result<(ContractInfo, BTreeMap<[u8; 32], Vec<u8>>)> {
+    let info: Option<Bytes> = cli
+        .request(
+            "state_getStorage",
+            rpc_params![Bytes(contract_info_key(contract)), block],
+        )
+        .await?;
+    let info = info.context(format!("No contract at {}", contract.to_ss58check()))?;
+    let info =
+        ContractInfo::decode(&mut &info.0[..]).context("Failed to decode the contract info")?;
+
+    let child = Bytes([CHILD_STORAGE_PREFIX, &info.trie_id].concat());
+    let mut entries = BTreeMap::new();
+    let mut start_key: Option<Bytes> = None;
+    loop {
+        let keys: Vec<Bytes> = cli
+            .request(
+                "childstate_getKeysPaged",
+                rpc_params![&child, Bytes(Vec::new()), KEYS_PAGE_SIZE, &start_key, block],
+            )
+            .await?;
+        for key in &keys {
+            let value: Option<Bytes> = cli
+                .request("childstate_getStorage", rpc_params![&child, key, block])
+                .await?;
+            if let (Ok(key), Some(value)) = (<[u8; 32]>::try_from(&key.0[..]), value) {
+                entries.insert(key, value.0);
+            }
+        }
+        if keys.len() < KEYS_PAGE_SIZE as usize {
+            return Ok((info, entries));
+        }
+        start_key = keys.last().cloned();
+    }
+}
+
+/// Decodes the cells of the `layout` present in the `entries` of the child trie, and returns them
+/// along with the entries which are not a cell of the layout.
+pub(super) fn decode_storage(
+    transcoder: &ContractMessageTranscoder<'_>,
+    layout: &Layout<PortableForm>,
+    entries: &BTreeMap<[u8; 32], Vec<u8>>,
+) -> Result<(Vec<StateField>, BTreeMap<String, String>)> {
+    let mut walker = LayoutWalker::new(transcoder, entries);
+    walker.walk(layout, "", 0)?;
+    let unresolved = walker.unresolved();
+    Ok((walker.fields, unresolved))
+}
+
+/// The key of `Contracts::ContractInfoOf`, which uses the `Twox64Concat` hasher.
+pub(super) fn contract_info_key(contract: &ContractAccount) -> Vec<u8> {
+    let account: &[u8] = contract.as_ref();
+    [
+        &twox_128(b"Contracts")[..],
+        &twox_128(b"ContractInfoOf")[..],
+        &twox_64(account)[..],
+        account,
+    ]
+    .concat()
+}
+
+/// Walks the storage layout and decodes the value of each cell present in the child trie.
+struct LayoutWalker<'a> {
+    transcoder: &'a ContractMessageTranscoder<'a>,
+    /// The entries of the child trie by their hashed key.
+    entries: &'a BTreeMap<[u8; 32], Vec<u8>>,
+    resolved: BTreeSet<[u8; 32]>,
+    fields: Vec<StateField>,
+    /// The cells walked, whether they are present in the child trie or not.
+    cells: Vec<StorageCell>,
+}
+
+impl<'a> LayoutWalker<'a> {
+    fn new(
+        transcoder: &'a ContractMessageTranscoder<'a>,
+        entries: &'a BTreeMap<[u8; 32], Vec<u8>>,
+    ) -> Self {
+        Self {
+            transcoder,
+            entries,
+            resolved: BTreeSet::new(),
+            fields: Vec::new(),
+            cells: Vec::new(),
+        }
+    }
+
+    /// Walks the `layout` at `path`, with its keys advanced by `shift` cells for the elements of
+    /// arrays.
+    fn walk(&mut self, layout: &Layout<PortableForm>, path: &str, shift: u64) -> Result<()> {
+        match layout {
+            Layout::Cell(cell) => {
+                let key = advance(cell.key().key(), shift);
+                self.cells.push(StorageCell {
+                    path: path.to_string(),
+                    key,
+                    ty: cell.ty().id(),
+                });
+                if let Some(value) = self.entry(&key) {
+                    let value = match self.transcoder.decode(cell.ty().id(), &mut &value[..]) {
+                        Ok(value) => value.to_string(),
+                        Err(err) => {
+                            log::warn!("Failed to decode {}: {}", path, err);
+                            format!("0x{}", hex::encode(value))
+                        }
+                    };
+                    self.fields.push(StateField {
+                        path: path.to_string(),
+                        key: format!("0x{}", hex::encode(key)),
+                        value,
+                    });
+                }
+            }
+            Layout::Struct(layout) => self.walk_struct(layout, path, shift)?,
+            Layout::Enum(layout) => {
+                let key = advance(layout.dispatch_key().key(), shift);
+                if let Some(discriminant) = self.entry(&key).and_then(|value| value.first()) {
+                    let variant = layout
+                        .variants()
+                        .iter()
+                        .find(|(variant, _)| variant.value() == *discriminant as usize)
+                        .map(|(_, variant)| variant)
+                        .context(format!("Invalid discriminant {} of {}", discriminant, path))?;
+                    self.walk_struct(variant, &format!("{}.{}", path, discriminant), shift)?;
+                }
+            }
+            Layout::Array(layout) => {
+                for index in 0..layout.len() {
+                    let shift = shift + index as u64 * layout.cells_per_elem();
+                    self.walk(layout.layout(), &format!("{}[{}]", path, index), shift)?;
+                }
+            }
+            // the entries are stored under the hashes of their keys
+            Layout::Hash(_) => (),
+        }
+        Ok(())
+    }
+
+    fn walk_struct(
+        &mut self,
+        layout: &StructLayout<PortableForm>,
+        path: &str,
+        shift: u64,
+    ) -> Result<()> {
+        for (index, field) in layout.fields().iter().enumerate() {
+            let path = field_path(path, field.name(), index);
+            self.walk(field.layout(), &path, shift)?;
+        }
+        Ok(())
+    }
+
+    /// The value of the cell with the `key`, which is marked as resolved.
+    fn entry(&mut self, key: &[u8; 32]) -> Option<&'a Vec<u8>> {
+        let hashed = blake2_256(key);
+        let value = self.entries.get(&hashed)?;
+        self.resolved.insert(hashed);
+        Some(value)
+    }
+
+    /// The entries of the child trie which are not a cell of the layout, by their hashed key.
+    fn unresolved(&self) -> BTreeMap<String, String> {
+        self.entries
+            .iter()
+            .filter(|(key, _)| !self.resolved.contains(*key))
+            .map(|(key, value)| {
+                (
+                    format!("0x{}", hex::encode(key)),
+                    format!("0x{}", hex::encode(value)),
+                )
+            })
+            .collect()
+    }
+}
+
+/// A cell of the storage layout whose key does not depend on the values of the storage, i.e.
+/// which is not part of an enum or a mapping.
+#[derive(Debug, PartialEq)]
+pub(super) struct StorageCell {
+    pub(super) path: String,
+    pub(super) key: [u8; 32],
+    pub(super) ty: u32,
+}
+
+/// Returns the cells of the `layout` whose keys do not depend on the values of the storage.
+pub(super) fn storage_cells(
+    transcoder: &ContractMessageTranscoder<'_>,
+    layout: &Layout<PortableForm>,
+) -> Result<Vec<StorageCell>> {
+    // without entries, neither the variants of enums nor the entries of mappings are walked
+    let entries = BTreeMap::new();
+    let mut walker = LayoutWalker::new(transcoder, &entries);
+    walker.walk(layout, "", 0)?;
+    Ok(walker.cells)
+}
+
+/// The path of the field with the `name` or `index` of the struct at `path`.
+fn field_path(path: &str, name: Option<&String>, index: usize) -> String {
+    let name = name.cloned().unwrap_or_else(|| index.to_string());
+    match path {
+        "" => name,
+        path => format!("{}.{}", path, name),
+    }
+}
+
+/// Advances the storage `key` by `cells`, like ink! does for the cells of the elements of arrays.
+fn advance(key: &[u8; 32], cells: u64) -> [u8; 32] {
+    let mut key = *key;
+    let mut carry = cells as u128;
+    for byte in key.iter_mut() {
+        if carry == 0 {
+            break;
+        }
+        let sum = *byte as u128 + (carry & 0xff);
+        *byte = sum as u8;
+        carry = (carry >> 8) + (sum >> 8);
+    }
+    key
+}
+
+impl ImportCommand {
+    fn run(&self) -> Result<()> {
+        let state = std::fs::read_to_string(&self.state)
+            .context(format!("Failed to read {}", self.state.display()))?;
+        let state: ContractState = serde_json::from_str(&state).context(format!(
+            "Failed to parse the state of {}",
+            self.state.display()
+        ))?;
+        let (_, project, _) = load_metadata(self.manifest_path.as_ref())?;
+        if !state.unresolved.is_empty() {
+            log::warn!(
+                "{} entries of mappings are not restored, they have to be migrated by calls \
+                 which insert them with their keys",
+                state.unresolved.len()
+            );
+        }
+        let commands = match (&self.constructor, &self.contract) {
+            (Some(constructor), _) => vec![constructor_command(&project, constructor, &state)?],
+            (None, Some(contract)) => setter_commands(&project, contract, &state),
+            (None, None) => unreachable!("`--contract` is required without `--constructor`"),
+        };
+        for command in commands {
+            println!("{}", command);
+        }
+        Ok(())
+    }
+}
+
+/// The `cargo contract call` of the setter of each top level field of the `state`, as far as the
+/// `project` has a message `set_<field>` with a single argument.
+fn setter_commands(
+    project: &InkProject,
+    contract: &ContractAccount,
+    state: &ContractState,
+) -> Vec<String> {
+    let mut commands = Vec::new();
+    for field in state
+        .fields
+        .iter()
+        .filter(|field| !field.path.contains('.'))
+    {
+        let setter = format!("set_{}", field.path);
+        let exists = project
+            .spec()
+            .messages()
+            .iter()
+            .any(|message| message.label() == &setter && message.args().len() == 1);
+        if !exists {
+            log::warn!("No message `{}` to restore `{}`", setter, field.path);
+            continue;
+        }
+        commands.push(format!(
+            "cargo contract call --contract {} --message {} --args {}",
+            contract.to_ss58check(),
+            setter,
+            shell_quote(&field.value)
+        ));
+    }
+    commands
+}
+
+/// The `cargo contract instantiate` of the `constructor`, with the fields of the `state` of the
+/// same name as its arguments, along with the encoded call data.
+fn constructor_command(
+    project: &InkProject,
+    constructor: &str,
+    state: &ContractState,
+) -> Result<String> {
+    let spec = project
+        .spec()
+        .constructors()
+        .iter()
+        .find(|spec| spec.label() == constructor)
+        .context(format!("No constructor `{}` in the metadata", constructor))?;
+    let args = spec
+        .args()
+        .iter()
+        .map(|arg| {
+            state
+                .fields
+                .iter()
+                .find(|field| &field.path == arg.label())
+                .map(|field| field.value.clone())
+                .context(format!(
+                    "No field `{}` in the state for the argument of `{}`",
+                    arg.label(),
+                    constructor
+                ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let data = ContractMessageTranscoder::new(project).encode(constructor, &args)?;
+    let args = args
+        .iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok(format!(
+        "# call data 0x{}\ncargo contract instantiate --constructor {} --args {}",
+        hex::encode(data),
+        constructor,
+        args
+    ))
+}
+
+/// Quotes the `arg` for POSIX shells.
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    pub(super) fn project() -> InkProject {
+        serde_json::from_value(json!({
+            "spec": {
+                "constructors": [{
+                    "args": [{
+                        "label": "total_supply",
+                        "type": { "displayName": ["Balance"], "type": 0 },
+                    }],
+                    "docs": [],
+                    "label": "migrate",
+                    "payable": false,
+                    "selector": "0x9bae9d5e",
+                }],
+                "docs": [],
+                "events": [],
+                "messages": [{
+                    "args": [{ "label": "value", "type": { "displayName": ["bool"], "type": 1 } }],
+                    "docs": [],
+                    "label": "set_paused",
+                    "mutates": true,
+                    "payable": false,
+                    "returnType": null,
+                    "selector": "0x6a4b2b4c",
+                }],
+            },
+            "storage": { "struct": { "fields": [
+                {
+                    "layout": { "cell": {
+                        "key": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                        "ty": 0,
+                    } },
+                    "name": "total_supply",
+                },
+                {
+                    "layout": { "cell": {
+                        "key": "0x0100000000000000000000000000000000000000000000000000000000000000",
+                        "ty": 1,
+                    } },
+                    "name": "paused",
+                },
+            ] } },
+            "types": [
+                { "id": 0, "type": { "def": { "primitive": "u128" } } },
+                { "id": 1, "type": { "def": { "primitive": "bool" } } },
+            ],
+        }))
+        .expect("invalid metadata")
+    }
+
+    #[test]
+    fn exported_cells_are_restored() {
+        let project = project();
+        let transcoder = ContractMessageTranscoder::new(&project);
+        let mut entries = BTreeMap::new();
+        entries.insert(blake2_256(&[0; 32]), 1000u128.to_le_bytes().to_vec());
+        entries.insert(blake2_256(&advance(&[0; 32], 1)), vec![1]);
+        entries.insert([0xaa; 32], vec![42]);
+
+        let mut walker = LayoutWalker::new(&transcoder, &entries);
+        walker.walk(project.layout(), "", 0).unwrap();
+        let state = ContractState {
+            contract: String::new(),
+            code_hash: String::new(),
+            block: String::new(),
+            unresolved: walker.unresolved(),
+            fields: walker.fields,
+        };
+        let fields = state
+            .fields
+            .iter()
+            .map(|field| (field.path.as_str(), field.value.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(fields, [("total_supply", "1000"), ("paused", "true")]);
+        assert_eq!(state.unresolved.len(), 1);
+
+        let contract = ContractAccount::new([1; 32]);
+        let commands = setter_commands(&project, &contract, &state);
+        assert_eq!(commands.len(), 1);
+        assert!(commands[0].ends_with("--message set_paused --args 'true'"));
+        let command = constructor_command(&project, "migrate", &state).unwrap();
+        assert_eq!(
+            command,
+            "# call data 0x9bae9d5ee8030000000000000000000000000000\n\
+             cargo contract instantiate --constructor migrate --args '1000'"
+        );
+    }
+
+    #[test]
+    fn keys_are_advanced_with_carry() {
+        let mut key = [0; 32];
+        key[0] = 0xff;
+        let advanced = advance(&key, 2);
+        assert_eq!(advanced[..2], [0x01, 0x01]);
+        assert_eq!(shell_quote("\"it's\""), r#"'"it'\''s"'"#);
+    }
+}
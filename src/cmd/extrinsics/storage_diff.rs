@@ -0,0 +1,222 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The changes of the storage of a contract a call would make, decoded with the storage layout.
+//!
+//! The dry-run RPC of `pallet-contracts` does not report the storage a call would write, so the
+//! call is dry-run with `dev_dryRun` of a fork of the chain, e.g. by chopsticks, which reports the
+//! storage changes of the extrinsic without including it in a block.
+
+use super::{
+    state::{contract_storage, decode_storage, StateField, CHILD_STORAGE_PREFIX},
+    CodeHash, ContractAccount, ContractMessageTranscoder,
+};
+use crate::name_value_println;
+use anyhow::{Context, Result};
+use ink_metadata::layout::Layout;
+use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use scale_info::form::PortableForm;
+use serde::Deserialize;
+use sp_core::{crypto::Ss58Codec, Bytes};
+use std::collections::BTreeMap;
+
+/// The result of `dev_dryRun` of a fork.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DryRun {
+    /// The changed keys of the storage, with their new value or `None` if they were deleted.
+    /// Keys of child tries are prefixed with the key of their child trie.
+    storage_diff: Vec<(Bytes, Option<Bytes>)>,
+}
+
+/// A change of a cell of the storage layout, or of an entry of a mapping by its hashed key.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct StorageChange {
+    pub(super) path: String,
+    pub(super) before: Option<String>,
+    pub(super) after: Option<String>,
+}
+
+/// Returns the changes between the storage `before` and `after`, ordered by their path.
+pub(super) fn diff(
+    before: (Vec<StateField>, BTreeMap<String, String>),
+    after: (Vec<StateField>, BTreeMap<String, String>),
+) -> Vec<StorageChange> {
+    let by_path = |(fields, unresolved): (Vec<StateField>, BTreeMap<String, String>)| {
+        fields
+            .into_iter()
+            .map(|field| (field.path, field.value))
+            .chain(unresolved)
+            .collect::<BTreeMap<_, _>>()
+    };
+    let mut before = by_path(before);
+    let after = by_path(after);
+
+    let mut changes = Vec::new();
+    for (path, value) in after {
+        match before.remove(&path) {
+            Some(previous) if previous == value => (),
+            previous => changes.push(StorageChange {
+                path,
+                before: previous,
+                after: Some(value),
+            }),
+        }
+    }
+    changes.extend(before.into_iter().map(|(path, value)| StorageChange {
+        path,
+        before: Some(value),
+        after: None,
+    }));
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+/// Returns the storage `entries` of the contract with the `trie_id` with the changes of the
+/// `storage_diff` of a dry-run applied.
+fn apply_storage_diff(
+    entries: &BTreeMap<[u8; 32], Vec<u8>>,
+    trie_id: &[u8],
+    storage_diff: Vec<(Bytes, Option<Bytes>)>,
+) -> BTreeMap<[u8; 32], Vec<u8>> {
+    let child = [CHILD_STORAGE_PREFIX, trie_id].concat();
+    let mut entries = entries.clone();
+    for (key, value) in storage_diff {
+        let key = match key
+            .0
+            .strip_prefix(&child[..])
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+        {
+            Some(key) => key,
+            None => continue,
+        };
+        match value {
+            Some(value) => entries.insert(key, value.0),
+            None => entries.remove(&key),
+        };
+    }
+    entries
+}
+
+/// Prints the changes of the storage of the `contract` the `call` signed by the `origin` would
+/// make, dry-run by the fork of the chain at `url`. Nothing is submitted.
+pub(super) async fn display_storage_diff(
+    url: &url::Url,
+    contract: &ContractAccount,
+    call: Vec<u8>,
+    origin: &ContractAccount,
+    transcoder: &ContractMessageTranscoder<'_>,
+    layout: &Layout<PortableForm>,
+) -> Result<()> {
+    let cli = WsClientBuilder::default().build(url.as_str()).await?;
+    let head: CodeHash = cli.request("chain_getBlockHash", rpc_params![]).await?;
+    let (info, before) = contract_storage(&cli, contract, head).await?;
+    let params = serde_json::json!({
+        "extrinsic": { "call": Bytes(call), "address": origin.to_ss58check() },
+        "at": head,
+    });
+    let dry_run: DryRun = cli
+        .request("dev_dryRun", rpc_params![params])
+        .await
+        .context(format!(
+            "Failed to dry-run the call with `dev_dryRun`, which is only supported by forks of \
+             the chain. Point `--url` at a fork, e.g. `npx @acala-network/chopsticks --endpoint \
+             <url>`, to review the storage changes of the call at {}",
+            url
+        ))?;
+    let after = apply_storage_diff(&before, &info.trie_id, dry_run.storage_diff);
+    let changes = diff(
+        decode_storage(transcoder, layout, &before)?,
+        decode_storage(transcoder, layout, &after)?,
+    );
+
+    name_value_println!("Storage", format!("{} changes", changes.len()));
+    for change in changes {
+        let (kind, value) = match (change.before, change.after) {
+            (None, Some(after)) => ("Created", after),
+            (Some(before), Some(after)) => ("Written", format!("{} -> {}", before, after)),
+            (Some(before), None) => ("Deleted", before),
+            (None, None) => continue,
+        };
+        name_value_println!(kind, format!("{}: {}", change.path, value));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_diff_of_the_contract_is_applied() {
+        let trie_id = [7u8; 4];
+        let child_key = |key: [u8; 32]| Bytes([CHILD_STORAGE_PREFIX, &trie_id, &key].concat());
+        let mut entries = BTreeMap::new();
+        entries.insert([1; 32], vec![1]);
+        entries.insert([2; 32], vec![2]);
+
+        let storage_diff = vec![
+            (child_key([1; 32]), Some(Bytes(vec![10]))),
+            (child_key([2; 32]), None),
+            (child_key([3; 32]), Some(Bytes(vec![3]))),
+            // the account of the signer, which pays the fee
+            (Bytes(vec![0xaa; 48]), Some(Bytes(vec![0]))),
+        ];
+        let after = apply_storage_diff(&entries, &trie_id, storage_diff);
+        assert_eq!(
+            after.into_iter().collect::<Vec<_>>(),
+            [([1; 32], vec![10]), ([3; 32], vec![3])]
+        );
+    }
+
+    fn field(path: &str, value: &str) -> StateField {
+        StateField {
+            path: path.into(),
+            key: String::new(),
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn changes_are_diffed_by_path() {
+        let mut mapping = BTreeMap::new();
+        mapping.insert("0xaa".to_string(), "0x01".to_string());
+        let before = (
+            vec![field("total_supply", "1000"), field("paused", "false")],
+            mapping,
+        );
+        let mut mapping = BTreeMap::new();
+        mapping.insert("0xbb".to_string(), "0x02".to_string());
+        let after = (
+            vec![field("total_supply", "1000"), field("paused", "true")],
+            mapping,
+        );
+
+        let change = |path: &str, before: Option<&str>, after: Option<&str>| StorageChange {
+            path: path.into(),
+            before: before.map(Into::into),
+            after: after.map(Into::into),
+        };
+        assert_eq!(
+            diff(before, after),
+            [
+                change("0xaa", Some("0x01"), None),
+                change("0xbb", None, Some("0x02")),
+                change("paused", Some("false"), Some("true")),
+            ]
+        );
+    }
+}
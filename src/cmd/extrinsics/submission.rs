@@ -0,0 +1,210 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    encode_call,
+    fees::{ensure_affordable, estimate_fee, Cost},
+    nonce::{is_stale_nonce, Nonce, NonceLock},
+    signed_extensions, sudo, wait_for_success_and_handle_error, warn_if_exceeds_block_length,
+    ExtrinsicOpts, ExtrinsicSigner, RuntimeApi, RuntimeDispatchError, RuntimeEvent, SignedExtra,
+};
+use crate::{name_value_println, timings};
+use anyhow::Result;
+use scale::{Encode, Output};
+use std::marker::PhantomData;
+use subxt::{Call, DefaultConfig, SubmittableExtrinsic, TransactionEvents};
+
+/// How often an extrinsic is resubmitted after its nonce turned out to be stale.
+const MAX_RESUBMISSIONS: usize = 3;
+
+/// Submits the `call` and waits for its success, wrapped into `Sudo::sudo` if `--sudo` is set.
+/// The calls of `pallet-contracts` are then dispatched with the signer as origin.
+///
+/// Before submitting, the free balance of the signer is checked to cover the estimated fee and
+/// the `cost` of the call.
+pub(super) async fn submit_extrinsic<'a, C>(
+    api: &'a RuntimeApi,
+    call: C,
+    signer: &ExtrinsicSigner,
+    opts: &ExtrinsicOpts,
+    cost: Cost,
+) -> Result<TransactionEvents<'a, DefaultConfig, RuntimeEvent>>
+where
+    C: Call + Send + Sync,
+{
+    warn_if_exceeds_block_length(api.client.metadata(), call.encoded_size());
+    if opts.sudo {
+        let metadata = api.client.metadata();
+        let mut encoded = encode_call(metadata, &call)?;
+        // `pallet-contracts` rejects the root origin, only accounts can upload and call contracts
+        if C::PALLET == "Contracts" {
+            encoded = sudo::dispatch_as(metadata, signer.account_id(), encoded)?;
+        }
+        return sudo::submit(api, EncodedCall(encoded), signer, opts, cost).await;
+    }
+    // The call is consumed by signing it, so every submission gets a copy.
+    let encoded = call.encode();
+    let copy = || Ok(CallCopy::<C>(encoded.clone(), PhantomData));
+    submit(api, copy, signer, opts, cost).await
+}
+
+/// Submits the call created by `make_call` with the nonce selected by `--nonce`, resubmitting it
+/// with a new nonce if the nonce was used by another extrinsic in the meantime.
+pub(super) async fn submit<'a, C, F>(
+    api: &'a RuntimeApi,
+    make_call: F,
+    signer: &ExtrinsicSigner,
+    opts: &ExtrinsicOpts,
+    cost: Cost,
+) -> Result<TransactionEvents<'a, DefaultConfig, RuntimeEvent>>
+where
+    C: Call + Send + Sync,
+    F: Fn() -> Result<C>,
+{
+    let mut signer = signer.clone();
+    signer.set_signed_extensions(Some(signed_extensions::configure(
+        api.client.metadata(),
+        &opts.signed_extensions()?,
+        opts.fee_asset,
+    )?));
+    let signer = &signer;
+    let fee_estimation = timings::phase("Fee estimation");
+    let fee = match opts.fee_asset {
+        // the fee is paid in the asset, only the value and deposit in the native token
+        Some(asset_id) => {
+            name_value_println!("Fee", format!("paid in asset {}", asset_id));
+            None
+        }
+        None => Some(estimate_fee(api, make_call()?, signer).await?),
+    };
+    ensure_affordable(api, signer.account_id(), fee, &cost).await?;
+    drop(fee_estimation);
+
+    let account = signer.account_id();
+    let lock = match opts.nonce {
+        Some(Nonce::AutoIncrement) => Some(NonceLock::acquire(api.client.genesis(), account)?),
+        _ => None,
+    };
+    let mut attempt = 0;
+    loop {
+        let mut signer = signer.clone();
+        let nonce = match (opts.nonce, &lock) {
+            (Some(Nonce::Fixed(nonce)), _) => Some(nonce),
+            (_, Some(lock)) => {
+                let chain_next = api.client.rpc().system_account_next_index(account).await?;
+                Some(lock.next(chain_next)?)
+            }
+            _ => None,
+        };
+        if let Some(nonce) = nonce {
+            signer.set_nonce(nonce);
+        }
+
+        let submitted =
+            SubmittableExtrinsic::<_, SignedExtra, _, RuntimeDispatchError, RuntimeEvent>::new(
+                &api.client,
+                make_call()?,
+            )
+            .sign_and_submit_then_watch(&signer)
+            .await;
+        signer.take_failure()?;
+        match submitted {
+            Ok(tx_progress) => {
+                if let (Some(lock), Some(nonce)) = (&lock, nonce) {
+                    lock.commit(nonce)?;
+                }
+                // Other processes may submit while this one waits for the inclusion.
+                drop(lock);
+                return wait_for_success_and_handle_error(&api.client, tx_progress, opts).await;
+            }
+            Err(err)
+                if is_stale_nonce(&err)
+                    && !matches!(opts.nonce, Some(Nonce::Fixed(_)))
+                    && attempt < MAX_RESUBMISSIONS =>
+            {
+                attempt += 1;
+                log::warn!("Stale nonce {:?}, resubmitting: {}", nonce, err);
+                if let (Some(lock), Some(nonce)) = (&lock, nonce) {
+                    lock.commit(nonce)?;
+                }
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// A call which is encoded already, e.g. with the pallet and call index of the runtime.
+#[derive(Clone, Debug)]
+pub(super) struct EncodedCall(pub(super) Vec<u8>);
+
+impl Encode for EncodedCall {
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.0)
+    }
+}
+
+/// A copy of the encoded call `C`, which is submitted as the `C` it was encoded from.
+struct CallCopy<C>(Vec<u8>, PhantomData<fn() -> C>);
+
+impl<C> Encode for CallCopy<C> {
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.0)
+    }
+}
+
+impl<C: Call> Call for CallCopy<C> {
+    const PALLET: &'static str = C::PALLET;
+    const FUNCTION: &'static str = C::FUNCTION;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Encode)]
+    struct Remark {
+        remark: Vec<u8>,
+    }
+
+    impl Call for Remark {
+        const PALLET: &'static str = "System";
+        const FUNCTION: &'static str = "remark";
+    }
+
+    #[test]
+    fn copied_call_is_submitted_as_the_original() {
+        let call = Remark {
+            remark: vec![1, 2, 3],
+        };
+        let copy = CallCopy::<Remark>(call.encode(), PhantomData);
+        assert_eq!(copy.encode(), call.encode());
+        assert_eq!(
+            (
+                <CallCopy<Remark> as Call>::PALLET,
+                <CallCopy<Remark> as Call>::FUNCTION
+            ),
+            ("System", "remark")
+        );
+    }
+}
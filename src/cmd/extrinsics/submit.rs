@@ -0,0 +1,170 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    dispatch_error, display_raw_events, load_metadata, node_url, progress,
+    ContractMessageTranscoder, RuntimeApi, RuntimeEvent,
+};
+use crate::{name_value_println, timings, Verbosity, VerbosityFlags};
+use anyhow::{Context, Result};
+use jsonrpsee::{core::client::SubscriptionClientT, rpc_params, ws_client::WsClientBuilder};
+use scale::Encode;
+use sp_core::{hashing::blake2_256, Bytes, H256};
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+use subxt::{rpc::SubstrateTransactionStatus, ClientBuilder, DefaultConfig, Phase};
+
+/// Submit an extrinsic which was encoded and signed elsewhere, e.g. on an air-gapped device.
+///
+/// The extrinsic is broadcast as it is, signed or unsigned, and the events of the contract are
+/// decoded with the metadata of the contract once it is included in a block.
+#[derive(Debug, clap::Args)]
+#[clap(name = "submit")]
+pub struct SubmitCommand {
+    /// The file with the hex encoded extrinsic, as produced e.g. by polkadot-js, or `-` to read
+    /// it from stdin.
+    #[clap(parse(from_os_str))]
+    extrinsic: PathBuf,
+    /// Path to the `Cargo.toml` of the contract, whose metadata decodes the contract events.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// Websockets url of a substrate node. Defaults to the url of the `--network`, or to
+    /// `ws://localhost:9944`.
+    #[clap(long, parse(try_from_str))]
+    url: Option<url::Url>,
+    /// The name of a network profile from the `cargo-contract` configuration file, or of a
+    /// profile with an `endpoint` in `[package.metadata.contract.profiles]` of the contract.
+    #[clap(long)]
+    network: Option<String>,
+    /// Wait for the finalization of the block which includes the extrinsic, instead of only for
+    /// its inclusion.
+    #[clap(long)]
+    finalized: bool,
+    #[clap(flatten)]
+    verbosity: VerbosityFlags,
+}
+
+impl SubmitCommand {
+    pub fn run(&self) -> Result<()> {
+        let (_, contract_metadata, _) = load_metadata(self.manifest_path.as_ref())?;
+        let transcoder = ContractMessageTranscoder::new(&contract_metadata);
+        let extrinsic = read_extrinsic(&self.extrinsic)?;
+        let verbosity = Verbosity::try_from(&self.verbosity)?;
+        let url = node_url(
+            self.url.as_ref(),
+            self.network.as_deref(),
+            self.manifest_path.as_ref(),
+        )?;
+
+        async_std::task::block_on(async {
+            let api = ClientBuilder::new()
+                .set_url(url.as_str())
+                .build()
+                .await?
+                .to_runtime_api::<RuntimeApi>();
+            let cli = WsClientBuilder::default().build(url.as_str()).await?;
+            let extrinsic_hash = H256(blake2_256(&extrinsic));
+            name_value_println!("Extrinsic", format!("{:?}", extrinsic_hash));
+
+            let statuses = cli
+                .subscribe::<SubstrateTransactionStatus<H256, H256>>(
+                    "author_submitAndWatchExtrinsic",
+                    rpc_params![Bytes(extrinsic.clone())],
+                    "author_unwatchExtrinsic",
+                )
+                .await?;
+            let _inclusion = timings::phase("Waiting for inclusion");
+            let block_hash =
+                progress::follow_statuses(&api.client, statuses, self.finalized, verbosity).await?;
+
+            let block = api
+                .client
+                .rpc()
+                .block(Some(block_hash))
+                .await?
+                .context(format!("The node does not know the block {:?}", block_hash))?;
+            let index = block
+                .block
+                .extrinsics
+                .iter()
+                .position(|included| H256(blake2_256(&included.encode())) == extrinsic_hash)
+                .context("The block does not include the extrinsic")?;
+            let events =
+                subxt::events::at::<DefaultConfig, RuntimeEvent>(&api.client, block_hash).await?;
+            let extrinsic_events = || {
+                events.iter_raw().filter(|event| match event {
+                    Ok(event) => {
+                        matches!(event.phase, Phase::ApplyExtrinsic(i) if i as usize == index)
+                    }
+                    Err(_) => true,
+                })
+            };
+
+            let metadata = api.client.metadata();
+            if let Some(error) = dispatch_error::extrinsic_error(metadata, extrinsic_events())? {
+                return Err(error);
+            }
+            tracing::info!(
+                target: "cargo_contract::events::extrinsic",
+                extrinsic_hash = ?extrinsic_hash,
+                block_hash = ?block_hash,
+            );
+            display_raw_events(extrinsic_events(), &transcoder, metadata, &verbosity)
+        })
+    }
+}
+
+/// Reads the hex encoded extrinsic from the file at `path`, or from stdin for `-`.
+fn read_extrinsic(path: &Path) -> Result<Vec<u8>> {
+    let mut hex = String::new();
+    if path == Path::new("-") {
+        std::io::stdin().read_to_string(&mut hex)?;
+    } else {
+        hex = std::fs::read_to_string(path).context(format!(
+            "Failed to read the extrinsic from {}",
+            path.display()
+        ))?;
+    }
+    parse_extrinsic(&hex)
+}
+
+fn parse_extrinsic(hex: &str) -> Result<Vec<u8>> {
+    let hex = hex.trim();
+    let extrinsic = hex::decode(hex.strip_prefix("0x").unwrap_or(hex))
+        .context("The extrinsic is not hex encoded")?;
+    if extrinsic.is_empty() {
+        anyhow::bail!("The extrinsic is empty")
+    }
+    Ok(extrinsic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extrinsics_are_parsed_from_hex() {
+        assert_eq!(
+            parse_extrinsic("0x2d02840a\n").unwrap(),
+            [0x2d, 0x02, 0x84, 0x0a]
+        );
+        assert_eq!(parse_extrinsic("2d02").unwrap(), [0x2d, 0x02]);
+        assert!(parse_extrinsic("0xzz").is_err());
+        assert!(parse_extrinsic(" ").is_err());
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    call_index,
+    dispatch_error::describe,
+    fees::Cost,
+    submission::{self, EncodedCall},
+    ContractAccount, ExtrinsicOpts, ExtrinsicSigner, RuntimeApi, RuntimeDispatchError,
+    RuntimeEvent,
+};
+use anyhow::{Context, Result};
+use scale::{Decode, Encode};
+use sp_core::{crypto::Ss58Codec, hashing::twox_128, storage::StorageKey};
+use subxt::{Call, DefaultConfig, Metadata, TransactionEvents};
+
+/// The index of the `Signed` variant of the `RawOrigin` of the system pallet.
+const SIGNED_ORIGIN: u8 = 1;
+
+/// Submits the encoded `call` wrapped into `Sudo::sudo` and fails if the wrapped call failed.
+pub(super) async fn submit<'a>(
+    api: &'a RuntimeApi,
+    call: EncodedCall,
+    signer: &ExtrinsicSigner,
+    opts: &ExtrinsicOpts,
+    cost: Cost,
+) -> Result<TransactionEvents<'a, DefaultConfig, RuntimeEvent>> {
+    ensure_sudo_key(api, signer.account_id()).await?;
+    let sudo = || Ok(SudoCall(call.clone()));
+    let result = submission::submit(api, sudo, signer, opts, cost).await?;
+    sudo_result(api.client.metadata(), &result)?;
+    Ok(result)
+}
+
+/// Wraps the encoded `call` into `Utility::dispatch_as` with the signed origin of the `account`,
+/// for calls which root can not dispatch itself, e.g. the ones of `pallet-contracts`.
+pub(super) fn dispatch_as(
+    metadata: &Metadata,
+    account: &ContractAccount,
+    call: Vec<u8>,
+) -> Result<Vec<u8>> {
+    let (utility, dispatch_as) = call_index(metadata, "Utility", "dispatch_as")
+        .context("`--sudo` requires `Utility::dispatch_as` to dispatch as the signer")?;
+    // the variants of the `OriginCaller` of the runtime are indexed by the index of their pallet
+    let system = metadata
+        .runtime_metadata()
+        .pallets
+        .iter()
+        .find(|pallet| pallet.name == "System")
+        .context("The runtime has no System pallet")?
+        .index;
+    let mut wrapped = vec![utility, dispatch_as, system, SIGNED_ORIGIN];
+    account.encode_to(&mut wrapped);
+    wrapped.extend(call);
+    Ok(wrapped)
+}
+
+/// Fails if the `account` is not the sudo key of the chain, as the call would fail for sure.
+async fn ensure_sudo_key(api: &RuntimeApi, account: &ContractAccount) -> Result<()> {
+    let key = StorageKey([twox_128(b"Sudo"), twox_128(b"Key")].concat());
+    let sudo_key = api
+        .client
+        .rpc()
+        .storage(&key, None)
+        .await?
+        .context("The chain has no sudo key")?;
+    let sudo_key = ContractAccount::decode(&mut &sudo_key.0[..])?;
+    if sudo_key != *account {
+        anyhow::bail!(
+            "The signer {} is not the sudo key {}",
+            account.to_ss58check(),
+            sudo_key.to_ss58check()
+        )
+    }
+    Ok(())
+}
+
+/// The extrinsic of `Sudo::sudo` succeeds even if the wrapped call fails, its result is part of
+/// the `Sudid` event.
+fn sudo_result(
+    metadata: &Metadata,
+    result: &TransactionEvents<'_, DefaultConfig, RuntimeEvent>,
+) -> Result<()> {
+    for event in result.iter_raw() {
+        let event = event?;
+        if event.pallet == "Sudo" && event.variant == "Sudid" {
+            let sudo_result = Result::<(), RuntimeDispatchError>::decode(&mut &event.data[..])?;
+            return sudo_result.map_err(|err| {
+                anyhow::anyhow!("The sudo call failed: {}", describe(metadata, &err))
+            });
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Encode)]
+struct SudoCall(EncodedCall);
+
+impl Call for SudoCall {
+    const PALLET: &'static str = "Sudo";
+    const FUNCTION: &'static str = "sudo";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapped_call_is_encoded_without_length_prefix() {
+        let call = SudoCall(EncodedCall(vec![8, 7, 0x10]));
+        assert_eq!(call.encode(), vec![8, 7, 0x10]);
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    apps::display_apps_link,
+    call::{ContractExecResult, RpcCallRequest},
+    display_contract_exec_result, display_events, display_explorer_links, encode_call,
+    fees::Cost,
+    load_metadata,
+    runtime_api::api,
+    submission::submit_extrinsic,
+    Balance, ContractAccount, ContractMessageTranscoder, ExplorerPage, ExtrinsicOpts,
+    ExtrinsicSigner, RuntimeApi, EXEC_RESULT_MAX_KEY_COL_WIDTH,
+};
+use crate::{address_book::parse_address, name_value_println, util::decode_hex};
+use anyhow::{Context, Result};
+use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use pallet_contracts_primitives::StorageDeposit;
+use scale::Encode;
+use sp_core::{crypto::Ss58Codec, Bytes};
+use subxt::{rpc::NumberOrHex, ClientBuilder};
+
+/// Terminate a contract, sending its remaining balance to a beneficiary.
+///
+/// Contracts can only be terminated by themselves, so this calls a message of the contract which
+/// calls `ink_env::terminate_contract` with the beneficiary passed as its only argument.
+#[derive(Debug, clap::Args)]
+#[clap(name = "terminate")]
+pub struct TerminateCommand {
+    /// The address of the contract to terminate, or its alias in the address book.
+    #[clap(long, env = "CONTRACT", parse(try_from_str = parse_address))]
+    contract: ContractAccount,
+    /// The account receiving the remaining balance of the contract, or its alias in the address
+    /// book.
+    #[clap(long, parse(try_from_str = parse_address))]
+    beneficiary: ContractAccount,
+    /// The name of the message terminating the contract.
+    #[clap(long, short, default_value = "terminate")]
+    message: String,
+    /// The hex encoded selector of the message terminating the contract, instead of its name.
+    /// Does not require the metadata of the contract.
+    #[clap(long, parse(try_from_str = parse_selector), conflicts_with = "message")]
+    selector: Option<[u8; 4]>,
+    #[clap(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// Maximum amount of gas to be used for this command.
+    #[clap(name = "gas", long, default_value = "50000000000")]
+    gas_limit: u64,
+}
+
+fn parse_selector(input: &str) -> Result<[u8; 4]> {
+    decode_hex(input)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("A selector should be 4 bytes in length"))
+}
+
+impl TerminateCommand {
+    pub fn run(&self) -> Result<()> {
+        let data = self.call_data()?;
+        let signer = self.extrinsic_opts.extrinsic_signer()?;
+        let url = self.extrinsic_opts.url()?;
+
+        async_std::task::block_on(async {
+            let cli = WsClientBuilder::default().build(url.as_str()).await?;
+            let call_request = RpcCallRequest {
+                origin: signer.account_id().clone(),
+                dest: self.contract.clone(),
+                value: NumberOrHex::Hex(0.into()),
+                gas_limit: NumberOrHex::Number(self.gas_limit),
+                storage_deposit_limit: self
+                    .extrinsic_opts
+                    .storage_deposit_limit
+                    .map(|limit| NumberOrHex::Hex(limit.into())),
+                input_data: Bytes(data.clone()),
+            };
+            let dry_run: ContractExecResult = cli
+                .request("contracts_call", rpc_params![&call_request])
+                .await?;
+            let refund = storage_deposit_refund(&dry_run.storage_deposit);
+
+            if self.extrinsic_opts.dry_run {
+                let result = match &dry_run.result {
+                    Ok(ret_val) if ret_val.did_revert() => "Reverted".to_string(),
+                    Ok(_) => "Success!".to_string(),
+                    Err(err) => format!("Error: {:?}", err),
+                };
+                name_value_println!("Result", result, EXEC_RESULT_MAX_KEY_COL_WIDTH);
+                name_value_println!(
+                    "Refund",
+                    format!("{}", refund),
+                    EXEC_RESULT_MAX_KEY_COL_WIDTH
+                );
+                return display_contract_exec_result(&dry_run);
+            }
+            if let Err(err) = &dry_run.result {
+                anyhow::bail!("The dry-run of the termination failed: {:?}", err)
+            }
+            if self.extrinsic_opts.apps_link {
+                let api = ClientBuilder::new()
+                    .set_url(url.as_str())
+                    .build()
+                    .await?
+                    .to_runtime_api::<RuntimeApi>();
+                let metadata = api.client.metadata();
+                let call = encode_call(metadata, &self.contract_call(data))?;
+                return display_apps_link(&self.extrinsic_opts, metadata, call);
+            }
+            self.terminate(&url, data, refund, &signer).await
+        })
+    }
+
+    /// The input of the message, which takes the beneficiary as its only argument.
+    fn call_data(&self) -> Result<Vec<u8>> {
+        if let Some(selector) = self.selector {
+            let mut data = selector.to_vec();
+            self.beneficiary.encode_to(&mut data);
+            return Ok(data);
+        }
+        let (_, contract_metadata, _) = load_metadata(self.extrinsic_opts.manifest_path.as_ref())?;
+        let transcoder = ContractMessageTranscoder::new(&contract_metadata);
+        transcoder
+            .encode(&self.message, &[self.beneficiary.to_ss58check()])
+            .context(format!(
+                "Failed to encode `{}` with the beneficiary as its only argument, pass the \
+                 `--message` or `--selector` terminating the contract",
+                self.message
+            ))
+    }
+
+    fn contract_call(&self, data: Vec<u8>) -> api::contracts::calls::Call {
+        api::contracts::calls::Call {
+            dest: self.contract.clone().into(),
+            value: 0,
+            gas_limit: self.gas_limit,
+            storage_deposit_limit: self.extrinsic_opts.storage_deposit_limit,
+            data,
+        }
+    }
+
+    async fn terminate(
+        &self,
+        url: &url::Url,
+        data: Vec<u8>,
+        refund: Balance,
+        signer: &ExtrinsicSigner,
+    ) -> Result<()> {
+        let api = ClientBuilder::new()
+            .set_url(url.as_str())
+            .build()
+            .await?
+            .to_runtime_api::<RuntimeApi>();
+        let call = self.contract_call(data);
+        let result =
+            submit_extrinsic(&api, call, signer, &self.extrinsic_opts, Cost::default()).await?;
+
+        let terminated = result
+            .find_first::<api::contracts::events::Terminated>()?
+            .context(
+                "The contract was not terminated, the message did not call `terminate_contract`",
+            )?;
+        name_value_println!("Terminated", terminated.contract.to_ss58check());
+        name_value_println!("Beneficiary", terminated.beneficiary.to_ss58check());
+        name_value_println!("Refund", format!("{}", refund));
+
+        if let Ok((_, contract_metadata, _)) =
+            load_metadata(self.extrinsic_opts.manifest_path.as_ref())
+        {
+            let transcoder = ContractMessageTranscoder::new(&contract_metadata);
+            display_events(
+                &result,
+                &transcoder,
+                api.client.metadata(),
+                &self.extrinsic_opts.verbosity()?,
+            )?;
+        }
+        display_explorer_links(
+            &self.extrinsic_opts,
+            &[
+                ExplorerPage::Extrinsic(result.extrinsic_hash()),
+                ExplorerPage::Block(result.block_hash()),
+            ],
+        )
+    }
+}
+
+/// The storage deposit refunded by the termination, as estimated by a dry-run.
+fn storage_deposit_refund(storage_deposit: &StorageDeposit<Balance>) -> Balance {
+    match storage_deposit {
+        StorageDeposit::Refund(refund) => *refund,
+        StorageDeposit::Charge(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selector_is_parsed() {
+        assert_eq!(
+            parse_selector("0x4d8b7c2e").unwrap(),
+            [0x4d, 0x8b, 0x7c, 0x2e]
+        );
+        assert!(parse_selector("0x4d8b7c").is_err());
+        assert_eq!(storage_deposit_refund(&StorageDeposit::Refund(7)), 7);
+    }
+}
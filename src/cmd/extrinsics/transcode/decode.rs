@@ -15,7 +15,7 @@
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::{
-    env_types::EnvTypesTranscoder,
+    env_types::{self, CustomTypeTranscoder, EnvTypesTranscoder},
     scon::{Map, Tuple, Value},
     CompositeTypeFields,
 };
@@ -193,7 +193,7 @@ impl<'a> Decoder<'a> {
             TypeDefPrimitive::U32 => decode_uint::<u32>(input),
             TypeDefPrimitive::U64 => decode_uint::<u64>(input),
             TypeDefPrimitive::U128 => decode_uint::<u128>(input),
-            TypeDefPrimitive::U256 => Err(anyhow::anyhow!("U256 currently not supported")),
+            TypeDefPrimitive::U256 => env_types::U256.decode_value(input),
             TypeDefPrimitive::I8 => decode_int::<i8>(input),
             TypeDefPrimitive::I16 => decode_int::<i16>(input),
             TypeDefPrimitive::I32 => decode_int::<i32>(input),
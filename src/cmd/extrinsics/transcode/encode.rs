@@ -14,7 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use super::{env_types::EnvTypesTranscoder, scon::Value, CompositeTypeFields};
+use super::{
+    env_types::{self, CustomTypeTranscoder, EnvTypesTranscoder},
+    scon::{whole_digits, NumberError, Value},
+    CompositeTypeFields,
+};
 use anyhow::Result;
 use itertools::Itertools;
 use scale::{Compact, Encode, Output};
@@ -25,7 +29,6 @@ use scale_info::{
 };
 use std::{
     convert::{TryFrom, TryInto},
-    error::Error,
     fmt::Debug,
     str::FromStr,
 };
@@ -189,7 +192,18 @@ impl<'a> Encoder<'a> {
             .variants()
             .iter()
             .find_position(|v| v.name() == &variant_ident)
-            .ok_or_else(|| anyhow::anyhow!("No variant '{}' found", variant_ident))?;
+            .ok_or_else(|| {
+                let variants = variant_def
+                    .variants()
+                    .iter()
+                    .map(|variant| variant.name().as_str())
+                    .collect::<Vec<_>>();
+                anyhow::anyhow!(
+                    "No variant '{}' found, expected one of: {}",
+                    variant_ident,
+                    variants.join(", ")
+                )
+            })?;
 
         let index: u8 = index
             .try_into()
@@ -261,7 +275,10 @@ impl<'a> Encoder<'a> {
             TypeDefPrimitive::U32 => encode_uint::<u32, O>(value, "u32", output),
             TypeDefPrimitive::U64 => encode_uint::<u64, O>(value, "u64", output),
             TypeDefPrimitive::U128 => encode_uint::<u128, O>(value, "u128", output),
-            TypeDefPrimitive::U256 => Err(anyhow::anyhow!("U256 currently not supported")),
+            TypeDefPrimitive::U256 => {
+                output.write(&env_types::U256.encode_value(value)?);
+                Ok(())
+            }
             TypeDefPrimitive::I8 => encode_int::<i8, O>(value, "i8", output),
             TypeDefPrimitive::I16 => encode_int::<i16, O>(value, "i16", output),
             TypeDefPrimitive::I32 => encode_int::<i32, O>(value, "i32", output),
@@ -362,19 +379,12 @@ impl<'a> Encoder<'a> {
 fn uint_from_value<T>(value: &Value, expected: &str) -> Result<T>
 where
     T: TryFrom<u128> + FromStr,
-    <T as TryFrom<u128>>::Error: Error + Send + Sync + 'static,
-    <T as FromStr>::Err: Error + Send + Sync + 'static,
 {
     match value {
-        Value::UInt(i) => {
-            let uint = (*i).try_into()?;
-            Ok(uint)
-        }
-        Value::String(s) => {
-            let sanitized = s.replace(&['_', ','][..], "");
-            let uint = T::from_str(&sanitized)?;
-            Ok(uint)
-        }
+        Value::UInt(i) => (*i)
+            .try_into()
+            .map_err(|_| NumberError::OutOfRange(i.to_string(), expected.into()).into()),
+        Value::String(s) => number_from_str(s, expected),
         _ => Err(anyhow::anyhow!(
             "Expected a {} or a String value, got {}",
             expected,
@@ -383,11 +393,23 @@ where
     }
 }
 
+/// Parses a number of the `expected` type from a string, which may contain `_` and `,`
+/// separators and an exponent, e.g. `"1,000"` or `"1.5e12"`.
+fn number_from_str<T: FromStr>(s: &str, expected: &str) -> Result<T> {
+    let sanitized = s.replace(&['_', ','][..], "");
+    let (sign, number) = match sanitized.strip_prefix('-') {
+        Some(number) => ("-", number),
+        None => ("", sanitized.strip_prefix('+').unwrap_or(&sanitized)),
+    };
+    let digits = whole_digits(number, 0)?;
+    format!("{}{}", sign, digits)
+        .parse::<T>()
+        .map_err(|_| NumberError::OutOfRange(s.into(), expected.into()).into())
+}
+
 fn encode_uint<T, O>(value: &Value, expected: &str, output: &mut O) -> Result<()>
 where
     T: TryFrom<u128> + FromStr + Encode,
-    <T as TryFrom<u128>>::Error: Error + Send + Sync + 'static,
-    <T as FromStr>::Err: Error + Send + Sync + 'static,
     O: Output,
 {
     let uint: T = uint_from_value(value, expected)?;
@@ -398,31 +420,21 @@ where
 fn encode_int<T, O>(value: &Value, expected: &str, output: &mut O) -> Result<()>
 where
     T: TryFrom<i128> + TryFrom<u128> + FromStr + Encode,
-    <T as TryFrom<i128>>::Error: Error + Send + Sync + 'static,
-    <T as TryFrom<u128>>::Error: Error + Send + Sync + 'static,
-    <T as FromStr>::Err: Error + Send + Sync + 'static,
     O: Output,
 {
-    let int = match value {
-        Value::Int(i) => {
-            let i: T = (*i).try_into()?;
-            Ok(i)
-        }
-        Value::UInt(u) => {
-            let i: T = (*u).try_into()?;
-            Ok(i)
+    let out_of_range = |value: String| NumberError::OutOfRange(value, expected.into());
+    let int: T = match value {
+        Value::Int(i) => (*i).try_into().map_err(|_| out_of_range(i.to_string()))?,
+        Value::UInt(u) => (*u).try_into().map_err(|_| out_of_range(u.to_string()))?,
+        Value::String(s) => number_from_str(s, expected)?,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Expected a {} or a String value, got {}",
+                expected,
+                value
+            ))
         }
-        Value::String(s) => {
-            let sanitized = s.replace(&['_', ','][..], "");
-            let i = T::from_str(&sanitized)?;
-            Ok(i)
-        }
-        _ => Err(anyhow::anyhow!(
-            "Expected a {} or a String value, got {}",
-            expected,
-            value
-        )),
-    }?;
+    };
     int.encode_to(output);
     Ok(())
 }
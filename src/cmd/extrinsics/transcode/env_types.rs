@@ -14,11 +14,15 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use super::scon::Value;
-use anyhow::Result;
+use super::scon::{whole_digits, NumberError, Value};
+use crate::address_book::AddressBook;
+use anyhow::{Context, Result};
 use scale::{Decode, Encode, Output};
 use scale_info::{form::PortableForm, IntoPortable, Path, TypeInfo};
-use sp_core::crypto::{AccountId32, Ss58Codec};
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    hashing::blake2_256,
+};
 use std::{boxed::Box, collections::HashMap, convert::TryFrom, str::FromStr};
 
 /// Provides custom encoding and decoding for predefined environment types.
@@ -110,18 +114,28 @@ pub type TypesByPath = HashMap<PathKey, u32>;
 
 pub struct AccountId;
 
+/// Returns the account id of an alias of the address book, which may be used instead of an SS58
+/// address.
+fn resolve_alias(alias: &str) -> Option<AccountId32> {
+    AddressBook::load().ok()?.resolve(alias).ok()
+}
+
 impl CustomTypeTranscoder for AccountId {
     fn aliases(&self) -> &[&'static str] {
         &["AccountId"]
     }
     fn encode_value(&self, value: &Value) -> Result<Vec<u8>> {
         let account_id = match value {
-            Value::Literal(literal) => AccountId32::from_str(literal).map_err(|e| {
-                anyhow::anyhow!("Error parsing AccountId from literal `{}`: {}", literal, e)
-            })?,
-            Value::String(string) => AccountId32::from_str(string).map_err(|e| {
-                anyhow::anyhow!("Error parsing AccountId from string '{}': {}", string, e)
-            })?,
+            Value::Literal(literal) => AccountId32::from_str(literal)
+                .or_else(|e| resolve_alias(literal).ok_or(e))
+                .map_err(|e| {
+                    anyhow::anyhow!("Error parsing AccountId from literal `{}`: {}", literal, e)
+                })?,
+            Value::String(string) => AccountId32::from_str(string)
+                .or_else(|e| resolve_alias(string).ok_or(e))
+                .map_err(|e| {
+                    anyhow::anyhow!("Error parsing AccountId from string '{}': {}", string, e)
+                })?,
             Value::Bytes(bytes) => AccountId32::try_from(bytes.bytes()).map_err(|_| {
                 anyhow::anyhow!("Error converting bytes `{:?}` to AccountId", bytes)
             })?,
@@ -139,3 +153,99 @@ impl CustomTypeTranscoder for AccountId {
         Ok(Value::Literal(account_id.to_ss58check()))
     }
 }
+
+pub struct Hash;
+
+impl Hash {
+    /// Returns the code hash of a `.wasm` file, or the one in the metadata of a `.contract` or
+    /// `.json` file, e.g. to pass the code hash of a contract to a delegate-call proxy.
+    fn code_hash_of_file(path: &str) -> Result<[u8; 32]> {
+        let path = std::path::Path::new(path);
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("wasm") => {
+                let code =
+                    std::fs::read(path).context(format!("Failed to read {}", path.display()))?;
+                Ok(blake2_256(&code))
+            }
+            Some("contract") | Some("json") => {
+                let file = std::fs::File::open(path)
+                    .context(format!("Failed to open {}", path.display()))?;
+                let metadata: serde_json::Value = serde_json::from_reader(file)
+                    .context(format!("Failed to parse {}", path.display()))?;
+                let hash = metadata["source"]["hash"]
+                    .as_str()
+                    .context(format!("No `source.hash` in {}", path.display()))?;
+                let bytes = crate::util::decode_hex(hash)?;
+                bytes.try_into().map_err(|_| {
+                    anyhow::anyhow!("The code hash in {} is not 32 bytes", path.display())
+                })
+            }
+            _ => {
+                anyhow::bail!(
+                    "Expected a hex encoded hash or the path to a `.wasm`, `.contract` or `.json` \
+                     file, got '{}'",
+                    path.display()
+                )
+            }
+        }
+    }
+}
+
+impl CustomTypeTranscoder for Hash {
+    fn aliases(&self) -> &[&'static str] {
+        &["Hash"]
+    }
+
+    fn encode_value(&self, value: &Value) -> Result<Vec<u8>> {
+        let hash: [u8; 32] = match value {
+            Value::Bytes(bytes) => bytes
+                .bytes()
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Expected 32 bytes for a Hash, got {:?}", bytes))?,
+            Value::String(path) => Self::code_hash_of_file(path)?,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Expected hex encoded bytes or a file path string for a Hash"
+                ))
+            }
+        };
+        Ok(hash.encode())
+    }
+
+    fn decode_value(&self, input: &mut &[u8]) -> Result<Value> {
+        let hash = <[u8; 32]>::decode(input)?;
+        Ok(Value::Bytes(hash.to_vec().into()))
+    }
+}
+
+/// A 256 bit unsigned integer, given like the other integers, e.g. `1_000`, `1.5e30` or quoted
+/// `"1e40"` beyond the range of a `u128`.
+pub struct U256;
+
+impl CustomTypeTranscoder for U256 {
+    fn aliases(&self) -> &[&'static str] {
+        &["U256"]
+    }
+
+    fn encode_value(&self, value: &Value) -> Result<Vec<u8>> {
+        let uint = match value {
+            Value::UInt(uint) => sp_core::U256::from(*uint),
+            Value::String(number) | Value::Literal(number) => {
+                let digits = whole_digits(&number.replace(&['_', ','][..], ""), 0)?;
+                sp_core::U256::from_dec_str(&digits)
+                    .map_err(|_| NumberError::OutOfRange(number.clone(), "U256".into()))?
+            }
+            _ => anyhow::bail!("Expected a U256 or a String value, got {}", value),
+        };
+        Ok(uint.encode())
+    }
+
+    fn decode_value(&self, input: &mut &[u8]) -> Result<Value> {
+        let uint = sp_core::U256::decode(input)?;
+        if uint <= sp_core::U256::from(u128::MAX) {
+            Ok(Value::UInt(uint.as_u128()))
+        } else {
+            Ok(Value::Literal(uint.to_string()))
+        }
+    }
+}
@@ -88,37 +88,59 @@ mod scon;
 mod transcoder;
 
 pub use self::{
-    scon::{Map, Value},
+    scon::{is_fixed_point, whole_digits, Map, Value},
     transcoder::{Transcoder, TranscoderBuilder},
 };
 
-use anyhow::Result;
-use ink_metadata::{ConstructorSpec, InkProject, MessageSpec};
+use anyhow::{Context, Result};
+use ink_metadata::{ConstructorSpec, InkProject, MessageParamSpec, MessageSpec};
 use scale::Input;
 use scale_info::{
     form::{Form, PortableForm},
-    Field,
+    Field, TypeDef, TypeDefPrimitive,
 };
-use std::fmt::Debug;
+use std::{collections::BTreeMap, fmt::Debug};
 
 /// Encode strings to SCALE encoded smart contract calls.
 /// Decode SCALE encoded smart contract events and return values into `Value` objects.
 pub struct ContractMessageTranscoder<'a> {
     metadata: &'a InkProject,
     transcoder: Transcoder<'a>,
+    /// The decimals of the native token of the chain, for balances given as fixed-point numbers.
+    token_decimals: Option<u32>,
+    /// The type ids of the return types of the constructors, by their labels.
+    constructor_return_types: BTreeMap<String, u32>,
 }
 
 impl<'a> ContractMessageTranscoder<'a> {
     pub fn new(metadata: &'a InkProject) -> Self {
         let transcoder = TranscoderBuilder::new(metadata.registry())
             .register_custom_type::<<ink_env::DefaultEnvironment as ink_env::Environment>::AccountId, _>(env_types::AccountId)
+            .register_custom_type::<<ink_env::DefaultEnvironment as ink_env::Environment>::Hash, _>(env_types::Hash)
+            .register_custom_type::<sp_core::U256, _>(env_types::U256)
             .done();
         Self {
             metadata,
             transcoder,
+            token_decimals: None,
+            constructor_return_types: BTreeMap::new(),
         }
     }
 
+    /// Accepts `Balance` arguments as fixed-point numbers of the token with the `decimals`, e.g.
+    /// `1.5` for `1_500_000_000_000` with 12 decimals.
+    pub fn with_token_decimals(mut self, decimals: u32) -> Self {
+        self.token_decimals = Some(decimals);
+        self
+    }
+
+    /// Decodes the data returned by constructors with the type ids of their return types, which
+    /// only ink! 4 metadata specifies and which are not part of the `V3` metadata.
+    pub fn with_constructor_return_types(mut self, types: BTreeMap<String, u32>) -> Self {
+        self.constructor_return_types = types;
+        self
+    }
+
     pub fn encode<I, S>(&self, name: &str, args: I) -> Result<Vec<u8>>
     where
         I: IntoIterator<Item = S>,
@@ -146,13 +168,70 @@ impl<'a> ContractMessageTranscoder<'a> {
 
         let mut encoded = selector.to_bytes().to_vec();
         for (spec, arg) in spec_args.iter().zip(args) {
-            let value = scon::parse_value(arg.as_ref())?;
+            let value = self
+                .parse_arg(spec, arg.as_ref())
+                .context(format!("Invalid argument `{}`", spec.label()))?;
             self.transcoder
                 .encode(spec.ty().ty().id(), &value, &mut encoded)?;
         }
         Ok(encoded)
     }
 
+    /// Returns the arguments of the constructor or message with the `name`.
+    pub fn args(&self, name: &str) -> Option<&[MessageParamSpec<PortableForm>]> {
+        self.find_constructor_spec(name)
+            .map(|constructor| constructor.args())
+            .or_else(|| self.find_message_spec(name).map(|message| message.args()))
+    }
+
+    /// Parses the argument `arg` of the `spec`.
+    ///
+    /// `@<path>` reads the argument from the file at `path`: the raw content for bytes, i.e. a
+    /// `Vec<u8>` or `[u8; N]`, otherwise the SCON text. Besides `0x` hex, bytes are accepted as
+    /// base64 with a `base64:` prefix. A `Balance` is accepted as fixed-point number of the token,
+    /// see [`Self::with_token_decimals`].
+    fn parse_arg(&self, spec: &MessageParamSpec<PortableForm>, arg: &str) -> Result<Value> {
+        let display_name = spec.ty().display_name().segments().last();
+        let is_balance = display_name.map(String::as_str) == Some("Balance");
+        if is_balance && scon::is_fixed_point(arg) {
+            let decimals = self.token_decimals.ok_or_else(|| {
+                anyhow::anyhow!("The decimals of the token are unknown, give the smallest unit")
+            })?;
+            return Ok(Value::String(scon::whole_digits(arg, decimals)?));
+        }
+        let is_bytes = self.is_bytes(spec.ty().ty().id());
+        if let Some(path) = arg.strip_prefix('@') {
+            if is_bytes {
+                let bytes = std::fs::read(path).context(format!("Failed to read {}", path))?;
+                return Ok(Value::Bytes(bytes.into()));
+            }
+            let text = std::fs::read_to_string(path).context(format!("Failed to read {}", path))?;
+            return scon::parse_value(text.trim());
+        }
+        if let Some(encoded) = arg.strip_prefix("base64:") {
+            if !is_bytes {
+                anyhow::bail!("Only bytes can be given as base64, not `{}`", arg)
+            }
+            let bytes = base64::decode(encoded).context(format!("Invalid base64 `{}`", encoded))?;
+            return Ok(Value::Bytes(bytes.into()));
+        }
+        scon::parse_value(arg)
+    }
+
+    /// Returns whether the type with the `type_id` is a sequence or array of bytes.
+    fn is_bytes(&self, type_id: u32) -> bool {
+        let registry = self.metadata.registry();
+        let elem = match registry.resolve(type_id).map(|ty| ty.type_def()) {
+            Some(TypeDef::Sequence(sequence)) => sequence.type_param(),
+            Some(TypeDef::Array(array)) => array.type_param(),
+            _ => return false,
+        };
+        matches!(
+            registry.resolve(elem.id()).map(|ty| ty.type_def()),
+            Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+        )
+    }
+
     fn constructors(&self) -> impl Iterator<Item = &ConstructorSpec<PortableForm>> {
         self.metadata.spec().constructors().iter()
     }
@@ -198,6 +277,11 @@ impl<'a> ContractMessageTranscoder<'a> {
         Ok(Value::Map(map))
     }
 
+    /// Decodes a value of the type `type_id` of the contract metadata, e.g. of a storage cell.
+    pub fn decode(&self, type_id: u32, data: &mut &[u8]) -> Result<Value> {
+        self.transcoder.decode(type_id, data)
+    }
+
     pub fn decode_return(&self, name: &str, data: &mut &[u8]) -> Result<Value> {
         let msg_spec = self
             .find_message_spec(name)
@@ -208,6 +292,19 @@ impl<'a> ContractMessageTranscoder<'a> {
             Ok(Value::Unit)
         }
     }
+
+    /// Decodes the data returned by the constructor with the `name`, e.g. the error of a reverted
+    /// fallible constructor. Returns `None` if the return type of the constructor is unknown.
+    pub fn decode_constructor_return(&self, name: &str, data: &mut &[u8]) -> Result<Option<Value>> {
+        let label = match self.find_constructor_spec(name) {
+            Some(constructor) => constructor.label(),
+            None => anyhow::bail!("Failed to find constructor spec with name '{}'", name),
+        };
+        match self.constructor_return_types.get(label) {
+            Some(ty) => self.transcoder.decode(*ty, data).map(Some),
+            None => Ok(None),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -305,6 +402,12 @@ mod tests {
             pub fn set_account_id(&self, account_id: AccountId) {
                 let _ = account_id;
             }
+
+            /// Dummy setter which receives bytes.
+            #[ink(message)]
+            pub fn set_data(&self, data: Vec<u8>) {
+                let _ = data;
+            }
         }
     }
 
@@ -333,6 +436,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn decode_constructor_return_with_known_return_type() -> Result<()> {
+        let metadata = generate_metadata();
+        let bool_ty = metadata
+            .spec()
+            .messages()
+            .iter()
+            .find(|message| message.label() == "get")
+            .and_then(|message| message.return_type().opt_type())
+            .expect("`get` returns a bool")
+            .ty()
+            .id();
+        let transcoder = ContractMessageTranscoder::new(&metadata);
+        assert_eq!(
+            transcoder.decode_constructor_return("new", &mut &[1u8][..])?,
+            None
+        );
+
+        let transcoder = transcoder
+            .with_constructor_return_types(BTreeMap::from([("new".to_string(), bool_ty)]));
+        assert_eq!(
+            transcoder.decode_constructor_return("new", &mut &[1u8][..])?,
+            Some(Value::Bool(true))
+        );
+        assert!(transcoder
+            .decode_constructor_return("unknown", &mut &[][..])
+            .is_err());
+        Ok(())
+    }
+
     #[test]
     fn encode_account_id_custom_ss58_encoding() -> Result<()> {
         let metadata = generate_metadata();
@@ -354,6 +487,30 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn encode_bytes_from_file_or_base64() -> Result<()> {
+        let metadata = generate_metadata();
+        let transcoder = ContractMessageTranscoder::new(&metadata);
+        let expected = vec![0xDEu8, 0xAD, 0xBE, 0xEF].encode();
+
+        let file = tempfile::NamedTempFile::new()?;
+        std::fs::write(file.path(), [0xDE, 0xAD, 0xBE, 0xEF])?;
+        let from_file = format!("@{}", file.path().display());
+        for arg in [
+            from_file.as_str(),
+            "base64:3q2+7w==",
+            "0xDEADBEEF",
+            "[222, 173, 190, 239]",
+        ] {
+            let encoded = transcoder.encode("set_data", &[arg])?;
+            assert_eq!(expected, &encoded[4..], "{}", arg);
+        }
+        // without the prefix, digits are not mistaken for base64
+        assert!(transcoder.encode("set_data", &["1234"]).is_err());
+        assert!(transcoder.encode("set_data", &["base64:!"]).is_err());
+        Ok(())
+    }
+
     #[test]
     fn decode_primitive_return() -> Result<()> {
         let metadata = generate_metadata();
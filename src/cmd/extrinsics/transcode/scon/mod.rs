@@ -17,6 +17,7 @@
 //! SCALE Object Notation (SCON)
 
 mod display;
+mod number;
 mod parse;
 
 use indexmap::IndexMap;
@@ -28,7 +29,10 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-pub use self::parse::parse_value;
+pub use self::{
+    number::{is_fixed_point, whole_digits, NumberError},
+    parse::parse_value,
+};
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Value {
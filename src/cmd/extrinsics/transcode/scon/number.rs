@@ -0,0 +1,109 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Unsigned decimal numbers with `_` separators, a fraction and an exponent, e.g. `1_000`,
+//! `1.5e12` or `2E18`.
+
+/// The most digits of a number, more than those of `U256::MAX`.
+const MAX_DIGITS: usize = 80;
+
+/// The reason why a number is invalid, with the number.
+#[derive(Debug, thiserror::Error)]
+pub enum NumberError {
+    #[error("`{0}` is not a number")]
+    NotANumber(String),
+    #[error("`{0}` is not a whole number")]
+    NotWhole(String),
+    #[error("`{0}` has more than {} digits", MAX_DIGITS)]
+    TooManyDigits(String),
+    #[error("`{0}` is out of the range of {1}")]
+    OutOfRange(String, String),
+}
+
+/// Returns the digits of the whole number `input` multiplied by `10^scale`, without leading
+/// zeros, failing if the `input` is not a number or the result is not a whole number.
+pub fn whole_digits(input: &str, scale: u32) -> Result<String, NumberError> {
+    let number = input.replace('_', "");
+    let (mantissa, exponent) = match number.split_once(|c| c == 'e' || c == 'E') {
+        Some((mantissa, exponent)) => {
+            let exponent = exponent.strip_prefix('+').unwrap_or(exponent);
+            let exponent = exponent
+                .parse::<i64>()
+                .map_err(|_| NumberError::NotANumber(input.into()))?;
+            (mantissa, exponent)
+        }
+        None => (number.as_str(), 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let is_digits = |digits: &str| digits.chars().all(|c| c.is_ascii_digit());
+    if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+        return Err(NumberError::NotANumber(input.into()));
+    }
+
+    let digits = format!("{}{}", whole, fraction);
+    let shift = exponent
+        .saturating_add(i64::from(scale))
+        .saturating_sub(fraction.len() as i64);
+    let digits = if shift >= 0 {
+        if shift.saturating_add(digits.len() as i64) > MAX_DIGITS as i64 {
+            return Err(NumberError::TooManyDigits(input.into()));
+        }
+        format!("{}{}", digits, "0".repeat(shift as usize))
+    } else {
+        let cut = digits.len().saturating_sub(shift.unsigned_abs() as usize);
+        if digits[cut..].chars().any(|c| c != '0') {
+            return Err(NumberError::NotWhole(input.into()));
+        }
+        digits[..cut].to_string()
+    };
+    let digits = digits.trim_start_matches('0');
+    Ok(if digits.is_empty() { "0" } else { digits }.to_string())
+}
+
+/// Returns whether the `input` is a fixed-point number without an exponent, e.g. `1.5`.
+pub fn is_fixed_point(input: &str) -> bool {
+    match input.split_once('.') {
+        Some((whole, fraction)) => [whole, fraction].iter().all(|digits| {
+            digits.starts_with(|c: char| c.is_ascii_digit())
+                && digits.chars().all(|c| c.is_ascii_digit() || c == '_')
+        }),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_are_scaled_to_whole_digits() {
+        assert_eq!(whole_digits("1_000_000e6", 0).unwrap(), "1000000000000");
+        assert_eq!(whole_digits("1.5E12", 0).unwrap(), "1500000000000");
+        assert_eq!(whole_digits("2.50", 1).unwrap(), "25");
+        assert_eq!(whole_digits("0.000_1", 4).unwrap(), "1");
+        assert_eq!(whole_digits("120e-1", 0).unwrap(), "12");
+        assert_eq!(whole_digits("000", 0).unwrap(), "0");
+
+        assert!(whole_digits("1.55e1", 0).is_err());
+        assert!(whole_digits("1e100", 0).is_err());
+        assert!(whole_digits("-1", 0).is_err());
+        assert!(whole_digits("e6", 0).is_err());
+
+        assert!(is_fixed_point("1_000.25"));
+        assert!(!is_fixed_point("1.5e12"));
+        assert!(!is_fixed_point("1000"));
+    }
+}
@@ -14,23 +14,73 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use super::{Bytes, Map, Tuple, Value};
+use super::{whole_digits, Bytes, Map, NumberError, Tuple, Value};
 use escape8259::unescape;
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_while1},
-    character::complete::{alphanumeric1, anychar, char, digit1, hex_digit1, multispace0},
-    multi::{many0, separated_list0},
+    character::complete::{alphanumeric1, anychar, char, digit1, hex_digit1, multispace0, one_of},
+    combinator::recognize,
+    multi::{many0, separated_list0, separated_list1},
     sequence::{delimited, pair, separated_pair, tuple},
     AsChar, IResult, Parser,
 };
-use nom_supreme::{error::ErrorTree, ParserExt};
+use nom_supreme::{
+    error::{ErrorTree, GenericErrorTree},
+    ParserExt,
+};
 
 /// Attempt to parse a SCON value
 pub fn parse_value(input: &str) -> anyhow::Result<Value> {
-    let (_, value) =
-        scon_value(input).map_err(|err| anyhow::anyhow!("Error parsing Value: {}", err))?;
-    Ok(value)
+    match scon_value.all_consuming().parse(input) {
+        Ok((_, value)) => Ok(value),
+        Err(nom::Err::Error(err)) | Err(nom::Err::Failure(err)) => Err(parse_error(input, &err)),
+        Err(nom::Err::Incomplete(_)) => Err(anyhow::anyhow!("Incomplete value `{}`", input)),
+    }
+}
+
+/// Returns an error pointing at the position in the `input` where parsing got the furthest.
+fn parse_error(input: &str, error: &ErrorTree<&str>) -> anyhow::Error {
+    let (location, expected) =
+        furthest_error(error).unwrap_or_else(|| (input, "a value".to_string()));
+    let offset = input.len() - location.len();
+    let column = input[..offset].chars().count();
+    anyhow::anyhow!(
+        "Failed to parse the value at column {}: {}\n  {}\n  {}^",
+        column + 1,
+        expected,
+        input,
+        " ".repeat(column)
+    )
+}
+
+/// Returns the remaining input at the furthest error and what was expected there.
+///
+/// Of alternatives the ones which got the furthest are reported, e.g. both the `)` of a tuple and
+/// the `:` of a map after `Some(3`.
+fn furthest_error<'a>(error: &ErrorTree<&'a str>) -> Option<(&'a str, String)> {
+    match error {
+        GenericErrorTree::Base { location, kind } => Some((*location, kind.to_string())),
+        GenericErrorTree::Stack { base, .. } => furthest_error(base),
+        GenericErrorTree::Alt(alternatives) => {
+            let errors = alternatives
+                .iter()
+                .filter_map(furthest_error)
+                .collect::<Vec<_>>();
+            let location = errors
+                .iter()
+                .map(|(location, _)| *location)
+                .min_by_key(|location| location.len())?;
+            let mut expected = errors
+                .into_iter()
+                .filter(|(other, _)| other.len() == location.len())
+                .map(|(_, expected)| expected)
+                .collect::<Vec<_>>();
+            expected.sort();
+            expected.dedup();
+            Some((location, expected.join(" or ")))
+        }
+    }
 }
 
 fn scon_value(input: &str) -> IResult<&str, Value, ErrorTree<&str>> {
@@ -104,6 +154,15 @@ fn scon_string(input: &str) -> IResult<&str, Value, ErrorTree<&str>> {
         .parse(input)
 }
 
+/// Parse a path of rust idents, e.g. `MyEnum::Variant`, returning the last ident.
+///
+/// The type of a value is known from the metadata, so only the variant is needed.
+fn rust_path(input: &str) -> IResult<&str, &str, ErrorTree<&str>> {
+    separated_list1(tag("::"), rust_ident)
+        .map(|segments| *segments.last().expect("there is at least one segment"))
+        .parse(input)
+}
+
 fn rust_ident(input: &str) -> IResult<&str, &str, ErrorTree<&str>> {
     let alpha_or_underscore = anychar.verify(|c: &char| c.is_alpha() || *c == '_');
 
@@ -112,17 +171,29 @@ fn rust_ident(input: &str) -> IResult<&str, &str, ErrorTree<&str>> {
         .parse(input)
 }
 
-/// Parse a signed or unsigned integer literal, supports optional Rust style underscore separators.
+/// Parse a signed or unsigned integer literal, supports optional Rust style underscore separators
+/// and an exponent, e.g. `1_000e6` or `1.5e12`, as long as the value is a whole number.
 fn scon_integer(input: &str) -> IResult<&str, Value, ErrorTree<&str>> {
     let sign = alt((char('+'), char('-')));
-    pair(sign.opt(), separated_list0(char('_'), digit1))
-        .map_res(|(sign, parts)| {
-            let digits = parts.join("");
+    let digits = || separated_list1(char('_'), digit1);
+    let exponent = || tuple((one_of("eE"), one_of("+-").opt(), digit1));
+    let number = alt((
+        recognize(tuple((digits(), char('.'), digits(), exponent()))),
+        recognize(pair(digits(), exponent().opt())),
+    ));
+    pair(sign.opt(), number)
+        .map_res::<_, _, NumberError>(|(sign, number)| {
+            let digits = whole_digits(number, 0)?;
             if let Some(sign) = sign {
                 let s = format!("{}{}", sign, digits);
-                s.parse::<i128>().map(Value::Int)
+                s.parse::<i128>()
+                    .map(Value::Int)
+                    .map_err(|_| NumberError::OutOfRange(number.into(), "i128".into()))
             } else {
-                digits.parse::<u128>().map(Value::UInt)
+                digits
+                    .parse::<u128>()
+                    .map(Value::UInt)
+                    .map_err(|_| NumberError::OutOfRange(number.into(), "u128".into()))
             }
         })
         .parse(input)
@@ -161,7 +232,7 @@ fn scon_tuple(input: &str) -> IResult<&str, Value, ErrorTree<&str>> {
         .preceded_by(ws(char('(')))
         .terminated(pair(ws(char(',')).opt(), ws(char(')'))));
 
-    tuple((ws(rust_ident).opt(), tuple_body))
+    tuple((ws(rust_path).opt(), tuple_body))
         .map(|(ident, v)| Value::Tuple(Tuple::new(ident, v.into_iter().collect())))
         .parse(input)
 }
@@ -169,7 +240,10 @@ fn scon_tuple(input: &str) -> IResult<&str, Value, ErrorTree<&str>> {
 /// Parse a rust ident on its own which could represent a struct with no fields or a enum unit
 /// variant e.g. "None"
 fn scon_unit_tuple(input: &str) -> IResult<&str, Value, ErrorTree<&str>> {
-    rust_ident
+    // the ident of a tuple or map, which report where they failed instead
+    let body = multispace0::<&str, ErrorTree<&str>>.precedes(one_of("({"));
+    rust_path
+        .terminated(body.not())
         .map(|ident| Value::Tuple(Tuple::new(Some(ident), Vec::new())))
         .parse(input)
 }
@@ -188,7 +262,7 @@ fn scon_map(input: &str) -> IResult<&str, Value, ErrorTree<&str>> {
     .preceded_by(ws(opening))
     .terminated(pair(ws(char(',')).opt(), ws(closing)));
 
-    tuple((ws(rust_ident).opt(), map_body))
+    tuple((ws(rust_path).opt(), map_body))
         .map(|(ident, v)| Value::Map(Map::new(ident, v.into_iter().collect())))
         .parse(input)
 }
@@ -281,6 +355,15 @@ mod tests {
             ("", Value::UInt(340282366920938463463374607431768211455))
         );
 
+        // exponents
+        assert_eq!(
+            scon_integer("1_000_000e6").unwrap(),
+            ("", Value::UInt(1_000_000_000_000))
+        );
+        assert_eq!(scon_integer("-1.5E3").unwrap(), ("", Value::Int(-1500)));
+        assert_matches!(scon_integer("1.55e1"), Err(nom::Err::Error(_)));
+        assert_matches!(scon_integer("1e39"), Err(nom::Err::Error(_)));
+
         // too many digits
         assert_matches!(
             scon_integer("3402823669209384634633746074317682114550"),
@@ -565,6 +648,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_enum_path() {
+        assert_scon_value(
+            "MyEnum::Variant { a: 1 }",
+            Value::Map(Map::new(
+                Some("Variant"),
+                vec![(Value::String("a".into()), Value::UInt(1))]
+                    .into_iter()
+                    .collect(),
+            )),
+        );
+        assert_scon_value(
+            "Option::Some(3)",
+            Value::Tuple(Tuple::new(Some("Some"), vec![Value::UInt(3)])),
+        );
+        assert_scon_value(
+            "MyEnum::Unit",
+            Value::Tuple(Tuple::new(Some("Unit"), Vec::new())),
+        );
+    }
+
+    #[test]
+    fn test_parse_error_position() {
+        let error = parse_value("Some(3").unwrap_err().to_string();
+        assert!(
+            error.starts_with("Failed to parse the value at column 7: "),
+            "{}",
+            error
+        );
+        assert!(error.ends_with("\n  Some(3\n        ^"), "{}", error);
+        let error = parse_value("[1, 2] 3").unwrap_err().to_string();
+        assert!(
+            error.starts_with("Failed to parse the value at column 8"),
+            "{}",
+            error
+        );
+    }
+
     #[test]
     fn test_char() {
         assert_scon_value(r#"'c'"#, Value::Char('c'));
@@ -475,6 +475,25 @@ mod tests {
         )
     }
 
+    #[test]
+    fn transcode_enum_unknown_variant_lists_variants() -> Result<()> {
+        #[derive(TypeInfo)]
+        #[allow(dead_code)]
+        enum E {
+            A(u32),
+            B { a: bool },
+        }
+
+        let (registry, ty) = registry_with_type::<E>()?;
+        let transcoder = Transcoder::new(&registry, Default::default());
+        let value = scon::parse_value("E::C(1)")?;
+        let error = transcoder.encode(ty, &value, &mut Vec::new()).unwrap_err();
+        assert!(error
+            .to_string()
+            .ends_with("No variant 'C' found, expected one of: A, B"));
+        Ok(())
+    }
+
     #[test]
     fn transcode_enum_variant_map() -> Result<()> {
         #[derive(TypeInfo)]
@@ -606,4 +625,34 @@ mod tests {
             )),
         )
     }
+
+    #[test]
+    fn transcode_hash_from_wasm_or_contract_file() {
+        crate::util::tests::with_tmp_dir(|dir| {
+            type Hash = <ink_env::DefaultEnvironment as ink_env::Environment>::Hash;
+            let (registry, ty) = registry_with_type::<Hash>()?;
+            let transcoder = TranscoderBuilder::new(&registry)
+                .register_custom_type::<Hash, _>(transcode::env_types::Hash)
+                .done();
+
+            let code = b"\0asm\x01\0\0\0".to_vec();
+            let expected = sp_core::hashing::blake2_256(&code);
+            let wasm = dir.join("proxy.wasm");
+            std::fs::write(&wasm, &code)?;
+            let contract = dir.join("proxy.contract");
+            let metadata =
+                serde_json::json!({ "source": { "hash": format!("0x{}", hex::encode(expected)) } });
+            std::fs::write(&contract, metadata.to_string())?;
+
+            for path in [wasm, contract] {
+                let value = scon::parse_value(&format!("{:?}", path.display().to_string()))?;
+                let mut output = Vec::new();
+                transcoder.encode(ty, &value, &mut output)?;
+                assert_eq!(output, expected.to_vec());
+                let decoded = transcoder.decode(ty, &mut &output[..])?;
+                assert_eq!(decoded, Value::Bytes(expected.to_vec().into()));
+            }
+            Ok(())
+        })
+    }
 }
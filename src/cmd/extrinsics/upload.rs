@@ -15,18 +15,20 @@
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::{
-    display_events, runtime_api::api, wait_for_success_and_handle_error, Balance, CodeHash,
-    ContractMessageTranscoder, ExtrinsicOpts, PairSigner, RuntimeApi,
+    apps::display_apps_link, display_events, display_explorer_links, encode_call, fees::Cost,
+    governance::GovernanceProposal, runtime_api::api, submission::submit_extrinsic, Balance,
+    CodeHash, ContractMessageTranscoder, ExplorerPage, ExtrinsicOpts, ExtrinsicSigner, RuntimeApi,
 };
-use crate::name_value_println;
+use crate::{name_value_println, timings, Determinism};
 use anyhow::{Context, Result};
 use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use scale::{Decode, Encode};
 use serde::Serialize;
 use sp_core::Bytes;
 use std::{fmt::Debug, path::PathBuf};
-use subxt::{rpc::NumberOrHex, ClientBuilder, Config, DefaultConfig, Signer};
+use subxt::{rpc::NumberOrHex, Call, ClientBuilder, Config, DefaultConfig, Metadata, Signer};
 
-type CodeUploadResult = pallet_contracts_primitives::CodeUploadResult<CodeHash, Balance>;
+pub(super) type CodeUploadResult = pallet_contracts_primitives::CodeUploadResult<CodeHash, Balance>;
 type CodeUploadReturnValue = pallet_contracts_primitives::CodeUploadReturnValue<CodeHash, Balance>;
 
 #[derive(Debug, clap::Args)]
@@ -37,14 +39,28 @@ pub struct UploadCommand {
     wasm_path: Option<PathBuf>,
     #[clap(flatten)]
     extrinsic_opts: ExtrinsicOpts,
+    /// Instead of submitting the upload, print the preimage of the call and the call data to
+    /// note it and to propose it in a referendum, for chains where uploading code is privileged.
+    #[clap(long, conflicts_with = "dry_run")]
+    as_governance: bool,
+    /// Upload code containing non-deterministic instructions with `relaxed`, e.g. for off-chain
+    /// execution. Such code cannot be instantiated.
+    ///
+    /// Build the code with `cargo contract build --determinism relaxed`.
+    #[clap(
+        long,
+        arg_enum,
+        default_value = "enforced",
+        value_name = "enforced | relaxed"
+    )]
+    determinism: Determinism,
 }
 
 impl UploadCommand {
     pub fn run(&self) -> Result<()> {
-        let (crate_metadata, contract_metadata) =
+        let (crate_metadata, contract_metadata, _) =
             super::load_metadata(self.extrinsic_opts.manifest_path.as_ref())?;
         let transcoder = ContractMessageTranscoder::new(&contract_metadata);
-        let signer = super::pair_signer(self.extrinsic_opts.signer()?);
 
         let wasm_path = match &self.wasm_path {
             Some(wasm_path) => wasm_path.clone(),
@@ -56,14 +72,29 @@ impl UploadCommand {
             .context(format!("Failed to read from {}", wasm_path.display()))?;
 
         async_std::task::block_on(async {
-            if self.extrinsic_opts.dry_run {
-                let result = self.upload_code_rpc(code, &signer).await?;
+            if self.as_governance {
+                self.governance_proposal(code).await?.display();
+                Ok(())
+            } else if self.extrinsic_opts.apps_link {
+                // nothing is signed, the upload is submitted by whoever opens the link
+                self.apps_link(code).await
+            } else if self.extrinsic_opts.dry_run {
+                let signer = self.extrinsic_opts.extrinsic_signer()?;
+                let url = self.extrinsic_opts.url()?;
+                let api = ClientBuilder::new()
+                    .set_url(url.as_str())
+                    .build()
+                    .await?
+                    .to_runtime_api::<RuntimeApi>();
+                let determinism = self.runtime_determinism(api.client.metadata())?;
+                let result = self.upload_code_rpc(code, &signer, determinism).await?;
 
                 name_value_println!("Code hash", format!("{:?}", result.code_hash));
                 name_value_println!("Deposit", format!("{:?}", result.deposit));
 
                 Ok(())
             } else {
+                let signer = self.extrinsic_opts.extrinsic_signer()?;
                 let code_stored = self.upload_code(code, &signer, &transcoder).await?;
 
                 name_value_println!("Code hash", format!("{:?}", code_stored.code_hash));
@@ -73,13 +104,31 @@ impl UploadCommand {
         })
     }
 
+    /// Returns the name of the `--determinism` in the runtime, `None` if the runtime predates
+    /// uploading code with relaxed determinism and the determinism is enforced.
+    fn runtime_determinism(&self, metadata: &Metadata) -> Result<Option<String>> {
+        match (determinism_variants(metadata), self.determinism) {
+            (Some(variants), determinism) => variants
+                .get(determinism as usize)
+                .cloned()
+                .map(Some)
+                .context("Unexpected `Determinism` type of the runtime"),
+            (None, Determinism::Enforced) => Ok(None),
+            (None, Determinism::Relaxed) => anyhow::bail!(
+                "The runtime does not support uploading code with relaxed determinism, \
+                 this requires a newer version of `pallet-contracts`"
+            ),
+        }
+    }
+
     async fn upload_code_rpc(
         &self,
         code: Vec<u8>,
-        signer: &PairSigner,
+        signer: &ExtrinsicSigner,
+        determinism: Option<String>,
     ) -> Result<CodeUploadReturnValue> {
-        let url = self.extrinsic_opts.url.to_string();
-        let cli = WsClientBuilder::default().build(&url).await?;
+        let url = self.extrinsic_opts.url()?;
+        let cli = WsClientBuilder::default().build(url.as_str()).await?;
         let storage_deposit_limit = self
             .extrinsic_opts
             .storage_deposit_limit
@@ -89,35 +138,95 @@ impl UploadCommand {
             origin: signer.account_id().clone(),
             code: Bytes(code),
             storage_deposit_limit,
+            determinism,
         };
         let params = rpc_params!(call_request);
 
+        let dry_run = timings::phase("Dry-run RPC");
         let result: CodeUploadResult = cli.request("contracts_upload_code", params).await?;
+        drop(dry_run);
 
         result.map_err(|e| anyhow::anyhow!("Failed to execute call via rpc: {:?}", e))
     }
 
+    /// Prepares the `Contracts::upload_code` call to be submitted through a referendum.
+    async fn governance_proposal(&self, code: Vec<u8>) -> Result<GovernanceProposal> {
+        let url = self.extrinsic_opts.url()?;
+        let api = ClientBuilder::new()
+            .set_url(url.as_str())
+            .build()
+            .await?
+            .to_runtime_api::<RuntimeApi>();
+        let metadata = api.client.metadata();
+        let call = self.encode_upload_code_call(metadata, code)?;
+        GovernanceProposal::new(metadata, call)
+    }
+
+    /// Prints a link to review and submit the upload in polkadot-js apps.
+    async fn apps_link(&self, code: Vec<u8>) -> Result<()> {
+        let url = self.extrinsic_opts.url()?;
+        let api = ClientBuilder::new()
+            .set_url(url.as_str())
+            .build()
+            .await?
+            .to_runtime_api::<RuntimeApi>();
+        let metadata = api.client.metadata();
+        let call = self.encode_upload_code_call(metadata, code)?;
+        display_apps_link(&self.extrinsic_opts, call)
+    }
+
+    fn upload_code_call(&self, code: Vec<u8>) -> api::contracts::calls::UploadCode {
+        api::contracts::calls::UploadCode {
+            code,
+            storage_deposit_limit: self.extrinsic_opts.storage_deposit_limit,
+        }
+    }
+
+    /// Encodes the `upload_code` call, with the `--determinism` if the runtime supports it.
+    fn encode_upload_code_call(&self, metadata: &Metadata, code: Vec<u8>) -> Result<Vec<u8>> {
+        let upload_code = self.upload_code_call(code);
+        match self.runtime_determinism(metadata)? {
+            Some(_) => encode_call(
+                metadata,
+                &UploadCodeWithDeterminism {
+                    upload_code,
+                    determinism: self.determinism,
+                },
+            ),
+            None => encode_call(metadata, &upload_code),
+        }
+    }
+
     async fn upload_code(
         &self,
         code: Vec<u8>,
-        signer: &PairSigner,
+        signer: &ExtrinsicSigner,
         transcoder: &ContractMessageTranscoder<'_>,
     ) -> Result<api::contracts::events::CodeStored> {
-        let url = self.extrinsic_opts.url.to_string();
+        let url = self.extrinsic_opts.url()?;
         let api = ClientBuilder::new()
-            .set_url(&url)
+            .set_url(url.as_str())
             .build()
             .await?
             .to_runtime_api::<RuntimeApi>();
 
-        let tx_progress = api
-            .tx()
-            .contracts()
-            .upload_code(code, self.extrinsic_opts.storage_deposit_limit)
-            .sign_and_submit_then_watch(signer)
-            .await?;
-
-        let result = wait_for_success_and_handle_error(tx_progress).await?;
+        let determinism = self.runtime_determinism(api.client.metadata())?;
+        let supports_determinism = determinism.is_some();
+        let deposit = self
+            .upload_code_rpc(code.clone(), signer, determinism)
+            .await?
+            .deposit;
+        let cost = Cost { value: 0, deposit };
+        let upload_code = self.upload_code_call(code);
+        let result = if supports_determinism {
+            let upload_code = UploadCodeWithDeterminism {
+                upload_code,
+                determinism: self.determinism,
+            };
+            submit_extrinsic(&api, upload_code, signer, &self.extrinsic_opts, cost).await?
+        } else {
+            submit_extrinsic(&api, upload_code, signer, &self.extrinsic_opts, cost).await?
+        };
 
         display_events(
             &result,
@@ -130,15 +239,109 @@ impl UploadCommand {
             .find_first::<api::contracts::events::CodeStored>()?
             .ok_or_else(|| anyhow::anyhow!("Failed to find CodeStored event"))?;
 
+        display_explorer_links(
+            &self.extrinsic_opts,
+            &[
+                ExplorerPage::CodeHash(code_stored.code_hash),
+                ExplorerPage::Extrinsic(result.extrinsic_hash()),
+                ExplorerPage::Block(result.block_hash()),
+            ],
+        )?;
+
         Ok(code_stored)
     }
 }
 
+/// Uploads the `code` and returns its code hash.
+pub(super) fn upload_code(
+    opts: &ExtrinsicOpts,
+    transcoder: &ContractMessageTranscoder<'_>,
+    code: Vec<u8>,
+) -> Result<CodeHash> {
+    let upload = UploadCommand {
+        wasm_path: None,
+        extrinsic_opts: opts.clone(),
+        as_governance: false,
+        determinism: Determinism::Enforced,
+    };
+    let signer = opts.extrinsic_signer()?;
+    let code_stored = async_std::task::block_on(upload.upload_code(code, &signer, transcoder))?;
+    Ok(code_stored.code_hash)
+}
+
 /// A struct that encodes RPC parameters required for a call to upload a new code.
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CodeUploadRequest {
-    origin: <DefaultConfig as Config>::AccountId,
-    code: Bytes,
-    storage_deposit_limit: Option<NumberOrHex>,
+    pub(super) origin: <DefaultConfig as Config>::AccountId,
+    pub(super) code: Bytes,
+    pub(super) storage_deposit_limit: Option<NumberOrHex>,
+    /// Only sent to runtimes which support uploading code with relaxed determinism.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) determinism: Option<String>,
+}
+
+/// `Contracts::upload_code` of runtimes which support uploading code with relaxed determinism,
+/// the `determinism` is appended to the arguments of the older call.
+#[derive(Debug, Encode, Decode)]
+struct UploadCodeWithDeterminism {
+    upload_code: api::contracts::calls::UploadCode,
+    determinism: Determinism,
+}
+
+impl Call for UploadCodeWithDeterminism {
+    const PALLET: &'static str = "Contracts";
+    const FUNCTION: &'static str = "upload_code";
+}
+
+/// Returns the names of the variants of the `determinism` argument of `Contracts::upload_code`,
+/// `None` if the runtime predates the argument.
+pub(super) fn determinism_variants(metadata: &Metadata) -> Option<Vec<String>> {
+    let runtime = metadata.runtime_metadata();
+    let calls = runtime
+        .pallets
+        .iter()
+        .find(|pallet| pallet.name == UploadCodeWithDeterminism::PALLET)?
+        .calls
+        .as_ref()?;
+    let upload_code = match runtime.types.resolve(calls.ty.id())?.type_def() {
+        scale_info::TypeDef::Variant(calls) => calls
+            .variants()
+            .iter()
+            .find(|call| call.name() == UploadCodeWithDeterminism::FUNCTION)?,
+        _ => return None,
+    };
+    let determinism = upload_code
+        .fields()
+        .iter()
+        .find(|field| field.name().map(String::as_str) == Some("determinism"))?;
+    match runtime.types.resolve(determinism.ty().id())?.type_def() {
+        scale_info::TypeDef::Variant(variants) => Some(
+            variants
+                .variants()
+                .iter()
+                .map(|variant| variant.name().clone())
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn determinism_is_appended_to_the_upload_code_arguments() {
+        let upload_code = || api::contracts::calls::UploadCode {
+            code: vec![0x00, 0x61, 0x73, 0x6d],
+            storage_deposit_limit: None,
+        };
+        let call = UploadCodeWithDeterminism {
+            upload_code: upload_code(),
+            determinism: Determinism::Relaxed,
+        };
+        assert_eq!(call.encode(), [upload_code().encode(), vec![1]].concat());
+        assert_eq!(Determinism::Enforced.encode(), vec![0]);
+    }
 }
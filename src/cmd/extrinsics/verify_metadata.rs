@@ -0,0 +1,199 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    call::{ContractExecResult, RpcCallRequest},
+    load_metadata_file, node_url, ContractAccount, ContractMessageTranscoder,
+};
+use crate::{
+    address_book::parse_address, crate_metadata::CrateMetadata, name_value_println,
+    workspace::ManifestPath,
+};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use serde_json::{Map, Value};
+use sp_core::{hashing::blake2_256, Bytes};
+use std::path::PathBuf;
+use subxt::rpc::NumberOrHex;
+
+/// Verify that the local metadata of a contract matches the hash committed to by the deployed
+/// contract.
+///
+/// By convention, the contract exposes a message returning the `blake2_256` hash of its metadata
+/// without the `source` section, serialized as compact JSON with sorted keys. The `source`
+/// section is excluded, as it contains the hash of the Wasm code which embeds the commitment.
+#[derive(Debug, clap::Args)]
+#[clap(name = "verify-metadata")]
+pub struct VerifyMetadataCommand {
+    /// The address of the deployed contract, or its alias in the address book. Without a
+    /// contract the hash of the local metadata is printed, e.g. to embed it into the contract.
+    #[clap(long, env = "CONTRACT", parse(try_from_str = parse_address))]
+    contract: Option<ContractAccount>,
+    /// The name of the message returning the hash of the metadata.
+    #[clap(long, short, default_value = "metadata_hash")]
+    message: String,
+    /// The metadata to verify, a `.contract` or `.json` file. Defaults to the metadata of the
+    /// contract at `--manifest-path`.
+    #[clap(long, parse(from_os_str))]
+    metadata: Option<PathBuf>,
+    /// Path to the `Cargo.toml` of the contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// Websockets url of a substrate node. Defaults to the url of the `--network`, or to
+    /// `ws://localhost:9944`.
+    #[clap(long, parse(try_from_str))]
+    url: Option<url::Url>,
+    /// The name of a network profile from the `cargo-contract` configuration file, or of a
+    /// profile with an `endpoint` in `[package.metadata.contract.profiles]` of the contract.
+    #[clap(long)]
+    network: Option<String>,
+    /// Maximum amount of gas to be used for the dry-run of the message.
+    #[clap(name = "gas", long, default_value = "50000000000")]
+    gas_limit: u64,
+}
+
+impl VerifyMetadataCommand {
+    pub fn run(&self) -> Result<()> {
+        let path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => {
+                let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+                CrateMetadata::collect(&manifest_path)?.metadata_path()
+            }
+        };
+        let (metadata, ink_project) = load_metadata_file(&path)?;
+        let local_hash = metadata_hash(&serde_json::to_value(&metadata)?);
+        name_value_println!("Local hash", format!("0x{}", hex::encode(local_hash)));
+
+        let contract = match &self.contract {
+            Some(contract) => contract,
+            None => return Ok(()),
+        };
+        let transcoder = ContractMessageTranscoder::new(&ink_project);
+        let data = transcoder
+            .encode(&self.message, Vec::<String>::new())
+            .context(format!(
+                "The metadata has no message `{}` without arguments, pass the `--message` \
+                 returning the hash of the metadata",
+                self.message
+            ))?;
+        let deployed_hash = async_std::task::block_on(self.deployed_hash(contract, data))?;
+        name_value_println!("Deployed hash", format!("0x{}", hex::encode(deployed_hash)));
+
+        if deployed_hash != local_hash {
+            anyhow::bail!(
+                "The metadata {} does not match the metadata committed to by the contract",
+                path.display()
+            )
+        }
+        println!(
+            "{}",
+            "The metadata matches the deployed contract".green().bold()
+        );
+        Ok(())
+    }
+
+    /// Dry-runs the message returning the hash of the metadata of the `contract`.
+    async fn deployed_hash(&self, contract: &ContractAccount, data: Vec<u8>) -> Result<[u8; 32]> {
+        let url = node_url(
+            self.url.as_ref(),
+            self.network.as_deref(),
+            self.manifest_path.as_ref(),
+        )?;
+        let cli = WsClientBuilder::default().build(url.as_str()).await?;
+        let call_request = RpcCallRequest {
+            // a query does not need a funded origin
+            origin: ContractAccount::new([0; 32]),
+            dest: contract.clone(),
+            value: NumberOrHex::Number(0),
+            gas_limit: NumberOrHex::Number(self.gas_limit),
+            storage_deposit_limit: None,
+            input_data: Bytes(data),
+        };
+        let result: ContractExecResult = cli
+            .request("contracts_call", rpc_params![&call_request])
+            .await?;
+        let ret_val = result
+            .result
+            .map_err(|err| anyhow::anyhow!("The call of `{}` failed: {:?}", self.message, err))?;
+        if ret_val.did_revert() {
+            anyhow::bail!("The call of `{}` reverted", self.message)
+        }
+        ret_val.data.0.as_slice().try_into().map_err(|_| {
+            anyhow::anyhow!(
+                "Expected `{}` to return a 32 byte hash, got {} bytes",
+                self.message,
+                ret_val.data.0.len()
+            )
+        })
+    }
+}
+
+/// Returns the hash of the `metadata` without its `source` section.
+fn metadata_hash(metadata: &Value) -> [u8; 32] {
+    let mut metadata = metadata.clone();
+    if let Some(object) = metadata.as_object_mut() {
+        object.remove("source");
+    }
+    let canonical = sort_keys(metadata).to_string();
+    blake2_256(canonical.as_bytes())
+}
+
+/// Sorts the keys of all objects, which `serde_json` preserves in insertion order with the
+/// `preserve_order` feature.
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut entries = object.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_hash_ignores_source_and_key_order() {
+        let metadata = serde_json::json!({
+            "source": { "hash": "0x01" },
+            "contract": { "name": "proxy", "version": "0.1.0" },
+            "V3": { "spec": { "messages": [] } },
+        });
+        let rebuilt = serde_json::json!({
+            "V3": { "spec": { "messages": [] } },
+            "contract": { "version": "0.1.0", "name": "proxy" },
+            "source": { "hash": "0x02" },
+        });
+        assert_eq!(metadata_hash(&metadata), metadata_hash(&rebuilt));
+
+        let changed = serde_json::json!({
+            "contract": { "name": "proxy", "version": "0.2.0" },
+            "V3": { "spec": { "messages": [] } },
+        });
+        assert_ne!(metadata_hash(&metadata), metadata_hash(&changed));
+    }
+}
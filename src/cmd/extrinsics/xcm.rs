@@ -0,0 +1,309 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Submission of extrinsics of a parachain as a `Transact` XCM from another chain.
+//!
+//! Only the subset of the XCM v2 types needed for a `Transact` is defined here, encoded as
+//! expected by the `send` call of the `pallet-xcm` of the sending chain.
+
+use super::{
+    parse_balance, Balance, ExtrinsicSigner, RuntimeDispatchError, RuntimeEvent, SignedExtra,
+};
+use anyhow::{Context, Result};
+use scale::Encode;
+use subxt::{Call, ClientBuilder, Config, DefaultConfig, SubmittableExtrinsic};
+
+/// Options to submit an extrinsic as a `Transact` XCM.
+#[derive(Clone, Debug, clap::Args)]
+pub(super) struct XcmOpts {
+    /// Submit the extrinsic as a `Transact` XCM from the chain at this url, e.g. the relay chain
+    /// or a sibling governing the parachain given by `--url`.
+    #[clap(
+        long,
+        parse(try_from_str),
+        requires = "xcm_para_id",
+        conflicts_with = "apps_link"
+    )]
+    xcm_from: Option<url::Url>,
+    /// The id of the parachain the extrinsic is executed on.
+    #[clap(long, requires = "xcm_from")]
+    xcm_para_id: Option<u32>,
+    /// Whether the chain at `--xcm-from` is the relay chain or a sibling parachain.
+    #[clap(long, arg_enum, default_value = "relay")]
+    xcm_sender: XcmSender,
+    /// The origin the extrinsic is dispatched with on the parachain, `superuser` is usually
+    /// reserved to messages of the relay chain.
+    #[clap(long, arg_enum, default_value = "sovereign-account")]
+    xcm_origin_kind: OriginKind,
+    /// The maximum weight of the `Transact`, defaults to twice the gas limit. It has to cover
+    /// the weight of the whole extrinsic, not only the gas consumed by the contract.
+    #[clap(long)]
+    xcm_weight: Option<u64>,
+    /// Pay for the execution of the message with this amount of the native asset of the
+    /// parachain, withdrawn from the account of the sender. Without a fee the execution is
+    /// unpaid, which the parachain has to allow for the sender.
+    #[clap(long, parse(try_from_str = parse_balance))]
+    xcm_fee: Option<Balance>,
+}
+
+/// The chain an XCM is sent from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ArgEnum)]
+enum XcmSender {
+    Relay,
+    Sibling,
+}
+
+/// The origin a `Transact` call is dispatched with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, clap::ArgEnum)]
+enum OriginKind {
+    #[codec(index = 0)]
+    Native,
+    #[codec(index = 1)]
+    SovereignAccount,
+    #[codec(index = 2)]
+    Superuser,
+    #[codec(index = 3)]
+    Xcm,
+}
+
+impl XcmOpts {
+    /// Returns the options if an extrinsic should be submitted as an XCM.
+    pub(super) fn enabled(&self) -> Option<&Self> {
+        self.xcm_from.as_ref().map(|_| self)
+    }
+
+    /// Sends the encoded `call` of the parachain as a `Transact` XCM, signed by the `signer` on
+    /// the sending chain. Returns the hash of the extrinsic on the sending chain.
+    pub(super) async fn send_transact(
+        &self,
+        call: Vec<u8>,
+        gas_limit: u64,
+        signer: &ExtrinsicSigner,
+    ) -> Result<<DefaultConfig as Config>::Hash> {
+        let url = self
+            .xcm_from
+            .as_ref()
+            .context("No `--xcm-from` chain to send the XCM from")?;
+        let send = XcmSend {
+            dest: VersionedMultiLocation::V1(self.dest()?),
+            message: self.transact_message(call, self.xcm_weight.unwrap_or(gas_limit * 2)),
+        };
+        let client = ClientBuilder::new()
+            .set_url(url.as_str())
+            .build::<DefaultConfig>()
+            .await?;
+        // the signed extensions configured for the network of the contract do not apply
+        let mut signer = signer.clone();
+        signer.set_signed_extensions(None);
+        let signer = &signer;
+        let hash = match self.xcm_sender {
+            XcmSender::Relay => {
+                SubmittableExtrinsic::<_, SignedExtra, _, RuntimeDispatchError, RuntimeEvent>::new(
+                    &client,
+                    RelaySend(send),
+                )
+                .sign_and_submit(signer)
+                .await
+            }
+            XcmSender::Sibling => {
+                SubmittableExtrinsic::<_, SignedExtra, _, RuntimeDispatchError, RuntimeEvent>::new(
+                    &client,
+                    ParachainSend(send),
+                )
+                .sign_and_submit(signer)
+                .await
+            }
+        };
+        signer.take_failure()?;
+        Ok(hash?)
+    }
+
+    /// The location of the parachain relative to the sending chain.
+    fn dest(&self) -> Result<MultiLocation> {
+        let para_id = self
+            .xcm_para_id
+            .context("The parachain id `--xcm-para-id` is required")?;
+        let parents = match self.xcm_sender {
+            XcmSender::Relay => 0,
+            XcmSender::Sibling => 1,
+        };
+        Ok(MultiLocation {
+            parents,
+            interior: Junctions::X1(Junction::Parachain(para_id)),
+        })
+    }
+
+    /// Wraps the `call` into a `Transact`, preceded by the purchase of its execution if there
+    /// is a `--xcm-fee`.
+    fn transact_message(&self, call: Vec<u8>, weight: u64) -> VersionedXcm {
+        let mut instructions = Vec::new();
+        if let Some(fee) = self.xcm_fee {
+            let asset = MultiAsset {
+                id: AssetId::Concrete(MultiLocation {
+                    parents: 0,
+                    interior: Junctions::Here,
+                }),
+                fun: Fungibility::Fungible(fee),
+            };
+            instructions.push(Instruction::WithdrawAsset(vec![asset.clone()]));
+            instructions.push(Instruction::BuyExecution {
+                fees: asset,
+                weight_limit: WeightLimit::Limited(weight),
+            });
+        }
+        instructions.push(Instruction::Transact {
+            origin_type: self.xcm_origin_kind,
+            require_weight_at_most: weight,
+            call,
+        });
+        VersionedXcm::V2(instructions)
+    }
+}
+
+#[derive(Clone, Debug, Encode)]
+struct MultiLocation {
+    parents: u8,
+    interior: Junctions,
+}
+
+#[derive(Clone, Debug, Encode)]
+enum Junctions {
+    #[codec(index = 0)]
+    Here,
+    #[codec(index = 1)]
+    X1(Junction),
+}
+
+#[derive(Clone, Debug, Encode)]
+enum Junction {
+    #[codec(index = 0)]
+    Parachain(#[codec(compact)] u32),
+}
+
+#[derive(Debug, Encode)]
+enum VersionedMultiLocation {
+    #[codec(index = 1)]
+    V1(MultiLocation),
+}
+
+#[derive(Clone, Debug, Encode)]
+struct MultiAsset {
+    id: AssetId,
+    fun: Fungibility,
+}
+
+#[derive(Clone, Debug, Encode)]
+enum AssetId {
+    #[codec(index = 0)]
+    Concrete(MultiLocation),
+}
+
+#[derive(Clone, Debug, Encode)]
+enum Fungibility {
+    #[codec(index = 0)]
+    Fungible(#[codec(compact)] Balance),
+}
+
+#[derive(Debug, Encode)]
+enum WeightLimit {
+    #[codec(index = 1)]
+    Limited(#[codec(compact)] u64),
+}
+
+#[derive(Debug, Encode)]
+enum Instruction {
+    #[codec(index = 0)]
+    WithdrawAsset(Vec<MultiAsset>),
+    #[codec(index = 6)]
+    Transact {
+        origin_type: OriginKind,
+        #[codec(compact)]
+        require_weight_at_most: u64,
+        call: Vec<u8>,
+    },
+    #[codec(index = 19)]
+    BuyExecution {
+        fees: MultiAsset,
+        weight_limit: WeightLimit,
+    },
+}
+
+#[derive(Debug, Encode)]
+enum VersionedXcm {
+    #[codec(index = 2)]
+    V2(Vec<Instruction>),
+}
+
+/// The arguments of the `send` call of `pallet-xcm`.
+#[derive(Debug, Encode)]
+struct XcmSend {
+    dest: VersionedMultiLocation,
+    message: VersionedXcm,
+}
+
+/// `pallet-xcm` is named `XcmPallet` in the relay chain runtimes.
+#[derive(Debug, Encode)]
+struct RelaySend(XcmSend);
+
+impl Call for RelaySend {
+    const PALLET: &'static str = "XcmPallet";
+    const FUNCTION: &'static str = "send";
+}
+
+/// `pallet-xcm` is named `PolkadotXcm` in the parachain runtimes.
+#[derive(Debug, Encode)]
+struct ParachainSend(XcmSend);
+
+impl Call for ParachainSend {
+    const PALLET: &'static str = "PolkadotXcm";
+    const FUNCTION: &'static str = "send";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(xcm_fee: Option<Balance>) -> XcmOpts {
+        XcmOpts {
+            xcm_from: Some(url::Url::parse("ws://localhost:9944").unwrap()),
+            xcm_para_id: Some(2000),
+            xcm_sender: XcmSender::Sibling,
+            xcm_origin_kind: OriginKind::Superuser,
+            xcm_weight: None,
+            xcm_fee,
+        }
+    }
+
+    #[test]
+    fn transact_is_encoded_as_xcm_v2() {
+        let opts = opts(None);
+        assert_eq!(
+            VersionedMultiLocation::V1(opts.dest().unwrap()).encode(),
+            // V1, one parent, X1(Parachain(compact 2000))
+            vec![1, 1, 1, 0, 0x41, 0x1f]
+        );
+        assert_eq!(
+            opts.transact_message(vec![0xab, 0xcd], 100).encode(),
+            // V2, one instruction: Transact(Superuser, compact 100, 2 bytes of call)
+            vec![2, 4, 6, 2, 0x91, 0x01, 8, 0xab, 0xcd]
+        );
+
+        let paid = opts(Some(1)).transact_message(vec![], 100).encode();
+        // WithdrawAsset, BuyExecution and Transact
+        assert_eq!(paid[1], 12);
+        assert_eq!(paid[2], 0);
+    }
+}
@@ -0,0 +1,308 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Adding the settings a contract requires to its `Cargo.toml`.
+//!
+//! The manifest is edited with `toml_edit`, so that its comments and formatting are preserved.
+//! Settings which are already present are never changed, even if they differ from the ones added.
+
+use crate::{cmd::check_project, name_value_println, workspace::ManifestPath, Verbosity};
+use anyhow::{Context, Result};
+use cargo_metadata::MetadataCommand;
+use colored::Colorize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use toml_edit::{Array, Document, Item, Table};
+
+/// Returns the settings of `[profile.release]` which are added if they are missing.
+fn release_profile() -> [(&'static str, toml_edit::Value); 2] {
+    [("overflow-checks", true.into()), ("panic", "abort".into())]
+}
+
+/// Adds the missing `cdylib` crate type and release profile settings to the `Cargo.toml` of the
+/// contract.
+#[derive(Debug, clap::Args)]
+#[clap(name = "fix-manifest")]
+pub struct FixManifestCommand {
+    /// Path to the `Cargo.toml` of the contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// Only list the settings which would be added.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl FixManifestCommand {
+    pub fn exec(&self) -> Result<()> {
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        fix_manifest(&manifest_path, self.dry_run)?;
+        if !self.dry_run {
+            check_project(&manifest_path, Verbosity::Default)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds the missing settings a contract requires to the manifest at `manifest_path` and to the
+/// manifest of its workspace, where `cargo` reads the profiles from.
+pub(crate) fn fix_manifest(manifest_path: &ManifestPath, dry_run: bool) -> Result<()> {
+    let path = manifest_path.as_ref().canonicalize()?;
+    let workspace_root = MetadataCommand::new()
+        .manifest_path(&path)
+        .no_deps()
+        .exec()
+        .context("Error invoking `cargo metadata`")?
+        .workspace_root
+        .join("Cargo.toml")
+        .canonicalize()?;
+
+    let mut manifest = read(&path)?;
+    let mut fixes = add_cdylib(&mut manifest)?;
+    if workspace_root == path {
+        fixes.extend(add_release_profile(&mut manifest)?);
+        apply(&path, &manifest, &fixes, dry_run)?;
+    } else {
+        apply(&path, &manifest, &fixes, dry_run)?;
+        let mut workspace = read(&workspace_root)?;
+        let fixes = add_release_profile(&mut workspace)?;
+        apply(&workspace_root, &workspace, &fixes, dry_run)?;
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Document> {
+    fs::read_to_string(path)
+        .context(format!("Failed to read {}", path.display()))?
+        .parse()
+        .context(format!("Failed to parse {}", path.display()))
+}
+
+/// Writes the `manifest` with the `fixes` to `path`, unless `dry_run`.
+fn apply(path: &Path, manifest: &Document, fixes: &[String], dry_run: bool) -> Result<()> {
+    if fixes.is_empty() {
+        return Ok(());
+    }
+    for fix in fixes {
+        let action = if dry_run { "Would add" } else { "Added" };
+        name_value_println!(action, format!("{} to {}", fix, path.display()));
+    }
+    if !dry_run {
+        fs::write(path, manifest.to_string())
+            .context(format!("Failed to write {}", path.display()))?;
+        println!(
+            "{} {}",
+            "Fixed".bright_green().bold(),
+            path.display().to_string().bold()
+        );
+    }
+    Ok(())
+}
+
+/// Adds `cdylib` to the crate types of the library target, keeping the existing ones.
+fn add_cdylib(manifest: &mut Document) -> Result<Vec<String>> {
+    let lib = manifest
+        .as_table_mut()
+        .entry("lib")
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+        .context("`lib` is not a table")?;
+    // `crate_type` is the deprecated spelling which `cargo` still accepts
+    let key = if lib.contains_key("crate_type") {
+        "crate_type"
+    } else {
+        "crate-type"
+    };
+    match lib.get_mut(key) {
+        Some(crate_types) => {
+            let crate_types = crate_types
+                .as_array_mut()
+                .context(format!("`{}` is not an array", key))?;
+            if crate_types
+                .iter()
+                .any(|crate_type| crate_type.as_str() == Some("cdylib"))
+            {
+                return Ok(Vec::new());
+            }
+            crate_types.push("cdylib");
+        }
+        None => {
+            lib.insert("crate-type", toml_edit::value(Array::from_iter(["cdylib"])));
+        }
+    }
+    Ok(vec![format!("`{} = [\"cdylib\"]` in `[lib]`", key)])
+}
+
+/// Adds the missing settings of the `release_profile` to `[profile.release]`.
+fn add_release_profile(manifest: &mut Document) -> Result<Vec<String>> {
+    let profiles = manifest
+        .as_table_mut()
+        .entry("profile")
+        .or_insert_with(|| {
+            let mut profiles = Table::new();
+            profiles.set_implicit(true);
+            Item::Table(profiles)
+        })
+        .as_table_like_mut()
+        .context("`profile` is not a table")?;
+    let release = profiles
+        .entry("release")
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+        .context("`profile.release` is not a table")?;
+
+    let mut fixes = Vec::new();
+    for (key, value) in release_profile() {
+        if release.contains_key(key) {
+            continue;
+        }
+        fixes.push(format!("`{} = {}` in `[profile.release]`", key, value));
+        release.insert(key, toml_edit::value(value));
+    }
+    Ok(fixes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    /// Writes a crate with the `manifest` and a contract to `dir`.
+    fn write_crate(dir: &Path, manifest: &str) -> Result<ManifestPath> {
+        fs::create_dir_all(dir.join("src"))?;
+        fs::write(
+            dir.join("src/lib.rs"),
+            "#[ink_lang::contract]\nmod flipper {}",
+        )?;
+        fs::write(dir.join("Cargo.toml"), manifest)?;
+        ManifestPath::new(dir.join("Cargo.toml"))
+    }
+
+    #[test]
+    fn missing_settings_are_added_preserving_comments() {
+        let mut manifest = r#"[package]
+name = "flipper"
+
+[lib]
+# The Wasm blob
+crate-type = ["rlib"]
+
+[profile.release]
+overflow-checks = false # on purpose
+"#
+        .parse::<Document>()
+        .unwrap();
+
+        assert_eq!(add_cdylib(&mut manifest).unwrap().len(), 1);
+        assert_eq!(add_release_profile(&mut manifest).unwrap().len(), 1);
+        assert!(add_cdylib(&mut manifest).unwrap().is_empty());
+        assert!(add_release_profile(&mut manifest).unwrap().is_empty());
+        let fixed = manifest.to_string();
+        assert!(fixed.contains("# The Wasm blob"));
+        assert!(fixed.contains("overflow-checks = false # on purpose"));
+        let fixed: toml::Value = toml::from_str(&fixed).unwrap();
+        assert_eq!(
+            fixed["lib"]["crate-type"],
+            toml::Value::Array(vec!["rlib".into(), "cdylib".into()])
+        );
+        assert_eq!(fixed["profile"]["release"]["overflow-checks"], false.into());
+        assert_eq!(fixed["profile"]["release"]["panic"], "abort".into());
+
+        let mut manifest = "[package]\nname = \"flipper\"\n"
+            .parse::<Document>()
+            .unwrap();
+        add_release_profile(&mut manifest).unwrap();
+        let fixed: toml::Value = toml::from_str(&manifest.to_string()).unwrap();
+        assert_eq!(fixed["profile"]["release"]["overflow-checks"], true.into());
+    }
+
+    #[test]
+    fn manifest_of_a_crate_is_fixed() {
+        with_tmp_dir(|dir| {
+            let manifest = r#"[package]
+name = "flipper"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+ink_lang = { version = "3", default-features = false }
+
+[lib]
+# The Wasm blob
+crate_type = ["rlib"]
+"#;
+            let manifest_path = write_crate(dir, manifest)?;
+            assert!(check_project(&manifest_path, Verbosity::Quiet).is_err());
+
+            fix_manifest(&manifest_path, true)?;
+            assert_eq!(fs::read_to_string(dir.join("Cargo.toml"))?, manifest);
+
+            fix_manifest(&manifest_path, false)?;
+            let fixed = fs::read_to_string(dir.join("Cargo.toml"))?;
+            assert!(fixed.contains("# The Wasm blob"));
+            let fixed: toml::Value = toml::from_str(&fixed)?;
+            assert_eq!(
+                fixed["lib"]["crate_type"],
+                toml::Value::Array(vec!["rlib".into(), "cdylib".into()])
+            );
+            assert!(fixed["lib"].get("crate-type").is_none());
+            assert_eq!(fixed["profile"]["release"]["overflow-checks"], true.into());
+            assert_eq!(fixed["profile"]["release"]["panic"], "abort".into());
+            check_project(&manifest_path, Verbosity::Quiet)?;
+
+            let fixed = fs::read_to_string(dir.join("Cargo.toml"))?;
+            fix_manifest(&manifest_path, false)?;
+            assert_eq!(fs::read_to_string(dir.join("Cargo.toml"))?, fixed);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn profile_of_a_workspace_member_is_added_to_the_workspace() {
+        with_tmp_dir(|dir| {
+            fs::write(
+                dir.join("Cargo.toml"),
+                "[workspace]\nmembers = [\"flipper\"]\n",
+            )?;
+            let manifest_path = write_crate(
+                &dir.join("flipper"),
+                r#"[package]
+name = "flipper"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+ink_lang = "3"
+"#,
+            )?;
+
+            fix_manifest(&manifest_path, false)?;
+            let member: toml::Value =
+                toml::from_str(&fs::read_to_string(dir.join("flipper/Cargo.toml"))?)?;
+            assert_eq!(
+                member["lib"]["crate-type"],
+                toml::Value::Array(vec!["cdylib".into()])
+            );
+            assert!(member.get("profile").is_none());
+            let workspace: toml::Value =
+                toml::from_str(&fs::read_to_string(dir.join("Cargo.toml"))?)?;
+            assert_eq!(workspace["workspace"]["members"][0], "flipper".into());
+            assert_eq!(workspace["profile"]["release"]["panic"], "abort".into());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,304 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::workspace::ManifestPath;
+use anyhow::{Context, Result};
+use cargo_metadata::{MetadataCommand, Package};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Output the dependencies between the contracts of a workspace as a graph.
+///
+/// A contract depends on another contract if it calls it through its `ContractRef`, i.e. the
+/// other contract is a dependency of its crate, or if it instantiates it by its code hash, i.e.
+/// its source code refers to `<other_contract>_code_hash`. The contracts are listed in the order
+/// they have to be deployed in, dependencies first.
+#[derive(Debug, clap::Args)]
+#[clap(name = "graph")]
+pub struct GraphCommand {
+    /// Path to the `Cargo.toml` of the workspace or of one of its contracts
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The format of the graph.
+    #[clap(long, arg_enum, default_value = "dot")]
+    format: GraphFormat,
+    /// Write the graph to this file instead of printing it.
+    #[clap(long, short, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+/// The formats the graph can be output in.
+#[derive(Debug, Clone, Copy, clap::ArgEnum)]
+pub enum GraphFormat {
+    /// A Graphviz DOT digraph.
+    Dot,
+    /// A Mermaid flowchart.
+    Mermaid,
+}
+
+impl GraphCommand {
+    pub fn exec(&self) -> Result<()> {
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        let metadata = MetadataCommand::new()
+            .manifest_path(manifest_path.as_ref())
+            .no_deps()
+            .exec()
+            .context("Error invoking `cargo metadata`")?;
+        let contracts = metadata
+            .packages
+            .iter()
+            .filter(|package| metadata.workspace_members.contains(&package.id))
+            .filter(|package| is_contract(package))
+            .collect::<Vec<_>>();
+        let graph = ContractGraph::from_packages(&contracts)?;
+
+        let rendered = match self.format {
+            GraphFormat::Dot => graph.dot()?,
+            GraphFormat::Mermaid => graph.mermaid()?,
+        };
+        match &self.output {
+            Some(output) => {
+                fs::write(output, rendered).context(format!("Failed to write {}", output.display()))
+            }
+            None => {
+                print!("{}", rendered);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns `true` if the `package` is an ink! contract, i.e. a `cdylib` depending on `ink_lang`.
+pub(crate) fn is_contract(package: &Package) -> bool {
+    package
+        .dependencies
+        .iter()
+        .any(|dependency| dependency.name == "ink_lang")
+        && package
+            .targets
+            .iter()
+            .any(|target| target.kind.iter().any(|kind| kind == "cdylib"))
+}
+
+/// The way a contract depends on another contract.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Dependency {
+    /// The contract calls the other contract through its `ContractRef`.
+    ContractRef,
+    /// The contract instantiates the other contract by its code hash.
+    CodeHash,
+}
+
+/// The contracts of a workspace and their dependencies on each other.
+#[derive(Debug, Default)]
+pub(crate) struct ContractGraph {
+    /// The dependencies of every contract by its name.
+    contracts: BTreeMap<String, BTreeSet<(String, Dependency)>>,
+}
+
+impl ContractGraph {
+    fn from_packages(packages: &[&Package]) -> Result<Self> {
+        let mut graph = Self::default();
+        for package in packages {
+            graph.add_contract(&package.name);
+        }
+        for package in packages {
+            let names = graph.contracts.keys().cloned().collect::<Vec<_>>();
+            for dependency in &package.dependencies {
+                if dependency.name != package.name && names.contains(&dependency.name) {
+                    graph.add_dependency(&package.name, &dependency.name, Dependency::ContractRef);
+                }
+            }
+            let source_dir = package
+                .manifest_path
+                .parent()
+                .expect("a manifest path has a parent directory")
+                .as_std_path()
+                .to_path_buf();
+            let mut sources = Vec::new();
+            collect_sources(&source_dir, &mut sources)?;
+            for name in names.iter().filter(|name| **name != package.name) {
+                let identifier = format!("{}_code_hash", name.replace('-', "_"));
+                if sources.iter().any(|source| source.contains(&identifier)) {
+                    graph.add_dependency(&package.name, name, Dependency::CodeHash);
+                }
+            }
+        }
+        Ok(graph)
+    }
+
+    pub(crate) fn add_contract(&mut self, name: &str) {
+        self.contracts.entry(name.to_string()).or_default();
+    }
+
+    pub(crate) fn add_dependency(&mut self, contract: &str, on: &str, dependency: Dependency) {
+        self.add_contract(on);
+        self.contracts
+            .entry(contract.to_string())
+            .or_default()
+            .insert((on.to_string(), dependency));
+    }
+
+    /// Returns the contracts in the order they have to be deployed in, every contract after the
+    /// contracts it depends on.
+    pub(crate) fn deployment_order(&self) -> Result<Vec<String>> {
+        let mut order = Vec::new();
+        let mut remaining = self.contracts.keys().cloned().collect::<BTreeSet<_>>();
+        while !remaining.is_empty() {
+            let ready = remaining
+                .iter()
+                .filter(|contract| {
+                    self.contracts[*contract]
+                        .iter()
+                        .all(|(on, _)| !remaining.contains(on))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            if ready.is_empty() {
+                anyhow::bail!(
+                    "The contracts {} depend on each other, there is no deployment order",
+                    remaining.into_iter().collect::<Vec<_>>().join(", ")
+                )
+            }
+            for contract in ready {
+                remaining.remove(&contract);
+                order.push(contract);
+            }
+        }
+        Ok(order)
+    }
+
+    fn dot(&self) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "digraph contracts {{")?;
+        writeln!(
+            out,
+            "    // deployment order: {}",
+            self.deployment_order()?.join(", ")
+        )?;
+        for (contract, dependencies) in &self.contracts {
+            writeln!(out, "    \"{}\";", contract)?;
+            for (on, dependency) in dependencies {
+                let attributes = match dependency {
+                    Dependency::ContractRef => "label=\"calls\"",
+                    Dependency::CodeHash => "label=\"code hash\", style=dashed",
+                };
+                writeln!(out, "    \"{}\" -> \"{}\" [{}];", contract, on, attributes)?;
+            }
+        }
+        writeln!(out, "}}")?;
+        Ok(out)
+    }
+
+    fn mermaid(&self) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "graph TD")?;
+        writeln!(
+            out,
+            "    %% deployment order: {}",
+            self.deployment_order()?.join(", ")
+        )?;
+        for (contract, dependencies) in &self.contracts {
+            writeln!(out, "    {}", contract)?;
+            for (on, dependency) in dependencies {
+                let arrow = match dependency {
+                    Dependency::ContractRef => "-->|calls|",
+                    Dependency::CodeHash => "-.->|code hash|",
+                };
+                writeln!(out, "    {} {} {}", contract, arrow, on)?;
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Reads the Rust sources in `dir` and its subdirectories, skipping the `target` directory.
+fn collect_sources(dir: &Path, sources: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir).context(format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if path.file_name().map_or(false, |name| name != "target") {
+                collect_sources(&path, sources)?;
+            }
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension == "rs")
+        {
+            sources.push(fs::read_to_string(&path)?);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> ContractGraph {
+        let mut graph = ContractGraph::default();
+        graph.add_contract("erc20");
+        graph.add_dependency("dex", "erc20", Dependency::ContractRef);
+        graph.add_dependency("factory", "dex", Dependency::CodeHash);
+        graph
+    }
+
+    #[test]
+    fn dependencies_are_deployed_first() {
+        assert_eq!(
+            graph().deployment_order().unwrap(),
+            vec!["erc20", "dex", "factory"]
+        );
+
+        let mut cyclic = graph();
+        cyclic.add_dependency("erc20", "factory", Dependency::ContractRef);
+        assert_eq!(
+            cyclic.deployment_order().unwrap_err().to_string(),
+            "The contracts dex, erc20, factory depend on each other, there is no deployment order"
+        );
+    }
+
+    #[test]
+    fn graph_is_rendered_as_dot_and_mermaid() {
+        assert_eq!(
+            graph().dot().unwrap(),
+            r#"digraph contracts {
+    // deployment order: erc20, dex, factory
+    "dex";
+    "dex" -> "erc20" [label="calls"];
+    "erc20";
+    "factory";
+    "factory" -> "dex" [label="code hash", style=dashed];
+}
+"#
+        );
+        assert_eq!(
+            graph().mermaid().unwrap(),
+            r#"graph TD
+    %% deployment order: erc20, dex, factory
+    dex
+    dex -->|calls| erc20
+    erc20
+    factory
+    factory -.->|code hash| dex
+"#
+        );
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+    cmd::build::{ensure_maximum_memory_pages, load_module, MAX_MEMORY_PAGES},
+    name_value_println, validate_wasm,
+};
+use anyhow::{Context, Result};
+use parity_wasm::elements::{External, Internal, Module, ResizableLimits, Section};
+use std::path::PathBuf;
+
+/// Prints the structure of a contract's Wasm and the result of the validation which
+/// `cargo contract build` applies to it.
+#[derive(Debug, clap::Args)]
+#[clap(name = "inspect-wasm")]
+pub struct InspectWasmCommand {
+    /// Path to the Wasm file to inspect.
+    #[clap(parse(from_os_str))]
+    wasm_path: PathBuf,
+    /// Print the whole module in the WebAssembly text format.
+    #[clap(long)]
+    wat: bool,
+}
+
+impl InspectWasmCommand {
+    pub fn exec(&self) -> Result<()> {
+        let module = load_module(&self.wasm_path)?;
+        let size = std::fs::metadata(&self.wasm_path)?.len();
+
+        name_value_println!("File", self.wasm_path.display().to_string());
+        name_value_println!("Size", format!("{} bytes", size));
+        name_value_println!(
+            "Sections",
+            module
+                .sections()
+                .iter()
+                .map(section_name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let imports = module
+            .import_section()
+            .map(|section| section.entries())
+            .unwrap_or_default();
+        for import in imports {
+            name_value_println!(
+                "Import",
+                format!(
+                    "{}::{} ({})",
+                    import.module(),
+                    import.field(),
+                    external_kind(import.external())
+                )
+            );
+        }
+        let exports = module
+            .export_section()
+            .map(|section| section.entries())
+            .unwrap_or_default();
+        for export in exports {
+            name_value_println!(
+                "Export",
+                format!("{} ({})", export.field(), internal_kind(export.internal()))
+            );
+        }
+        for section in module.sections() {
+            if let Section::Custom(custom) = section {
+                name_value_println!(
+                    "Custom",
+                    format!("{} ({} bytes)", custom.name(), custom.payload().len())
+                );
+            }
+        }
+        let validation = match validate(&module) {
+            Ok(()) => "ok".to_string(),
+            Err(err) => format!("{:?}", err),
+        };
+        name_value_println!("Validation", validation);
+
+        if self.wat {
+            let wat = wasmprinter::print_file(&self.wasm_path)
+                .context("Failed to disassemble the Wasm")?;
+            println!("\n{}", wat);
+        }
+        Ok(())
+    }
+}
+
+/// Applies the validation of `cargo contract build` to the `module`, without modifying it.
+fn validate(module: &Module) -> Result<()> {
+    validate_wasm::validate_import_section(module)?;
+    validate_wasm::validate_instructions(module, false)?;
+    ensure_maximum_memory_pages(&mut module.clone(), MAX_MEMORY_PAGES)
+}
+
+pub(crate) fn section_name(section: &Section) -> String {
+    match section {
+        Section::Unparsed { id, .. } => format!("unparsed ({})", id),
+        Section::Custom(custom) => format!("custom ({})", custom.name()),
+        Section::Type(_) => "type".into(),
+        Section::Import(_) => "import".into(),
+        Section::Function(_) => "function".into(),
+        Section::Table(_) => "table".into(),
+        Section::Memory(_) => "memory".into(),
+        Section::Global(_) => "global".into(),
+        Section::Export(_) => "export".into(),
+        Section::Start(_) => "start".into(),
+        Section::Element(_) => "element".into(),
+        Section::DataCount(_) => "data count".into(),
+        Section::Code(_) => "code".into(),
+        Section::Data(_) => "data".into(),
+        Section::Name(_) => "name".into(),
+        Section::Reloc(_) => "reloc".into(),
+    }
+}
+
+fn external_kind(external: &External) -> String {
+    match external {
+        External::Function(type_index) => format!("function of type {}", type_index),
+        External::Table(_) => "table".to_string(),
+        External::Memory(memory) => format!("memory, {}", limits(memory.limits())),
+        External::Global(_) => "global".to_string(),
+    }
+}
+
+fn internal_kind(internal: &Internal) -> String {
+    match internal {
+        Internal::Function(index) => format!("function {}", index),
+        Internal::Table(index) => format!("table {}", index),
+        Internal::Memory(index) => format!("memory {}", index),
+        Internal::Global(index) => format!("global {}", index),
+    }
+}
+
+fn limits(limits: &ResizableLimits) -> String {
+    match limits.maximum() {
+        Some(maximum) => format!("{} to {} pages", limits.initial(), maximum),
+        None => format!("{} pages, no maximum", limits.initial()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn contract_wasm_is_inspected() {
+        with_tmp_dir(|path| {
+            let wasm = wabt::wat2wasm(
+                r#"(module
+                    (import "env" "memory" (memory 2 16))
+                    (func (export "call"))
+                    (func (export "deploy"))
+                )"#,
+            )
+            .expect("invalid wat");
+            let module = parity_wasm::deserialize_buffer::<Module>(&wasm).expect("invalid wasm");
+            assert!(validate(&module).is_ok());
+            assert_eq!(
+                external_kind(module.import_section().unwrap().entries()[0].external()),
+                "memory, 2 to 16 pages"
+            );
+
+            let wasm_path = path.join("contract.wasm");
+            std::fs::write(&wasm_path, wasm)?;
+            let cmd = InspectWasmCommand {
+                wasm_path,
+                wat: true,
+            };
+            cmd.exec()
+        })
+    }
+
+    #[test]
+    fn exceeding_memory_pages_fail_validation() {
+        let wasm = wabt::wat2wasm(r#"(module (import "env" "memory" (memory 2 32)))"#)
+            .expect("invalid wat");
+        let module = parity_wasm::deserialize_buffer::<Module>(&wasm).expect("invalid wasm");
+        assert!(validate(&module).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    message_name,
+    types::{docs, RustTypes},
+};
+use anyhow::{Context, Result};
+use heck::{ToSnakeCase as _, ToUpperCamelCase as _};
+use ink_metadata::InkProject;
+use std::{fmt::Write, fs, path::Path};
+
+/// Writes a crate to `dir` which exposes a `<Contract>Ref` for calling the messages of the
+/// deployed `contract`.
+///
+/// The ink! dependencies of the crate have the `ink_version` the contract was built with.
+pub(crate) fn write_crate(
+    dir: &Path,
+    contract: &str,
+    ink_version: &str,
+    project: &InkProject,
+) -> Result<()> {
+    let crate_name = format!("{}-ref", contract.replace('_', "-"));
+    let ref_name = format!("{}Ref", contract.to_upper_camel_case());
+    fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+    fs::write(dir.join("Cargo.toml"), manifest(&crate_name, ink_version))?;
+    fs::write(
+        dir.join("lib.rs"),
+        contract_ref(contract, &ref_name, project)?,
+    )?;
+    Ok(())
+}
+
+fn manifest(crate_name: &str, ink_version: &str) -> String {
+    format!(
+        r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+ink_primitives = {{ version = "{ink}", default-features = false }}
+ink_env = {{ version = "{ink}", default-features = false }}
+ink_storage = {{ version = "{ink}", default-features = false }}
+ink_prelude = {{ version = "{ink}", default-features = false }}
+
+scale = {{ package = "parity-scale-codec", version = "3", default-features = false, features = ["derive"] }}
+scale-info = {{ version = "2", default-features = false, features = ["derive"], optional = true }}
+
+[lib]
+name = "{lib}"
+path = "lib.rs"
+crate-type = ["rlib"]
+
+[features]
+default = ["std"]
+std = [
+    "ink_primitives/std",
+    "ink_env/std",
+    "ink_storage/std",
+    "ink_prelude/std",
+    "scale/std",
+    "scale-info/std",
+]
+"#,
+        name = crate_name,
+        lib = crate_name.to_snake_case(),
+        ink = ink_version,
+    )
+}
+
+/// Returns the `lib.rs` of the crate, containing the `ref_name` struct with a method for every
+/// message of the `contract`, along with the types it defines which are used by the messages.
+pub(crate) fn contract_ref(contract: &str, ref_name: &str, project: &InkProject) -> Result<String> {
+    let mut types = RustTypes::new(project.registry());
+    let mut methods = String::new();
+    for message in project.spec().messages() {
+        let name = message_name(message.label());
+        let receiver = match message.mutates() {
+            true => "&mut self",
+            false => "&self",
+        };
+        let mut params = vec![receiver.to_string()];
+        let mut args = String::new();
+        for arg in message.args() {
+            params.push(format!(
+                "{}: {}",
+                arg.label(),
+                types.name(arg.ty().ty().id())?
+            ));
+            write!(args, "\n                    .push_arg({})", arg.label())?;
+        }
+        let transferred_value = match message.payable() {
+            true => {
+                params.push("transferred_value: u128".to_string());
+                "transferred_value"
+            }
+            false => "0",
+        };
+        let (output, returns) = match message.return_type().opt_type() {
+            Some(ty) => {
+                let output = types.name(ty.ty().id())?;
+                let returns = format!("ReturnType<{}>", output);
+                (output, returns)
+            }
+            None => ("()".to_string(), "()".to_string()),
+        };
+        let selector = message
+            .selector()
+            .to_bytes()
+            .iter()
+            .map(|byte| format!("0x{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        writeln!(methods)?;
+        docs(&mut methods, message.docs(), "    ")?;
+        writeln!(
+            methods,
+            r#"    pub fn {name}({params}) -> Result<{output}, ink_env::Error> {{
+        build_call::<DefaultEnvironment>()
+            .callee(self.account_id)
+            .gas_limit(0)
+            .transferred_value({transferred_value})
+            .exec_input(
+                ExecutionInput::new(Selector::new([{selector}])){args},
+            )
+            .returns::<{returns}>()
+            .fire()
+    }}"#,
+            name = name,
+            params = params.join(", "),
+            output = output,
+            transferred_value = transferred_value,
+            selector = selector,
+            args = args,
+            returns = returns,
+        )?;
+    }
+    // the definitions may reference further types of `ink_prelude`
+    let definitions = types.definitions()?;
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "//! Calls the messages of a deployed `{}` contract, generated from its metadata by \
+        `cargo contract interface --ref-crate`.\n",
+        contract
+    )?;
+    writeln!(out, "#![cfg_attr(not(feature = \"std\"), no_std)]\n")?;
+    writeln!(
+        out,
+        "use ink_env::{{\n    call::{{build_call, utils::ReturnType, ExecutionInput, Selector}},\n    \
+        AccountId, DefaultEnvironment,\n}};"
+    )?;
+    if let Some(import) = types.prelude_import() {
+        writeln!(out, "{}", import)?;
+    }
+    writeln!(out)?;
+    out.push_str(&definitions);
+    docs(&mut out, project.spec().docs(), "")?;
+    writeln!(
+        out,
+        r#"#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[derive(ink_storage::traits::SpreadLayout, ink_storage::traits::PackedLayout)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+)]
+pub struct {name} {{
+    account_id: AccountId,
+}}
+
+impl {name} {{
+    /// Returns a reference to the contract deployed at `account_id`.
+    pub fn from_account_id(account_id: AccountId) -> Self {{
+        Self {{ account_id }}
+    }}
+
+    /// Returns the account id of the contract.
+    pub fn account_id(&self) -> AccountId {{
+        self.account_id
+    }}
+{methods}}}"#,
+        name = ref_name,
+        methods = methods,
+    )?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::interface::tests::erc20_project;
+
+    #[test]
+    fn contract_ref_calls_messages_by_selector() {
+        let contract_ref = contract_ref("erc20", "Erc20Ref", &erc20_project()).unwrap();
+
+        assert!(contract_ref.contains("pub struct Erc20Ref {"));
+        assert!(contract_ref.contains("pub enum Error {"));
+        assert!(contract_ref.contains(
+            r#"    pub fn transfer(&mut self, to: ink_env::AccountId, value: u128, transferred_value: u128) -> Result<Result<(), Error>, ink_env::Error> {
+        build_call::<DefaultEnvironment>()
+            .callee(self.account_id)
+            .gas_limit(0)
+            .transferred_value(transferred_value)
+            .exec_input(
+                ExecutionInput::new(Selector::new([0x84, 0xa1, 0x5d, 0xa1]))
+                    .push_arg(to)
+                    .push_arg(value),
+            )
+            .returns::<ReturnType<Result<(), Error>>>()
+            .fire()
+    }"#
+        ));
+    }
+}
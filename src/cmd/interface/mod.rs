@@ -0,0 +1,295 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+mod contract_ref;
+mod schema;
+mod types;
+
+pub(crate) use self::{
+    schema::openrpc,
+    types::{RustTypes, EXTERNAL_CRATES},
+};
+
+use self::types::docs;
+use super::extrinsics::load_metadata_file;
+use crate::{crate_metadata::CrateMetadata, name_value_println, workspace::ManifestPath};
+use anyhow::{Context, Result};
+use heck::ToUpperCamelCase as _;
+use ink_metadata::InkProject;
+use std::{convert::TryFrom, fmt::Write, path::PathBuf};
+
+/// Generates an ink! trait definition of a contract's messages from its metadata.
+///
+/// Other contracts can implement the trait definition or call the contract through it, without
+/// depending on the contract's source code. Constructors are not part of the trait definition.
+///
+/// With `--ref-crate` a crate is generated instead, which exposes a `<Contract>Ref` for calling
+/// the messages of a deployed contract. With `--openrpc` the constructors and messages are
+/// described by an OpenRPC document with JSON Schemas of their parameters and return types.
+#[derive(Debug, clap::Args)]
+#[clap(name = "interface")]
+pub struct InterfaceCommand {
+    /// Path to the `metadata.json` or `<name>.contract` file of the contract. Defaults to the
+    /// metadata of the contract at `--manifest-path`, which must have been built.
+    #[clap(parse(from_os_str))]
+    metadata_path: Option<PathBuf>,
+    /// Path to the `Cargo.toml` of the contract
+    #[clap(long, parse(from_os_str), conflicts_with = "metadata_path")]
+    manifest_path: Option<PathBuf>,
+    /// The name of the trait, defaults to the name of the contract in upper camel case.
+    #[clap(long)]
+    trait_name: Option<String>,
+    /// Write the trait definition or OpenRPC document to this file instead of printing it.
+    #[clap(long, short, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// Generate a crate in this directory, which exposes a `<Contract>Ref` with a method for
+    /// every message of the contract.
+    #[clap(long, parse(from_os_str), conflicts_with_all = &["trait_name", "output"])]
+    ref_crate: Option<PathBuf>,
+    /// Generate an OpenRPC document with JSON Schemas of the parameters and return types of the
+    /// constructors and messages, e.g. to validate arguments in other languages.
+    #[clap(long, conflicts_with_all = &["trait_name", "ref_crate"])]
+    openrpc: bool,
+}
+
+impl InterfaceCommand {
+    pub fn exec(&self) -> Result<()> {
+        let path = match &self.metadata_path {
+            Some(path) => path.clone(),
+            None => {
+                let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+                CrateMetadata::collect(&manifest_path)?.metadata_path()
+            }
+        };
+        let (metadata, project) = load_metadata_file(&path)?;
+        if let Some(dir) = &self.ref_crate {
+            let ink_version = metadata.source.language.version.to_string();
+            contract_ref::write_crate(dir, &metadata.contract.name, &ink_version, &project)?;
+            name_value_println!("Generated", dir.display().to_string());
+            return Ok(());
+        }
+        let generated = if self.openrpc {
+            let document = schema::openrpc(
+                &metadata.contract.name,
+                &metadata.contract.version.to_string(),
+                &project,
+            )?;
+            serde_json::to_string_pretty(&document)?
+        } else {
+            let trait_name = self
+                .trait_name
+                .clone()
+                .unwrap_or_else(|| metadata.contract.name.to_upper_camel_case());
+            trait_definition(&metadata.contract.name, &trait_name, &project)?
+        };
+
+        match &self.output {
+            Some(output) => std::fs::write(output, generated)
+                .context(format!("Failed to write {}", output.display())),
+            None => {
+                print!("{}", generated);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns a module containing the `#[ink_lang::trait_definition]` of the messages of the
+/// `contract`, along with the types it defines which are used by the messages.
+pub(crate) fn trait_definition(
+    contract: &str,
+    trait_name: &str,
+    project: &InkProject,
+) -> Result<String> {
+    let mut types = RustTypes::new(project.registry());
+    let mut definition = String::new();
+    docs(&mut definition, project.spec().docs(), "")?;
+    writeln!(definition, "#[ink_lang::trait_definition]")?;
+    writeln!(definition, "pub trait {} {{", trait_name)?;
+    for (i, message) in project.spec().messages().iter().enumerate() {
+        if i > 0 {
+            writeln!(definition)?;
+        }
+        let mut attributes = vec!["message".to_string()];
+        if message.payable() {
+            attributes.push("payable".to_string());
+        }
+        attributes.push(format!(
+            "selector = 0x{}",
+            hex::encode(message.selector().to_bytes())
+        ));
+        let receiver = match message.mutates() {
+            true => "&mut self",
+            false => "&self",
+        };
+        let mut params = vec![receiver.to_string()];
+        for arg in message.args() {
+            params.push(format!(
+                "{}: {}",
+                arg.label(),
+                types.name(arg.ty().ty().id())?
+            ));
+        }
+        let output = match message.return_type().opt_type() {
+            Some(ty) => format!(" -> {}", types.name(ty.ty().id())?),
+            None => String::new(),
+        };
+
+        docs(&mut definition, message.docs(), "    ")?;
+        writeln!(definition, "    #[ink({})]", attributes.join(", "))?;
+        writeln!(
+            definition,
+            "    fn {}({}){};",
+            message_name(message.label()),
+            params.join(", "),
+            output
+        )?;
+    }
+    writeln!(definition, "}}")?;
+    // the definitions may reference further types of `ink_prelude`
+    let definitions = types.definitions()?;
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "//! Interface of the `{}` contract, generated from its metadata by \
+        `cargo contract interface`.\n",
+        contract
+    )?;
+    if let Some(import) = types.prelude_import() {
+        writeln!(out, "{}\n", import)?;
+    }
+    out.push_str(&definitions);
+    out.push_str(&definition);
+    Ok(out)
+}
+
+/// Returns the name of the function of a message, without the prefix of the trait the message
+/// is implemented for, e.g. `transfer` for `Erc20::transfer`.
+pub(crate) fn message_name(label: &str) -> &str {
+    label.rsplit("::").next().unwrap_or(label)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    pub(crate) fn erc20_project() -> InkProject {
+        serde_json::from_value(json!({
+            "spec": {
+                "constructors": [],
+                "docs": [" A simple ERC-20 contract."],
+                "events": [],
+                "messages": [
+                    {
+                        "args": [],
+                        "docs": [" Returns the total token supply."],
+                        "label": "total_supply",
+                        "mutates": false,
+                        "payable": false,
+                        "returnType": { "displayName": ["Balance"], "type": 0 },
+                        "selector": "0xdb6375a8",
+                    },
+                    {
+                        "args": [
+                            { "label": "to", "type": { "displayName": ["AccountId"], "type": 1 } },
+                            { "label": "value", "type": { "displayName": ["Balance"], "type": 0 } },
+                        ],
+                        "docs": [],
+                        "label": "Erc20::transfer",
+                        "mutates": true,
+                        "payable": true,
+                        "returnType": { "displayName": ["Result"], "type": 5 },
+                        "selector": "0x84a15da1",
+                    },
+                ],
+            },
+            "storage": { "struct": { "fields": [] } },
+            "types": [
+                { "id": 0, "type": { "def": { "primitive": "u128" } } },
+                {
+                    "id": 1,
+                    "type": {
+                        "def": { "composite": { "fields": [{ "type": 2, "typeName": "[u8; 32]" }] } },
+                        "path": ["ink_env", "types", "AccountId"],
+                    },
+                },
+                { "id": 2, "type": { "def": { "array": { "len": 32, "type": 3 } } } },
+                { "id": 3, "type": { "def": { "primitive": "u8" } } },
+                {
+                    "id": 4,
+                    "type": {
+                        "def": { "variant": { "variants": [
+                            { "index": 0, "name": "InsufficientBalance" },
+                            { "fields": [{ "type": 6 }], "index": 1, "name": "Other" },
+                        ] } },
+                        "docs": [" The error types."],
+                        "path": ["erc20", "erc20", "Error"],
+                    },
+                },
+                {
+                    "id": 5,
+                    "type": {
+                        "def": { "variant": { "variants": [
+                            { "fields": [{ "type": 7 }], "index": 0, "name": "Ok" },
+                            { "fields": [{ "type": 4 }], "index": 1, "name": "Err" },
+                        ] } },
+                        "params": [{ "name": "T", "type": 7 }, { "name": "E", "type": 4 }],
+                        "path": ["Result"],
+                    },
+                },
+                { "id": 6, "type": { "def": { "primitive": "str" } } },
+                { "id": 7, "type": { "def": { "tuple": [] } } },
+            ],
+        }))
+        .expect("invalid metadata")
+    }
+
+    #[test]
+    fn trait_definition_is_generated_from_metadata() {
+        let definition = trait_definition("erc20", "Erc20", &erc20_project()).unwrap();
+
+        assert_eq!(
+            definition,
+            r#"//! Interface of the `erc20` contract, generated from its metadata by `cargo contract interface`.
+
+use ink_prelude::string::String;
+
+/// The error types.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum Error {
+    #[codec(index = 0)]
+    InsufficientBalance,
+    #[codec(index = 1)]
+    Other(String),
+}
+
+/// A simple ERC-20 contract.
+#[ink_lang::trait_definition]
+pub trait Erc20 {
+    /// Returns the total token supply.
+    #[ink(message, selector = 0xdb6375a8)]
+    fn total_supply(&self) -> u128;
+
+    #[ink(message, payable, selector = 0x84a15da1)]
+    fn transfer(&mut self, to: ink_env::AccountId, value: u128) -> Result<(), Error>;
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,338 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of the contract API as an [OpenRPC](https://spec.open-rpc.org) document, whose
+//! parameter and result types are JSON Schemas.
+//!
+//! The schemas describe the JSON representation of the SCALE types:
+//!
+//! - integers are numbers, bytes are `0x` prefixed hex strings and `AccountId`s are SS58 strings
+//! - structs are objects and tuples as well as tuple structs are arrays
+//! - `Option`s are nullable, unit enum variants are strings and other variants are objects with
+//!   the variant name as the single key, e.g. `{ "Err": "InsufficientBalance" }`
+
+use super::types::EXTERNAL_CRATES;
+use anyhow::{Context, Result};
+use ink_metadata::{InkProject, MessageParamSpec, TypeSpec};
+use scale_info::{form::PortableForm, Field, PortableRegistry, Type, TypeDef, TypeDefPrimitive};
+use serde_json::{json, Map, Value};
+
+/// The version of the OpenRPC specification the document conforms to.
+const OPENRPC_VERSION: &str = "1.2.6";
+
+/// Returns the OpenRPC document of the constructors and messages of the `contract`.
+pub(crate) fn openrpc(contract: &str, version: &str, project: &InkProject) -> Result<Value> {
+    let mut schemas = Schemas::new(project.registry());
+    let mut methods = Vec::new();
+    for constructor in project.spec().constructors() {
+        methods.push(json!({
+            "name": constructor.label(),
+            "description": description(constructor.docs()),
+            "params": schemas.params(constructor.args())?,
+            "result": { "name": "instantiated", "schema": { "type": "null" } },
+            "x-kind": "constructor",
+            "x-selector": format!("0x{}", hex::encode(constructor.selector().to_bytes())),
+        }));
+    }
+    for message in project.spec().messages() {
+        let result = match message.return_type().opt_type() {
+            Some(ty) => schemas.schema(ty.ty().id())?,
+            None => json!({ "type": "null" }),
+        };
+        methods.push(json!({
+            "name": message.label(),
+            "description": description(message.docs()),
+            "params": schemas.params(message.args())?,
+            "result": { "name": "result", "schema": result },
+            "x-kind": "message",
+            "x-selector": format!("0x{}", hex::encode(message.selector().to_bytes())),
+            "x-mutates": message.mutates(),
+            "x-payable": message.payable(),
+        }));
+    }
+    Ok(json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": contract,
+            "version": version,
+            "description": description(project.spec().docs()),
+        },
+        "methods": methods,
+        "components": { "schemas": schemas.components },
+    }))
+}
+
+fn description(docs: &[String]) -> String {
+    docs.iter()
+        .map(|line| line.trim())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Resolves the types of the registry to JSON Schemas.
+///
+/// The types defined by the contract are added to the `components` and referenced by their name.
+struct Schemas<'a> {
+    registry: &'a PortableRegistry,
+    components: Map<String, Value>,
+}
+
+impl<'a> Schemas<'a> {
+    fn new(registry: &'a PortableRegistry) -> Self {
+        Self {
+            registry,
+            components: Map::new(),
+        }
+    }
+
+    fn params(&mut self, args: &[MessageParamSpec<PortableForm>]) -> Result<Vec<Value>> {
+        args.iter()
+            .map(|arg| {
+                Ok(json!({
+                    "name": arg.label(),
+                    "required": true,
+                    "schema": self.schema(arg.ty().ty().id())?,
+                    "x-display-name": display_name(arg.ty()),
+                }))
+            })
+            .collect()
+    }
+
+    fn schema(&mut self, id: u32) -> Result<Value> {
+        let ty = self
+            .registry
+            .resolve(id)
+            .context(format!("Type {} not found in the type registry", id))?;
+        let name = match component_name(ty) {
+            Some(name) => name,
+            None => return self.definition(ty),
+        };
+        if !self.components.contains_key(&name) {
+            // a placeholder is inserted first, so that recursive types terminate
+            self.components.insert(name.clone(), Value::Null);
+            let schema = self.definition(ty)?;
+            self.components.insert(name.clone(), schema);
+        }
+        Ok(json!({ "$ref": format!("#/components/schemas/{}", name) }))
+    }
+
+    fn definition(&mut self, ty: &Type<PortableForm>) -> Result<Value> {
+        let segments = ty.path().segments();
+        let schema = match ty.type_def() {
+            TypeDef::Primitive(primitive) => primitive_schema(primitive),
+            TypeDef::Sequence(sequence) => match self.is_u8(sequence.type_param().id()) {
+                true => json!({ "type": "string", "pattern": "^0x([0-9a-fA-F]{2})*$" }),
+                false => {
+                    json!({ "type": "array", "items": self.schema(sequence.type_param().id())? })
+                }
+            },
+            TypeDef::Array(array) => match self.is_u8(array.type_param().id()) {
+                true => json!({
+                    "type": "string",
+                    "pattern": format!("^0x[0-9a-fA-F]{{{}}}$", array.len() * 2),
+                }),
+                false => json!({
+                    "type": "array",
+                    "items": self.schema(array.type_param().id())?,
+                    "minItems": array.len(),
+                    "maxItems": array.len(),
+                }),
+            },
+            TypeDef::Tuple(tuple) => {
+                let items = tuple
+                    .fields()
+                    .iter()
+                    .map(|field| self.schema(field.id()))
+                    .collect::<Result<Vec<_>>>()?;
+                tuple_schema(items)
+            }
+            TypeDef::Compact(compact) => self.schema(compact.type_param().id())?,
+            TypeDef::BitSequence(_) => {
+                json!({ "type": "array", "items": { "type": "boolean" } })
+            }
+            TypeDef::Composite(_) if segments.last().map(String::as_str) == Some("AccountId") => {
+                json!({ "type": "string", "description": "SS58 encoded account id" })
+            }
+            TypeDef::Composite(composite) => self.fields_schema(composite.fields())?,
+            TypeDef::Variant(variant) if segments == ["Option"] => {
+                let some = variant
+                    .variants()
+                    .iter()
+                    .find(|variant| variant.name() == "Some")
+                    .and_then(|variant| variant.fields().first())
+                    .context("Option without a `Some` variant")?;
+                json!({ "anyOf": [{ "type": "null" }, self.schema(some.ty().id())?] })
+            }
+            TypeDef::Variant(variant) => {
+                let mut variants = Vec::new();
+                for variant in variant.variants() {
+                    variants.push(match variant.fields().is_empty() {
+                        true => json!({ "const": variant.name() }),
+                        false => json!({
+                            "type": "object",
+                            "properties": { (variant.name()): self.fields_schema(variant.fields())? },
+                            "required": [variant.name()],
+                            "additionalProperties": false,
+                        }),
+                    });
+                }
+                json!({ "oneOf": variants })
+            }
+        };
+        Ok(schema)
+    }
+
+    /// Returns the schema of the fields of a struct or enum variant.
+    ///
+    /// A single unnamed field is represented by the schema of its type.
+    fn fields_schema(&mut self, fields: &[Field<PortableForm>]) -> Result<Value> {
+        if fields.iter().all(|field| field.name().is_none()) {
+            let mut items = fields
+                .iter()
+                .map(|field| self.schema(field.ty().id()))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(match items.len() {
+                1 => items.remove(0),
+                _ => tuple_schema(items),
+            });
+        }
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for field in fields {
+            let name = field
+                .name()
+                .context("Mixed named and unnamed fields are not supported")?;
+            properties.insert(name.clone(), self.schema(field.ty().id())?);
+            required.push(name.clone());
+        }
+        Ok(json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        }))
+    }
+
+    fn is_u8(&self, id: u32) -> bool {
+        matches!(
+            self.registry.resolve(id).map(|ty| ty.type_def()),
+            Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+        )
+    }
+}
+
+fn display_name(ty: &TypeSpec<PortableForm>) -> String {
+    ty.display_name().segments().join("::")
+}
+
+/// Returns the name of a type defined by the contract, which is added to the `components`.
+fn component_name(ty: &Type<PortableForm>) -> Option<String> {
+    let segments = ty.path().segments();
+    match segments.first() {
+        Some(krate)
+            if segments.len() > 1
+                && !EXTERNAL_CRATES.contains(&krate.as_str())
+                && ty.type_params().is_empty() =>
+        {
+            segments.last().cloned()
+        }
+        _ => None,
+    }
+}
+
+/// Returns the schema of a tuple, the unit tuple is `null`.
+fn tuple_schema(items: Vec<Value>) -> Value {
+    if items.is_empty() {
+        return json!({ "type": "null" });
+    }
+    let len = items.len();
+    json!({ "type": "array", "prefixItems": items, "minItems": len, "maxItems": len })
+}
+
+fn primitive_schema(primitive: &TypeDefPrimitive) -> Value {
+    let unsigned = |format: &str| json!({ "type": "integer", "minimum": 0, "format": format });
+    let signed = |format: &str| json!({ "type": "integer", "format": format });
+    match primitive {
+        TypeDefPrimitive::Bool => json!({ "type": "boolean" }),
+        TypeDefPrimitive::Char => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+        TypeDefPrimitive::Str => json!({ "type": "string" }),
+        TypeDefPrimitive::U8 => json!({ "type": "integer", "minimum": 0, "maximum": u8::MAX }),
+        TypeDefPrimitive::U16 => json!({ "type": "integer", "minimum": 0, "maximum": u16::MAX }),
+        TypeDefPrimitive::U32 => json!({ "type": "integer", "minimum": 0, "maximum": u32::MAX }),
+        TypeDefPrimitive::U64 => unsigned("u64"),
+        TypeDefPrimitive::U128 => unsigned("u128"),
+        TypeDefPrimitive::U256 => unsigned("u256"),
+        TypeDefPrimitive::I8 => {
+            json!({ "type": "integer", "minimum": i8::MIN, "maximum": i8::MAX })
+        }
+        TypeDefPrimitive::I16 => {
+            json!({ "type": "integer", "minimum": i16::MIN, "maximum": i16::MAX })
+        }
+        TypeDefPrimitive::I32 => {
+            json!({ "type": "integer", "minimum": i32::MIN, "maximum": i32::MAX })
+        }
+        TypeDefPrimitive::I64 => signed("i64"),
+        TypeDefPrimitive::I128 => signed("i128"),
+        TypeDefPrimitive::I256 => signed("i256"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::interface::tests::erc20_project;
+
+    #[test]
+    fn openrpc_document_describes_messages() {
+        let document = openrpc("erc20", "0.1.0", &erc20_project()).unwrap();
+
+        assert_eq!(document["info"]["title"], "erc20");
+        assert_eq!(document["info"]["description"], "A simple ERC-20 contract.");
+        let transfer = &document["methods"][1];
+        assert_eq!(transfer["name"], "Erc20::transfer");
+        assert_eq!(transfer["x-selector"], "0x84a15da1");
+        assert_eq!(transfer["x-payable"], true);
+        assert_eq!(
+            transfer["params"][0]["schema"],
+            json!({ "type": "string", "description": "SS58 encoded account id" })
+        );
+        assert_eq!(
+            transfer["params"][1]["schema"],
+            json!({ "type": "integer", "minimum": 0, "format": "u128" })
+        );
+        assert_eq!(
+            transfer["result"]["schema"]["oneOf"][1],
+            json!({
+                "type": "object",
+                "properties": { "Err": { "$ref": "#/components/schemas/Error" } },
+                "required": ["Err"],
+                "additionalProperties": false,
+            })
+        );
+        assert_eq!(
+            document["components"]["schemas"]["Error"],
+            json!({ "oneOf": [
+                { "const": "InsufficientBalance" },
+                {
+                    "type": "object",
+                    "properties": { "Other": { "type": "string" } },
+                    "required": ["Other"],
+                    "additionalProperties": false,
+                },
+            ] })
+        );
+    }
+}
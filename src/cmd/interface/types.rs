@@ -0,0 +1,287 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::{Context, Result};
+use scale_info::{form::PortableForm, Field, PortableRegistry, Type, TypeDef, TypeDefPrimitive};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+/// The crates whose types are not defined by the contract, they are referenced by their path
+/// instead of being generated.
+pub(crate) const EXTERNAL_CRATES: [&str; 4] =
+    ["ink_env", "ink_primitives", "ink_prelude", "ink_storage"];
+
+/// Renders the types of a contract's type registry as Rust code.
+///
+/// Types which are defined by the contract itself are collected while rendering the names, their
+/// definitions are generated by [`RustTypes::definitions`].
+pub(crate) struct RustTypes<'a> {
+    registry: &'a PortableRegistry,
+    /// The ids of the types defined by the contract, by their name.
+    custom: BTreeMap<String, u32>,
+    /// The referenced types which have to be imported from `ink_prelude` for `no_std`.
+    prelude: BTreeSet<&'static str>,
+}
+
+impl<'a> RustTypes<'a> {
+    pub fn new(registry: &'a PortableRegistry) -> Self {
+        Self {
+            registry,
+            custom: BTreeMap::new(),
+            prelude: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the `use` declaration of the referenced types of `ink_prelude`, if there are any.
+    pub fn prelude_import(&self) -> Option<String> {
+        match self.prelude.len() {
+            0 => None,
+            1 => Some(format!(
+                "use ink_prelude::{};",
+                self.prelude
+                    .iter()
+                    .next()
+                    .expect("there is one import; qed")
+            )),
+            _ => Some(format!(
+                "use ink_prelude::{{{}}};",
+                self.prelude.iter().cloned().collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+
+    fn resolve(&self, id: u32) -> Result<&'a Type<PortableForm>> {
+        self.registry
+            .resolve(id)
+            .context(format!("Type {} not found in the type registry", id))
+    }
+
+    /// Returns the Rust name of the type with the `id`, e.g. `Result<(), Error>`.
+    pub fn name(&mut self, id: u32) -> Result<String> {
+        let ty = self.resolve(id)?;
+        let name = match ty.type_def() {
+            TypeDef::Primitive(primitive) => {
+                if let TypeDefPrimitive::Str = primitive {
+                    self.prelude.insert("string::String");
+                }
+                primitive_name(primitive)?.to_string()
+            }
+            TypeDef::Sequence(sequence) => {
+                self.prelude.insert("vec::Vec");
+                format!("Vec<{}>", self.name(sequence.type_param().id())?)
+            }
+            TypeDef::Array(array) => {
+                format!("[{}; {}]", self.name(array.type_param().id())?, array.len())
+            }
+            TypeDef::Tuple(tuple) => {
+                let fields = tuple
+                    .fields()
+                    .iter()
+                    .map(|field| self.name(field.id()))
+                    .collect::<Result<Vec<_>>>()?;
+                match fields.len() {
+                    1 => format!("({},)", fields[0]),
+                    _ => format!("({})", fields.join(", ")),
+                }
+            }
+            TypeDef::Compact(compact) => {
+                format!("scale::Compact<{}>", self.name(compact.type_param().id())?)
+            }
+            TypeDef::BitSequence(_) => anyhow::bail!("Bit sequences are not supported"),
+            TypeDef::Composite(_) | TypeDef::Variant(_) => self.path_name(id, ty)?,
+        };
+        Ok(name)
+    }
+
+    /// Returns the name of a composite or variant type, which is identified by its path.
+    fn path_name(&mut self, id: u32, ty: &Type<PortableForm>) -> Result<String> {
+        let segments = ty.path().segments();
+        let ident = segments
+            .last()
+            .context(format!("Type {} has no path", id))?
+            .clone();
+        let mut name = match segments.first().map(String::as_str) {
+            // the types of the standard library, e.g. `Option`, only have a single segment
+            _ if segments.len() == 1 => {
+                match ident.as_str() {
+                    "BTreeMap" => self.prelude.insert("collections::BTreeMap"),
+                    "BTreeSet" => self.prelude.insert("collections::BTreeSet"),
+                    _ => false,
+                };
+                ident
+            }
+            Some(krate) if EXTERNAL_CRATES.contains(&krate) => format!("{}::{}", krate, ident),
+            _ => {
+                if !ty.type_params().is_empty() {
+                    anyhow::bail!("The generic type {} is not supported", segments.join("::"))
+                }
+                match self.custom.insert(ident.clone(), id) {
+                    Some(other) if other != id => anyhow::bail!(
+                        "The contract defines multiple types named {}, which is not supported",
+                        ident
+                    ),
+                    _ => (),
+                }
+                ident
+            }
+        };
+        let params = ty
+            .type_params()
+            .iter()
+            .filter_map(|param| param.ty())
+            .map(|param| self.name(param.id()))
+            .collect::<Result<Vec<_>>>()?;
+        if !params.is_empty() {
+            write!(name, "<{}>", params.join(", "))?;
+        }
+        Ok(name)
+    }
+
+    /// Returns the definitions of all types defined by the contract which were referenced so far,
+    /// including the types referenced by those definitions.
+    pub fn definitions(&mut self) -> Result<String> {
+        let mut definitions = String::new();
+        let mut defined = BTreeSet::new();
+        while let Some((ident, id)) = self
+            .custom
+            .iter()
+            .find(|(ident, _)| !defined.contains(*ident))
+            .map(|(ident, id)| (ident.clone(), *id))
+        {
+            definitions.push_str(&self.definition(&ident, id)?);
+            defined.insert(ident);
+        }
+        Ok(definitions)
+    }
+
+    fn definition(&mut self, ident: &str, id: u32) -> Result<String> {
+        let ty = self.resolve(id)?;
+        let mut out = String::new();
+        docs(&mut out, ty.docs(), "")?;
+        writeln!(
+            out,
+            "#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]"
+        )?;
+        writeln!(
+            out,
+            "#[cfg_attr(feature = \"std\", derive(scale_info::TypeInfo))]"
+        )?;
+        match ty.type_def() {
+            TypeDef::Composite(composite) => {
+                let fields = self.fields(composite.fields(), "    ", "pub ")?;
+                match fields {
+                    Fields::Unit => writeln!(out, "pub struct {};", ident)?,
+                    Fields::Named(fields) => {
+                        writeln!(out, "pub struct {} {{\n{}}}", ident, fields)?
+                    }
+                    Fields::Unnamed(fields) => writeln!(out, "pub struct {}({});", ident, fields)?,
+                }
+            }
+            TypeDef::Variant(variant) => {
+                writeln!(out, "pub enum {} {{", ident)?;
+                for variant in variant.variants() {
+                    docs(&mut out, variant.docs(), "    ")?;
+                    writeln!(out, "    #[codec(index = {})]", variant.index())?;
+                    match self.fields(variant.fields(), "        ", "")? {
+                        Fields::Unit => writeln!(out, "    {},", variant.name())?,
+                        Fields::Named(fields) => {
+                            writeln!(out, "    {} {{\n{}    }},", variant.name(), fields)?
+                        }
+                        Fields::Unnamed(fields) => {
+                            writeln!(out, "    {}({}),", variant.name(), fields)?
+                        }
+                    }
+                }
+                writeln!(out, "}}")?;
+            }
+            _ => unreachable!("only composite and variant types are defined by the contract"),
+        }
+        writeln!(out)?;
+        Ok(out)
+    }
+
+    fn fields(
+        &mut self,
+        fields: &[Field<PortableForm>],
+        indent: &str,
+        visibility: &str,
+    ) -> Result<Fields> {
+        if fields.is_empty() {
+            return Ok(Fields::Unit);
+        }
+        if fields.iter().all(|field| field.name().is_none()) {
+            let types = fields
+                .iter()
+                .map(|field| Ok(format!("{}{}", visibility, self.name(field.ty().id())?)))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Fields::Unnamed(types.join(", ")));
+        }
+        let mut named = String::new();
+        for field in fields {
+            let name = field
+                .name()
+                .context("Mixed named and unnamed fields are not supported")?;
+            docs(&mut named, field.docs(), indent)?;
+            writeln!(
+                named,
+                "{}{}{}: {},",
+                indent,
+                visibility,
+                name,
+                self.name(field.ty().id())?
+            )?;
+        }
+        Ok(Fields::Named(named))
+    }
+}
+
+enum Fields {
+    Unit,
+    Named(String),
+    Unnamed(String),
+}
+
+/// Writes the `docs` as doc comments with the `indent`.
+pub(crate) fn docs(out: &mut String, docs: &[String], indent: &str) -> Result<()> {
+    for line in docs {
+        writeln!(out, "{}///{}", indent, line)?;
+    }
+    Ok(())
+}
+
+fn primitive_name(primitive: &TypeDefPrimitive) -> Result<&'static str> {
+    let name = match primitive {
+        TypeDefPrimitive::Bool => "bool",
+        TypeDefPrimitive::Char => "char",
+        TypeDefPrimitive::Str => "String",
+        TypeDefPrimitive::U8 => "u8",
+        TypeDefPrimitive::U16 => "u16",
+        TypeDefPrimitive::U32 => "u32",
+        TypeDefPrimitive::U64 => "u64",
+        TypeDefPrimitive::U128 => "u128",
+        TypeDefPrimitive::I8 => "i8",
+        TypeDefPrimitive::I16 => "i16",
+        TypeDefPrimitive::I32 => "i32",
+        TypeDefPrimitive::I64 => "i64",
+        TypeDefPrimitive::I128 => "i128",
+        TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => {
+            anyhow::bail!("256 bit integers are not supported")
+        }
+    };
+    Ok(name)
+}
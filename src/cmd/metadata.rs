@@ -16,14 +16,15 @@
 
 use crate::{
     crate_metadata::CrateMetadata,
-    maybe_println, util,
+    step_println,
+    toolchain::WasmTarget,
+    util,
     workspace::{ManifestPath, Workspace},
-    Network, UnstableFlags, Verbosity,
+    CargoFlags, UnstableFlags, Verbosity,
 };
 
 use anyhow::Result;
 use blake2::digest::{consts::U32, Digest as _};
-use colored::Colorize;
 use contract_metadata::{
     CodeHash, Compiler, Contract, ContractMetadata, Language, Source, SourceCompiler,
     SourceLanguage, SourceWasm, User,
@@ -53,16 +54,40 @@ struct ExtendedMetadataResult {
     user: Option<User>,
 }
 
+/// Returns the arguments for `cargo run` of the metadata generation package in the workspace of
+/// `manifest_path`.
+///
+/// `--locked` is never passed, since the generated package is not part of the contract's
+/// `Cargo.lock`. All of its dependencies are however, so the build still succeeds offline.
+pub(crate) fn metadata_gen_args(
+    manifest_path: &ManifestPath,
+    target_directory: &Path,
+    contract_package: &str,
+    cargo_flags: &CargoFlags,
+) -> Result<Vec<String>> {
+    let mut args = vec![
+        "--package".to_string(),
+        "metadata-gen".to_string(),
+        manifest_path.cargo_arg()?,
+        format!("--target-dir={}", target_directory.to_string_lossy()),
+        "--release".to_string(),
+    ];
+    args.extend(cargo_flags.args(false));
+    args.extend(cargo_flags.features_arg(Some(contract_package)));
+    Ok(args)
+}
+
 /// Generates a file with metadata describing the ABI of the smart contract.
 ///
 /// It does so by generating and invoking a temporary workspace member.
 pub(crate) fn execute(
     crate_metadata: &CrateMetadata,
     final_contract_wasm: &Path,
-    network: Network,
+    cargo_flags: &CargoFlags,
     verbosity: Verbosity,
     total_steps: usize,
     unstable_options: &UnstableFlags,
+    keep_debug_artifacts: bool,
 ) -> Result<MetadataResult> {
     util::assert_channel()?;
 
@@ -81,26 +106,23 @@ pub(crate) fn execute(
 
     let generate_metadata = |manifest_path: &ManifestPath| -> Result<()> {
         let mut current_progress = 4;
-        maybe_println!(
+        step_println!(
             verbosity,
-            " {} {}",
-            format!("[{}/{}]", current_progress, total_steps).bold(),
-            "Generating metadata".bright_green().bold()
+            current_progress,
+            total_steps,
+            "Generating metadata"
         );
-        let target_dir_arg = format!("--target-dir={}", target_directory.to_string_lossy());
         let stdout = util::invoke_cargo(
             "run",
-            &[
-                "--package",
-                "metadata-gen",
-                &manifest_path.cargo_arg()?,
-                &target_dir_arg,
-                "--release",
-                &network.to_string(),
-            ],
+            &metadata_gen_args(
+                manifest_path,
+                &target_directory,
+                &crate_metadata.root_package.name,
+                cargo_flags,
+            )?,
             crate_metadata.manifest_path.directory(),
             verbosity,
-            vec![],
+            cargo_flags.env(),
         )?;
 
         let ink_meta: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(&stdout)?;
@@ -113,11 +135,11 @@ pub(crate) fn execute(
             current_progress += 1;
         }
 
-        maybe_println!(
+        step_println!(
             verbosity,
-            " {} {}",
-            format!("[{}/{}]", current_progress, total_steps).bold(),
-            "Generating bundle".bright_green().bold()
+            current_progress,
+            total_steps,
+            "Generating bundle"
         );
         let contents = serde_json::to_string(&metadata)?;
         fs::write(&out_path_bundle, contents)?;
@@ -136,7 +158,16 @@ pub(crate) fn execute(
                 Ok(())
             })?
             .with_metadata_gen_package(crate_metadata.manifest_path.absolute_directory()?)?
-            .using_temp(generate_metadata)?;
+            .keep_temp_dir(keep_debug_artifacts)
+            .using_temp(generate_metadata)?
+            .into_iter()
+            .for_each(|path| {
+                super::build::print_debug_artifact(
+                    verbosity,
+                    "metadata generation workspace",
+                    &path,
+                )
+            });
     }
 
     Ok(MetadataResult {
@@ -171,7 +202,11 @@ fn extended_metadata(
         let compiler = SourceCompiler::new(Compiler::RustC, rust_version);
         let wasm = fs::read(final_contract_wasm)?;
         let hash = blake2_hash(wasm.as_slice());
-        Source::new(Some(SourceWasm::new(wasm)), hash, lang, compiler)
+        let source = Source::new(Some(SourceWasm::new(wasm)), hash, lang, compiler);
+        match crate_metadata.wasm_target {
+            WasmTarget::Wasm32 => source,
+            WasmTarget::Wasm64 => source.with_target(WasmTarget::Wasm64.triple()),
+        }
     };
 
     // Required contract fields
@@ -216,7 +251,7 @@ fn extended_metadata(
 }
 
 /// Returns the blake2 hash of the submitted slice.
-fn blake2_hash(code: &[u8]) -> CodeHash {
+pub(crate) fn blake2_hash(code: &[u8]) -> CodeHash {
     let mut blake2 = blake2::Blake2b::<U32>::new();
     blake2.update(code);
     let result = blake2.finalize();
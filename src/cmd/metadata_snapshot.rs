@@ -0,0 +1,258 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A snapshot of the contract metadata committed to the repository, so that changes of the ABI
+//! show up in code review and unexpected ones fail CI.
+//!
+//! The `source` section of the metadata is not part of the snapshot: the code hash and compiler
+//! versions change with every toolchain update without changing the ABI.
+
+use crate::{
+    cmd::build::{execute, ExecuteArgs},
+    name_value_println,
+    workspace::ManifestPath,
+    BuildArtifacts, Verbosity, VerbosityFlags,
+};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::{Map, Value};
+use std::{convert::TryFrom, fs, path::PathBuf};
+
+/// The location of the snapshot, relative to the directory of the contract.
+const DEFAULT_SNAPSHOT: &str = ".ink/metadata.json";
+
+/// The sections of the metadata which are excluded from the snapshot.
+const IGNORED_SECTIONS: [&str; 1] = ["source"];
+
+/// Writes the metadata of the contract to a snapshot, or checks that it matches the snapshot.
+#[derive(Debug, clap::Args)]
+#[clap(name = "metadata")]
+pub struct MetadataCommand {
+    /// Path to the `Cargo.toml` of the contract.
+    #[clap(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// Path to the snapshot, `.ink/metadata.json` in the directory of the contract by default.
+    #[clap(long, parse(from_os_str))]
+    snapshot: Option<PathBuf>,
+    /// Fail with the differences if the metadata does not match the snapshot, instead of
+    /// updating the snapshot.
+    #[clap(long)]
+    check: bool,
+    #[clap(flatten)]
+    verbosity: VerbosityFlags,
+}
+
+impl MetadataCommand {
+    pub fn exec(&self) -> Result<()> {
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        let verbosity = TryFrom::<&VerbosityFlags>::try_from(&self.verbosity)?;
+        let snapshot_path = match &self.snapshot {
+            Some(snapshot) => snapshot.clone(),
+            None => manifest_path.absolute_directory()?.join(DEFAULT_SNAPSHOT),
+        };
+
+        let metadata = generate(manifest_path, verbosity)?;
+        if !self.check {
+            if let Some(dir) = snapshot_path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(
+                &snapshot_path,
+                serde_json::to_string_pretty(&metadata)? + "\n",
+            )
+            .context(format!("Failed to write {}", snapshot_path.display()))?;
+            name_value_println!("Snapshot", snapshot_path.display().to_string());
+            return Ok(());
+        }
+
+        let snapshot: Value =
+            serde_json::from_slice(&fs::read(&snapshot_path).context(format!(
+                "Failed to read the snapshot {}, it is created by `cargo contract metadata`",
+                snapshot_path.display()
+            ))?)
+            .context(format!("Failed to parse {}", snapshot_path.display()))?;
+        let differences = diff(&snapshot, &metadata);
+        if !differences.is_empty() {
+            anyhow::bail!(
+                "The metadata differs from the snapshot {}:\n{}\n\nIf the changes are intended, \
+                 update the snapshot with `cargo contract metadata`.",
+                snapshot_path.display(),
+                differences
+                    .iter()
+                    .map(|difference| format!("  {}", colorize(difference)))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        }
+        println!(
+            "{} {}",
+            "The metadata matches the snapshot".bright_green().bold(),
+            snapshot_path.display().to_string().bold()
+        );
+        Ok(())
+    }
+}
+
+/// Colors a line of the `diff` by the kind of the difference.
+fn colorize(difference: &str) -> String {
+    match difference.split_at(1) {
+        ("+", rest) => format!("{}{}", "+".green(), rest),
+        ("-", rest) => format!("{}{}", "-".red(), rest),
+        ("~", rest) => format!("{}{}", "~".yellow(), rest),
+        _ => difference.to_string(),
+    }
+}
+
+/// Builds the contract and returns its metadata, without the `IGNORED_SECTIONS`.
+fn generate(manifest_path: ManifestPath, verbosity: Verbosity) -> Result<Value> {
+    let result = execute(ExecuteArgs {
+        manifest_path,
+        verbosity,
+        build_artifact: BuildArtifacts::All,
+        ..Default::default()
+    })?;
+    let path = result
+        .metadata_result
+        .context("The metadata is generated when building all artifacts")?
+        .dest_metadata;
+    let mut metadata: Value = serde_json::from_slice(
+        &fs::read(&path).context(format!("Failed to read {}", path.display()))?,
+    )
+    .context(format!("Failed to parse {}", path.display()))?;
+    if let Some(sections) = metadata.as_object_mut() {
+        for section in IGNORED_SECTIONS {
+            sections.remove(section);
+        }
+    }
+    Ok(metadata)
+}
+
+/// Returns the differences between the `snapshot` and the `current` metadata, one line each,
+/// prefixed with the JSON path of the changed value.
+///
+/// Elements of arrays are identified by their `label` where they have one, e.g. the messages of
+/// the contract, so that reordering or inserting a message reports just that message.
+fn diff(snapshot: &Value, current: &Value) -> Vec<String> {
+    let mut differences = Vec::new();
+    diff_values("", snapshot, current, &mut differences);
+    differences
+}
+
+fn diff_values(path: &str, snapshot: &Value, current: &Value, differences: &mut Vec<String>) {
+    match (snapshot, current) {
+        (Value::Object(snapshot), Value::Object(current)) => {
+            for (key, value) in snapshot {
+                let path = child_path(path, key);
+                match current.get(key) {
+                    Some(current) => diff_values(&path, value, current, differences),
+                    None => differences.push(format!("- {}", path)),
+                }
+            }
+            for key in current.keys().filter(|key| !snapshot.contains_key(*key)) {
+                differences.push(format!("+ {}", child_path(path, key)));
+            }
+        }
+        (Value::Array(snapshot), Value::Array(current)) => {
+            match (labelled(snapshot), labelled(current)) {
+                (Some(snapshot), Some(current)) => diff_values(
+                    path,
+                    &Value::Object(snapshot),
+                    &Value::Object(current),
+                    differences,
+                ),
+                _ => {
+                    for (index, (snapshot, current)) in snapshot.iter().zip(current).enumerate() {
+                        diff_values(
+                            &format!("{}[{}]", path, index),
+                            snapshot,
+                            current,
+                            differences,
+                        )
+                    }
+                    for index in current.len()..snapshot.len() {
+                        differences.push(format!("- {}[{}]", path, index));
+                    }
+                    for index in snapshot.len()..current.len() {
+                        differences.push(format!("+ {}[{}]", path, index));
+                    }
+                }
+            }
+        }
+        (snapshot, current) if snapshot != current => {
+            differences.push(format!("~ {}: {} -> {}", path, snapshot, current))
+        }
+        _ => (),
+    }
+}
+
+/// Returns the path of the entry `key` of the object at `path`.
+fn child_path(path: &str, key: &str) -> String {
+    if key.starts_with('[') {
+        format!("{}{}", path, key)
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Returns the elements keyed by their `label`, if every element has a unique one.
+fn labelled(elements: &[Value]) -> Option<Map<String, Value>> {
+    let mut labelled = Map::new();
+    for element in elements {
+        let label = element.get("label")?.as_str()?;
+        if labelled
+            .insert(format!("[{}]", label), element.clone())
+            .is_some()
+        {
+            return None;
+        }
+    }
+    Some(labelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn differences_are_reported_by_path() {
+        let snapshot = json!({
+            "contract": { "name": "flipper", "version": "0.1.0" },
+            "V3": { "spec": { "messages": [
+                { "label": "flip", "args": [], "selector": "0x633aa551" },
+                { "label": "get", "args": [], "selector": "0x2f865bd9" },
+            ] } }
+        });
+        assert!(diff(&snapshot, &snapshot).is_empty());
+
+        let current = json!({
+            "contract": { "name": "flipper", "version": "0.2.0" },
+            "V3": { "spec": { "messages": [
+                { "label": "set", "args": [{ "label": "value" }], "selector": "0x11111111" },
+                { "label": "flip", "args": [{ "label": "times" }], "selector": "0x633aa551" },
+            ] } }
+        });
+        assert_eq!(
+            diff(&snapshot, &current),
+            vec![
+                "+ .V3.spec.messages[flip].args[times]",
+                "- .V3.spec.messages[get]",
+                "+ .V3.spec.messages[set]",
+                "~ .contract.version: \"0.1.0\" -> \"0.2.0\"",
+            ]
+        );
+    }
+}
@@ -14,15 +14,54 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
+pub mod access_control;
+pub mod account;
+pub mod add;
+pub mod address_book;
 pub mod build;
+pub mod ci;
+pub mod clean;
+pub mod codegen;
+pub mod completions;
+pub mod conformance;
+pub mod fix_manifest;
+pub mod graph;
+pub mod inspect;
+pub mod interface;
 pub mod metadata;
+pub mod metadata_snapshot;
 pub mod new;
+mod preflight;
+pub mod size_history;
 pub mod test;
+pub mod wasm_diff;
 
 pub(crate) use self::{
+    access_control::AccessControlCommand,
+    account::AccountCommand,
+    add::AddCommand,
+    address_book::AddressBookCommand,
     build::{BuildCommand, CheckCommand},
+    ci::InitCiCommand,
+    clean::CleanCommand,
+    codegen::TypegenCommand,
+    completions::{CompleteCommand, CompletionsCommand},
+    conformance::ConformanceCommand,
+    fix_manifest::FixManifestCommand,
+    graph::GraphCommand,
+    inspect::InspectWasmCommand,
+    interface::InterfaceCommand,
+    metadata_snapshot::MetadataCommand,
+    preflight::check_project,
+    size_history::SizeHistoryCommand,
     test::TestCommand,
+    wasm_diff::WasmDiffCommand,
 };
-mod extrinsics;
+pub(crate) mod extrinsics;
 
-pub(crate) use self::extrinsics::{CallCommand, InstantiateCommand, UploadCommand};
+pub(crate) use self::extrinsics::{
+    BenchDeployCommand, CallCommand, CallManyCommand, ChainInfoCommand, ConsoleCommand,
+    DeployCommand, DownloadCommand, GetCommand, IndexCommand, InstantiateCommand, LogsCommand,
+    ReplayCommand, ServeCommand, SimulateUpgradeCommand, StateCommand, SubmitCommand,
+    TerminateCommand, UploadCommand, VerifyMetadataCommand,
+};
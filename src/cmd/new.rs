@@ -14,11 +14,47 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{env, fs, path::Path};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Result;
 
 pub(crate) fn execute<P>(name: &str, dir: Option<P>) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let out_dir = create_out_dir(name, dir)?;
+    let template = include_bytes!(concat!(env!("OUT_DIR"), "/template.zip"));
+
+    crate::util::unzip(template, out_dir, Some(name))?;
+
+    Ok(())
+}
+
+/// Creates the crates of a chain extension `name`: the runtime side in `runtime/` and the ink!
+/// side in `ink/`, with matching function ids and error codes.
+pub(crate) fn execute_chain_extension<P>(name: &str, dir: Option<P>) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let out_dir = create_out_dir(name, dir)?;
+    if out_dir.join("runtime").join("Cargo.toml").exists()
+        || out_dir.join("ink").join("Cargo.toml").exists()
+    {
+        anyhow::bail!("A chain extension already exists in {}", name);
+    }
+    let template = include_bytes!(concat!(env!("OUT_DIR"), "/chain-extension-template.zip"));
+
+    crate::util::unzip(template, out_dir, Some(name))?;
+
+    Ok(())
+}
+
+/// Validates the `name` of the project and creates its directory in `dir`, or the current
+/// directory.
+fn create_out_dir<P>(name: &str, dir: Option<P>) -> Result<PathBuf>
 where
     P: AsRef<Path>,
 {
@@ -44,12 +80,7 @@ where
     if !out_dir.exists() {
         fs::create_dir(&out_dir)?;
     }
-
-    let template = include_bytes!(concat!(env!("OUT_DIR"), "/template.zip"));
-
-    crate::util::unzip(template, out_dir, Some(name))?;
-
-    Ok(())
+    Ok(out_dir)
 }
 
 #[cfg(test)]
@@ -112,6 +143,27 @@ mod tests {
         })
     }
 
+    #[test]
+    fn chain_extension_crates_are_created() {
+        with_tmp_dir(|path| {
+            execute_chain_extension("rand_extension", Some(path))?;
+
+            let dir = path.join("rand_extension");
+            let runtime = fs::read_to_string(dir.join("runtime").join("Cargo.toml"))?;
+            assert!(runtime.contains("name = \"rand_extension_runtime\""));
+            let ink = fs::read_to_string(dir.join("ink").join("lib.rs"))?;
+            assert!(ink.contains("pub trait RandExtension"));
+            assert!(dir.join("README.md").exists());
+
+            let result = execute_chain_extension("rand_extension", Some(path));
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                "A chain extension already exists in rand_extension"
+            );
+            Ok(())
+        })
+    }
+
     #[test]
     fn dont_overwrite_existing_files_not_in_cargo_project() {
         with_tmp_dir(|path| {
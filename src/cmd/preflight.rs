@@ -0,0 +1,284 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A fast check of the crate before it is built, so that a crate which is no contract fails with
+//! a list of what is missing instead of an obscure error after minutes of compiling.
+
+use crate::{maybe_println, workspace::ManifestPath, Verbosity};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use regex::Regex;
+use std::{fs, path::Path};
+use toml::value;
+
+/// The names of the ink! crate providing the `#[ink::contract]` macro, `ink_lang` before ink! 4.
+const INK_CRATES: [&str; 2] = ["ink", "ink_lang"];
+
+/// The outcome of checking the manifest and sources of a crate.
+#[derive(Debug, Default)]
+struct ProjectCheck {
+    /// The requirements of a contract the crate does not fulfill.
+    missing: Vec<String>,
+    /// Settings which do not prevent the build, but are likely unintended.
+    warnings: Vec<String>,
+    /// Whether the crate type is missing, which `cargo contract fix-manifest` adds.
+    missing_crate_type: bool,
+}
+
+/// Checks that the crate at `manifest_path` is a contract, failing with everything it misses.
+///
+/// This only reads the manifest and the sources of the library target, it does not invoke
+/// `cargo`.
+pub(crate) fn check_project(manifest_path: &ManifestPath, verbosity: Verbosity) -> Result<()> {
+    let path = manifest_path.as_ref();
+    let manifest: value::Table =
+        toml::from_slice(&fs::read(path).context(format!("Failed to read {}", path.display()))?)
+            .context(format!("Failed to parse {}", path.display()))?;
+    let check = check_manifest(&manifest, &manifest_path.absolute_directory()?)?;
+
+    for warning in &check.warnings {
+        maybe_println!(verbosity, "{} {}", "warning:".yellow().bold(), warning);
+    }
+    if !check.missing.is_empty() {
+        let hint = if check.missing_crate_type {
+            "\n\nThe crate type can be added with `cargo contract fix-manifest`."
+        } else {
+            ""
+        };
+        anyhow::bail!(
+            "{} is not an ink! contract:\n{}{}",
+            path.display(),
+            check
+                .missing
+                .iter()
+                .map(|missing| format!("  - {}", missing))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            hint
+        )
+    }
+    Ok(())
+}
+
+/// Checks the parsed `manifest` of the crate in `crate_dir`.
+fn check_manifest(manifest: &value::Table, crate_dir: &Path) -> Result<ProjectCheck> {
+    let mut check = ProjectCheck::default();
+    if !manifest.contains_key("package") {
+        check.missing.push(
+            "The manifest has no `[package]` section. To build all contracts of a workspace \
+             use `--workspace`, otherwise pass the `--manifest-path` of a contract."
+                .into(),
+        );
+        return Ok(check);
+    }
+
+    let lib = manifest.get("lib").and_then(value::Value::as_table);
+    // `crate_type` is the deprecated spelling which `cargo` still accepts
+    let is_cdylib = lib
+        .and_then(|lib| lib.get("crate-type").or_else(|| lib.get("crate_type")))
+        .and_then(value::Value::as_array)
+        .map_or(false, |crate_types| {
+            crate_types
+                .iter()
+                .any(|crate_type| crate_type.as_str() == Some("cdylib"))
+        });
+    if !is_cdylib {
+        check.missing_crate_type = true;
+        check.missing.push(
+            "The crate type `cdylib`, add `crate-type = [\"cdylib\"]` to the `[lib]` section."
+                .into(),
+        );
+    }
+
+    // the dependencies of the crate, including the ones of specific targets
+    let target_dependencies = manifest
+        .get("target")
+        .and_then(value::Value::as_table)
+        .into_iter()
+        .flat_map(|targets| targets.values())
+        .filter_map(|target| target.get("dependencies"));
+    let ink_dependency = manifest
+        .get("dependencies")
+        .into_iter()
+        .chain(target_dependencies)
+        .filter_map(value::Value::as_table)
+        .flat_map(|dependencies| dependencies.iter())
+        .find_map(|(name, dependency)| {
+            let package = dependency
+                .get("package")
+                .and_then(value::Value::as_str)
+                .unwrap_or(name);
+            INK_CRATES.iter().copied().find(|ink| *ink == package)
+        });
+    if ink_dependency.is_none() {
+        check.missing.push(
+            "A dependency on ink!, add `ink_lang` (or `ink` since ink! 4) to the \
+             `[dependencies]`."
+                .into(),
+        );
+    }
+
+    let lib_path = lib
+        .and_then(|lib| lib.get("path"))
+        .and_then(value::Value::as_str)
+        .unwrap_or("src/lib.rs");
+    let lib_path = crate_dir.join(lib_path);
+    if !lib_path.exists() {
+        check.missing.push(format!(
+            "The source of the library target, {} does not exist.",
+            lib_path.display()
+        ));
+    } else if !contains_contract(&lib_path)? {
+        check.missing.push(format!(
+            "An `#[ink::contract]` module, none was found in {} or the modules next to it.",
+            lib_path.display()
+        ));
+    }
+
+    let overflow_checks = manifest
+        .get("profile")
+        .and_then(|profile| profile.get("release"))
+        .and_then(|release| release.get("overflow-checks"))
+        .and_then(value::Value::as_bool);
+    if overflow_checks == Some(false) && ink_dependency == Some("ink") {
+        check.warnings.push(
+            "`overflow-checks = false` in `[profile.release]`: arithmetic overflows of the \
+             contract wrap around silently. This was only a workaround for ink! 3."
+                .into(),
+        );
+    }
+    Ok(check)
+}
+
+/// Returns whether the library source at `lib_path`, or a source file in its directory, contains
+/// a `#[contract]` attribute, e.g. `#[ink::contract]` or `#[ink_lang::contract(env = ..)]`.
+fn contains_contract(lib_path: &Path) -> Result<bool> {
+    let attribute =
+        Regex::new(r"#\s*\[(?:[^\]]*::\s*|\s*)contract\s*[\]\(]").expect("the regex is valid");
+    let source_dir = lib_path.parent().unwrap_or_else(|| Path::new("."));
+    let sources = std::iter::once(lib_path.to_path_buf()).chain(
+        walkdir::WalkDir::new(source_dir)
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != "target")
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.into_path())
+            .filter(|path| path.extension().map_or(false, |ext| ext == "rs")),
+    );
+    for source in sources {
+        let source =
+            fs::read_to_string(&source).context(format!("Failed to read {}", source.display()))?;
+        if attribute.is_match(&source) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn everything_missing_is_reported() {
+        with_tmp_dir(|dir| {
+            fs::create_dir(dir.join("src"))?;
+            fs::write(dir.join("src/lib.rs"), "pub fn answer() -> u32 { 42 }")?;
+            let manifest = toml::from_str(
+                r#"
+                [package]
+                name = "not_a_contract"
+
+                [dependencies]
+                scale = { package = "parity-scale-codec", version = "3" }
+                "#,
+            )?;
+
+            let check = check_manifest(&manifest, dir)?;
+            assert_eq!(check.missing.len(), 3, "{:?}", check.missing);
+            assert!(check.missing[0].contains("cdylib"));
+            assert!(check.missing[1].contains("ink_lang"));
+            assert!(check.missing[2].contains("#[ink::contract]"));
+
+            fs::write(
+                dir.join("src/lib.rs"),
+                "#[cfg_attr(feature = \"astar\", ink_lang::contract(env = AstarEnv))]\nmod c {}",
+            )?;
+            let manifest = toml::from_str(
+                r#"
+                [package]
+                name = "contract"
+
+                [dependencies]
+                ink = { version = "4", package = "ink" }
+
+                [lib]
+                crate-type = ["cdylib"]
+
+                [profile.release]
+                overflow-checks = false
+                "#,
+            )?;
+            let check = check_manifest(&manifest, dir)?;
+            assert!(check.missing.is_empty(), "{:?}", check.missing);
+            assert_eq!(check.warnings.len(), 1);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn manifest_on_disk_is_checked() {
+        with_tmp_dir(|dir| {
+            fs::create_dir(dir.join("src"))?;
+            fs::write(
+                dir.join("src/lib.rs"),
+                "#[ink_lang::contract]\nmod flipper {}",
+            )?;
+            fs::write(
+                dir.join("Cargo.toml"),
+                r#"[package]
+name = "flipper"
+version = "0.1.0"
+edition = "2021"
+
+[target.'cfg(target_arch = "wasm32")'.dependencies]
+ink = { version = "3", package = "ink_lang" }
+
+[lib]
+crate-type = ["rlib"]
+"#,
+            )?;
+            let manifest_path = ManifestPath::new(dir.join("Cargo.toml"))?;
+
+            let err = check_project(&manifest_path, Verbosity::Quiet).unwrap_err();
+            let err = err.to_string();
+            assert!(err.contains("is not an ink! contract"), "{}", err);
+            assert!(err.contains("cdylib"), "{}", err);
+            assert!(!err.contains("ink_lang"), "{}", err);
+            assert!(err.contains("cargo contract fix-manifest"), "{}", err);
+
+            let manifest = fs::read_to_string(dir.join("Cargo.toml"))?
+                .replace("crate-type = [\"rlib\"]", "crate_type = [\"cdylib\"]");
+            fs::write(dir.join("Cargo.toml"), manifest)?;
+            check_project(&manifest_path, Verbosity::Quiet)?;
+
+            fs::write(dir.join("src/lib.rs"), "mod flipper {}")?;
+            let err = check_project(&manifest_path, Verbosity::Quiet).unwrap_err();
+            assert!(!err.to_string().contains("fix-manifest"), "{}", err);
+            Ok(())
+        })
+    }
+}
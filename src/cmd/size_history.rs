@@ -0,0 +1,339 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The size of the contract at every git commit it was built with `--size-history`, stored in the
+//! repository as CSV, or as JSON if the file name ends with `.json`.
+
+use crate::{cmd::build::load_module, name_value_println, workspace::ManifestPath, BuildResult};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The location of the history, relative to the directory of the contract.
+const DEFAULT_HISTORY: &str = ".ink/size-history.csv";
+
+/// The columns of the CSV history, in the order of the fields of `SizeRecord`.
+const CSV_HEADER: &str = "commit,date,build_mode,original_size,optimized_size,functions,data_size";
+
+/// The size of the contract built at a git commit.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SizeRecord {
+    /// The abbreviated hash of the commit.
+    pub commit: String,
+    /// The date of the commit, e.g. `2022-04-01`.
+    pub date: String,
+    pub build_mode: String,
+    /// The size of the Wasm before `wasm-opt`, in bytes.
+    pub original_size: u64,
+    /// The size of the optimized Wasm, in bytes.
+    pub optimized_size: u64,
+    /// The number of functions defined in the optimized Wasm.
+    pub functions: usize,
+    /// The size of the data segments of the optimized Wasm, in bytes.
+    pub data_size: usize,
+}
+
+/// Shows the size of the contract at every commit it was built with `--size-history`.
+#[derive(Debug, clap::Args)]
+#[clap(name = "size-history")]
+pub struct SizeHistoryCommand {
+    #[clap(subcommand)]
+    action: SizeHistoryAction,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum SizeHistoryAction {
+    /// Print the recorded sizes and their change from one commit to the next
+    Show {
+        /// Path to the `Cargo.toml` of the contract.
+        #[clap(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
+        /// Path to the history, `.ink/size-history.csv` in the directory of the contract by
+        /// default.
+        #[clap(long, parse(from_os_str))]
+        history: Option<PathBuf>,
+        /// Only show the last `N` records.
+        #[clap(long, value_name = "N")]
+        last: Option<usize>,
+        /// Print the records as JSON.
+        #[clap(long)]
+        output_json: bool,
+    },
+}
+
+impl SizeHistoryCommand {
+    pub fn exec(&self) -> Result<()> {
+        match &self.action {
+            SizeHistoryAction::Show {
+                manifest_path,
+                history,
+                last,
+                output_json,
+            } => {
+                let manifest_path = ManifestPath::try_from(manifest_path.as_ref())?;
+                let path = history_path(&manifest_path, history.as_deref())?;
+                let mut records = load(&path)?;
+                if records.is_empty() {
+                    anyhow::bail!(
+                        "No sizes recorded in {}, they are recorded by \
+                         `cargo contract build --size-history`",
+                        path.display()
+                    )
+                }
+                if let Some(last) = last {
+                    records.drain(..records.len().saturating_sub(*last));
+                }
+                if *output_json {
+                    println!("{}", serde_json::to_string_pretty(&records)?);
+                } else {
+                    print!("{}", display(&records));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Records the sizes of the artifacts of the `result` for the current git commit, replacing a
+/// record of the same commit and build mode.
+pub(crate) fn record(
+    manifest_path: &ManifestPath,
+    history: Option<&Path>,
+    result: &BuildResult,
+) -> Result<()> {
+    let (dest_wasm, optimization) = match (&result.dest_wasm, &result.optimization_result) {
+        (Some(dest_wasm), Some(optimization)) => (dest_wasm, optimization),
+        _ => anyhow::bail!("Recording the size history requires the Wasm to be built"),
+    };
+    let crate_dir = manifest_path.absolute_directory()?;
+    let (commit, date) = current_commit(&crate_dir)?;
+    let module = load_module(dest_wasm)?;
+    let record = SizeRecord {
+        commit,
+        date,
+        build_mode: result.build_mode.to_string(),
+        original_size: optimization.original_bytes,
+        optimized_size: fs::metadata(dest_wasm)?.len(),
+        functions: module
+            .code_section()
+            .map_or(0, |section| section.bodies().len()),
+        data_size: module.data_section().map_or(0, |section| {
+            section
+                .entries()
+                .iter()
+                .map(|segment| segment.value().len())
+                .sum()
+        }),
+    };
+
+    let path = history_path(manifest_path, history)?;
+    let mut records = load(&path)?;
+    match records
+        .iter_mut()
+        .find(|r| r.commit == record.commit && r.build_mode == record.build_mode)
+    {
+        Some(existing) => *existing = record,
+        None => records.push(record),
+    }
+    save(&path, &records)?;
+    if result.verbosity.is_verbose() {
+        name_value_println!("Size history", path.display().to_string());
+    }
+    Ok(())
+}
+
+/// Returns the `history` path, or the default one in the directory of the contract.
+fn history_path(manifest_path: &ManifestPath, history: Option<&Path>) -> Result<PathBuf> {
+    match history {
+        Some(history) => Ok(history.to_path_buf()),
+        None => Ok(manifest_path.absolute_directory()?.join(DEFAULT_HISTORY)),
+    }
+}
+
+/// Returns the abbreviated hash and the date of the commit checked out in `dir`.
+fn current_commit(dir: &Path) -> Result<(String, String)> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%h %cs"])
+        .current_dir(dir)
+        .output()
+        .context("Failed to run `git`, which is required to record the size history")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "The size history is recorded per git commit, but no commit is checked out in {}: {}",
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+    let stdout = String::from_utf8(output.stdout)?;
+    let (commit, date) = stdout
+        .trim()
+        .split_once(' ')
+        .context("Unexpected output of `git log`")?;
+    Ok((commit.to_string(), date.to_string()))
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext == "json")
+}
+
+/// Loads the records of the history at `path`, none if it does not exist yet.
+fn load(path: &Path) -> Result<Vec<SizeRecord>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    let records = if is_json(path) {
+        serde_json::from_str(&content).map_err(Into::into)
+    } else {
+        parse_csv(&content)
+    };
+    records.context(format!("Failed to parse {}", path.display()))
+}
+
+fn save(path: &Path, records: &[SizeRecord]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let content = if is_json(path) {
+        serde_json::to_string_pretty(records)? + "\n"
+    } else {
+        to_csv(records)
+    };
+    fs::write(path, content).context(format!("Failed to write {}", path.display()))
+}
+
+fn to_csv(records: &[SizeRecord]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for r in records {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            r.commit,
+            r.date,
+            r.build_mode,
+            r.original_size,
+            r.optimized_size,
+            r.functions,
+            r.data_size
+        );
+    }
+    csv
+}
+
+fn parse_csv(csv: &str) -> Result<Vec<SizeRecord>> {
+    csv.lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(index, line)| {
+            let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+            if fields.len() != 7 {
+                anyhow::bail!(
+                    "Line {} has {} columns, expected `{}`",
+                    index + 2,
+                    fields.len(),
+                    CSV_HEADER
+                )
+            }
+            let number = |field: &str| {
+                field
+                    .parse()
+                    .context(format!("Line {}: invalid number `{}`", index + 2, field))
+            };
+            Ok(SizeRecord {
+                commit: fields[0].to_string(),
+                date: fields[1].to_string(),
+                build_mode: fields[2].to_string(),
+                original_size: number(fields[3])?,
+                optimized_size: number(fields[4])?,
+                functions: number(fields[5])? as usize,
+                data_size: number(fields[6])? as usize,
+            })
+        })
+        .collect()
+}
+
+/// Displays the records as a table, with the change of the optimized size from the previous
+/// record of the same build mode.
+fn display(records: &[SizeRecord]) -> String {
+    let mut table = format!(
+        "{:<12} {:<10} {:<8} {:>10} {:>10} {:>9} {:>10} {:>8}\n",
+        "Commit", "Date", "Mode", "Original", "Optimized", "Change", "Functions", "Data"
+    );
+    for (index, r) in records.iter().enumerate() {
+        let change = records[..index]
+            .iter()
+            .rev()
+            .find(|previous| previous.build_mode == r.build_mode)
+            .map_or_else(
+                || "-".to_string(),
+                |previous| {
+                    format!(
+                        "{:+}",
+                        r.optimized_size as i64 - previous.optimized_size as i64
+                    )
+                },
+            );
+        let _ = writeln!(
+            table,
+            "{:<12} {:<10} {:<8} {:>10} {:>10} {:>9} {:>10} {:>8}",
+            r.commit,
+            r.date,
+            r.build_mode,
+            r.original_size,
+            r.optimized_size,
+            change,
+            r.functions,
+            r.data_size
+        );
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_round_trips_and_displays_changes() {
+        let record = |commit: &str, optimized_size| SizeRecord {
+            commit: commit.into(),
+            date: "2022-04-01".into(),
+            build_mode: "release".into(),
+            original_size: 40_000,
+            optimized_size,
+            functions: 42,
+            data_size: 512,
+        };
+        let records = vec![record("1a2b3c4", 12_000), record("5d6e7f8", 12_345)];
+
+        let csv = to_csv(&records);
+        assert!(csv.starts_with(CSV_HEADER));
+        assert_eq!(parse_csv(&csv).unwrap(), records);
+        assert!(parse_csv(&format!("{}\n1a2b3c4,2022-04-01\n", CSV_HEADER)).is_err());
+
+        let table = display(&records);
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains("1a2b3c4") && lines[1].contains(" - "));
+        assert!(lines[2].contains("+345"));
+    }
+}
@@ -14,10 +14,21 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{maybe_println, util, workspace::ManifestPath, Verbosity, VerbosityFlags};
-use anyhow::Result;
-use colored::Colorize;
-use std::{convert::TryFrom, path::PathBuf};
+use crate::{step_println, util, workspace::ManifestPath, Verbosity, VerbosityFlags};
+use anyhow::{Context, Result};
+use std::{
+    convert::TryFrom,
+    path::{Path, PathBuf},
+};
+
+/// The target the tests are compiled for with `--wasm`.
+///
+/// Unlike `wasm32-unknown-unknown` it provides the system interface the test harness requires to
+/// report its results, while sharing the 32-bit `usize` and the instruction set of contracts.
+const WASM_TEST_TARGET: &str = "wasm32-wasi";
+
+/// The runner the tests compiled to Wasm are executed with by default.
+const DEFAULT_WASM_RUNNER: &str = "wasmtime";
 
 /// Executes smart contract tests off-chain by delegating to `cargo test`.
 #[derive(Debug, clap::Args)]
@@ -26,16 +37,40 @@ pub struct TestCommand {
     /// Path to the `Cargo.toml` of the contract to test.
     #[clap(long, parse(from_os_str))]
     manifest_path: Option<PathBuf>,
+    /// Additionally compile the tests to Wasm and run them with the `--wasm-runner`, to catch
+    /// issues which only occur in Wasm, e.g. assumptions of a 64-bit `usize`.
+    ///
+    /// The tests are compiled for `wasm32-wasi`, which must be installed with
+    /// `rustup target add wasm32-wasi`.
+    #[clap(long)]
+    wasm: bool,
+    /// The executable running the tests compiled to Wasm, e.g. `wasmtime` or `wasmi_cli`.
+    /// Defaults to `wasmtime` from the `PATH`.
+    #[clap(long, parse(from_os_str), requires = "wasm")]
+    wasm_runner: Option<PathBuf>,
     #[clap(flatten)]
     verbosity: VerbosityFlags,
+    /// Arguments which are passed on to `cargo test`, e.g.
+    /// `cargo contract test -- my_test -- --nocapture`
+    #[clap(last = true)]
+    test_args: Vec<String>,
 }
 
 impl TestCommand {
     pub fn exec(&self) -> Result<TestResult> {
         let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
         let verbosity = TryFrom::<&VerbosityFlags>::try_from(&self.verbosity)?;
+        let wasm_runner = match self.wasm {
+            true => Some(wasm_runner(self.wasm_runner.as_deref())?),
+            false => None,
+        };
 
-        execute(&manifest_path, verbosity)
+        execute(
+            &manifest_path,
+            wasm_runner.as_deref(),
+            &self.test_args,
+            verbosity,
+        )
     }
 }
 
@@ -53,22 +88,88 @@ impl TestResult {
     }
 }
 
-/// Executes `cargo +nightly test`.
-pub(crate) fn execute(manifest_path: &ManifestPath, verbosity: Verbosity) -> Result<TestResult> {
+/// Executes `cargo +nightly test` with the `args`, and again for the Wasm test target with the
+/// `wasm_runner` if one is given.
+pub(crate) fn execute(
+    manifest_path: &ManifestPath,
+    wasm_runner: Option<&Path>,
+    args: &[String],
+    verbosity: Verbosity,
+) -> Result<TestResult> {
     util::assert_channel()?;
+    let steps = if wasm_runner.is_some() { 2 } else { 1 };
 
-    maybe_println!(
-        verbosity,
-        " {} {}",
-        format!("[{}/{}]", 1, 1).bold(),
-        "Running tests".bright_green().bold()
-    );
+    step_println!(verbosity, 1, steps, "Running tests");
 
-    let stdout = util::invoke_cargo("test", &[""], manifest_path.directory(), verbosity, vec![])?;
+    let mut stdout =
+        util::invoke_cargo("test", args, manifest_path.directory(), verbosity, vec![])?;
+
+    if let Some(wasm_runner) = wasm_runner {
+        step_println!(
+            verbosity,
+            2,
+            steps,
+            format!(
+                "Running tests compiled to Wasm with {}",
+                wasm_runner.display()
+            )
+        );
+        let (runner_key, runner) = runner_env(wasm_runner);
+        stdout.extend(util::invoke_cargo(
+            "test",
+            ["--target", WASM_TEST_TARGET]
+                .iter()
+                .copied()
+                .chain(args.iter().map(String::as_str))
+                .collect::<Vec<_>>(),
+            manifest_path.directory(),
+            verbosity,
+            vec![(runner_key.as_str(), Some(runner.as_str()))],
+        )?);
+    }
 
     Ok(TestResult { stdout, verbosity })
 }
 
+/// Returns the path of the `runner` of the tests compiled to Wasm, `wasmtime` by default.
+fn wasm_runner(runner: Option<&Path>) -> Result<PathBuf> {
+    let runner = runner.unwrap_or_else(|| Path::new(DEFAULT_WASM_RUNNER));
+    which::which(runner).context(format!(
+        "The Wasm test runner `{}` was not found. Install wasmtime from https://wasmtime.dev, \
+         or pass the path of another runner with `--wasm-runner`.",
+        runner.display()
+    ))
+}
+
+/// Returns the environment variable which makes `cargo` execute the tests of the Wasm test target
+/// with the `runner`.
+fn runner_env(runner: &Path) -> (String, String) {
+    (
+        format!(
+            "CARGO_TARGET_{}_RUNNER",
+            WASM_TEST_TARGET.to_uppercase().replace('-', "_")
+        ),
+        runner.display().to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wasm_runner_is_configured_for_the_test_target() {
+        assert_eq!(
+            runner_env(Path::new("/usr/bin/wasmtime")),
+            (
+                "CARGO_TARGET_WASM32_WASI_RUNNER".to_string(),
+                "/usr/bin/wasmtime".to_string()
+            )
+        );
+        assert!(wasm_runner(Some(Path::new("/does/not/exist/wasmtime"))).is_err());
+    }
+}
+
 #[cfg(feature = "test-ci-only")]
 #[cfg(test)]
 mod tests_ci_only {
@@ -82,8 +183,8 @@ mod tests_ci_only {
                 Regex::new(r"test result: ok. \d+ passed; 0 failed; \d+ ignored")
                     .expect("regex pattern compilation failed");
 
-            let res =
-                super::execute(&manifest_path, Verbosity::Default).expect("test execution failed");
+            let res = super::execute(&manifest_path, None, &[], Verbosity::Default)
+                .expect("test execution failed");
 
             assert!(ok_output_pattern.is_match(&String::from_utf8_lossy(&res.stdout)));
 
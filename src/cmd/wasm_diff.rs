@@ -0,0 +1,351 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A semantic diff of two contract Wasm files, e.g. of two releases under review.
+//!
+//! Functions are matched by their name in the `name` section or their export, and the remaining
+//! ones by their signature and body. The indices of called functions are ignored for the
+//! matching, since adding a single function shifts the indices of all functions after it.
+
+use super::inspect::section_name;
+use crate::{cmd::build::load_module, name_value_println};
+use anyhow::Result;
+use parity_wasm::elements::{External, Instruction, Internal, Module, Type};
+use serde::Serialize;
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Compare two contract Wasm files by their functions, imports, exports and section sizes.
+#[derive(Debug, clap::Args)]
+#[clap(name = "wasm-diff")]
+pub struct WasmDiffCommand {
+    /// Path to the old Wasm file.
+    #[clap(parse(from_os_str))]
+    old: PathBuf,
+    /// Path to the new Wasm file.
+    #[clap(parse(from_os_str))]
+    new: PathBuf,
+    /// Print the diff as JSON.
+    #[clap(long)]
+    output_json: bool,
+}
+
+/// The differences between two Wasm modules.
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WasmDiff {
+    /// The sizes of the sections of both modules in bytes, by their name.
+    sections: BTreeMap<String, (usize, usize)>,
+    added_imports: Vec<String>,
+    removed_imports: Vec<String>,
+    added_exports: Vec<String>,
+    removed_exports: Vec<String>,
+    added_functions: Vec<Function>,
+    removed_functions: Vec<Function>,
+    /// The functions with the same name but a different signature or body, old and new.
+    changed_functions: Vec<(Function, Function)>,
+    unchanged_functions: usize,
+}
+
+/// A function defined by a module.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Function {
+    /// The index in the function index space, which includes the imported functions.
+    index: usize,
+    /// The name from the `name` section or the export.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// The size of the body in bytes.
+    size: usize,
+    /// The signature and the body with the indices of called functions zeroed.
+    #[serde(skip)]
+    fingerprint: Vec<u8>,
+}
+
+impl WasmDiffCommand {
+    pub fn exec(&self) -> Result<()> {
+        let old = load_module(&self.old)?;
+        let new = load_module(&self.new)?;
+        let diff = diff(old, new)?;
+        if self.output_json {
+            println!("{}", serde_json::to_string_pretty(&diff)?);
+            return Ok(());
+        }
+
+        let (old_size, new_size) = diff
+            .sections
+            .values()
+            .fold((0, 0), |(old, new), sizes| (old + sizes.0, new + sizes.1));
+        name_value_println!("Size", size_change(old_size, new_size));
+        for (section, (old, new)) in &diff.sections {
+            if old != new {
+                name_value_println!(
+                    "Section",
+                    format!("{}: {}", section, size_change(*old, *new))
+                );
+            }
+        }
+        for import in &diff.added_imports {
+            name_value_println!("Import", format!("+ {}", import));
+        }
+        for import in &diff.removed_imports {
+            name_value_println!("Import", format!("- {}", import));
+        }
+        for export in &diff.added_exports {
+            name_value_println!("Export", format!("+ {}", export));
+        }
+        for export in &diff.removed_exports {
+            name_value_println!("Export", format!("- {}", export));
+        }
+        for (old, new) in &diff.changed_functions {
+            name_value_println!(
+                "Function",
+                format!("~ {}: {}", new.label(), size_change(old.size, new.size))
+            );
+        }
+        for function in &diff.added_functions {
+            name_value_println!(
+                "Function",
+                format!("+ {} ({} bytes)", function.label(), function.size)
+            );
+        }
+        for function in &diff.removed_functions {
+            name_value_println!(
+                "Function",
+                format!("- {} ({} bytes)", function.label(), function.size)
+            );
+        }
+        name_value_println!(
+            "Unchanged",
+            format!("{} functions", diff.unchanged_functions)
+        );
+        Ok(())
+    }
+}
+
+impl Function {
+    fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{} (func[{}])", name, self.index),
+            None => format!("func[{}]", self.index),
+        }
+    }
+}
+
+fn size_change(old: usize, new: usize) -> String {
+    format!("{} -> {} bytes ({:+})", old, new, new as i64 - old as i64)
+}
+
+fn diff(old: Module, new: Module) -> Result<WasmDiff> {
+    let mut diff = WasmDiff::default();
+    for (module, is_new) in [(&old, false), (&new, true)] {
+        for section in module.sections() {
+            let size = parity_wasm::serialize(section.clone())?.len();
+            let sizes = diff.sections.entry(section_name(section)).or_default();
+            if is_new {
+                sizes.1 += size
+            } else {
+                sizes.0 += size
+            }
+        }
+    }
+
+    let (old_imports, new_imports) = (imports(&old), imports(&new));
+    diff.added_imports = difference(&new_imports, &old_imports);
+    diff.removed_imports = difference(&old_imports, &new_imports);
+    let (old_exports, new_exports) = (exports(&old), exports(&new));
+    diff.added_exports = difference(&new_exports, &old_exports);
+    diff.removed_exports = difference(&old_exports, &new_exports);
+
+    let mut old_functions = functions(old)?;
+    for function in functions(new)? {
+        let matching = old_functions.iter().position(|old| match &function.name {
+            Some(name) => old.name.as_ref() == Some(name),
+            None => old.name.is_none() && old.fingerprint == function.fingerprint,
+        });
+        match matching.map(|index| old_functions.remove(index)) {
+            Some(old) if old.fingerprint == function.fingerprint => diff.unchanged_functions += 1,
+            Some(old) => diff.changed_functions.push((old, function)),
+            None => diff.added_functions.push(function),
+        }
+    }
+    diff.removed_functions = old_functions;
+    Ok(diff)
+}
+
+/// Returns the items of `a` which are not in `b`.
+fn difference(a: &[String], b: &[String]) -> Vec<String> {
+    a.iter().filter(|item| !b.contains(item)).cloned().collect()
+}
+
+fn imports(module: &Module) -> Vec<String> {
+    let entries = module
+        .import_section()
+        .map(|section| section.entries())
+        .unwrap_or_default();
+    entries
+        .iter()
+        .map(|import| {
+            let kind = match import.external() {
+                External::Function(type_index) => signature(module, *type_index),
+                External::Table(_) => "table".to_string(),
+                External::Memory(_) => "memory".to_string(),
+                External::Global(_) => "global".to_string(),
+            };
+            format!("{}::{} {}", import.module(), import.field(), kind)
+        })
+        .collect()
+}
+
+fn exports(module: &Module) -> Vec<String> {
+    let entries = module
+        .export_section()
+        .map(|section| section.entries())
+        .unwrap_or_default();
+    entries
+        .iter()
+        .map(|export| {
+            let kind = match export.internal() {
+                Internal::Function(_) => "function",
+                Internal::Table(_) => "table",
+                Internal::Memory(_) => "memory",
+                Internal::Global(_) => "global",
+            };
+            format!("{} ({})", export.field(), kind)
+        })
+        .collect()
+}
+
+/// Returns the signature of the function type at `type_index`, e.g. `(i32, i32) -> i32`.
+fn signature(module: &Module, type_index: u32) -> String {
+    let types = module
+        .type_section()
+        .map(|section| section.types())
+        .unwrap_or_default();
+    match types.get(type_index as usize) {
+        Some(Type::Function(function)) => {
+            let params = function
+                .params()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            let results = function
+                .results()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            format!("({}) -> ({})", params.join(", "), results.join(", "))
+        }
+        None => format!("type {}", type_index),
+    }
+}
+
+/// Returns the functions defined by the `module`, in the order of their index.
+fn functions(module: Module) -> Result<Vec<Function>> {
+    let module = module.parse_names().unwrap_or_else(|(_, module)| module);
+    let imported = module.import_count(parity_wasm::elements::ImportCountType::Function);
+    let mut names = BTreeMap::new();
+    for export in module
+        .export_section()
+        .map(|section| section.entries())
+        .unwrap_or_default()
+    {
+        if let Internal::Function(index) = export.internal() {
+            names.insert(*index as usize, export.field().to_string());
+        }
+    }
+    if let Some(function_names) = module.names_section().and_then(|names| names.functions()) {
+        for (index, name) in function_names.names() {
+            names.insert(index as usize, name.clone());
+        }
+    }
+
+    let declarations = module
+        .function_section()
+        .map(|section| section.entries())
+        .unwrap_or_default();
+    let bodies = module
+        .code_section()
+        .map(|section| section.bodies())
+        .unwrap_or_default();
+    let mut functions = Vec::new();
+    for (position, (declaration, body)) in declarations.iter().zip(bodies).enumerate() {
+        let index = imported + position;
+        let size = parity_wasm::serialize(body.clone())?.len();
+        let mut normalized = body.clone();
+        for instruction in normalized.code_mut().elements_mut() {
+            if let Instruction::Call(_) = instruction {
+                *instruction = Instruction::Call(0);
+            }
+        }
+        let mut fingerprint = signature(&module, declaration.type_ref()).into_bytes();
+        fingerprint.extend(parity_wasm::serialize(normalized)?);
+        functions.push(Function {
+            index,
+            name: names.get(&index).cloned(),
+            size,
+            fingerprint,
+        });
+    }
+    Ok(functions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(wat: &str) -> Module {
+        let wasm = wabt::wat2wasm(wat).expect("invalid wat");
+        parity_wasm::deserialize_buffer(&wasm).expect("invalid wasm")
+    }
+
+    #[test]
+    fn functions_are_matched_despite_shifted_indices() {
+        let old = module(
+            r#"(module
+                (import "seal0" "seal_input" (func (param i32 i32)))
+                (func $helper (result i32) i32.const 1)
+                (func (export "call") (drop (call $helper)))
+                (func (export "deploy"))
+            )"#,
+        );
+        let new = module(
+            r#"(module
+                (import "seal0" "seal_input" (func (param i32 i32)))
+                (import "seal1" "seal_call" (func (param i32) (result i32)))
+                (func $helper (result i32) i32.const 1)
+                (func $added (result i64) i64.const 2)
+                (func (export "call") (drop (call $helper)) (drop (call $added)))
+                (func (export "deploy"))
+            )"#,
+        );
+
+        let diff = diff(old, new).unwrap();
+        assert_eq!(diff.added_imports, ["seal1::seal_call (i32) -> (i32)"]);
+        assert!(diff.removed_imports.is_empty());
+        assert!(diff.added_exports.is_empty());
+        assert_eq!(diff.unchanged_functions, 2);
+        assert_eq!(
+            diff.changed_functions
+                .iter()
+                .map(|(old, new)| (old.label(), new.label()))
+                .collect::<Vec<_>>(),
+            [("call (func[2])".to_string(), "call (func[4])".to_string())]
+        );
+        assert_eq!(diff.added_functions.len(), 1);
+        assert_eq!(diff.added_functions[0].index, 3);
+        assert!(diff.removed_functions.is_empty());
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use url::Url;
+
+/// Environment variable which overrides the directory the user configuration is read from.
+const CONFIG_DIR_ENV: &str = "CARGO_CONTRACT_CONFIG_DIR";
+
+/// Environment variable which overrides the directory downloaded tools are cached in.
+const DATA_DIR_ENV: &str = "CARGO_CONTRACT_DATA_DIR";
+
+/// Name of the user configuration file inside of the configuration directory.
+const CONFIG_FILE: &str = "config.toml";
+
+/// The user configuration file given with `--config`, read instead of [`CONFIG_FILE`].
+static CONFIG_FILE_OVERRIDE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// The user configuration of `cargo-contract`.
+///
+/// It is read from `config.toml` in the directory returned by [`config_dir`], e.g.
+///
+/// ```toml
+/// [networks.rococo]
+/// url = "wss://rococo-contracts-rpc.polkadot.io"
+/// fallback-urls = ["wss://rococo-contracts.example"]
+///
+/// [networks.rococo.explorer]
+/// extrinsic = "https://rococo.subscan.io/extrinsic/{extrinsic}"
+/// contract = "https://rococo.subscan.io/account/{contract}"
+///
+/// [networks.statemine]
+/// url = "wss://statemine-rpc.polkadot.io"
+///
+/// [[networks.statemine.signed-extensions]]
+/// identifier = "ChargeAssetTxPayment"
+/// extra = "0x0000"
+///
+/// [ci]
+/// toolchain = "nightly-2022-06-30"
+/// networks = ["rococo"]
+///
+/// [defaults.call]
+/// network = "rococo"
+/// ```
+///
+/// A different file can be given with `--config-file <path>` or `CARGO_CONTRACT_CONFIG_FILE`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Named network profiles, selected with `--network <name>`.
+    #[serde(default)]
+    pub networks: BTreeMap<String, NetworkProfile>,
+    /// Defaults of the pipelines generated by `cargo contract init-ci`.
+    #[serde(default)]
+    pub ci: CiConfig,
+    /// The defaults of the command line options, see [`crate::defaults`].
+    #[serde(default)]
+    pub defaults: toml::value::Table,
+}
+
+/// The settings for interacting with a specific chain.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct NetworkProfile {
+    /// Websockets url of a node of the network.
+    pub url: Url,
+    /// Websockets urls of other nodes of the network, failed over to in order if the node at
+    /// `url` is down, syncing or its finality lags behind.
+    #[serde(default)]
+    pub fallback_urls: Vec<Url>,
+    /// How many blocks the finalized block of a node may lag behind its best block for the node
+    /// to be healthy, checked if there are `fallback-urls`.
+    #[serde(default = "default_max_finality_lag")]
+    pub max_finality_lag: u32,
+    /// Url templates of a block explorer for this network.
+    #[serde(default)]
+    pub explorer: ExplorerUrls,
+    /// The signed extensions of the network which are not supported by default.
+    #[serde(default)]
+    pub signed_extensions: Vec<SignedExtensionConfig>,
+    /// Whether the nodes run a local fork of a live chain, e.g. with `chopsticks`, to which
+    /// `simulate-upgrade` may submit the upgrade.
+    #[serde(default)]
+    pub fork: bool,
+}
+
+/// How many blocks finality may lag behind by default, see [`NetworkProfile::max_finality_lag`].
+pub const DEFAULT_MAX_FINALITY_LAG: u32 = 20;
+
+fn default_max_finality_lag() -> u32 {
+    DEFAULT_MAX_FINALITY_LAG
+}
+
+/// The SCALE encoded values of a signed extension of a network, e.g. `ChargeAssetTxPayment`.
+///
+/// The values of a signed extension which is supported by default, e.g.
+/// `ChargeTransactionPayment`, replace the default ones.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SignedExtensionConfig {
+    /// The identifier of the signed extension in the runtime metadata.
+    pub identifier: String,
+    /// The hex encoded value which is included in the extrinsic, e.g. the tip.
+    #[serde(default)]
+    pub extra: String,
+    /// The hex encoded value which is only included in the signed payload.
+    #[serde(default)]
+    pub additional_signed: String,
+}
+
+/// Url templates of a block explorer.
+///
+/// The placeholders `{block}`, `{extrinsic}`, `{contract}` and `{code_hash}` are replaced with the
+/// respective `0x` prefixed hash or the SS58 address of the contract.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExplorerUrls {
+    pub block: Option<String>,
+    pub extrinsic: Option<String>,
+    pub contract: Option<String>,
+    pub code_hash: Option<String>,
+}
+
+/// Defaults of the CI pipelines generated for contract projects.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CiConfig {
+    /// The Rust toolchain the contract is built with, e.g. `nightly-2022-06-30`.
+    pub toolchain: Option<String>,
+    /// The network profiles the code of the contract is uploaded to on tagged commits.
+    #[serde(default)]
+    pub networks: Vec<String>,
+}
+
+impl Config {
+    /// Load the user configuration, returns the default configuration if the file does not exist.
+    pub fn load() -> Result<Self> {
+        if let Some(path) = config_file_override() {
+            if !path.exists() {
+                anyhow::bail!("The config file {} does not exist", path.display())
+            }
+            return Self::load_from(&path);
+        }
+        match config_dir() {
+            Some(dir) => Self::load_from(&dir.join(CONFIG_FILE)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Load the configuration from the file at `path`, returns the default configuration if the
+    /// file does not exist.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .context(format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents).context(format!("Failed to parse config file {}", path.display()))
+    }
+
+    /// Returns the network profile with the given `name`.
+    pub fn network(&self, name: &str) -> Result<&NetworkProfile> {
+        self.networks.get(name).ok_or_else(|| {
+            let known = self.networks.keys().cloned().collect::<Vec<_>>();
+            anyhow!(
+                "Network '{}' is not configured. Known networks: [{}]",
+                name,
+                known.join(", ")
+            )
+        })
+    }
+}
+
+impl ExplorerUrls {
+    /// Renders the given url `template`, replacing the `placeholder` with `value`.
+    pub fn render(template: &str, placeholder: &str, value: &str) -> String {
+        template.replace(&format!("{{{}}}", placeholder), value)
+    }
+}
+
+/// Reads the user configuration from the file at `path` instead of the configuration directory.
+pub fn set_config_file(path: PathBuf) {
+    if let Ok(mut config_file) = CONFIG_FILE_OVERRIDE.lock() {
+        *config_file = Some(path);
+    }
+}
+
+fn config_file_override() -> Option<PathBuf> {
+    CONFIG_FILE_OVERRIDE
+        .lock()
+        .ok()
+        .and_then(|config_file| config_file.clone())
+}
+
+/// Returns the directory containing the `cargo-contract` user configuration.
+///
+/// This is `$CARGO_CONTRACT_CONFIG_DIR` if set, otherwise `cargo-contract` inside of the
+/// platform's configuration directory.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(CONFIG_DIR_ENV) {
+        return Some(PathBuf::from(dir));
+    }
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|dir| dir.join("cargo-contract"))
+}
+
+/// Returns the directory `cargo-contract` caches downloaded tools in, e.g. `wasm-opt`.
+///
+/// This is `$CARGO_CONTRACT_DATA_DIR` if set, otherwise `cargo-contract` inside of the
+/// platform's data directory.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV) {
+        return Some(PathBuf::from(dir));
+    }
+    let base = if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".local").join("share"))
+            })
+    };
+    base.map(|dir| dir.join("cargo-contract"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn network_profiles_are_parsed() {
+        with_tmp_dir(|path| {
+            let config_file = path.join(CONFIG_FILE);
+            std::fs::write(
+                &config_file,
+                r#"
+                [networks.rococo]
+                url = "wss://rococo-contracts-rpc.polkadot.io"
+                fallback-urls = ["wss://rococo.example"]
+
+                [networks.rococo.explorer]
+                extrinsic = "https://explorer.example/extrinsic/{extrinsic}"
+
+                [ci]
+                toolchain = "nightly-2022-06-30"
+                networks = ["rococo"]
+                "#,
+            )?;
+
+            let config = Config::load_from(&config_file)?;
+            let rococo = config.network("rococo")?;
+            assert_eq!(
+                rococo.url.as_str(),
+                "wss://rococo-contracts-rpc.polkadot.io/"
+            );
+            assert_eq!(rococo.fallback_urls[0].as_str(), "wss://rococo.example/");
+            assert_eq!(rococo.max_finality_lag, 20);
+            assert_eq!(
+                rococo.explorer.extrinsic.as_deref(),
+                Some("https://explorer.example/extrinsic/{extrinsic}")
+            );
+            assert!(rococo.explorer.contract.is_none());
+            assert!(config.network("kusama").is_err());
+            assert_eq!(config.ci.toolchain.as_deref(), Some("nightly-2022-06-30"));
+            assert_eq!(config.ci.networks, vec!["rococo".to_string()]);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn missing_config_file_yields_default() {
+        with_tmp_dir(|path| {
+            let config = Config::load_from(&path.join(CONFIG_FILE))?;
+            assert!(config.networks.is_empty());
+            assert!(config.ci.toolchain.is_none());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn explorer_url_is_rendered() {
+        let url = ExplorerUrls::render("https://explorer.example/block/{block}", "block", "0x01");
+        assert_eq!(url, "https://explorer.example/block/0x01");
+    }
+}
@@ -14,12 +14,20 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::ManifestPath;
+use crate::{
+    cmd::build::{parse_size, parse_version, BuildHooks},
+    toolchain::WasmTarget,
+    CargoFlags, ManifestPath, PanicMode,
+};
 use anyhow::{Context, Result};
-use cargo_metadata::{Metadata as CargoMetadata, MetadataCommand, Package};
+use cargo_metadata::{Metadata as CargoMetadata, MetadataCommand, Package, Target};
 use semver::Version;
 use serde_json::{Map, Value};
-use std::{fs, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 use toml::value;
 use url::Url;
 
@@ -38,24 +46,41 @@ pub struct CrateMetadata {
     pub documentation: Option<Url>,
     pub homepage: Option<Url>,
     pub user: Option<Map<String, Value>>,
+    pub hooks: BuildHooks,
+    /// The crates of the standard library to build, from `[package.metadata.contract]`.
+    pub build_std: Vec<String>,
+    /// How panics are handled, from `[package.metadata.contract]`.
+    pub panic: Option<PanicMode>,
+    /// The custom sections embedded into the Wasm, from
+    /// `[package.metadata.contract.custom-sections]`.
+    pub custom_sections: BTreeMap<String, String>,
+    /// The maximum size of the optimized Wasm in bytes, `max-size` of
+    /// `[package.metadata.contract]`.
+    pub max_size: Option<u64>,
+    /// The binaryen release whose `wasm-opt` optimizes the contract, `wasm-opt-version` of
+    /// `[package.metadata.contract]`.
+    pub wasm_opt_version: Option<u32>,
+    /// The Wasm target the contract is compiled for, `wasm32-unknown-unknown` unless
+    /// `-Z wasm64` is passed.
+    pub wasm_target: WasmTarget,
     pub target_directory: PathBuf,
 }
 
 impl CrateMetadata {
     /// Parses the contract manifest and returns relevant metadata.
     pub fn collect(manifest_path: &ManifestPath) -> Result<Self> {
-        let (metadata, root_package) = get_cargo_metadata(manifest_path)?;
+        Self::collect_with(manifest_path, &CargoFlags::default())
+    }
+
+    /// Parses the contract manifest and returns relevant metadata, forwarding the `cargo_flags`
+    /// to `cargo metadata`.
+    pub fn collect_with(manifest_path: &ManifestPath, cargo_flags: &CargoFlags) -> Result<Self> {
+        let (metadata, root_package) = get_cargo_metadata(manifest_path, cargo_flags)?;
         let mut target_directory = metadata.target_directory.as_path().join("ink");
 
         // Normalize the package and lib name.
         let package_name = root_package.name.replace('-', "_");
-        let lib_name = &root_package
-            .targets
-            .iter()
-            .find(|target| target.kind.iter().any(|t| t == "cdylib"))
-            .expect("lib name not found")
-            .name
-            .replace('-', "_");
+        let lib_name = &contract_lib_target(&root_package)?.name.replace('-', "_");
 
         let absolute_manifest_path = manifest_path.absolute_directory()?;
         let absolute_workspace_root = metadata.workspace_root.canonicalize()?;
@@ -65,12 +90,8 @@ impl CrateMetadata {
             target_directory = target_directory.join(package_name);
         }
 
-        // {target_dir}/wasm32-unknown-unknown/release/{lib_name}.wasm
-        let mut original_wasm = target_directory.clone();
-        original_wasm.push("wasm32-unknown-unknown");
-        original_wasm.push("release");
-        original_wasm.push(lib_name.clone());
-        original_wasm.set_extension("wasm");
+        let wasm_target = WasmTarget::default();
+        let original_wasm = original_wasm_path(&target_directory, wasm_target, lib_name);
 
         // {target_dir}/{lib_name}.wasm
         let mut dest_wasm = target_directory.clone();
@@ -81,7 +102,8 @@ impl CrateMetadata {
             .packages
             .iter()
             .find_map(|package| {
-                if package.name == "ink_lang" {
+                // since ink! 4 the crate is named `ink` instead of `ink_lang`
+                if package.name == "ink_lang" || package.name == "ink" {
                     Some(
                         Version::parse(&package.version.to_string())
                             .expect("Invalid ink_lang version string"),
@@ -90,12 +112,18 @@ impl CrateMetadata {
                     None
                 }
             })
-            .ok_or_else(|| anyhow::anyhow!("No 'ink_lang' dependency found"))?;
+            .ok_or_else(|| anyhow::anyhow!("No 'ink' or 'ink_lang' dependency found"))?;
 
         let ExtraMetadata {
             documentation,
             homepage,
             user,
+            hooks,
+            build_std,
+            panic,
+            custom_sections,
+            max_size,
+            wasm_opt_version,
         } = get_cargo_toml_metadata(manifest_path)?;
 
         let crate_metadata = CrateMetadata {
@@ -103,12 +131,19 @@ impl CrateMetadata {
             cargo_meta: metadata,
             root_package,
             contract_artifact_name: lib_name.to_string(),
-            original_wasm: original_wasm.into(),
+            original_wasm,
             dest_wasm: dest_wasm.into(),
             ink_version,
             documentation,
             homepage,
             user,
+            hooks,
+            build_std,
+            panic,
+            custom_sections,
+            max_size,
+            wasm_opt_version,
+            wasm_target,
             target_directory: target_directory.into(),
         };
         Ok(crate_metadata)
@@ -118,10 +153,46 @@ impl CrateMetadata {
     pub fn metadata_path(&self) -> PathBuf {
         self.target_directory.join(METADATA_FILE)
     }
+
+    /// Returns the path of the source file of the contract's library target, e.g. `lib.rs`.
+    pub fn lib_src_path(&self) -> Result<PathBuf> {
+        Ok(contract_lib_target(&self.root_package)?
+            .src_path
+            .clone()
+            .into())
+    }
+
+    /// Compiles the contract for the `wasm_target`, which changes the location of the
+    /// `original_wasm` built by `cargo`.
+    pub(crate) fn set_wasm_target(&mut self, wasm_target: WasmTarget) {
+        self.wasm_target = wasm_target;
+        self.original_wasm = original_wasm_path(
+            &self.target_directory,
+            wasm_target,
+            &self.contract_artifact_name,
+        );
+    }
+}
+
+/// Returns the path of the Wasm built by `cargo`:
+/// `{target_dir}/{target triple}/release/{lib_name}.wasm`.
+fn original_wasm_path<P: AsRef<Path>>(
+    target_directory: P,
+    wasm_target: WasmTarget,
+    lib_name: &str,
+) -> PathBuf {
+    let mut original_wasm = target_directory.as_ref().join(wasm_target.triple());
+    original_wasm.push("release");
+    original_wasm.push(lib_name);
+    original_wasm.set_extension("wasm");
+    original_wasm
 }
 
 /// Get the result of `cargo metadata`, together with the root package id.
-fn get_cargo_metadata(manifest_path: &ManifestPath) -> Result<(CargoMetadata, Package)> {
+fn get_cargo_metadata(
+    manifest_path: &ManifestPath,
+    cargo_flags: &CargoFlags,
+) -> Result<(CargoMetadata, Package)> {
     log::info!(
         "Fetching cargo metadata for {}",
         manifest_path.as_ref().to_string_lossy()
@@ -129,6 +200,7 @@ fn get_cargo_metadata(manifest_path: &ManifestPath) -> Result<(CargoMetadata, Pa
     let mut cmd = MetadataCommand::new();
     let metadata = cmd
         .manifest_path(manifest_path.as_ref())
+        .other_options(cargo_flags.args(true))
         .exec()
         .context("Error invoking `cargo metadata`")?;
     let root_package_id = metadata
@@ -148,11 +220,70 @@ fn get_cargo_metadata(manifest_path: &ManifestPath) -> Result<(CargoMetadata, Pa
     Ok((metadata, root_package))
 }
 
+/// Returns the library target of the `package`, which the contract is compiled to Wasm from.
+///
+/// Fails with an explanation if the package has no `cdylib` library target, e.g. because the
+/// contract is defined in a binary target.
+fn contract_lib_target(package: &Package) -> Result<&Target> {
+    let has_kind = |target: &&Target, kinds: &[&str]| {
+        target
+            .kind
+            .iter()
+            .any(|kind| kinds.contains(&kind.as_str()))
+    };
+    if let Some(cdylib) = package
+        .targets
+        .iter()
+        .find(|target| has_kind(target, &["cdylib"]))
+    {
+        return Ok(cdylib);
+    }
+    if let Some(lib) = package
+        .targets
+        .iter()
+        .find(|target| has_kind(target, &["lib", "rlib"]))
+    {
+        anyhow::bail!(
+            "The library target `{}` ({}) of `{}` is not a `cdylib`. Contracts are compiled to \
+             Wasm from a `cdylib`, add `crate-type = [\"cdylib\"]` to the `[lib]` section of the \
+             `Cargo.toml`.",
+            lib.name,
+            lib.src_path,
+            package.name
+        )
+    }
+    let bins = package
+        .targets
+        .iter()
+        .filter(|target| has_kind(target, &["bin"]))
+        .map(|bin| format!("`{}` ({})", bin.name, bin.src_path))
+        .collect::<Vec<_>>();
+    if !bins.is_empty() {
+        anyhow::bail!(
+            "`{}` has no library target, only the binary targets {}. Contracts must be defined \
+             in a library target: move the `#[ink::contract]` module into `src/lib.rs`, or add \
+             a `[lib]` section with `crate-type = [\"cdylib\"]` and the `path` of its file.",
+            package.name,
+            bins.join(", ")
+        )
+    }
+    anyhow::bail!(
+        "`{}` has no library target to compile the contract from",
+        package.name
+    )
+}
+
 /// Extra metadata not available via `cargo metadata`.
 struct ExtraMetadata {
     documentation: Option<Url>,
     homepage: Option<Url>,
     user: Option<Map<String, Value>>,
+    hooks: BuildHooks,
+    build_std: Vec<String>,
+    panic: Option<PanicMode>,
+    custom_sections: BTreeMap<String, String>,
+    max_size: Option<u64>,
+    wasm_opt_version: Option<u32>,
 }
 
 /// Read extra metadata not available via `cargo metadata` directly from `Cargo.toml`
@@ -186,9 +317,85 @@ fn get_cargo_toml_metadata(manifest_path: &ManifestPath) -> Result<ExtraMetadata
         })
         .transpose()?;
 
+    let hooks = toml
+        .get("package")
+        .and_then(|v| v.get("metadata"))
+        .and_then(|v| v.get("contract"))
+        .and_then(|v| v.get("hooks"))
+        .cloned()
+        .map(|v| v.try_into::<BuildHooks>())
+        .transpose()
+        .context("Invalid `[package.metadata.contract.hooks]`")?
+        .unwrap_or_default();
+
+    let contract = toml
+        .get("package")
+        .and_then(|v| v.get("metadata"))
+        .and_then(|v| v.get("contract"));
+
+    let build_std = contract
+        .and_then(|v| v.get("build-std"))
+        .cloned()
+        .map(|v| v.try_into::<Vec<String>>())
+        .transpose()
+        .context("`build-std` of `[package.metadata.contract]` must be a list of crates")?
+        .unwrap_or_default();
+
+    let panic = contract
+        .and_then(|v| v.get("panic"))
+        .map(|v| {
+            v.as_str()
+                .ok_or_else(|| anyhow::anyhow!("must be a string"))
+                .and_then(str::parse::<PanicMode>)
+        })
+        .transpose()
+        .context("Invalid `panic` of `[package.metadata.contract]`")?;
+
+    let custom_sections = contract
+        .and_then(|v| v.get("custom-sections"))
+        .cloned()
+        .map(|v| v.try_into::<BTreeMap<String, String>>())
+        .transpose()
+        .context("`[package.metadata.contract.custom-sections]` must map names to strings")?
+        .unwrap_or_default();
+    if custom_sections.contains_key("name") {
+        anyhow::bail!(
+            "The custom section `name` is reserved for the debug symbols, choose another name in \
+             `[package.metadata.contract.custom-sections]`"
+        )
+    }
+
+    let max_size = contract
+        .and_then(|v| v.get("max-size"))
+        .map(|v| match v {
+            value::Value::String(size) => parse_size(size),
+            value::Value::Integer(bytes) => u64::try_from(*bytes).map_err(Into::into),
+            _ => Err(anyhow::anyhow!("must be a size such as \"120K\"")),
+        })
+        .transpose()
+        .context("Invalid `max-size` of `[package.metadata.contract]`")?;
+
+    let wasm_opt_version = contract
+        .and_then(|v| v.get("wasm-opt-version"))
+        .map(|v| match v {
+            value::Value::String(version) => parse_version(version),
+            value::Value::Integer(version) => parse_version(&version.to_string()),
+            _ => Err(anyhow::anyhow!(
+                "must be a binaryen release such as \"116\""
+            )),
+        })
+        .transpose()
+        .context("Invalid `wasm-opt-version` of `[package.metadata.contract]`")?;
+
     Ok(ExtraMetadata {
         documentation,
         homepage,
         user,
+        hooks,
+        build_std,
+        panic,
+        custom_sections,
+        max_size,
+        wasm_opt_version,
     })
 }
@@ -0,0 +1,342 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The defaults of the command line options from the environment and the configuration.
+//!
+//! The options which are not passed on the command line are taken from the first of
+//!
+//! 1. the environment variable `CARGO_CONTRACT_<OPTION>`, e.g. `CARGO_CONTRACT_URL` for `--url`,
+//! 2. `[package.metadata.contract.defaults]` in the `Cargo.toml` of the contract,
+//! 3. `[defaults]` of the user configuration, see [`Config`],
+//!
+//! and otherwise it has the default documented by `--help`. Within the configuration the options
+//! in the table of a command take precedence over the options for all commands:
+//!
+//! ```toml
+//! [defaults]
+//! url = "wss://rococo-contracts-rpc.polkadot.io"
+//!
+//! [defaults.call]
+//! gas = 100000000000
+//! dry-run = true
+//! ```
+//!
+//! The defaults are inserted into the arguments before they are parsed, so that they are
+//! validated like the arguments of the command line. The options of [`NOT_DEFAULTABLE`], e.g.
+//! secrets, are not taken from the environment or the configuration.
+//!
+//! A configuration which fails to load is reported as warning, so that it does not prevent
+//! commands which do not need it, e.g. `build`.
+
+use crate::{config::Config, output};
+use anyhow::{Context, Result};
+use clap::{Command, CommandFactory};
+use heck::ToShoutySnakeCase;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+use toml::{value::Table, Value};
+
+/// The prefix of the environment variables of the options.
+const ENV_PREFIX: &str = "CARGO_CONTRACT_";
+
+/// The long names of the options whose defaults are not taken from the environment and the
+/// configuration: secrets, the configuration file itself and the options which only print.
+const NOT_DEFAULTABLE: &[&str] = &["suri", "password", "config-file", "help", "version"];
+
+/// Returns the `args` of `cargo contract` with the defaults of the options which are missing.
+///
+/// If the defaults cannot be loaded or are invalid, a warning is printed and the `args` are
+/// returned as they are.
+pub(crate) fn with_defaults(args: Vec<OsString>) -> Vec<OsString> {
+    match try_with_defaults(args.clone()) {
+        Ok(args) => args,
+        Err(err) => {
+            output::warning(format!("Ignoring the defaults of the options: {:?}", err));
+            args
+        }
+    }
+}
+
+fn try_with_defaults(args: Vec<OsString>) -> Result<Vec<OsString>> {
+    let opts = crate::Opts::command();
+    let command = match opts.find_subcommand("contract") {
+        Some(command) => command,
+        None => return Ok(args),
+    };
+    let manifest_path = option_value(&args, "manifest-path")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("Cargo.toml"));
+    let project = project_defaults(&manifest_path)?;
+    let user = Config::load()?.defaults;
+    insert_defaults(
+        command,
+        args,
+        &|name| std::env::var(name).ok(),
+        &project,
+        &user,
+    )
+}
+
+/// Inserts the defaults of the missing options into the `args`, which are `cargo-contract
+/// contract <command> ..` as returned by [`crate::normalize_args`].
+fn insert_defaults(
+    command: &Command,
+    mut args: Vec<OsString>,
+    env: &dyn Fn(&str) -> Option<String>,
+    project: &Table,
+    user: &Table,
+) -> Result<Vec<OsString>> {
+    // the arguments after `--` are passed on, e.g. to `cargo test`
+    let end = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(args.len());
+    // the commands on the command line, with the position of their name
+    let mut commands = vec![(command, 1)];
+    for (position, arg) in args[..end].iter().enumerate().skip(2) {
+        let current = commands.last().expect("the root command is present").0;
+        if let Some(subcommand) = arg.to_str().and_then(|arg| current.find_subcommand(arg)) {
+            commands.push((subcommand, position));
+        }
+    }
+    let names = commands[1..]
+        .iter()
+        .map(|(command, _)| command.get_name())
+        .collect::<Vec<_>>();
+
+    let mut insertions = Vec::new();
+    for (command, position) in &commands {
+        let mut defaults = Vec::new();
+        for arg in command.get_arguments() {
+            let long = match arg.get_long() {
+                Some(long) if !NOT_DEFAULTABLE.contains(&long) => long,
+                _ => continue,
+            };
+            let own_env = arg.get_env().and_then(|name| name.to_str());
+            if is_present(&args[..end], long, arg.get_short()) || own_env.and_then(env).is_some() {
+                continue;
+            }
+            let env_name = format!("{}{}", ENV_PREFIX, long.to_shouty_snake_case());
+            let (value, source) = match env(&env_name) {
+                Some(value) => (Value::String(value), env_name),
+                None => match (lookup(project, &names, long), lookup(user, &names, long)) {
+                    (Some(value), _) => (value.clone(), "the Cargo.toml".to_string()),
+                    (None, Some(value)) => (value.clone(), "the user configuration".to_string()),
+                    (None, None) => continue,
+                },
+            };
+            let option_args = option_args(long, arg.is_takes_value_set(), &value)
+                .context(format!("Invalid default of `--{}` from {}", long, source))?;
+            defaults.extend(option_args);
+        }
+        insertions.push((*position, defaults));
+    }
+    // from the last command, so that the positions of the previous ones stay valid
+    for (position, defaults) in insertions.into_iter().rev() {
+        args.splice(position + 1..position + 1, defaults);
+    }
+    Ok(args)
+}
+
+/// Returns the value of the option `long` in the `table` of the most specific of the `commands`.
+fn lookup<'a>(mut table: &'a Table, commands: &[&str], long: &str) -> Option<&'a Value> {
+    let mut value = table.get(long).filter(|value| !value.is_table());
+    for command in commands {
+        table = match table.get(*command).and_then(Value::as_table) {
+            Some(table) => table,
+            None => break,
+        };
+        if let Some(specific) = table.get(long).filter(|value| !value.is_table()) {
+            value = Some(specific);
+        }
+    }
+    value
+}
+
+/// Returns the arguments which pass the `value` to the option `long`.
+fn option_args(long: &str, takes_value: bool, value: &Value) -> Result<Vec<OsString>> {
+    let option = OsString::from(format!("--{}", long));
+    match value {
+        Value::Array(values) => {
+            let mut args = Vec::new();
+            for value in values {
+                args.extend(option_args(long, takes_value, value)?);
+            }
+            Ok(args)
+        }
+        Value::Boolean(set) if !takes_value => Ok(if *set { vec![option] } else { Vec::new() }),
+        Value::String(value) if !takes_value => match value.as_str() {
+            "true" | "1" => Ok(vec![option]),
+            "false" | "0" | "" => Ok(Vec::new()),
+            _ => anyhow::bail!("Expected `true` or `false` for a flag, got `{}`", value),
+        },
+        _ if !takes_value => anyhow::bail!("Expected `true` or `false` for a flag, got {}", value),
+        Value::String(value) => Ok(vec![option, value.into()]),
+        Value::Integer(_) | Value::Float(_) | Value::Boolean(_) => {
+            Ok(vec![option, value.to_string().into()])
+        }
+        _ => anyhow::bail!("Expected a string, number, boolean or array, got {}", value),
+    }
+}
+
+/// Returns whether the option `long` is passed in the `args`.
+fn is_present(args: &[OsString], long: &str, short: Option<char>) -> bool {
+    let option = format!("--{}", long);
+    args.iter().filter_map(|arg| arg.to_str()).any(|arg| {
+        arg == option
+            || arg.starts_with(&format!("{}=", option))
+            || short.map_or(false, |short| {
+                !arg.starts_with("--")
+                    && arg
+                        .strip_prefix('-')
+                        .map_or(false, |arg| arg.starts_with(short))
+            })
+    })
+}
+
+/// Returns the value of the option `long` passed in the `args`.
+pub(crate) fn option_value(args: &[OsString], long: &str) -> Option<OsString> {
+    let option = format!("--{}", long);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg.to_str() == Some(&option) {
+            return args.next().cloned();
+        }
+        if let Some(value) = arg
+            .to_str()
+            .and_then(|arg| arg.strip_prefix(&format!("{}=", option)))
+        {
+            return Some(value.into());
+        }
+    }
+    None
+}
+
+/// Returns `[package.metadata.contract.defaults]` of the manifest at `path`, if any.
+fn project_defaults(path: &Path) -> Result<Table> {
+    if !path.exists() {
+        return Ok(Table::new());
+    }
+    let manifest: Value = toml::from_str(&std::fs::read_to_string(path)?)
+        .context(format!("Failed to parse {}", path.display()))?;
+    let defaults = ["package", "metadata", "contract", "defaults"]
+        .iter()
+        .try_fold(&manifest, |value, key| value.get(key))
+        .and_then(Value::as_table);
+    Ok(defaults.cloned().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn defaults_are_layered() {
+        let command = Command::new("contract")
+            .arg(Arg::new("timings").long("timings").global(true))
+            .subcommand(
+                Command::new("call")
+                    .arg(Arg::new("url").long("url").takes_value(true))
+                    .arg(Arg::new("suri").long("suri").short('s').takes_value(true))
+                    .arg(Arg::new("gas").long("gas").takes_value(true))
+                    .arg(Arg::new("dry-run").long("dry-run"))
+                    .arg(Arg::new("fee-asset").long("fee-asset").takes_value(true))
+                    .arg(Arg::new("password").long("password").takes_value(true)),
+            );
+        let project: Table = toml::from_str(
+            r#"
+            url = "ws://project:9944"
+            [call]
+            gas = 100
+            "#,
+        )
+        .unwrap();
+        let user: Table = toml::from_str(
+            r#"
+            url = "ws://user:9944"
+            suri = "//Bob"
+            password = "secret"
+            timings = true
+            [call]
+            dry-run = true
+            "#,
+        )
+        .unwrap();
+        let env = |name: &str| match name {
+            "CARGO_CONTRACT_URL" => Some("ws://env:9944".to_string()),
+            "CARGO_CONTRACT_FEE_ASSET" => Some("1984".to_string()),
+            _ => None,
+        };
+
+        let with_defaults = insert_defaults(
+            &command,
+            args(&["cargo-contract", "contract", "call", "-s", "//Alice"]),
+            &env,
+            &project,
+            &user,
+        )
+        .unwrap();
+        assert_eq!(
+            with_defaults,
+            args(&[
+                "cargo-contract",
+                "contract",
+                "--timings",
+                "call",
+                "--url",
+                "ws://env:9944",
+                "--gas",
+                "100",
+                "--dry-run",
+                "--fee-asset",
+                "1984",
+                "-s",
+                "//Alice",
+            ])
+        );
+
+        // secrets are only taken from the command line
+        let with_defaults = insert_defaults(
+            &command,
+            args(&["cargo-contract", "contract", "call"]),
+            &|_| None,
+            &Table::new(),
+            &user,
+        )
+        .unwrap();
+        assert_eq!(option_value(&with_defaults, "suri"), None);
+        assert_eq!(option_value(&with_defaults, "password"), None);
+        assert_eq!(
+            option_value(&with_defaults, "url"),
+            Some("ws://user:9944".into())
+        );
+
+        let command_line = args(&["cargo-contract", "contract", "call", "--url=ws://cli:9944"]);
+        let with_defaults =
+            insert_defaults(&command, command_line, &env, &project, &Table::new()).unwrap();
+        assert_eq!(
+            option_value(&with_defaults, "url"),
+            Some("ws://cli:9944".into())
+        );
+    }
+}
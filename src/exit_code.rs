@@ -0,0 +1,116 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The exit codes of `cargo contract`, so that scripts can branch on the class of a failure.
+//!
+//! Errors are classified where they occur with [`Classify::classify`], which keeps their
+//! message and causes as they are. Invalid arguments exit with `2`, as reported by `clap`.
+
+use anyhow::Result;
+use std::fmt;
+
+/// The class of a failure, which determines the exit code of the process.
+///
+/// The codes are stable, new classes only ever get new codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ExitCode {
+    /// Any failure which is not classified otherwise.
+    Failure = 1,
+    /// The Rust toolchain or a component of it is missing or unsupported.
+    Toolchain = 3,
+    /// Compiling the contract failed.
+    Build = 4,
+    /// The contract was built, but failed a validation, e.g. of its imports or size budget.
+    Validation = 5,
+    /// The node could not be reached or rejected a request or an extrinsic.
+    Rpc = 6,
+    /// The extrinsic was included in a block, but its dispatch failed.
+    Dispatch = 7,
+    /// The dry-run of a contract call or instantiation reverted.
+    Reverted = 8,
+}
+
+/// An error classified with its exit code, displayed as the error itself.
+#[derive(Debug)]
+struct Classified {
+    code: ExitCode,
+    error: anyhow::Error,
+}
+
+impl fmt::Display for Classified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for Classified {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Classifies the `error` with the exit `code`.
+pub(crate) fn classify(error: anyhow::Error, code: ExitCode) -> anyhow::Error {
+    anyhow::Error::new(Classified { code, error })
+}
+
+/// Classifies the error of a result with an exit code.
+pub(crate) trait Classify<T> {
+    fn classify(self, code: ExitCode) -> Result<T>;
+}
+
+impl<T, E> Classify<T> for std::result::Result<T, E>
+where
+    E: Into<anyhow::Error>,
+{
+    fn classify(self, code: ExitCode) -> Result<T> {
+        self.map_err(|err| classify(err.into(), code))
+    }
+}
+
+/// Returns the exit code of the `error`, the outermost classification if there are several.
+///
+/// Errors of the RPC clients are failures of the RPC, even if they were not classified.
+pub(crate) fn exit_code(error: &anyhow::Error) -> ExitCode {
+    for cause in error.chain() {
+        if let Some(classified) = cause.downcast_ref::<Classified>() {
+            return classified.code;
+        }
+        if cause.is::<jsonrpsee::core::Error>() || cause.is::<subxt::BasicError>() {
+            return ExitCode::Rpc;
+        }
+    }
+    ExitCode::Failure
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn classified_errors_keep_their_message() {
+        let result: Result<()> = Err(anyhow!("`cargo build` failed")).classify(ExitCode::Build);
+        let error = result.context("Build of the contract failed").unwrap_err();
+
+        assert_eq!(exit_code(&error), ExitCode::Build);
+        assert_eq!(
+            format!("{:#}", error),
+            "Build of the contract failed: `cargo build` failed"
+        );
+        assert_eq!(exit_code(&anyhow!("unclassified")), ExitCode::Failure);
+    }
+}
@@ -0,0 +1,1132 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `cargo-contract`, the tool to develop ink! smart contracts.
+//!
+//! The `cargo-contract` executable only calls [`run`]. With the `testing` feature this library
+//! additionally exports the [`testing`] helpers, e.g. for the end-to-end tests of contracts.
+
+mod address_book;
+mod cmd;
+mod config;
+mod crate_metadata;
+mod defaults;
+mod exit_code;
+mod logging;
+mod output;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod timings;
+mod toolchain;
+mod util;
+mod validate_wasm;
+mod workspace;
+
+use self::{
+    cmd::{
+        metadata::MetadataResult, AccessControlCommand, AccountCommand, AddCommand,
+        AddressBookCommand, BenchDeployCommand, BuildCommand, CallCommand, CallManyCommand,
+        ChainInfoCommand, CheckCommand, CleanCommand, CompleteCommand, CompletionsCommand,
+        ConformanceCommand, ConsoleCommand, DeployCommand, DownloadCommand, FixManifestCommand,
+        GetCommand, GraphCommand, IndexCommand, InitCiCommand, InspectWasmCommand,
+        InstantiateCommand, InterfaceCommand, LogsCommand, MetadataCommand, ReplayCommand,
+        ServeCommand, SimulateUpgradeCommand, SizeHistoryCommand, StateCommand, SubmitCommand,
+        TerminateCommand, TestCommand, TypegenCommand, UploadCommand, VerifyMetadataCommand,
+        WasmDiffCommand,
+    },
+    logging::LogFormat,
+    timings::Timings,
+    toolchain::WasmTarget,
+    util::DEFAULT_KEY_COL_WIDTH,
+    workspace::ManifestPath,
+};
+
+use std::{
+    convert::TryFrom,
+    ffi::OsString,
+    fmt::{Display, Formatter, Result as DisplayResult},
+    path::PathBuf,
+    str::FromStr,
+    time::Instant,
+};
+
+use anyhow::{Error, Result};
+use clap::{AppSettings, Args, Parser, Subcommand};
+use colored::Colorize;
+
+#[derive(Debug, Parser)]
+#[clap(bin_name = "cargo")]
+#[clap(version = env!("CARGO_CONTRACT_CLI_IMPL_VERSION"))]
+pub(crate) enum Opts {
+    /// Utilities to develop Wasm smart contracts.
+    #[clap(name = "contract")]
+    #[clap(version = env!("CARGO_CONTRACT_CLI_IMPL_VERSION"))]
+    #[clap(setting = AppSettings::DeriveDisplayOrder)]
+    Contract(ContractArgs),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ContractArgs {
+    #[clap(subcommand)]
+    cmd: Command,
+    /// The format of the logs written to stderr. `json` also emits structured events, e.g. of the
+    /// build steps and submitted extrinsics, for CI observability tools.
+    #[clap(
+        long,
+        global = true,
+        arg_enum,
+        env = "CARGO_CONTRACT_LOG_FORMAT",
+        default_value = "text"
+    )]
+    log_format: LogFormat,
+    /// Print how long the phases of the command took, e.g. the cargo build, wasm-opt or waiting
+    /// for the inclusion of an extrinsic.
+    #[clap(long, global = true)]
+    timings: bool,
+    /// Write the timings of the phases as JSON to this file, implies `--timings`.
+    #[clap(long, global = true, parse(from_os_str))]
+    timings_file: Option<PathBuf>,
+    /// Do not color the output. Colors are also disabled if `NO_COLOR` is set or stdout is not a
+    /// terminal, unless `CLICOLOR_FORCE` is set.
+    #[clap(long, global = true)]
+    no_color: bool,
+    /// Read the user configuration from this file instead of `config.toml` in the configuration
+    /// directory. Its `[defaults]` are the defaults of the options of all commands.
+    #[clap(
+        long,
+        global = true,
+        env = "CARGO_CONTRACT_CONFIG_FILE",
+        parse(from_os_str)
+    )]
+    config_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct HexData(pub Vec<u8>);
+
+impl std::str::FromStr for HexData {
+    type Err = hex::FromHexError;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        hex::decode(input).map(HexData)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OptimizationPasses {
+    Zero,
+    One,
+    Two,
+    Three,
+    Four,
+    S,
+    Z,
+}
+
+impl Display for OptimizationPasses {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        let out = match self {
+            OptimizationPasses::Zero => "0",
+            OptimizationPasses::One => "1",
+            OptimizationPasses::Two => "2",
+            OptimizationPasses::Three => "3",
+            OptimizationPasses::Four => "4",
+            OptimizationPasses::S => "s",
+            OptimizationPasses::Z => "z",
+        };
+        write!(f, "{}", out)
+    }
+}
+
+impl Default for OptimizationPasses {
+    fn default() -> OptimizationPasses {
+        OptimizationPasses::Z
+    }
+}
+
+impl std::str::FromStr for OptimizationPasses {
+    type Err = Error;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        // We need to replace " here, since the input string could come
+        // from either the CLI or the `Cargo.toml` profile section.
+        // If it is from the profile it could e.g. be "3" or 3.
+        let normalized_input = input.replace('"', "").to_lowercase();
+        match normalized_input.as_str() {
+            "0" => Ok(OptimizationPasses::Zero),
+            "1" => Ok(OptimizationPasses::One),
+            "2" => Ok(OptimizationPasses::Two),
+            "3" => Ok(OptimizationPasses::Three),
+            "4" => Ok(OptimizationPasses::Four),
+            "s" => Ok(OptimizationPasses::S),
+            "z" => Ok(OptimizationPasses::Z),
+            _ => anyhow::bail!("Unknown optimization passes for option {}", input),
+        }
+    }
+}
+
+impl From<std::string::String> for OptimizationPasses {
+    fn from(str: String) -> Self {
+        OptimizationPasses::from_str(&str).expect("conversion failed")
+    }
+}
+
+/// How panics are handled in the contract's Wasm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanicMode {
+    /// Abort without formatting the panic message, by building the standard library with
+    /// `panic_immediate_abort`. This results in the smallest Wasm and is the default for
+    /// release builds.
+    ImmediateAbort,
+    /// Abort after formatting the panic message, so that it can be printed as a debug message.
+    /// This is the default for debug builds.
+    Abort,
+    /// Unwind the stack, which requires the standard library to be built with `panic_unwind`.
+    Unwind,
+}
+
+impl Display for PanicMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        match self {
+            Self::ImmediateAbort => write!(f, "immediate-abort"),
+            Self::Abort => write!(f, "abort"),
+            Self::Unwind => write!(f, "unwind"),
+        }
+    }
+}
+
+impl std::str::FromStr for PanicMode {
+    type Err = Error;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input {
+            "immediate-abort" => Ok(Self::ImmediateAbort),
+            "abort" => Ok(Self::Abort),
+            "unwind" => Ok(Self::Unwind),
+            _ => anyhow::bail!(
+                "Unknown panic mode {}, expected one of: immediate-abort, abort, unwind",
+                input
+            ),
+        }
+    }
+}
+
+#[derive(Default, Clone, Debug, Args)]
+pub struct VerbosityFlags {
+    /// No output printed to stdout
+    #[clap(long)]
+    quiet: bool,
+    /// Use verbose output
+    #[clap(long)]
+    verbose: bool,
+}
+
+/// Denotes if output should be printed to stdout.
+#[derive(Clone, Copy, serde::Serialize, Eq, PartialEq)]
+pub enum Verbosity {
+    /// Use default output
+    Default,
+    /// No output printed to stdout
+    Quiet,
+    /// Use verbose output
+    Verbose,
+}
+
+impl Default for Verbosity {
+    fn default() -> Self {
+        Verbosity::Default
+    }
+}
+
+impl Verbosity {
+    /// Returns `true` if output should be printed (i.e. verbose output is set).
+    pub(crate) fn is_verbose(&self) -> bool {
+        match self {
+            Verbosity::Quiet => false,
+            Verbosity::Default | Verbosity::Verbose => true,
+        }
+    }
+}
+
+impl TryFrom<&VerbosityFlags> for Verbosity {
+    type Error = Error;
+
+    fn try_from(value: &VerbosityFlags) -> Result<Self, Self::Error> {
+        match (value.quiet, value.verbose) {
+            (false, false) => Ok(Verbosity::Default),
+            (true, false) => Ok(Verbosity::Quiet),
+            (false, true) => Ok(Verbosity::Verbose),
+            (true, true) => anyhow::bail!("Cannot pass both --quiet and --verbose flags"),
+        }
+    }
+}
+
+#[derive(Default, Clone, Debug, Args)]
+struct UnstableOptions {
+    /// Unstable options: `original-manifest` uses the original manifest (Cargo.toml) without
+    /// modifying it for build optimizations, `wasm64` compiles the contract for the experimental
+    /// `wasm64-unknown-unknown` target
+    #[clap(long = "unstable-options", short = 'Z', number_of_values = 1)]
+    options: Vec<String>,
+}
+
+#[derive(Clone, Default)]
+struct UnstableFlags {
+    original_manifest: bool,
+    wasm64: bool,
+}
+
+impl TryFrom<&UnstableOptions> for UnstableFlags {
+    type Error = Error;
+
+    fn try_from(value: &UnstableOptions) -> Result<Self, Self::Error> {
+        let valid_flags = ["original-manifest", "wasm64"];
+        let invalid_flags = value
+            .options
+            .iter()
+            .filter(|o| !valid_flags.contains(&o.as_str()))
+            .collect::<Vec<_>>();
+        if !invalid_flags.is_empty() {
+            anyhow::bail!("Unknown unstable-options {:?}", invalid_flags)
+        }
+        Ok(UnstableFlags {
+            original_manifest: value.options.contains(&"original-manifest".to_owned()),
+            wasm64: value.options.contains(&"wasm64".to_owned()),
+        })
+    }
+}
+
+impl UnstableFlags {
+    /// The Wasm target the contract is compiled for.
+    fn wasm_target(&self) -> WasmTarget {
+        if self.wasm64 {
+            WasmTarget::Wasm64
+        } else {
+            WasmTarget::Wasm32
+        }
+    }
+}
+
+/// Describes which artifacts to generate
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Subcommand, serde::Serialize)]
+#[clap(name = "build-artifacts")]
+pub enum BuildArtifacts {
+    /// Generate the Wasm, the metadata and a bundled `<name>.contract` file
+    #[clap(name = "all")]
+    All,
+    /// Only the Wasm is created, generation of metadata and a bundled `<name>.contract` file is skipped
+    #[clap(name = "code-only")]
+    CodeOnly,
+    CheckOnly,
+}
+
+impl BuildArtifacts {
+    /// Returns the number of steps required to complete a build artifact.
+    /// Used as output on the cli.
+    pub fn steps(&self) -> usize {
+        match self {
+            BuildArtifacts::All => 5,
+            BuildArtifacts::CodeOnly => 3,
+            BuildArtifacts::CheckOnly => 2,
+        }
+    }
+}
+
+impl std::str::FromStr for BuildArtifacts {
+    type Err = String;
+    fn from_str(artifact: &str) -> Result<Self, Self::Err> {
+        match artifact {
+            "all" => Ok(BuildArtifacts::All),
+            "code-only" => Ok(BuildArtifacts::CodeOnly),
+            _ => Err("Could not parse build artifact".to_string()),
+        }
+    }
+}
+
+impl Default for BuildArtifacts {
+    fn default() -> Self {
+        BuildArtifacts::All
+    }
+}
+
+/// The mode to build the contract in.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, serde::Serialize)]
+pub enum BuildMode {
+    /// Functionality to output debug messages is build into the contract.
+    Debug,
+    /// The contract is build without any debugging functionality.
+    Release,
+}
+
+impl Default for BuildMode {
+    fn default() -> BuildMode {
+        BuildMode::Debug
+    }
+}
+
+impl Display for BuildMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        match self {
+            Self::Debug => write!(f, "debug"),
+            Self::Release => write!(f, "release"),
+        }
+    }
+}
+
+/// Use network connection to build contracts and generate metadata or use cached dependencies only.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, serde::Serialize)]
+pub enum Network {
+    /// Use network
+    Online,
+    /// Use cached dependencies.
+    Offline,
+}
+
+impl Default for Network {
+    fn default() -> Network {
+        Network::Online
+    }
+}
+
+impl Display for Network {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        match self {
+            Self::Online => write!(f, ""),
+            Self::Offline => write!(f, "--offline"),
+        }
+    }
+}
+
+/// Options which are forwarded to every `cargo` invocation of a build.
+///
+/// This allows for hermetic builds, e.g. in a sandbox with vendored dependencies.
+#[derive(Default, Clone, Debug, Args)]
+pub struct CargoOptions {
+    /// Build offline, this is also the case if `CARGO_NET_OFFLINE=true` is set
+    #[clap(long)]
+    offline: bool,
+    /// Require the `Cargo.lock` of the contract to be up to date
+    #[clap(long)]
+    locked: bool,
+    /// Override a cargo configuration value, e.g. `--config net.offline=true`
+    #[clap(long = "config", value_name = "KEY=VALUE", number_of_values = 1)]
+    config: Vec<String>,
+    /// The crates of the standard library to build, e.g. `--build-std core,alloc`. Defaults to
+    /// `build-std` of `[package.metadata.contract]`, or to the crates `cargo` selects.
+    #[clap(long, value_name = "CRATES", use_delimiter = true)]
+    build_std: Vec<String>,
+    /// How panics are handled: `immediate-abort`, `abort` or `unwind`. Defaults to `panic` of
+    /// `[package.metadata.contract]`, or to `immediate-abort` for release and `abort` for
+    /// debug builds.
+    #[clap(long, value_name = "MODE")]
+    panic: Option<PanicMode>,
+    /// Arguments which are passed on to the `cargo` invocation building the contract's Wasm,
+    /// e.g. `cargo contract build -- -Z build-std=core,alloc`
+    #[clap(last = true)]
+    build_args: Vec<String>,
+}
+
+/// The options which are forwarded to `cargo`.
+#[derive(Clone, Debug, Default)]
+pub struct CargoFlags {
+    network: Network,
+    locked: bool,
+    config: Vec<String>,
+    /// The features of the contract to enable.
+    features: Vec<String>,
+    /// Additional environment variables to set.
+    env: Vec<(String, String)>,
+    /// Additional arguments for the `cargo` invocation building the contract's Wasm.
+    build_args: Vec<String>,
+    /// The crates of the standard library to build, all by default.
+    build_std: Vec<String>,
+    /// How panics are handled, depends on the build mode by default.
+    panic: Option<PanicMode>,
+    /// Additional outputs of `rustc` for the contract crate.
+    emit: Vec<cmd::build::Emit>,
+    /// Whether the default `[profile.release]` settings overwrite the user-defined ones.
+    profile_strict: bool,
+}
+
+impl From<&CargoOptions> for CargoFlags {
+    fn from(value: &CargoOptions) -> Self {
+        let offline_env = std::env::var("CARGO_NET_OFFLINE")
+            .map(|offline| offline == "true")
+            .unwrap_or(false);
+        let network = match value.offline || offline_env {
+            true => Network::Offline,
+            false => Network::Online,
+        };
+        CargoFlags {
+            network,
+            locked: value.locked,
+            config: value.config.clone(),
+            features: Vec::new(),
+            env: Vec::new(),
+            build_args: value.build_args.clone(),
+            build_std: value.build_std.clone(),
+            panic: value.panic,
+            emit: Vec::new(),
+            profile_strict: false,
+        }
+    }
+}
+
+impl CargoFlags {
+    /// Returns the arguments to pass to `cargo`.
+    ///
+    /// `--locked` is only passed if `locked` is `true`. It must not be passed for packages which
+    /// are generated by us and hence are not part of the contract's `Cargo.lock`.
+    pub(crate) fn args(&self, locked: bool) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.network == Network::Offline {
+            args.push(self.network.to_string());
+        }
+        if locked && self.locked {
+            args.push("--locked".to_string());
+        }
+        if !self.config.is_empty() {
+            // `--config` is still unstable on older nightly toolchains.
+            args.push("-Zunstable-options".to_string());
+            for config in &self.config {
+                args.push(format!("--config={}", config));
+            }
+        }
+        args
+    }
+
+    /// Returns the `--features` argument which enables the contract features, if there are any.
+    ///
+    /// If the contract is a dependency of the built package, the features have to be prefixed
+    /// with the name of the contract's `package`.
+    pub(crate) fn features_arg(&self, package: Option<&str>) -> Option<String> {
+        if self.features.is_empty() {
+            return None;
+        }
+        let features = self
+            .features
+            .iter()
+            .map(|feature| match package {
+                Some(package) => format!("{}/{}", package, feature),
+                None => feature.clone(),
+            })
+            .collect::<Vec<_>>();
+        Some(format!("--features={}", features.join(",")))
+    }
+
+    /// Returns the environment variables to set for `cargo`.
+    ///
+    /// Setting `CARGO_NET_OFFLINE` ensures that nested `cargo` invocations, e.g. the ones by
+    /// `cargo dylint`, don't access the network either.
+    pub(crate) fn env(&self) -> Vec<(&str, Option<&str>)> {
+        let mut env = match self.network {
+            Network::Offline => vec![("CARGO_NET_OFFLINE", Some("true"))],
+            Network::Online => vec![],
+        };
+        env.extend(
+            self.env
+                .iter()
+                .map(|(key, value)| (key.as_str(), Some(value.as_str()))),
+        );
+        env
+    }
+}
+
+/// Whether the code of a contract must be deterministic.
+///
+/// `pallet-contracts` only instantiates deterministic code, code with relaxed determinism, e.g.
+/// containing floating point instructions, can only be uploaded for off-chain execution or to be
+/// called by delegate calls of off-chain executions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ArgEnum, scale::Encode, scale::Decode)]
+pub enum Determinism {
+    /// The code must not contain non-deterministic instructions.
+    Enforced,
+    /// The code may contain non-deterministic instructions.
+    Relaxed,
+}
+
+impl Default for Determinism {
+    fn default() -> Self {
+        Determinism::Enforced
+    }
+}
+
+/// The type of output to display at the end of a build.
+pub enum OutputType {
+    /// Output build results in a human readable format.
+    HumanReadable,
+    /// Output the build results JSON formatted.
+    Json,
+}
+
+impl Default for OutputType {
+    fn default() -> Self {
+        OutputType::HumanReadable
+    }
+}
+
+/// Result of the metadata generation process.
+#[derive(serde::Serialize)]
+pub struct BuildResult {
+    /// Path to the resulting Wasm file.
+    pub dest_wasm: Option<PathBuf>,
+    /// Result of the metadata generation.
+    pub metadata_result: Option<MetadataResult>,
+    /// Path to the directory where output files are written to.
+    pub target_directory: PathBuf,
+    /// If existent the result of the optimization.
+    pub optimization_result: Option<OptimizationResult>,
+    /// The mode to build the contract in.
+    pub build_mode: BuildMode,
+    /// Which build artifacts were generated.
+    pub build_artifact: BuildArtifacts,
+    /// The verbosity flags.
+    pub verbosity: Verbosity,
+    /// The name of the feature set the contract was built with in a feature matrix build.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feature_set: Option<String>,
+    /// The type of formatting to use for the build output.
+    #[serde(skip_serializing)]
+    pub output_type: OutputType,
+}
+
+/// Result of the optimization process.
+#[derive(serde::Serialize)]
+pub struct OptimizationResult {
+    /// The path of the optimized Wasm file.
+    pub dest_wasm: PathBuf,
+    /// The original Wasm size.
+    pub original_size: f64,
+    /// The exact original Wasm size, in bytes.
+    #[serde(skip_serializing)]
+    pub original_bytes: u64,
+    /// The Wasm size after optimizations have been applied.
+    pub optimized_size: f64,
+    /// The safer optimization passes applied if `wasm-opt` failed with the requested ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<OptimizationFallback>,
+}
+
+/// The optimization passes `wasm-opt` was retried with after failing.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct OptimizationFallback {
+    /// The optimization passes `wasm-opt` failed with, e.g. `-Oz`.
+    pub dropped: Vec<String>,
+    /// The optimization passes the Wasm was optimized with instead.
+    pub applied: String,
+    /// The error `wasm-opt` failed with for the requested passes.
+    pub error: String,
+}
+
+impl BuildResult {
+    pub fn display(&self) -> String {
+        let optimization = self.display_optimization();
+        let mut size_diff = format!(
+            "\nOriginal wasm size: {}, Optimized: {}\n\n",
+            format!("{:.1}K", optimization.0).bold(),
+            format!("{:.1}K", optimization.1).bold(),
+        );
+        if let Some(fallback) = self
+            .optimization_result
+            .as_ref()
+            .and_then(|optimization| optimization.fallback.as_ref())
+        {
+            size_diff.push_str(&format!(
+                "{} wasm-opt failed with {}, the Wasm was optimized with {} instead.\n\n",
+                "warning:".yellow().bold(),
+                fallback.dropped.join(", "),
+                fallback.applied.bold(),
+            ));
+        }
+        debug_assert!(
+            optimization.1 > 0.0,
+            "optimized file size must be greater 0"
+        );
+
+        let mut build_mode = format!(
+            "The contract was built in {} mode",
+            format!("{}", self.build_mode).to_uppercase().bold(),
+        );
+        if let Some(feature_set) = self.feature_set.as_ref() {
+            build_mode.push_str(&format!(" with feature set {}", feature_set.bold()));
+        }
+        build_mode.push_str(".\n\n");
+
+        if self.build_artifact == BuildArtifacts::CodeOnly {
+            let out = format!(
+                "{}{}Your contract's code is ready. You can find it here:\n{}",
+                size_diff,
+                build_mode,
+                self.dest_wasm
+                    .as_ref()
+                    .expect("wasm path must exist")
+                    .display()
+                    .to_string()
+                    .bold()
+            );
+            return out;
+        };
+
+        let mut out = format!(
+            "{}{}Your contract artifacts are ready. You can find them in:\n{}\n\n",
+            size_diff,
+            build_mode,
+            self.target_directory.display().to_string().bold(),
+        );
+        if let Some(metadata_result) = self.metadata_result.as_ref() {
+            let bundle = format!(
+                "  - {} (code + metadata)\n",
+                util::base_name(&metadata_result.dest_bundle).bold()
+            );
+            out.push_str(&bundle);
+        }
+        if let Some(dest_wasm) = self.dest_wasm.as_ref() {
+            let wasm = format!(
+                "  - {} (the contract's code)\n",
+                util::base_name(dest_wasm).bold()
+            );
+            out.push_str(&wasm);
+        }
+        if let Some(metadata_result) = self.metadata_result.as_ref() {
+            let metadata = format!(
+                "  - {} (the contract's metadata)",
+                util::base_name(&metadata_result.dest_metadata).bold()
+            );
+            out.push_str(&metadata);
+        }
+        out
+    }
+
+    /// Returns a tuple of `(original_size, optimized_size)`.
+    ///
+    /// Panics if no optimization result is available.
+    fn display_optimization(&self) -> (f64, f64) {
+        let optimization = self
+            .optimization_result
+            .as_ref()
+            .expect("optimization result must exist");
+        (optimization.original_size, optimization.optimized_size)
+    }
+
+    /// Display the build results in a pretty formatted JSON string.
+    pub fn serialize_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Setup and create a new smart contract project
+    #[clap(name = "new")]
+    New {
+        /// The name of the newly created smart contract
+        name: String,
+        /// The optional target directory for the contract project
+        #[clap(short, long, parse(from_os_str))]
+        target_dir: Option<PathBuf>,
+    },
+    /// Setup the runtime and ink! crates of a new chain extension
+    #[clap(name = "new-chain-extension")]
+    NewChainExtension {
+        /// The name of the newly created chain extension
+        name: String,
+        /// The optional target directory for the chain extension crates
+        #[clap(short, long, parse(from_os_str))]
+        target_dir: Option<PathBuf>,
+    },
+    /// Compiles the contract, generates metadata, bundles both together in a `<name>.contract` file
+    #[clap(name = "build")]
+    Build(BuildCommand),
+    /// Check that the code builds as Wasm; does not output any `<name>.contract` artifact to the `target/` directory
+    #[clap(name = "check")]
+    Check(CheckCommand),
+    /// Remove the artifacts generated by building the contract
+    #[clap(name = "clean")]
+    Clean(CleanCommand),
+    /// Add boilerplate, such as end-to-end tests or PSP22, to an existing contract
+    #[clap(name = "add")]
+    Add(AddCommand),
+    /// Add the missing settings a contract requires to its `Cargo.toml`
+    #[clap(name = "fix-manifest")]
+    FixManifest(FixManifestCommand),
+    /// Write the metadata of the contract to a snapshot, or check it against the snapshot
+    #[clap(name = "metadata")]
+    Metadata(MetadataCommand),
+    /// Show the sizes of the contract recorded with `cargo contract build --size-history`
+    #[clap(name = "size-history")]
+    SizeHistory(SizeHistoryCommand),
+    /// Test the smart contract off-chain
+    #[clap(name = "test")]
+    Test(TestCommand),
+    /// Output the cross-contract dependencies of a workspace as a DOT or Mermaid graph
+    #[clap(name = "graph")]
+    Graph(GraphCommand),
+    /// Generate a CI pipeline linting, testing, building and uploading the contract
+    #[clap(name = "init-ci")]
+    InitCi(InitCiCommand),
+    /// Print the sections, imports, exports and validation result of a contract's Wasm
+    #[clap(name = "inspect-wasm")]
+    InspectWasm(InspectWasmCommand),
+    /// Compare two contract Wasm files by their functions, imports, exports and section sizes
+    #[clap(name = "wasm-diff")]
+    WasmDiff(WasmDiffCommand),
+    /// Generate an ink! trait definition of a contract's messages from its metadata
+    #[clap(name = "interface")]
+    Interface(InterfaceCommand),
+    /// Check the messages and events of a contract against a standard token interface
+    #[clap(name = "conformance")]
+    Conformance(ConformanceCommand),
+    /// Report which messages of a contract check their caller, as a first-pass audit
+    #[clap(name = "access-control")]
+    AccessControl(AccessControlCommand),
+    /// Generate client bindings of a contract in other languages from its metadata
+    #[clap(name = "typegen")]
+    Typegen(TypegenCommand),
+    /// Upload contract code
+    #[clap(name = "upload")]
+    Upload(UploadCommand),
+    /// Instantiate a contract
+    #[clap(name = "instantiate")]
+    Instantiate(InstantiateCommand),
+    /// Call a contract
+    #[clap(name = "call")]
+    Call(CallCommand),
+    /// Call a contract message once per row of a CSV file, e.g. for airdrops
+    #[clap(name = "call-many")]
+    CallMany(CallManyCommand),
+    /// Start an interactive console to call the messages of a contract
+    #[clap(name = "console")]
+    Console(ConsoleCommand),
+    /// Query a contract without a signer, optionally verified with read proofs
+    #[clap(name = "get")]
+    Get(GetCommand),
+    /// Terminate a contract, sending its remaining balance to a beneficiary
+    #[clap(name = "terminate")]
+    Terminate(TerminateCommand),
+    /// Verify the local metadata against the metadata hash committed to by a deployed contract
+    #[clap(name = "verify-metadata")]
+    VerifyMetadata(VerifyMetadataCommand),
+    /// Manage human readable aliases of deployed contracts
+    #[clap(name = "address-book")]
+    AddressBook(AddressBookCommand),
+    /// Generate, inspect and derive accounts and convert addresses
+    #[clap(name = "account")]
+    Account(AccountCommand),
+    /// Deploy the contracts of a workspace in the order of their dependencies
+    #[clap(name = "deploy")]
+    Deploy(DeployCommand),
+    /// Compare the cost of deploying the contract on several networks with dry-runs
+    #[clap(name = "bench-deploy")]
+    BenchDeploy(BenchDeployCommand),
+    /// Print the pallet-contracts configuration of a chain
+    #[clap(name = "chain-info")]
+    ChainInfo(ChainInfoCommand),
+    /// Rehearse the upgrade of a contract on a fork of the chain and report incompatibilities
+    #[clap(name = "simulate-upgrade")]
+    SimulateUpgrade(SimulateUpgradeCommand),
+    /// Export the storage of a contract, or restore it on a re-deployed contract
+    #[clap(name = "state")]
+    State(StateCommand),
+    /// Submit an extrinsic which was encoded and signed elsewhere, e.g. on an air-gapped device
+    #[clap(name = "submit")]
+    Submit(SubmitCommand),
+    /// Download the Wasm code of a code hash from the chain
+    #[clap(name = "download")]
+    Download(DownloadCommand),
+    /// Stream the debug messages of the calls of a contract on a development node
+    #[clap(name = "logs")]
+    Logs(LogsCommand),
+    /// Index the events of a contract into an SQLite database
+    #[clap(name = "index")]
+    Index(IndexCommand),
+    /// Replay a recorded `call` or `instantiate` session offline
+    #[clap(name = "replay")]
+    Replay(ReplayCommand),
+    /// Serve the artifacts of a contract and encode and decode its calls on localhost
+    #[clap(name = "serve")]
+    Serve(ServeCommand),
+    /// Generate shell completions for `cargo contract`
+    #[clap(name = "completions")]
+    Completions(CompletionsCommand),
+    /// Print the candidates for dynamic shell completions
+    #[clap(name = "__complete", hide = true)]
+    Complete(CompleteCommand),
+}
+
+/// Parses the arguments of `cargo contract` and executes the command, exiting the process with
+/// an error code if it fails.
+pub fn run() {
+    let args = normalize_args(std::env::args_os());
+    // the user configuration is read for the defaults of the options, before they are parsed
+    let config_file = defaults::option_value(&args, "config-file")
+        .or_else(|| std::env::var_os("CARGO_CONTRACT_CONFIG_FILE"));
+    if let Some(config_file) = config_file {
+        config::set_config_file(config_file.into());
+    }
+    let args = defaults::with_defaults(args);
+    let command = args
+        .get(2)
+        .and_then(|arg| arg.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let Opts::Contract(args) = Opts::parse_from(args);
+    if let Some(config_file) = &args.config_file {
+        config::set_config_file(config_file.clone());
+    }
+    output::init(args.no_color);
+    if let Err(err) = logging::init(args.log_format) {
+        output::warning(format!("{:?}", err));
+    }
+
+    let started = Instant::now();
+    let result = {
+        let _span = tracing::info_span!("command", command = %command).entered();
+        exec(args.cmd)
+    };
+    let total = started.elapsed();
+    let duration_ms = total.as_millis() as u64;
+    match &result {
+        Ok(()) => tracing::info!(
+            target: "cargo_contract::events::command",
+            %command,
+            duration_ms,
+            success = true,
+        ),
+        Err(err) => tracing::info!(
+            target: "cargo_contract::events::command",
+            %command,
+            duration_ms,
+            success = false,
+            error = %format!("{:#}", err),
+        ),
+    }
+    if args.timings || args.timings_file.is_some() {
+        let timings = Timings {
+            command,
+            total,
+            phases: timings::take(),
+        };
+        // on stderr, so that it does not mix with machine-readable output such as `--output-json`
+        eprintln!("{}", timings.summary());
+        if let Some(path) = &args.timings_file {
+            if let Err(err) = timings.write(path) {
+                output::warning(format!("{:?}", err));
+            }
+        }
+    }
+    match result {
+        Ok(()) => {}
+        Err(err) => {
+            output::error(format!("{:?}", err));
+            std::process::exit(exit_code::exit_code(&err) as i32);
+        }
+    }
+}
+
+/// Returns the `args` as if `cargo contract` was invoked.
+///
+/// `cargo contract build` executes `cargo-contract contract build`, while the binary invoked
+/// directly as `cargo-contract build` lacks the `contract` argument, which is inserted then.
+pub(crate) fn normalize_args<I>(args: I) -> Vec<OsString>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut args = args.into_iter().collect::<Vec<_>>();
+    if !args.is_empty() && args.get(1).and_then(|arg| arg.to_str()) != Some("contract") {
+        args.insert(1, "contract".into());
+    }
+    args
+}
+
+fn exec(cmd: Command) -> Result<()> {
+    match &cmd {
+        Command::New { name, target_dir } => {
+            cmd::new::execute(name, target_dir.as_ref())?;
+            println!("Created contract {}", name);
+            Ok(())
+        }
+        Command::NewChainExtension { name, target_dir } => {
+            cmd::new::execute_chain_extension(name, target_dir.as_ref())?;
+            println!("Created chain extension {}", name);
+            Ok(())
+        }
+        Command::Build(build) => {
+            if let Some(plan) = build.plan()? {
+                println!("{}", plan.serialize_json()?);
+                return Ok(());
+            }
+            if let Some(report) = build.compare()? {
+                if build.output_json() {
+                    println!("{}", report.serialize_json()?)
+                } else {
+                    print!("\n{}", report.display())
+                }
+                return Ok(());
+            }
+            let results = match build.exec_matrix()? {
+                Some(results) => Some(results),
+                None => build.exec_workspace()?,
+            };
+            if let Some(results) = results {
+                if matches!(results[0].output_type, OutputType::Json) {
+                    println!("{}", serde_json::to_string_pretty(&results)?)
+                } else {
+                    for result in results.iter().filter(|r| r.verbosity.is_verbose()) {
+                        println!("{}\n", result.display())
+                    }
+                }
+                return Ok(());
+            }
+            let result = build.exec()?;
+
+            if matches!(result.output_type, OutputType::Json) {
+                println!("{}", result.serialize_json()?)
+            } else if result.verbosity.is_verbose() {
+                println!("{}", result.display())
+            }
+            Ok(())
+        }
+        Command::Check(check) => {
+            let res = check.exec()?;
+            assert!(
+                res.dest_wasm.is_none(),
+                "no dest_wasm must be on the generation result"
+            );
+            if res.verbosity.is_verbose() {
+                println!("\nYour contract's code was built successfully.")
+            }
+            Ok(())
+        }
+        Command::Clean(clean) => clean.exec(),
+        Command::Add(add) => add.exec(),
+        Command::FixManifest(fix_manifest) => fix_manifest.exec(),
+        Command::Metadata(metadata) => metadata.exec(),
+        Command::SizeHistory(size_history) => size_history.exec(),
+        Command::Test(test) => {
+            let res = test.exec()?;
+            if res.verbosity.is_verbose() {
+                println!("{}", res.display()?)
+            }
+            Ok(())
+        }
+        Command::Graph(graph) => graph.exec(),
+        Command::InitCi(init_ci) => init_ci.exec(),
+        Command::InspectWasm(inspect) => inspect.exec(),
+        Command::WasmDiff(wasm_diff) => wasm_diff.exec(),
+        Command::Interface(interface) => interface.exec(),
+        Command::Conformance(conformance) => conformance.exec(),
+        Command::AccessControl(access_control) => access_control.exec(),
+        Command::Typegen(typegen) => typegen.exec(),
+        Command::Upload(upload) => upload.run(),
+        Command::Instantiate(instantiate) => instantiate.run(),
+        Command::Call(call) => call.run(),
+        Command::CallMany(call_many) => call_many.run(),
+        Command::Console(console) => console.run(),
+        Command::Get(get) => get.run(),
+        Command::Terminate(terminate) => terminate.run(),
+        Command::VerifyMetadata(verify) => verify.run(),
+        Command::AddressBook(address_book) => address_book.exec(),
+        Command::Account(account) => account.exec(),
+        Command::Deploy(deploy) => deploy.run(),
+        Command::BenchDeploy(bench) => bench.run(),
+        Command::ChainInfo(chain_info) => chain_info.run(),
+        Command::SimulateUpgrade(simulate) => simulate.run(),
+        Command::State(state) => state.run(),
+        Command::Submit(submit) => submit.run(),
+        Command::Download(download) => download.run(),
+        Command::Logs(logs) => logs.run(),
+        Command::Index(index) => index.run(),
+        Command::Replay(replay) => replay.run(),
+        Command::Serve(serve) => serve.run(),
+        Command::Completions(completions) => completions.exec(),
+        Command::Complete(complete) => complete.exec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_invocation_styles_are_parsed_alike() {
+        let parse = |args: &[&str]| {
+            let Opts::Contract(args) =
+                Opts::try_parse_from(normalize_args(args.iter().map(OsString::from))).unwrap();
+            format!("{:?}", args.cmd)
+        };
+        assert_eq!(
+            parse(&["cargo-contract", "contract", "test", "--", "flip"]),
+            parse(&["cargo-contract", "test", "--", "flip"])
+        );
+        assert!(parse(&["cargo-contract", "test", "--", "flip"]).contains("\"flip\""));
+
+        let err = Opts::try_parse_from(normalize_args(
+            ["cargo-contract", "instanciate"].iter().map(OsString::from),
+        ))
+        .unwrap_err();
+        assert_eq!(err.kind(), clap::ErrorKind::InvalidSubcommand);
+        assert!(err.to_string().contains("instantiate"), "{}", err);
+    }
+
+    #[test]
+    fn build_result_seralization_sanity_check() {
+        // given
+        let raw_result = r#"{
+  "dest_wasm": "/path/to/contract.wasm",
+  "metadata_result": {
+    "dest_metadata": "/path/to/metadata.json",
+    "dest_bundle": "/path/to/contract.contract"
+  },
+  "target_directory": "/path/to/target",
+  "optimization_result": {
+    "dest_wasm": "/path/to/contract.wasm",
+    "original_size": 64.0,
+    "optimized_size": 32.0
+  },
+  "build_mode": "Debug",
+  "build_artifact": "All",
+  "verbosity": "Quiet"
+}"#;
+
+        let build_result = crate::BuildResult {
+            dest_wasm: Some(PathBuf::from("/path/to/contract.wasm")),
+            metadata_result: Some(crate::cmd::metadata::MetadataResult {
+                dest_metadata: PathBuf::from("/path/to/metadata.json"),
+                dest_bundle: PathBuf::from("/path/to/contract.contract"),
+            }),
+            target_directory: PathBuf::from("/path/to/target"),
+            optimization_result: Some(crate::OptimizationResult {
+                dest_wasm: PathBuf::from("/path/to/contract.wasm"),
+                original_size: 64.0,
+                original_bytes: 64_000,
+                optimized_size: 32.0,
+                fallback: None,
+            }),
+            build_mode: Default::default(),
+            build_artifact: Default::default(),
+            verbosity: Verbosity::Quiet,
+            feature_set: None,
+            output_type: OutputType::Json,
+        };
+
+        // when
+        let serialized_result = build_result.serialize_json();
+
+        // then
+        assert!(serialized_result.is_ok());
+        assert_eq!(serialized_result.unwrap(), raw_result);
+    }
+}
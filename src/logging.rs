@@ -0,0 +1,79 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Logging to stderr, as human readable text or as JSON events for CI observability tools.
+//!
+//! Besides the log messages, the commands emit structured events below the [`EVENTS`] target,
+//! e.g. of the steps of a build, the sizes of its artifacts and the hashes of submitted
+//! extrinsics. These duplicate what is printed to stdout, so they are only logged as JSON.
+
+use anyhow::Result;
+use tracing_subscriber::EnvFilter;
+
+/// The target prefix of the structured events.
+pub const EVENTS: &str = "cargo_contract::events";
+
+/// The format of the logs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat::Text
+    }
+}
+
+/// Installs the global logger, filtered by `RUST_LOG`.
+///
+/// Without `RUST_LOG`, only errors are logged as text, like `env_logger` does, and the
+/// informational logs and all events of `cargo-contract` as JSON.
+pub(crate) fn init(format: LogFormat) -> Result<()> {
+    let default_filter = match format {
+        LogFormat::Text => "error",
+        LogFormat::Json => "warn,cargo_contract=info",
+    };
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    let builder = tracing_subscriber::fmt().with_writer(std::io::stderr);
+    let result = match format {
+        LogFormat::Text => builder
+            .with_env_filter(filter.add_directive(format!("{}=off", EVENTS).parse()?))
+            .try_init(),
+        LogFormat::Json => builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_env_filter(filter.add_directive(format!("{}=info", EVENTS).parse()?))
+            .try_init(),
+    };
+    result.map_err(|err| anyhow::anyhow!("Failed to initialize the logger: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ArgEnum;
+
+    #[test]
+    fn log_format_is_parsed() {
+        assert_eq!(LogFormat::from_str("json", false), Ok(LogFormat::Json));
+        assert_eq!(LogFormat::from_str("TEXT", true), Ok(LogFormat::Text));
+        assert!(LogFormat::from_str("yaml", true).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The rendering of the human readable output, used by the printing macros.
+//!
+//! Colors are written only to a terminal, unless disabled with `--no-color` or `NO_COLOR`, or
+//! forced with `CLICOLOR_FORCE`. Interactive prompts are only shown on a terminal as well.
+
+use colored::Colorize;
+use std::{ffi::OsStr, fmt::Display};
+
+/// Decides whether colors are written, for all output of the process.
+pub(crate) fn init(no_color: bool) {
+    let colors = if no_color || env_set("NO_COLOR") {
+        false
+    } else {
+        env_set("CLICOLOR_FORCE") || atty::is(atty::Stream::Stdout)
+    };
+    colored::control::set_override(colors);
+}
+
+/// Returns whether the environment variable `name` is set, to anything but empty or `0`.
+fn env_set(name: &str) -> bool {
+    std::env::var_os(name).map_or(false, |value| !value.is_empty() && value != OsStr::new("0"))
+}
+
+/// Returns whether the user can answer prompts, i.e. stdin is a terminal. The prompts are written
+/// to stderr, so stdout may be piped.
+pub(crate) fn is_interactive() -> bool {
+    atty::is(atty::Stream::Stdin)
+}
+
+/// Returns whether a password can be prompted for. It is read from the terminal of the process
+/// rather than from stdin, so stdin and stdout may both be redirected.
+pub(crate) fn has_terminal() -> bool {
+    #[cfg(unix)]
+    {
+        std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .is_ok()
+    }
+    #[cfg(not(unix))]
+    {
+        is_interactive()
+    }
+}
+
+/// Renders `value` after `name`, right aligned to `width` characters.
+///
+/// The lines of a value spanning several lines are aligned below each other.
+pub(crate) fn name_value(name: &str, value: &str, width: usize) -> String {
+    let indent = format!("\n{:width$} ", "", width = width);
+    let value = value.lines().collect::<Vec<_>>().join(&indent);
+    format!(
+        "{:>width$} {}",
+        name.bright_purple().bold(),
+        value.bright_white(),
+        width = width,
+    )
+}
+
+/// Renders the progress of the step `current` of `total` steps.
+pub(crate) fn step(current: impl Display, total: impl Display, description: &str) -> String {
+    format!(
+        " {} {}",
+        format!("[{}/{}]", current, total).bold(),
+        description.bright_green().bold()
+    )
+}
+
+/// Renders a change of the status of a submitted extrinsic.
+pub(crate) fn progress(status: impl Display) -> String {
+    format!("{} {}", "  =>".bright_blue().bold(), status)
+}
+
+/// Prints a warning to stderr.
+pub(crate) fn warning(message: impl Display) {
+    eprintln!("{} {}", "warning:".yellow().bold(), message);
+}
+
+/// Prints an error to stderr.
+pub(crate) fn error(message: impl Display) {
+    eprintln!(
+        "{} {}",
+        "ERROR:".bright_red().bold(),
+        message.to_string().bright_red()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_of_values_are_aligned() {
+        colored::control::set_override(false);
+        assert_eq!(name_value("Code hash", "0x01", 13), "    Code hash 0x01");
+        assert_eq!(
+            name_value("Debug Message", "first\nsecond", 13),
+            "Debug Message first\n              second"
+        );
+        assert_eq!(name_value("Größe", "1 KiB", 7), "  Größe 1 KiB");
+        assert_eq!(step(1, 5, "Building"), " [1/5] Building");
+        assert_eq!(progress("Ready"), "  => Ready");
+    }
+}
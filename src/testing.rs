@@ -0,0 +1,222 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers for end-to-end tests of contracts: creating and building contracts, spawning a
+//! contracts node and funding accounts on it.
+//!
+//! Enabled by the `testing` feature:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! cargo-contract = { version = "0.18", features = ["testing"] }
+//! ```
+
+use crate::{
+    cmd::{
+        build::{execute, ExecuteArgs},
+        extrinsics::{RuntimeApi, SignedExtra},
+    },
+    BuildMode, BuildResult,
+};
+use anyhow::Result;
+use sp_core::{sr25519, Pair};
+use std::{
+    ffi::OsStr,
+    path::Path,
+    process,
+    sync::atomic::{AtomicU32, Ordering},
+    thread, time,
+};
+use subxt::{Client, ClientBuilder, Config, DefaultConfig, PairSigner};
+
+pub use crate::workspace::ManifestPath;
+
+/// The executable of the contracts node spawned by default.
+pub const CONTRACTS_NODE: &str = "substrate-contracts-node";
+
+/// The secret URI of the endowed development account accounts are funded from.
+const FAUCET_SURI: &str = "//Alice";
+
+/// Creates a temporary directory and passes the `tmp_dir` path to `f`.
+/// Panics if `f` returns an `Err`.
+pub fn with_tmp_dir<F>(f: F)
+where
+    F: FnOnce(&Path) -> anyhow::Result<()>,
+{
+    let tmp_dir = tempfile::Builder::new()
+        .prefix("cargo-contract.test.")
+        .tempdir()
+        .expect("temporary directory creation failed");
+
+    // catch test panics in order to clean up temp dir which will be very large
+    f(&tmp_dir.path().canonicalize().unwrap()).expect("Error executing test with tmp dir")
+}
+
+/// Global counter to generate unique contract names in `with_new_contract_project`.
+///
+/// We typically use `with_tmp_dir` to generate temporary folders to build contracts
+/// in. But for caching purposes our CI uses `CARGO_TARGET_DIR` to overwrite the
+/// target directory of any contract build -- it is set to a fixed cache directory
+/// instead.
+/// This poses a problem since we still want to ensure that each test builds to its
+/// own, unique target directory -- without interfering with the target directory of
+/// other tests. In the past this has been a problem when a test tried to create a
+/// contract with the same contract name as another test -- both were then build
+/// into the same target directory, sometimes causing test failures for strange reasons.
+///
+/// The fix we decided on is to append a unique number to each contract name which
+/// is created. This `COUNTER` provides a global counter which is accessed by each test
+/// (in each thread) to get the current `COUNTER` number and increase it afterwards.
+///
+/// We decided to go for this counter instead of hashing (with e.g. the temp dir) to
+/// prevent an infinite number of contract artifacts being created in the cache directory.
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// Creates a new contract into a temporary directory. The contract's
+/// `ManifestPath` is passed into `f`.
+pub fn with_new_contract_project<F>(f: F)
+where
+    F: FnOnce(ManifestPath) -> anyhow::Result<()>,
+{
+    with_tmp_dir(|tmp_dir| {
+        let unique_name = format!("new_project_{}", COUNTER.fetch_add(1, Ordering::SeqCst));
+
+        crate::cmd::new::execute(&unique_name, Some(tmp_dir)).expect("new project creation failed");
+        let working_dir = tmp_dir.join(unique_name);
+        let manifest_path = ManifestPath::new(working_dir.join("Cargo.toml"))?;
+
+        f(manifest_path)
+    })
+}
+
+/// Builds the contract at `manifest_path` in release mode, as `cargo contract build --release`
+/// does, and returns the paths of its artifacts.
+pub fn build_contract<P: AsRef<Path>>(manifest_path: P) -> Result<BuildResult> {
+    execute(ExecuteArgs {
+        manifest_path: ManifestPath::new(manifest_path)?,
+        build_mode: BuildMode::Release,
+        ..Default::default()
+    })
+}
+
+/// A contracts node running in development mode, which is killed when dropped.
+pub struct ContractsNode {
+    proc: process::Child,
+    client: Client<DefaultConfig>,
+    // Removed when the node is dropped.
+    _tmp_dir: tempfile::TempDir,
+}
+
+impl Drop for ContractsNode {
+    fn drop(&mut self) {
+        self.kill()
+    }
+}
+
+impl ContractsNode {
+    /// Spawns the node `program`, e.g. [`CONTRACTS_NODE`], with a temporary base path and waits
+    /// until its RPC at `ws://127.0.0.1:9944` accepts connections.
+    pub async fn spawn<S>(program: S) -> Result<Self>
+    where
+        S: AsRef<OsStr>,
+    {
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("cargo-contract.cli.test.node")
+            .tempdir()?;
+
+        let mut proc = process::Command::new(program)
+            .env("RUST_LOG", "error")
+            .arg("--dev")
+            .arg(format!("--base-path={}", tmp_dir.path().to_string_lossy()))
+            .spawn()?;
+        // wait for rpc to be initialized
+        const MAX_ATTEMPTS: u32 = 10;
+        let mut attempts = 1;
+        let client = loop {
+            thread::sleep(time::Duration::from_secs(1));
+            log::info!(
+                "Connecting to contracts enabled node, attempt {}/{}",
+                attempts,
+                MAX_ATTEMPTS
+            );
+            let result = ClientBuilder::new().build().await;
+            if let Ok(client) = result {
+                break Ok(client);
+            }
+            if attempts < MAX_ATTEMPTS {
+                attempts += 1;
+                continue;
+            }
+            if let Err(err) = result {
+                break Err(err);
+            }
+        };
+        match client {
+            Ok(client) => Ok(Self {
+                proc,
+                client,
+                _tmp_dir: tmp_dir,
+            }),
+            Err(err) => {
+                let err = anyhow::anyhow!(
+                    "Failed to connect to node rpc after {} attempts: {}",
+                    attempts,
+                    err
+                );
+                log::error!("{}", err);
+                proc.kill()?;
+                Err(err)
+            }
+        }
+    }
+
+    /// The client connected to the node.
+    pub fn client(&self) -> &Client<DefaultConfig> {
+        &self.client
+    }
+
+    /// Transfers `amount` from the endowed `//Alice` account to `account`, waiting until the
+    /// transfer is finalized.
+    pub async fn fund_account(
+        &self,
+        account: &<DefaultConfig as Config>::AccountId,
+        amount: u128,
+    ) -> Result<()> {
+        let api = self.client.clone().to_runtime_api::<RuntimeApi>();
+        let faucet = sr25519::Pair::from_string(FAUCET_SURI, None)
+            .map_err(|err| anyhow::anyhow!("Invalid faucet secret URI: {:?}", err))?;
+        let signer = PairSigner::<DefaultConfig, SignedExtra, _>::new(faucet);
+        api.tx()
+            .balances()
+            .transfer(account.clone().into(), amount)
+            .sign_and_submit_then_watch(&signer)
+            .await?
+            .wait_for_finalized_success()
+            .await?;
+        Ok(())
+    }
+
+    fn kill(&mut self) {
+        log::info!("Killing contracts node process {}", self.proc.id());
+        if let Err(err) = self.proc.kill() {
+            log::error!(
+                "Error killing contracts node process {}: {}",
+                self.proc.id(),
+                err
+            )
+        }
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The time spent in the phases of a command, reported with `--timings`.
+//!
+//! The steps printed with [`step_println!`](crate::step_println) are phases which last until
+//! the next step starts, other phases, e.g. waiting for an RPC response, are timed with
+//! [`phase`]. Nothing is reported anywhere but to the user.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::{
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+static RECORDER: Mutex<Recorder> = Mutex::new(Recorder {
+    phases: Vec::new(),
+    step: None,
+});
+
+#[derive(Default)]
+struct Recorder {
+    phases: Vec<Phase>,
+    step: Option<(String, Instant)>,
+}
+
+impl Recorder {
+    fn record(&mut self, name: String, duration: Duration) {
+        match self.phases.iter_mut().find(|phase| phase.name == name) {
+            Some(phase) => phase.duration += duration,
+            None => self.phases.push(Phase { name, duration }),
+        }
+    }
+
+    fn end_step(&mut self) {
+        if let Some((name, started)) = self.step.take() {
+            self.record(name, started.elapsed());
+        }
+    }
+}
+
+/// The accumulated duration of all occurrences of a phase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Phase {
+    pub name: String,
+    #[serde(rename = "ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+}
+
+fn serialize_millis<S: serde::Serializer>(duration: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u64(duration.as_millis() as u64)
+}
+
+/// Times a phase until the returned guard is dropped.
+pub(crate) fn phase(name: &str) -> PhaseGuard {
+    PhaseGuard {
+        name: name.to_string(),
+        started: Instant::now(),
+    }
+}
+
+pub(crate) struct PhaseGuard {
+    name: String,
+    started: Instant,
+}
+
+impl Drop for PhaseGuard {
+    fn drop(&mut self) {
+        let duration = self.started.elapsed();
+        if let Ok(mut recorder) = RECORDER.lock() {
+            recorder.record(std::mem::take(&mut self.name), duration);
+        }
+    }
+}
+
+/// Ends the current step and starts the step `name`.
+pub(crate) fn step(name: &str) {
+    if let Ok(mut recorder) = RECORDER.lock() {
+        recorder.end_step();
+        recorder.step = Some((name.to_string(), Instant::now()));
+    }
+}
+
+/// Ends the current step, e.g. when the last step of a build is done.
+pub(crate) fn end_step() {
+    if let Ok(mut recorder) = RECORDER.lock() {
+        recorder.end_step();
+    }
+}
+
+/// Ends the current step and returns the phases recorded so far, in the order they started.
+pub(crate) fn take() -> Vec<Phase> {
+    match RECORDER.lock() {
+        Ok(mut recorder) => {
+            recorder.end_step();
+            std::mem::take(&mut recorder.phases)
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+/// The timings of a command, as written to `--timings-file`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Timings {
+    pub command: String,
+    #[serde(rename = "totalMs", serialize_with = "serialize_millis")]
+    pub total: Duration,
+    pub phases: Vec<Phase>,
+}
+
+impl Timings {
+    /// Renders the summary printed at the end of the command.
+    pub fn summary(&self) -> String {
+        let width = self
+            .phases
+            .iter()
+            .map(|phase| phase.name.len())
+            .max()
+            .unwrap_or_default()
+            .max("Total".len());
+        let mut summary = format!("{}\n", "Timings".bright_purple().bold());
+        for phase in &self.phases {
+            summary.push_str(&format!(
+                "  {:<width$} {:>9} {:>5.1}%\n",
+                phase.name,
+                format_duration(phase.duration),
+                percent(phase.duration, self.total),
+                width = width
+            ));
+        }
+        let other = self
+            .total
+            .saturating_sub(self.phases.iter().map(|phase| phase.duration).sum());
+        if !self.phases.is_empty() {
+            summary.push_str(&format!(
+                "  {:<width$} {:>9} {:>5.1}%\n",
+                "Other",
+                format_duration(other),
+                percent(other, self.total),
+                width = width
+            ));
+        }
+        let total = format!("{:<width$}", "Total", width = width);
+        summary.push_str(&format!(
+            "  {} {:>9}",
+            total.bold(),
+            format_duration(self.total)
+        ));
+        summary
+    }
+
+    /// Writes the timings as JSON to `path`.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .context(format!("Failed to write the timings to {}", path.display()))
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}s", duration.as_secs_f64())
+}
+
+fn percent(duration: Duration, total: Duration) -> f64 {
+    if total.is_zero() {
+        return 0.0;
+    }
+    duration.as_secs_f64() * 100.0 / total.as_secs_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_phases_are_accumulated() {
+        let mut recorder = Recorder::default();
+        recorder.record("RPC".into(), Duration::from_millis(200));
+        recorder.record("Inclusion".into(), Duration::from_millis(6000));
+        recorder.record("RPC".into(), Duration::from_millis(300));
+        assert_eq!(
+            recorder.phases,
+            [
+                Phase {
+                    name: "RPC".into(),
+                    duration: Duration::from_millis(500),
+                },
+                Phase {
+                    name: "Inclusion".into(),
+                    duration: Duration::from_millis(6000),
+                },
+            ]
+        );
+
+        let timings = Timings {
+            command: "instantiate".into(),
+            total: Duration::from_millis(7000),
+            phases: recorder.phases,
+        };
+        assert_eq!(
+            serde_json::to_value(&timings).unwrap(),
+            serde_json::json!({
+                "command": "instantiate",
+                "totalMs": 7000,
+                "phases": [
+                    { "name": "RPC", "ms": 500 },
+                    { "name": "Inclusion", "ms": 6000 },
+                ],
+            })
+        );
+        assert!(timings.summary().contains("Other         0.50s   7.1%"));
+    }
+}
@@ -0,0 +1,259 @@
+// Copyright 2018-2022 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Inspection of the `rustup` toolchain used for building contracts.
+
+use crate::{maybe_println, Verbosity};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::{
+    fmt::{Display, Formatter, Result as DisplayResult},
+    io::{self, BufRead, Write},
+    path::Path,
+    process::Command,
+    str,
+};
+
+/// The Wasm target contracts are compiled for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WasmTarget {
+    Wasm32,
+    /// 64-bit Wasm with the `memory64` proposal, enabled by `-Z wasm64`. Experimental, it is not
+    /// supported by any production runtime yet.
+    Wasm64,
+}
+
+impl Default for WasmTarget {
+    fn default() -> Self {
+        Self::Wasm32
+    }
+}
+
+impl WasmTarget {
+    /// Returns the target triple passed to `cargo --target`.
+    pub const fn triple(self) -> &'static str {
+        match self {
+            Self::Wasm32 => "wasm32-unknown-unknown",
+            Self::Wasm64 => "wasm64-unknown-unknown",
+        }
+    }
+}
+
+/// A part of the toolchain which is required for building contracts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Requirement {
+    /// The sources of the standard library, required by `-Z build-std`.
+    RustSrc,
+    /// The standard library for the Wasm target.
+    WasmTarget,
+}
+
+impl Requirement {
+    /// Returns the `rustup` arguments which install the requirement for `toolchain`.
+    fn install_args<'a>(&self, toolchain: &'a str) -> [&'a str; 5] {
+        match self {
+            Self::RustSrc => ["component", "add", "rust-src", "--toolchain", toolchain],
+            Self::WasmTarget => [
+                "target",
+                "add",
+                WasmTarget::Wasm32.triple(),
+                "--toolchain",
+                toolchain,
+            ],
+        }
+    }
+}
+
+impl Display for Requirement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        match self {
+            Self::RustSrc => write!(f, "the `rust-src` component"),
+            Self::WasmTarget => write!(f, "the `{}` target", WasmTarget::Wasm32.triple()),
+        }
+    }
+}
+
+/// Ensures that the `rust-src` component and the Wasm target are installed for the toolchain
+/// which is active in `working_dir`.
+///
+/// The standard library of `wasm64-unknown-unknown` is not distributed by `rustup`, it is built
+/// from `rust-src` by `-Z build-std` instead.
+///
+/// Missing requirements are installed with `rustup` if `assume_yes` is `true` or the user
+/// confirms the installation, otherwise an error is returned. If `rustup` is not installed the
+/// toolchain is not inspected.
+pub(crate) fn ensure_wasm_requirements(
+    working_dir: &Path,
+    wasm_target: WasmTarget,
+    assume_yes: bool,
+    verbosity: Verbosity,
+) -> Result<()> {
+    if which::which("rustup").is_err() {
+        log::debug!("rustup not found, skipping the inspection of the toolchain");
+        return Ok(());
+    }
+    let active_toolchain = rustup(working_dir, &["show", "active-toolchain"])?;
+    let toolchain = parse_active_toolchain(&active_toolchain).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Unexpected output of `rustup show active-toolchain`: {}",
+            active_toolchain
+        )
+    })?;
+    let components = rustup(
+        working_dir,
+        &["component", "list", "--installed", "--toolchain", toolchain],
+    )?;
+    let targets = rustup(
+        working_dir,
+        &["target", "list", "--installed", "--toolchain", toolchain],
+    )?;
+
+    for requirement in missing_requirements(&components, &targets, wasm_target) {
+        let install_args = requirement.install_args(toolchain);
+        let install_command = format!("rustup {}", install_args.join(" "));
+        if !assume_yes
+            && !confirm(&format!(
+                "{} is missing for the toolchain `{}`, run `{}`?",
+                requirement, toolchain, install_command
+            ))?
+        {
+            anyhow::bail!(
+                "Building contracts requires {}. Install it with `{}`, or pass `--yes` to \
+                install missing toolchain components automatically.",
+                requirement,
+                install_command
+            );
+        }
+        maybe_println!(
+            verbosity,
+            " {} {}",
+            "[toolchain]".bold(),
+            format!("Installing {}", requirement).bright_green().bold()
+        );
+        rustup(working_dir, &install_args)?;
+    }
+    Ok(())
+}
+
+/// Returns the name of the toolchain from the output of `rustup show active-toolchain`, e.g.
+/// `nightly-x86_64-unknown-linux-gnu (directory override for '/contracts/flipper')`.
+fn parse_active_toolchain(output: &str) -> Option<&str> {
+    output.split_whitespace().next()
+}
+
+/// Returns the requirements for the `wasm_target` which are not part of the `--installed`
+/// components and targets listed by `rustup`.
+fn missing_requirements(
+    components: &str,
+    targets: &str,
+    wasm_target: WasmTarget,
+) -> Vec<Requirement> {
+    let mut missing = Vec::new();
+    // `rust-src` is not specific to a target, hence it is listed without a target triple
+    if !components.lines().any(|line| line.trim() == "rust-src") {
+        missing.push(Requirement::RustSrc);
+    }
+    if wasm_target == WasmTarget::Wasm32
+        && !targets
+            .lines()
+            .any(|line| line.trim() == WasmTarget::Wasm32.triple())
+    {
+        missing.push(Requirement::WasmTarget);
+    }
+    missing
+}
+
+/// Asks the user to confirm `question`, a missing answer (e.g. without a terminal) is a no.
+fn confirm(question: &str) -> Result<bool> {
+    if !crate::output::is_interactive() {
+        return Ok(false);
+    }
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Invokes `rustup` with `args` in `working_dir`, so that toolchain overrides are respected.
+///
+/// If successful, returns the stdout.
+fn rustup(working_dir: &Path, args: &[&str]) -> Result<String> {
+    let mut cmd = Command::new("rustup");
+    cmd.args(args).current_dir(working_dir);
+    log::info!("Invoking rustup: {:?}", cmd);
+    let output = cmd
+        .output()
+        .context(format!("Error executing `{:?}`", cmd))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`{:?}` failed with exit code {:?}: {}",
+            cmd,
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(str::from_utf8(&output.stdout)
+        .context("rustup output is not valid utf-8")?
+        .trim()
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_toolchain_is_parsed() {
+        assert_eq!(
+            parse_active_toolchain(
+                "nightly-x86_64-unknown-linux-gnu (directory override for '/contracts/flipper')"
+            ),
+            Some("nightly-x86_64-unknown-linux-gnu")
+        );
+        assert_eq!(parse_active_toolchain(""), None);
+    }
+
+    #[test]
+    fn missing_requirements_are_detected() {
+        let components = "cargo-x86_64-unknown-linux-gnu\nrust-src\nrustc-x86_64-unknown-linux-gnu";
+        let targets = "x86_64-unknown-linux-gnu";
+        assert_eq!(
+            missing_requirements(components, targets, WasmTarget::Wasm32),
+            vec![Requirement::WasmTarget]
+        );
+        assert_eq!(
+            missing_requirements("rustc-x86_64-unknown-linux-gnu", "", WasmTarget::Wasm32),
+            vec![Requirement::RustSrc, Requirement::WasmTarget]
+        );
+        assert!(missing_requirements(
+            components,
+            "wasm32-unknown-unknown\nx86_64-unknown-linux-gnu",
+            WasmTarget::Wasm32
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn wasm64_only_requires_rust_src() {
+        let targets = "x86_64-unknown-linux-gnu";
+        assert!(missing_requirements("rust-src", targets, WasmTarget::Wasm64).is_empty());
+        assert_eq!(
+            missing_requirements("", targets, WasmTarget::Wasm64),
+            vec![Requirement::RustSrc]
+        );
+    }
+}
@@ -123,6 +123,26 @@ pub(crate) fn base_name(path: &Path) -> &str {
         .expect("must be valid utf-8")
 }
 
+/// The maximum length of a path on Windows, unless it is prefixed with `\\?\`.
+const WINDOWS_MAX_PATH: usize = 260;
+
+/// Returns the `path` without the `\\?\` prefix which `canonicalize` adds on Windows.
+///
+/// Many tools, including `cargo` build scripts, do not support these verbatim paths. The prefix
+/// is only retained for paths exceeding `MAX_PATH`, which cannot be accessed without it.
+pub(crate) fn strip_verbatim_prefix(path: PathBuf) -> PathBuf {
+    let stripped = match path.to_str().and_then(|path| path.strip_prefix(r"\\?\")) {
+        Some(stripped) => stripped,
+        None => return path,
+    };
+    let is_drive_path = stripped.as_bytes().get(1) == Some(&b':');
+    if is_drive_path && stripped.len() < WINDOWS_MAX_PATH {
+        PathBuf::from(stripped)
+    } else {
+        path
+    }
+}
+
 /// Decode hex string with or without 0x prefix
 pub fn decode_hex(input: &str) -> Result<Vec<u8>, hex::FromHexError> {
     if input.starts_with("0x") {
@@ -142,18 +162,44 @@ macro_rules! maybe_println {
     };
 }
 
+/// Prints the progress of a step of a command to stdout if `verbosity.is_verbose()` is `true`,
+/// and emits it as structured event.
+#[macro_export]
+macro_rules! step_println {
+    ($verbosity:expr, $current:expr, $total:expr, $description:expr) => {{
+        let description = $description;
+        ::tracing::info!(
+            target: "cargo_contract::events::step",
+            step = $current,
+            total = $total,
+            description = %description,
+        );
+        $crate::timings::step(&description.to_string());
+        $crate::maybe_println!(
+            $verbosity,
+            "{}",
+            $crate::output::step($current, $total, &description.to_string())
+        );
+    }};
+}
+
 pub const DEFAULT_KEY_COL_WIDTH: usize = 13;
 
-/// Pretty print name value, name right aligned with colour.
+/// Pretty print name value, name right aligned with colour, and emit it as structured event.
+///
+/// The lines of a value spanning several lines are aligned below each other.
 #[macro_export]
 macro_rules! name_value_println {
     ($name:tt, $value:expr, $width:expr) => {{
-        use colored::Colorize as _;
+        let value = $value;
+        ::tracing::info!(
+            target: "cargo_contract::events::output",
+            name = %$name,
+            value = %value,
+        );
         ::std::println!(
-            "{:>width$} {}",
-            $name.bright_purple().bold(),
-            $value.bright_white(),
-            width = $width,
+            "{}",
+            $crate::output::name_value(&$name.to_string(), &value.to_string(), $width)
         );
     }};
     ($name:tt, $value:expr) => {
@@ -163,61 +209,30 @@ macro_rules! name_value_println {
 
 #[cfg(test)]
 pub mod tests {
-    use crate::ManifestPath;
-    use std::path::Path;
-    use std::sync::atomic::{AtomicU32, Ordering};
-
-    /// Creates a temporary directory and passes the `tmp_dir` path to `f`.
-    /// Panics if `f` returns an `Err`.
-    pub fn with_tmp_dir<F>(f: F)
-    where
-        F: FnOnce(&Path) -> anyhow::Result<()>,
-    {
-        let tmp_dir = tempfile::Builder::new()
-            .prefix("cargo-contract.test.")
-            .tempdir()
-            .expect("temporary directory creation failed");
-
-        // catch test panics in order to clean up temp dir which will be very large
-        f(&tmp_dir.path().canonicalize().unwrap()).expect("Error executing test with tmp dir")
-    }
-
-    /// Global counter to generate unique contract names in `with_new_contract_project`.
-    ///
-    /// We typically use `with_tmp_dir` to generate temporary folders to build contracts
-    /// in. But for caching purposes our CI uses `CARGO_TARGET_DIR` to overwrite the
-    /// target directory of any contract build -- it is set to a fixed cache directory
-    /// instead.
-    /// This poses a problem since we still want to ensure that each test builds to its
-    /// own, unique target directory -- without interfering with the target directory of
-    /// other tests. In the past this has been a problem when a test tried to create a
-    /// contract with the same contract name as another test -- both were then build
-    /// into the same target directory, sometimes causing test failures for strange reasons.
-    ///
-    /// The fix we decided on is to append a unique number to each contract name which
-    /// is created. This `COUNTER` provides a global counter which is accessed by each test
-    /// (in each thread) to get the current `COUNTER` number and increase it afterwards.
-    ///
-    /// We decided to go for this counter instead of hashing (with e.g. the temp dir) to
-    /// prevent an infinite number of contract artifacts being created in the cache directory.
-    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    use super::strip_verbatim_prefix;
+    use std::path::PathBuf;
 
-    /// Creates a new contract into a temporary directory. The contract's
-    /// `ManifestPath` is passed into `f`.
-    pub fn with_new_contract_project<F>(f: F)
-    where
-        F: FnOnce(ManifestPath) -> anyhow::Result<()>,
-    {
-        with_tmp_dir(|tmp_dir| {
-            let unique_name = format!("new_project_{}", COUNTER.fetch_add(1, Ordering::SeqCst));
+    pub use crate::testing::{with_new_contract_project, with_tmp_dir};
 
-            crate::cmd::new::execute(&unique_name, Some(tmp_dir))
-                .expect("new project creation failed");
-            let working_dir = tmp_dir.join(unique_name);
-            let manifest_path = ManifestPath::new(working_dir.join("Cargo.toml"))?;
-
-            f(manifest_path)
-        })
+    #[test]
+    fn verbatim_prefix_is_stripped_from_short_drive_paths() {
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from(r"\\?\C:\contracts\flipper")),
+            PathBuf::from(r"C:\contracts\flipper")
+        );
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from(r"\\?\UNC\server\share")),
+            PathBuf::from(r"\\?\UNC\server\share")
+        );
+        let long_path = format!(r"\\?\C:\{}", "a".repeat(300));
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from(&long_path)),
+            PathBuf::from(&long_path)
+        );
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from("/tmp/flipper")),
+            PathBuf::from("/tmp/flipper")
+        );
     }
 }
 
@@ -235,9 +250,13 @@ pub fn unzip(template: &[u8], out_dir: PathBuf, name: Option<&str>) -> Result<()
 
     for i in 0..archive.len() {
         let mut file = archive.by_index(i)?;
-        let outpath = out_dir.join(file.name());
+        // `enclosed_name` converts the `/` separated name of the archive into a native path
+        let outpath = match file.enclosed_name() {
+            Some(path) => out_dir.join(path),
+            None => anyhow::bail!("Invalid file name in archive: {}", file.name()),
+        };
 
-        if (*file.name()).ends_with('/') {
+        if file.is_dir() {
             fs::create_dir_all(&outpath)?;
         } else {
             if let Some(p) = outpath.parent() {
@@ -14,15 +14,24 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::Colorize;
 use impl_serde::serialize as serde_hex;
-use parity_wasm::elements::Module;
+use parity_wasm::elements::{ImportCountType, Instruction, Module};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as DisplayResult},
+};
+use wasmparser::{Parser, Payload, Validator, WasmFeatures};
 
 /// Marker inserted by the ink! codegen for an error which can't
 /// be checked at compile time.
 const INK_ENFORCE_ERR: &str = "__ink_enforce_error_";
 
+/// The number of offending instructions listed in the error of the validation, unless all of
+/// them are requested with `--list-offending`.
+const MAX_LISTED_OFFENDING: usize = 5;
+
 /// Errors which may occur when forwarding a call is not allowed.
 ///
 /// We insert markers for these errors in the generated contract code.
@@ -76,18 +85,50 @@ pub enum EnforcedErrors {
 /// - Markers inserted by the ink! codegen for errors which can't be checked at compile time.
 pub fn validate_import_section(module: &Module) -> Result<()> {
     let imports = match module.import_section() {
-        Some(section) => section.entries().iter(),
+        Some(section) => section.entries().iter().map(|entry| entry.field()),
         None => {
             // the module does not contain any imports,
             // hence no further validation is necessary.
             return Ok(());
         }
     };
+    validate_imports(imports.collect())
+}
+
+/// Validates the `wasm64` module, which `parity-wasm` can not parse as it does not support the
+/// `memory64` proposal.
+///
+/// Besides the checks of the import section, the module is validated against the `memory64`
+/// proposal, rejecting the non-deterministic floating point instructions if `deterministic_only`.
+pub fn validate_wasm64(wasm: &[u8], deterministic_only: bool) -> Result<()> {
+    let mut validator = Validator::new();
+    validator.wasm_features(WasmFeatures {
+        memory64: true,
+        deterministic_only,
+        ..Default::default()
+    });
+    validator
+        .validate_all(wasm)
+        .context("Validation of the wasm64 module failed")?;
+
+    let mut imports = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::ImportSection(reader) = payload? {
+            for import in reader {
+                imports.push(import?.field.unwrap_or_default());
+            }
+        }
+    }
+    validate_imports(imports)
+}
+
+/// Validates the `fields` of the imports of the Wasm.
+fn validate_imports(fields: Vec<&str>) -> Result<()> {
+    let imports = fields.into_iter();
     let original_imports_len = imports.len();
     let mut errs = Vec::new();
 
-    let filtered_imports = imports.filter(|section| {
-        let field = section.field();
+    let filtered_imports = imports.filter(|field| {
         if field.contains("panic") {
             errs.push(String::from(
                 "An unexpected panic function import was found in the contract Wasm.\n\
@@ -122,6 +163,215 @@ pub fn validate_import_section(module: &Module) -> Result<()> {
     Ok(())
 }
 
+/// Validates that the code of the Wasm contains no non-deterministic instructions, i.e. floating
+/// point instructions, which are rejected by `pallet-contracts` when uploading the code.
+///
+/// The offending instructions are reported with the name of their function from the name
+/// section, if present, whose demangled path approximates their location in the source code.
+/// Only the first few are listed, unless `list_offending` is set.
+pub fn validate_instructions(module: &Module, list_offending: bool) -> Result<()> {
+    let offending = offending_instructions(module);
+    if offending.is_empty() {
+        return Ok(());
+    }
+    let listed = if list_offending {
+        offending.len()
+    } else {
+        offending.len().min(MAX_LISTED_OFFENDING)
+    };
+    let mut msg = format!(
+        "{} non-deterministic instructions were found in the contract Wasm.\n\
+         Floating point arithmetic is not supported by `pallet-contracts`, the instructions are \
+         used in:",
+        offending.len()
+    );
+    for instruction in &offending[..listed] {
+        msg.push_str(&format!("\n  {}", instruction));
+    }
+    if listed < offending.len() {
+        msg.push_str(&format!(
+            "\n  ... and {} more, pass `--list-offending` to list all of them",
+            offending.len() - listed
+        ));
+    }
+    anyhow::bail!(msg)
+}
+
+/// A non-deterministic instruction in the code of a function.
+struct OffendingInstruction {
+    /// The index of the function, including the imported functions.
+    function: u32,
+    /// The demangled name of the function from the name section.
+    name: Option<String>,
+    /// The position of the instruction in the body of the function.
+    position: usize,
+    instruction: String,
+}
+
+impl Display for OffendingInstruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        match &self.name {
+            Some(name) => write!(f, "`{}` (function #{})", name, self.function)?,
+            None => write!(f, "function #{}", self.function)?,
+        }
+        write!(
+            f,
+            ", instruction {}: {}",
+            self.position,
+            self.instruction.trim()
+        )
+    }
+}
+
+/// Returns all floating point instructions in the code of the `module`.
+fn offending_instructions(module: &Module) -> Vec<OffendingInstruction> {
+    let module = module
+        .clone()
+        .parse_names()
+        .unwrap_or_else(|(_, module)| module);
+    let names = module
+        .names_section()
+        .and_then(|section| section.functions())
+        .map(|functions| functions.names());
+    let imported = module.import_count(ImportCountType::Function) as u32;
+    let bodies = module
+        .code_section()
+        .map(|section| section.bodies())
+        .unwrap_or_default();
+
+    let mut offending = Vec::new();
+    for (index, body) in bodies.iter().enumerate() {
+        let function = imported + index as u32;
+        for (position, instruction) in body.code().elements().iter().enumerate() {
+            if !is_float_instruction(instruction) {
+                continue;
+            }
+            offending.push(OffendingInstruction {
+                function,
+                name: names
+                    .and_then(|names| names.get(function))
+                    .map(|name| format!("{:#}", rustc_demangle::demangle(name))),
+                position,
+                instruction: instruction.to_string(),
+            });
+        }
+    }
+    offending
+}
+
+/// Returns `true` for instructions operating on or converting from or to floating point numbers.
+fn is_float_instruction(instruction: &Instruction) -> bool {
+    use Instruction::*;
+    matches!(
+        instruction,
+        F32Load(..)
+            | F64Load(..)
+            | F32Store(..)
+            | F64Store(..)
+            | F32Const(_)
+            | F64Const(_)
+            | F32Eq
+            | F32Ne
+            | F32Lt
+            | F32Gt
+            | F32Le
+            | F32Ge
+            | F64Eq
+            | F64Ne
+            | F64Lt
+            | F64Gt
+            | F64Le
+            | F64Ge
+            | F32Abs
+            | F32Neg
+            | F32Ceil
+            | F32Floor
+            | F32Trunc
+            | F32Nearest
+            | F32Sqrt
+            | F32Add
+            | F32Sub
+            | F32Mul
+            | F32Div
+            | F32Min
+            | F32Max
+            | F32Copysign
+            | F64Abs
+            | F64Neg
+            | F64Ceil
+            | F64Floor
+            | F64Trunc
+            | F64Nearest
+            | F64Sqrt
+            | F64Add
+            | F64Sub
+            | F64Mul
+            | F64Div
+            | F64Min
+            | F64Max
+            | F64Copysign
+            | I32TruncSF32
+            | I32TruncUF32
+            | I32TruncSF64
+            | I32TruncUF64
+            | I64TruncSF32
+            | I64TruncUF32
+            | I64TruncSF64
+            | I64TruncUF64
+            | F32ConvertSI32
+            | F32ConvertUI32
+            | F32ConvertSI64
+            | F32ConvertUI64
+            | F32DemoteF64
+            | F64ConvertSI32
+            | F64ConvertUI32
+            | F64ConvertSI64
+            | F64ConvertUI64
+            | F64PromoteF32
+            | I32ReinterpretF32
+            | I64ReinterpretF64
+            | F32ReinterpretI32
+            | F64ReinterpretI64
+    )
+}
+
+/// Validates the custom sections in the Wasm against the allow-list of sections declared in
+/// `[package.metadata.contract.custom-sections]`.
+///
+/// Besides the `name` section holding the debug symbols, only the declared sections are allowed,
+/// and each of them must be present with the declared content, e.g. after the optimization.
+pub fn validate_custom_sections(module: &Module, allowed: &BTreeMap<String, String>) -> Result<()> {
+    let mut found = BTreeMap::new();
+    for section in module.custom_sections() {
+        if section.name() == "name" {
+            continue;
+        }
+        if !allowed.contains_key(section.name()) {
+            anyhow::bail!(
+                "An unexpected custom section was found in the contract Wasm: {}.\n\
+                 Only the custom sections declared in `[package.metadata.contract.custom-sections]` \
+                 are allowed.",
+                section.name()
+            );
+        }
+        found.insert(section.name(), section.payload());
+    }
+    for (name, content) in allowed {
+        match found.get(name.as_str()) {
+            Some(payload) if *payload == content.as_bytes() => (),
+            Some(_) => anyhow::bail!(
+                "The custom section `{}` of the contract Wasm differs from its declaration",
+                name
+            ),
+            None => anyhow::bail!(
+                "The custom section `{}` is missing from the contract Wasm",
+                name
+            ),
+        }
+    }
+    Ok(())
+}
+
 /// Returns `true` if the import is allowed.
 fn check_import(field: &str) -> Result<(), String> {
     let allowed_prefixes = ["seal", "memory"];
@@ -194,14 +444,39 @@ fn parse_linker_error(field: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::validate_import_section;
-    use parity_wasm::elements::Module;
+    use super::{
+        is_float_instruction, validate_custom_sections, validate_import_section,
+        validate_instructions, validate_wasm64,
+    };
+    use parity_wasm::elements::{CustomSection, Module, Section};
+    use std::collections::BTreeMap;
 
     fn create_module(contract: &str) -> Module {
         let wasm = wabt::wat2wasm(contract).expect("invalid wabt");
         parity_wasm::deserialize_buffer(&wasm).expect("deserializing must work")
     }
 
+    #[test]
+    fn wasm64_imports_are_validated() {
+        // given
+        let contract = r#"
+            (module
+                (type (;0;) (func (param i32 i32 i32)))
+                (import "env" "_ZN4core9panicking5panic17h00e3acdd8048cb7cE" (func (;0;) (type 0)))
+                (import "seal0" "seal_return" (func (;1;) (type 0)))
+            )"#;
+        let wasm = wabt::wat2wasm(contract).expect("invalid wabt");
+
+        // when
+        let res = validate_wasm64(&wasm, true);
+
+        // then
+        assert!(res
+            .unwrap_err()
+            .to_string()
+            .contains("An unexpected panic function import was found in the contract Wasm."));
+    }
+
     #[test]
     fn must_catch_panic_import() {
         // given
@@ -320,4 +595,72 @@ mod tests {
         // then
         assert!(res.is_ok());
     }
+
+    #[test]
+    fn must_only_allow_declared_custom_sections() {
+        // given
+        let mut module = create_module("(module)");
+        module
+            .sections_mut()
+            .push(Section::Custom(CustomSection::new(
+                "license".into(),
+                b"Apache-2.0".to_vec(),
+            )));
+        let mut allowed = BTreeMap::new();
+
+        // when
+        let undeclared = validate_custom_sections(&module, &allowed);
+        allowed.insert("license".to_string(), "Apache-2.0".to_string());
+        let declared = validate_custom_sections(&module, &allowed);
+        allowed.insert("source".to_string(), "https://example.com".to_string());
+        let missing = validate_custom_sections(&module, &allowed);
+
+        // then
+        assert!(undeclared
+            .unwrap_err()
+            .to_string()
+            .contains("An unexpected custom section was found in the contract Wasm: license."));
+        assert!(declared.is_ok());
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn must_list_float_instructions() {
+        // given
+        let contract = r#"
+            (module
+                (func (;0;) (param f32) (result f32)
+                    get_local 0
+                    f32.const 1
+                    f32.add)
+                (func (;1;) (param i32) (result i32)
+                    get_local 0)
+                (func (;2;) (param i32) (result f64)
+                    get_local 0
+                    f64.convert_s/i32)
+            )"#;
+        let module = create_module(contract);
+
+        // when
+        let res = validate_instructions(&module, true);
+
+        // then
+        let err = res.unwrap_err().to_string();
+        assert!(err.contains("3 non-deterministic instructions were found in the contract Wasm."));
+        assert!(err.contains("function #0, instruction 1"));
+        assert!(err.contains("function #2, instruction 1"));
+        assert!(!err.contains("function #1"));
+    }
+
+    #[test]
+    fn instructions_typed_with_floats_are_no_float_instructions() {
+        use parity_wasm::elements::{BlockType, Instruction, ValueType};
+
+        assert!(is_float_instruction(&Instruction::F32Add));
+        assert!(is_float_instruction(&Instruction::I64TruncSF64));
+        assert!(!is_float_instruction(&Instruction::Block(
+            BlockType::Value(ValueType::F32)
+        )));
+        assert!(!is_float_instruction(&Instruction::I32Add));
+    }
 }
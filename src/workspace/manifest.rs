@@ -16,11 +16,11 @@
 
 use anyhow::{Context, Result};
 
-use super::{metadata, Profile};
+use super::{metadata, Profile, ProfileConflict};
 use crate::OptimizationPasses;
 
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     convert::TryFrom,
     fs,
     path::{Path, PathBuf},
@@ -57,6 +57,7 @@ impl ManifestPath {
             .path
             .canonicalize()
             .map_err(|err| anyhow::anyhow!("Failed to canonicalize {:?}: {:?}", self.path, err))?;
+        let path = crate::util::strip_verbatim_prefix(path);
         Ok(format!("--manifest-path={}", path.to_string_lossy()))
     }
 
@@ -142,13 +143,21 @@ impl Manifest {
 
     /// Get mutable reference to `[lib] crate-types = []` section
     fn get_crate_types_mut(&mut self) -> Result<&mut value::Array> {
-        let lib = self
-            .toml
-            .get_mut("lib")
-            .ok_or_else(|| anyhow::anyhow!("lib section not found"))?;
-        let crate_types = lib
-            .get_mut("crate-type")
-            .ok_or_else(|| anyhow::anyhow!("crate-type section not found"))?;
+        let path = self.path.as_ref().display().to_string();
+        let lib = self.toml.get_mut("lib").ok_or_else(|| {
+            anyhow::anyhow!(
+                "No `[lib]` section found in {}, contracts are compiled from a library target \
+                 with `crate-type = [\"cdylib\"]`",
+                path
+            )
+        })?;
+        let crate_types = lib.get_mut("crate-type").ok_or_else(|| {
+            anyhow::anyhow!(
+                "No `crate-type` found in the `[lib]` section of {}, add \
+                 `crate-type = [\"cdylib\"]`",
+                path
+            )
+        })?;
 
         crate_types
             .as_array_mut()
@@ -180,6 +189,50 @@ impl Manifest {
             .map(Into::into)
     }
 
+    /// Extract `feature-matrix` from `[package.metadata.contract]`, mapping the name of each
+    /// feature set to its features.
+    pub fn get_feature_matrix(&self) -> Result<Option<BTreeMap<String, Vec<String>>>> {
+        self.toml
+            .get("package")
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get("contract"))
+            .and_then(|contract| contract.get("feature-matrix"))
+            .cloned()
+            .map(|matrix| matrix.try_into())
+            .transpose()
+            .context("`feature-matrix` should map names to arrays of features")
+    }
+
+    /// Get the profile with the given `name` from `[package.metadata.contract.profiles]`.
+    pub fn get_contract_profile(&self, name: &str) -> Option<&value::Value> {
+        self.get_contract_profiles()?.get(name)
+    }
+
+    /// Get the names of all profiles in `[package.metadata.contract.profiles]`.
+    pub fn get_contract_profile_names(&self) -> Vec<String> {
+        self.get_contract_profiles()
+            .map(|profiles| profiles.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn get_contract_profiles(&self) -> Option<&value::Table> {
+        self.toml
+            .get("package")?
+            .get("metadata")?
+            .get("contract")?
+            .get("profiles")?
+            .as_table()
+    }
+
+    /// Get the names of all features declared in the `[features]` section.
+    pub fn get_features(&self) -> Vec<String> {
+        self.toml
+            .get("features")
+            .and_then(|features| features.as_table())
+            .map(|features| features.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
     /// Set `optimization-passes` in `[package.metadata.contract]`
     #[cfg(feature = "test-ci-only")]
     #[cfg(test)]
@@ -287,6 +340,46 @@ impl Manifest {
         Ok(self)
     }
 
+    /// Set the `[profile.release]` settings of the `defaults`, overwriting the user defined ones.
+    pub fn with_profile_release_enforced(&mut self, defaults: Profile) -> Result<&mut Self> {
+        let profile_release = self.get_profile_release_table_mut()?;
+        defaults.enforce(profile_release);
+        Ok(self)
+    }
+
+    /// Set the `[profile.release]` settings of the workspace which are not defined by this
+    /// manifest.
+    ///
+    /// Profiles of workspace members are ignored by `cargo`, so a member which is built as the
+    /// root of a temporary workspace inherits the profile of its original workspace this way.
+    pub fn with_inherited_profile_release(
+        &mut self,
+        workspace_profile: &value::Table,
+    ) -> Result<&mut Self> {
+        let profile_release = self.get_profile_release_table_mut()?;
+        for (key, value) in workspace_profile {
+            if !profile_release.contains_key(key) {
+                profile_release.insert(key.clone(), value.clone());
+            }
+        }
+        Ok(self)
+    }
+
+    /// Returns the `[profile.release]` settings which differ from the `defaults`.
+    pub fn profile_release_conflicts(&self, defaults: &Profile) -> Vec<ProfileConflict> {
+        self.profile_release()
+            .map(|profile| defaults.conflicts(profile))
+            .unwrap_or_default()
+    }
+
+    /// Returns the `[profile.release]` section, if any.
+    pub fn profile_release(&self) -> Option<&value::Table> {
+        self.toml
+            .get("profile")
+            .and_then(|profile| profile.get("release"))
+            .and_then(value::Value::as_table)
+    }
+
     /// Set empty `[workspace]` section if it does not exist.
     ///
     /// Ignores the `workspace` from the parent `Cargo.toml`.
@@ -396,37 +489,44 @@ impl Manifest {
             Ok(())
         };
 
-        let rewrite_path = |table_value: &mut value::Value, table_section: &str, default: &str| {
-            let table = table_value.as_table_mut().ok_or_else(|| {
-                anyhow::anyhow!("'[{}]' section should be a table", table_section)
-            })?;
+        // The default paths are tried in order, like `cargo` does for targets without a `path`.
+        let rewrite_path =
+            |table_value: &mut value::Value, table_section: &str, defaults: &[String]| {
+                let table = table_value.as_table_mut().ok_or_else(|| {
+                    anyhow::anyhow!("'[{}]' section should be a table", table_section)
+                })?;
 
-            match table.get_mut("path") {
-                Some(existing_path) => {
-                    to_absolute(format!("[{}]/path", table_section), existing_path)
-                }
-                None => {
-                    let default_path = PathBuf::from(default);
-                    if !default_path.exists() {
-                        anyhow::bail!(
-                            "No path specified, and the default `{}` was not found",
-                            default
-                        )
+                match table.get_mut("path") {
+                    Some(existing_path) => {
+                        to_absolute(format!("[{}]/path", table_section), existing_path)
+                    }
+                    None => {
+                        let path = defaults
+                            .iter()
+                            .map(|default| abs_dir.join(default))
+                            .find(|path| path.exists())
+                            .ok_or_else(|| {
+                                anyhow::anyhow!(
+                                    "No path specified for '[{}]' in {}, and none of the defaults \
+                                 `{}` was found",
+                                    table_section,
+                                    abs_path.display(),
+                                    defaults.join("`, `")
+                                )
+                            })?;
+                        log::debug!("Adding default path '{}'", path.display());
+                        table.insert(
+                            "path".into(),
+                            value::Value::String(path.to_string_lossy().into()),
+                        );
+                        Ok(())
                     }
-                    let path = abs_dir.join(default_path);
-                    log::debug!("Adding default path '{}'", path.display());
-                    table.insert(
-                        "path".into(),
-                        value::Value::String(path.to_string_lossy().into()),
-                    );
-                    Ok(())
                 }
-            }
-        };
+            };
 
         // Rewrite `[lib] path = /path/to/lib.rs`
         if let Some(lib) = self.toml.get_mut("lib") {
-            rewrite_path(lib, "lib", "src/lib.rs")?;
+            rewrite_path(lib, "lib", &["src/lib.rs".to_string()])?;
         }
 
         // Rewrite `[[bin]] path = /path/to/main.rs`
@@ -437,7 +537,17 @@ impl Manifest {
 
             // Rewrite `[[bin]] path =` value to an absolute path.
             for bin in bins {
-                rewrite_path(bin, "[bin]", "src/main.rs")?;
+                let name = bin
+                    .get("name")
+                    .and_then(|name| name.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                let defaults = [
+                    format!("src/bin/{}.rs", name),
+                    format!("src/bin/{}/main.rs", name),
+                    "src/main.rs".to_string(),
+                ];
+                rewrite_path(bin, "[bin]", &defaults)?;
             }
         }
 
@@ -524,7 +634,7 @@ fn crate_type_exists(crate_type: &str, crate_types: &[value::Value]) -> bool {
 
 #[cfg(test)]
 mod test {
-    use super::ManifestPath;
+    use super::{Manifest, ManifestPath};
     use crate::util::tests::with_tmp_dir;
     use std::fs;
 
@@ -547,4 +657,45 @@ mod test {
             Ok(())
         })
     }
+
+    #[test]
+    fn must_rewrite_default_paths_of_lib_and_bin_targets() {
+        with_tmp_dir(|path| {
+            // given
+            let cargo_toml_path = path.join("Cargo.toml");
+            fs::write(
+                &cargo_toml_path,
+                r#"
+                    [package]
+                    name = "flipper"
+                    version = "0.1.0"
+
+                    [lib]
+                    crate-type = ["cdylib"]
+
+                    [[bin]]
+                    name = "deploy"
+                "#,
+            )?;
+            fs::create_dir_all(path.join("src/bin"))?;
+            fs::write(path.join("src/lib.rs"), "")?;
+            fs::write(path.join("src/bin/deploy.rs"), "")?;
+            let manifest_path = ManifestPath::new(&cargo_toml_path)?;
+            let mut manifest = Manifest::new(manifest_path)?;
+
+            // when
+            manifest.rewrite_relative_paths::<_, &str>([])?;
+
+            // then
+            let path = path.canonicalize()?;
+            let lib_path = manifest.toml["lib"]["path"].as_str().unwrap().to_string();
+            let bin_path = manifest.toml["bin"][0]["path"]
+                .as_str()
+                .unwrap()
+                .to_string();
+            assert_eq!(lib_path, path.join("src/lib.rs").to_string_lossy());
+            assert_eq!(bin_path, path.join("src/bin/deploy.rs").to_string_lossy());
+            Ok(())
+        })
+    }
 }
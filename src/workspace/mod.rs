@@ -21,10 +21,10 @@ mod profile;
 #[doc(inline)]
 pub use self::{
     manifest::{Manifest, ManifestPath},
-    profile::Profile,
+    profile::{Profile, ProfileConflict},
 };
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use cargo_metadata::{Metadata as CargoMetadata, Package, PackageId};
 
 use std::{
@@ -32,6 +32,10 @@ use std::{
     path::{Path, PathBuf},
 };
 
+/// The file in a kept temporary workspace with the path of the manifest it was created for, so
+/// that `cargo contract clean` only removes the workspaces of its contract.
+pub const KEPT_WORKSPACE_MARKER: &str = ".cargo-contract-manifest";
+
 /// Make a copy of a cargo workspace, maintaining only the directory structure and manifest
 /// files. Relative paths to source files and non-workspace dependencies are rewritten to absolute
 /// paths to the original locations.
@@ -42,6 +46,7 @@ pub struct Workspace {
     workspace_root: PathBuf,
     root_package: PackageId,
     members: HashMap<PackageId, (Package, Manifest)>,
+    keep_temp_dir: bool,
 }
 
 impl Workspace {
@@ -77,9 +82,17 @@ impl Workspace {
             workspace_root: metadata.workspace_root.clone().into(),
             root_package: root_package.clone(),
             members,
+            keep_temp_dir: false,
         })
     }
 
+    /// Do not remove the temporary directory created by [`Workspace::using_temp`], so that it can
+    /// be inspected afterwards.
+    pub fn keep_temp_dir(&mut self, keep: bool) -> &mut Self {
+        self.keep_temp_dir = keep;
+        self
+    }
+
     /// Amend the root package manifest using the supplied function.
     ///
     /// # Note
@@ -171,7 +184,10 @@ impl Workspace {
 
     /// Copy the workspace with amended manifest files to a temporary directory, executing the
     /// supplied function with the root manifest path before the directory is cleaned up.
-    pub fn using_temp<F>(&mut self, f: F) -> Result<()>
+    ///
+    /// If the directory is kept, its path is returned. Should `f` fail the path is part of the
+    /// error instead.
+    pub fn using_temp<F>(&mut self, f: F) -> Result<Option<PathBuf>>
     where
         F: FnOnce(&ManifestPath) -> Result<()>,
     {
@@ -180,6 +196,12 @@ impl Workspace {
             .tempdir()?;
         log::debug!("Using temp workspace at '{}'", tmp_dir.path().display());
         let new_paths = self.write(&tmp_dir)?;
+        // Without the lockfile cargo would resolve all dependencies anew, which requires network
+        // access and might result in different dependency versions being used.
+        let lockfile = self.workspace_root.join("Cargo.lock");
+        if lockfile.exists() {
+            std::fs::copy(&lockfile, tmp_dir.path().join("Cargo.lock"))?;
+        }
         let root_manifest_path = new_paths
             .iter()
             .find_map(|(pid, path)| {
@@ -190,6 +212,104 @@ impl Workspace {
                 }
             })
             .expect("root package should be a member of the temp workspace");
-        f(root_manifest_path)
+        let result = f(root_manifest_path);
+        if !self.keep_temp_dir {
+            return result.map(|()| None);
+        }
+        let path = tmp_dir.into_path();
+        let (root_package, _) = &self.members[&self.root_package];
+        std::fs::write(
+            path.join(KEPT_WORKSPACE_MARKER),
+            root_package.manifest_path.as_str(),
+        )?;
+        result
+            .with_context(|| format!("Kept temporary workspace at '{}'", path.display()))
+            .map(|()| Some(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+    use std::fs;
+
+    /// Writes a crate without dependencies to `dir` and returns its workspace.
+    fn workspace(dir: &Path) -> Result<Workspace> {
+        fs::create_dir_all(dir.join("src"))?;
+        fs::write(dir.join("src/lib.rs"), "")?;
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"flipper\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+             [lib]\npath = \"src/lib.rs\"\ncrate-type = [\"cdylib\"]\n",
+        )?;
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(dir.join("Cargo.toml"))
+            .no_deps()
+            .exec()?;
+        let root_package = metadata
+            .root_package()
+            .expect("the crate is the root package")
+            .id
+            .clone();
+        Workspace::new(&metadata, &root_package)
+    }
+
+    #[test]
+    fn temp_dir_is_removed_by_default() {
+        with_tmp_dir(|path| {
+            let mut used = None;
+            let kept = workspace(path)?.using_temp(|manifest_path| {
+                used = Some(manifest_path.absolute_directory()?);
+                Ok(())
+            })?;
+
+            assert_eq!(kept, None);
+            let used = used.expect("the temp workspace was used");
+            assert!(!used.exists());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn kept_temp_dir_is_returned_and_marked() {
+        with_tmp_dir(|path| {
+            let kept = workspace(path)?
+                .keep_temp_dir(true)
+                .using_temp(|manifest_path| {
+                    assert!(manifest_path.as_ref().exists());
+                    Ok(())
+                })?
+                .expect("the temp workspace is kept");
+
+            let marker = fs::read_to_string(kept.join(KEPT_WORKSPACE_MARKER));
+            let manifest_exists = kept.join("Cargo.toml").exists();
+            fs::remove_dir_all(&kept)?;
+            assert_eq!(marker?, path.join("Cargo.toml").to_string_lossy());
+            assert!(manifest_exists);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn kept_temp_dir_is_part_of_the_error() {
+        with_tmp_dir(|path| {
+            let err = workspace(path)?
+                .keep_temp_dir(true)
+                .using_temp(|_| anyhow::bail!("build failed"))
+                .expect_err("the build fails");
+
+            let message = format!("{:#}", err);
+            let kept = message
+                .strip_prefix("Kept temporary workspace at '")
+                .and_then(|rest| rest.split('\'').next())
+                .map(PathBuf::from)
+                .expect("the error contains the kept workspace");
+            let kept_exists = kept.join(KEPT_WORKSPACE_MARKER).exists();
+            fs::remove_dir_all(&kept)?;
+            assert!(kept_exists);
+            assert!(message.ends_with("build failed"));
+            Ok(())
+        })
     }
 }
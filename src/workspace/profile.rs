@@ -38,24 +38,97 @@ impl Profile {
         }
     }
 
+    /// The settings of this profile in the order they appear in a `[profile]` section.
+    fn settings(&self) -> Vec<(&'static str, value::Value)> {
+        let mut settings = vec![
+            ("opt-level", self.opt_level.to_toml_value()),
+            ("lto", self.lto.to_toml_value()),
+        ];
+        if let Some(codegen_units) = self.codegen_units {
+            settings.push(("codegen-units", codegen_units.into()));
+        }
+        settings.push(("overflow-checks", self.overflow_checks.into()));
+        settings.push(("panic", self.panic.to_toml_value()));
+        settings
+    }
+
     /// Set any unset profile settings from the config.
     ///
     /// Therefore:
     ///   - If the user has explicitly defined a profile setting, it will not be overwritten.
     ///   - If a profile setting is not defined, the value from this profile instance will be added
     pub(super) fn merge(&self, profile: &mut value::Table) {
-        let mut set_value_if_vacant = |key: &'static str, value: value::Value| {
+        for (key, value) in self.settings() {
             if !profile.contains_key(key) {
                 profile.insert(key.into(), value);
             }
-        };
-        set_value_if_vacant("opt-level", self.opt_level.to_toml_value());
-        set_value_if_vacant("lto", self.lto.to_toml_value());
-        if let Some(codegen_units) = self.codegen_units {
-            set_value_if_vacant("codegen-units", codegen_units.into());
         }
-        set_value_if_vacant("overflow-checks", self.overflow_checks.into());
-        set_value_if_vacant("panic", self.panic.to_toml_value());
+    }
+
+    /// Set all profile settings from the config, overwriting the ones defined by the user.
+    pub(super) fn enforce(&self, profile: &mut value::Table) {
+        for (key, value) in self.settings() {
+            profile.insert(key.into(), value);
+        }
+    }
+
+    /// Returns the `CARGO_PROFILE_RELEASE_*` environment variables which override the settings of
+    /// the `[profile.release]` with the ones of this profile, for builds of the original manifest.
+    pub fn release_env(&self) -> Vec<(String, String)> {
+        self.settings()
+            .into_iter()
+            .map(|(key, value)| {
+                let var = format!(
+                    "CARGO_PROFILE_RELEASE_{}",
+                    key.to_uppercase().replace('-', "_")
+                );
+                let value = match value {
+                    value::Value::String(value) => value,
+                    value => value.to_string(),
+                };
+                (var, value)
+            })
+            .collect()
+    }
+
+    /// Returns the settings of the user defined `profile` which differ from this profile.
+    pub(super) fn conflicts(&self, profile: &value::Table) -> Vec<ProfileConflict> {
+        self.settings()
+            .into_iter()
+            .filter_map(|(key, default)| {
+                let user = profile.get(key)?;
+                if normalize(key, user) == normalize(key, &default) {
+                    return None;
+                }
+                Some(ProfileConflict {
+                    key,
+                    user: user.clone(),
+                    default,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A setting of a user defined profile which differs from the preferred default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileConflict {
+    pub key: &'static str,
+    pub user: value::Value,
+    pub default: value::Value,
+}
+
+impl std::fmt::Display for ProfileConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{} = {}`", self.key, self.user)
+    }
+}
+
+/// Normalizes the equivalent values of a setting, e.g. `lto = true` and `lto = "fat"`.
+fn normalize(key: &str, value: &value::Value) -> value::Value {
+    match (key, value) {
+        ("lto", value::Value::Boolean(true)) => "fat".into(),
+        _ => value.clone(),
     }
 }
 
@@ -175,4 +248,47 @@ mod tests {
 
         assert_eq!(expected, manifest_profile)
     }
+
+    #[test]
+    fn conflicts_with_user_defined_settings_are_detected() {
+        let profile = Profile::default_contract_release();
+
+        let manifest_toml = r#"
+            lto = true
+            opt-level = "z"
+            codegen-units = 16
+        "#;
+        let mut manifest_profile: value::Table = toml::from_str(manifest_toml).unwrap();
+
+        let conflicts = profile.conflicts(&manifest_profile);
+        assert_eq!(
+            conflicts,
+            vec![ProfileConflict {
+                key: "codegen-units",
+                user: value::Value::Integer(16),
+                default: value::Value::Integer(1),
+            }]
+        );
+        assert_eq!(conflicts[0].to_string(), "`codegen-units = 16`");
+
+        profile.enforce(&mut manifest_profile);
+        assert!(profile.conflicts(&manifest_profile).is_empty());
+        assert_eq!(manifest_profile["lto"], value::Value::String("fat".into()));
+    }
+
+    #[test]
+    fn release_env_overrides_every_setting() {
+        let env = Profile::default_contract_release().release_env();
+        assert_eq!(
+            env,
+            [
+                ("CARGO_PROFILE_RELEASE_OPT_LEVEL", "z"),
+                ("CARGO_PROFILE_RELEASE_LTO", "fat"),
+                ("CARGO_PROFILE_RELEASE_CODEGEN_UNITS", "1"),
+                ("CARGO_PROFILE_RELEASE_OVERFLOW_CHECKS", "true"),
+                ("CARGO_PROFILE_RELEASE_PANIC", "abort"),
+            ]
+            .map(|(var, value)| (var.to_string(), value.to_string()))
+        );
+    }
 }
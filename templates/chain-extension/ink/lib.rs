@@ -0,0 +1,101 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! The ink! side of the `{{camel_name}}` chain extension.
+//!
+//! Contracts use it by being generic over the `{{camel_name}}Environment`:
+//!
+//! ```ignore
+//! #[ink::contract(env = {{name}}::{{camel_name}}Environment)]
+//! mod my_contract {
+//!     // ...
+//!     let random = self.env().extension().fetch_random(subject)?;
+//! }
+//! ```
+//!
+//! The function ids and status codes must match the ones of the runtime side in
+//! `../runtime/lib.rs`.
+
+use ink_env::{DefaultEnvironment, Environment};
+use ink_lang as ink;
+
+/// The functions the runtime provides to contracts.
+#[ink::chain_extension]
+pub trait {{camel_name}} {
+    type ErrorCode = {{camel_name}}Error;
+
+    /// Returns a random value seeded with the `subject`.
+    #[ink(extension = 1101)]
+    fn fetch_random(subject: [u8; 32]) -> [u8; 32];
+}
+
+/// The errors of the chain extension, decoded from the status code returned by the runtime.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum {{camel_name}}Error {
+    /// No randomness is available.
+    FailGetRandomSource,
+}
+
+impl ink_env::chain_extension::FromStatusCode for {{camel_name}}Error {
+    fn from_status_code(status_code: u32) -> Result<(), Self> {
+        match status_code {
+            0 => Ok(()),
+            1 => Err(Self::FailGetRandomSource),
+            _ => panic!("encountered unknown status code"),
+        }
+    }
+}
+
+/// The environment of contracts using the chain extension, the `DefaultEnvironment` otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum {{camel_name}}Environment {}
+
+impl Environment for {{camel_name}}Environment {
+    const MAX_EVENT_TOPICS: usize = <DefaultEnvironment as Environment>::MAX_EVENT_TOPICS;
+
+    type AccountId = <DefaultEnvironment as Environment>::AccountId;
+    type Balance = <DefaultEnvironment as Environment>::Balance;
+    type Hash = <DefaultEnvironment as Environment>::Hash;
+    type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+    type Timestamp = <DefaultEnvironment as Environment>::Timestamp;
+
+    type ChainExtension = {{camel_name}};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ink_env::chain_extension::FromStatusCode;
+    use ink_lang::ChainExtensionInstance;
+
+    /// Mocks the `fetch_random` function of the runtime side.
+    struct MockedFetchRandom;
+
+    impl ink_env::test::ChainExtension for MockedFetchRandom {
+        fn func_id(&self) -> u32 {
+            1101
+        }
+
+        fn call(&mut self, _input: &[u8], output: &mut Vec<u8>) -> u32 {
+            scale::Encode::encode_to(&[7u8; 32], output);
+            0
+        }
+    }
+
+    #[test]
+    fn functions_are_dispatched_by_their_id() {
+        ink_env::test::register_chain_extension(MockedFetchRandom);
+        let random = <{{camel_name}} as ChainExtensionInstance>::instantiate().fetch_random([0; 32]);
+        assert_eq!(random, Ok([7; 32]));
+    }
+
+    #[test]
+    fn status_codes_match_the_runtime_side() {
+        assert_eq!({{camel_name}}Error::from_status_code(0), Ok(()));
+        assert_eq!(
+            {{camel_name}}Error::from_status_code(1),
+            Err({{camel_name}}Error::FailGetRandomSource)
+        );
+    }
+}
@@ -0,0 +1,107 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! The runtime side of the `{{camel_name}}` chain extension.
+//!
+//! Add it to the `pallet_contracts::Config` of your runtime:
+//!
+//! ```ignore
+//! impl pallet_contracts::Config for Runtime {
+//!     type ChainExtension = {{name}}_runtime::{{camel_name}};
+//!     // ...
+//! }
+//! ```
+//!
+//! The function ids and status codes must match the ones of the ink! side in `../ink/lib.rs`.
+
+use codec::Encode;
+use frame_support::traits::Randomness;
+use pallet_contracts::chain_extension::{
+    ChainExtension, Environment, Ext, InitState, RetVal, SysConfig, UncheckedFrom,
+};
+use sp_runtime::DispatchError;
+
+/// The functions of the chain extension, identified by the `extension` id of the ink! side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuncId {
+    /// `#[ink(extension = 1101)] fn fetch_random(subject: [u8; 32]) -> [u8; 32]`
+    FetchRandom,
+}
+
+impl TryFrom<u32> for FuncId {
+    type Error = DispatchError;
+
+    fn try_from(func_id: u32) -> Result<Self, Self::Error> {
+        match func_id {
+            1101 => Ok(Self::FetchRandom),
+            _ => {
+                log::error!("Called an unregistered `func_id`: {}", func_id);
+                Err(DispatchError::Other("Unimplemented func_id"))
+            }
+        }
+    }
+}
+
+/// The errors of the functions, returned to the contract as the status code of the call.
+///
+/// `0` is returned on success, the ink! side maps the other codes back to its `ErrorCode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum {{camel_name}}Error {
+    /// No randomness is available.
+    FailGetRandomSource = 1,
+}
+
+impl From<{{camel_name}}Error> for u32 {
+    fn from(error: {{camel_name}}Error) -> Self {
+        error as u32
+    }
+}
+
+/// The chain extension made available to contracts.
+#[derive(Default)]
+pub struct {{camel_name}};
+
+impl<C> ChainExtension<C> for {{camel_name}}
+where
+    C: pallet_contracts::Config,
+{
+    fn call<E>(func_id: u32, env: Environment<E, InitState>) -> Result<RetVal, DispatchError>
+    where
+        E: Ext<T = C>,
+        <E::T as SysConfig>::AccountId: UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
+    {
+        match FuncId::try_from(func_id)? {
+            FuncId::FetchRandom => {
+                let mut env = env.buf_in_buf_out();
+                let subject: [u8; 32] = env.read_as()?;
+                // charge the weight of your function before doing any expensive work, e.g. with
+                // `env.charge_weight(..)`, and return a `{{camel_name}}Error` as the status code
+                // if it fails: `return Ok(RetVal::Converging(error.into()))`
+                let (random, _) = <C as pallet_contracts::Config>::Randomness::random(&subject);
+                env.write(&random.encode(), false, None)
+                    .map_err(|_| DispatchError::Other("Failed to write the random value"))?;
+            }
+        }
+        Ok(RetVal::Converging(0))
+    }
+
+    fn enabled() -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn function_ids_match_the_ink_side() {
+        assert_eq!(FuncId::try_from(1101), Ok(FuncId::FetchRandom));
+        assert!(FuncId::try_from(1102).is_err());
+    }
+
+    #[test]
+    fn status_codes_match_the_ink_side() {
+        assert_eq!(u32::from({{camel_name}}Error::FailGetRandomSource), 1);
+    }
+}